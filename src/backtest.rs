@@ -0,0 +1,395 @@
+//! Order-book recording and offline replay for backtests.
+//!
+//! With `RECORD_SNAPSHOTS=<file>` the live bot appends timestamped snapshots of
+//! every market whose books changed (both platforms' top of book plus the
+//! Polymarket depth) to a JSONL file. `REPLAY_FILE=<file>` feeds a recording
+//! back through the same arb detection and `ExecutionEngine`, forced to dry run
+//! with no exchange clients, on a paused clock that follows the recorded
+//! timestamps. The paper P&L is tallied by a `PerformanceTracker`. Replay
+//! needs tokio's paused clock and is only built with the `backtest` feature.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::RecordConfig;
+use crate::polymarket_clob::{OrderBook, OrderBookStore};
+use crate::types::{GlobalState, MarketPair, PriceCents, SizeCents, fxhash_str};
+
+#[cfg(any(test, feature = "backtest"))]
+use {
+    anyhow::bail,
+    std::io::BufRead,
+    tokio::sync::RwLock,
+    crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig},
+    crate::config::{ARB_THRESHOLD, PositionLimitsConfig},
+    crate::execution::{
+        ExecutionEngine, ExecutionMode, ExecutionReport, NanoClock, create_execution_report_channel,
+    },
+    crate::position_tracker::{PositionLimits, PositionTracker, create_position_channel},
+    crate::telegram::PerformanceTracker,
+    crate::types::FastExecutionRequest,
+};
+
+/// Packed top of book: (yes_ask, no_ask, yes_size, no_size)
+pub type TopOfBook = (PriceCents, PriceCents, SizeCents, SizeCents);
+
+/// One line of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordLine {
+    /// First line: the recorded pairs. Snapshots refer to them by index.
+    Pairs { pairs: Vec<MarketPair> },
+    Snapshot(MarketSnapshot),
+}
+
+/// State of one market at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketSnapshot {
+    /// Unix timestamp in milliseconds
+    pub ts_ms: i64,
+    /// Index into the recording's pairs
+    pub market: u16,
+    pub kalshi: TopOfBook,
+    pub poly: TopOfBook,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poly_yes_book: Option<BookLevels>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poly_no_book: Option<BookLevels>,
+}
+
+/// Polymarket depth as (price_bps, size) levels, best first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookLevels {
+    pub bids: Vec<(u64, f64)>,
+    pub asks: Vec<(u64, f64)>,
+}
+
+impl From<OrderBook> for BookLevels {
+    fn from(book: OrderBook) -> Self {
+        Self {
+            bids: book.bids.into_iter().rev().collect(),
+            asks: book.asks.into_iter().collect(),
+        }
+    }
+}
+
+/// Appends snapshots of changed markets to a recording
+pub struct SnapshotRecorder {
+    out: BufWriter<File>,
+    /// Recorded market_ids, in header order
+    market_ids: Vec<u16>,
+    last: Vec<Option<MarketSnapshot>>,
+}
+
+impl SnapshotRecorder {
+    /// Create (or truncate) the recording and write the pairs header
+    pub fn create(path: &Path, state: &GlobalState) -> Result<Self> {
+        let file = OpenOptions::new().create(true).write(true).truncate(true).open(path)
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+
+        let (market_ids, pairs): (Vec<u16>, Vec<MarketPair>) = state.markets.iter()
+            .take(state.market_count())
+            .filter_map(|m| m.pair.as_ref().map(|p| (m.market_id, (**p).clone())))
+            .unzip();
+        serde_json::to_writer(&mut out, &RecordLine::Pairs { pairs })?;
+        out.write_all(b"\n")?;
+        out.flush()?;
+
+        let last = vec![None; market_ids.len()];
+        Ok(Self { out, market_ids, last })
+    }
+
+    /// Write every market that changed since the previous call; returns how many
+    pub fn record(&mut self, ts_ms: i64, state: &GlobalState, books: &OrderBookStore) -> Result<usize> {
+        let mut written = 0;
+        for (index, &market_id) in self.market_ids.iter().enumerate() {
+            let Some(market) = state.get_by_id(market_id) else { continue };
+            let Some(pair) = &market.pair else { continue };
+
+            let mut snapshot = MarketSnapshot {
+                ts_ms,
+                market: index as u16,
+                kalshi: market.kalshi.load(),
+                poly: market.poly.load(),
+                poly_yes_book: books.get(fxhash_str(&pair.poly_yes_token)).map(BookLevels::from),
+                poly_no_book: books.get(fxhash_str(&pair.poly_no_token)).map(BookLevels::from),
+            };
+            if let Some(last) = &self.last[index] {
+                // Compare everything but the timestamp
+                snapshot.ts_ms = last.ts_ms;
+                if *last == snapshot {
+                    continue;
+                }
+                snapshot.ts_ms = ts_ms;
+            }
+
+            serde_json::to_writer(&mut self.out, &RecordLine::Snapshot(snapshot.clone()))?;
+            self.out.write_all(b"\n")?;
+            self.last[index] = Some(snapshot);
+            written += 1;
+        }
+        self.out.flush()?;
+        Ok(written)
+    }
+}
+
+/// Record snapshots every `interval_ms` until the process exits
+pub async fn record_loop(config: RecordConfig, state: Arc<GlobalState>, books: Arc<OrderBookStore>) {
+    let Some(path) = config.path else { return };
+    let mut recorder = match SnapshotRecorder::create(Path::new(&path), &state) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            warn!("[BACKTEST] Recording disabled: {:#}", e);
+            return;
+        }
+    };
+    info!("[BACKTEST] Recording order-book snapshots to {} every {}ms", path, config.interval_ms);
+
+    let mut interval = tokio::time::interval(Duration::from_millis(config.interval_ms.max(1)));
+    loop {
+        interval.tick().await;
+        let ts_ms = chrono::Utc::now().timestamp_millis();
+        if let Err(e) = recorder.record(ts_ms, &state, &books) {
+            warn!("[BACKTEST] Recording stopped: {:#}", e);
+            return;
+        }
+    }
+}
+
+/// Outcome of a replay
+#[cfg(any(test, feature = "backtest"))]
+pub struct BacktestReport {
+    pub snapshots: usize,
+    /// Execution requests raised by arb detection
+    pub requests: usize,
+    pub performance: PerformanceTracker,
+}
+
+#[cfg(feature = "backtest")]
+impl BacktestReport {
+    pub fn log(&self) {
+        let perf = &self.performance;
+        info!("[BACKTEST] Replay complete:");
+        info!("   - Snapshots replayed: {}", self.snapshots);
+        info!("   - Execution requests: {}", self.requests);
+        info!("   - Opportunities: {}", perf.opportunities_detected);
+        info!("   - Paper trades: {} ({} successful)", perf.paper_trades, perf.paper_successful_trades);
+        info!("   - Paper profit: ${:.2}", perf.paper_profit_cents as f64 / 100.0);
+    }
+}
+
+/// Replay a recording file on a dedicated paused-clock runtime
+#[cfg(feature = "backtest")]
+pub fn replay_file(path: &Path) -> Result<BacktestReport> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open recording {}", path.display()))?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()?;
+    runtime.block_on(replay(std::io::BufReader::new(file)))
+}
+
+/// Replay a recording. Must run on a runtime with a paused clock: time is
+/// advanced to each snapshot's timestamp so in-flight and dedup windows
+/// behave as they did live.
+#[cfg(any(test, feature = "backtest"))]
+pub async fn replay<R: BufRead>(reader: R) -> Result<BacktestReport> {
+    let mut lines = reader.lines();
+    let header = lines.next().context("Recording is empty")??;
+    let RecordLine::Pairs { pairs } = serde_json::from_str(&header).context("Invalid recording header")? else {
+        bail!("Recording must start with the market pairs");
+    };
+
+    let state = Arc::new({
+        let mut s = GlobalState::new();
+        for pair in pairs {
+            s.add_pair(pair);
+        }
+        s
+    });
+    let books = Arc::new(OrderBookStore::new());
+    let positions = Arc::new(RwLock::new({
+        let mut tracker = PositionTracker::new();
+        tracker.set_limits(PositionLimits::load(PositionLimitsConfig::from_env()));
+        tracker
+    }));
+    let (position_channel, _position_rx) = create_position_channel();
+    let (report_tx, mut report_rx) = create_execution_report_channel();
+    let engine = ExecutionEngine::new(
+        None,
+        None,
+        books.clone(),
+        state.clone(),
+        Arc::new(CircuitBreaker::new(CircuitBreakerConfig::from_env())),
+        position_channel,
+        positions,
+        report_tx,
+        ExecutionMode::DryRun,
+    );
+    let clock = NanoClock::new();
+    let threshold_cents: PriceCents = ((ARB_THRESHOLD * 100.0).round() as u16).max(1);
+
+    let mut report = BacktestReport { snapshots: 0, requests: 0, performance: PerformanceTracker::new(0) };
+    let mut last_ts = None;
+    for (line_no, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let snapshot = match serde_json::from_str(&line) {
+            Ok(RecordLine::Snapshot(snapshot)) => snapshot,
+            Ok(RecordLine::Pairs { .. }) => bail!("Unexpected pairs header on line {}", line_no + 2),
+            Err(e) => bail!("Invalid snapshot on line {}: {}", line_no + 2, e),
+        };
+
+        if let Some(prev) = last_ts {
+            let elapsed = snapshot.ts_ms.saturating_sub(prev).max(0) as u64;
+            tokio::time::advance(Duration::from_millis(elapsed)).await;
+            tokio::task::yield_now().await;
+        }
+        last_ts = Some(snapshot.ts_ms);
+        report.snapshots += 1;
+
+        let Some(market) = state.get_by_id(snapshot.market) else { continue };
+        let Some(pair) = &market.pair else { continue };
+        let (yes_ask, no_ask, yes_size, no_size) = snapshot.kalshi;
+        market.kalshi.store(yes_ask, no_ask, yes_size, no_size);
+        let (yes_ask, no_ask, yes_size, no_size) = snapshot.poly;
+        market.poly.store(yes_ask, no_ask, yes_size, no_size);
+        for (token, book) in [(&pair.poly_yes_token, snapshot.poly_yes_book), (&pair.poly_no_token, snapshot.poly_no_book)] {
+            match book {
+                Some(book) => { books.apply_snapshot(fxhash_str(token), book.bids, book.asks); }
+                None => books.remove(fxhash_str(token)),
+            }
+        }
+
+        let arb_mask = market.check_arbs(threshold_cents);
+        if let Some(req) = FastExecutionRequest::from_arb_mask(market.market_id, market, arb_mask, clock.now_ns()) {
+            report.requests += 1;
+            if let Err(e) = engine.process(req).await {
+                warn!("[BACKTEST] {}: {}", pair.description, e);
+            }
+        }
+
+        while let Ok(execution_report) = report_rx.try_recv() {
+            record(&mut report.performance, execution_report);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(any(test, feature = "backtest"))]
+fn record(perf: &mut PerformanceTracker, report: ExecutionReport) {
+    match report {
+        ExecutionReport::Opportunity(_) => perf.record_opportunity(),
+        ExecutionReport::Trade(trade) => perf.record_paper_trade(trade.success, trade.profit_cents),
+        // Approvals, balance checks and cancels only concern live orders;
+        // replays skip probation and trailing stops
+        ExecutionReport::Declined(_) | ExecutionReport::Underfunded(_) | ExecutionReport::Probation(_)
+        | ExecutionReport::TrailingStop(_) | ExecutionReport::CancelUnconfirmed(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MarketType;
+
+    fn test_pair() -> MarketPair {
+        MarketPair {
+            pair_id: "test-pair".into(),
+            league: "nba".into(),
+            market_type: MarketType::Moneyline,
+            description: "Test Market".into(),
+            kalshi_event_ticker: "KXNBAGAME-TEST".into(),
+            kalshi_series_ticker: "KXNBAGAME".into(),
+            kalshi_market_ticker: "KXNBAGAME-TEST-YES".into(),
+            poly_slug: "test-slug".into(),
+            poly_yes_token: "yes_token".into(),
+            poly_no_token: "no_token".into(),
+            poly_constraints: Default::default(),
+            line_value: None,
+            team_suffix: None,
+        }
+    }
+
+    fn snapshot(ts_ms: i64, yes: PriceCents, no: PriceCents) -> String {
+        let book = |price: PriceCents| Some(BookLevels { bids: vec![], asks: vec![(price as u64 * 100, 10.0)] });
+        let line = RecordLine::Snapshot(MarketSnapshot {
+            ts_ms,
+            market: 0,
+            kalshi: (0, 0, 0, 0),
+            poly: (yes, no, 1000, 1000),
+            poly_yes_book: book(yes),
+            poly_no_book: book(no),
+        });
+        serde_json::to_string(&line).unwrap()
+    }
+
+    fn recording(snapshots: &[String]) -> String {
+        let header = serde_json::to_string(&RecordLine::Pairs { pairs: vec![test_pair()] }).unwrap();
+        std::iter::once(header).chain(snapshots.iter().cloned()).collect::<Vec<_>>().join("\n")
+    }
+
+    #[test]
+    fn test_recorder_writes_only_changed_markets() {
+        let path = std::env::temp_dir().join(format!("arb_recording_{}.jsonl", std::process::id()));
+        let mut state = GlobalState::new();
+        state.add_pair(test_pair());
+        let books = OrderBookStore::new();
+
+        let mut recorder = SnapshotRecorder::create(&path, &state).unwrap();
+        state.markets[0].poly.store(45, 50, 1000, 1000);
+        assert_eq!(recorder.record(1, &state, &books).unwrap(), 1);
+        assert_eq!(recorder.record(2, &state, &books).unwrap(), 0);
+        books.apply_snapshot(fxhash_str("yes_token"), [], [(4500, 10.0)]);
+        assert_eq!(recorder.record(3, &state, &books).unwrap(), 1);
+
+        let lines: Vec<RecordLine> = std::fs::read_to_string(&path).unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(&lines[0], RecordLine::Pairs { pairs } if pairs[0].pair_id.as_ref() == "test-pair"));
+        let RecordLine::Snapshot(last) = &lines[2] else { panic!("expected a snapshot") };
+        assert_eq!(last.ts_ms, 3);
+        assert_eq!(last.poly, (45, 50, 1000, 1000));
+        assert_eq!(last.poly_yes_book.as_ref().unwrap().asks, vec![(4500, 10.0)]);
+        assert!(last.poly_no_book.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_paper_trades_follow_recorded_time() {
+        // 45¢ + 50¢ leaves 5¢ per contract on a Poly-only arb. The market stays
+        // in flight for 10s after a trade, so only the snapshot 20s later trades again.
+        let data = recording(&[
+            snapshot(0, 45, 50),
+            snapshot(2_000, 45, 51),
+            snapshot(22_000, 45, 50),
+            snapshot(23_000, 50, 50),
+        ]);
+
+        let report = replay(data.as_bytes()).await.unwrap();
+        assert_eq!(report.snapshots, 4);
+        assert_eq!(report.requests, 3);
+        assert_eq!(report.performance.paper_trades, 2);
+        assert!(report.performance.paper_profit_cents > 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_rejects_missing_header() {
+        let data = snapshot(0, 45, 50);
+        assert!(replay(data.as_bytes()).await.is_err());
+    }
+}
@@ -1,12 +1,17 @@
-//! Team code mapping cache for cross-platform market matching.
+//! Team code mapping cache and bounded TTL cache for market metadata.
 //!
 //! This module provides bidirectional mapping between Polymarket and Kalshi
-//! team codes to enable accurate market discovery across platforms.
+//! team codes to enable accurate market discovery across platforms, plus a
+//! generic TTL + LRU cache used for API lookups made during discovery.
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 const CACHE_FILE: &str = "kalshi_team_cache.json";
 
@@ -140,6 +145,212 @@ impl TeamCache {
     }
 }
 
+// === TTL Cache ===
+
+/// Eviction policy for [`TtlCache`]
+#[derive(Debug, Clone, Copy)]
+pub struct CacheConfig {
+    /// Entry lifetime from insertion
+    pub ttl_secs: u64,
+    /// Capacity; least recently used entries are evicted beyond it
+    pub max_entries: usize,
+    /// Interval of the background sweep removing expired entries
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 2 * 60 * 60,
+            max_entries: 10_000,
+            sweep_interval_secs: 60,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// From CACHE_TTL_SECS, CACHE_MAX_ENTRIES and CACHE_SWEEP_INTERVAL_SECS
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            ttl_secs: std::env::var("CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.ttl_secs),
+            max_entries: std::env::var("CACHE_MAX_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_entries),
+            sweep_interval_secs: std::env::var("CACHE_SWEEP_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.sweep_interval_secs),
+        }
+    }
+}
+
+/// Hit/miss counters and current size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+struct CacheEntry<V> {
+    value: V,
+    inserted: Instant,
+    /// Position in the LRU order
+    tick: u64,
+}
+
+struct CacheInner<K, V> {
+    entries: HashMap<K, CacheEntry<V>>,
+    /// tick → key, oldest access first
+    lru: BTreeMap<u64, K>,
+    next_tick: u64,
+}
+
+/// Thread-safe cache with per-entry TTL and an LRU capacity bound.
+///
+/// Expired entries are dropped lazily on read and by [`TtlCache::sweep`],
+/// which [`TtlCache::spawn_sweeper`] runs periodically.
+pub struct TtlCache<K, V> {
+    config: CacheConfig,
+    inner: Mutex<CacheInner<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> TtlCache<K, V> {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(CacheInner {
+                entries: HashMap::new(),
+                lru: BTreeMap::new(),
+                next_tick: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.ttl_secs)
+    }
+
+    /// Fresh value for `key`, marking it most recently used
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.get_at(key, Instant::now())
+    }
+
+    fn get_at(&self, key: &K, now: Instant) -> Option<V> {
+        let ttl = self.ttl();
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let inner = &mut *inner;
+
+        let (expired, old_tick) = match inner.entries.get(key) {
+            Some(entry) => (now.duration_since(entry.inserted) >= ttl, entry.tick),
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+
+        inner.lru.remove(&old_tick);
+        if expired {
+            inner.entries.remove(key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let tick = inner.next_tick;
+        inner.next_tick += 1;
+        inner.lru.insert(tick, key.clone());
+        let entry = inner.entries.get_mut(key)?;
+        entry.tick = tick;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.value.clone())
+    }
+
+    /// Insert or replace, evicting the least recently used entries beyond capacity
+    pub fn insert(&self, key: K, value: V) {
+        self.insert_at(key, value, Instant::now());
+    }
+
+    fn insert_at(&self, key: K, value: V, now: Instant) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let inner = &mut *inner;
+
+        let tick = inner.next_tick;
+        inner.next_tick += 1;
+        inner.lru.insert(tick, key.clone());
+        if let Some(old) = inner.entries.insert(key, CacheEntry { value, inserted: now, tick }) {
+            inner.lru.remove(&old.tick);
+        }
+
+        while inner.entries.len() > self.config.max_entries.max(1) {
+            let Some((_, oldest)) = inner.lru.pop_first() else { break };
+            inner.entries.remove(&oldest);
+        }
+    }
+
+    /// Remove all expired entries; returns how many were evicted
+    pub fn sweep(&self) -> usize {
+        self.sweep_at(Instant::now())
+    }
+
+    fn sweep_at(&self, now: Instant) -> usize {
+        let ttl = self.ttl();
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let inner = &mut *inner;
+
+        let before = inner.entries.len();
+        let lru = &mut inner.lru;
+        inner.entries.retain(|_, entry| {
+            let keep = now.duration_since(entry.inserted) < ttl;
+            if !keep {
+                lru.remove(&entry.tick);
+            }
+            keep
+        });
+        before - inner.entries.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.inner.lock().unwrap_or_else(|e| e.into_inner()).entries.len(),
+        }
+    }
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Hash + Eq + Clone + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    /// Periodically sweep expired entries. The task ends once the cache is dropped.
+    pub fn spawn_sweeper(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let cache = Arc::downgrade(self);
+        let interval = Duration::from_secs(self.config.sweep_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(cache) = cache.upgrade() else { break };
+                let evicted = cache.sweep();
+                if evicted > 0 {
+                    tracing::debug!("[CACHE] Swept {} expired entries", evicted);
+                }
+            }
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,4 +365,25 @@ mod tests {
         assert_eq!(cache.poly_to_kalshi("epl", "CHE"), Some("cfc".to_string()));
         assert_eq!(cache.kalshi_to_poly("epl", "cfc"), Some("che".to_string()));
     }
+
+    #[test]
+    fn test_ttl_cache_expiry_and_lru() {
+        let cache = TtlCache::new(CacheConfig { ttl_secs: 10, max_entries: 2, sweep_interval_secs: 60 });
+        let t0 = Instant::now();
+
+        cache.insert_at("a", 1, t0);
+        cache.insert_at("b", 2, t0);
+        assert_eq!(cache.get_at(&"a", t0), Some(1));
+
+        // "b" is least recently used and gets evicted
+        cache.insert_at("c", 3, t0 + Duration::from_secs(5));
+        assert_eq!(cache.get_at(&"b", t0), None);
+
+        // Lazy expiry on read, sweep for the rest
+        assert_eq!(cache.get_at(&"a", t0 + Duration::from_secs(10)), None);
+        assert_eq!(cache.sweep_at(t0 + Duration::from_secs(15)), 1);
+
+        let stats = cache.stats();
+        assert_eq!(stats, CacheStats { hits: 1, misses: 2, entries: 0 });
+    }
 }
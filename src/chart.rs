@@ -0,0 +1,121 @@
+//! Cumulative profit chart rendered as a PNG (feature `charts`).
+//!
+//! Drawn directly into an RGB buffer: a zero line, a frame and the profit
+//! curve, with no text (the numbers go in the Telegram caption), so the only
+//! dependency is the PNG encoder.
+
+use anyhow::Result;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 320;
+const MARGIN: i64 = 16;
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const FRAME: [u8; 3] = [200, 200, 200];
+const ZERO_LINE: [u8; 3] = [150, 150, 150];
+const GAIN: [u8; 3] = [22, 163, 74];
+const LOSS: [u8; 3] = [220, 38, 38];
+
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Self { pixels: BACKGROUND.repeat((WIDTH * HEIGHT) as usize) }
+    }
+
+    fn put(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if (0..WIDTH as i64).contains(&x) && (0..HEIGHT as i64).contains(&y) {
+            let i = ((y as u32 * WIDTH + x as u32) * 3) as usize;
+            self.pixels[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    /// Bresenham line, 2px thick
+    fn line(&mut self, (mut x0, mut y0): (i64, i64), (x1, y1): (i64, i64), color: [u8; 3]) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let mut err = dx + dy;
+        loop {
+            self.put(x0, y0, color);
+            self.put(x0, y0 + 1, color);
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+}
+
+/// PNG of cumulative profit over time from `(unix seconds, cumulative cents)`
+/// points, green when the last point is at or above zero, red otherwise
+pub fn profit_chart_png(points: &[(i64, i64)]) -> Result<Vec<u8>> {
+    let mut canvas = Canvas::new();
+    let (left, right) = (MARGIN, WIDTH as i64 - 1 - MARGIN);
+    let (top, bottom) = (MARGIN, HEIGHT as i64 - 1 - MARGIN);
+
+    // Start from zero so the first trade shows as a step
+    let t0 = points.first().map(|p| p.0).unwrap_or(0);
+    let t1 = points.last().map(|p| p.0).unwrap_or(0);
+    let lo = points.iter().map(|p| p.1).min().unwrap_or(0).min(0);
+    let hi = points.iter().map(|p| p.1).max().unwrap_or(0).max(0);
+    let span_t = (t1 - t0).max(1) as f64;
+    let span_v = (hi - lo).max(1) as f64;
+
+    let x_of = |t: i64| left + ((t - t0) as f64 / span_t * (right - left) as f64).round() as i64;
+    let y_of = |v: i64| bottom - ((v - lo) as f64 / span_v * (bottom - top) as f64).round() as i64;
+
+    canvas.line((left, top), (right, top), FRAME);
+    canvas.line((left, bottom), (right, bottom), FRAME);
+    canvas.line((left, top), (left, bottom), FRAME);
+    canvas.line((right, top), (right, bottom), FRAME);
+    canvas.line((left, y_of(0)), (right, y_of(0)), ZERO_LINE);
+
+    let color = if points.last().is_some_and(|p| p.1 < 0) { LOSS } else { GAIN };
+    let mut prev = (x_of(t0), y_of(0));
+    for &(t, v) in points {
+        // Step chart: profit is flat between trades
+        let (x, y) = (x_of(t), y_of(v));
+        canvas.line(prev, (x, prev.1), color);
+        canvas.line((x, prev.1), (x, y), color);
+        prev = (x, y);
+    }
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, WIDTH, HEIGHT);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&canvas.pixels)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profit_chart_is_png() {
+        let png = profit_chart_png(&[(1_700_000_000, 12), (1_700_000_600, 5), (1_700_003_600, 40)]).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (WIDTH, HEIGHT));
+
+        // Degenerate input still renders
+        assert!(profit_chart_png(&[(5, -3)]).is_ok());
+        assert!(profit_chart_png(&[]).is_ok());
+    }
+}
@@ -3,9 +3,10 @@
 //! This module provides configurable risk limits, position tracking, and
 //! automatic trading halt mechanisms to protect against excessive losses.
 
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use tracing::{error, warn, info};
 
 /// Circuit breaker configuration from environment
@@ -23,8 +24,14 @@ pub struct CircuitBreakerConfig {
     /// Maximum number of consecutive errors before halting
     pub max_consecutive_errors: u32,
     
-    /// Cooldown period after a trip (seconds)
+    /// Cooldown period after a trip before a probe trade is allowed (seconds)
     pub cooldown_secs: u64,
+
+    /// Rolling window for cumulative loss tracking (seconds)
+    pub loss_window_secs: u64,
+
+    /// Maximum cumulative loss within the rolling window (in cents, 0 = disabled)
+    pub max_window_loss_cents: i64,
     
     /// Whether circuit breakers are enabled
     pub enabled: bool,
@@ -57,6 +64,16 @@ impl CircuitBreakerConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(300), // 5 minutes default
+
+            loss_window_secs: std::env::var("CB_LOSS_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+
+            max_window_loss_cents: std::env::var("CB_MAX_WINDOW_LOSS_CENTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
             
             enabled: std::env::var("CB_ENABLED")
                 .map(|v| v == "1" || v == "true")
@@ -72,6 +89,11 @@ pub enum TripReason {
     MaxTotalPosition { position: i64, limit: i64 },
     MaxDailyLoss { loss: f64, limit: f64 },
     ConsecutiveErrors { count: u32, limit: u32 },
+    WindowLoss { loss_cents: i64, limit_cents: i64, window_secs: u64 },
+    /// An exchange rejected our credentials; not retried until a manual reset
+    AuthFailed(String),
+    /// A WebSocket feed keeps dropping, so its prices can't be trusted
+    FeedUnstable { platform: String, reconnects: u32, window_secs: u64 },
     ManualHalt,
 }
 
@@ -90,6 +112,15 @@ impl std::fmt::Display for TripReason {
             TripReason::ConsecutiveErrors { count, limit } => {
                 write!(f, "Consecutive errors: {} (limit: {})", count, limit)
             }
+            TripReason::WindowLoss { loss_cents, limit_cents, window_secs } => {
+                write!(f, "Loss over last {}s: {}¢ (limit: {}¢)", window_secs, loss_cents, limit_cents)
+            }
+            TripReason::AuthFailed(msg) => {
+                write!(f, "Exchange rejected credentials: {}", msg)
+            }
+            TripReason::FeedUnstable { platform, reconnects, window_secs } => {
+                write!(f, "{} feed reconnected {} times in {}s", platform, reconnects, window_secs)
+            }
             TripReason::ManualHalt => {
                 write!(f, "Manual halt triggered")
             }
@@ -97,6 +128,28 @@ impl std::fmt::Display for TripReason {
     }
 }
 
+/// Breaker state machine
+///
+/// Closed → Open on a trip; Open → HalfOpen once the cooldown elapses
+/// (except after a manual halt); HalfOpen allows a single probe trade
+/// which closes the breaker on success or re-opens it on failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl std::fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakerState::Closed => write!(f, "CLOSED"),
+            BreakerState::Open => write!(f, "OPEN"),
+            BreakerState::HalfOpen => write!(f, "HALF-OPEN"),
+        }
+    }
+}
+
 /// Position tracking for a single market
 #[derive(Debug, Default)]
 pub struct MarketPosition {
@@ -139,6 +192,15 @@ pub struct CircuitBreaker {
     
     /// Positions per market
     positions: RwLock<std::collections::HashMap<String, MarketPosition>>,
+
+    /// Losses (timestamp, cents) within the rolling window
+    recent_losses: RwLock<VecDeque<(Instant, i64)>>,
+
+    /// Current breaker state, observable via `subscribe`
+    state: watch::Sender<BreakerState>,
+
+    /// When the half-open probe was let through
+    probe_started_at: RwLock<Option<Instant>>,
 }
 
 impl CircuitBreaker {
@@ -150,7 +212,10 @@ impl CircuitBreaker {
         info!("[CB]   Max daily loss: ${:.2}", config.max_daily_loss);
         info!("[CB]   Max consecutive errors: {}", config.max_consecutive_errors);
         info!("[CB]   Cooldown: {}s", config.cooldown_secs);
-        
+        if config.max_window_loss_cents > 0 {
+            info!("[CB]   Max loss: {}¢ per {}s window", config.max_window_loss_cents, config.loss_window_secs);
+        }
+
         Self {
             config,
             halted: AtomicBool::new(false),
@@ -159,6 +224,31 @@ impl CircuitBreaker {
             consecutive_errors: AtomicI64::new(0),
             daily_pnl_cents: AtomicI64::new(0),
             positions: RwLock::new(std::collections::HashMap::new()),
+            recent_losses: RwLock::new(VecDeque::new()),
+            state: watch::Sender::new(BreakerState::Closed),
+            probe_started_at: RwLock::new(None),
+        }
+    }
+
+    /// Cooldown before a probe trade is allowed after a trip
+    pub fn cooldown_secs(&self) -> u64 {
+        self.config.cooldown_secs
+    }
+
+    /// Current breaker state
+    pub fn state(&self) -> BreakerState {
+        *self.state.borrow()
+    }
+
+    /// Receive every state transition
+    pub fn subscribe(&self) -> watch::Receiver<BreakerState> {
+        self.state.subscribe()
+    }
+
+    fn set_state(&self, new_state: BreakerState) {
+        let old = self.state.send_replace(new_state);
+        if old != new_state {
+            info!("[CB] State {} → {}", old, new_state);
         }
     }
     
@@ -177,7 +267,7 @@ impl CircuitBreaker {
             return Ok(());
         }
         
-        if self.halted.load(Ordering::SeqCst) {
+        if self.halted.load(Ordering::SeqCst) && !self.try_probe().await {
             let reason = self.trip_reason.read().await;
             return Err(reason.clone().unwrap_or(TripReason::ManualHalt));
         }
@@ -218,6 +308,38 @@ impl CircuitBreaker {
         Ok(())
     }
     
+    /// Decide whether a halted breaker may let a probe trade through.
+    /// Moves Open → HalfOpen once the cooldown has elapsed.
+    async fn try_probe(&self) -> bool {
+        if matches!(*self.trip_reason.read().await, Some(TripReason::ManualHalt | TripReason::AuthFailed(_))) {
+            return false;
+        }
+
+        let cooldown = Duration::from_secs(self.config.cooldown_secs);
+        match self.state() {
+            BreakerState::Closed => true,
+            BreakerState::Open => {
+                let cooled_down = self.tripped_at.read().await
+                    .map(|t| t.elapsed() >= cooldown)
+                    .unwrap_or(false);
+                if cooled_down {
+                    *self.probe_started_at.write().await = Some(Instant::now());
+                    self.set_state(BreakerState::HalfOpen);
+                }
+                cooled_down
+            }
+            BreakerState::HalfOpen => {
+                // One probe at a time; allow another if the previous never reported back
+                let mut probe = self.probe_started_at.write().await;
+                let stale = probe.map(|t| t.elapsed() >= cooldown).unwrap_or(true);
+                if stale {
+                    *probe = Some(Instant::now());
+                }
+                stale
+            }
+        }
+    }
+
     /// Record a successful execution
     pub async fn record_success(&self, market_id: &str, kalshi_contracts: i64, poly_contracts: i64, pnl: f64) {
         // Reset consecutive errors
@@ -228,15 +350,67 @@ impl CircuitBreaker {
         self.daily_pnl_cents.fetch_add(pnl_cents, Ordering::SeqCst);
         
         // Update positions
-        let mut positions = self.positions.write().await;
-        let pos = positions.entry(market_id.to_string()).or_default();
-        pos.kalshi_yes += kalshi_contracts;
-        pos.poly_no += poly_contracts;
+        {
+            let mut positions = self.positions.write().await;
+            let pos = positions.entry(market_id.to_string()).or_default();
+            pos.kalshi_yes += kalshi_contracts;
+            pos.poly_no += poly_contracts;
+        }
+
+        if pnl_cents < 0 && self.record_window_loss(-pnl_cents).await {
+            return;
+        }
+
+        // Successful probe closes the breaker
+        if self.state() == BreakerState::HalfOpen {
+            info!("[CB] Probe trade succeeded");
+            self.reset().await;
+        }
+    }
+
+    /// Track a loss in the rolling window; returns true if it tripped the breaker
+    async fn record_window_loss(&self, loss_cents: i64) -> bool {
+        if self.config.max_window_loss_cents <= 0 {
+            return false;
+        }
+
+        let window = Duration::from_secs(self.config.loss_window_secs);
+        let total = {
+            let mut losses = self.recent_losses.write().await;
+            let now = Instant::now();
+            losses.push_back((now, loss_cents));
+            while losses.front().is_some_and(|(t, _)| now.duration_since(*t) > window) {
+                losses.pop_front();
+            }
+            losses.iter().map(|(_, l)| l).sum::<i64>()
+        };
+
+        if total > self.config.max_window_loss_cents {
+            self.trip(TripReason::WindowLoss {
+                loss_cents: total,
+                limit_cents: self.config.max_window_loss_cents,
+                window_secs: self.config.loss_window_secs,
+            }).await;
+            return true;
+        }
+        false
     }
     
     /// Record an error
     pub async fn record_error(&self) {
         let errors = self.consecutive_errors.fetch_add(1, Ordering::SeqCst) + 1;
+
+        // Failed probe re-opens the breaker and restarts the cooldown
+        if self.state() == BreakerState::HalfOpen {
+            warn!("[CB] Probe trade failed");
+            let reason = self.trip_reason.read().await.clone()
+                .unwrap_or(TripReason::ConsecutiveErrors {
+                    count: errors as u32,
+                    limit: self.config.max_consecutive_errors,
+                });
+            self.trip(reason).await;
+            return;
+        }
         
         if errors >= self.config.max_consecutive_errors as i64 {
             self.trip(TripReason::ConsecutiveErrors {
@@ -264,6 +438,8 @@ impl CircuitBreaker {
         self.halted.store(true, Ordering::SeqCst);
         *self.tripped_at.write().await = Some(Instant::now());
         *self.trip_reason.write().await = Some(reason);
+        *self.probe_started_at.write().await = None;
+        self.set_state(BreakerState::Open);
     }
     
     /// Manually halt trading
@@ -280,7 +456,10 @@ impl CircuitBreaker {
         self.halted.store(false, Ordering::SeqCst);
         *self.tripped_at.write().await = None;
         *self.trip_reason.write().await = None;
+        *self.probe_started_at.write().await = None;
         self.consecutive_errors.store(0, Ordering::SeqCst);
+        self.recent_losses.write().await.clear();
+        self.set_state(BreakerState::Closed);
     }
 
     /// Reset daily P&L (call at midnight)
@@ -317,6 +496,7 @@ impl CircuitBreaker {
         
         CircuitBreakerStatus {
             enabled: self.config.enabled,
+            state: self.state(),
             halted: self.halted.load(Ordering::SeqCst),
             trip_reason: self.trip_reason.read().await.clone(),
             consecutive_errors: self.consecutive_errors.load(Ordering::SeqCst) as u32,
@@ -331,6 +511,7 @@ impl CircuitBreaker {
 #[allow(dead_code)]
 pub struct CircuitBreakerStatus {
     pub enabled: bool,
+    pub state: BreakerState,
     pub halted: bool,
     pub trip_reason: Option<TripReason>,
     pub consecutive_errors: u32,
@@ -346,7 +527,7 @@ impl std::fmt::Display for CircuitBreakerStatus {
         }
         
         if self.halted {
-            write!(f, "Circuit Breaker: 🛑 HALTED")?;
+            write!(f, "Circuit Breaker: 🛑 HALTED [{}]", self.state)?;
             if let Some(reason) = &self.trip_reason {
                 write!(f, " ({})", reason)?;
             }
@@ -371,6 +552,8 @@ mod tests {
             max_daily_loss: 100.0,
             max_consecutive_errors: 3,
             cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
             enabled: true,
         };
         
@@ -395,6 +578,8 @@ mod tests {
             max_daily_loss: 100.0,
             max_consecutive_errors: 3,
             cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
             enabled: true,
         };
         
@@ -409,4 +594,62 @@ mod tests {
         cb.record_error().await;
         assert!(!cb.is_trading_allowed());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_half_open_probe() {
+        let config = CircuitBreakerConfig {
+            max_position_per_market: 100,
+            max_total_position: 500,
+            max_daily_loss: 100.0,
+            max_consecutive_errors: 2,
+            cooldown_secs: 0,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
+            enabled: true,
+        };
+
+        let cb = CircuitBreaker::new(config);
+        cb.record_error().await;
+        cb.record_error().await;
+        assert_eq!(cb.state(), BreakerState::Open);
+
+        // Cooldown elapsed: a single probe is let through
+        assert!(cb.can_execute("market1", 1).await.is_ok());
+        assert_eq!(cb.state(), BreakerState::HalfOpen);
+
+        // Failed probe re-opens
+        cb.record_error().await;
+        assert_eq!(cb.state(), BreakerState::Open);
+
+        // Successful probe closes
+        assert!(cb.can_execute("market1", 1).await.is_ok());
+        cb.record_success("market1", 1, 1, 0.01).await;
+        assert_eq!(cb.state(), BreakerState::Closed);
+        assert!(cb.is_trading_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_window_loss_trip() {
+        let config = CircuitBreakerConfig {
+            max_position_per_market: 100,
+            max_total_position: 500,
+            max_daily_loss: 100.0,
+            max_consecutive_errors: 5,
+            cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 500,
+            enabled: true,
+        };
+
+        let cb = CircuitBreaker::new(config);
+        cb.record_success("market1", 1, 1, -3.0).await;
+        assert_eq!(cb.state(), BreakerState::Closed);
+
+        cb.record_success("market2", 1, 1, -2.5).await;
+        assert_eq!(cb.state(), BreakerState::Open);
+        assert!(matches!(
+            cb.can_execute("market3", 1).await,
+            Err(TripReason::WindowLoss { loss_cents: 550, .. })
+        ));
+    }
+}
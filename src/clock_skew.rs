@@ -0,0 +1,175 @@
+//! Host clock drift against the exchanges.
+//!
+//! Kalshi request signatures carry a timestamp and Polymarket orders an
+//! expiry, so a drifting host clock surfaces as a cascade of auth and expiry
+//! failures. At startup and every `CLOCK_SKEW_CHECK_INTERVAL_SECS` the `Date`
+//! header of each exchange's response is compared with local time; while a
+//! platform's skew exceeds `CLOCK_SKEW_MAX_MS` the `ExecutionEngine` sends no
+//! orders to it.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+use crate::config::{ClockSkewConfig, HttpConfig};
+use crate::types::Platform;
+
+/// `Date` only has second resolution: the server time is taken as the middle
+/// of the second it reports
+const DATE_RESOLUTION_MS: i64 = 1000;
+
+/// Local clock minus exchange clock in milliseconds. The request is assumed to
+/// reach the server halfway between `sent` and `received`.
+pub fn skew_ms(sent: DateTime<Utc>, received: DateTime<Utc>, server_date: DateTime<Utc>) -> i64 {
+    let local_mid = sent.timestamp_millis() + (received - sent).num_milliseconds() / 2;
+    local_mid - (server_date.timestamp_millis() + DATE_RESOLUTION_MS / 2)
+}
+
+/// Measure the skew against `url` from its `Date` response header
+pub async fn measure(http: &reqwest::Client, url: &str) -> Result<i64> {
+    let sent = Utc::now();
+    let resp = http.get(url).send().await.with_context(|| format!("GET {}", url))?;
+    let received = Utc::now();
+    let date = resp.headers().get(reqwest::header::DATE)
+        .with_context(|| format!("{} returned no Date header", url))?
+        .to_str()?;
+    let server_date = DateTime::parse_from_rfc2822(date)
+        .with_context(|| format!("Invalid Date header {:?}", date))?
+        .with_timezone(&Utc);
+    Ok(skew_ms(sent, received, server_date))
+}
+
+/// Last measured skew per platform, shared with the execution engine
+pub struct ClockSkewGuard {
+    max_skew_ms: Option<u64>,
+    skews: Mutex<HashMap<Platform, i64>>,
+    /// Platforms over the limit, so each breach is reported once
+    alerting: Mutex<HashSet<Platform>>,
+}
+
+impl ClockSkewGuard {
+    pub fn new(config: ClockSkewConfig) -> Self {
+        Self { max_skew_ms: config.max_skew_ms, skews: Mutex::default(), alerting: Mutex::default() }
+    }
+
+    pub fn max_skew_ms(&self) -> Option<u64> {
+        self.max_skew_ms
+    }
+
+    /// Store a measurement; true if the platform just went over the limit
+    pub fn record(&self, platform: Platform, skew_ms: i64) -> bool {
+        self.skews.lock().unwrap_or_else(|e| e.into_inner()).insert(platform, skew_ms);
+        let mut alerting = self.alerting.lock().unwrap_or_else(|e| e.into_inner());
+        if self.exceeds(skew_ms) {
+            alerting.insert(platform)
+        } else {
+            alerting.remove(&platform);
+            false
+        }
+    }
+
+    /// Last measured skew of `platform`
+    pub fn skew_ms(&self, platform: Platform) -> Option<i64> {
+        self.skews.lock().unwrap_or_else(|e| e.into_inner()).get(&platform).copied()
+    }
+
+    /// First of `platforms` whose clock is too far off to trade, with its skew
+    pub fn blocked(&self, platforms: impl IntoIterator<Item = Platform>) -> Option<(Platform, i64)> {
+        platforms.into_iter()
+            .filter_map(|platform| self.skew_ms(platform).map(|skew| (platform, skew)))
+            .find(|&(_, skew)| self.exceeds(skew))
+    }
+
+    fn exceeds(&self, skew_ms: i64) -> bool {
+        self.max_skew_ms.is_some_and(|max| skew_ms.unsigned_abs() > max)
+    }
+}
+
+/// Measure every `(platform, url)` probe now and then every `interval`,
+/// calling `on_breach` once each time a platform goes over the limit, until
+/// `shutdown`
+pub async fn monitor(
+    guard: Arc<ClockSkewGuard>,
+    probes: Vec<(Platform, String)>,
+    interval: Duration,
+    mut on_breach: impl FnMut(Platform, i64),
+    mut shutdown: watch::Receiver<bool>,
+) {
+    // Each exchange through its own proxy settings
+    let probes: Vec<_> = match probes.into_iter()
+        .map(|(platform, url)| HttpConfig::for_platform(platform).client().map(|http| (platform, url, http)))
+        .collect::<Result<_, _>>()
+    {
+        Ok(probes) => probes,
+        Err(e) => {
+            warn!("[CLOCK] Clock checks disabled, failed to build HTTP client: {}", e);
+            return;
+        }
+    };
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = shutdown.wait_for(|&stop| stop) => return,
+            _ = ticker.tick() => {}
+        }
+        for (platform, url, http) in &probes {
+            let skew_ms = match measure(http, url).await {
+                Ok(skew_ms) => skew_ms,
+                Err(e) => {
+                    warn!("[CLOCK] {} clock check failed: {:#}", platform, e);
+                    continue;
+                }
+            };
+            let first = guard.skew_ms(*platform).is_none();
+            if guard.record(*platform, skew_ms) {
+                error!("[CLOCK] 🕐 Local clock {:+}ms off {} (limit {}ms): orders to {} held until it is fixed",
+                       skew_ms, platform, guard.max_skew_ms().unwrap_or_default(), platform);
+                on_breach(*platform, skew_ms);
+            } else if first {
+                info!("[CLOCK] {} clock skew {:+}ms", platform, skew_ms);
+            } else {
+                debug!("[CLOCK] {} clock skew {:+}ms", platform, skew_ms);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skew_from_date_header() {
+        let server = DateTime::parse_from_rfc2822("Wed, 14 Oct 2026 12:00:00 GMT").unwrap().with_timezone(&Utc);
+        let at = |ms| server + chrono::Duration::milliseconds(ms);
+        // 200ms round trip centred on the reported second: no skew
+        assert_eq!(skew_ms(at(400), at(600), server), 0);
+        // Local clock 3s ahead
+        assert_eq!(skew_ms(at(3400), at(3600), server), 3000);
+        assert_eq!(skew_ms(at(-2500), at(-2500), server), -3000);
+    }
+
+    #[test]
+    fn test_guard_blocks_and_alerts_once() {
+        let guard = ClockSkewGuard::new(ClockSkewConfig { max_skew_ms: Some(2000), check_interval_secs: 300 });
+        assert_eq!(guard.blocked([Platform::Kalshi, Platform::Polymarket]), None, "nothing measured yet");
+
+        assert!(!guard.record(Platform::Polymarket, 300));
+        assert!(guard.record(Platform::Kalshi, -4500));
+        assert!(!guard.record(Platform::Kalshi, -4200), "already alerted");
+        assert_eq!(guard.blocked([Platform::Polymarket]), None);
+        assert_eq!(guard.blocked([Platform::Polymarket, Platform::Kalshi]), Some((Platform::Kalshi, -4200)));
+
+        assert!(!guard.record(Platform::Kalshi, 100));
+        assert_eq!(guard.blocked([Platform::Kalshi]), None);
+        assert!(guard.record(Platform::Kalshi, 2500), "re-armed after recovering");
+
+        let disabled = ClockSkewGuard::new(ClockSkewConfig { max_skew_ms: None, check_interval_secs: 300 });
+        assert!(!disabled.record(Platform::Kalshi, 60_000));
+        assert_eq!(disabled.blocked([Platform::Kalshi]), None);
+    }
+}
@@ -3,21 +3,32 @@
 //! This module handles the discovery of matching markets between Kalshi and Polymarket,
 //! with support for caching, incremental updates, and parallel processing.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures_util::{stream, StreamExt};
 use governor::{Quota, RateLimiter, state::NotKeyed, clock::DefaultClock, middleware::NoOpMiddleware};
 use serde::{Serialize, Deserialize};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::num::NonZeroU32;
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::Semaphore;
-use tracing::{info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rand::Rng;
+use tokio::sync::{broadcast, mpsc, watch, Semaphore};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
 
 use crate::cache::TeamCache;
-use crate::config::{LeagueConfig, get_league_configs, get_league_config};
+use crate::config::{BookDepthConfig, FeeSchedule, HotMarketsConfig, IdleConfig, LeagueConfig, Liquidity, MatchConfig, PlatformHealthConfig, SpreadCaptureConfig, StaleTicksConfig, TradeCooldownConfig, WarmupConfig, get_league_configs, get_league_config};
+use crate::execution::{arb_legs, NanoClock};
 use crate::kalshi::KalshiApiClient;
-use crate::polymarket::GammaClient;
-use crate::types::{MarketPair, MarketType, DiscoveryResult, KalshiMarket, KalshiEvent};
+use crate::polymarket::{GammaClient, GammaEventMarket, MarketStatus, SlugCache, ask_bps_to_cents, bid_bps_to_cents, contracts_to_size_cents};
+use crate::polymarket_clob::OrderBookStore;
+use crate::position_tracker::SharedPositionTracker;
+use crate::types::{
+    AtomicMarketState, GlobalState, MarketPair, MarketType, DiscoveryResult, FastExecutionRequest, KalshiMarket, KalshiEvent,
+    MultiOutcomeMarket, MultiOutcomeRequest, OrderType, Platform, SpreadRequest, fxhash_str, unix_ms, MAX_MARKETS, NO_PRICE,
+};
 
 /// Max concurrent Gamma API requests
 const GAMMA_CONCURRENCY: usize = 20;
@@ -36,11 +47,20 @@ const DISCOVERY_CACHE_PATH: &str = ".discovery_cache.json";
 /// Cache TTL in seconds (2 hours - new markets appear every ~2 hours)
 const CACHE_TTL_SECS: u64 = 2 * 60 * 60;
 
+/// Markets disabled with `/disable`, kept across restarts
+const DISABLED_MARKETS_PATH: &str = ".disabled_markets.json";
+
 /// Task for parallel Gamma lookup
 struct GammaLookupTask {
     event: Arc<KalshiEvent>,
     market: KalshiMarket,
     poly_slug: String,
+    /// Polymarket event slug, searched by title when `poly_slug` is not found
+    event_slug: String,
+    /// `poly_slug` comes from a manual override (no fuzzy fallback)
+    is_override: bool,
+    /// Listed in HOT_MARKETS: looked up without the slug cache
+    hot: bool,
     market_type: MarketType,
     league: String,
 }
@@ -97,9 +117,13 @@ pub struct DiscoveryClient {
     kalshi: Arc<KalshiApiClient>,
     gamma: Arc<GammaClient>,
     pub team_cache: Arc<TeamCache>,
+    matcher: Arc<TitleMatcher>,
+    hot: HotMarketsConfig,
     kalshi_limiter: Arc<KalshiRateLimiter>,
     kalshi_semaphore: Arc<Semaphore>,  // Global concurrency limit for Kalshi
     gamma_semaphore: Arc<Semaphore>,
+    /// Window the Gamma lookups of a discovery are spread over
+    lookup_warmup: Duration,
 }
 
 impl DiscoveryClient {
@@ -112,12 +136,27 @@ impl DiscoveryClient {
             kalshi: Arc::new(kalshi),
             gamma: Arc::new(GammaClient::new()),
             team_cache: Arc::new(team_cache),
+            matcher: Arc::new(TitleMatcher::load(MatchConfig::from_env())),
+            hot: HotMarketsConfig::from_env(),
             kalshi_limiter,
             kalshi_semaphore: Arc::new(Semaphore::new(KALSHI_GLOBAL_CONCURRENCY)),
             gamma_semaphore: Arc::new(Semaphore::new(GAMMA_CONCURRENCY)),
+            lookup_warmup: Duration::ZERO,
         }
     }
 
+    /// Spread each league's Gamma lookups over `warmup` instead of sending
+    /// them all at once
+    pub fn with_lookup_warmup(mut self, warmup: Duration) -> Self {
+        self.lookup_warmup = warmup;
+        self
+    }
+
+    /// Gamma slug lookup cache
+    pub fn gamma_cache(&self) -> Arc<SlugCache> {
+        self.gamma.cache()
+    }
+
     /// Load cache from disk (async)
     async fn load_cache() -> Option<DiscoveryCache> {
         let data = tokio::fs::read_to_string(DISCOVERY_CACHE_PATH).await.ok()?;
@@ -229,6 +268,27 @@ impl DiscoveryClient {
         result
     }
 
+    /// Remove pairs whose Polymarket market has closed or resolved.
+    /// Pairs are kept when the status lookup fails.
+    async fn drop_closed(&self, pairs: Vec<MarketPair>) -> Vec<MarketPair> {
+        let statuses = futures_util::future::join_all(pairs.iter().map(|pair| async {
+            let _permit = self.gamma_semaphore.acquire().await;
+            self.gamma.market_status(&pair.poly_slug).await
+        })).await;
+
+        pairs.into_iter()
+            .zip(statuses)
+            .filter(|(pair, status)| match status {
+                Ok(Some(MarketStatus::Closed | MarketStatus::Resolved { .. })) => {
+                    info!("  🗑 Dropping closed market: {}", pair.poly_slug);
+                    false
+                }
+                _ => true,
+            })
+            .map(|(pair, _)| pair)
+            .collect()
+    }
+
     /// Incremental discovery - merge cached pairs with newly discovered ones
     async fn discover_incremental(&self, leagues: &[&str], cache: DiscoveryCache) -> DiscoveryResult {
         let configs: Vec<_> = if leagues.is_empty() {
@@ -246,8 +306,8 @@ impl DiscoveryClient {
 
         let league_results = futures_util::future::join_all(league_futures).await;
 
-        // Merge cached pairs with newly discovered ones
-        let mut all_pairs = cache.pairs;
+        // Merge cached pairs (minus markets closed since) with newly discovered ones
+        let mut all_pairs = self.drop_closed(cache.pairs).await;
         let mut new_count = 0;
 
         for league_result in league_results {
@@ -411,48 +471,71 @@ impl DiscoveryClient {
         let lookup_futures: Vec<_> = event_markets
             .into_iter()
             .map(|(parsed, event, market)| {
-                let poly_slug = self.build_poly_slug(config.poly_prefix, &parsed, market_type, &market);
+                let override_slug = self.matcher.override_for(&market.ticker);
+                let poly_slug = override_slug.map(str::to_string)
+                    .unwrap_or_else(|| self.build_poly_slug(config.poly_prefix, &parsed, market_type, &market));
                 
                 GammaLookupTask {
+                    hot: self.hot.is_hot(&market.ticker, &poly_slug),
                     event,
                     market,
                     poly_slug,
+                    event_slug: self.build_poly_event_slug(config.poly_prefix, &parsed),
+                    is_override: override_slug.is_some(),
                     market_type,
                     league: config.league_code.to_string(),
                 }
             })
             .collect();
         
-        // Execute lookups in parallel 
-        let pairs: Vec<MarketPair> = stream::iter(lookup_futures)
-            .map(|task| {
+        // Execute lookups in parallel, ramped up over the warm-up window
+        let lookups = PollSchedule::staggered_batches(lookup_futures, 1, self.lookup_warmup, Instant::now());
+        let pairs: Vec<MarketPair> = stream::iter(lookups.into_iter().flat_map(|(due, tasks)| tasks.into_iter().map(move |task| (due, task))))
+            .map(|(due, task)| {
                 let gamma = self.gamma.clone();
+                let matcher = self.matcher.clone();
                 let semaphore = self.gamma_semaphore.clone();
                 async move {
+                    tokio::time::sleep_until(due).await;
                     let _permit = semaphore.acquire().await.ok()?;
-                    match gamma.lookup_market(&task.poly_slug).await {
-                        Ok(Some((yes_token, no_token))) => {
-                            let team_suffix = extract_team_suffix(&task.market.ticker);
-                            Some(MarketPair {
-                                pair_id: format!("{}-{}", task.poly_slug, task.market.ticker).into(),
-                                league: task.league.into(),
-                                market_type: task.market_type,
-                                description: format!("{} - {}", task.event.title, task.market.title).into(),
-                                kalshi_event_ticker: task.event.event_ticker.clone().into(),
-                                kalshi_market_ticker: task.market.ticker.into(),
-                                poly_slug: task.poly_slug.into(),
-                                poly_yes_token: yes_token.into(),
-                                poly_no_token: no_token.into(),
-                                line_value: task.market.floor_strike,
-                                team_suffix: team_suffix.map(|s| s.into()),
-                            })
+                    let found = match gamma.lookup_market(&task.poly_slug, task.hot).await {
+                        Ok(Some(listing)) => Some((task.poly_slug.clone(), listing)),
+                        Ok(None) if !task.is_override => {
+                            // Slug guess missed: score the event's markets by title
+                            match gamma.event_markets(&task.event_slug).await {
+                                Ok(candidates) => matcher
+                                    .best_match(&task.market.ticker, &kalshi_match_title(&task.market), &candidates)
+                                    .map(|m| (m.slug.clone(), m.listing.clone())),
+                                Err(e) => {
+                                    debug!("  Gamma event lookup failed for {}: {}", task.event_slug, e);
+                                    None
+                                }
+                            }
                         }
                         Ok(None) => None,
                         Err(e) => {
                             warn!("  ⚠️ Gamma lookup failed for {}: {}", task.poly_slug, e);
                             None
                         }
-                    }
+                    };
+
+                    let (poly_slug, listing) = found?;
+                    let team_suffix = extract_team_suffix(&task.market.ticker);
+                    Some(MarketPair {
+                        pair_id: format!("{}-{}", poly_slug, task.market.ticker).into(),
+                        league: task.league.into(),
+                        market_type: task.market_type,
+                        description: format!("{} - {}", task.event.title, task.market.title).into(),
+                        kalshi_event_ticker: task.event.event_ticker.clone().into(),
+                        kalshi_series_ticker: task.event.series_ticker.clone().unwrap_or_default().into(),
+                        kalshi_market_ticker: task.market.ticker.into(),
+                        poly_slug: poly_slug.into(),
+                        poly_yes_token: listing.yes_token.into(),
+                        poly_no_token: listing.no_token.into(),
+                        poly_constraints: listing.constraints,
+                        line_value: task.market.floor_strike,
+                        team_suffix: team_suffix.map(|s| s.into()),
+                    })
                 }
             })
             .buffer_unordered(GAMMA_CONCURRENCY)
@@ -463,14 +546,8 @@ impl DiscoveryClient {
         Ok(pairs)
     }
     
-    /// Build Polymarket slug from Kalshi event data
-    fn build_poly_slug(
-        &self,
-        poly_prefix: &str,
-        parsed: &ParsedKalshiTicker,
-        market_type: MarketType,
-        market: &KalshiMarket,
-    ) -> String {
+    /// Build the Polymarket event slug (league-team1-team2-date) from a Kalshi event
+    fn build_poly_event_slug(&self, poly_prefix: &str, parsed: &ParsedKalshiTicker) -> String {
         // Convert Kalshi team codes to Polymarket codes using cache
         let poly_team1 = self.team_cache
             .kalshi_to_poly(poly_prefix, &parsed.team1)
@@ -482,8 +559,19 @@ impl DiscoveryClient {
         // Convert date from "25DEC27" to "2025-12-27"
         let date_str = kalshi_date_to_iso(&parsed.date);
         
+        format!("{}-{}-{}-{}", poly_prefix, poly_team1, poly_team2, date_str)
+    }
+
+    /// Build Polymarket slug from Kalshi event data
+    fn build_poly_slug(
+        &self,
+        poly_prefix: &str,
+        parsed: &ParsedKalshiTicker,
+        market_type: MarketType,
+        market: &KalshiMarket,
+    ) -> String {
         // Base slug: league-team1-team2-date
-        let base = format!("{}-{}-{}-{}", poly_prefix, poly_team1, poly_team2, date_str);
+        let base = self.build_poly_event_slug(poly_prefix, parsed);
         
         match market_type {
             MarketType::Moneyline => {
@@ -523,6 +611,114 @@ impl DiscoveryClient {
     }
 }
 
+// === Title Matching ===
+
+/// Words that carry no identity in market titles
+const TITLE_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "at", "be", "by", "for", "in", "of", "on", "or", "the", "to", "v", "vs", "will", "win", "winner",
+];
+
+/// Lowercase, strip punctuation and stopwords, and split into a token set.
+/// "Will Biden win 2024?" and "BIDEN-2024" both become {"biden", "2024"}.
+pub fn normalize_title(title: &str) -> HashSet<String> {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty() && !TITLE_STOPWORDS.contains(t))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Jaccard similarity of the normalized token sets (0.0-1.0)
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_title(a), normalize_title(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Text of a Kalshi market used for title matching
+fn kalshi_match_title(market: &KalshiMarket) -> String {
+    match &market.yes_sub_title {
+        Some(sub) => format!("{} {}", market.title, sub),
+        None => market.title.clone(),
+    }
+}
+
+/// Scores Polymarket candidates against a Kalshi market title, with manual
+/// overrides for pairs the similarity score gets wrong
+pub struct TitleMatcher {
+    threshold: f64,
+    /// Kalshi market ticker → Polymarket market slug
+    overrides: HashMap<String, String>,
+}
+
+impl TitleMatcher {
+    pub fn new(threshold: f64, overrides: HashMap<String, String>) -> Self {
+        Self { threshold, overrides }
+    }
+
+    /// Build from config, reading overrides from `overrides_path` if present
+    pub fn load(config: MatchConfig) -> Self {
+        let overrides = match std::fs::read_to_string(&config.overrides_path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Failed to parse match overrides {}: {}", config.overrides_path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        if !overrides.is_empty() {
+            info!("📂 Loaded {} manual market overrides", overrides.len());
+        }
+        Self::new(config.threshold, overrides)
+    }
+
+    /// Manual Polymarket slug for a Kalshi market, if configured
+    pub fn override_for(&self, kalshi_ticker: &str) -> Option<&str> {
+        self.overrides.get(kalshi_ticker).map(String::as_str)
+    }
+
+    /// Best candidate scoring at least the threshold. Ties for the best
+    /// score are ambiguous and rejected; near-misses are logged at debug.
+    pub fn best_match<'a>(
+        &self,
+        kalshi_ticker: &str,
+        kalshi_title: &str,
+        candidates: &'a [GammaEventMarket],
+    ) -> Option<&'a GammaEventMarket> {
+        let mut best: Option<(&GammaEventMarket, f64)> = None;
+        let mut tied = false;
+        for candidate in candidates {
+            let score = title_similarity(kalshi_title, &candidate.question);
+            if score < self.threshold {
+                if score >= self.threshold / 2.0 {
+                    debug!("  Near-miss {} ↔ {} ({:.2} < {:.2}): '{}' vs '{}'",
+                           kalshi_ticker, candidate.slug, score, self.threshold, kalshi_title, candidate.question);
+                }
+                continue;
+            }
+            match best {
+                Some((_, best_score)) if (score - best_score).abs() < f64::EPSILON => tied = true,
+                Some((_, best_score)) if score < best_score => {}
+                _ => {
+                    best = Some((candidate, score));
+                    tied = false;
+                }
+            }
+        }
+
+        if tied {
+            debug!("  Ambiguous title match for {} ('{}'), skipping", kalshi_ticker, kalshi_title);
+            return None;
+        }
+        let (candidate, score) = best?;
+        info!("  🔗 Title match {} ↔ {} ({:.2})", kalshi_ticker, candidate.slug, score);
+        Some(candidate)
+    }
+}
+
 // === Helpers ===
 
 #[derive(Debug, Clone)]
@@ -653,10 +849,1074 @@ fn extract_team_suffix(ticker: &str) -> Option<String> {
     splits.next().map(|s| s.to_uppercase())
 }
 
+// === Market status polling ===
+
+/// Per-market due times for one platform's status checks.
+///
+/// Watchlisted markets come due at the fast interval and the rest at the base
+/// interval, so each wake-up only polls the markets that are actually due.
+pub struct PollSchedule {
+    base: Duration,
+    fast: Option<Duration>,
+    watched: HashSet<u16>,
+    next_due: HashMap<u16, Instant>,
+    /// Markets not yet polled since startup
+    warming: HashSet<u16>,
+}
+
+impl PollSchedule {
+    /// Every market starts due at `now`; `markets` yields (market_id, watchlisted).
+    /// A fast interval no shorter than the base one is ignored.
+    pub fn new(base: Duration, fast: Option<Duration>, markets: impl IntoIterator<Item = (u16, bool)>, now: Instant) -> Self {
+        let mut watched = HashSet::new();
+        let mut next_due = HashMap::new();
+        for (id, watch) in markets {
+            if watch {
+                watched.insert(id);
+            }
+            next_due.insert(id, now);
+        }
+        let warming = next_due.keys().copied().collect();
+        Self { base, fast: fast.filter(|f| *f < base), watched, next_due, warming }
+    }
+
+    /// Spread the first polls over `warmup` instead of firing them all at
+    /// `now`: each market gets its own slot (watchlisted first) plus a random
+    /// offset within that slot, so startup ramps up to the full rate.
+    pub fn staggered(mut self, warmup: Duration, now: Instant) -> Self {
+        if warmup.is_zero() || self.next_due.is_empty() {
+            return self;
+        }
+        let mut ids: Vec<u16> = self.next_due.keys().copied().collect();
+        ids.sort_by_key(|id| (!self.watched.contains(id), *id));
+        let slot = warmup / ids.len() as u32;
+        let mut rng = rand::thread_rng();
+        for (i, id) in ids.into_iter().enumerate() {
+            let jitter = if slot.is_zero() { Duration::ZERO } else { rng.gen_range(Duration::ZERO..slot) };
+            self.next_due.insert(id, now + slot * i as u32 + jitter);
+        }
+        self
+    }
+
+    /// `items` in order, in batches of `batch` due over `warmup` the way
+    /// `staggered` spreads the first polls, for startup requests that are not
+    /// on a schedule (discovery lookups, WebSocket subscriptions)
+    pub fn staggered_batches<T>(items: Vec<T>, batch: usize, warmup: Duration, now: Instant) -> VecDeque<(Instant, Vec<T>)> {
+        let mut batches: Vec<Vec<T>> = Vec::new();
+        for item in items {
+            match batches.last_mut() {
+                Some(last) if last.len() < batch.max(1) => last.push(item),
+                _ => batches.push(vec![item]),
+            }
+        }
+        let ids = (0..batches.len()).map_while(|i| u16::try_from(i).ok()).map(|id| (id, false));
+        let schedule = Self::new(warmup, None, ids, now).staggered(warmup, now);
+        batches.into_iter().enumerate()
+            .map(|(i, batch)| {
+                let due = u16::try_from(i).ok().and_then(|id| schedule.next_due.get(&id).copied());
+                (due.unwrap_or(now + warmup), batch)
+            })
+            .collect()
+    }
+
+    /// True until every market has had its first poll
+    pub fn warming_up(&self) -> bool {
+        !self.warming.is_empty()
+    }
+
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    pub fn interval(&self, market_id: u16) -> Duration {
+        match self.fast {
+            Some(fast) if self.watched.contains(&market_id) => fast,
+            _ => self.base,
+        }
+    }
+
+    /// Markets due at `now` (watchlisted first), each rescheduled one interval from `now`
+    pub fn take_due(&mut self, now: Instant) -> Vec<u16> {
+        let mut due: Vec<u16> = self.next_due.iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        due.sort_by_key(|id| (!self.watched.contains(id), *id));
+        for id in &due {
+            let next = now + self.interval(*id);
+            self.next_due.insert(*id, next);
+            self.warming.remove(id);
+        }
+        due
+    }
+
+    /// Earliest due time, `None` once every market has been removed
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.next_due.values().min().copied()
+    }
+
+    /// Stop polling a market (e.g. once it has resolved)
+    pub fn remove(&mut self, market_id: u16) {
+        self.next_due.remove(&market_id);
+        self.watched.remove(&market_id);
+        self.warming.remove(&market_id);
+    }
+
+    pub fn markets(&self) -> usize {
+        self.next_due.len()
+    }
+
+    pub fn watched(&self) -> usize {
+        self.watched.iter().filter(|id| self.next_due.contains_key(id)).count()
+    }
+
+    /// Status requests per minute at steady state
+    pub fn polls_per_min(&self) -> f64 {
+        self.next_due.keys().map(|id| 60.0 / self.interval(*id).as_secs_f64()).sum()
+    }
+}
+
+/// Where a status poller reads market lifecycle from
+pub enum StatusSource {
+    Kalshi(Arc<KalshiApiClient>),
+    Polymarket(GammaClient),
+}
+
+impl StatusSource {
+    pub fn platform(&self) -> Platform {
+        match self {
+            Self::Kalshi(_) => Platform::Kalshi,
+            Self::Polymarket(_) => Platform::Polymarket,
+        }
+    }
+
+    /// Requests in flight per round (the shared rate limiters still apply)
+    fn concurrency(&self) -> usize {
+        match self {
+            Self::Kalshi(_) => 2,
+            Self::Polymarket(_) => 8,
+        }
+    }
+
+    async fn status(&self, pair: &MarketPair) -> crate::error::Result<Option<MarketStatus>> {
+        match self {
+            Self::Kalshi(api) => Ok(Some(api.get_market(&pair.kalshi_market_ticker).await?.market_status())),
+            Self::Polymarket(gamma) => gamma.market_status(&pair.poly_slug).await,
+        }
+    }
+}
+
+/// Whether each platform's API is answering, judged from the status checks.
+/// After `PLATFORM_DOWN_ERRORS` failed checks in a row a platform is down:
+/// opportunities with a leg on it are held back while the other platform
+/// keeps trading, until one of its checks succeeds again.
+pub struct PlatformHealth {
+    down_after: Option<u32>,
+    /// Failed checks in a row, per platform
+    failures: [AtomicU32; 2],
+    down: [AtomicBool; 2],
+}
+
+impl PlatformHealth {
+    pub fn new(config: PlatformHealthConfig) -> Self {
+        Self { down_after: config.down_after_errors, failures: Default::default(), down: Default::default() }
+    }
+
+    /// Record one check on `platform`; returns the new state (true = down)
+    /// when the platform just went down or came back
+    pub fn record(&self, platform: Platform, ok: bool) -> Option<bool> {
+        let slot = platform_slot(platform);
+        let down = if ok {
+            self.failures[slot].store(0, Ordering::Relaxed);
+            false
+        } else {
+            let failures = self.failures[slot].fetch_add(1, Ordering::Relaxed) + 1;
+            match self.down_after {
+                Some(limit) => failures >= limit,
+                None => return None,
+            }
+        };
+        (self.down[slot].swap(down, Ordering::Relaxed) != down).then_some(down)
+    }
+
+    pub fn is_down(&self, platform: Platform) -> bool {
+        self.down[platform_slot(platform)].load(Ordering::Relaxed)
+    }
+
+    /// First leg of `arb_type` on a platform that is down
+    pub fn blocking(&self, arb_type: crate::types::ArbType) -> Option<Platform> {
+        let (yes, no) = arb_legs(arb_type);
+        [yes.platform, no.platform].into_iter().find(|p| self.is_down(*p))
+    }
+}
+
+/// Check market status on `schedule` and forward every market that is no
+/// longer open. Each platform runs its own poller, so a slow API never delays
+/// another platform's checks. Resolved markets are dropped from the schedule;
+/// failed lookups are logged and retried at the next interval. Every check
+/// goes to `health`; `on_health` is called when the platform goes down or
+/// comes back. Each poll is also a `stale` tick on the platform's book;
+/// `on_stale` gets every pair that goes stale with its ticks and silence.
+/// Returns between polls once `shutdown` is signalled.
+#[allow(clippy::too_many_arguments)]
+pub async fn poll_market_status(
+    source: StatusSource,
+    mut schedule: PollSchedule,
+    state: Arc<GlobalState>,
+    health: Arc<PlatformHealth>,
+    mut on_health: impl FnMut(bool),
+    stale: Arc<StaleTicks>,
+    mut on_stale: impl FnMut(&MarketPair, u32, Duration),
+    tx: mpsc::Sender<(Platform, u16, MarketStatus)>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let platform = source.platform();
+    let mut next_full_set_tick = Instant::now() + schedule.base();
+    while let Some(wake) = schedule.next_wake() {
+        tokio::select! {
+            _ = shutdown.wait_for(|&stop| stop) => return,
+            _ = tokio::time::sleep_until(wake) => {}
+        }
+        let warming = schedule.warming_up();
+        let now = Instant::now();
+        let due = schedule.take_due(now);
+        // Full sets are not on the schedule: one cycle per base interval
+        if platform == Platform::Polymarket && now >= next_full_set_tick {
+            stale.tick_full_sets();
+            next_full_set_tick = now + schedule.base();
+        }
+        for change in stale.polled(platform, &due, &state) {
+            let Some(market) = state.get_by_id(change.market_id) else { continue };
+            let Some(pair) = market.pair.as_deref() else { continue };
+            if change.stale {
+                let updated_ms = book_updated_ms(market, platform).unwrap_or_default();
+                let silent = Duration::from_millis(unix_ms().saturating_sub(updated_ms));
+                warn!("[DISCOVERY] 🧊 {} stale: no {} book update for {} polls ({}s), not traded until the next one",
+                      pair.description, platform, change.ticks, silent.as_secs());
+                on_stale(pair, change.ticks, silent);
+            } else {
+                info!("[DISCOVERY] {} {} book updating again after {} polls", pair.description, platform, change.ticks);
+            }
+        }
+        if warming && !schedule.warming_up() {
+            info!("[DISCOVERY] {} warm-up complete: all {} markets polled, now at full rate ({:.1} req/min)",
+                  platform, schedule.markets(), schedule.polls_per_min());
+        }
+        debug!("[DISCOVERY] {} status poll: {} due of {} markets ({} watchlisted), effective rate {:.1} req/min",
+               platform, due.len(), schedule.markets(), schedule.watched(), schedule.polls_per_min());
+
+        let pairs: Vec<_> = due.into_iter()
+            .filter_map(|id| state.get_by_id(id).and_then(|m| m.pair.clone()).map(|pair| (id, pair)))
+            .collect();
+        let (source, health) = (&source, &health);
+        let checks: Vec<_> = stream::iter(pairs)
+            .map(|(id, pair)| async move {
+                match source.status(&pair).await {
+                    Ok(status) => Ok(status.map(|s| (id, s))),
+                    Err(e) => {
+                        // Logged once per outage instead while the platform is down
+                        if !health.is_down(platform) {
+                            warn!("[DISCOVERY] {} status check failed for {}: {}", platform, pair.pair_id, e);
+                        }
+                        Err(())
+                    }
+                }
+            })
+            .buffer_unordered(source.concurrency())
+            .collect()
+            .await;
+
+        let mut results = Vec::with_capacity(checks.len());
+        for check in checks {
+            if let Some(down) = health.record(platform, check.is_ok()) {
+                on_health(down);
+            }
+            results.extend(check.ok().flatten());
+        }
+
+        for (id, status) in results {
+            if matches!(status, MarketStatus::Resolved { .. }) {
+                schedule.remove(id);
+            }
+            if status != MarketStatus::Open && tx.send((platform, id, status)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Load the configured Polymarket neg-risk events. Events that are unknown,
+/// not neg-risk or have a closed outcome are skipped with a warning.
+pub async fn discover_multi_outcome(gamma: &GammaClient, event_slugs: &[String]) -> Vec<MultiOutcomeMarket> {
+    let mut markets = Vec::new();
+    for slug in event_slugs {
+        match gamma.neg_risk_event(slug).await {
+            Ok(Some(market)) => {
+                info!("[DISCOVERY] Neg-risk event {}: {} outcomes", slug, market.outcomes.len());
+                markets.push(market);
+            }
+            Ok(None) => warn!("[DISCOVERY] {} is not a tradable neg-risk event, skipped", slug),
+            Err(e) => warn!("[DISCOVERY] Neg-risk event lookup failed for {}: {}", slug, e),
+        }
+    }
+    markets
+}
+
+/// Full-set arb on a multi-outcome market at the current top of its books:
+/// the asks of all outcomes leave at least `min_profit_cents` after fees
+pub fn full_set_arb(
+    state: &GlobalState,
+    books: &OrderBookStore,
+    index: u16,
+    fees: &FeeSchedule,
+    now_ns: u64,
+) -> Option<MultiOutcomeRequest> {
+    let market = state.multi_outcome.get(index as usize)?;
+    let asks = market.outcomes.iter()
+        .map(|outcome| books.best_ask(fxhash_str(&outcome.token))
+            .map_or((NO_PRICE, 0), |(bps, contracts)| (ask_bps_to_cents(bps), contracts_to_size_cents(contracts))))
+        .collect();
+    let req = MultiOutcomeRequest { market_index: index, asks, detected_ns: now_ns };
+    (req.net_profit_cents(fees)? >= fees.min_profit_cents && req.contracts() >= 1).then_some(req)
+}
+
+/// Re-check a multi-outcome market whenever one of its outcome quotes moves
+/// and forward full-set arbs to execution, unless the market is disabled,
+/// one of its outcome books is stale or Polymarket is down. If the quote
+/// stream lags, every market is re-checked.
+#[allow(clippy::too_many_arguments)]
+pub async fn watch_full_set_arbs(
+    state: Arc<GlobalState>,
+    books: Arc<OrderBookStore>,
+    disabled: Arc<DisabledMarkets>,
+    health: Arc<PlatformHealth>,
+    stale: Arc<StaleTicks>,
+    idle: Arc<IdleWatchdog>,
+    tx: mpsc::Sender<MultiOutcomeRequest>,
+) {
+    if state.multi_outcome.is_empty() {
+        return;
+    }
+    let fees = FeeSchedule::from_env();
+    let clock = NanoClock::new();
+    let mut quotes = books.subscribe();
+    loop {
+        let indices: Vec<u16> = match quotes.recv().await {
+            Ok(update) => match state.poly_outcome_to_id.get(&update.token_hash) {
+                Some(&(index, _)) => {
+                    stale.quoted(update.token_hash);
+                    vec![index]
+                }
+                None => continue,
+            },
+            Err(broadcast::error::RecvError::Lagged(_)) => (0..state.multi_outcome.len() as u16).collect(),
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        for index in indices {
+            let Some(req) = full_set_arb(&state, &books, index, &fees, clock.now_ns()) else { continue };
+            idle.detected(unix_ms());
+            let market = &state.multi_outcome[index as usize];
+            if disabled.contains_full_set(market) {
+                debug!("[DISCOVERY] Disabled: full set on {} held back", market.description);
+                continue;
+            }
+            if stale.is_full_set_stale(market) {
+                debug!("[DISCOVERY] Stale: full set on {} held back", market.description);
+                continue;
+            }
+            if health.is_down(Platform::Polymarket) {
+                debug!("[DISCOVERY] {} down: full set on {} held back", Platform::Polymarket, market.description);
+                continue;
+            }
+            let _ = tx.try_send(req);
+        }
+    }
+}
+
+/// Time since an opportunity was last detected on any market, so a stalled
+/// pipeline is noticed before the missing trades are
+pub struct IdleWatchdog {
+    alert_after: Option<Duration>,
+    /// Unix milliseconds of the last detection (startup until the first one)
+    last_detection_ms: AtomicU64,
+    alerted: AtomicBool,
+}
+
+impl IdleWatchdog {
+    pub fn new(config: IdleConfig, now_ms: u64) -> Self {
+        Self {
+            alert_after: config.alert_after_secs.map(Duration::from_secs),
+            last_detection_ms: AtomicU64::new(now_ms),
+            alerted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn alert_after(&self) -> Option<Duration> {
+        self.alert_after
+    }
+
+    /// An opportunity was detected: the idle time starts over
+    pub fn detected(&self, now_ms: u64) {
+        self.last_detection_ms.store(now_ms, Ordering::Relaxed);
+        self.alerted.store(false, Ordering::Relaxed);
+    }
+
+    /// Time without a detection, once per idle stretch past the alert delay
+    pub fn check(&self, now_ms: u64) -> Option<Duration> {
+        let alert_after = self.alert_after?;
+        let idle = Duration::from_millis(now_ms.saturating_sub(self.last_detection_ms.load(Ordering::Relaxed)));
+        (idle >= alert_after && !self.alerted.swap(true, Ordering::Relaxed)).then_some(idle)
+    }
+}
+
+/// Check `watchdog` periodically until `shutdown`, calling `on_idle` with
+/// the idle time when it goes off. Returns at once if the alert is disabled.
+pub async fn watch_idle(watchdog: Arc<IdleWatchdog>, mut on_idle: impl FnMut(Duration), mut shutdown: watch::Receiver<bool>) {
+    let Some(alert_after) = watchdog.alert_after() else { return };
+    let mut ticker = tokio::time::interval((alert_after / 10).clamp(Duration::from_secs(1), Duration::from_secs(60)));
+    loop {
+        tokio::select! {
+            _ = shutdown.wait_for(|&stop| stop) => return,
+            _ = ticker.tick() => {}
+        }
+        if let Some(idle) = watchdog.check(unix_ms()) {
+            warn!("[DISCOVERY] 💤 No opportunity detected on any market for {}s", idle.as_secs());
+            on_idle(idle);
+        }
+    }
+}
+
+/// Startup gate: trading stays off until every monitored pair has a complete
+/// book from each platform it trades on (both Polymarket tokens' snapshots,
+/// a Kalshi top of book), or the warmup timeout runs out
+pub struct Warmup {
+    started_ms: u64,
+    timeout: Option<Duration>,
+    done: AtomicBool,
+}
+
+/// How the warmup ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmupDone {
+    /// Pairs with all their books in, out of `total`
+    pub ready: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+    /// The timeout ran out before every book was in
+    pub timed_out: bool,
+}
+
+impl Warmup {
+    pub fn new(config: WarmupConfig, now_ms: u64) -> Self {
+        Self {
+            started_ms: now_ms,
+            timeout: config.timeout_secs.map(Duration::from_secs),
+            done: AtomicBool::new(config.timeout_secs.is_none()),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+
+    /// Pairs whose books are all in, and monitored pairs. Kalshi books only
+    /// count when `kalshi` is traded.
+    pub fn progress(state: &GlobalState, books: &OrderBookStore, kalshi: bool) -> (usize, usize) {
+        let pairs = state.markets.iter().filter_map(|m| m.pair.as_deref().map(|pair| (m, pair)));
+        pairs.fold((0, 0), |(ready, total), (market, pair)| {
+            let complete = (!kalshi || market.kalshi.updated_ms().is_some())
+                && books.get(fxhash_str(&pair.poly_yes_token)).is_some()
+                && books.get(fxhash_str(&pair.poly_no_token)).is_some();
+            (ready + complete as usize, total + 1)
+        })
+    }
+
+    /// Open the gate at `now_ms` if the books are in or the timeout ran out;
+    /// the outcome is returned the one time it opens
+    pub fn check(&self, state: &GlobalState, books: &OrderBookStore, kalshi: bool, now_ms: u64) -> Option<WarmupDone> {
+        if self.is_done() {
+            return None;
+        }
+        let (ready, total) = Self::progress(state, books, kalshi);
+        let elapsed = Duration::from_millis(now_ms.saturating_sub(self.started_ms));
+        let timed_out = ready < total && self.timeout.is_some_and(|t| elapsed >= t);
+        if ready < total && !timed_out {
+            return None;
+        }
+        (!self.done.swap(true, Ordering::AcqRel)).then_some(WarmupDone { ready, total, elapsed, timed_out })
+    }
+}
+
+/// Check `warmup` until it opens, then call `on_done`. Returns at once if
+/// there is no warmup.
+pub async fn watch_warmup(
+    warmup: Arc<Warmup>,
+    state: Arc<GlobalState>,
+    books: Arc<OrderBookStore>,
+    kalshi: bool,
+    on_done: impl FnOnce(WarmupDone),
+) {
+    let mut ticker = tokio::time::interval(Duration::from_millis(250));
+    while !warmup.is_done() {
+        ticker.tick().await;
+        let Some(done) = warmup.check(&state, &books, kalshi, unix_ms()) else { continue };
+        if done.timed_out {
+            warn!("[DISCOVERY] ⏱ Warmup timed out after {}s with {}/{} markets' books in: trading enabled",
+                  done.elapsed.as_secs(), done.ready, done.total);
+        } else {
+            info!("[DISCOVERY] ✅ Warmup complete: books of all {} markets in after {:.1}s, trading enabled",
+                  done.total, done.elapsed.as_secs_f64());
+        }
+        on_done(done);
+        return;
+    }
+}
+
+/// Status polls since each market's books last changed, a tick-count view of
+/// staleness next to `MAX_SNAPSHOT_AGE_MS`. Each platform's poller counts its
+/// own polls against that platform's book, so a market is only as fresh as
+/// the older of its books: once it goes `MAX_STALE_TICKS` polls without an
+/// update on a platform it is stale, and its opportunities and spreads are
+/// held back until that book updates. Full-set outcomes are counted on
+/// Polymarket poll cycles. Books that never came in are left to the warmup.
+pub struct StaleTicks {
+    max_ticks: Option<u32>,
+    /// Per market, one slot per platform
+    markets: Box<[[MarketTicks; 2]]>,
+    /// Poll cycles since each full-set outcome's book last changed, by token hash
+    outcomes: Mutex<HashMap<u64, u32>>,
+}
+
+#[derive(Default)]
+struct MarketTicks {
+    /// Book update seen at the previous poll
+    seen_ms: AtomicU64,
+    /// Polls in a row since `seen_ms` changed
+    ticks: AtomicU32,
+    stale: AtomicBool,
+}
+
+/// A market's book going stale or fresh again at a poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleChange {
+    pub market_id: u16,
+    pub platform: Platform,
+    pub stale: bool,
+    /// Polls without an update when it went stale, or when the update came
+    pub ticks: u32,
+}
+
+fn platform_slot(platform: Platform) -> usize {
+    match platform {
+        Platform::Kalshi => 0,
+        Platform::Polymarket => 1,
+    }
+}
+
+/// Latest book update of `market` on `platform`
+fn book_updated_ms(market: &AtomicMarketState, platform: Platform) -> Option<u64> {
+    match platform {
+        Platform::Kalshi => market.kalshi.updated_ms(),
+        Platform::Polymarket => market.poly.updated_ms(),
+    }
+}
+
+impl StaleTicks {
+    pub fn new(config: StaleTicksConfig) -> Self {
+        Self {
+            max_ticks: config.max_ticks,
+            markets: (0..MAX_MARKETS).map(|_| Default::default()).collect(),
+            outcomes: Default::default(),
+        }
+    }
+
+    pub fn max_ticks(&self) -> Option<u32> {
+        self.max_ticks
+    }
+
+    /// Stale on either platform, unless that book updated since its last poll
+    pub fn is_stale(&self, market: &AtomicMarketState) -> bool {
+        let Some(slots) = self.markets.get(market.market_id as usize) else { return false };
+        [Platform::Kalshi, Platform::Polymarket].into_iter().any(|platform| {
+            let slot = &slots[platform_slot(platform)];
+            slot.stale.load(Ordering::Relaxed)
+                && book_updated_ms(market, platform).unwrap_or(0) == slot.seen_ms.load(Ordering::Relaxed)
+        })
+    }
+
+    /// Count one poll of `market_ids` on `platform`; returns the markets whose
+    /// book there went stale or came back since their previous poll
+    pub fn polled(&self, platform: Platform, market_ids: &[u16], state: &GlobalState) -> Vec<StaleChange> {
+        let Some(max_ticks) = self.max_ticks else { return Vec::new() };
+        market_ids.iter().filter_map(|&market_id| {
+            let market = state.get_by_id(market_id).filter(|m| m.pair.is_some())?;
+            let updated_ms = book_updated_ms(market, platform)?;
+            let slot = &self.markets.get(market_id as usize)?[platform_slot(platform)];
+            if slot.seen_ms.swap(updated_ms, Ordering::Relaxed) != updated_ms {
+                let ticks = slot.ticks.swap(0, Ordering::Relaxed);
+                return slot.stale.swap(false, Ordering::Relaxed)
+                    .then_some(StaleChange { market_id, platform, stale: false, ticks });
+            }
+            let ticks = slot.ticks.fetch_add(1, Ordering::Relaxed).saturating_add(1);
+            (ticks >= max_ticks && !slot.stale.swap(true, Ordering::Relaxed))
+                .then_some(StaleChange { market_id, platform, stale: true, ticks })
+        }).collect()
+    }
+
+    /// A full-set outcome's book changed
+    pub fn quoted(&self, token_hash: u64) {
+        if self.max_ticks.is_some() {
+            self.outcomes.lock().unwrap_or_else(|e| e.into_inner()).insert(token_hash, 0);
+        }
+    }
+
+    /// Count one poll cycle on every full-set outcome that has been quoted
+    pub fn tick_full_sets(&self) {
+        if self.max_ticks.is_some() {
+            for ticks in self.outcomes.lock().unwrap_or_else(|e| e.into_inner()).values_mut() {
+                *ticks = ticks.saturating_add(1);
+            }
+        }
+    }
+
+    /// Stale once an outcome of `market` went `MAX_STALE_TICKS` cycles
+    /// without a book change
+    pub fn is_full_set_stale(&self, market: &MultiOutcomeMarket) -> bool {
+        let Some(max_ticks) = self.max_ticks else { return false };
+        let outcomes = self.outcomes.lock().unwrap_or_else(|e| e.into_inner());
+        market.outcomes.iter().any(|o| outcomes.get(&fxhash_str(&o.token)).is_some_and(|&ticks| ticks >= max_ticks))
+    }
+}
+
+/// Post-trade cooldown per market, timed from the latest fill recorded on its
+/// position. Full sets themselves are not held back, but their fills are
+/// recorded per outcome, so one on a pair's token starts that pair's cooldown.
+#[derive(Clone)]
+pub struct TradeCooldowns {
+    positions: SharedPositionTracker,
+    cooldown: Option<Duration>,
+}
+
+impl TradeCooldowns {
+    pub fn new(positions: SharedPositionTracker, config: TradeCooldownConfig) -> Self {
+        Self { positions, cooldown: config.cooldown_secs.map(Duration::from_secs) }
+    }
+
+    /// Time before `pair` may be traded again, `None` if it may now
+    pub async fn remaining(&self, pair: &MarketPair) -> Option<Duration> {
+        let cooldown = self.cooldown?;
+        self.positions.read().await.cooldown_remaining(&pair.pair_id, cooldown, unix_ms())
+    }
+
+    /// Description of an open position on another pair of `pair`'s Kalshi
+    /// event. Markets of one event are not independent hedges: trading both
+    /// can leave offsetting positions that net to nothing after fees.
+    pub async fn event_conflict(&self, state: &GlobalState, pair: &MarketPair) -> Option<String> {
+        let positions = self.positions.read().await;
+        state.event_siblings(pair)
+            .find(|other| positions.get(&other.pair_id).is_some_and(|p| p.status == "open"))
+            .map(|other| other.description.to_string())
+    }
+}
+
+/// Markets excluded from trading at runtime. A key names a pair by its pair
+/// id, Kalshi market ticker or Polymarket slug, and a multi-outcome market by
+/// its Polymarket event slug (case-insensitive). The default set is in memory
+/// only.
+#[derive(Default)]
+pub struct DisabledMarkets {
+    /// None keeps the set in memory only
+    path: Option<PathBuf>,
+    keys: Mutex<BTreeSet<String>>,
+}
+
+impl DisabledMarkets {
+    /// Set persisted to `.disabled_markets.json`, starting from its contents
+    pub fn load() -> Self {
+        let path = PathBuf::from(DISABLED_MARKETS_PATH);
+        let keys = match Self::read(&path) {
+            Ok(keys) => keys,
+            Err(e) => {
+                warn!("[DISCOVERY] {:#}, no market disabled", e);
+                BTreeSet::new()
+            }
+        };
+        Self { path: Some(path), keys: Mutex::new(keys) }
+    }
+
+    fn read(path: &Path) -> Result<BTreeSet<String>> {
+        if !path.exists() {
+            return Ok(BTreeSet::new());
+        }
+        let data = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("Invalid disabled markets in {}", path.display()))
+    }
+
+    fn save(&self, keys: &BTreeSet<String>) {
+        let Some(path) = &self.path else { return };
+        let result = serde_json::to_string_pretty(keys)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| std::fs::write(path, data).with_context(|| format!("Failed to write {}", path.display())));
+        if let Err(e) = result {
+            warn!("[DISCOVERY] {:#}", e);
+        }
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut BTreeSet<String>) -> bool) -> bool {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        let changed = apply(&mut keys);
+        if changed {
+            self.save(&keys);
+        }
+        changed
+    }
+
+    /// Exclude `key`; false if it already was
+    pub fn disable(&self, key: &str) -> bool {
+        let key = key.trim().to_lowercase();
+        !key.is_empty() && self.update(|keys| keys.insert(key))
+    }
+
+    /// Trade `key` again; false if it was not disabled
+    pub fn enable(&self, key: &str) -> bool {
+        let key = key.trim().to_lowercase();
+        self.update(|keys| keys.remove(&key))
+    }
+
+    /// Disabled keys, sorted
+    pub fn list(&self) -> Vec<String> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
+    /// `pair` is named by a disabled key
+    pub fn contains(&self, pair: &MarketPair) -> bool {
+        let keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        !keys.is_empty() && Self::names(pair).any(|name| keys.contains(&name.to_lowercase()))
+    }
+
+    /// `key` names `pair`
+    pub fn matches(key: &str, pair: &MarketPair) -> bool {
+        Self::names(pair).any(|name| name.eq_ignore_ascii_case(key.trim()))
+    }
+
+    /// The multi-outcome `market` is named by a disabled key
+    pub fn contains_full_set(&self, market: &MultiOutcomeMarket) -> bool {
+        let keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        !keys.is_empty() && keys.contains(&market.event_slug.to_lowercase())
+    }
+
+    /// `key` names the multi-outcome `market`
+    pub fn matches_full_set(key: &str, market: &MultiOutcomeMarket) -> bool {
+        market.event_slug.eq_ignore_ascii_case(key.trim())
+    }
+
+    fn names(pair: &MarketPair) -> impl Iterator<Item = &str> {
+        [&*pair.pair_id, &*pair.kalshi_market_ticker, &*pair.poly_slug].into_iter()
+    }
+}
+
+/// Send an opportunity post-only when it only clears the minimum profit at
+/// maker rates (rebates); true if it was flagged. Requests that already name
+/// an order type are left alone. The engine prices post-only legs inside the
+/// spread; they rest until ORDER_TTL_MS and a one-sided fill is balanced or
+/// unwound like any other.
+pub fn flag_maker_only(req: &mut FastExecutionRequest, fees: &FeeSchedule) -> bool {
+    if req.order_type.is_some()
+        || req.net_profit_cents_as(fees, Liquidity::Taker) >= fees.min_profit_cents
+        || req.net_profit_cents_as(fees, Liquidity::Maker) < fees.min_profit_cents
+    {
+        return false;
+    }
+    req.order_type = Some(OrderType::PostOnly);
+    true
+}
+
+/// The first leg of `req` with less than its platform's minimum at the top
+/// of the book, and the contracts it shows
+pub fn thin_leg(req: &FastExecutionRequest, depth: &BookDepthConfig) -> Option<(Platform, f64)> {
+    let (yes, no) = arb_legs(req.arb_type);
+    [(yes.platform, req.yes_size), (no.platform, req.no_size)].into_iter()
+        .map(|(platform, size)| (platform, size as f64 / 100.0))
+        .find(|&(platform, contracts)| depth.min_contracts(platform).is_some_and(|min| contracts < min))
+}
+
+/// Pass arb opportunities from the price feeds on to execution, except on
+/// disabled markets, ones with a leg on a platform that is down, stale ones
+/// (see `StaleTicks`), ones whose books are too thin to bother with, ones
+/// still cooling down from their last trade and ones whose Kalshi event
+/// already has a position open on another pair. Opportunities that need maker
+/// rates to pay are flagged post-only on the way. Every opportunity resets
+/// `idle`, held back or not.
+#[allow(clippy::too_many_arguments)]
+pub async fn screen_opportunities(
+    state: Arc<GlobalState>,
+    cooldowns: TradeCooldowns,
+    disabled: Arc<DisabledMarkets>,
+    health: Arc<PlatformHealth>,
+    stale: Arc<StaleTicks>,
+    depth: BookDepthConfig,
+    fees: FeeSchedule,
+    idle: Arc<IdleWatchdog>,
+    mut rx: mpsc::Receiver<FastExecutionRequest>,
+    tx: mpsc::Sender<FastExecutionRequest>,
+) {
+    // Rejections for depth per platform, logged for tuning the minimums
+    let mut thin = [0u64; 2];
+    while let Some(mut req) = rx.recv().await {
+        idle.detected(unix_ms());
+        if let Some(pair) = state.get_by_id(req.market_id).and_then(|m| m.pair.as_ref()) {
+            if disabled.contains(pair) {
+                debug!("[DISCOVERY] Disabled: {:?} on {} held back", req.arb_type, pair.description);
+                continue;
+            }
+            if let Some(platform) = health.blocking(req.arb_type) {
+                debug!("[DISCOVERY] {} down: {:?} on {} held back", platform, req.arb_type, pair.description);
+                continue;
+            }
+            if stale.is_stale(&state.markets[req.market_id as usize]) {
+                debug!("[DISCOVERY] Stale: {:?} on {} held back, no book update for {} cycles",
+                       req.arb_type, pair.description, stale.max_ticks().unwrap_or_default());
+                continue;
+            }
+            if let Some((platform, contracts)) = thin_leg(&req, &depth) {
+                thin[(platform == Platform::Polymarket) as usize] += 1;
+                let total = thin[0] + thin[1];
+                debug!("[DISCOVERY] Thin book: {:?} on {} held back, {} shows {:.0} contracts (min {:.0})",
+                       req.arb_type, pair.description, platform, contracts, depth.min_contracts(platform).unwrap_or_default());
+                if total % 100 == 1 {
+                    info!("[DISCOVERY] 📉 {} opportunities rejected for top-of-book depth so far (Kalshi {}, Polymarket {})",
+                          total, thin[0], thin[1]);
+                }
+                continue;
+            }
+            if let Some(left) = cooldowns.remaining(pair).await {
+                debug!("[DISCOVERY] Cooldown: {:?} on {} held back, {}ms left", req.arb_type, pair.description, left.as_millis());
+                continue;
+            }
+            if let Some(open) = cooldowns.event_conflict(&state, pair).await {
+                debug!("[DISCOVERY] Same event: {:?} on {} held back, position open on {}", req.arb_type, pair.description, open);
+                continue;
+            }
+            if flag_maker_only(&mut req, &fees) {
+                debug!("[DISCOVERY] Maker-only edge: {:?} on {} sent post-only ({:.1}¢ at maker rates)",
+                       req.arb_type, pair.description, req.net_profit_cents(&fees));
+            }
+        }
+        let _ = tx.try_send(req);
+    }
+}
+
+/// Spread capture: sends every top-of-book change of a paired market's
+/// Polymarket YES book that leaves at least `SPREAD_MIN_CENTS` between the
+/// best bid and ask, outside the market's post-trade cooldown, unless the
+/// market is disabled or stale, its Kalshi event has a position open on
+/// another pair or Polymarket is down
+#[allow(clippy::too_many_arguments)]
+pub async fn watch_spreads(
+    state: Arc<GlobalState>,
+    books: Arc<OrderBookStore>,
+    cooldowns: TradeCooldowns,
+    disabled: Arc<DisabledMarkets>,
+    health: Arc<PlatformHealth>,
+    stale: Arc<StaleTicks>,
+    idle: Arc<IdleWatchdog>,
+    tx: mpsc::Sender<SpreadRequest>,
+) {
+    let config = SpreadCaptureConfig::from_env();
+    let clock = NanoClock::new();
+    let mut quotes = books.subscribe();
+    loop {
+        let update = match quotes.recv().await {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let Some(&market_id) = state.poly_yes_to_id.get(&update.token_hash) else { continue };
+        let (Some((bid, _)), Some((ask, _))) = (update.best_bid, update.best_ask) else { continue };
+        let req = SpreadRequest {
+            market_id,
+            bid: bid_bps_to_cents(bid),
+            ask: ask_bps_to_cents(ask),
+            detected_ns: clock.now_ns(),
+        };
+        if req.bid == NO_PRICE || req.ask == NO_PRICE || req.spread_cents() < config.min_spread_cents
+            || health.is_down(Platform::Polymarket) {
+            continue;
+        }
+        idle.detected(unix_ms());
+        if let Some(pair) = state.get_by_id(market_id).and_then(|m| m.pair.as_ref()) {
+            if disabled.contains(pair) || stale.is_stale(&state.markets[market_id as usize]) {
+                continue;
+            }
+            if let Some(left) = cooldowns.remaining(pair).await {
+                debug!("[DISCOVERY] Cooldown: spread on {} held back, {}ms left", pair.description, left.as_millis());
+                continue;
+            }
+            if let Some(open) = cooldowns.event_conflict(&state, pair).await {
+                debug!("[DISCOVERY] Same event: spread on {} held back, position open on {}", pair.description, open);
+                continue;
+            }
+        }
+        let _ = tx.try_send(req);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::polymarket::GammaListing;
+    use crate::types::Outcome;
     
+    #[test]
+    fn test_thin_leg_against_platform_minimum() {
+        let depth = BookDepthConfig { kalshi_min_contracts: Some(20.0), poly_min_contracts: Some(50.0) };
+        let req = FastExecutionRequest {
+            market_id: 0,
+            yes_price: 40,
+            no_price: 50,
+            yes_size: 6000,
+            no_size: 2500,
+            arb_type: crate::types::ArbType::PolyYesKalshiNo,
+            detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
+        };
+        assert_eq!(thin_leg(&req, &depth), None);
+        assert_eq!(thin_leg(&FastExecutionRequest { no_size: 1500, ..req }, &depth), Some((Platform::Kalshi, 15.0)));
+        assert_eq!(thin_leg(&FastExecutionRequest { arb_type: crate::types::ArbType::PolyOnly, ..req }, &depth),
+                   Some((Platform::Polymarket, 25.0)));
+        assert_eq!(thin_leg(&FastExecutionRequest { no_size: 100, ..req }, &BookDepthConfig::default()), None);
+    }
+
+    #[test]
+    fn test_platform_down_after_sustained_errors() {
+        let health = PlatformHealth::new(PlatformHealthConfig { down_after_errors: Some(3) });
+        assert_eq!(health.record(Platform::Kalshi, false), None);
+        assert_eq!(health.record(Platform::Kalshi, true), None, "one success resets the count");
+        let changes: Vec<_> = (0..5).map(|_| health.record(Platform::Kalshi, false)).collect();
+        assert_eq!(changes, [None, None, Some(true), None, None], "one change per outage");
+        assert!(health.is_down(Platform::Kalshi));
+        assert_eq!(health.blocking(crate::types::ArbType::PolyYesKalshiNo), Some(Platform::Kalshi));
+        assert_eq!(health.blocking(crate::types::ArbType::PolyOnly), None, "Polymarket-only arbs keep trading");
+
+        assert_eq!(health.record(Platform::Kalshi, true), Some(false));
+        assert_eq!(health.blocking(crate::types::ArbType::KalshiOnly), None);
+
+        let off = PlatformHealth::new(PlatformHealthConfig { down_after_errors: None });
+        assert!((0..100).all(|_| off.record(Platform::Polymarket, false).is_none()));
+    }
+
+    #[test]
+    fn test_disabled_markets_match_any_name() {
+        let pair = MarketPair {
+            pair_id: "epl-che-avl".into(),
+            league: "epl".into(),
+            market_type: MarketType::Moneyline,
+            description: "Chelsea vs Aston Villa".into(),
+            kalshi_event_ticker: "KXEPLGAME-25DEC27CFCAVL".into(),
+            kalshi_series_ticker: "KXEPLGAME".into(),
+            kalshi_market_ticker: "KXEPLGAME-25DEC27CFCAVL-CFC".into(),
+            poly_slug: "epl-che-avl-2025-12-27-che".into(),
+            poly_yes_token: "yes".into(),
+            poly_no_token: "no".into(),
+            poly_constraints: Default::default(),
+            line_value: None,
+            team_suffix: None,
+        };
+        let disabled = DisabledMarkets::default();
+        assert!(!disabled.contains(&pair));
+
+        assert!(disabled.disable(" kxeplgame-25dec27cfcavl-cfc "));
+        assert!(!disabled.disable("KXEPLGAME-25DEC27CFCAVL-CFC"), "already disabled");
+        assert!(disabled.contains(&pair));
+        assert!(DisabledMarkets::matches("EPL-CHE-AVL", &pair));
+        assert!(!DisabledMarkets::matches("epl-che", &pair));
+
+        assert!(!disabled.enable("epl-che-avl"), "disabled under another name");
+        assert!(disabled.enable("KXEPLGAME-25DEC27CFCAVL-CFC"));
+        assert!(!disabled.contains(&pair));
+        assert!(disabled.list().is_empty());
+
+        let full_set = MultiOutcomeMarket {
+            event_slug: "epl-winner".into(),
+            description: "Premier League Winner".into(),
+            neg_risk: true,
+            outcomes: Vec::new(),
+        };
+        assert!(disabled.disable("EPL-Winner"));
+        assert!(disabled.contains_full_set(&full_set));
+        assert!(!disabled.contains(&pair));
+        assert!(DisabledMarkets::matches_full_set("epl-winner ", &full_set));
+    }
+
+    #[tokio::test]
+    async fn test_open_position_blocks_rest_of_kalshi_event() {
+        let home = MarketPair {
+            pair_id: "epl-che-avl-che".into(),
+            league: "epl".into(),
+            market_type: MarketType::Moneyline,
+            description: "Chelsea vs Aston Villa - Chelsea".into(),
+            kalshi_event_ticker: "KXEPLGAME-25DEC27CFCAVL".into(),
+            kalshi_series_ticker: "KXEPLGAME".into(),
+            kalshi_market_ticker: "KXEPLGAME-25DEC27CFCAVL-CFC".into(),
+            poly_slug: "epl-che-avl-2025-12-27-che".into(),
+            poly_yes_token: "yes-che".into(),
+            poly_no_token: "no-che".into(),
+            poly_constraints: Default::default(),
+            line_value: None,
+            team_suffix: None,
+        };
+        let away = MarketPair {
+            pair_id: "epl-che-avl-avl".into(),
+            description: "Chelsea vs Aston Villa - Aston Villa".into(),
+            kalshi_market_ticker: "KXEPLGAME-25DEC27CFCAVL-AVL".into(),
+            poly_yes_token: "yes-avl".into(),
+            poly_no_token: "no-avl".into(),
+            poly_constraints: Default::default(),
+            ..home.clone()
+        };
+        let other = MarketPair {
+            pair_id: "epl-ars-bri-ars".into(),
+            kalshi_event_ticker: "KXEPLGAME-25DEC27ARSBRI".into(),
+            kalshi_market_ticker: "KXEPLGAME-25DEC27ARSBRI-ARS".into(),
+            poly_yes_token: "yes-ars".into(),
+            poly_no_token: "no-ars".into(),
+            poly_constraints: Default::default(),
+            ..home.clone()
+        };
+        let mut state = GlobalState::new();
+        for pair in [&home, &away, &other] {
+            state.add_pair(pair.clone());
+        }
+        assert_eq!(state.event_siblings(&home).map(|p| p.pair_id.to_string()).collect::<Vec<_>>(), ["epl-che-avl-avl"]);
+
+        let positions: SharedPositionTracker = Default::default();
+        positions.write().await.record_fill(&crate::position_tracker::FillRecord::new(
+            &home.pair_id, &home.description, "kalshi", "yes", 10.0, 0.40, 0.0, "order1",
+        ));
+        let cooldowns = TradeCooldowns::new(positions, TradeCooldownConfig { cooldown_secs: None });
+        assert_eq!(cooldowns.event_conflict(&state, &away).await.as_deref(), Some("Chelsea vs Aston Villa - Chelsea"));
+        assert_eq!(cooldowns.event_conflict(&state, &home).await, None, "the open pair itself may keep trading");
+        assert_eq!(cooldowns.event_conflict(&state, &other).await, None);
+    }
+
+    #[test]
+    fn test_idle_watchdog_alerts_once_per_stretch() {
+        const MIN: u64 = 60_000;
+        let watchdog = IdleWatchdog::new(IdleConfig { alert_after_secs: Some(600) }, 0);
+        assert_eq!(watchdog.check(9 * MIN), None);
+        assert_eq!(watchdog.check(10 * MIN), Some(Duration::from_secs(600)));
+        assert_eq!(watchdog.check(30 * MIN), None, "already alerted");
+
+        // Any detection re-arms it and restarts the clock
+        watchdog.detected(31 * MIN);
+        assert_eq!(watchdog.check(40 * MIN), None);
+        assert_eq!(watchdog.check(41 * MIN), Some(Duration::from_secs(600)));
+
+        let off = IdleWatchdog::new(IdleConfig { alert_after_secs: None }, 0);
+        assert_eq!(off.check(1_000 * MIN), None);
+    }
+
     #[test]
     fn test_parse_kalshi_ticker() {
         let parsed = parse_kalshi_event_ticker("KXEPLGAME-25DEC27CFCAVL").unwrap();
@@ -665,9 +1925,327 @@ mod tests {
         assert_eq!(parsed.team2, "AVL");
     }
     
+    #[test]
+    fn test_title_similarity() {
+        assert_eq!(title_similarity("Will Biden win 2024?", "BIDEN-2024"), 1.0);
+        assert_eq!(title_similarity("Chelsea vs Aston Villa", "aston villa - chelsea!"), 1.0);
+        assert!(title_similarity("Chelsea FC", "Chelsea") > 0.4);
+        assert_eq!(title_similarity("Arsenal", "Chelsea"), 0.0);
+    }
+
+    #[test]
+    fn test_title_matcher_threshold_and_ties() {
+        let market = |slug: &str, question: &str| GammaEventMarket {
+            slug: slug.into(),
+            question: question.into(),
+            listing: GammaListing { yes_token: "y".into(), no_token: "n".into(), constraints: Default::default() },
+        };
+        let candidates = [
+            market("epl-che-avl-2025-12-27-che", "Will Chelsea win?"),
+            market("epl-che-avl-2025-12-27-avl", "Will Aston Villa win?"),
+            market("epl-che-avl-2025-12-27-draw", "Will it be a draw?"),
+        ];
+
+        let mut overrides = HashMap::new();
+        overrides.insert("KXEPLGAME-25DEC27CFCAVL-TIE".to_string(), "epl-che-avl-2025-12-27-draw".to_string());
+        let matcher = TitleMatcher::new(0.5, overrides);
+
+        let found = matcher.best_match("T", "Aston Villa", &candidates).unwrap();
+        assert_eq!(found.slug, "epl-che-avl-2025-12-27-avl");
+        assert!(matcher.best_match("T", "Liverpool", &candidates).is_none());
+
+        // Equal best scores are ambiguous
+        let dupes = [market("a", "Chelsea"), market("b", "Chelsea")];
+        assert!(matcher.best_match("T", "Chelsea", &dupes).is_none());
+
+        assert_eq!(matcher.override_for("KXEPLGAME-25DEC27CFCAVL-TIE"), Some("epl-che-avl-2025-12-27-draw"));
+    }
+
+    #[test]
+    fn test_poll_schedule_interleaves_watchlist() {
+        let now = Instant::now();
+        let base = Duration::from_secs(60);
+        let mut schedule = PollSchedule::new(base, Some(Duration::from_secs(10)), [(1, false), (2, true)], now);
+
+        // Everything is due at start, watchlisted first
+        assert_eq!(schedule.take_due(now), vec![2, 1]);
+        assert!(schedule.take_due(now).is_empty());
+        assert_eq!(schedule.next_wake(), Some(now + Duration::from_secs(10)));
+
+        // The watched market comes due five more times before the base one
+        let mut polls = (0, 0);
+        for s in 1..=6 {
+            for id in schedule.take_due(now + Duration::from_secs(s * 10)) {
+                if id == 2 { polls.1 += 1 } else { polls.0 += 1 }
+            }
+        }
+        assert_eq!(polls, (1, 6));
+        assert!((schedule.polls_per_min() - 7.0).abs() < 1e-9);
+
+        schedule.remove(2);
+        assert_eq!(schedule.watched(), 0);
+        assert!((schedule.polls_per_min() - 1.0).abs() < 1e-9);
+        schedule.remove(1);
+        assert_eq!(schedule.next_wake(), None);
+
+        // A "fast" interval slower than the base one is ignored
+        let slow = PollSchedule::new(base, Some(Duration::from_secs(120)), [(1, true)], now);
+        assert_eq!(slow.interval(1), base);
+    }
+
+    #[test]
+    fn test_poll_schedule_staggered_warmup() {
+        let now = Instant::now();
+        let warmup = Duration::from_secs(40);
+        let markets = (1..=4).map(|id| (id, id == 4));
+        let mut schedule = PollSchedule::new(Duration::from_secs(60), None, markets, now).staggered(warmup, now);
+        assert!(schedule.warming_up());
+
+        // One market per 10s slot, the watchlisted one first
+        assert!(schedule.next_wake().unwrap() < now + Duration::from_secs(10));
+        assert_eq!(schedule.take_due(now + Duration::from_secs(10) - Duration::from_millis(1)), vec![4]);
+        let mut polled = vec![4];
+        for s in [20, 30] {
+            let due = schedule.take_due(now + Duration::from_secs(s) - Duration::from_millis(1));
+            assert_eq!(due.len(), 1);
+            polled.extend(due);
+        }
+        assert!(schedule.warming_up());
+        polled.extend(schedule.take_due(now + warmup));
+        assert_eq!(polled, vec![4, 1, 2, 3]);
+        assert!(!schedule.warming_up());
+
+        // No warm-up keeps everything due at start
+        let mut instant = PollSchedule::new(Duration::from_secs(60), None, [(1, false), (2, false)], now)
+            .staggered(Duration::ZERO, now);
+        assert_eq!(instant.take_due(now), vec![1, 2]);
+        assert!(!instant.warming_up());
+    }
+
+    #[test]
+    fn test_staggered_batches_in_order() {
+        let now = Instant::now();
+        let warmup = Duration::from_secs(30);
+        let batches = PollSchedule::staggered_batches((0..250).collect(), 100, warmup, now);
+        assert_eq!(batches.iter().map(|(_, b)| b.len()).collect::<Vec<_>>(), vec![100, 100, 50]);
+        assert_eq!(batches[2].1[0], 200, "items keep their order");
+        // One batch per 10s slot, in order
+        for (i, (due, _)) in batches.iter().enumerate() {
+            assert!(*due >= now + Duration::from_secs(10) * i as u32 && *due < now + Duration::from_secs(10) * (i as u32 + 1));
+        }
+
+        let at_once = PollSchedule::staggered_batches(vec!["a", "b"], 100, Duration::ZERO, now);
+        assert_eq!(at_once, VecDeque::from([(now, vec!["a", "b"])]));
+    }
+
+    #[test]
+    fn test_kalshi_market_status() {
+        let market = |status: &str, result: &str| -> KalshiMarket {
+            serde_json::from_value(serde_json::json!({
+                "ticker": "T", "title": "T", "status": status, "result": result,
+            })).unwrap()
+        };
+        assert_eq!(market("active", "").market_status(), MarketStatus::Open);
+        assert_eq!(market("closed", "").market_status(), MarketStatus::Closed);
+        assert_eq!(market("settled", "yes").market_status(), MarketStatus::Resolved { yes_won: true });
+        assert_eq!(market("finalized", "no").market_status(), MarketStatus::Resolved { yes_won: false });
+        assert_eq!(market("determined", "").market_status(), MarketStatus::Closed);
+    }
+
     #[test]
     fn test_kalshi_date_to_iso() {
         assert_eq!(kalshi_date_to_iso("25DEC27"), "2025-12-27");
         assert_eq!(kalshi_date_to_iso("25JAN01"), "2025-01-01");
     }
+
+    #[test]
+    fn test_warmup_waits_for_every_book_or_the_timeout() {
+        let pair = |id: &str| MarketPair {
+            pair_id: id.into(),
+            league: "epl".into(),
+            market_type: MarketType::Moneyline,
+            description: id.into(),
+            kalshi_event_ticker: "KXEPLGAME-25DEC27CFCAVL".into(),
+            kalshi_series_ticker: "KXEPLGAME".into(),
+            kalshi_market_ticker: format!("KXEPLGAME-25DEC27CFCAVL-{}", id).into(),
+            poly_slug: id.into(),
+            poly_yes_token: format!("yes-{}", id).into(),
+            poly_no_token: format!("no-{}", id).into(),
+            poly_constraints: Default::default(),
+            line_value: None,
+            team_suffix: None,
+        };
+        let mut state = GlobalState::new();
+        state.add_pair(pair("che"));
+        state.add_pair(pair("avl"));
+        let books = OrderBookStore::new();
+        let warmup = Warmup::new(WarmupConfig { timeout_secs: Some(30) }, 0);
+        assert!(!warmup.is_done());
+
+        // One Polymarket token and no Kalshi book yet
+        books.apply_snapshot(fxhash_str("yes-che"), [], [(4500, 10.0)]);
+        assert_eq!(Warmup::progress(&state, &books, true), (0, 2));
+        books.apply_snapshot(fxhash_str("no-che"), [], [(5600, 10.0)]);
+        assert_eq!(Warmup::progress(&state, &books, false), (1, 2), "Kalshi not traded");
+        assert_eq!(Warmup::progress(&state, &books, true), (0, 2));
+
+        state.markets[0].kalshi.store(45, 56, 100, 100);
+        for token in ["yes-avl", "no-avl"] {
+            books.apply_snapshot(fxhash_str(token), [], [(5000, 10.0)]);
+        }
+        assert_eq!(warmup.check(&state, &books, true, 5_000), None, "avl has no Kalshi book");
+        state.markets[1].kalshi.store(50, 51, 100, 100);
+        let done = warmup.check(&state, &books, true, 6_000).unwrap();
+        assert_eq!(done, WarmupDone { ready: 2, total: 2, elapsed: Duration::from_secs(6), timed_out: false });
+        assert!(warmup.is_done());
+        assert_eq!(warmup.check(&state, &books, true, 7_000), None, "reported once");
+
+        // A market that never gets its books opens the gate at the timeout
+        let warmup = Warmup::new(WarmupConfig { timeout_secs: Some(30) }, 0);
+        state.add_pair(pair("ars"));
+        assert_eq!(warmup.check(&state, &books, true, 29_000), None);
+        let done = warmup.check(&state, &books, true, 30_000).unwrap();
+        assert_eq!((done.ready, done.total, done.timed_out), (2, 3, true));
+
+        assert!(Warmup::new(WarmupConfig { timeout_secs: None }, 0).is_done(), "no warmup");
+    }
+
+    #[test]
+    fn test_market_stale_after_ticks_without_update() {
+        let mut state = GlobalState::new();
+        for id in ["che", "avl"] {
+            state.add_pair(MarketPair {
+                pair_id: id.into(),
+                league: "epl".into(),
+                market_type: MarketType::Moneyline,
+                description: id.into(),
+                kalshi_event_ticker: "KXEPLGAME-25DEC27CFCAVL".into(),
+                kalshi_series_ticker: "KXEPLGAME".into(),
+                kalshi_market_ticker: format!("KXEPLGAME-25DEC27CFCAVL-{}", id).into(),
+                poly_slug: id.into(),
+                poly_yes_token: format!("yes-{}", id).into(),
+                poly_no_token: format!("no-{}", id).into(),
+                poly_constraints: Default::default(),
+                line_value: None,
+                team_suffix: None,
+            });
+        }
+        let stale = StaleTicks::new(StaleTicksConfig { max_ticks: Some(3) });
+        state.markets[0].poly.store(45, 56, 100, 100);
+        let poll = |platform| stale.polled(platform, &[0, 1], &state);
+        let change = |platform, stale, ticks| vec![StaleChange { market_id: 0, platform, stale, ticks }];
+
+        // "avl" never got a book, nor "che" on Kalshi: left to the warmup, never stale
+        assert!(poll(Platform::Polymarket).is_empty(), "first poll sees the update");
+        assert!(poll(Platform::Polymarket).is_empty());
+        assert!(poll(Platform::Polymarket).is_empty());
+        for _ in 0..5 {
+            assert!(poll(Platform::Kalshi).is_empty());
+        }
+        assert!(!stale.is_stale(&state.markets[0]));
+        assert_eq!(poll(Platform::Polymarket), change(Platform::Polymarket, true, 3));
+        assert!(stale.is_stale(&state.markets[0]));
+        assert!(!stale.is_stale(&state.markets[1]));
+        assert!(poll(Platform::Polymarket).is_empty(), "reported once");
+
+        // Only as fresh as its older book: a Kalshi update does not lift it
+        std::thread::sleep(Duration::from_millis(2));
+        state.markets[0].kalshi.store(44, 57, 100, 100);
+        assert!(stale.is_stale(&state.markets[0]));
+        assert!(poll(Platform::Kalshi).is_empty());
+
+        // A fresh Polymarket update lifts it at once, the next poll reports it
+        state.markets[0].poly.store(46, 55, 100, 100);
+        assert!(!stale.is_stale(&state.markets[0]));
+        assert_eq!(poll(Platform::Polymarket), change(Platform::Polymarket, false, 4));
+        assert!(poll(Platform::Polymarket).is_empty());
+
+        let off = StaleTicks::new(StaleTicksConfig { max_ticks: None });
+        for _ in 0..10 {
+            assert!(off.polled(Platform::Polymarket, &[0, 1], &state).is_empty());
+        }
+        assert!(!off.is_stale(&state.markets[0]));
+    }
+
+    #[test]
+    fn test_full_set_stale_after_cycles_without_quote() {
+        let outcome = |name: &str| Outcome { name: name.into(), token: format!("tok-{}", name).into(), constraints: Default::default() };
+        let market = MultiOutcomeMarket {
+            event_slug: "epl-winner".into(),
+            description: "Premier League Winner".into(),
+            neg_risk: true,
+            outcomes: vec![outcome("ars"), outcome("liv")],
+        };
+        let stale = StaleTicks::new(StaleTicksConfig { max_ticks: Some(2) });
+        stale.quoted(fxhash_str("tok-ars"));
+        stale.tick_full_sets();
+        stale.quoted(fxhash_str("tok-liv"));
+        assert!(!stale.is_full_set_stale(&market));
+        stale.tick_full_sets();
+        assert!(stale.is_full_set_stale(&market), "ars went two cycles without a quote");
+        stale.quoted(fxhash_str("tok-ars"));
+        assert!(!stale.is_full_set_stale(&market));
+    }
+
+    #[test]
+    fn test_full_set_arb_from_books() {
+        let mut state = GlobalState::new();
+        let outcome = |name: &str| Outcome { name: name.into(), token: format!("tok-{}", name).into(), constraints: Default::default() };
+        state.add_multi_outcome(MultiOutcomeMarket {
+            event_slug: "epl-winner".into(),
+            description: "Premier League Winner".into(),
+            neg_risk: true,
+            outcomes: vec![outcome("ars"), outcome("liv"), outcome("mci")],
+        });
+        let books = OrderBookStore::new();
+        let fees = FeeSchedule { fixed_cost_cents: 0.0, min_profit_cents: 1.0, ..FeeSchedule::default() };
+
+        books.apply_snapshot(fxhash_str("tok-ars"), [], [(3000, 10.0)]);
+        books.apply_snapshot(fxhash_str("tok-liv"), [], [(3500, 4.0)]);
+        // One outcome without an ask: no full set
+        assert!(full_set_arb(&state, &books, 0, &fees, 0).is_none());
+
+        books.apply_snapshot(fxhash_str("tok-mci"), [], [(2800, 20.0)]);
+        let req = full_set_arb(&state, &books, 0, &fees, 42).unwrap();
+        assert_eq!(req.asks, vec![(30, 1000), (35, 400), (28, 2000)]);
+        assert_eq!(req.contracts(), 4);
+        assert_eq!(req.detected_ns, 42);
+
+        // 30 + 35 + 35 = 100¢: no edge left
+        books.apply_snapshot(fxhash_str("tok-mci"), [], [(3500, 20.0)]);
+        assert!(full_set_arb(&state, &books, 0, &fees, 0).is_none());
+        assert!(full_set_arb(&state, &books, 1, &fees, 0).is_none());
+    }
+
+    #[test]
+    fn test_maker_only_edge_sent_post_only() {
+        use crate::config::FeeTiers;
+        use crate::types::ArbType;
+        // 2% taker fee, 0.5% maker rebate on Polymarket
+        let fees = FeeSchedule { poly: FeeTiers::parse("0:200:-50").unwrap(), ..FeeSchedule::default() };
+        let quote = FastExecutionRequest {
+            market_id: 0, yes_price: 48, no_price: 50, yes_size: 1000, no_size: 1000,
+            arb_type: ArbType::PolyOnly, detected_ns: 0, snapshot_ms: 0, order_type: None,
+        };
+
+        // 2¢ gross: 0.04¢ after taker fees, 2.49¢ with the rebates
+        let mut req = quote;
+        assert!(flag_maker_only(&mut req, &fees));
+        assert_eq!(req.order_type, Some(OrderType::PostOnly));
+        assert!((req.net_profit_cents(&fees) - 2.49).abs() < 1e-9);
+
+        // Profitable as a taker: left to the default order type
+        let mut req = FastExecutionRequest { yes_price: 40, ..quote };
+        assert!(!flag_maker_only(&mut req, &fees));
+        assert_eq!(req.order_type, None);
+
+        // Short of the minimum even with rebates
+        let mut req = FastExecutionRequest { yes_price: 49, no_price: 51, ..quote };
+        assert!(!flag_maker_only(&mut req, &fees));
+
+        // An explicit order type is kept
+        let mut req = FastExecutionRequest { order_type: Some(OrderType::Ioc), ..quote };
+        assert!(!flag_maker_only(&mut req, &fees));
+        assert_eq!(req.order_type, Some(OrderType::Ioc));
+    }
 }
@@ -0,0 +1,303 @@
+//! Error type for the exchange clients and the execution engine.
+//!
+//! Failures are classified where they happen (HTTP status, response body,
+//! transport error) so callers can branch on the kind: rate limits and
+//! network errors are worth retrying, auth failures stop trading, closed
+//! markets are skipped. The binary converts to `anyhow` at the top level.
+//!
+//! A critical operation (order submit or cancel, balance fetch) whose retries
+//! all failed is reported through `retries_exhausted`, which the binary turns
+//! into a Telegram alert, at most one per operation per cooldown.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::error;
+
+use crate::types::Platform;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Exchange answered 429 (after any retries of our own)
+    RateLimited(Platform),
+    /// Credentials missing or rejected, or request signing failed
+    Auth(String),
+    /// Market no longer accepts orders
+    MarketClosed(String),
+    /// Timeout or dropped connection; a submitted order may still have reached the exchange
+    Network(String),
+    /// Not enough resting liquidity for the requested size
+    InsufficientDepth(String),
+    /// Request or response body could not be (de)serialized
+    Serialization(String),
+    /// Any other non-2xx response
+    Api { platform: Platform, status: u16, message: String },
+    /// Bad input or missing setup on our side (unknown market, client not configured, invalid price)
+    Invalid(String),
+    /// An error with a note on what was being attempted
+    Context { context: String, source: Box<Error> },
+}
+
+impl Error {
+    /// Classify a non-2xx response
+    pub fn from_response(platform: Platform, status: reqwest::StatusCode, body: &str) -> Self {
+        let lower = body.to_ascii_lowercase();
+        match status.as_u16() {
+            429 => Error::RateLimited(platform),
+            401 | 403 => Error::Auth(format!("{} API {}: {}", platform, status, body)),
+            _ if ["market_closed", "market is closed", "market closed", "not accepting orders"]
+                .iter().any(|m| lower.contains(m)) => Error::MarketClosed(format!("{}: {}", platform, body)),
+            _ => Error::Api { platform, status: status.as_u16(), message: body.to_string() },
+        }
+    }
+
+    /// Error body of a failed response, classified
+    pub async fn from_failed(platform: Platform, resp: reqwest::Response) -> Self {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        Self::from_response(platform, status, &body)
+    }
+
+    pub fn auth(e: impl fmt::Display) -> Self {
+        Error::Auth(e.to_string())
+    }
+
+    pub fn invalid(e: impl fmt::Display) -> Self {
+        Error::Invalid(e.to_string())
+    }
+
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Error::Context { context: context.into(), source: Box::new(self) }
+    }
+
+    /// The underlying error, without any context wrappers
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// Transient failures where the same request may succeed later
+    pub fn is_retryable(&self) -> bool {
+        match self.root() {
+            Error::RateLimited(_) | Error::Network(_) => true,
+            Error::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// True when a failed order submit may still have reached the exchange:
+    /// the request timed out or the connection dropped before a response was
+    /// read. Responses from the exchange are definite rejections.
+    pub fn outcome_unknown(&self) -> bool {
+        matches!(self.root(), Error::Network(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::RateLimited(platform) => write!(f, "{} API rate limited", platform),
+            Error::Auth(msg) => write!(f, "authentication failed: {}", msg),
+            Error::MarketClosed(msg) => write!(f, "market closed: {}", msg),
+            Error::Network(msg) => write!(f, "network error: {}", msg),
+            Error::InsufficientDepth(msg) => write!(f, "insufficient depth: {}", msg),
+            Error::Serialization(msg) => write!(f, "serialization error: {}", msg),
+            Error::Api { platform, status, message } => write!(f, "{} API error {}: {}", platform, status, message),
+            Error::Invalid(msg) => write!(f, "{}", msg),
+            Error::Context { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_builder() {
+            Error::Invalid(e.to_string())
+        } else if e.is_decode() {
+            Error::Serialization(e.to_string())
+        } else {
+            Error::Network(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Serialization(e.to_string())
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        use tokio_tungstenite::tungstenite::Error as WsError;
+        match e {
+            WsError::Http(resp) if matches!(resp.status().as_u16(), 401 | 403) => {
+                Error::Auth(format!("WebSocket handshake rejected: {}", resp.status()))
+            }
+            e => Error::Network(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Invalid(e.to_string())
+    }
+}
+
+/// The last retry of a critical operation failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetriesExhausted {
+    /// What was being done ("order submit", "balance fetch"...)
+    pub operation: &'static str,
+    pub platform: Platform,
+    pub attempts: u32,
+    /// The last failure
+    pub error: String,
+}
+
+impl fmt::Display for RetriesExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} failed after {} attempt(s): {}", self.platform, self.operation, self.attempts, self.error)
+    }
+}
+
+/// Where exhausted retries go, spaced out per (operation, platform)
+pub struct ExhaustedAlerts {
+    cooldown: Duration,
+    last: Mutex<HashMap<(&'static str, Platform), Instant>>,
+    tx: mpsc::UnboundedSender<RetriesExhausted>,
+}
+
+impl ExhaustedAlerts {
+    pub fn new(cooldown: Duration) -> (Self, mpsc::UnboundedReceiver<RetriesExhausted>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { cooldown, last: Mutex::new(HashMap::new()), tx }, rx)
+    }
+
+    /// Forward `failure` unless the same operation was alerted within the
+    /// cooldown; returns whether it was sent
+    pub fn report(&self, failure: RetriesExhausted, now: Instant) -> bool {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (failure.operation, failure.platform);
+        if last.get(&key).is_some_and(|at| now.duration_since(*at) < self.cooldown) {
+            return false;
+        }
+        last.insert(key, now);
+        self.tx.send(failure).is_ok()
+    }
+}
+
+static EXHAUSTED: OnceLock<ExhaustedAlerts> = OnceLock::new();
+
+/// Receive the alerts of every exhausted retry from now on, spaced out by
+/// `cooldown`; None if already subscribed
+pub fn subscribe_exhausted(cooldown: Duration) -> Option<mpsc::UnboundedReceiver<RetriesExhausted>> {
+    let (alerts, rx) = ExhaustedAlerts::new(cooldown);
+    EXHAUSTED.set(alerts).ok().map(|()| rx)
+}
+
+/// Log that `operation` on `platform` failed on its last attempt, and alert
+/// the subscriber if that operation is not cooling down
+pub fn retries_exhausted(operation: &'static str, platform: Platform, attempts: u32, error: &Error) {
+    let failure = RetriesExhausted { operation, platform, attempts, error: error.to_string() };
+    error!("[RETRY] ❌ {}", failure);
+    if let Some(alerts) = EXHAUSTED.get() {
+        alerts.report(failure, Instant::now());
+    }
+}
+
+/// `anyhow`-style context on results
+pub trait Context<T> {
+    fn context(self, context: impl Into<String>) -> Result<T>;
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T> {
+        self.map_err(|e| e.into().context(f()))
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn context(self, context: impl Into<String>) -> Result<T> {
+        self.ok_or_else(|| Error::Invalid(context.into()))
+    }
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T> {
+        self.ok_or_else(|| Error::Invalid(f().into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_classify_responses() {
+        assert!(matches!(Error::from_response(Platform::Kalshi, StatusCode::TOO_MANY_REQUESTS, ""),
+                         Error::RateLimited(Platform::Kalshi)));
+        assert!(matches!(Error::from_response(Platform::Polymarket, StatusCode::UNAUTHORIZED, "bad key"),
+                         Error::Auth(_)));
+        assert!(matches!(Error::from_response(Platform::Kalshi, StatusCode::BAD_REQUEST,
+                                              r#"{"error":{"code":"market_closed"}}"#),
+                         Error::MarketClosed(_)));
+        let err = Error::from_response(Platform::Polymarket, StatusCode::BAD_REQUEST, "invalid price");
+        assert!(matches!(err, Error::Api { status: 400, .. }));
+        assert_eq!(err.to_string(), "POLYMARKET API error 400: invalid price");
+    }
+
+    #[test]
+    fn test_exhausted_alerts_once_per_operation_per_cooldown() {
+        let (alerts, mut rx) = ExhaustedAlerts::new(Duration::from_secs(600));
+        let failure = |operation, platform| RetriesExhausted { operation, platform, attempts: 2, error: "timeout".into() };
+        let t = Instant::now();
+        assert!(alerts.report(failure("order submit", Platform::Kalshi), t));
+        assert!(!alerts.report(failure("order submit", Platform::Kalshi), t + Duration::from_secs(60)), "cooling down");
+        assert!(alerts.report(failure("order submit", Platform::Polymarket), t + Duration::from_secs(60)));
+        assert!(alerts.report(failure("balance fetch", Platform::Kalshi), t + Duration::from_secs(60)));
+        assert!(alerts.report(failure("order submit", Platform::Kalshi), t + Duration::from_secs(600)));
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.to_string(), "KALSHI order submit failed after 2 attempt(s): timeout");
+        assert_eq!(std::iter::from_fn(|| rx.try_recv().ok()).count(), 3);
+    }
+
+    #[test]
+    fn test_context_keeps_kind() {
+        let err = Error::Network("timed out".into()).context("Kalshi order abc");
+        assert!(err.outcome_unknown());
+        assert!(err.is_retryable());
+        assert_eq!(err.to_string(), "Kalshi order abc: network error: timed out");
+
+        let err = Error::RateLimited(Platform::Kalshi).context("get");
+        assert!(err.is_retryable());
+        assert!(!err.outcome_unknown());
+        assert!(!Error::MarketClosed("x".into()).is_retryable());
+        assert!(Error::Api { platform: Platform::Polymarket, status: 502, message: String::new() }.is_retryable());
+        assert!(!Error::Api { platform: Platform::Polymarket, status: 400, message: String::new() }.is_retryable());
+
+        let missing: Result<u8> = None.context("No pair for market_id 3");
+        assert!(matches!(missing, Err(Error::Invalid(_))));
+    }
+}
@@ -0,0 +1,282 @@
+//! Structured decision log for post-hoc analysis.
+//!
+//! With `EVENT_LOG_FILE=<file>` every opportunity the `ExecutionEngine`
+//! evaluates is appended as one JSON line: the market (as `MarketPair` /
+//! `MarketType` from discovery), the inputs the decision was made on (prices,
+//! top-of-book depth, fees) and the outcome (traded, below threshold or
+//! skipped with the reason). Unlike the tracing output this is meant to be
+//! read back with `read_events`.
+//!
+//! The file is rotated once it reaches `EVENT_LOG_MAX_MB`: `decisions.jsonl`
+//! becomes `decisions.jsonl.1`, `.1` becomes `.2` and so on; the oldest file
+//! beyond `EVENT_LOG_MAX_FILES` is dropped, so disk use stays bounded.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::config::{EventLogConfig, FeeSchedule};
+use crate::execution::ExecutionResult;
+use crate::types::{ArbType, FastExecutionRequest, MarketPair, MarketType, PriceCents, SizeCents, unix_ms};
+
+/// Errors reported after the legs were sent: the trade happened, even if it
+/// did not (fully) fill
+const POST_TRADE_ERRORS: &[&str] = &["Partial/no fill", "Orders expired", "Cancel unconfirmed", "Execution failed"];
+
+/// One evaluated opportunity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionEvent {
+    /// Unix timestamp in milliseconds
+    pub ts_ms: u64,
+    pub market_id: u16,
+    pub pair_id: Arc<str>,
+    pub market_type: MarketType,
+    pub description: Arc<str>,
+    pub arb_type: ArbType,
+    pub inputs: DecisionInputs,
+    #[serde(flatten)]
+    pub decision: Decision,
+}
+
+/// What the decision was based on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionInputs {
+    pub yes_price: PriceCents,
+    pub no_price: PriceCents,
+    /// Top-of-book size in cents
+    pub yes_size: SizeCents,
+    pub no_size: SizeCents,
+    pub kalshi_fee_cents: PriceCents,
+    /// Per-contract profit after all fees, at the quoted prices
+    pub net_profit_cents: f64,
+    pub min_profit_cents: f64,
+}
+
+/// Outcome of an evaluation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum Decision {
+    /// Orders were sent (or simulated in dry run)
+    Traded {
+        dry_run: bool,
+        success: bool,
+        profit_cents: i16,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Not enough edge, at the quotes or after slippage
+    BelowThreshold { reason: String },
+    /// The edge was gone at the quotes re-read just before sending
+    Vanished,
+    /// Any other check stopped the trade (limits, depth, balance, approval...)
+    Skipped { reason: String },
+}
+
+impl Decision {
+    pub fn from_result(result: &ExecutionResult) -> Self {
+        let profit_cents = result.profit_cents;
+        match result.error {
+            None => Decision::Traded { dry_run: false, success: result.success, profit_cents, error: None },
+            Some("DRY_RUN") => Decision::Traded { dry_run: true, success: result.success, profit_cents, error: None },
+            Some(e) if POST_TRADE_ERRORS.contains(&e) => Decision::Traded {
+                dry_run: false, success: result.success, profit_cents, error: Some(e.to_string()),
+            },
+            Some(reason) if reason.starts_with("Profit below threshold") => {
+                Decision::BelowThreshold { reason: reason.to_string() }
+            }
+            Some("Opportunity vanished") => Decision::Vanished,
+            Some(reason) => Decision::Skipped { reason: reason.to_string() },
+        }
+    }
+}
+
+impl DecisionEvent {
+    pub fn new(pair: &MarketPair, req: &FastExecutionRequest, fees: &FeeSchedule, result: &ExecutionResult) -> Self {
+        Self {
+            ts_ms: unix_ms(),
+            market_id: req.market_id,
+            pair_id: pair.pair_id.clone(),
+            market_type: pair.market_type,
+            description: pair.description.clone(),
+            arb_type: req.arb_type,
+            inputs: DecisionInputs {
+                yes_price: req.yes_price,
+                no_price: req.no_price,
+                yes_size: req.yes_size,
+                no_size: req.no_size,
+                kalshi_fee_cents: req.estimated_fee_cents(),
+                net_profit_cents: req.net_profit_cents(fees),
+                min_profit_cents: fees.min_profit_cents,
+            },
+            decision: Decision::from_result(result),
+        }
+    }
+}
+
+/// Append-only NDJSON writer with size-based rotation
+pub struct EventLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    out: BufWriter<File>,
+    written: u64,
+}
+
+impl EventLog {
+    /// Open `path` for appending; an existing file counts towards `max_bytes`
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> Result<Self> {
+        let file = Self::open_file(path, false)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self { path: path.to_path_buf(), max_bytes, max_files, out: BufWriter::new(file), written })
+    }
+
+    fn open_file(path: &Path, truncate: bool) -> Result<File> {
+        OpenOptions::new().create(true).append(!truncate).write(true).truncate(truncate).open(path)
+            .with_context(|| format!("Failed to open event log {}", path.display()))
+    }
+
+    /// `path.n`, the n-th most recent rotated file
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    pub fn append(&mut self, event: &DecisionEvent) -> Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        if self.written > 0 && self.written + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.out.write_all(&line)?;
+        self.out.flush()?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.out.flush()?;
+        if self.max_files > 0 {
+            for n in (1..self.max_files).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.out = BufWriter::new(Self::open_file(&self.path, true)?);
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// Read back one log file, oldest event first
+#[allow(dead_code)]
+pub fn read_events(path: &Path) -> Result<Vec<DecisionEvent>> {
+    let file = File::open(path).with_context(|| format!("Failed to open event log {}", path.display()))?;
+    BufReader::new(file).lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+        .map(|(i, line)| {
+            serde_json::from_str(&line?).with_context(|| format!("{}:{}: invalid event", path.display(), i + 1))
+        })
+        .collect()
+}
+
+pub type EventSender = mpsc::Sender<DecisionEvent>;
+
+/// Start the writer task, or `None` when the log is disabled or can't be opened
+pub fn spawn(config: EventLogConfig) -> Option<EventSender> {
+    let path = config.path?;
+    let mut log = match EventLog::open(Path::new(&path), config.max_bytes, config.max_files) {
+        Ok(log) => log,
+        Err(e) => {
+            warn!("[EVENTS] Decision log disabled: {:#}", e);
+            return None;
+        }
+    };
+    info!("[EVENTS] Logging decisions to {} (rotated at {} bytes, {} old files kept)",
+          path, config.max_bytes, config.max_files);
+
+    let (tx, mut rx) = mpsc::channel::<DecisionEvent>(1024);
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            if let Err(e) = log.append(&event) {
+                warn!("[EVENTS] Decision log stopped: {:#}", e);
+                return;
+            }
+        }
+    });
+    Some(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(success: bool, error: Option<&'static str>) -> ExecutionResult {
+        ExecutionResult { market_id: 0, success, profit_cents: if success { 12 } else { 0 }, latency_ns: 0, error }
+    }
+
+    fn event(n: u16, decision: Decision) -> DecisionEvent {
+        DecisionEvent {
+            ts_ms: 1_700_000_000_000 + n as u64,
+            market_id: n,
+            pair_id: "epl-che-avl".into(),
+            market_type: MarketType::Moneyline,
+            description: "Chelsea vs Aston Villa".into(),
+            arb_type: ArbType::PolyYesKalshiNo,
+            inputs: DecisionInputs {
+                yes_price: 40, no_price: 55, yes_size: 1000, no_size: 800,
+                kalshi_fee_cents: 2, net_profit_cents: 2.5, min_profit_cents: 1.0,
+            },
+            decision,
+        }
+    }
+
+    #[test]
+    fn test_decision_from_result() {
+        assert_eq!(Decision::from_result(&result(true, None)),
+                   Decision::Traded { dry_run: false, success: true, profit_cents: 12, error: None });
+        assert!(matches!(Decision::from_result(&result(true, Some("DRY_RUN"))), Decision::Traded { dry_run: true, .. }));
+        assert_eq!(Decision::from_result(&result(false, Some("Orders expired"))),
+                   Decision::Traded { dry_run: false, success: false, profit_cents: 0, error: Some("Orders expired".into()) });
+        assert!(matches!(Decision::from_result(&result(false, Some("Profit below threshold after slippage"))),
+                         Decision::BelowThreshold { .. }));
+        assert_eq!(Decision::from_result(&result(false, Some("Position limit reached"))),
+                   Decision::Skipped { reason: "Position limit reached".into() });
+        assert_eq!(Decision::from_result(&result(false, Some("Opportunity vanished"))), Decision::Vanished);
+        assert_eq!(serde_json::to_value(Decision::Vanished).unwrap(), serde_json::json!({"decision": "vanished"}));
+    }
+
+    #[test]
+    fn test_event_log_round_trip_and_rotation() {
+        let path = std::env::temp_dir().join(format!("arb_decisions_{}.jsonl", std::process::id()));
+        let events: Vec<_> = (0..5).map(|n| event(n, Decision::Skipped { reason: "Circuit breaker".into() })).collect();
+        let line_len = serde_json::to_vec(&events[0]).unwrap().len() as u64 + 1;
+
+        // Two lines per file, one rotated file kept
+        let mut log = EventLog::open(&path, line_len * 2, 1).unwrap();
+        for e in &events {
+            log.append(e).unwrap();
+        }
+
+        let line = std::fs::read_to_string(&path).unwrap();
+        assert!(line.contains("\"decision\":\"skipped\""));
+        assert!(line.contains("\"arb_type\":\"poly_yes_kalshi_no\""));
+        assert!(line.contains("\"market_type\":\"Moneyline\""));
+        assert_eq!(read_events(&path).unwrap(), events[4..]);
+        assert_eq!(read_events(&log.rotated_path(1)).unwrap(), events[2..4]);
+        assert!(!log.rotated_path(2).exists());
+
+        for p in [path.clone(), log.rotated_path(1)] {
+            let _ = std::fs::remove_file(p);
+        }
+    }
+}
@@ -3,27 +3,41 @@
 //! This module handles concurrent order execution across both platforms,
 //! position reconciliation, and automatic exposure management.
 
-use anyhow::{Result, anyhow};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
-use tracing::{info, warn, error};
+use futures_util::future::BoxFuture;
+use tokio::sync::{mpsc, oneshot, watch, Semaphore};
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn, error};
 
-use crate::kalshi::KalshiApiClient;
-use crate::polymarket_clob::SharedAsyncClient;
+use crate::error::{Error, Result};
+use crate::kalshi::{self, KalshiApiClient};
+use crate::polymarket_clob::{BookSide, OrderBookStore, SharedAsyncClient};
 use crate::types::{
-    ArbType, MarketPair,
-    FastExecutionRequest, GlobalState,
-    cents_to_price,
+    ArbType, MarketPair, OrderConstraints, OrderOutcome, OrderType, Platform, Price, PriceCents,
+    FastExecutionRequest, GlobalState, MultiOutcomeRequest, OpportunityValue, SpreadRequest,
+    fxhash_str, kalshi_fee_cents, leg_fees_cents, unix_ms,
 };
-use crate::circuit_breaker::CircuitBreaker;
-use crate::position_tracker::{FillRecord, PositionChannel};
+use crate::circuit_breaker::{CircuitBreaker, TripReason};
+use crate::clock_skew::ClockSkewGuard;
+use crate::config::{ApprovalConfig, BalanceConfig, ClockSkewConfig, FeeSchedule, default_order_type, FlattenConfig, ImbalanceConfig, Liquidity, LotConfig, OpportunityDedupConfig, ParallelismConfig, PartialFillConfig, RankingConfig, RankingPolicy, RiskLimitsConfig, SizingPolicy, SpreadCaptureConfig, Strategy, TradeSizingConfig, TrailingStopConfig};
+use crate::discovery::{PlatformHealth, Warmup};
+use crate::event_log::{DecisionEvent, EventSender};
+use crate::position_tracker::{FillRecord, LimitHit, PositionChannel, PositionTracker, SharedPositionTracker, best_bids};
+use crate::shadow::{ShadowBook, ShadowDecision, ShadowRecord};
 
 // =============================================================================
 // EXECUTION ENGINE
 // =============================================================================
 
+/// Pause between re-checks of an order whose cancel the exchange hasn't confirmed
+const CANCEL_RECHECK_SECS: u64 = 5;
+/// Cancel checks of an order left resting before giving up on it
+const CANCEL_RECHECK_ATTEMPTS: u32 = 12;
+
 /// High-precision monotonic clock for latency measurement and performance tracking
 pub struct NanoClock {
     start: Instant,
@@ -46,50 +60,1714 @@ impl Default for NanoClock {
     }
 }
 
+/// Whether orders are actually sent to the exchanges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Place real orders
+    Live,
+    /// Paper trading: log and record the intended trade, skip the API calls
+    DryRun,
+}
+
+impl ExecutionMode {
+    /// Read from `DRY_RUN` (defaults to dry run for safety)
+    pub fn from_env() -> Self {
+        let dry_run = std::env::var("DRY_RUN")
+            .map(|v| v == "1" || v == "true")
+            .unwrap_or(true);
+        if dry_run { Self::DryRun } else { Self::Live }
+    }
+}
+
+/// Outcome of an executed (or simulated) trade, reported for stats and notifications
+#[derive(Debug, Clone)]
+pub struct TradeReport {
+    pub market: String,
+    pub arb_type: ArbType,
+    /// Contracts filled (matched across both legs)
+    pub contracts: i64,
+    /// Contracts intended before slippage-aware sizing
+    pub requested_contracts: i64,
+    /// Size sent on every leg once aligned to each platform's lot and
+    /// minimum (none for spread quotes, whose sides differ)
+    pub hedge_contracts: Option<i64>,
+    /// Position limit that trimmed the order, if any
+    pub limit_hit: Option<LimitHit>,
+    /// Realized profit, including the loss on any unwound excess
+    pub profit_cents: i16,
+    pub success: bool,
+    pub latency_ms: u64,
+    /// True for paper trades produced in `ExecutionMode::DryRun`
+    pub simulated: bool,
+    /// Excess contracts of a partial fill sold back to stay hedged
+    pub unwound_contracts: i64,
+    /// Legs cancelled because they were still resting at their time-to-live
+    pub cancelled_orders: u32,
+    /// What each leg holds after the trade (none for paper trades)
+    pub fills: Vec<TradeFill>,
+}
+
+/// Contracts one leg of a trade ended up with and the order that bought them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradeFill {
+    /// "kalshi" or "polymarket", as in the position tracker
+    pub platform: &'static str,
+    /// "yes"/"no", or the outcome of a full set
+    pub side: String,
+    pub contracts: i64,
+    pub cost_cents: i64,
+    pub order_id: String,
+}
+
+/// Newly detected (or materially changed) opportunity, after deduplication
+#[derive(Debug, Clone)]
+pub struct OpportunityReport {
+    pub market: String,
+    pub arb_type: ArbType,
+    pub yes_price: Price,
+    pub no_price: Price,
+    pub profit_cents: i16,
+    /// Depth-weighted estimate at the quoted sizes
+    pub value: OpportunityValue,
+}
+
+/// Trade skipped because it was not approved
+#[derive(Debug, Clone)]
+pub struct DeclinedReport {
+    pub market: String,
+    pub arb_type: ArbType,
+    pub contracts: i64,
+    /// Cost of the trade in dollars
+    pub notional: f64,
+    pub decision: ApprovalDecision,
+}
+
+/// Profitable trade skipped because one platform's account can't pay for its legs
+#[derive(Debug, Clone)]
+pub struct UnderfundedReport {
+    pub market: String,
+    pub platform: Platform,
+    pub required_cents: i64,
+    pub available_cents: i64,
+    pub expected_profit_cents: i64,
+}
+
+/// New pair whose probation just ended
+#[derive(Debug, Clone)]
+pub struct ProbationReport {
+    pub market: String,
+    /// True if it now trades, false if rejected as a likely mismatch
+    pub graduated: bool,
+    pub record: ShadowRecord,
+}
+
+/// An order whose cancel the exchange never confirmed: its remainder may
+/// still fill without the engine knowing
+#[derive(Debug, Clone)]
+pub struct CancelUnconfirmedReport {
+    pub market: String,
+    pub platform: Platform,
+    pub order_id: String,
+    /// Cancel checks made before giving up
+    pub checks: u32,
+}
+
+/// Directional inventory sold by its trailing stop
+#[derive(Debug, Clone)]
+pub struct TrailingStopReport {
+    pub market: String,
+    /// "kalshi" or "polymarket"
+    pub platform: &'static str,
+    /// "yes" or "no"
+    pub side: &'static str,
+    /// High-water mark and bid that fired the stop, in dollars
+    pub high_water: f64,
+    pub mark: f64,
+    pub contracts: f64,
+    pub closed: f64,
+    /// Realized P&L of the sale, in dollars
+    pub realized_pnl: f64,
+    pub error: Option<String>,
+    pub simulated: bool,
+}
+
+/// Events published by the execution engine for stats and notifications
+#[derive(Debug, Clone)]
+pub enum ExecutionReport {
+    Opportunity(OpportunityReport),
+    Trade(TradeReport),
+    Declined(DeclinedReport),
+    Underfunded(UnderfundedReport),
+    Probation(ProbationReport),
+    TrailingStop(TrailingStopReport),
+    CancelUnconfirmed(CancelUnconfirmedReport),
+}
+
+pub type ExecutionReportSender = mpsc::UnboundedSender<ExecutionReport>;
+
+pub fn create_execution_report_channel() -> (ExecutionReportSender, mpsc::UnboundedReceiver<ExecutionReport>) {
+    mpsc::unbounded_channel()
+}
+
+/// Answer to an `ApprovalRequest`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    Approved,
+    Rejected,
+    /// No answer before the request's timeout
+    TimedOut,
+    /// The request could not be delivered to anyone
+    Unavailable,
+}
+
+/// Trade above `ApprovalConfig::min_notional` waiting for a human decision
+/// before its orders are sent. The approver answers through `reply` within
+/// `timeout`; anything but `Approved` skips the trade.
+#[derive(Debug)]
+pub struct ApprovalRequest {
+    pub id: u64,
+    pub market: String,
+    pub arb_type: ArbType,
+    pub contracts: i64,
+    /// Cost of the trade in dollars
+    pub notional: f64,
+    pub expected_profit_cents: i64,
+    pub timeout: Duration,
+    pub reply: oneshot::Sender<ApprovalDecision>,
+}
+
+pub type ApprovalSender = mpsc::Sender<ApprovalRequest>;
+
+/// Suppresses repeat detections of the same opportunity across ticks.
+///
+/// Keyed by market + direction. An opportunity is reported when first seen,
+/// when it reappears after lapsing for the TTL, or when its profit moves by at
+/// least the configured threshold since it was last reported. The key space is
+/// bounded by markets × arb types, so entries are overwritten rather than swept.
+pub struct OpportunityDeduper {
+    ttl: Duration,
+    min_profit_change_cents: i16,
+    /// (market_id, arb_type) → (last reported profit, last seen)
+    seen: FxHashMap<(u16, ArbType), (i16, Instant)>,
+}
+
+impl OpportunityDeduper {
+    pub fn new(config: OpportunityDedupConfig) -> Self {
+        Self {
+            ttl: Duration::from_secs(config.ttl_secs),
+            min_profit_change_cents: config.min_profit_change_cents.max(1),
+            seen: FxHashMap::default(),
+        }
+    }
+
+    /// Record a detection; returns true if it should be reported
+    pub fn observe(&mut self, market_id: u16, arb_type: ArbType, profit_cents: i16, now: Instant) -> bool {
+        match self.seen.get_mut(&(market_id, arb_type)) {
+            Some((reported_profit, last_seen))
+                if now.duration_since(*last_seen) < self.ttl
+                    && (profit_cents - *reported_profit).abs() < self.min_profit_change_cents =>
+            {
+                *last_seen = now;
+                false
+            }
+            _ => {
+                self.seen.insert((market_id, arb_type), (profit_cents, now));
+                true
+            }
+        }
+    }
+}
+
+/// Expected fill prices for both legs of an arb
+#[derive(Debug, Clone, Copy)]
+struct LegPricing {
+    /// Average YES fill price in cents
+    yes_avg: f64,
+    /// Average NO fill price in cents
+    no_avg: f64,
+    /// YES limit price reaching the required depth
+    yes_limit: PriceCents,
+    /// NO limit price reaching the required depth
+    no_limit: PriceCents,
+}
+
+impl LegPricing {
+    /// Expected profit per contract in cents after both legs' fees at the
+    /// fill prices and fixed costs
+    fn profit_per_contract(&self, arb_type: ArbType, contracts: i64, liquidity: Liquidity, fees: &FeeSchedule) -> f64 {
+        100.0 - self.yes_avg - self.no_avg
+            - leg_fees_cents(arb_type, self.yes_avg, self.no_avg, contracts, liquidity, fees)
+            - fees.fixed_cost_cents
+    }
+}
+
+/// IDs of one submitted leg: the exchange's order ID and the client-side
+/// idempotency key it was submitted with (kept on the position for reconciliation)
+#[derive(Debug, Clone, Default)]
+struct OrderRef {
+    order_id: String,
+    client_order_id: String,
+    outcome: OrderOutcome,
+}
+
+/// One order on an arb leg, as handed to the `OrderGateway`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegOrder<'a> {
+    pub platform: Platform,
+    /// Kalshi market ticker or Polymarket token
+    pub market: &'a str,
+    /// "yes" or "no" (a Polymarket token already is one side)
+    pub side: &'static str,
+    /// False for a sale, always IOC/FAK
+    pub buy: bool,
+    pub price: Price,
+    pub contracts: i64,
+    pub order_type: OrderType,
+}
+
+/// What one order did once its fill was final
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderFill {
+    pub filled: i64,
+    /// Cents paid for a buy, received for a sale
+    pub cost_cents: i64,
+    /// Exchange fees on the fill (cents)
+    pub fee_cents: i64,
+    pub order_id: String,
+    pub client_order_id: String,
+    pub outcome: OrderOutcome,
+}
+
+impl From<crate::kalshi::KalshiOrderResponse> for OrderFill {
+    fn from(resp: crate::kalshi::KalshiOrderResponse) -> Self {
+        Self { outcome: resp.outcome, ..resp.order.into() }
+    }
+}
+
+impl From<crate::kalshi::KalshiOrderDetails> for OrderFill {
+    fn from(order: crate::kalshi::KalshiOrderDetails) -> Self {
+        Self {
+            filled: order.filled_count(),
+            cost_cents: order.taker_fill_cost.unwrap_or(0) + order.maker_fill_cost.unwrap_or(0),
+            fee_cents: order.fees(),
+            client_order_id: order.client_order_id.clone().unwrap_or_default(),
+            order_id: order.order_id,
+            outcome: OrderOutcome::Completed,
+        }
+    }
+}
+
+impl From<crate::polymarket_clob::PolyFillAsync> for OrderFill {
+    /// Polymarket order IDs are the order hash, which is also the key
+    fn from(fill: crate::polymarket_clob::PolyFillAsync) -> Self {
+        Self {
+            filled: fill.filled_size as i64,
+            cost_cents: (fill.fill_cost * 100.0).round() as i64,
+            fee_cents: (fill.fees * 100.0).round() as i64,
+            client_order_id: fill.order_id.clone(),
+            order_id: fill.order_id,
+            outcome: fill.outcome,
+        }
+    }
+}
+
+impl From<OrderFill> for LegFill {
+    fn from(fill: OrderFill) -> Self {
+        Self {
+            filled: fill.filled,
+            cost: fill.cost_cents,
+            fees: fill.fee_cents,
+            order: OrderRef {
+                order_id: fill.order_id,
+                client_order_id: fill.client_order_id,
+                outcome: fill.outcome,
+            },
+        }
+    }
+}
+
+/// Where the engine sends the orders of its arb legs, including follow-up
+/// and unwind orders. `ExchangeOrders` is the default; tests pass their own
+/// to `ExecutionEngine::with_orders` to script delays, partial fills and errors.
+pub trait OrderGateway: Send + Sync {
+    /// Place `order` and wait until its fill is final
+    fn submit<'a>(&'a self, order: LegOrder<'a>) -> BoxFuture<'a, Result<OrderFill>>;
+
+    /// What an order sent on `platform` actually filled, fees included, as
+    /// the exchange reports it
+    fn fill_details<'a>(&'a self, platform: Platform, order_id: &'a str) -> BoxFuture<'a, Result<OrderFill>>;
+
+    /// Cancel an order whose cancel went unconfirmed, if it still rests, and
+    /// report what it filled; `outcome` stays `CancelUnconfirmed` while the
+    /// exchange still doesn't confirm it
+    fn confirm_cancel<'a>(&'a self, platform: Platform, order_id: &'a str) -> BoxFuture<'a, Result<OrderFill>>;
+
+    /// Whether orders can be sent on `platform` at all
+    fn configured(&self, platform: Platform) -> bool;
+}
+
+/// Orders sent through the exchange clients
+pub struct ExchangeOrders {
+    kalshi: Option<Arc<KalshiApiClient>>,
+    poly: Option<Arc<SharedAsyncClient>>,
+}
+
+impl ExchangeOrders {
+    pub fn new(kalshi: Option<Arc<KalshiApiClient>>, poly: Option<Arc<SharedAsyncClient>>) -> Self {
+        Self { kalshi, poly }
+    }
+}
+
+impl OrderGateway for ExchangeOrders {
+    fn submit<'a>(&'a self, order: LegOrder<'a>) -> BoxFuture<'a, Result<OrderFill>> {
+        Box::pin(async move {
+            match order.platform {
+                Platform::Kalshi => {
+                    let kalshi = self.kalshi.as_ref().ok_or_else(|| Error::Invalid("Kalshi not configured".into()))?;
+                    let resp = if order.buy {
+                        kalshi.buy(order.market, order.side, order.price, order.contracts, order.order_type).await?
+                    } else {
+                        kalshi.sell_ioc(order.market, order.side, order.price, order.contracts).await?
+                    };
+                    Ok(resp.into())
+                }
+                Platform::Polymarket => {
+                    let poly = self.poly.as_ref().ok_or_else(|| Error::Invalid("Polymarket not configured".into()))?;
+                    let fill = if order.buy {
+                        poly.buy(order.market, order.price, order.contracts as f64, order.order_type).await?
+                    } else {
+                        poly.sell_fak(order.market, order.price, order.contracts as f64).await?
+                    };
+                    Ok(fill.into())
+                }
+            }
+        })
+    }
+
+    fn fill_details<'a>(&'a self, platform: Platform, order_id: &'a str) -> BoxFuture<'a, Result<OrderFill>> {
+        Box::pin(async move {
+            match platform {
+                Platform::Kalshi => {
+                    let kalshi = self.kalshi.as_ref().ok_or_else(|| Error::Invalid("Kalshi not configured".into()))?;
+                    Ok(kalshi.get_order(order_id).await?.into())
+                }
+                Platform::Polymarket => {
+                    let poly = self.poly.as_ref().ok_or_else(|| Error::Invalid("Polymarket not configured".into()))?;
+                    Ok(poly.fill_details(order_id).await?.into())
+                }
+            }
+        })
+    }
+
+    fn confirm_cancel<'a>(&'a self, platform: Platform, order_id: &'a str) -> BoxFuture<'a, Result<OrderFill>> {
+        Box::pin(async move {
+            match platform {
+                Platform::Kalshi => {
+                    let kalshi = self.kalshi.as_ref().ok_or_else(|| Error::Invalid("Kalshi not configured".into()))?;
+                    let (order, outcome) = kalshi.confirm_cancelled(order_id).await?;
+                    Ok(OrderFill { outcome, ..order.into() })
+                }
+                Platform::Polymarket => {
+                    let poly = self.poly.as_ref().ok_or_else(|| Error::Invalid("Polymarket not configured".into()))?;
+                    let (_, outcome) = poly.confirm_cancelled(order_id).await?;
+                    // Matched prices and fees come from the order's trades
+                    Ok(OrderFill { outcome, ..poly.fill_details(order_id).await?.into() })
+                }
+            }
+        })
+    }
+
+    fn configured(&self, platform: Platform) -> bool {
+        match platform {
+            Platform::Kalshi => self.kalshi.is_some(),
+            Platform::Polymarket => self.poly.is_some(),
+        }
+    }
+}
+
+/// One leg of an arb: the platform it trades on and the side it buys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArbLeg {
+    pub platform: Platform,
+    pub side: &'static str,
+}
+
+impl ArbLeg {
+    /// Platform name used by the position tracker
+    pub fn platform_name(&self) -> &'static str {
+        match self.platform {
+            Platform::Kalshi => "kalshi",
+            Platform::Polymarket => "polymarket",
+        }
+    }
+}
+
+impl std::fmt::Display for ArbLeg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = if self.platform == Platform::Kalshi { "K" } else { "P" };
+        write!(f, "{}_{}", prefix, self.side)
+    }
+}
+
+/// (YES leg, NO leg) of an arb type
+pub fn arb_legs(arb_type: ArbType) -> (ArbLeg, ArbLeg) {
+    let leg = |platform, side| ArbLeg { platform, side };
+    match arb_type {
+        ArbType::PolyYesKalshiNo => (leg(Platform::Polymarket, "yes"), leg(Platform::Kalshi, "no")),
+        ArbType::KalshiYesPolyNo => (leg(Platform::Kalshi, "yes"), leg(Platform::Polymarket, "no")),
+        ArbType::PolyOnly => (leg(Platform::Polymarket, "yes"), leg(Platform::Polymarket, "no")),
+        ArbType::KalshiOnly => (leg(Platform::Kalshi, "yes"), leg(Platform::Kalshi, "no")),
+    }
+}
+
+/// Largest size up to `max` valid on every leg, given each leg's (lot
+/// increment, minimum): a multiple of all the lots and at least every
+/// minimum. `None` if no such size reaches a contract.
+pub fn hedge_size(max: i64, legs: &[(i64, i64)]) -> Option<i64> {
+    fn gcd(a: i64, b: i64) -> i64 {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+    let lot = legs.iter().try_fold(1i64, |lcm, &(lot, _)| {
+        let lot = lot.max(1);
+        (lcm / gcd(lcm, lot)).checked_mul(lot)
+    })?;
+    let minimum = legs.iter().map(|&(_, min)| min).max().unwrap_or(0).max(1);
+    let size = max / lot * lot;
+    (size >= minimum).then_some(size)
+}
+
+/// Whether every Polymarket book an arb buys from leans at least
+/// `min_imbalance` towards bids, so the entry isn't made just as sellers take
+/// over. Books with no known imbalance pass; Kalshi legs are not checked.
+pub fn imbalance_allows(min_imbalance: Option<f64>, arb_type: ArbType, imbalance: (Option<f64>, Option<f64>)) -> bool {
+    let Some(min) = min_imbalance else { return true };
+    let (yes_leg, no_leg) = arb_legs(arb_type);
+    [(yes_leg, imbalance.0), (no_leg, imbalance.1)].into_iter()
+        .filter(|(leg, _)| leg.platform == Platform::Polymarket)
+        .all(|(_, value)| value.is_none_or(|v| v >= min))
+}
+
+/// Contracts held on one leg after execution and what they cost (cents)
+#[derive(Debug, Clone, Default)]
+struct LegFill {
+    filled: i64,
+    cost: i64,
+    /// Exchange fees (cents) as reported with the order, corrected once the
+    /// fill is reconciled
+    fees: i64,
+    order: OrderRef,
+}
+
+impl LegFill {
+    /// Average cost per contract in cents (0 when nothing filled)
+    fn avg_cost(&self) -> f64 {
+        if self.filled > 0 { self.cost as f64 / self.filled as f64 } else { 0.0 }
+    }
+}
+
+/// Unhedged excess sold back after a partial fill
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Unwind {
+    pub contracts: i64,
+    /// What the sold contracts cost on entry (cents)
+    pub cost: i64,
+    /// Sale proceeds (cents)
+    pub proceeds: i64,
+}
+
+impl Unwind {
+    /// Realized P&L of the unwind, usually a loss
+    pub fn pnl_cents(&self) -> i64 {
+        self.proceeds - self.cost
+    }
+}
+
+/// Locked-in profit of the hedged pairs: $1 per matched pair minus what the
+/// matched contracts cost on each leg. Unhedged contracts are not counted.
+pub fn hedged_profit_cents(yes_filled: i64, yes_cost: i64, no_filled: i64, no_cost: i64) -> i64 {
+    let matched = yes_filled.min(no_filled);
+    if matched <= 0 {
+        return 0;
+    }
+    matched * 100 - yes_cost * matched / yes_filled - no_cost * matched / no_filled
+}
+
+/// Remove a market from an in-flight set, after a pause if `delayed`
+fn release_market(in_flight: Arc<std::sync::Mutex<FxHashSet<u16>>>, index: u16, delayed: bool) {
+    if !delayed {
+        in_flight.lock().unwrap_or_else(|e| e.into_inner()).remove(&index);
+        return;
+    }
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        in_flight.lock().unwrap_or_else(|e| e.into_inner()).remove(&index);
+    });
+}
+
+/// Order price for a leg, rejecting quotes outside 0–100¢
+fn leg_price(cents: PriceCents) -> Result<Price> {
+    Price::from_cents(cents).ok_or_else(|| Error::Invalid(format!("invalid price {}¢", cents)))
+}
+
+/// Post-only buy price: one tick above the best bid while that stays under
+/// `ask`, else joining the bid, so the order rests instead of crossing and
+/// being rejected. One tick under the ask when the bid is unknown.
+fn maker_price(bid: Option<PriceCents>, ask: PriceCents, tick: PriceCents) -> PriceCents {
+    let below_ask = ask.saturating_sub(tick.max(1));
+    bid.map_or(below_ask, |bid| (bid + tick.max(1)).min(below_ask)).max(1)
+}
+
+/// Fit a Polymarket order to its market's constraints: the price moved onto
+/// the tick (buys down, sells up), or why it can't be sent
+fn fit_poly_order(constraints: OrderConstraints, buy: bool, price: PriceCents, contracts: i64) -> std::result::Result<PriceCents, &'static str> {
+    if !constraints.allows_size(contracts as f64) {
+        return Err("Below minimum order size");
+    }
+    let fitted = if buy { constraints.floor_price(price) } else { constraints.ceil_price(price) };
+    fitted.ok_or("No valid price on tick")
+}
+
+/// Highest price the short leg of a partial fill may be completed at: its
+/// original limit plus `max_chase_cents`, capped so the pair (with the Kalshi
+/// fee when the short leg is on Kalshi) still costs at most 99¢.
+/// `None` if no such price exists.
+pub fn completion_limit(short_price: PriceCents, long_avg_cents: f64, max_chase_cents: u16, platform: Platform) -> Option<PriceCents> {
+    let ceiling = 99 - long_avg_cents.ceil() as i64;
+    let mut limit = (short_price as i64 + max_chase_cents as i64).min(ceiling).min(99);
+    if platform == Platform::Kalshi {
+        while limit >= 1 && limit + kalshi_fee_cents(limit as PriceCents) as i64 > ceiling {
+            limit -= 1;
+        }
+    }
+    (limit >= 1).then_some(limit as PriceCents)
+}
+
+/// Cost of buying one leg at its limit price, Kalshi taker fee included
+fn leg_cost_cents(leg: ArbLeg, contracts: i64, price: PriceCents) -> i64 {
+    let fee = if leg.platform == Platform::Kalshi { kalshi_fee_cents(price) } else { 0 };
+    contracts * (price + fee) as i64
+}
+
+/// Cash each platform needs to buy both legs at their limit prices (Kalshi
+/// legs include the taker fee), one entry per platform involved
+pub fn required_funds(arb_type: ArbType, contracts: i64, yes_price: PriceCents, no_price: PriceCents) -> Vec<(Platform, i64)> {
+    let (yes_leg, no_leg) = arb_legs(arb_type);
+    let mut funds: Vec<(Platform, i64)> = Vec::with_capacity(2);
+    for (leg, price) in [(yes_leg, yes_price), (no_leg, no_price)] {
+        let cost = leg_cost_cents(leg, contracts, price);
+        match funds.iter_mut().find(|(platform, _)| *platform == leg.platform) {
+            Some((_, total)) => *total += cost,
+            None => funds.push((leg.platform, cost)),
+        }
+    }
+    funds
+}
+
+/// Worst-case loss of a trade: one leg fills, the other doesn't, and the
+/// filled leg ends up worthless. That is the cost of the dearer leg.
+pub fn worst_case_loss_cents(arb_type: ArbType, contracts: i64, yes_price: PriceCents, no_price: PriceCents) -> i64 {
+    let (yes_leg, no_leg) = arb_legs(arb_type);
+    leg_cost_cents(yes_leg, contracts, yes_price).max(leg_cost_cents(no_leg, contracts, no_price))
+}
+
+/// Start of the trading day after the one `now` falls in, when days start
+/// at `reset_hour` UTC
+pub fn next_day_start(now: chrono::DateTime<chrono::Utc>, reset_hour: u32) -> chrono::DateTime<chrono::Utc> {
+    let start = crate::telegram::trading_day_start(now, reset_hour) + 86_400;
+    chrono::DateTime::from_timestamp(start, 0).expect("date within chrono's range")
+}
+
+/// Hard loss limits checked before every trade. Days are trading days
+/// starting at the configured UTC hour: once a day's realized loss passes
+/// the daily limit, new trades stay halted until the next one starts,
+/// whatever the local timezone of the host.
+pub struct RiskGuard {
+    limits: RiskLimitsConfig,
+    /// Unix ms trading resumes at (0 = not halted)
+    halted_until_ms: AtomicU64,
+}
+
+impl RiskGuard {
+    pub fn new(limits: RiskLimitsConfig) -> Self {
+        Self { limits, halted_until_ms: AtomicU64::new(0) }
+    }
+
+    pub fn limits(&self) -> RiskLimitsConfig {
+        self.limits
+    }
+
+    /// When trading resumes, if the daily limit has halted it at `now`
+    pub fn halted_until(&self, now: chrono::DateTime<chrono::Utc>) -> Option<chrono::DateTime<chrono::Utc>> {
+        let until = self.halted_until_ms.load(Ordering::Acquire);
+        if until == 0 {
+            return None;
+        }
+        if now.timestamp_millis() >= until as i64 {
+            if self.halted_until_ms.compare_exchange(until, 0, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+                info!("[RISK] New trading day, daily loss halt lifted");
+            }
+            return None;
+        }
+        chrono::DateTime::from_timestamp_millis(until as i64)
+    }
+
+    /// Apply the daily limit to the P&L of the trading day `now` falls on.
+    /// Returns the resume time when this call halts trading; `None` when
+    /// within the limit or already halted.
+    pub fn check_daily_loss(&self, daily_pnl_cents: i64, now: chrono::DateTime<chrono::Utc>) -> Option<chrono::DateTime<chrono::Utc>> {
+        let limit = self.limits.max_daily_loss_cents?;
+        if -daily_pnl_cents <= limit || self.halted_until(now).is_some() {
+            return None;
+        }
+        let resume = next_day_start(now, self.limits.daily_reset_hour);
+        self.halted_until_ms.store(resume.timestamp_millis() as u64, Ordering::Release);
+        error!("[RISK] 🛑 Daily loss {}¢ over the {}¢ limit, trading halted until {}",
+               -daily_pnl_cents, limit, resume.format("%Y-%m-%d %H:%M UTC"));
+        Some(resume)
+    }
+
+    /// Whether a trade with this worst-case loss is within the per-trade limit
+    pub fn trade_allowed(&self, worst_case_loss_cents: i64) -> bool {
+        self.limits.max_trade_loss_cents.is_none_or(|limit| worst_case_loss_cents <= limit)
+    }
+}
+
+/// Account balances (cents) per platform, reused for a short TTL so the
+/// check before each trade doesn't cost a round trip
+pub struct BalanceCache {
+    ttl: Duration,
+    entries: std::sync::Mutex<FxHashMap<Platform, (i64, Instant)>>,
+    /// Funds held by trades whose orders are not out yet, so trades running
+    /// in parallel can't all count on the same balance
+    reserved: std::sync::Mutex<FxHashMap<Platform, i64>>,
+}
+
+impl BalanceCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: std::sync::Mutex::default(), reserved: std::sync::Mutex::default() }
+    }
+
+    /// Hold `funds` if each platform's balance (None = unknown, not checked)
+    /// covers them on top of what other trades hold, or the first platform
+    /// short: (platform, required, available)
+    pub fn reserve(&self, funds: &[(Platform, i64)], balances: &[Option<i64>]) -> std::result::Result<(), (Platform, i64, i64)> {
+        let mut reserved = self.reserved.lock().unwrap_or_else(|e| e.into_inner());
+        for (&(platform, required), balance) in funds.iter().zip(balances) {
+            let Some(balance) = balance else { continue };
+            let available = balance - reserved.get(&platform).copied().unwrap_or(0);
+            if available < required {
+                return Err((platform, required, available));
+            }
+        }
+        for &(platform, required) in funds {
+            *reserved.entry(platform).or_default() += required;
+        }
+        Ok(())
+    }
+
+    /// Give back funds taken by `reserve`
+    pub fn release(&self, funds: &[(Platform, i64)]) {
+        let mut reserved = self.reserved.lock().unwrap_or_else(|e| e.into_inner());
+        for &(platform, held) in funds {
+            if let Some(total) = reserved.get_mut(&platform) {
+                *total = (*total - held).max(0);
+            }
+        }
+    }
+
+    /// Funds currently held on `platform`
+    #[allow(dead_code)]
+    pub fn reserved(&self, platform: Platform) -> i64 {
+        self.reserved.lock().unwrap_or_else(|e| e.into_inner()).get(&platform).copied().unwrap_or(0)
+    }
+
+    /// Cached balance, if fetched within the TTL
+    pub fn get(&self, platform: Platform) -> Option<i64> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(&platform)
+            .filter(|(_, fetched)| fetched.elapsed() < self.ttl)
+            .map(|&(cents, _)| cents)
+    }
+
+    pub fn store(&self, platform: Platform, cents: i64) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).insert(platform, (cents, Instant::now()));
+    }
+
+    /// Drop a balance that orders have since changed
+    pub fn invalidate(&self, platform: Platform) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(&platform);
+    }
+}
+
+/// Funds reserved in a `BalanceCache`, given back when dropped
+struct FundsHold<'a> {
+    balances: &'a BalanceCache,
+    funds: Vec<(Platform, i64)>,
+}
+
+impl Drop for FundsHold<'_> {
+    fn drop(&mut self) {
+        self.balances.release(&self.funds);
+    }
+}
+
 /// Core execution engine for processing arbitrage opportunities
 pub struct ExecutionEngine {
     kalshi: Option<Arc<KalshiApiClient>>,
-    poly_async: Arc<SharedAsyncClient>,
+    poly_async: Option<Arc<SharedAsyncClient>>,
+    /// Where leg orders are sent; the exchange clients unless a test swaps it
+    orders: Arc<dyn OrderGateway>,
+    poly_books: Arc<OrderBookStore>,
+    /// Kalshi books from the orderbook_delta feed; without them Kalshi legs
+    /// are priced at the quote
+    kalshi_books: Option<Arc<OrderBookStore>>,
     state: Arc<GlobalState>,
     circuit_breaker: Arc<CircuitBreaker>,
     position_channel: PositionChannel,
+    positions: SharedPositionTracker,
     in_flight: Arc<[AtomicU64; 8]>,
+    opportunities: std::sync::Mutex<OpportunityDeduper>,
+    reports: ExecutionReportSender,
     clock: NanoClock,
-    pub dry_run: bool,
+    pub mode: ExecutionMode,
     test_mode: bool,
+    /// Max average price degradation per Polymarket leg vs the quoted price
+    max_slippage_cents: f64,
+    /// Oldest price snapshot still traded on when the orders go out (None = any)
+    max_snapshot_age_ms: Option<u64>,
+    /// Opportunities dropped so far because their prices had gone stale
+    stale_dropped: AtomicU64,
+    /// Gap between expected and reconciled profit (cents) worth a warning
+    fill_discrepancy_cents: i64,
+    /// Re-check the edge at the current quotes right before sending (`REVALIDATE_BEFORE_SEND`)
+    revalidate: bool,
+    /// Opportunities gone by the time their orders were due
+    vanished: AtomicU64,
+    lots: LotConfig,
+    /// Costs subtracted before comparing against the minimum profit
+    fees: FeeSchedule,
+    /// Completion/unwind of legs that filled unevenly
+    partial_fills: PartialFillConfig,
+    /// Optional order-book imbalance entry filter
+    imbalance: ImbalanceConfig,
+    /// Size above which live trades wait for approval
+    approval: ApprovalConfig,
+    approvals: Option<ApprovalSender>,
+    next_approval_id: AtomicU64,
+    /// Daily and per-trade loss limits
+    risk: Arc<RiskGuard>,
+    /// Account balances checked before live trades
+    balances: BalanceCache,
+    /// Capital per trade as a share of the balances, capped
+    sizing: TradeSizingConfig,
+    /// Contracts aimed for before the other clamps
+    sizing_policy: SizingPolicy,
+    balance_alert_interval: Duration,
+    /// Last underfunded alert per platform
+    balance_alerts: std::sync::Mutex<FxHashMap<Platform, Instant>>,
+    /// Decision log, one event per evaluated opportunity
+    events: Option<EventSender>,
+    /// Order type of requests that don't set one (`ORDER_TYPE`)
+    order_type: OrderType,
+    /// Host clock drift per platform; orders are held while it is too large
+    clock_skew: Arc<ClockSkewGuard>,
+    ranking: RankingConfig,
+    /// Probation of newly matched pairs
+    shadow: Option<Arc<ShadowBook>>,
+    /// Multi-outcome markets with a full-set trade in progress
+    full_sets_in_flight: Arc<std::sync::Mutex<FxHashSet<u16>>>,
+    /// Which requests the execution loop acts on
+    strategy: Strategy,
+    spread: SpreadCaptureConfig,
+    /// Markets with spread-capture quotes out
+    spreads_in_flight: Arc<std::sync::Mutex<FxHashSet<u16>>>,
+    /// Startup gate; requests are dropped until it opens
+    warmup: Option<Arc<Warmup>>,
+    /// Platforms whose API is down; nothing is sent to them until they recover
+    health: Option<Arc<PlatformHealth>>,
+    /// One permit per market with an arb execution in progress
+    parallel: Arc<Semaphore>,
+    max_parallel: usize,
 }
 
 impl ExecutionEngine {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         kalshi: Option<Arc<KalshiApiClient>>,
-        poly_async: Arc<SharedAsyncClient>,
+        poly_async: Option<Arc<SharedAsyncClient>>,
+        poly_books: Arc<OrderBookStore>,
         state: Arc<GlobalState>,
         circuit_breaker: Arc<CircuitBreaker>,
         position_channel: PositionChannel,
-        dry_run: bool,
+        positions: SharedPositionTracker,
+        reports: ExecutionReportSender,
+        mode: ExecutionMode,
     ) -> Self {
         let test_mode = std::env::var("TEST_ARB")
             .map(|v| v == "1" || v == "true")
             .unwrap_or(false);
 
+        let max_slippage_cents = std::env::var("MAX_SLIPPAGE_CENTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let max_snapshot_age_ms = std::env::var("MAX_SNAPSHOT_AGE_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&ms: &u64| ms > 0);
+        let fill_discrepancy_cents = std::env::var("FILL_DISCREPANCY_CENTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let revalidate = std::env::var("REVALIDATE_BEFORE_SEND")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "0" | "false" | "off"))
+            .unwrap_or(true);
+        let balance = BalanceConfig::from_env();
+        let parallelism = ParallelismConfig::from_env();
+
         Self {
+            orders: Arc::new(ExchangeOrders::new(kalshi.clone(), poly_async.clone())),
             kalshi,
             poly_async,
+            poly_books,
+            kalshi_books: None,
             state,
             circuit_breaker,
             position_channel,
+            positions,
             in_flight: Arc::new(std::array::from_fn(|_| AtomicU64::new(0))),
+            opportunities: std::sync::Mutex::new(OpportunityDeduper::new(OpportunityDedupConfig::from_env())),
+            reports,
             clock: NanoClock::new(),
-            dry_run,
+            mode,
             test_mode,
+            max_slippage_cents,
+            max_snapshot_age_ms,
+            stale_dropped: AtomicU64::new(0),
+            fill_discrepancy_cents,
+            revalidate,
+            vanished: AtomicU64::new(0),
+            lots: LotConfig::from_env(),
+            fees: FeeSchedule::from_env(),
+            partial_fills: PartialFillConfig::from_env(),
+            imbalance: ImbalanceConfig::from_env(),
+            approval: ApprovalConfig::from_env(),
+            approvals: None,
+            next_approval_id: AtomicU64::new(1),
+            risk: Arc::new(RiskGuard::new(RiskLimitsConfig::from_env())),
+            balances: BalanceCache::new(balance.cache_ttl()),
+            sizing: TradeSizingConfig::from_env(),
+            sizing_policy: SizingPolicy::from_env(),
+            balance_alert_interval: Duration::from_secs(balance.alert_interval_secs),
+            balance_alerts: std::sync::Mutex::new(FxHashMap::default()),
+            events: None,
+            order_type: default_order_type(),
+            clock_skew: Arc::new(ClockSkewGuard::new(ClockSkewConfig::from_env())),
+            ranking: RankingConfig::from_env(),
+            shadow: None,
+            full_sets_in_flight: Arc::default(),
+            strategy: Strategy::from_env(),
+            spread: SpreadCaptureConfig::from_env(),
+            spreads_in_flight: Arc::default(),
+            warmup: None,
+            health: None,
+            parallel: Arc::new(Semaphore::new(parallelism.max_markets)),
+            max_parallel: parallelism.max_markets,
+        }
+    }
+
+    /// Loss limits shared with whoever tracks the realized daily P&L
+    pub fn risk_guard(&self) -> Arc<RiskGuard> {
+        self.risk.clone()
+    }
+
+    /// Clock skew measurements, updated by the periodic check
+    pub fn clock_skew(&self) -> Arc<ClockSkewGuard> {
+        self.clock_skew.clone()
+    }
+
+    /// Ask `approvals` before sending trades above `TRADE_APPROVAL_MIN_NOTIONAL`.
+    /// Without it such trades are skipped.
+    pub fn with_approvals(mut self, approvals: ApprovalSender) -> Self {
+        self.approvals = Some(approvals);
+        self
+    }
+
+    /// Use the loss limits and fees of the validated `AppConfig` instead of
+    /// reading them from the environment again
+    pub fn with_risk_and_fees(mut self, risk: RiskLimitsConfig, fees: FeeSchedule) -> Self {
+        self.risk = Arc::new(RiskGuard::new(risk));
+        self.fees = fees;
+        self
+    }
+
+    /// Price and size Kalshi legs against the local Kalshi books
+    pub fn with_kalshi_books(mut self, books: Arc<OrderBookStore>) -> Self {
+        self.kalshi_books = Some(books);
+        self
+    }
+
+    /// Send leg orders through `orders` instead of the exchange clients
+    #[allow(dead_code)]
+    pub fn with_orders(mut self, orders: Arc<dyn OrderGateway>) -> Self {
+        self.orders = orders;
+        self
+    }
+
+    /// Aim trades at `policy` instead of `TRADE_SIZING`
+    #[allow(dead_code)]
+    pub fn with_sizing_policy(mut self, policy: SizingPolicy) -> Self {
+        self.sizing_policy = policy;
+        self
+    }
+
+    /// Record every evaluated opportunity and its outcome to `events`
+    pub fn with_event_log(mut self, events: EventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Shadow the opportunities of pairs still on probation in `shadow`
+    pub fn with_shadow(mut self, shadow: Arc<ShadowBook>) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    /// Trade nothing until `warmup` opens
+    pub fn with_warmup(mut self, warmup: Arc<Warmup>) -> Self {
+        self.warmup = Some(warmup);
+        self
+    }
+
+    fn warming_up(&self) -> bool {
+        self.warmup.as_ref().is_some_and(|w| !w.is_done())
+    }
+
+    /// Send nothing to a platform `health` reports down
+    pub fn with_platform_health(mut self, health: Arc<PlatformHealth>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// First of `platforms` whose API is down
+    fn platform_down(&self, platforms: impl IntoIterator<Item = Platform>) -> Option<Platform> {
+        let health = self.health.as_ref()?;
+        platforms.into_iter().find(|p| health.is_down(*p))
+    }
+
+    /// Run at most `max_markets` arb executions at once instead of `EXEC_MAX_PARALLEL`
+    #[allow(dead_code)]
+    pub fn with_max_parallel(mut self, max_markets: usize) -> Self {
+        self.max_parallel = max_markets.max(1);
+        self.parallel = Arc::new(Semaphore::new(self.max_parallel));
+        self
+    }
+
+    /// Process an execution request. If its orders expired unfilled and the
+    /// exchange confirmed the cancels, the opportunity is re-evaluated once at
+    /// the market's current quotes.
+    #[cfg_attr(not(feature = "backtest"), allow(dead_code))]
+    pub async fn process(&self, req: FastExecutionRequest) -> Result<ExecutionResult> {
+        self.process_allocated(req, None).await
+    }
+
+    /// `process` with the size capped at `allocated` contracts
+    pub async fn process_allocated(&self, req: FastExecutionRequest, allocated: Option<i64>) -> Result<ExecutionResult> {
+        let result = self.process_in_slot(req, allocated).await;
+        self.release_slot(req.market_id, &result);
+        result
+    }
+
+    /// Take a MAX_OPEN_POSITIONS slot for an entry on `pair`; returns the
+    /// slots in use and the cap if they are all taken
+    async fn acquire_slot(&self, pair: &MarketPair) -> Option<(usize, usize)> {
+        let mut positions = self.positions.write().await;
+        (!positions.acquire_slot(&pair.pair_id))
+            .then(|| (positions.slots_used(), positions.max_open_positions().unwrap_or_default()))
+    }
+
+    /// Release the slot of a finished entry. Queued behind its fills, so a
+    /// position that opened takes over the slot; a duplicate request leaves
+    /// the running entry's slot alone.
+    fn release_slot(&self, market_id: u16, result: &Result<ExecutionResult>) {
+        if result.as_ref().is_ok_and(|r| r.error == Some("Already in-flight")) {
+            return;
+        }
+        if let Some(pair) = self.state.get_by_id(market_id).and_then(|m| m.pair.as_ref()) {
+            self.position_channel.release_slot(&pair.pair_id);
+        }
+    }
+
+    async fn process_in_slot(&self, req: FastExecutionRequest, allocated: Option<i64>) -> Result<ExecutionResult> {
+        let result = self.execute(req, allocated).await?;
+        self.log_decision(&req, &result);
+        if result.error != Some("Orders expired") {
+            return Ok(result);
+        }
+        match self.requote(req.market_id, req.arb_type) {
+            Some(fresh) => {
+                info!("[EXEC] 🔁 Orders expired on {}, re-evaluating {:?} at y={}¢ n={}¢",
+                      self.market_description(req.market_id), fresh.arb_type, fresh.yes_price, fresh.no_price);
+                let result = self.execute(fresh, allocated).await?;
+                self.log_decision(&fresh, &result);
+                Ok(result)
+            }
+            None => Ok(result),
+        }
+    }
+
+    /// Queue the decision for the event log; dropped if the writer falls behind
+    fn log_decision(&self, req: &FastExecutionRequest, result: &ExecutionResult) {
+        let Some(events) = &self.events else { return };
+        let Some(pair) = self.state.get_by_id(req.market_id).and_then(|m| m.pair.as_ref()) else { return };
+        if events.try_send(DecisionEvent::new(pair, req, &self.fees, result)).is_err() {
+            debug!("[EXEC] Event log full, decision on {} dropped", pair.description);
+        }
+    }
+
+    /// Buy one share of every outcome of a multi-outcome market. Legs that
+    /// filled more than the others are sold back down to the smallest fill,
+    /// so only complete sets (worth $1 each at resolution) are kept. Each
+    /// outcome's fill is recorded under `poly_position_key`: on the pair that
+    /// trades its token, or as a one-sided position keyed by the token.
+    pub async fn process_full_set(&self, req: MultiOutcomeRequest) -> Result<ExecutionResult> {
+        let index = req.market_index;
+        let market = self.state.multi_outcome.get(index as usize)
+            .ok_or_else(|| Error::Invalid(format!("Unknown multi-outcome market {}", index)))?;
+        let skipped = |error| Ok(ExecutionResult {
+            market_id: index,
+            success: false,
+            profit_cents: 0,
+            latency_ns: self.clock.now_ns() - req.detected_ns,
+            error: Some(error),
+        });
+
+        if !self.full_sets_in_flight.lock().unwrap_or_else(|e| e.into_inner()).insert(index) {
+            return skipped("Already in-flight");
+        }
+
+        let net_profit = req.net_profit_cents(&self.fees).unwrap_or(f64::MIN);
+        if net_profit < self.fees.min_profit_cents {
+            self.release_full_set(index, false);
+            return skipped("Profit below threshold");
+        }
+
+        let requested_contracts = req.contracts();
+        let mut contracts = requested_contracts;
+        if self.test_mode && contracts > 10 {
+            warn!("[EXEC] ⚠️ TEST_MODE: Position size capped from {} to 10 contracts", contracts);
+            contracts = 10;
+        }
+        if contracts < 1 {
+            self.release_full_set(index, false);
+            return skipped("Insufficient liquidity");
+        }
+
+        // Price every leg against its book; the limit is the deepest level
+        // needed, or a resting price inside the spread for post-only legs
+        let mut legs = Vec::with_capacity(market.outcomes.len());
+        for (outcome, &(ask, _)) in market.outcomes.iter().zip(&req.asks) {
+            let token = fxhash_str(&outcome.token);
+            if self.order_type == OrderType::PostOnly {
+                let bid = self.poly_books.best_bid(token).and_then(|(bps, _)| Price::bid_from_bps(bps)).map(Price::cents);
+                let price = maker_price(bid, ask, outcome.constraints.tick_bps.div_ceil(100) as PriceCents);
+                legs.push((price as f64, price));
+                continue;
+            }
+            match self.poly_books.vwap(token, BookSide::Ask, contracts as f64) {
+                Ok(fill) => legs.push((fill.avg_price_bps / 100.0, fill.worst_price_bps.div_ceil(100) as PriceCents)),
+                Err(e) => {
+                    warn!("[EXEC] Depth check failed: {} | {} {}x | {}", market.description, outcome.name, contracts, e);
+                    self.release_full_set(index, false);
+                    return skipped("Insufficient depth");
+                }
+            }
+        }
+        for (outcome, (_, limit)) in market.outcomes.iter().zip(legs.iter_mut()) {
+            match fit_poly_order(outcome.constraints, true, *limit, contracts) {
+                Ok(price) => *limit = price,
+                Err(reason) => {
+                    info!("[EXEC] {}: {} | {} {}x at {}¢", reason, market.description, outcome.name, contracts, limit);
+                    self.release_full_set(index, true);
+                    return skipped(reason);
+                }
+            }
+        }
+        let liquidity = Liquidity::of(self.order_type);
+        let vwap_profit = 100.0 - legs.iter()
+            .map(|&(avg, _)| avg + self.fees.fee_cents(Platform::Polymarket, avg, contracts, liquidity))
+            .sum::<f64>()
+            - self.fees.fixed_cost_cents;
+        if vwap_profit < self.fees.min_profit_cents {
+            info!("[EXEC] Slippage kills edge: {} | full set quoted={:.1}¢ vwap={:.1}¢ | {}x",
+                  market.description, net_profit, vwap_profit, contracts);
+            self.release_full_set(index, false);
+            return skipped("Profit below threshold after slippage");
+        }
+
+        if let Some(resume) = self.risk.halted_until(chrono::Utc::now()) {
+            debug!("[EXEC] Daily loss limit: {} skipped until {}", market.description, resume);
+            self.release_full_set(index, true);
+            return skipped("Daily loss limit");
+        }
+        let at_limits = MultiOutcomeRequest {
+            asks: legs.iter().zip(&req.asks).map(|(&(_, limit), &(_, size))| (limit, size)).collect(),
+            ..req.clone()
+        };
+        let worst_case = at_limits.worst_case_loss_cents(contracts);
+        if !self.risk.trade_allowed(worst_case) {
+            info!("[EXEC] Trade risk limit: {} | full set {}x could lose {}¢", market.description, contracts, worst_case);
+            self.release_full_set(index, true);
+            return skipped("Trade risk limit");
+        }
+
+        if self.circuit_breaker.can_execute(&market.event_slug, contracts).await.is_err() {
+            self.release_full_set(index, false);
+            return skipped("Circuit breaker");
+        }
+
+        let description = format!("{} ({} outcomes)", market.description, market.outcomes.len());
+        let latency_to_exec = self.clock.now_ns() - req.detected_ns;
+        let expected_profit = (vwap_profit * contracts as f64).round().min(i16::MAX as f64) as i16;
+        info!(
+            market = %market.description,
+            profit_cents = expected_profit,
+            latency_us = latency_to_exec / 1000,
+            "[EXEC] 🎯 Full set of {} outcomes, cost={:.1}¢ | profit={:.1}¢/set | {}x {}",
+            market.outcomes.len(), 100.0 - vwap_profit, vwap_profit, contracts, self.order_type,
+        );
+
+        if self.mode == ExecutionMode::DryRun {
+            info!(market = %market.description, profit_cents = expected_profit,
+                  "[EXEC] 🏃 DRY RUN - would buy {} full sets", contracts);
+            self.release_full_set(index, true);
+            self.report_poly_trade(description, contracts, requested_contracts, Some(contracts), expected_profit, true, latency_to_exec, true, 0, 0, Vec::new());
+            return Ok(ExecutionResult {
+                market_id: index,
+                success: true,
+                profit_cents: expected_profit,
+                latency_ns: latency_to_exec,
+                error: Some("DRY_RUN"),
+            });
+        }
+
+        if let Some((platform, skew_ms)) = self.clock_skew.blocked([Platform::Polymarket]) {
+            warn!("[EXEC] 🕐 {} clock skew {:+}ms: {} not sent", platform, skew_ms, market.description);
+            self.release_full_set(index, true);
+            return skipped("Clock skew");
+        }
+        if let Some(platform) = self.platform_down([Platform::Polymarket]) {
+            warn!("[EXEC] 🚧 {} down: {} not sent", platform, market.description);
+            self.release_full_set(index, false);
+            return skipped("Platform down");
+        }
+
+        let Some(poly) = self.poly_async.as_ref() else {
+            self.release_full_set(index, false);
+            return Err(Error::Invalid("Polymarket not configured - cannot execute full-set arb".into()));
+        };
+
+        let required = at_limits.cost_cents() as i64 * contracts;
+        if let Some(available) = self.balance(Platform::Polymarket).await.filter(|&available| available < required) {
+            warn!("[EXEC] 💸 Insufficient POLYMARKET balance: {} | full set {}x needs {}¢, {}¢ available",
+                  market.description, contracts, required, available);
+            self.release_full_set(index, true);
+            if self.should_alert_balance(Platform::Polymarket) {
+                let _ = self.reports.send(ExecutionReport::Underfunded(UnderfundedReport {
+                    market: description,
+                    platform: Platform::Polymarket,
+                    required_cents: required,
+                    available_cents: available,
+                    expected_profit_cents: expected_profit as i64,
+                }));
+            }
+            return skipped("Insufficient balance");
+        }
+
+        let notional = required as f64 / 100.0;
+        if self.approval.requires(notional) {
+            let decision = self.await_approval(&description, ArbType::PolyOnly, contracts, notional, expected_profit as i64).await;
+            if decision != ApprovalDecision::Approved {
+                info!("[EXEC] 🙅 Trade skipped ({:?}): {} | {}x ${:.2}", decision, description, contracts, notional);
+                self.release_full_set(index, true);
+                let _ = self.reports.send(ExecutionReport::Declined(DeclinedReport {
+                    market: description,
+                    arb_type: ArbType::PolyOnly,
+                    contracts,
+                    notional,
+                    decision,
+                }));
+                return skipped(match decision {
+                    ApprovalDecision::Rejected => "Approval rejected",
+                    ApprovalDecision::TimedOut => "Approval timed out",
+                    _ => "Approval unavailable",
+                });
+            }
+        }
+
+        // Every leg at once
+        let orders = market.outcomes.iter().zip(&legs).map(|(outcome, &(_, limit))| async move {
+            poly.buy(&outcome.token, leg_price(limit)?, contracts as f64, self.order_type).await
+        });
+        let results = futures_util::future::join_all(orders).await;
+        self.balances.invalidate(Platform::Polymarket);
+
+        let mut fills = Vec::with_capacity(results.len());
+        let mut trade_fills = Vec::with_capacity(results.len());
+        let mut cancelled_orders = 0;
+        for (outcome, result) in market.outcomes.iter().zip(results) {
+            match result {
+                Ok(fill) => {
+                    cancelled_orders += (fill.outcome != OrderOutcome::Completed) as u32;
+                    fills.push((fill.filled_size as i64, (fill.fill_cost * 100.0).round() as i64));
+                    if fill.filled_size as i64 > 0 {
+                        trade_fills.push(TradeFill {
+                            platform: "polymarket",
+                            side: outcome.name.to_string(),
+                            contracts: fill.filled_size as i64,
+                            cost_cents: (fill.fill_cost * 100.0).round() as i64,
+                            order_id: fill.order_id,
+                        });
+                    }
+                }
+                Err(e) => {
+                    warn!("[EXEC] ⚠️ {} leg {} failed: {}", market.description, outcome.name, e);
+                    fills.push((0, 0));
+                }
+            }
+        }
+
+        let matched = fills.iter().map(|&(filled, _)| filled).min().unwrap_or(0);
+        let mut profit = matched * 100 - fills.iter()
+            .map(|&(filled, cost)| if filled > 0 { cost * matched / filled } else { 0 })
+            .sum::<i64>();
+
+        // Sell back whatever was bought beyond the complete sets
+        let mut unwound = 0;
+        if fills.iter().any(|&(filled, _)| filled > matched) {
+            warn!("[EXEC] ⚠️ Fill mismatch on {}: {:?} (complete sets: {})",
+                  market.description, fills.iter().map(|f| f.0).collect::<Vec<_>>(), matched);
+            // Freshly bought shares take a moment to settle before they can be sold
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            for ((outcome, &(_, limit)), &(filled, cost)) in market.outcomes.iter().zip(&legs).zip(&fills) {
+                let excess = filled - matched;
+                if excess <= 0 {
+                    continue;
+                }
+                let close_price = limit.saturating_sub(self.partial_fills.unwind_discount_cents).max(1);
+                let sale = match fit_poly_order(outcome.constraints, false, close_price, excess) {
+                    Ok(close_price) => poly.sell_fak(&outcome.token, leg_price(close_price)?, excess as f64).await,
+                    Err(reason) => Err(Error::Invalid(reason.into())),
+                };
+                match sale {
+                    Ok(sale) => {
+                        let sold = (sale.filled_size as i64).min(excess);
+                        let pnl = (sale.fill_cost * 100.0).round() as i64 - cost * sold / filled;
+                        info!("[EXEC] ✅ Unwound {} {} contracts (P&L: {}¢)", sold, outcome.name, pnl);
+                        profit += pnl;
+                        unwound += sold;
+                        if let Some(held) = trade_fills.iter_mut().find(|f| *f.side == *outcome.name) {
+                            held.contracts -= sold;
+                            held.cost_cents -= cost * sold / filled;
+                        }
+                        if sold < excess {
+                            error!("[EXEC] ❌ {} {} contracts left unhedged", excess - sold, outcome.name);
+                        }
+                    }
+                    Err(e) => error!("[EXEC] ❌ Failed to unwind {} {} contracts: {}", excess, outcome.name, e),
+                }
+            }
+        }
+
+        // One position per outcome; contracts the unwind couldn't sell stay on it
+        for fill in trade_fills.iter().filter(|f| f.contracts > 0) {
+            let Some(outcome) = market.outcomes.iter().find(|o| *o.name == *fill.side) else { continue };
+            let (market_id, side) = poly_position_key(&self.state, &outcome.token);
+            self.position_channel.record_fill(FillRecord::new(
+                &market_id, &format!("{} - {}", market.description, outcome.name), "polymarket", side,
+                fill.contracts as f64, fill.cost_cents as f64 / 100.0 / fill.contracts as f64, 0.0, &fill.order_id,
+            ));
+        }
+
+        let success = matched > 0;
+        let profit_cents = profit.clamp(i16::MIN as i64, i16::MAX as i64) as i16;
+        if success {
+            self.circuit_breaker.record_success(&market.event_slug, 0, matched, profit_cents as f64 / 100.0).await;
+        } else if fills.iter().all(|&(filled, _)| filled == 0) {
+            self.circuit_breaker.record_error().await;
+        } else {
+            self.circuit_breaker.record_pnl(profit_cents as f64 / 100.0);
+        }
+
+        let latency_ns = self.clock.now_ns() - req.detected_ns;
+        self.report_poly_trade(description, matched, requested_contracts, Some(contracts), profit_cents, success, latency_ns, false, unwound, cancelled_orders, trade_fills);
+        self.release_full_set(index, true);
+        Ok(ExecutionResult {
+            market_id: index,
+            success,
+            profit_cents,
+            latency_ns,
+            error: if success { None } else { Some("Partial/no fill") },
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn report_poly_trade(
+        &self,
+        market: String,
+        contracts: i64,
+        requested_contracts: i64,
+        hedge_contracts: Option<i64>,
+        profit_cents: i16,
+        success: bool,
+        latency_ns: u64,
+        simulated: bool,
+        unwound_contracts: i64,
+        cancelled_orders: u32,
+        fills: Vec<TradeFill>,
+    ) {
+        let _ = self.reports.send(ExecutionReport::Trade(TradeReport {
+            market,
+            arb_type: ArbType::PolyOnly,
+            contracts,
+            requested_contracts,
+            hedge_contracts,
+            limit_hit: None,
+            profit_cents,
+            success,
+            latency_ms: latency_ns / 1_000_000,
+            simulated,
+            unwound_contracts,
+            cancelled_orders,
+            fills,
+        }));
+    }
+
+    /// Allow new full-set trades on a market, after a pause if orders may have been sent
+    fn release_full_set(&self, index: u16, delayed: bool) {
+        release_market(self.full_sets_in_flight.clone(), index, delayed);
+    }
+
+    /// Quote both sides of a market inside its spread (`Strategy::SpreadCapture`):
+    /// a post-only YES bid and a post-only NO bid standing for the YES ask,
+    /// resting until the order TTL. Matched YES/NO fills lock in the spread;
+    /// the rest is carried as inventory, which skews the next quotes.
+    pub async fn process_spread(&self, req: SpreadRequest) -> Result<ExecutionResult> {
+        let result = self.quote_spread(req).await;
+        self.release_slot(req.market_id, &result);
+        result
+    }
+
+    async fn quote_spread(&self, req: SpreadRequest) -> Result<ExecutionResult> {
+        let market_id = req.market_id;
+        let pair = self.state.get_by_id(market_id)
+            .and_then(|m| m.pair.clone())
+            .ok_or_else(|| Error::Invalid(format!("Unknown market {}", market_id)))?;
+        let skipped = |error| Ok(ExecutionResult {
+            market_id,
+            success: false,
+            profit_cents: 0,
+            latency_ns: self.clock.now_ns() - req.detected_ns,
+            error: Some(error),
+        });
+
+        if !self.spreads_in_flight.lock().unwrap_or_else(|e| e.into_inner()).insert(market_id) {
+            return skipped("Already in-flight");
+        }
+        if req.spread_cents() < self.spread.min_spread_cents {
+            self.release_spread(market_id, false);
+            return skipped("Spread below threshold");
+        }
+
+        let (inventory, mut quote) = {
+            let positions = self.positions.read().await;
+            let inventory = positions.inventory(&pair.pair_id);
+            let Some(mut quote) = req.quote(&self.spread, inventory) else {
+                drop(positions);
+                debug!("[EXEC] Spread capture: no side to quote on {} (inventory {:+})", pair.description, inventory);
+                self.release_spread(market_id, false);
+                return skipped("Inventory limit");
+            };
+            // Only the side adding to the inventory counts against the
+            // position limits; the other one works it off
+            if inventory >= 0.0 {
+                if let Some((price, contracts)) = quote.bid {
+                    let allowed = positions.can_open(&pair.pair_id, contracts as f64, price as f64 / 100.0).contracts.floor() as i64;
+                    quote.bid = (allowed >= 1).then_some((price, allowed.min(contracts)));
+                }
+            }
+            if inventory <= 0.0 {
+                if let Some((price, contracts)) = quote.ask {
+                    let allowed = positions.can_open(&pair.pair_id, contracts as f64, (100 - price) as f64 / 100.0).contracts.floor() as i64;
+                    quote.ask = (allowed >= 1).then_some((price, allowed.min(contracts)));
+                }
+            }
+            (inventory, quote)
+        };
+        if quote.bid.is_none() && quote.ask.is_none() {
+            info!("[EXEC] Position limit reached: {} | spread capture", pair.description);
+            self.release_spread(market_id, false);
+            return skipped("Position limit reached");
+        }
+        if let Some((used, max)) = self.acquire_slot(&pair).await {
+            info!("[EXEC] Open position cap reached: {} | spread capture skipped ({}/{} slots in use)", pair.description, used, max);
+            self.release_spread(market_id, false);
+            return skipped("Open position cap reached");
+        }
+
+        // Both quotes are Polymarket buys: on the tick, rounded away from the
+        // opposite side, and at least the minimum size
+        if let Some((price, contracts)) = quote.bid {
+            quote.bid = fit_poly_order(pair.poly_constraints, true, price, contracts)
+                .ok().map(|price| (price, contracts));
+        }
+        if let Some((price, contracts)) = quote.ask {
+            quote.ask = fit_poly_order(pair.poly_constraints, true, 100 - price, contracts)
+                .ok().map(|no_price| (100 - no_price, contracts));
+        }
+        if quote.bid.is_none() && quote.ask.is_none() {
+            info!("[EXEC] Below Polymarket order constraints: {} | spread capture {} contracts",
+                  pair.description, self.spread.quote_contracts);
+            self.release_spread(market_id, true);
+            return skipped("Below minimum order size");
+        }
+
+        if let Some(resume) = self.risk.halted_until(chrono::Utc::now()) {
+            debug!("[EXEC] Daily loss limit: {} skipped until {}", pair.description, resume);
+            self.release_spread(market_id, false);
+            return skipped("Daily loss limit");
+        }
+        if self.circuit_breaker.can_execute(&pair.pair_id, self.spread.quote_contracts).await.is_err() {
+            self.release_spread(market_id, false);
+            return skipped("Circuit breaker");
+        }
+
+        let describe = |side: Option<(PriceCents, i64)>| side.map_or("-".to_string(), |(price, contracts)| format!("{}x@{}¢", contracts, price));
+        let description = format!("{} (spread)", pair.description);
+        let latency_to_exec = self.clock.now_ns() - req.detected_ns;
+        info!(
+            market = %pair.description,
+            latency_us = latency_to_exec / 1000,
+            "[EXEC] 📐 Spread capture: book {}¢/{}¢ | quoting bid {} ask {} | inventory {:+}",
+            req.bid, req.ask, describe(quote.bid), describe(quote.ask), inventory,
+        );
+
+        if self.mode == ExecutionMode::DryRun {
+            info!(market = %pair.description, "[EXEC] 🏃 DRY RUN - would quote bid {} ask {}", describe(quote.bid), describe(quote.ask));
+            self.release_spread(market_id, false);
+            return Ok(ExecutionResult {
+                market_id,
+                success: true,
+                profit_cents: 0,
+                latency_ns: latency_to_exec,
+                error: Some("DRY_RUN"),
+            });
+        }
+
+        if let Some((platform, skew_ms)) = self.clock_skew.blocked([Platform::Polymarket]) {
+            warn!("[EXEC] 🕐 {} clock skew {:+}ms: {} not sent", platform, skew_ms, pair.description);
+            self.release_spread(market_id, false);
+            return skipped("Clock skew");
+        }
+        if let Some(platform) = self.platform_down([Platform::Polymarket]) {
+            warn!("[EXEC] 🚧 {} down: {} not sent", platform, pair.description);
+            self.release_spread(market_id, false);
+            return skipped("Platform down");
+        }
+        let Some(poly) = self.poly_async.as_ref() else {
+            self.release_spread(market_id, false);
+            return Err(Error::Invalid("Polymarket not configured - cannot quote spreads".into()));
+        };
+
+        let required = quote.bid.map_or(0, |(price, contracts)| price as i64 * contracts)
+            + quote.ask.map_or(0, |(price, contracts)| (100 - price) as i64 * contracts);
+        if let Some(available) = self.balance(Platform::Polymarket).await.filter(|&available| available < required) {
+            warn!("[EXEC] 💸 Insufficient POLYMARKET balance: {} | quotes need {}¢, {}¢ available",
+                  pair.description, required, available);
+            self.release_spread(market_id, false);
+            return skipped("Insufficient balance");
+        }
+
+        // The YES ask is a NO bid at the complement
+        let legs = [
+            ("yes", &pair.poly_yes_token, quote.bid),
+            ("no", &pair.poly_no_token, quote.ask.map(|(price, contracts)| (100 - price, contracts))),
+        ];
+        let orders = legs.iter().map(|&(side, token, order)| async move {
+            let Some((price, contracts)) = order else { return Ok(None) };
+            let fill = poly.buy(token, leg_price(price)?, contracts as f64, OrderType::PostOnly).await?;
+            Ok::<_, Error>(Some((side, fill)))
+        });
+        let results = futures_util::future::join_all(orders).await;
+        self.balances.invalidate(Platform::Polymarket);
+
+        let mut fills = [(0i64, 0i64); 2];
+        let mut trade_fills = Vec::new();
+        let mut cancelled_orders = 0;
+        let mut unconfirmed = false;
+        for (slot, result) in fills.iter_mut().zip(results) {
+            match result {
+                Ok(Some((side, fill))) => {
+                    cancelled_orders += (fill.outcome != OrderOutcome::Completed) as u32;
+                    unconfirmed |= fill.outcome == OrderOutcome::CancelUnconfirmed;
+                    let filled = fill.filled_size as i64;
+                    *slot = (filled, (fill.fill_cost * 100.0).round() as i64);
+                    if filled > 0 {
+                        self.position_channel.record_fill(FillRecord::new(
+                            &pair.pair_id, &pair.description, "polymarket", side,
+                            filled as f64, fill.fill_cost / filled as f64, 0.0, &fill.order_id,
+                        ));
+                        trade_fills.push(TradeFill {
+                            platform: "polymarket",
+                            side: side.to_string(),
+                            contracts: filled,
+                            cost_cents: slot.1,
+                            order_id: fill.order_id,
+                        });
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("[EXEC] ⚠️ {} quote failed: {}", pair.description, e);
+                    unconfirmed = true;
+                }
+            }
+        }
+
+        // Each YES + NO pair pays out 100¢ whatever the result
+        let [(yes_filled, yes_cost), (no_filled, no_cost)] = fills;
+        let matched = yes_filled.min(no_filled);
+        let profit = hedged_profit_cents(yes_filled, yes_cost, no_filled, no_cost);
+        let profit_cents = profit.clamp(i16::MIN as i64, i16::MAX as i64) as i16;
+        let success = yes_filled > 0 || no_filled > 0;
+        if matched > 0 {
+            self.circuit_breaker.record_success(&pair.pair_id, 0, matched, profit_cents as f64 / 100.0).await;
+        }
+        let latency_ns = self.clock.now_ns() - req.detected_ns;
+        // Quotes expiring unfilled are the normal case and not reported
+        if success {
+            info!(market = %pair.description, profit_cents,
+                  "[EXEC] ✅ Spread capture filled yes={} no={} (matched {})", yes_filled, no_filled, matched);
+            let quoted = quote.bid.map_or(0, |(_, c)| c).max(quote.ask.map_or(0, |(_, c)| c));
+            self.report_poly_trade(description, matched, quoted, None, profit_cents, success, latency_ns, false, 0, cancelled_orders, trade_fills);
+        }
+        // A quote may still be resting if its cancel went unconfirmed
+        self.release_spread(market_id, unconfirmed);
+        Ok(ExecutionResult {
+            market_id,
+            success,
+            profit_cents,
+            latency_ns,
+            error: if success { None } else { Some("Quotes not filled") },
+        })
+    }
+
+    /// Allow new quotes on a market, after a pause if an order may still rest
+    fn release_spread(&self, market_id: u16, delayed: bool) {
+        release_market(self.spreads_in_flight.clone(), market_id, delayed);
+    }
+
+    /// Sell the unhedged contracts of every directional leg whose bid fell
+    /// `retrace_cents` below its high-water mark, IOC/FAK down to the bid
+    /// minus the allowed slippage. Dry runs only report the stop and re-arm it.
+    pub async fn check_trailing_stops(&self, retrace_cents: f64) -> Vec<TrailingStopReport> {
+        let stops = self.positions.write().await
+            .trailing_stops(retrace_cents / 100.0, best_bids(&self.state, &self.poly_books));
+        let mut reports = Vec::new();
+        for stop in stops {
+            let pair = self.state.markets.iter()
+                .filter_map(|m| m.pair.as_deref())
+                .find(|pair| *pair.pair_id == stop.market_id);
+            let mut report = TrailingStopReport {
+                market: stop.description.clone(),
+                platform: stop.platform,
+                side: stop.side,
+                high_water: stop.high_water,
+                mark: stop.mark,
+                contracts: stop.contracts.floor(),
+                closed: 0.0,
+                realized_pnl: 0.0,
+                error: None,
+                simulated: self.mode == ExecutionMode::DryRun,
+            };
+            if report.contracts < 1.0 {
+                continue;
+            }
+            // Unmanaged until its platform recovers; the stop fires again then
+            if let Some(platform) = self.platform_down([leg_platform(stop.platform)]) {
+                debug!("[EXEC] {} down: trailing stop on {} {} held", platform, stop.description, stop.side);
+                continue;
+            }
+            warn!("[EXEC] 📉 Trailing stop: {} {}/{} bid ${:.2} is {:.1}¢ off its ${:.2} high, selling {:.0}",
+                  stop.description, stop.platform, stop.side, stop.mark,
+                  (stop.high_water - stop.mark) * 100.0, stop.high_water, report.contracts);
+            if report.simulated {
+                info!("[EXEC] 🏃 DRY RUN - would sell {:.0} {} {}/{}", report.contracts, stop.description, stop.platform, stop.side);
+                self.positions.write().await.reset_high_water(&stop.market_id, stop.platform, stop.side);
+                reports.push(report);
+                continue;
+            }
+
+            let mut limit = Price::from_poly(stop.mark - self.max_slippage_cents / 100.0)
+                .filter(|p| p.is_tradable())
+                .unwrap_or(Price::CENT);
+            // The CLOB rejects sells off the market's tick
+            if let Some(constraints) = pair.filter(|_| stop.platform == "polymarket").map(|p| p.poly_constraints) {
+                if !constraints.on_tick(limit) {
+                    limit = constraints.ceil_price(limit.cents()).and_then(Price::from_cents).unwrap_or(limit);
+                }
+            }
+            let target = leg_target(pair, &stop.market_id, stop.platform, stop.side);
+            let mut legs = [FlattenLeg {
+                market_id: stop.market_id.clone(),
+                description: stop.description.clone(),
+                platform: stop.platform,
+                side: stop.side,
+                error: target.is_none().then(|| "market not found".to_string()),
+                target,
+                contracts: report.contracts,
+                closed: 0.0,
+                proceeds: 0.0,
+                realized_pnl: 0.0,
+                attempts: 0,
+            }];
+            // Once per check: a stop that didn't fill fires again next time
+            let once = FlattenConfig { max_attempts: 1, retry_delay_ms: 0 };
+            let (kalshi, poly) = (self.kalshi.as_deref(), self.poly_async.as_deref());
+            close_legs(&mut legs, once, |platform, side, target, contracts| {
+                async move { sell_leg(kalshi, poly, platform, side, &target, limit, contracts).await }
+            }).await;
+
+            let [leg] = legs;
+            if leg.closed > 0.0 {
+                report.realized_pnl = self.positions.write().await
+                    .record_close(&leg.market_id, leg.platform, leg.side, leg.closed, leg.proceeds)
+                    .unwrap_or(0.0);
+            }
+            report.closed = leg.closed;
+            if !leg.is_done() {
+                report.error = leg.error;
+            }
+            reports.push(report);
         }
+        for report in &reports {
+            let _ = self.reports.send(ExecutionReport::TrailingStop(report.clone()));
+        }
+        reports
+    }
+
+    /// The same arb priced from the market's current quotes, if both legs are quoted
+    fn requote(&self, market_id: u16, arb_type: ArbType) -> Option<FastExecutionRequest> {
+        let market = self.state.get_by_id(market_id)?;
+        FastExecutionRequest::from_arb_mask(market_id, market, arb_type.mask_bit(), self.clock.now_ns())
     }
 
-    /// Process an execution request
     #[inline]
-    pub async fn process(&self, req: FastExecutionRequest) -> Result<ExecutionResult> {
+    async fn execute(&self, req: FastExecutionRequest, allocated: Option<i64>) -> Result<ExecutionResult> {
         let market_id = req.market_id;
 
+        self.report_opportunity(&req);
+
         // Deduplication check (512 markets via 8x u64 bitmask)
         if market_id < 512 {
             let slot = (market_id / 64) as usize;
@@ -109,14 +1787,16 @@ impl ExecutionEngine {
 
         // Get market pair 
         let market = self.state.get_by_id(market_id)
-            .ok_or_else(|| anyhow!("Unknown market_id {}", market_id))?;
+            .ok_or_else(|| Error::Invalid(format!("Unknown market_id {}", market_id)))?;
 
         let pair = market.pair.as_ref()
-            .ok_or_else(|| anyhow!("No pair for market_id {}", market_id))?;
+            .ok_or_else(|| Error::Invalid(format!("No pair for market_id {}", market_id)))?;
 
-        // Calculate profit
-        let profit_cents = req.profit_cents();
-        if profit_cents < 1 {
+        // Calculate profit net of all trading costs
+        let liquidity = Liquidity::of(req.order_type.unwrap_or(self.order_type));
+        let net_profit = req.net_profit_cents_as(&self.fees, liquidity);
+        let profit_cents = net_profit.floor() as i16;
+        if net_profit < self.fees.min_profit_cents {
             self.release_in_flight(market_id);
             return Ok(ExecutionResult {
                 market_id,
@@ -127,8 +1807,36 @@ impl ExecutionEngine {
             });
         }
 
-        // Calculate max contracts from size (min of both sides)
-        let mut max_contracts = (req.yes_size.min(req.no_size) / 100) as i64;
+        let imbalance = market.poly_imbalance.load();
+        if !imbalance_allows(self.imbalance.min_imbalance, req.arb_type, imbalance) {
+            debug!("[EXEC] Book imbalance against entry: {} | {:?} yes={:?} no={:?} (min {:?})",
+                   pair.description, req.arb_type, imbalance.0, imbalance.1, self.imbalance.min_imbalance);
+            self.release_in_flight(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Book imbalance against entry"),
+            });
+        }
+
+        // Size clamps, each one only ever shrinking the size:
+        //  1. top-of-book depth (the smaller side), and the sizing policy's
+        //     target if that is smaller
+        //  2. TEST_ARB cap of 10 contracts
+        //  3. share of the batch allocation
+        //  4. capital per trade (TRADE_BALANCE_FRACTION, MAX_TRADE_CAPITAL)
+        //  5. what the Polymarket books fill within MAX_SLIPPAGE_CENTS
+        //  6. per-market and global position limits
+        //  7. common lot size and minimum of both legs (rounded down)
+        // A clamp leaving no contract skips the trade; the balance check
+        // before sending then skips rather than shrinks.
+        let mut max_contracts = req.hedged_contracts();
+        if let Some(target) = self.sizing_policy.target_contracts(net_profit).filter(|&t| t < max_contracts) {
+            debug!("[EXEC] Sized by policy: {} | {:.1}¢ edge, {}x → {}x", pair.description, net_profit, max_contracts, target);
+            max_contracts = target;
+        }
 
         // Safety: In test mode, cap position size at 10 contracts
         // Note: Polymarket enforces a $1 minimum order value. At 40¢ per contract,
@@ -139,6 +1847,96 @@ impl ExecutionEngine {
             max_contracts = 10;
         }
 
+        let requested_contracts = max_contracts;
+
+        // Share of the balance given to this opportunity within its batch
+        if let Some(allocated) = allocated.filter(|&a| a < max_contracts) {
+            info!("[EXEC] 💰 Size reduced to allocation: {} | {}x → {}x", pair.description, max_contracts, allocated);
+            max_contracts = allocated;
+        }
+
+        // Capital per trade: a share of the smaller balance, under the cap
+        if let Some(budgeted) = self.capital_contracts(&req).await.filter(|&c| c < max_contracts) {
+            if budgeted < 1 {
+                info!("[EXEC] Trade capital too small: {} | {:?} one contract costs more than the per-trade budget",
+                      pair.description, req.arb_type);
+                self.release_in_flight_delayed(market_id);
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("Trade capital too small"),
+                });
+            }
+            info!("[EXEC] 💰 Size reduced to capital per trade: {} | {}x → {}x", pair.description, max_contracts, budgeted);
+            max_contracts = budgeted;
+        }
+
+        // Shrink to what the Polymarket books can fill within the slippage budget
+        let fillable = self.max_fillable_contracts(&req, pair);
+        if fillable < max_contracts {
+            if fillable < 1 {
+                info!(
+                    "[EXEC] Slippage budget exceeded: {} | {:?} wanted {}x, none fillable within {}¢",
+                    pair.description, req.arb_type, max_contracts, self.max_slippage_cents
+                );
+                self.release_in_flight(market_id);
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("Slippage budget exceeded"),
+                });
+            }
+            info!(
+                "[EXEC] 📉 Size reduced for slippage: {} | {}x → {}x (max {}¢)",
+                pair.description, max_contracts, fillable, self.max_slippage_cents
+            );
+            max_contracts = fillable;
+        }
+
+        // Enforce per-market and global exposure limits
+        let cost_per_contract = (req.yes_price + req.no_price) as f64 / 100.0;
+        let check = self.positions.read().await
+            .can_open(&pair.pair_id, max_contracts as f64, cost_per_contract);
+        let allowed = check.contracts.floor() as i64;
+        let mut limit_hit = None;
+        if allowed < max_contracts {
+            let limit = check.limit.expect("can_open only trims when a limit applies");
+            if allowed < 1 {
+                info!("[EXEC] Position limit reached: {} | {:?} {}x | {}",
+                      pair.description, req.arb_type, max_contracts, limit);
+                self.release_in_flight(market_id);
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("Position limit reached"),
+                });
+            }
+            info!("[EXEC] 🚧 Size reduced for position limit: {} | {}x → {}x ({})",
+                  pair.description, max_contracts, allowed, limit);
+            max_contracts = allowed;
+            limit_hit = Some(limit);
+        }
+
+        // A new market needs one of the MAX_OPEN_POSITIONS slots
+        if let Some((used, max)) = self.acquire_slot(pair).await {
+            info!("[EXEC] Open position cap reached: {} | {:?} {}x skipped ({}/{} slots in use)",
+                  pair.description, req.arb_type, max_contracts, used, max);
+            self.release_in_flight(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Open position cap reached"),
+            });
+        }
+
         if max_contracts < 1 {
             warn!(
                 "[EXEC] Liquidity fail: {:?} | yes_size={}¢ no_size={}¢",
@@ -154,6 +1952,129 @@ impl ExecutionEngine {
             });
         }
 
+        // Both legs trade one size, valid on each platform: a hedge with
+        // uneven legs would leave a naked position
+        let (yes_leg, no_leg) = arb_legs(req.arb_type);
+        let mut lots = Vec::with_capacity(2);
+        for leg in [yes_leg, no_leg] {
+            let lot = self.lots.lot_size(leg.platform);
+            let minimum = match leg.platform {
+                Platform::Kalshi => lot,
+                Platform::Polymarket => pair.poly_constraints.min_size.ceil() as i64,
+            };
+            lots.push((lot, minimum));
+        }
+        match hedge_size(max_contracts, &lots) {
+            Some(size) if size < max_contracts => {
+                info!("[EXEC] 📏 Size aligned to lots: {} | {}x → {}x (lot, minimum per leg: {:?})",
+                      pair.description, max_contracts, size, lots);
+                max_contracts = size;
+            }
+            Some(_) => {}
+            None => {
+                info!("[EXEC] No common hedge size: {} | {:?} {}x (lot, minimum per leg: {:?})",
+                      pair.description, req.arb_type, max_contracts, lots);
+                self.release_in_flight_delayed(market_id);
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("No common hedge size"),
+                });
+            }
+        }
+
+        // Price the Polymarket legs against current book depth
+        let mut pricing = match self.price_legs(&req, pair, max_contracts) {
+            Ok(pricing) => pricing,
+            Err(e) => {
+                warn!("[EXEC] Depth check failed: {} | {:?} {}x | {}",
+                      pair.description, req.arb_type, max_contracts, e);
+                self.release_in_flight(market_id);
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("Insufficient depth"),
+                });
+            }
+        };
+
+        // Polymarket limits on the market's tick
+        for (leg, limit) in [(yes_leg, &mut pricing.yes_limit), (no_leg, &mut pricing.no_limit)] {
+            if leg.platform != Platform::Polymarket {
+                continue;
+            }
+            match fit_poly_order(pair.poly_constraints, true, *limit, max_contracts) {
+                Ok(price) => *limit = price,
+                Err(reason) => {
+                    info!("[EXEC] {}: {} | {:?} {} {}x at {}¢",
+                          reason, pair.description, req.arb_type, leg, max_contracts, limit);
+                    self.release_in_flight_delayed(market_id);
+                    return Ok(ExecutionResult {
+                        market_id,
+                        success: false,
+                        profit_cents: 0,
+                        latency_ns: self.clock.now_ns() - req.detected_ns,
+                        error: Some(reason),
+                    });
+                }
+            }
+        }
+
+        let vwap_profit = pricing.profit_per_contract(req.arb_type, max_contracts, liquidity, &self.fees);
+        if vwap_profit < self.fees.min_profit_cents {
+            info!("[EXEC] Slippage kills edge: {} | {:?} quoted={}¢ vwap={:.1}¢ | {}x",
+                  pair.description, req.arb_type, profit_cents, vwap_profit, max_contracts);
+            self.release_in_flight(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Profit below threshold after slippage"),
+            });
+        }
+
+        if let Some(skipped) = self.check_probation(&req, pair, vwap_profit, max_contracts) {
+            self.release_in_flight_delayed(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some(skipped),
+            });
+        }
+
+        // Hard loss limits: daily halt, then the trade's own worst case
+        if let Some(resume) = self.risk.halted_until(chrono::Utc::now()) {
+            debug!("[EXEC] Daily loss limit: {} skipped until {}", pair.description, resume);
+            self.release_in_flight_delayed(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Daily loss limit"),
+            });
+        }
+        let worst_case = worst_case_loss_cents(req.arb_type, max_contracts, pricing.yes_limit, pricing.no_limit);
+        if !self.risk.trade_allowed(worst_case) {
+            info!("[EXEC] Trade risk limit: {} | {:?} {}x could lose {}¢",
+                  pair.description, req.arb_type, max_contracts, worst_case);
+            self.release_in_flight_delayed(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Trade risk limit"),
+            });
+        }
+
         // Circuit breaker check
         if let Err(_reason) = self.circuit_breaker.can_execute(&pair.pair_id, max_contracts).await {
             self.release_in_flight(market_id);
@@ -166,114 +2087,266 @@ impl ExecutionEngine {
             });
         }
 
+        if self.snapshot_stale(&req, pair) {
+            self.release_in_flight(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Stale snapshot"),
+            });
+        }
+
         let latency_to_exec = self.clock.now_ns() - req.detected_ns;
         info!(
-            "[EXEC] 🎯 {} | {:?} y={}¢ n={}¢ | profit={}¢ | {}x | {}µs",
-            pair.description,
+            market = %pair.description,
+            profit_cents = (vwap_profit * max_contracts as f64).round() as i64,
+            latency_us = latency_to_exec / 1000,
+            "[EXEC] 🎯 {:?} y={:.1}¢ n={:.1}¢ | profit={:.1}¢/contract | {}x {}",
             req.arb_type,
-            req.yes_price,
-            req.no_price,
-            profit_cents,
+            pricing.yes_avg,
+            pricing.no_avg,
+            vwap_profit,
             max_contracts,
-            latency_to_exec / 1000
+            req.order_type.unwrap_or(self.order_type),
         );
 
-        if self.dry_run {
-            info!("[EXEC] 🏃 DRY RUN - would execute {} contracts", max_contracts);
+        if self.mode == ExecutionMode::DryRun {
+            if self.opportunity_vanished(&req, pair, liquidity) {
+                self.release_in_flight(market_id);
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("Opportunity vanished"),
+                });
+            }
+            // Assume both legs fill in full at the book VWAP
+            let cost_cents = (max_contracts as f64 * (100.0 - vwap_profit)).round() as i64;
+            let expected_profit = (vwap_profit * max_contracts as f64).round().min(i16::MAX as f64) as i16;
+            info!(market = %pair.description, profit_cents = expected_profit,
+                  "[EXEC] 🏃 DRY RUN - would execute {} contracts | cost={}¢", max_contracts, cost_cents);
             self.release_in_flight_delayed(market_id);
+            self.report_trade(pair, req.arb_type, max_contracts, requested_contracts, max_contracts, limit_hit, expected_profit, true, latency_to_exec, true, 0, 0, Vec::new());
             return Ok(ExecutionResult {
                 market_id,
                 success: true,
-                profit_cents,
+                profit_cents: expected_profit,
                 latency_ns: latency_to_exec,
                 error: Some("DRY_RUN"),
             });
         }
 
+        let (yes_leg, no_leg) = arb_legs(req.arb_type);
+        if let Some((platform, skew_ms)) = self.clock_skew.blocked([yes_leg.platform, no_leg.platform]) {
+            warn!("[EXEC] 🕐 {} clock skew {:+}ms: {} not sent", platform, skew_ms, pair.description);
+            self.release_in_flight_delayed(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Clock skew"),
+            });
+        }
+        if let Some(platform) = self.platform_down([yes_leg.platform, no_leg.platform]) {
+            warn!("[EXEC] 🚧 {} down: {} not sent", platform, pair.description);
+            self.release_in_flight_delayed(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Platform down"),
+            });
+        }
+
+        // Both accounts must cover their legs at the limit prices, net of
+        // what executions running alongside hold
+        let funds = match self.hold_funds(req.arb_type, max_contracts, pricing.yes_limit, pricing.no_limit).await {
+            Ok(funds) => funds,
+            Err((platform, required, available)) => {
+                let expected_profit = (vwap_profit * max_contracts as f64).round() as i64;
+                warn!("[EXEC] 💸 Insufficient {} balance: {} | {:?} {}x needs {}¢, {}¢ available (missed {}¢ profit)",
+                      platform, pair.description, req.arb_type, max_contracts, required, available, expected_profit);
+                self.release_in_flight_delayed(market_id);
+                if self.should_alert_balance(platform) {
+                    let _ = self.reports.send(ExecutionReport::Underfunded(UnderfundedReport {
+                        market: pair.description.to_string(),
+                        platform,
+                        required_cents: required,
+                        available_cents: available,
+                        expected_profit_cents: expected_profit,
+                    }));
+                }
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("Insufficient balance"),
+                });
+            }
+        };
+
+        let notional = max_contracts as f64 * (pricing.yes_avg + pricing.no_avg) / 100.0;
+        if self.approval.requires(notional) {
+            // The market stays in flight while waiting; the legs' limit prices
+            // still cap what is paid if the book moved in the meantime
+            let expected_profit = (vwap_profit * max_contracts as f64).round() as i64;
+            let decision = self.await_approval(&pair.description, req.arb_type, max_contracts, notional, expected_profit).await;
+            if decision != ApprovalDecision::Approved {
+                info!("[EXEC] 🙅 Trade skipped ({:?}): {} | {:?} {}x ${:.2}",
+                      decision, pair.description, req.arb_type, max_contracts, notional);
+                self.release_in_flight_delayed(market_id);
+                let _ = self.reports.send(ExecutionReport::Declined(DeclinedReport {
+                    market: pair.description.to_string(),
+                    arb_type: req.arb_type,
+                    contracts: max_contracts,
+                    notional,
+                    decision,
+                }));
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some(match decision {
+                        ApprovalDecision::Rejected => "Approval rejected",
+                        ApprovalDecision::TimedOut => "Approval timed out",
+                        _ => "Approval unavailable",
+                    }),
+                });
+            }
+            info!("[EXEC] 👍 Trade approved: {} | {}x ${:.2}", pair.description, max_contracts, notional);
+            // Waiting for the approval may well have outlived the prices
+            if self.snapshot_stale(&req, pair) {
+                self.release_in_flight(market_id);
+                return Ok(ExecutionResult {
+                    market_id,
+                    success: false,
+                    profit_cents: 0,
+                    latency_ns: self.clock.now_ns() - req.detected_ns,
+                    error: Some("Stale snapshot"),
+                });
+            }
+        }
+
+        // Last look at the current quotes: balance checks and approvals take time
+        if self.opportunity_vanished(&req, pair, liquidity) {
+            self.release_in_flight(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Opportunity vanished"),
+            });
+        }
+
+        // Polymarket legs are limited at the deepest level needed to fill
+        let req = FastExecutionRequest {
+            yes_price: pricing.yes_limit,
+            no_price: pricing.no_limit,
+            ..req
+        };
+
         // Execute both legs concurrently 
         let result = self.execute_both_legs_async(&req, pair, max_contracts).await;
-
-        // Release in-flight after delay
-        self.release_in_flight_delayed(market_id);
+        for (platform, _) in required_funds(req.arb_type, max_contracts, req.yes_price, req.no_price) {
+            self.balances.invalidate(platform);
+        }
+        // The balances fetched from now on reflect the orders
+        drop(funds);
 
         match result {
             // Note: For same-platform arbs (PolyOnly/KalshiOnly), these are YES/NO fills, not platform fills
-            Ok((yes_filled, no_filled, yes_cost, no_cost, yes_order_id, no_order_id)) => {
-                let matched = yes_filled.min(no_filled);
-                let success = matched > 0;
-                let actual_profit = matched as i16 * 100 - (yes_cost + no_cost) as i16;
-
-                // === Automatic exposure management for mismatched fills ===
-                // If one leg fills more than the other, automatically close the excess
-                // to maintain market-neutral exposure (non-blocking background task)
-                if yes_filled != no_filled && (yes_filled > 0 || no_filled > 0) {
-                    let excess = (yes_filled - no_filled).abs();
-                    let (leg1_name, leg2_name) = match req.arb_type {
-                        ArbType::PolyYesKalshiNo => ("P_yes", "K_no"),
-                        ArbType::KalshiYesPolyNo => ("K_yes", "P_no"),
-                        ArbType::PolyOnly => ("P_yes", "P_no"),
-                        ArbType::KalshiOnly => ("K_yes", "K_no"),
-                    };
-                    warn!("[EXEC] ⚠️ Fill mismatch: {}={} {}={} (excess={})",
-                        leg1_name, yes_filled, leg2_name, no_filled, excess);
-
-                    // Spawn auto-close in background (don't block hot path with 2s sleep)
-                    let kalshi = self.kalshi.clone();
-                    let poly_async = self.poly_async.clone();
-                    let arb_type = req.arb_type;
-                    let yes_price = req.yes_price;
-                    let no_price = req.no_price;
-                    let poly_yes_token = pair.poly_yes_token.clone();
-                    let poly_no_token = pair.poly_no_token.clone();
-                    let kalshi_ticker = pair.kalshi_market_ticker.clone();
-                    let original_cost_per_contract = if yes_filled > no_filled {
-                        if yes_filled > 0 { yes_cost / yes_filled } else { 0 }
-                    } else {
-                        if no_filled > 0 { no_cost / no_filled } else { 0 }
-                    };
+            Ok((mut yes, mut no)) => {
+                let (yes_leg, no_leg) = arb_legs(req.arb_type);
+                let unconfirmed = self.await_cancels(pair, [(yes_leg, req.yes_price, &mut yes), (no_leg, req.no_price, &mut no)]).await;
+                let reported = [yes.clone(), no.clone()];
 
-                    tokio::spawn(async move {
-                        Self::auto_close_background(
-                            kalshi, poly_async, arb_type, yes_filled, no_filled,
-                            yes_price, no_price, poly_yes_token, poly_no_token,
-                            kalshi_ticker, original_cost_per_contract
-                        ).await;
-                    });
-                }
+                // One leg filled more than the other: complete the short leg
+                // or sell the excess, so no directional exposure is left
+                let unwind = if yes.filled != no.filled {
+                    self.balance_legs(&req, pair, &mut yes, &mut no).await
+                } else {
+                    Unwind::default()
+                };
+                self.reconcile_fills(&req, pair, reported, &mut yes, &mut no).await;
+
+                let matched = yes.filled.min(no.filled);
+                let success = matched > 0;
+                let actual_profit = (hedged_profit_cents(yes.filled, yes.cost, no.filled, no.cost) + unwind.pnl_cents() - yes.fees - no.fees)
+                    .clamp(i16::MIN as i64, i16::MAX as i64) as i16;
 
                 if success {
                     self.circuit_breaker.record_success(&pair.pair_id, matched, matched, actual_profit as f64 / 100.0).await;
+                } else if actual_profit != 0 {
+                    // Nothing hedged but the unwind realized a loss
+                    self.circuit_breaker.record_pnl(actual_profit as f64 / 100.0);
                 }
 
-                if matched > 0 {
-                    let (platform1, side1, platform2, side2) = match req.arb_type {
-                        ArbType::PolyYesKalshiNo => ("polymarket", "yes", "kalshi", "no"),
-                        ArbType::KalshiYesPolyNo => ("kalshi", "yes", "polymarket", "no"),
-                        ArbType::PolyOnly => ("polymarket", "yes", "polymarket", "no"),
-                        ArbType::KalshiOnly => ("kalshi", "yes", "kalshi", "no"),
-                    };
+                for (leg, fill) in [(yes_leg, &yes), (no_leg, &no)] {
+                    // Unhedged contracts the unwind couldn't sell stay on the position
+                    if fill.filled > 0 {
+                        self.position_channel.record_fill(FillRecord::new(
+                            &pair.pair_id, &pair.description, leg.platform_name(), leg.side,
+                            fill.filled as f64, fill.cost as f64 / 100.0 / fill.filled as f64,
+                            fill.fees as f64 / 100.0, &fill.order.order_id,
+                        ).with_client_order_id(&fill.order.client_order_id));
+                    }
+                }
 
-                    self.position_channel.record_fill(FillRecord::new(
-                        &pair.pair_id, &pair.description, platform1, side1,
-                        matched as f64, yes_cost as f64 / 100.0 / yes_filled.max(1) as f64,
-                        0.0, &yes_order_id,
-                    ));
-                    self.position_channel.record_fill(FillRecord::new(
-                        &pair.pair_id, &pair.description, platform2, side2,
-                        matched as f64, no_cost as f64 / 100.0 / no_filled.max(1) as f64,
-                        0.0, &no_order_id,
-                    ));
+                let cancelled_orders = [&yes.order, &no.order].iter()
+                    .filter(|order| order.outcome != OrderOutcome::Completed)
+                    .count() as u32;
+
+                let trade_fills = [(yes_leg, &yes), (no_leg, &no)].into_iter()
+                    .filter(|(_, fill)| fill.filled > 0)
+                    .map(|(leg, fill)| TradeFill {
+                        platform: leg.platform_name(),
+                        side: leg.side.to_string(),
+                        contracts: fill.filled,
+                        cost_cents: fill.cost,
+                        order_id: fill.order.order_id.clone(),
+                    })
+                    .collect();
+
+                let latency_ns = self.clock.now_ns() - req.detected_ns;
+                self.report_trade(pair, req.arb_type, matched, requested_contracts, max_contracts, limit_hit, actual_profit, success, latency_ns, false, unwind.contracts, cancelled_orders, trade_fills);
+
+                // Expired orders leave the opportunity open, but only once the
+                // exchange has confirmed their cancels
+                let cancel_unconfirmed = !unconfirmed.is_empty();
+                if cancel_unconfirmed {
+                    self.release_in_flight_delayed(market_id);
+                } else if cancelled_orders > 0 {
+                    self.release_in_flight(market_id);
+                } else {
+                    self.release_in_flight_delayed(market_id);
                 }
 
                 Ok(ExecutionResult {
                     market_id,
                     success,
                     profit_cents: actual_profit,
-                    latency_ns: self.clock.now_ns() - req.detected_ns,
-                    error: if success { None } else { Some("Partial/no fill") },
+                    latency_ns,
+                    error: if success {
+                        None
+                    } else if cancel_unconfirmed {
+                        Some("Cancel unconfirmed")
+                    } else if cancelled_orders > 0 {
+                        Some("Orders expired")
+                    } else {
+                        Some("Partial/no fill")
+                    },
                 })
             }
             Err(_e) => {
+                self.release_in_flight_delayed(market_id);
                 self.circuit_breaker.record_error().await;
                 Ok(ExecutionResult {
                     market_id,
@@ -286,318 +2359,597 @@ impl ExecutionEngine {
         }
     }
 
+    /// Send an approval request and wait for the answer. The approver enforces
+    /// the timeout; the extra grace here only covers an approver that stalls.
+    async fn await_approval(&self, market: &str, arb_type: ArbType, contracts: i64, notional: f64, expected_profit_cents: i64) -> ApprovalDecision {
+        let Some(approvals) = &self.approvals else {
+            warn!("[EXEC] ${:.2} trade needs approval but no approver is configured", notional);
+            return ApprovalDecision::Unavailable;
+        };
+        let timeout = Duration::from_secs(self.approval.timeout_secs);
+        let (reply, decision) = oneshot::channel();
+        let request = ApprovalRequest {
+            id: self.next_approval_id.fetch_add(1, Ordering::Relaxed),
+            market: market.to_string(),
+            arb_type,
+            contracts,
+            notional,
+            expected_profit_cents,
+            timeout,
+            reply,
+        };
+        info!("[EXEC] 🙋 Waiting up to {}s for approval of {} | {}x ${:.2}", timeout.as_secs(), market, contracts, notional);
+        if approvals.send(request).await.is_err() {
+            return ApprovalDecision::Unavailable;
+        }
+        match tokio::time::timeout(timeout + Duration::from_secs(5), decision).await {
+            Ok(Ok(decision)) => decision,
+            Ok(Err(_)) => ApprovalDecision::Unavailable,
+            Err(_) => ApprovalDecision::TimedOut,
+        }
+    }
+
+    /// Local book of a leg and its key: the token's on Polymarket, the
+    /// market side's on Kalshi while its feed has one
+    fn leg_book(&self, pair: &MarketPair, leg: ArbLeg) -> Option<(&OrderBookStore, u64)> {
+        match leg.platform {
+            Platform::Polymarket => {
+                let token = if leg.side == "yes" { &pair.poly_yes_token } else { &pair.poly_no_token };
+                Some((&self.poly_books, fxhash_str(token)))
+            }
+            Platform::Kalshi => {
+                let books = self.kalshi_books.as_deref()?;
+                let hash = kalshi::book_hash(&pair.kalshi_market_ticker, leg.side);
+                books.best_ask(hash).is_some().then_some((books, hash))
+            }
+        }
+    }
+
+    /// Best bid on `leg`'s local book (cents)
+    fn leg_bid(&self, pair: &MarketPair, leg: ArbLeg) -> Option<PriceCents> {
+        let (books, hash) = self.leg_book(pair, leg)?;
+        books.best_bid(hash).and_then(|(bps, _)| Price::bid_from_bps(bps)).map(Price::cents)
+    }
+
+    /// Whole contracts fillable on every leg with a local book without its
+    /// average price exceeding the quoted price by more than
+    /// `max_slippage_cents`. Legs without a book are unconstrained.
+    fn max_fillable_contracts(&self, req: &FastExecutionRequest, pair: &MarketPair) -> i64 {
+        let (yes_leg, no_leg) = arb_legs(req.arb_type);
+        [(yes_leg, req.yes_price), (no_leg, req.no_price)].into_iter()
+            .filter_map(|(leg, quoted)| {
+                let (books, hash) = self.leg_book(pair, leg)?;
+                let max_avg_bps = (quoted as f64 + self.max_slippage_cents) * 100.0;
+                // Small tolerance so levels exactly at the limit are not lost to float error
+                Some((books.max_fill_within(hash, BookSide::Ask, max_avg_bps) + 1e-9).floor() as i64)
+            })
+            .min()
+            .unwrap_or(i64::MAX)
+    }
+
+    /// Expected fill prices for `contracts` on each leg, by walking its local
+    /// order book. Legs without a book use the quoted price. Post-only legs
+    /// rest inside the spread instead (see `maker_price`).
+    fn price_legs(&self, req: &FastExecutionRequest, pair: &MarketPair, contracts: i64) -> Result<LegPricing> {
+        let mut pricing = LegPricing {
+            yes_avg: req.yes_price as f64,
+            no_avg: req.no_price as f64,
+            yes_limit: req.yes_price,
+            no_limit: req.no_price,
+        };
+
+        let (yes_leg, no_leg) = arb_legs(req.arb_type);
+        for (leg, avg, limit) in [
+            (yes_leg, &mut pricing.yes_avg, &mut pricing.yes_limit),
+            (no_leg, &mut pricing.no_avg, &mut pricing.no_limit),
+        ] {
+            if req.order_type.unwrap_or(self.order_type) == OrderType::PostOnly {
+                let tick = match leg.platform {
+                    Platform::Kalshi => 1,
+                    Platform::Polymarket => pair.poly_constraints.tick_bps.div_ceil(100) as PriceCents,
+                };
+                *limit = maker_price(self.leg_bid(pair, leg), *limit, tick);
+                *avg = *limit as f64;
+                continue;
+            }
+            let Some((books, hash)) = self.leg_book(pair, leg) else { continue };
+            let fill = books.vwap(hash, BookSide::Ask, contracts as f64)?;
+            *avg = fill.avg_price_bps / 100.0;
+            *limit = fill.worst_price_bps.div_ceil(100) as PriceCents;
+        }
+
+        Ok(pricing)
+    }
+
     async fn execute_both_legs_async(
         &self,
         req: &FastExecutionRequest,
         pair: &MarketPair,
         contracts: i64,
-    ) -> Result<(i64, i64, i64, i64, String, String)> {
-        match req.arb_type {
-            // === CROSS-PLATFORM: Poly YES + Kalshi NO ===
-            ArbType::PolyYesKalshiNo => {
-                let kalshi = self.kalshi.as_ref()
-                    .ok_or_else(|| anyhow!("Kalshi not configured - cannot execute cross-platform arb"))?;
-                let kalshi_fut = kalshi.buy_ioc(
-                    &pair.kalshi_market_ticker,
-                    "no",
-                    req.no_price as i64,
-                    contracts,
-                );
-                let poly_fut = self.poly_async.buy_fak(
-                    &pair.poly_yes_token,
-                    cents_to_price(req.yes_price),
-                    contracts as f64,
-                );
-                let (kalshi_res, poly_res) = tokio::join!(kalshi_fut, poly_fut);
-                self.extract_cross_results(kalshi_res, poly_res)
-            }
-
-            // === CROSS-PLATFORM: Kalshi YES + Poly NO ===
-            ArbType::KalshiYesPolyNo => {
-                let kalshi = self.kalshi.as_ref()
-                    .ok_or_else(|| anyhow!("Kalshi not configured - cannot execute cross-platform arb"))?;
-                let kalshi_fut = kalshi.buy_ioc(
-                    &pair.kalshi_market_ticker,
-                    "yes",
-                    req.yes_price as i64,
-                    contracts,
-                );
-                let poly_fut = self.poly_async.buy_fak(
-                    &pair.poly_no_token,
-                    cents_to_price(req.no_price),
-                    contracts as f64,
-                );
-                let (kalshi_res, poly_res) = tokio::join!(kalshi_fut, poly_fut);
-                self.extract_cross_results(kalshi_res, poly_res)
-            }
+    ) -> Result<(LegFill, LegFill)> {
+        // Legs that fill short of `contracts` (IOC remainder cancelled, resting
+        // order cancelled at its TTL) are evened out by `balance_legs`
+        let order_type = req.order_type.unwrap_or(self.order_type);
+        let (yes_leg, no_leg) = arb_legs(req.arb_type);
+        // Never send one leg of an arb whose other leg can't go out
+        if let Some(leg) = [yes_leg, no_leg].into_iter().find(|leg| !self.orders.configured(leg.platform)) {
+            return Err(Error::Invalid(format!("{} not configured - cannot execute {:?} arb", leg.platform, req.arb_type)));
+        }
+        let yes = self.leg_order(pair, yes_leg, true, leg_price(req.yes_price)?, contracts, order_type);
+        let no = self.leg_order(pair, no_leg, true, leg_price(req.no_price)?, contracts, order_type);
+        let (yes_res, no_res) = tokio::join!(self.orders.submit(yes), self.orders.submit(no));
+        self.on_leg_errors(req.market_id, [yes_res.as_ref().err(), no_res.as_ref().err()]).await;
 
-            // === SAME-PLATFORM: Poly YES + Poly NO ===
-            ArbType::PolyOnly => {
-                let yes_fut = self.poly_async.buy_fak(
-                    &pair.poly_yes_token,
-                    cents_to_price(req.yes_price),
-                    contracts as f64,
-                );
-                let no_fut = self.poly_async.buy_fak(
-                    &pair.poly_no_token,
-                    cents_to_price(req.no_price),
-                    contracts as f64,
-                );
-                let (yes_res, no_res) = tokio::join!(yes_fut, no_fut);
-                self.extract_poly_only_results(yes_res, no_res)
-            }
-
-            // === SAME-PLATFORM: Kalshi YES + Kalshi NO ===
-            ArbType::KalshiOnly => {
-                let kalshi = self.kalshi.as_ref()
-                    .ok_or_else(|| anyhow!("Kalshi not configured - cannot execute Kalshi-only arb"))?;
-                let yes_fut = kalshi.buy_ioc(
-                    &pair.kalshi_market_ticker,
-                    "yes",
-                    req.yes_price as i64,
-                    contracts,
-                );
-                let no_fut = kalshi.buy_ioc(
-                    &pair.kalshi_market_ticker,
-                    "no",
-                    req.no_price as i64,
-                    contracts,
-                );
-                let (yes_res, no_res) = tokio::join!(yes_fut, no_fut);
-                self.extract_kalshi_only_results(yes_res, no_res)
-            }
+        let [yes, no] = [(yes_leg, yes_res), (no_leg, no_res)]
+            .map(|(leg, res)| match res {
+                Ok(fill) => LegFill::from(fill),
+                Err(e) => {
+                    warn!("[EXEC] {} failed: {}", leg, e);
+                    LegFill::default()
+                }
+            });
+        Ok((yes, no))
+    }
+
+    /// The order for `leg` of `pair`: its Kalshi ticker or Polymarket token
+    fn leg_order<'a>(&self, pair: &'a MarketPair, leg: ArbLeg, buy: bool, price: Price, contracts: i64, order_type: OrderType) -> LegOrder<'a> {
+        let market = match (leg.platform, leg.side) {
+            (Platform::Kalshi, _) => &*pair.kalshi_market_ticker,
+            (Platform::Polymarket, "yes") => &*pair.poly_yes_token,
+            (Platform::Polymarket, _) => &*pair.poly_no_token,
+        };
+        LegOrder { platform: leg.platform, market, side: leg.side, buy, price, contracts, order_type }
+    }
+
+    /// Whether the prices behind `req` are older than `MAX_SNAPSHOT_AGE_MS`
+    /// by now, in which case its edge has most likely gone
+    fn snapshot_stale(&self, req: &FastExecutionRequest, pair: &MarketPair) -> bool {
+        let Some(max_age) = self.max_snapshot_age_ms else { return false };
+        let Some(age) = req.snapshot_age_ms(unix_ms()).filter(|&age| age > max_age) else { return false };
+        let dropped = self.stale_dropped.fetch_add(1, Ordering::Relaxed) + 1;
+        debug!("[EXEC] Stale snapshot: {} | {:?} prices {}ms old (max {}ms) | {} opportunities dropped as stale",
+               pair.description, req.arb_type, age, max_age, dropped);
+        true
+    }
+
+    /// Whether the edge of `req` is gone at the market's current quotes
+    /// (below the minimum profit, or a leg no longer quoted). The book can
+    /// move between detection and the orders going out.
+    fn opportunity_vanished(&self, req: &FastExecutionRequest, pair: &MarketPair, liquidity: Liquidity) -> bool {
+        // TEST_ARB prices are synthetic: the market state never quoted them
+        if !self.revalidate || self.test_mode {
+            return false;
         }
+        let fresh = self.requote(req.market_id, req.arb_type);
+        let edge = fresh.map(|fresh| fresh.net_profit_cents_as(&self.fees, liquidity));
+        if edge.is_some_and(|edge| edge >= self.fees.min_profit_cents) {
+            return false;
+        }
+        let vanished = self.vanished.fetch_add(1, Ordering::Relaxed) + 1;
+        info!("[EXEC] 💨 Opportunity vanished: {} | {:?} {:.1}¢/contract at detection, {} now | {} vanished so far",
+              pair.description, req.arb_type, req.net_profit_cents_as(&self.fees, liquidity),
+              edge.map_or("no quote".to_string(), |edge| format!("{:.1}¢", edge)), vanished);
+        true
     }
 
-    /// Extract results from cross-platform execution
-    fn extract_cross_results(
-        &self,
-        kalshi_res: Result<crate::kalshi::KalshiOrderResponse>,
-        poly_res: Result<crate::polymarket_clob::PolyFillAsync>,
-    ) -> Result<(i64, i64, i64, i64, String, String)> {
-        let (kalshi_filled, kalshi_cost, kalshi_order_id) = match kalshi_res {
-            Ok(resp) => {
-                let filled = resp.order.filled_count();
-                let cost = resp.order.taker_fill_cost.unwrap_or(0) + resp.order.maker_fill_cost.unwrap_or(0);
-                (filled, cost, resp.order.order_id)
+    /// Why a trade on a pair still on (or failed) probation is not sent
+    fn check_probation(&self, req: &FastExecutionRequest, pair: &MarketPair, edge_cents: f64, contracts: i64) -> Option<&'static str> {
+        let shadow = self.shadow.as_ref()?;
+        let (graduated, record) = match shadow.evaluate(&pair.pair_id, edge_cents, contracts, unix_ms()) {
+            ShadowDecision::Trade => return None,
+            ShadowDecision::Blocked => return Some("Rejected pair"),
+            ShadowDecision::Shadow(record) => {
+                info!(market = %pair.description, profit_cents = (edge_cents * contracts as f64).round() as i64,
+                      "[SHADOW] 👻 Would trade {:?} {}x at {:.1}¢/contract | observation {}/{}, shadow P&L {:.0}¢",
+                      req.arb_type, contracts, edge_cents, record.observations, shadow.observations_required(), record.pnl_cents);
+                return Some("Shadow");
             }
-            Err(e) => {
-                warn!("[EXEC] Kalshi failed: {}", e);
-                (0, 0, String::new())
+            ShadowDecision::Graduated(record) => {
+                info!("[SHADOW] ✅ {} goes live after {} observations (mean edge {:.1}¢, shadow P&L {:.0}¢)",
+                      pair.description, record.observations, record.mean_edge_cents(), record.pnl_cents);
+                (true, record)
+            }
+            ShadowDecision::Rejected(record) => {
+                error!("[SHADOW] ❌ {} rejected: mean edge {:.1}¢ over {} observations looks like mismatched markets",
+                       pair.description, record.mean_edge_cents(), record.observations);
+                (false, record)
             }
         };
+        let _ = self.reports.send(ExecutionReport::Probation(ProbationReport {
+            market: pair.description.to_string(),
+            graduated,
+            record,
+        }));
+        (!graduated).then_some("Rejected pair")
+    }
 
-        let (poly_filled, poly_cost, poly_order_id) = match poly_res {
-            Ok(fill) => {
-                ((fill.filled_size as i64), (fill.fill_cost * 100.0) as i64, fill.order_id)
+    /// React to failed legs by error kind: rejected credentials halt trading
+    /// at once, a closed market stops being quoted, and exchange or transport
+    /// failures count towards the consecutive-error limit.
+    async fn on_leg_errors(&self, market_id: u16, errors: [Option<&Error>; 2]) {
+        for err in errors.into_iter().flatten() {
+            match err.root() {
+                Error::Auth(msg) => self.circuit_breaker.trip(TripReason::AuthFailed(msg.clone())).await,
+                Error::MarketClosed(_) => {
+                    warn!("[EXEC] Market {} closed, no longer quoting", market_id);
+                    crate::polymarket::halt_market(&self.state, &self.poly_books, market_id);
+                }
+                Error::RateLimited(_) | Error::Network(_) | Error::Api { .. } => self.circuit_breaker.record_error().await,
+                _ => {}
             }
-            Err(e) => {
-                warn!("[EXEC] Poly failed: {}", e);
-                (0, 0, String::new())
+        }
+    }
+
+    /// Correct each leg by the difference between what its order response
+    /// said (`reported`) and the fill the exchange reports, fees included,
+    /// so the P&L booked is the one on the account statements. Runs once the
+    /// legs are balanced, keeping the lookups off the unwind's path. A leg
+    /// whose details can't be fetched keeps the response's numbers.
+    async fn reconcile_fills(&self, req: &FastExecutionRequest, pair: &MarketPair, reported: [LegFill; 2], yes: &mut LegFill, no: &mut LegFill) {
+        let (yes_leg, no_leg) = arb_legs(req.arb_type);
+        let fetch = |leg: ArbLeg, fill: &LegFill| {
+            let order_id = fill.order.order_id.clone();
+            let wanted = fill.filled > 0 && !order_id.is_empty();
+            async move {
+                if !wanted {
+                    return None;
+                }
+                match self.orders.fill_details(leg.platform, &order_id).await {
+                    Ok(details) => Some(details),
+                    Err(e) => {
+                        warn!("[EXEC] Fill details of {} order {} unavailable, booking the order response: {}", leg, order_id, e);
+                        None
+                    }
+                }
             }
         };
+        let [yes_reported, no_reported] = &reported;
+        let (yes_details, no_details) = tokio::join!(fetch(yes_leg, yes_reported), fetch(no_leg, no_reported));
 
-        Ok((kalshi_filled, poly_filled, kalshi_cost, poly_cost, kalshi_order_id, poly_order_id))
+        let expected = hedged_profit_cents(yes.filled, yes.cost, no.filled, no.cost) - yes.fees - no.fees;
+        let mut changes = Vec::new();
+        for (leg, fill, reported, details) in [(yes_leg, &mut *yes, yes_reported, yes_details), (no_leg, &mut *no, no_reported, no_details)] {
+            let Some(details) = details else { continue };
+            if (details.filled, details.cost_cents, details.fee_cents) == (reported.filled, reported.cost, reported.fees) {
+                continue;
+            }
+            changes.push(format!("{} {}x {}¢ + {}¢ fees → {}x {}¢ + {}¢ fees",
+                                 leg, reported.filled, reported.cost, reported.fees, details.filled, details.cost_cents, details.fee_cents));
+            // The legs may have been balanced since: only the difference applies
+            fill.filled += details.filled - reported.filled;
+            fill.cost += details.cost_cents - reported.cost;
+            fill.fees += details.fee_cents - reported.fees;
+        }
+        let realized = hedged_profit_cents(yes.filled, yes.cost, no.filled, no.cost) - yes.fees - no.fees;
+        if (realized - expected).abs() >= self.fill_discrepancy_cents {
+            warn!("[EXEC] 🧾 Fill discrepancy on {}: expected {}¢, realized {}¢ | {}",
+                  pair.description, expected, realized, changes.join(", "));
+        }
     }
 
-    /// Extract results from Poly-only execution (same-platform)
-    fn extract_poly_only_results(
-        &self,
-        yes_res: Result<crate::polymarket_clob::PolyFillAsync>,
-        no_res: Result<crate::polymarket_clob::PolyFillAsync>,
-    ) -> Result<(i64, i64, i64, i64, String, String)> {
-        let (yes_filled, yes_cost, yes_order_id) = match yes_res {
-            Ok(fill) => {
-                ((fill.filled_size as i64), (fill.fill_cost * 100.0) as i64, fill.order_id)
-            }
-            Err(e) => {
-                warn!("[EXEC] Poly YES failed: {}", e);
-                (0, 0, String::new())
-            }
+    /// Even out a partial fill. The short leg is topped up with a follow-up
+    /// order (if enabled and still profitable); whatever excess remains on the
+    /// long leg is sold back at a discount. Legs are updated in place to what
+    /// is still held; the returned `Unwind` is what was sold and for how much.
+    async fn balance_legs(&self, req: &FastExecutionRequest, pair: &MarketPair, yes: &mut LegFill, no: &mut LegFill) -> Unwind {
+        let (yes_leg, no_leg) = arb_legs(req.arb_type);
+        warn!("[EXEC] ⚠️ Fill mismatch: {}={} {}={} (excess={})",
+              yes_leg, yes.filled, no_leg, no.filled, (yes.filled - no.filled).abs());
+
+        let yes_short = yes.filled < no.filled;
+        let (short_leg, short_price, long_leg, long_price) = if yes_short {
+            (yes_leg, req.yes_price, no_leg, req.no_price)
+        } else {
+            (no_leg, req.no_price, yes_leg, req.yes_price)
         };
+        let (short, long) = if yes_short { (yes, no) } else { (no, yes) };
 
-        let (no_filled, no_cost, no_order_id) = match no_res {
-            Ok(fill) => {
-                ((fill.filled_size as i64), (fill.fill_cost * 100.0) as i64, fill.order_id)
-            }
-            Err(e) => {
-                warn!("[EXEC] Poly NO failed: {}", e);
-                (0, 0, String::new())
+        if self.partial_fills.complete {
+            let shortfall = long.filled - short.filled;
+            let limit = completion_limit(short_price, long.avg_cost(), self.partial_fills.max_chase_cents, short_leg.platform);
+            match limit {
+                Some(limit) => match self.send_leg(pair, short_leg, true, limit, shortfall).await {
+                    Ok(fill) => {
+                        info!("[EXEC] 🔁 Completed {} of {} {} contracts @{}¢", fill.filled, shortfall, short_leg, limit);
+                        short.filled += fill.filled;
+                        short.cost += fill.cost;
+                        short.fees += fill.fees;
+                    }
+                    Err(e) => warn!("[EXEC] ⚠️ Follow-up {} order failed: {}", short_leg, e),
+                },
+                None => info!("[EXEC] No profitable price left to complete {}", short_leg),
             }
-        };
+        }
 
-        // For same-platform, return YES as "kalshi" slot and NO as "poly" slot
-        // This keeps the existing result handling logic working
-        Ok((yes_filled, no_filled, yes_cost, no_cost, yes_order_id, no_order_id))
-    }
+        let excess = long.filled - short.filled;
+        if excess <= 0 {
+            return Unwind::default();
+        }
 
-    /// Extract results from Kalshi-only execution (same-platform)
-    fn extract_kalshi_only_results(
-        &self,
-        yes_res: Result<crate::kalshi::KalshiOrderResponse>,
-        no_res: Result<crate::kalshi::KalshiOrderResponse>,
-    ) -> Result<(i64, i64, i64, i64, String, String)> {
-        let (yes_filled, yes_cost, yes_order_id) = match yes_res {
-            Ok(resp) => {
-                let filled = resp.order.filled_count();
-                let cost = resp.order.taker_fill_cost.unwrap_or(0) + resp.order.maker_fill_cost.unwrap_or(0);
-                (filled, cost, resp.order.order_id)
-            }
+        if long_leg.platform == Platform::Polymarket {
+            // Freshly bought shares take a moment to settle before they can be sold
+            info!("[EXEC] 🔄 Waiting 2s for Poly settlement before unwinding {} {}", excess, long_leg);
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        let close_price = long_price.saturating_sub(self.partial_fills.unwind_discount_cents).max(1);
+        let sold = match self.send_leg(pair, long_leg, false, close_price, excess).await {
+            Ok(sale) => Some((sale.filled.min(excess), sale.cost, sale.fees)),
             Err(e) => {
-                warn!("[EXEC] Kalshi YES failed: {}", e);
-                (0, 0, String::new())
+                warn!("[EXEC] ⚠️ Failed to unwind {} excess: {}", long_leg, e);
+                None
             }
         };
+        let Some((sold, proceeds, fees)) = sold.filter(|&(sold, _, _)| sold > 0) else {
+            error!("[EXEC] ❌ {} {} contracts left unhedged", excess, long_leg);
+            return Unwind::default();
+        };
 
-        let (no_filled, no_cost, no_order_id) = match no_res {
-            Ok(resp) => {
-                let filled = resp.order.filled_count();
-                let cost = resp.order.taker_fill_cost.unwrap_or(0) + resp.order.maker_fill_cost.unwrap_or(0);
-                (filled, cost, resp.order.order_id)
-            }
-            Err(e) => {
-                warn!("[EXEC] Kalshi NO failed: {}", e);
-                (0, 0, String::new())
+        let cost = long.cost * sold / long.filled;
+        long.filled -= sold;
+        long.cost -= cost;
+        // Booked on the leg, like the fees of the order that bought them
+        long.fees += fees;
+        let unwind = Unwind { contracts: sold, cost, proceeds };
+        info!("[EXEC] ✅ Unwound {} {} contracts for {}¢ (P&L: {}¢)", sold, long_leg, proceeds, unwind.pnl_cents());
+        if sold < excess {
+            error!("[EXEC] ❌ {} {} contracts left unhedged", excess - sold, long_leg);
+        }
+        unwind
+    }
+
+    /// Send one IOC/FAK order on a leg; the fill's cost is the cents paid or received
+    async fn send_leg(&self, pair: &MarketPair, leg: ArbLeg, buy: bool, price: PriceCents, contracts: i64) -> Result<LegFill> {
+        if !self.orders.configured(leg.platform) {
+            return Err(Error::Invalid(format!("{} not configured", leg.platform)));
+        }
+        let price = match leg.platform {
+            Platform::Kalshi => price,
+            Platform::Polymarket => {
+                fit_poly_order(pair.poly_constraints, buy, price, contracts)
+                    .map_err(|reason| Error::Invalid(format!("{} {}: {}", pair.description, leg, reason)))?
             }
         };
-
-        // For same-platform, return YES as "kalshi" slot and NO as "poly" slot
-        Ok((yes_filled, no_filled, yes_cost, no_cost, yes_order_id, no_order_id))
+        let order = self.leg_order(pair, leg, buy, leg_price(price)?, contracts, OrderType::Ioc);
+        Ok(self.orders.submit(order).await?.into())
     }
 
-    /// Background task to automatically close excess exposure from mismatched fills
-    async fn auto_close_background(
-        kalshi: Option<Arc<KalshiApiClient>>,
-        poly_async: Arc<SharedAsyncClient>,
+    #[allow(clippy::too_many_arguments)]
+    fn report_trade(
+        &self,
+        pair: &MarketPair,
         arb_type: ArbType,
-        yes_filled: i64,
-        no_filled: i64,
-        yes_price: u16,
-        no_price: u16,
-        poly_yes_token: Arc<str>,
-        poly_no_token: Arc<str>,
-        kalshi_ticker: Arc<str>,
-        original_cost_per_contract: i64,
+        contracts: i64,
+        requested_contracts: i64,
+        hedge_contracts: i64,
+        limit_hit: Option<LimitHit>,
+        profit_cents: i16,
+        success: bool,
+        latency_ns: u64,
+        simulated: bool,
+        unwound_contracts: i64,
+        cancelled_orders: u32,
+        fills: Vec<TradeFill>,
     ) {
-        let excess = (yes_filled - no_filled).abs();
-        if excess == 0 {
+        let _ = self.reports.send(ExecutionReport::Trade(TradeReport {
+            market: pair.description.to_string(),
+            arb_type,
+            contracts,
+            requested_contracts,
+            hedge_contracts: Some(hedge_contracts),
+            limit_hit,
+            profit_cents,
+            success,
+            latency_ms: latency_ns / 1_000_000,
+            simulated,
+            unwound_contracts,
+            cancelled_orders,
+            fills,
+        }));
+    }
+
+    /// Publish a detection unless it repeats a recently reported opportunity
+    fn report_opportunity(&self, req: &FastExecutionRequest) {
+        let net_profit = req.net_profit_cents(&self.fees);
+        if net_profit < self.fees.min_profit_cents {
             return;
         }
+        let profit_cents = net_profit.floor() as i16;
 
-        // Helper to log P&L after close
-        let log_close_pnl = |platform: &str, closed: i64, proceeds: i64| {
-            if closed > 0 {
-                let close_pnl = proceeds - (original_cost_per_contract * excess);
-                info!("[EXEC] ✅ Closed {} {} contracts for {}¢ (P&L: {}¢)",
-                    closed, platform, proceeds, close_pnl);
-            } else {
-                warn!("[EXEC] ⚠️ Failed to close {} excess - 0 filled", platform);
-            }
-        };
-
-        match arb_type {
-            ArbType::PolyOnly => {
-                let (token, side, price) = if yes_filled > no_filled {
-                    (&poly_yes_token, "yes", yes_price)
-                } else {
-                    (&poly_no_token, "no", no_price)
-                };
-                let close_price = cents_to_price((price as i16).saturating_sub(10).max(1) as u16);
+        // Tokio clock so backtest replays (paused time) see the recorded spacing
+        let is_new = self.opportunities.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .observe(req.market_id, req.arb_type, profit_cents, tokio::time::Instant::now().into_std());
+        if !is_new {
+            return;
+        }
 
-                info!("[EXEC] 🔄 Waiting 2s for Poly settlement before auto-close ({} {} contracts)", excess, side);
-                tokio::time::sleep(Duration::from_secs(2)).await;
+        let Some(pair) = self.state.get_by_id(req.market_id).and_then(|m| m.pair.as_ref()) else { return };
+        let (Some(yes_price), Some(no_price)) = (Price::from_cents(req.yes_price), Price::from_cents(req.no_price)) else { return };
+        let _ = self.reports.send(ExecutionReport::Opportunity(OpportunityReport {
+            market: pair.description.to_string(),
+            arb_type: req.arb_type,
+            yes_price,
+            no_price,
+            profit_cents,
+            value: req.value(&self.fees, self.ranking.full_confidence_contracts),
+        }));
+    }
 
-                match poly_async.sell_fak(token, close_price, excess as f64).await {
-                    Ok(fill) => log_close_pnl("Poly", fill.filled_size as i64, (fill.fill_cost * 100.0) as i64),
-                    Err(e) => warn!("[EXEC] ⚠️ Failed to close Poly excess: {}", e),
-                }
-            }
+    /// Pair description for logs ("?" for unknown markets)
+    fn market_description(&self, market_id: u16) -> &str {
+        self.state.get_by_id(market_id)
+            .and_then(|m| m.pair.as_deref())
+            .map(|pair| &*pair.description)
+            .unwrap_or("?")
+    }
 
-            ArbType::KalshiOnly => {
-                if let Some(kalshi) = &kalshi {
-                    let (side, price) = if yes_filled > no_filled {
-                        ("yes", yes_price as i64)
-                    } else {
-                        ("no", no_price as i64)
-                    };
-                    let close_price = price.saturating_sub(10).max(1);
+    #[inline(always)]
+    fn release_in_flight(&self, market_id: u16) {
+        if market_id < 512 {
+            let slot = (market_id / 64) as usize;
+            let bit = market_id % 64;
+            let mask = !(1u64 << bit);
+            self.in_flight[slot].fetch_and(mask, Ordering::Release);
+        }
+    }
 
-                    match kalshi.sell_ioc(&kalshi_ticker, side, close_price, excess).await {
-                        Ok(resp) => {
-                            let proceeds = resp.order.taker_fill_cost.unwrap_or(0) + resp.order.maker_fill_cost.unwrap_or(0);
-                            log_close_pnl("Kalshi", resp.order.filled_count(), proceeds);
+    /// Wait for the exchanges to confirm the cancels of orders left resting,
+    /// re-checking every `CANCEL_RECHECK_SECS` at most `CANCEL_RECHECK_ATTEMPTS`
+    /// times. Until then their remainders may still fill: late fills are added
+    /// to their leg, so they are balanced and recorded with the rest of the
+    /// trade. Returns the orders given up on, which get a critical alert.
+    async fn await_cancels(&self, pair: &MarketPair, legs: [(ArbLeg, PriceCents, &mut LegFill); 2]) -> Vec<(Platform, String)> {
+        let mut unconfirmed = Vec::new();
+        for (leg, price, fill) in legs {
+            let mut checks = 0;
+            while fill.order.outcome == OrderOutcome::CancelUnconfirmed && checks < CANCEL_RECHECK_ATTEMPTS {
+                tokio::time::sleep(Duration::from_secs(CANCEL_RECHECK_SECS)).await;
+                checks += 1;
+                match self.orders.confirm_cancel(leg.platform, &fill.order.order_id).await {
+                    Ok(order) => {
+                        let late = order.filled - fill.filled;
+                        if late > 0 {
+                            warn!("[EXEC] {} order {} filled {} more {} after its late cancel",
+                                  leg.platform, fill.order.order_id, late, leg);
+                            fill.cost = if order.cost_cents > fill.cost { order.cost_cents } else { fill.cost + late * price as i64 };
+                            fill.fees = fill.fees.max(order.fee_cents);
+                            fill.filled = order.filled;
                         }
-                        Err(e) => warn!("[EXEC] ⚠️ Failed to close Kalshi excess: {}", e),
+                        fill.order.outcome = order.outcome;
                     }
-                } else {
-                    warn!("[EXEC] ⚠️ Cannot close Kalshi excess - Kalshi not configured");
+                    Err(e) => warn!("[EXEC] Cancel check of {} order {} failed: {}", leg.platform, fill.order.order_id, e),
                 }
             }
+            if fill.order.outcome == OrderOutcome::CancelUnconfirmed {
+                error!("[EXEC] ❌ {} order {} on {} still unconfirmed after {} cancel checks, may still fill",
+                       leg.platform, fill.order.order_id, pair.description, checks);
+                let _ = self.reports.send(ExecutionReport::CancelUnconfirmed(CancelUnconfirmedReport {
+                    market: pair.description.to_string(),
+                    platform: leg.platform,
+                    order_id: fill.order.order_id.clone(),
+                    checks,
+                }));
+                unconfirmed.push((leg.platform, fill.order.order_id.clone()));
+            }
+        }
+        unconfirmed
+    }
 
-            ArbType::PolyYesKalshiNo => {
-                if yes_filled > no_filled {
-                    // Poly YES excess
-                    let close_price = cents_to_price((yes_price as i16).saturating_sub(10).max(1) as u16);
-                    info!("[EXEC] 🔄 Waiting 2s for Poly settlement before auto-close ({} yes contracts)", excess);
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-
-                    match poly_async.sell_fak(&poly_yes_token, close_price, excess as f64).await {
-                        Ok(fill) => log_close_pnl("Poly", fill.filled_size as i64, (fill.fill_cost * 100.0) as i64),
-                        Err(e) => warn!("[EXEC] ⚠️ Failed to close Poly excess: {}", e),
-                    }
-                } else if let Some(kalshi) = &kalshi {
-                    // Kalshi NO excess
-                    let close_price = (no_price as i64).saturating_sub(10).max(1);
-                    match kalshi.sell_ioc(&kalshi_ticker, "no", close_price, excess).await {
-                        Ok(resp) => {
-                            let proceeds = resp.order.taker_fill_cost.unwrap_or(0) + resp.order.maker_fill_cost.unwrap_or(0);
-                            log_close_pnl("Kalshi", resp.order.filled_count(), proceeds);
-                        }
-                        Err(e) => warn!("[EXEC] ⚠️ Failed to close Kalshi excess: {}", e),
+    /// Size every opportunity of a batch with `allocate`, against the current
+    /// balances and each market's position limits, logging what each got
+    /// and why. Returns the requests left with contracts to trade.
+    async fn allocate_batch(&self, batch: Vec<FastExecutionRequest>) -> Vec<(FastExecutionRequest, i64)> {
+        let mut max_contracts = Vec::with_capacity(batch.len());
+        let mut platforms: Vec<Platform> = Vec::new();
+        {
+            let positions = self.positions.read().await;
+            for req in &batch {
+                let mut wanted = req.hedged_contracts();
+                if let Some(pair) = self.state.get_by_id(req.market_id).and_then(|m| m.pair.as_ref()) {
+                    let cost_per_contract = (req.yes_price + req.no_price) as f64 / 100.0;
+                    wanted = wanted.min(positions.can_open(&pair.pair_id, wanted as f64, cost_per_contract).contracts.floor() as i64);
+                }
+                max_contracts.push(wanted);
+                let (yes_leg, no_leg) = arb_legs(req.arb_type);
+                for platform in [yes_leg.platform, no_leg.platform] {
+                    if !platforms.contains(&platform) {
+                        platforms.push(platform);
                     }
-                } else {
-                    warn!("[EXEC] ⚠️ Cannot close Kalshi NO excess - Kalshi not configured");
                 }
             }
+        }
+        let mut balances = Vec::with_capacity(platforms.len());
+        for platform in platforms {
+            if let Some(cents) = self.balance(platform).await {
+                balances.push((platform, cents));
+            }
+        }
 
-            ArbType::KalshiYesPolyNo => {
-                if yes_filled > no_filled {
-                    // Kalshi YES excess
-                    if let Some(kalshi) = &kalshi {
-                        let close_price = (yes_price as i64).saturating_sub(10).max(1);
-                        match kalshi.sell_ioc(&kalshi_ticker, "yes", close_price, excess).await {
-                            Ok(resp) => {
-                                let proceeds = resp.order.taker_fill_cost.unwrap_or(0) + resp.order.maker_fill_cost.unwrap_or(0);
-                                log_close_pnl("Kalshi", resp.order.filled_count(), proceeds);
-                            }
-                            Err(e) => warn!("[EXEC] ⚠️ Failed to close Kalshi excess: {}", e),
-                        }
-                    } else {
-                        warn!("[EXEC] ⚠️ Cannot close Kalshi YES excess - Kalshi not configured");
-                    }
-                } else {
-                    // Poly NO excess
-                    let close_price = cents_to_price((no_price as i16).saturating_sub(10).max(1) as u16);
-                    info!("[EXEC] 🔄 Waiting 2s for Poly settlement before auto-close ({} no contracts)", excess);
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-
-                    match poly_async.sell_fak(&poly_no_token, close_price, excess as f64).await {
-                        Ok(fill) => log_close_pnl("Poly", fill.filled_size as i64, (fill.fill_cost * 100.0) as i64),
-                        Err(e) => warn!("[EXEC] ⚠️ Failed to close Poly excess: {}", e),
-                    }
+        let allocations = allocate(&batch, &max_contracts, &balances, &self.fees);
+        let mut sized = Vec::with_capacity(batch.len());
+        for (req, a) in batch.into_iter().zip(allocations) {
+            let market = self.market_description(req.market_id);
+            match a.limit {
+                None => debug!("[EXEC] 💰 Allocation: {} | {:?} {:.1}¢/$ → {}x", market, req.arb_type, a.cents_per_dollar, a.contracts),
+                Some(limit) => info!("[EXEC] 💰 Allocation: {} | {:?} {:.1}¢/$ → {}x of {}x ({}, balances {:?})",
+                                     market, req.arb_type, a.cents_per_dollar, a.contracts, a.wanted, limit, balances),
+            }
+            if a.contracts > 0 {
+                sized.push((req, a.contracts));
+            } else {
+                self.report_opportunity(&req);
+            }
+        }
+        sized
+    }
+
+    /// Contracts the per-trade capital buys at the request's prices (Kalshi
+    /// fees included), None without a sizing limit
+    async fn capital_contracts(&self, req: &FastExecutionRequest) -> Option<i64> {
+        if self.sizing == TradeSizingConfig::default() {
+            return None;
+        }
+        let mut min_balance = None;
+        if self.sizing.balance_fraction.is_some() {
+            for platform in [Platform::Kalshi, Platform::Polymarket] {
+                if let Some(cents) = self.balance(platform).await {
+                    min_balance = Some(min_balance.map_or(cents, |m: i64| m.min(cents)));
                 }
             }
         }
+        let cost: i64 = required_funds(req.arb_type, 1, req.yes_price, req.no_price).iter().map(|(_, cents)| cents).sum();
+        self.sizing.max_contracts(min_balance, cost)
     }
 
-    #[inline(always)]
-    fn release_in_flight(&self, market_id: u16) {
-        if market_id < 512 {
-            let slot = (market_id / 64) as usize;
-            let bit = market_id % 64;
-            let mask = !(1u64 << bit);
-            self.in_flight[slot].fetch_and(mask, Ordering::Release);
+    /// Reserve the funds both legs need at these prices, or the first
+    /// platform whose balance, net of what other executions hold, can't
+    /// cover its legs: (platform, required, available). A balance that
+    /// can't be fetched doesn't block the trade.
+    async fn hold_funds(&self, arb_type: ArbType, contracts: i64, yes_price: PriceCents, no_price: PriceCents) -> std::result::Result<FundsHold<'_>, (Platform, i64, i64)> {
+        let funds = required_funds(arb_type, contracts, yes_price, no_price);
+        let mut balances = Vec::with_capacity(funds.len());
+        for &(platform, _) in &funds {
+            balances.push(self.balance(platform).await);
+        }
+        self.balances.reserve(&funds, &balances)?;
+        Ok(FundsHold { balances: &self.balances, funds })
+    }
+
+    /// Account balance in cents, from the cache or the exchange
+    async fn balance(&self, platform: Platform) -> Option<i64> {
+        if let Some(cents) = self.balances.get(platform) {
+            return Some(cents);
+        }
+        let fetched = match platform {
+            Platform::Kalshi => match &self.kalshi {
+                Some(kalshi) => kalshi.get_balance().await,
+                None => return None,
+            },
+            Platform::Polymarket => match &self.poly_async {
+                Some(poly) => poly.get_balance().await,
+                None => return None,
+            },
+        };
+        match fetched {
+            Ok(cents) => {
+                self.balances.store(platform, cents);
+                Some(cents)
+            }
+            Err(e) => {
+                warn!("[EXEC] Could not fetch {} balance, trading without the check: {}", platform, e);
+                None
+            }
+        }
+    }
+
+    /// Whether an underfunded alert for this platform is due
+    fn should_alert_balance(&self, platform: Platform) -> bool {
+        let mut alerts = self.balance_alerts.lock().unwrap_or_else(|e| e.into_inner());
+        let due = alerts.get(&platform).is_none_or(|last| last.elapsed() >= self.balance_alert_interval);
+        if due {
+            alerts.insert(platform, Instant::now());
         }
+        due
     }
 
     fn release_in_flight_delayed(&self, market_id: u16) {
@@ -634,39 +2986,597 @@ pub fn create_execution_channel() -> (mpsc::Sender<FastExecutionRequest>, mpsc::
     mpsc::channel(256)
 }
 
-/// Main execution event loop - processes arbitrage opportunities as they arrive
+/// Create the channel carrying full-set arbs on multi-outcome markets
+pub fn create_full_set_channel() -> (mpsc::Sender<MultiOutcomeRequest>, mpsc::Receiver<MultiOutcomeRequest>) {
+    mpsc::channel(64)
+}
+
+/// Create the channel carrying wide spreads for `Strategy::SpreadCapture`
+pub fn create_spread_channel() -> (mpsc::Sender<SpreadRequest>, mpsc::Receiver<SpreadRequest>) {
+    mpsc::channel(64)
+}
+
+/// Check spread-capture inventory against its trailing stop every
+/// `config.interval_ms` until shutdown
+pub async fn run_trailing_stops(engine: Arc<ExecutionEngine>, config: TrailingStopConfig, mut shutdown: watch::Receiver<bool>) {
+    let Some(retrace_cents) = config.retrace_cents else { return };
+    info!("[EXEC] Trailing stop at {:.1}¢ below the high-water mark of directional inventory", retrace_cents);
+    let mut interval = tokio::time::interval(Duration::from_millis(config.interval_ms));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        tokio::select! {
+            _ = shutdown.wait_for(|&stop| stop) => return,
+            _ = interval.tick() => {}
+        }
+        if !engine.warming_up() {
+            engine.check_trailing_stops(retrace_cents).await;
+        }
+    }
+}
+
+/// Main execution event loop - processes arbitrage opportunities (pair arbs
+/// and multi-outcome full sets) as they arrive, or spread-capture requests
+/// under `Strategy::SpreadCapture`. On shutdown, stops taking requests and
+/// returns once in-progress ones finish.
 pub async fn run_execution_loop(
     mut rx: mpsc::Receiver<FastExecutionRequest>,
+    mut full_sets: mpsc::Receiver<MultiOutcomeRequest>,
+    mut spreads: mpsc::Receiver<SpreadRequest>,
     engine: Arc<ExecutionEngine>,
+    mut shutdown: watch::Receiver<bool>,
 ) {
-    info!("[EXEC] Execution engine started (dry_run={})", engine.dry_run);
-
-    while let Some(req) = rx.recv().await {
-        let engine = engine.clone();
-
-        // Process immediately in spawned task
-        tokio::spawn(async move {
-            match engine.process(req).await {
-                Ok(result) if result.success => {
-                    info!(
-                        "[EXEC] ✅ market_id={} profit={}¢ latency={}µs",
-                        result.market_id, result.profit_cents, result.latency_ns / 1000
-                    );
-                }
-                Ok(result) => {
-                    if result.error != Some("Already in-flight") {
-                        warn!(
-                            "[EXEC] ⚠️ market_id={}: {:?}",
-                            result.market_id, result.error
-                        );
+    info!("[EXEC] Execution engine started (mode={:?}, strategy={:?})", engine.mode, engine.strategy);
+    let arbitrage = engine.strategy == Strategy::Arbitrage;
+
+    let mut in_progress = JoinSet::new();
+    loop {
+        let req = tokio::select! {
+            biased;
+
+            _ = shutdown.wait_for(|&stop| stop) => break,
+            req = rx.recv() => match req {
+                Some(req) => req,
+                None => break,
+            },
+            Some(req) = full_sets.recv() => {
+                if engine.warming_up() {
+                    continue;
+                }
+                let engine = engine.clone();
+                in_progress.spawn(async move {
+                    match engine.process_full_set(req).await {
+                        Ok(result) if result.success => {
+                            info!(profit_cents = result.profit_cents, latency_us = result.latency_ns / 1000,
+                                  "[EXEC] ✅ Executed full set on multi-outcome market {}", result.market_id);
+                        }
+                        Ok(result) if result.error != Some("Already in-flight") => {
+                            warn!(latency_us = result.latency_ns / 1000,
+                                  "[EXEC] ⚠️ multi-outcome market {}: {:?}", result.market_id, result.error);
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("[EXEC] ❌ Error: {}", e),
                     }
+                });
+                continue;
+            }
+            Some(req) = spreads.recv() => {
+                if engine.warming_up() {
+                    continue;
                 }
-                Err(e) => {
-                    error!("[EXEC] ❌ Error: {}", e);
+                let engine = engine.clone();
+                in_progress.spawn(async move {
+                    match engine.process_spread(req).await {
+                        Ok(result) if result.error.is_none_or(|e| e == "DRY_RUN") => {}
+                        Ok(result) if result.error != Some("Already in-flight") => {
+                            debug!(market = %engine.market_description(result.market_id),
+                                   "[EXEC] Spread capture: {:?}", result.error);
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("[EXEC] ❌ Error: {}", e),
+                    }
+                });
+                continue;
+            }
+            // Reap finished executions
+            Some(_) = in_progress.join_next(), if !in_progress.is_empty() => continue,
+        };
+        // Price feeds send arbs whatever the strategy
+        if !arbitrage {
+            continue;
+        }
+        if engine.warming_up() {
+            debug!("[EXEC] Warming up: {} not traded", engine.market_description(req.market_id));
+            continue;
+        }
+        // Act on all the opportunities that arrived together, on the best of
+        // them, or on all of them sized to the balances; the others are
+        // still reported
+        let batch = match engine.ranking.policy {
+            RankingPolicy::All => {
+                let mut batch = vec![(req, None)];
+                while let Ok(next) = rx.try_recv() {
+                    batch.push((next, None));
                 }
+                batch
             }
-        });
+            RankingPolicy::Top => {
+                let mut batch = vec![req];
+                while let Ok(next) = rx.try_recv() {
+                    batch.push(next);
+                }
+                let Some((top, rest)) = rank_top(batch, &engine.fees, engine.ranking.full_confidence_contracts) else { continue };
+                let others = rest.iter().filter(|r| r.market_id != top.market_id).count();
+                if others > 0 {
+                    debug!("[EXEC] Ranking: {} chosen over {} simultaneous opportunities", engine.market_description(top.market_id), others);
+                }
+                for skipped in &rest {
+                    engine.report_opportunity(skipped);
+                }
+                vec![(top, None)]
+            }
+            RankingPolicy::Allocate => {
+                let mut batch = vec![req];
+                while let Ok(next) = rx.try_recv() {
+                    batch.push(next);
+                }
+                engine.allocate_batch(batch).await.into_iter().map(|(req, contracts)| (req, Some(contracts))).collect()
+            }
+        };
+        match batch[..] {
+            [] => {}
+            [(req, allocated)] => spawn_process(&mut in_progress, engine.clone(), req, allocated),
+            _ => {
+                let engine = engine.clone();
+                in_progress.spawn(async move {
+                    execute_batch(engine, batch).await;
+                });
+            }
+        }
+    }
+
+    if !in_progress.is_empty() {
+        info!("[EXEC] Waiting for {} in-progress execution(s)", in_progress.len());
     }
+    while in_progress.join_next().await.is_some() {}
 
     info!("[EXEC] Execution engine stopped");
-}
\ No newline at end of file
+}
+
+/// Process an arb in its own task, logging the outcome
+fn spawn_process(in_progress: &mut JoinSet<()>, engine: Arc<ExecutionEngine>, req: FastExecutionRequest, allocated: Option<i64>) {
+    in_progress.spawn(async move {
+        process_logged(engine, req, allocated).await;
+    });
+}
+
+/// Wall time of a batch of executions against running them one by one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleTiming {
+    pub executions: usize,
+    /// First execution started to last one done, waits for a permit included
+    pub elapsed: Duration,
+    /// Sum of the executions' own durations
+    pub sequential: Duration,
+}
+
+/// Execute the opportunities detected together, distinct markets in
+/// parallel up to `EXEC_MAX_PARALLEL`. A market takes a single permit for
+/// both its legs, and a second opportunity on a market already executing
+/// is turned away as in flight. Every order still waits on its platform's
+/// rate limiter, and the funds of each trade are held against the balances
+/// until its orders are out.
+pub async fn execute_batch(engine: Arc<ExecutionEngine>, batch: Vec<(FastExecutionRequest, Option<i64>)>) -> CycleTiming {
+    let started = tokio::time::Instant::now();
+    let executions = batch.len();
+    let markets = batch.iter().map(|(req, _)| req.market_id).collect::<FxHashSet<_>>().len();
+    let mut running = JoinSet::new();
+    for (req, allocated) in batch {
+        running.spawn(process_logged(engine.clone(), req, allocated));
+    }
+    let mut sequential = Duration::ZERO;
+    while let Some(took) = running.join_next().await {
+        sequential += took.unwrap_or_default();
+    }
+    let timing = CycleTiming { executions, elapsed: started.elapsed(), sequential };
+    info!("[EXEC] ⚡ Cycle of {} executions on {} markets in {:.0}ms, up to {} in parallel (~{:.0}ms one by one, {:.1}x)",
+          executions, markets, timing.elapsed.as_secs_f64() * 1000.0, engine.max_parallel,
+          timing.sequential.as_secs_f64() * 1000.0, timing.sequential.as_secs_f64() / timing.elapsed.as_secs_f64().max(1e-6));
+    timing
+}
+
+/// Process an arb once a parallelism permit is free, logging the outcome;
+/// returns how long it took past the wait
+async fn process_logged(engine: Arc<ExecutionEngine>, req: FastExecutionRequest, allocated: Option<i64>) -> Duration {
+    let Ok(_permit) = engine.parallel.clone().acquire_owned().await else { return Duration::ZERO };
+    let started = tokio::time::Instant::now();
+    match engine.process_allocated(req, allocated).await {
+        Ok(result) if result.success => {
+            info!(
+                market = %engine.market_description(result.market_id),
+                profit_cents = result.profit_cents,
+                latency_us = result.latency_ns / 1000,
+                "[EXEC] ✅ Executed market_id={}", result.market_id
+            );
+        }
+        Ok(result) => {
+            if result.error != Some("Already in-flight") {
+                warn!(
+                    market = %engine.market_description(result.market_id),
+                    latency_us = result.latency_ns / 1000,
+                    "[EXEC] ⚠️ market_id={}: {:?}", result.market_id, result.error
+                );
+            }
+        }
+        Err(e) => {
+            error!("[EXEC] ❌ Error: {}", e);
+        }
+    }
+    started.elapsed()
+}
+
+/// What kept an opportunity from getting all the contracts it could fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationLimit {
+    /// The platform's balance ran out
+    Balance(Platform),
+    /// A more profitable opportunity of the batch is on the same market
+    SameMarket,
+    /// Nothing to gain after fees
+    Unprofitable,
+}
+
+impl std::fmt::Display for AllocationLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Balance(platform) => write!(f, "{} balance", platform),
+            Self::SameMarket => write!(f, "better opportunity on the same market"),
+            Self::Unprofitable => write!(f, "unprofitable"),
+        }
+    }
+}
+
+/// Size given to one opportunity of a batch by `allocate`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Allocation {
+    pub contracts: i64,
+    /// Contracts the books and position limits would have allowed
+    pub wanted: i64,
+    /// Net profit per dollar put up, in cents
+    pub cents_per_dollar: f64,
+    pub limit: Option<AllocationLimit>,
+}
+
+/// Share `balances` (cents per platform; platforms not listed are not
+/// constrained) over `batch`, where opportunity `i` can take up to
+/// `max_contracts[i]`. Opportunities are served greedily by net profit per
+/// dollar, ties to the earliest, each taking as many contracts as every
+/// platform it trades on can still pay for. Only the best opportunity of each
+/// market gets contracts, since a market executes one at a time. Returns one
+/// allocation per request, in batch order.
+pub fn allocate(batch: &[FastExecutionRequest], max_contracts: &[i64], balances: &[(Platform, i64)], fees: &FeeSchedule) -> Vec<Allocation> {
+    let density = |req: &FastExecutionRequest| {
+        let per_contract: i64 = required_funds(req.arb_type, 1, req.yes_price, req.no_price).iter().map(|(_, c)| c).sum();
+        req.net_profit_cents(fees) * 100.0 / per_contract.max(1) as f64
+    };
+    let mut allocations: Vec<Allocation> = batch.iter().zip(max_contracts)
+        .map(|(req, &wanted)| Allocation { contracts: 0, wanted: wanted.max(0), cents_per_dollar: density(req), limit: None })
+        .collect();
+    let mut order: Vec<usize> = (0..batch.len()).collect();
+    order.sort_by(|&a, &b| allocations[b].cents_per_dollar.total_cmp(&allocations[a].cents_per_dollar));
+
+    let mut remaining = balances.to_vec();
+    let shortfall = |remaining: &[(Platform, i64)], req: &FastExecutionRequest, contracts: i64| {
+        required_funds(req.arb_type, contracts, req.yes_price, req.no_price).into_iter()
+            .find(|(platform, cost)| remaining.iter().any(|(p, left)| p == platform && cost > left))
+            .map(|(platform, _)| platform)
+    };
+    let mut served = FxHashSet::default();
+    for i in order {
+        let (req, allocation) = (&batch[i], &mut allocations[i]);
+        if req.net_profit_cents(fees) <= 0.0 {
+            allocation.limit = Some(AllocationLimit::Unprofitable);
+            continue;
+        }
+        if !served.insert(req.market_id) {
+            allocation.limit = Some(AllocationLimit::SameMarket);
+            continue;
+        }
+        // Largest size every platform can still pay for
+        let (mut lo, mut hi) = (0, allocation.wanted);
+        while lo < hi {
+            let mid = lo + (hi - lo + 1) / 2;
+            if shortfall(&remaining, req, mid).is_none() { lo = mid } else { hi = mid - 1 }
+        }
+        allocation.contracts = lo;
+        if lo < allocation.wanted {
+            allocation.limit = shortfall(&remaining, req, lo + 1).map(AllocationLimit::Balance);
+        }
+        for (platform, cost) in required_funds(req.arb_type, lo, req.yes_price, req.no_price) {
+            if let Some((_, left)) = remaining.iter_mut().find(|(p, _)| *p == platform) {
+                *left -= cost;
+            }
+        }
+    }
+    allocations
+}
+
+/// Split opportunities queued together into the highest-ranked one and the
+/// rest; ties go to the earliest
+pub fn rank_top(
+    mut batch: Vec<FastExecutionRequest>,
+    fees: &FeeSchedule,
+    full_confidence_contracts: i64,
+) -> Option<(FastExecutionRequest, Vec<FastExecutionRequest>)> {
+    let best = batch.iter()
+        .map(|req| req.value(fees, full_confidence_contracts).score())
+        .enumerate()
+        .rev()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?
+        .0;
+    let top = batch.remove(best);
+    Some((top, batch))
+}
+
+// =============================================================================
+// KILL SWITCH
+// =============================================================================
+
+/// One position leg closed by `flatten_all`, and how the close went
+#[derive(Debug, Clone)]
+pub struct FlattenLeg {
+    pub market_id: String,
+    pub description: String,
+    /// "kalshi" or "polymarket"
+    pub platform: &'static str,
+    /// "yes" or "no"
+    pub side: &'static str,
+    /// Kalshi ticker or Polymarket token to sell (None if it can't be traded)
+    pub target: Option<Arc<str>>,
+    /// Contracts held when the flatten started
+    pub contracts: f64,
+    pub closed: f64,
+    /// Sale proceeds in dollars
+    pub proceeds: f64,
+    /// Booked against the leg's average cost once the flatten ends
+    pub realized_pnl: f64,
+    pub attempts: u32,
+    /// Last failure, cleared by a successful fill
+    pub error: Option<String>,
+}
+
+impl FlattenLeg {
+    pub fn remaining(&self) -> f64 {
+        (self.contracts - self.closed).max(0.0)
+    }
+
+    /// Nothing left that can be sold (orders are in whole contracts)
+    pub fn is_done(&self) -> bool {
+        self.remaining() < 1.0
+    }
+}
+
+impl std::fmt::Display for FlattenLeg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}/{} {:.0}/{:.0}", self.description, self.platform, self.side, self.closed, self.contracts)?;
+        if self.closed > 0.0 {
+            write!(f, " (${:+.2})", self.realized_pnl)?;
+        }
+        if let Some(error) = self.error.as_ref().filter(|_| !self.is_done()) {
+            write!(f, " - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of `flatten_all`
+#[derive(Debug, Clone, Default)]
+pub struct FlattenReport {
+    pub legs: Vec<FlattenLeg>,
+    /// Realized P&L of every close, in dollars
+    pub realized_pnl: f64,
+    /// Dry run: nothing was sent
+    pub simulated: bool,
+}
+
+/// Legs of every open position, with the ticker or token to sell taken from
+/// the discovered pairs. Positions adopted from a Kalshi ticker the bot no
+/// longer quotes are keyed by the ticker itself.
+pub fn flatten_plan(tracker: &PositionTracker, state: &GlobalState) -> Vec<FlattenLeg> {
+    let pairs: FxHashMap<&str, &MarketPair> = state.markets.iter()
+        .filter_map(|m| m.pair.as_deref().map(|pair| (&*pair.pair_id, pair)))
+        .collect();
+
+    let mut legs = Vec::new();
+    let mut positions = tracker.open_positions();
+    positions.sort_by(|a, b| a.market_id.cmp(&b.market_id));
+    for position in positions {
+        let pair = pairs.get(position.market_id.as_str()).copied();
+        for (platform, side) in [("kalshi", "yes"), ("kalshi", "no"), ("polymarket", "yes"), ("polymarket", "no")] {
+            let Some(leg) = position.leg(platform, side).filter(|l| l.contracts > 0.0) else { continue };
+            let target = leg_target(pair, &position.market_id, platform, side).or_else(|| {
+                // Full-set outcome positions are keyed by their token
+                (platform == "polymarket" && state.poly_outcome_to_id.contains_key(&fxhash_str(&position.market_id)))
+                    .then(|| Arc::from(position.market_id.as_str()))
+            });
+            legs.push(FlattenLeg {
+                market_id: position.market_id.clone(),
+                description: position.description.clone(),
+                platform,
+                side,
+                error: target.is_none().then(|| "market not found".to_string()),
+                target,
+                contracts: leg.contracts,
+                closed: 0.0,
+                proceeds: 0.0,
+                realized_pnl: 0.0,
+                attempts: 0,
+            });
+        }
+    }
+    legs
+}
+
+/// Tracker market id and side of a Polymarket token: the pair trading it,
+/// or the token itself for a full-set outcome no pair trades
+fn poly_position_key(state: &GlobalState, token: &str) -> (String, &'static str) {
+    let hash = fxhash_str(token);
+    let pair_id = |id| state.get_by_id(id).and_then(|m| m.pair.as_ref()).map(|pair| pair.pair_id.to_string());
+    match (state.id_by_poly_yes_hash(hash).and_then(pair_id), state.id_by_poly_no_hash(hash).and_then(pair_id)) {
+        (Some(pair_id), _) => (pair_id, "yes"),
+        (None, Some(pair_id)) => (pair_id, "no"),
+        (None, None) => (token.to_string(), "yes"),
+    }
+}
+
+/// Platform of a position leg, named as in the position tracker
+fn leg_platform(platform: &str) -> Platform {
+    match platform {
+        "kalshi" => Platform::Kalshi,
+        _ => Platform::Polymarket,
+    }
+}
+
+/// Kalshi ticker or Polymarket token traded by a position leg; positions
+/// without a known pair are keyed by their Kalshi ticker
+fn leg_target(pair: Option<&MarketPair>, market_id: &str, platform: &str, side: &str) -> Option<Arc<str>> {
+    match (platform, side, pair) {
+        ("kalshi", _, Some(pair)) => Some(pair.kalshi_market_ticker.clone()),
+        ("kalshi", _, None) => Some(Arc::from(market_id)),
+        ("polymarket", "yes", Some(pair)) => Some(pair.poly_yes_token.clone()),
+        ("polymarket", _, Some(pair)) => Some(pair.poly_no_token.clone()),
+        _ => None,
+    }
+}
+
+/// Sell up to `contracts` of a leg IOC/FAK at `limit` or better; returns
+/// (contracts filled, proceeds in dollars)
+async fn sell_leg(
+    kalshi: Option<&KalshiApiClient>,
+    poly: Option<&SharedAsyncClient>,
+    platform: &str,
+    side: &str,
+    target: &str,
+    limit: Price,
+    contracts: f64,
+) -> Result<(f64, f64)> {
+    match (platform, kalshi, poly) {
+        ("kalshi", Some(kalshi), _) => {
+            let resp = kalshi.sell_ioc(target, side, limit, contracts as i64).await?;
+            let proceeds = resp.order.taker_fill_cost.unwrap_or(0) + resp.order.maker_fill_cost.unwrap_or(0);
+            Ok((resp.order.filled_count() as f64, proceeds as f64 / 100.0))
+        }
+        ("polymarket", _, Some(poly)) => {
+            let fill = poly.sell_fak(target, limit, contracts).await?;
+            Ok((fill.filled_size, fill.fill_cost))
+        }
+        _ => Err(Error::Invalid(format!("{} not configured", platform))),
+    }
+}
+
+/// Sell every leg down, retrying the ones that failed or filled partially in
+/// later rounds so one stuck leg doesn't hold up the rest. A leg whose close
+/// fails with a non-retryable error (auth, market closed, rejected order) is
+/// given up on. `close(platform, side, target, contracts)` sells up to
+/// `contracts` and returns (contracts filled, proceeds in dollars).
+pub async fn close_legs<F, Fut>(legs: &mut [FlattenLeg], config: FlattenConfig, mut close: F)
+where
+    F: FnMut(&'static str, &'static str, Arc<str>, f64) -> Fut,
+    Fut: std::future::Future<Output = Result<(f64, f64)>>,
+{
+    for round in 0..config.max_attempts {
+        let pending: Vec<usize> = (0..legs.len())
+            .filter(|&i| legs[i].target.is_some() && !legs[i].is_done())
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+        if round > 0 {
+            tokio::time::sleep(Duration::from_millis(config.retry_delay_ms)).await;
+        }
+
+        for i in pending {
+            let leg = &mut legs[i];
+            let Some(target) = leg.target.clone() else { continue };
+            leg.attempts += 1;
+            match close(leg.platform, leg.side, target, leg.remaining().floor()).await {
+                Ok((filled, proceeds)) if filled > 0.0 => {
+                    leg.closed += filled;
+                    leg.proceeds += proceeds;
+                    leg.error = None;
+                }
+                Ok(_) => leg.error = Some("no fill".to_string()),
+                Err(e) => {
+                    warn!("[FLATTEN] {} {}/{} attempt {} failed: {}", leg.description, leg.platform, leg.side, leg.attempts, e);
+                    if !e.is_retryable() {
+                        leg.target = None;
+                    }
+                    leg.error = Some(e.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Kill switch: halt trading, then market-sell every open position on its
+/// platform (IOC/FAK at the minimum price) and book the realized P&L.
+/// The breaker is halted first so no new entry races the closes, and stays
+/// halted until an explicit reset. Nothing is sent in dry-run mode, nor to a
+/// platform `health` reports down.
+#[allow(clippy::too_many_arguments)]
+pub async fn flatten_all(
+    kalshi: Option<&KalshiApiClient>,
+    poly: Option<&SharedAsyncClient>,
+    state: &GlobalState,
+    positions: &SharedPositionTracker,
+    circuit_breaker: &CircuitBreaker,
+    health: &PlatformHealth,
+    mode: ExecutionMode,
+    config: FlattenConfig,
+) -> FlattenReport {
+    circuit_breaker.halt().await;
+
+    let mut legs = flatten_plan(&*positions.read().await, state);
+    warn!("[FLATTEN] 🚨 Flattening {} leg(s)", legs.len());
+    if mode == ExecutionMode::DryRun {
+        for leg in &legs {
+            info!("[FLATTEN] 🏃 DRY RUN - would sell {}", leg);
+        }
+        return FlattenReport { legs, realized_pnl: 0.0, simulated: true };
+    }
+
+    for leg in &mut legs {
+        let configured = match leg.platform {
+            "kalshi" => kalshi.is_some(),
+            _ => poly.is_some(),
+        };
+        if !configured && leg.target.take().is_some() {
+            leg.error = Some(format!("{} not configured", leg.platform));
+        } else if health.is_down(leg_platform(leg.platform)) && leg.target.take().is_some() {
+            leg.error = Some(format!("{} down", leg.platform));
+        }
+    }
+
+    close_legs(&mut legs, config, |platform, side, target, contracts| {
+        async move { sell_leg(kalshi, poly, platform, side, &target, Price::CENT, contracts).await }
+    }).await;
+
+    let mut report = FlattenReport::default();
+    {
+        let mut tracker = positions.write().await;
+        for leg in &mut legs {
+            if leg.closed > 0.0 {
+                leg.realized_pnl = tracker
+                    .record_close(&leg.market_id, leg.platform, leg.side, leg.closed, leg.proceeds)
+                    .unwrap_or(0.0);
+                report.realized_pnl += leg.realized_pnl;
+            }
+            if leg.is_done() {
+                info!("[FLATTEN] ✅ {}", leg);
+            } else {
+                error!("[FLATTEN] ❌ {}", leg);
+            }
+        }
+    }
+    report.legs = legs;
+    report
+}
@@ -0,0 +1,232 @@
+//! Reconnection of the WebSocket price feeds.
+//!
+//! A dropped feed is reconnected after a capped exponential delay with jitter
+//! (`<PLATFORM>_WS_RECONNECT_BASE_MS` up to `<PLATFORM>_WS_RECONNECT_MAX_MS`)
+//! instead of a fixed pause, so an exchange that closes every connection is
+//! not hammered. The delay only starts over once a session stayed up for
+//! `STABLE_SESSION_SECS`. More than `<PLATFORM>_WS_MAX_RECONNECTS` reconnects
+//! within `<PLATFORM>_WS_RECONNECT_WINDOW_SECS` trip the circuit breaker, and
+//! a feed down for `<PLATFORM>_WS_DOWN_ALERT_SECS` raises one alert per outage.
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::circuit_breaker::{CircuitBreaker, TripReason};
+use crate::config::ReconnectConfig;
+use crate::types::Platform;
+
+/// A session connected for this long resets the backoff
+const STABLE_SESSION_SECS: u64 = 60;
+
+/// Handed to each session, which marks it once the socket is subscribed
+#[derive(Debug, Default)]
+pub struct FeedLink {
+    connected_at: OnceLock<Instant>,
+}
+
+impl FeedLink {
+    pub fn connected(&self) {
+        let _ = self.connected_at.set(Instant::now());
+    }
+
+    fn connected_at(&self) -> Option<Instant> {
+        self.connected_at.get().copied()
+    }
+}
+
+/// What to do after a session ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Disconnect {
+    pub delay: Duration,
+    /// Reconnects in the window
+    pub reconnects: u32,
+    /// The reconnect count just went over the limit
+    pub trip: bool,
+}
+
+/// Backoff, reconnect rate and outage state of one feed
+pub struct FeedSupervisor {
+    config: ReconnectConfig,
+    /// Failures in a row since the last stable session
+    attempt: u32,
+    reconnects: VecDeque<Instant>,
+    tripped: bool,
+    down_since: Option<Instant>,
+    alerted: bool,
+}
+
+impl FeedSupervisor {
+    pub fn new(config: ReconnectConfig) -> Self {
+        Self { config, attempt: 0, reconnects: VecDeque::new(), tripped: false, down_since: None, alerted: false }
+    }
+
+    /// The current session is connected; returns how long the feed was down
+    /// if that outage was alerted
+    pub fn reconnected(&mut self, now: Instant) -> Option<Duration> {
+        let down_since = self.down_since.take()?;
+        std::mem::take(&mut self.alerted).then(|| now.duration_since(down_since))
+    }
+
+    /// A session ended at `now`, after being connected since `connected_at`
+    /// (None if it never connected)
+    pub fn disconnected(&mut self, connected_at: Option<Instant>, now: Instant) -> Disconnect {
+        if let Some(at) = connected_at {
+            self.reconnected(at);
+            if now.duration_since(at) >= Duration::from_secs(STABLE_SESSION_SECS) {
+                self.attempt = 0;
+            }
+        }
+        self.down_since.get_or_insert(now);
+
+        let window = Duration::from_secs(self.config.window_secs);
+        self.reconnects.push_back(now);
+        while self.reconnects.front().is_some_and(|t| now.duration_since(*t) > window) {
+            self.reconnects.pop_front();
+        }
+        let reconnects = self.reconnects.len() as u32;
+        let over = self.config.max_reconnects.is_some_and(|max| reconnects > max);
+        let trip = over && !self.tripped;
+        self.tripped = over;
+
+        let delay = self.config.delay_for(self.attempt);
+        self.attempt = self.attempt.saturating_add(1);
+        Disconnect { delay, reconnects, trip }
+    }
+
+    /// When the current outage is due for an alert, if one is still to be sent
+    pub fn alert_at(&self) -> Option<Instant> {
+        let secs = self.config.alert_after_secs?;
+        let down_since = self.down_since.filter(|_| !self.alerted)?;
+        Some(down_since + Duration::from_secs(secs))
+    }
+
+    /// How long the feed has been down, once per outage past the alert delay
+    pub fn down_alert(&mut self, now: Instant) -> Option<Duration> {
+        let due = self.alert_at().is_some_and(|at| now >= at);
+        self.alerted |= due;
+        due.then(|| now.duration_since(self.down_since.unwrap_or(now)))
+    }
+}
+
+/// Run `session` for `platform` forever, reconnecting it with backoff.
+/// `on_down` is called with the outage length when the feed has been down
+/// past the alert delay.
+pub async fn supervise<F, Fut, E>(
+    platform: Platform,
+    config: ReconnectConfig,
+    breaker: Arc<CircuitBreaker>,
+    mut on_down: impl FnMut(Duration),
+    mut session: F,
+) where
+    F: FnMut(Arc<FeedLink>) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Display,
+{
+    let mut supervisor = FeedSupervisor::new(config);
+    loop {
+        let link = Arc::new(FeedLink::default());
+        let run = session(link.clone());
+        tokio::pin!(run);
+        let result = loop {
+            let alert_at = supervisor.alert_at();
+            tokio::select! {
+                result = &mut run => break result,
+                _ = tokio::time::sleep_until(alert_at.unwrap_or_else(Instant::now)), if alert_at.is_some() => {
+                    if let Some(at) = link.connected_at() {
+                        supervisor.reconnected(at);
+                    } else if let Some(down) = supervisor.down_alert(Instant::now()) {
+                        warn!("[FEED] {} feed down for {}s", platform, down.as_secs());
+                        on_down(down);
+                    }
+                }
+            }
+        };
+        if let Some(down) = link.connected_at().and_then(|at| supervisor.reconnected(at)) {
+            info!("[FEED] {} feed back after {}s", platform, down.as_secs());
+        }
+
+        let d = supervisor.disconnected(link.connected_at(), Instant::now());
+        match result {
+            Ok(()) => warn!("[FEED] {} feed closed, reconnecting in {}ms", platform, d.delay.as_millis()),
+            Err(e) => error!("[FEED] {} feed disconnected: {} - reconnecting in {}ms", platform, e, d.delay.as_millis()),
+        }
+        if d.trip {
+            breaker.trip(TripReason::FeedUnstable {
+                platform: platform.to_string(),
+                reconnects: d.reconnects,
+                window_secs: config.window_secs,
+            }).await;
+        }
+        tokio::time::sleep(d.delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ReconnectConfig {
+        ReconnectConfig {
+            base_delay_ms: 1000,
+            max_delay_ms: 8000,
+            max_reconnects: Some(3),
+            window_secs: 60,
+            alert_after_secs: Some(30),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_capped_and_resets_after_stable_session() {
+        let mut feed = FeedSupervisor::new(ReconnectConfig { max_reconnects: None, ..config() });
+        let t = Instant::now();
+        let delays: Vec<u128> = (0..6).map(|i| feed.disconnected(None, t + Duration::from_secs(i)).delay.as_millis()).collect();
+        for (delay, cap) in delays.iter().zip([1000, 2000, 4000, 8000, 8000, 8000]) {
+            assert!((cap / 2..=cap).contains(delay), "{:?}", delays);
+        }
+
+        // Connected but dropped within a few seconds: still backing off
+        let at = t + Duration::from_secs(100);
+        assert!(feed.disconnected(Some(at), at + Duration::from_secs(5)).delay >= Duration::from_millis(4000));
+        let d = feed.disconnected(Some(at), at + Duration::from_secs(STABLE_SESSION_SECS));
+        assert!(d.delay <= Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_reconnect_storm_trips_once_per_window() {
+        let mut feed = FeedSupervisor::new(config());
+        let t = Instant::now();
+        let trips: Vec<bool> = (0..6).map(|i| feed.disconnected(None, t + Duration::from_secs(i * 5)).trip).collect();
+        assert_eq!(trips, [false, false, false, true, false, false]);
+
+        // Quiet for longer than the window: re-armed
+        let later = t + Duration::from_secs(200);
+        assert_eq!(feed.disconnected(None, later).reconnects, 1);
+        let trips: Vec<bool> = (1..4).map(|i| feed.disconnected(None, later + Duration::from_secs(i)).trip).collect();
+        assert_eq!(trips, [false, false, true]);
+    }
+
+    #[test]
+    fn test_outage_alerted_once_until_reconnected() {
+        let mut feed = FeedSupervisor::new(config());
+        let t = Instant::now();
+        assert_eq!(feed.alert_at(), None, "never disconnected");
+
+        feed.disconnected(None, t);
+        feed.disconnected(None, t + Duration::from_secs(10));
+        assert_eq!(feed.alert_at(), Some(t + Duration::from_secs(30)), "outage counted from the first drop");
+        assert_eq!(feed.down_alert(t + Duration::from_secs(20)), None);
+        assert_eq!(feed.down_alert(t + Duration::from_secs(31)), Some(Duration::from_secs(31)));
+        assert_eq!(feed.down_alert(t + Duration::from_secs(90)), None, "already alerted");
+
+        assert_eq!(feed.reconnected(t + Duration::from_secs(95)), Some(Duration::from_secs(95)));
+        assert_eq!(feed.alert_at(), None);
+        // A short outage that comes back before the delay is never reported
+        feed.disconnected(Some(t + Duration::from_secs(95)), t + Duration::from_secs(200));
+        assert_eq!(feed.reconnected(t + Duration::from_secs(210)), None);
+    }
+}
@@ -4,30 +4,61 @@
 //! the Kalshi prediction market platform, including order execution and
 //! real-time price feed management.
 
-use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use futures_util::{SinkExt, StreamExt};
 use pkcs1::DecodeRsaPrivateKey;
 use rsa::{
+    pkcs8::DecodePrivateKey,
     pss::SigningKey,
     sha2::Sha256,
-    signature::{RandomizedSigner, SignatureEncoding},
+    signature::{Keypair, RandomizedSigner, SignatureEncoding, Verifier},
     RsaPrivateKey,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc;
+use tokio::time::interval;
 use tokio_tungstenite::{connect_async, tungstenite::{http::Request, Message}};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::config::{KALSHI_WS_URL, KALSHI_API_BASE, KALSHI_API_DELAY_MS};
+use crate::config::{
+    KALSHI_WS_URL, KALSHI_API_BASE, KALSHI_DEMO_WS_URL, KALSHI_DEMO_API_BASE,
+    KALSHI_PING_INTERVAL_SECS, KALSHI_WS_STALE_SECS,
+    ORDER_STATUS_POLL_MS, ORDER_SUBMIT_ATTEMPTS, CrossedBookConfig, HttpConfig, OrderTtlConfig,
+};
+use crate::discovery::PollSchedule;
+use crate::error::{retries_exhausted, Context, Error, Result};
 use crate::execution::NanoClock;
+use crate::feed::FeedLink;
+use crate::polymarket::MarketStatus;
+use crate::polymarket_clob::{BookSide, OrderBookStore};
+use crate::latency::Timed;
+use crate::rate_limit;
 use crate::types::{
-    KalshiEventsResponse, KalshiMarketsResponse, KalshiEvent, KalshiMarket,
-    GlobalState, FastExecutionRequest, ArbType, PriceCents, SizeCents, fxhash_str,
+    KalshiEventsResponse, KalshiMarketResponse, KalshiMarketsResponse, KalshiEvent, KalshiMarket,
+    BookCheck, GlobalState, FastExecutionRequest, OrderOutcome, OrderType, Platform, Price, PriceCents, SizeCents, NO_PRICE,
+    fxhash_str, unix_ms,
 };
 
+// === Market Status ===
+
+impl KalshiMarket {
+    /// Lifecycle state; settled markets without a yes/no result count as closed
+    pub fn market_status(&self) -> MarketStatus {
+        match self.status.as_deref().unwrap_or("active") {
+            "settled" | "finalized" | "determined" => match self.result.as_deref() {
+                Some("yes") => MarketStatus::Resolved { yes_won: true },
+                Some("no") => MarketStatus::Resolved { yes_won: false },
+                _ => MarketStatus::Closed,
+            },
+            "closed" | "inactive" => MarketStatus::Closed,
+            _ => MarketStatus::Open,
+        }
+    }
+}
+
 // === Order Types ===
 
 use std::borrow::Cow;
@@ -51,42 +82,37 @@ pub struct KalshiOrderRequest<'a> {
     pub expiration_ts: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time_in_force: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
 }
 
 impl<'a> KalshiOrderRequest<'a> {
-    /// Create an IOC (immediate-or-cancel) buy order
-    pub fn ioc_buy(ticker: Cow<'a, str>, side: &'static str, price_cents: i64, count: i64, client_order_id: Cow<'a, str>) -> Self {
+    /// Create a limit order; `order_type` sets the time in force
+    pub fn new(
+        ticker: Cow<'a, str>,
+        action: &'static str,
+        side: &'static str,
+        price_cents: i64,
+        count: i64,
+        client_order_id: Cow<'a, str>,
+        order_type: OrderType,
+    ) -> Self {
         let (yes_price, no_price) = if side == "yes" {
             (Some(price_cents), None)
         } else {
             (None, Some(price_cents))
         };
-
-        Self {
-            ticker,
-            action: "buy",
-            side,
-            order_type: "limit",
-            count,
-            yes_price,
-            no_price,
-            client_order_id,
-            expiration_ts: None,
-            time_in_force: Some("immediate_or_cancel"),
-        }
-    }
-
-    /// Create an IOC (immediate-or-cancel) sell order
-    pub fn ioc_sell(ticker: Cow<'a, str>, side: &'static str, price_cents: i64, count: i64, client_order_id: Cow<'a, str>) -> Self {
-        let (yes_price, no_price) = if side == "yes" {
-            (Some(price_cents), None)
-        } else {
-            (None, Some(price_cents))
+        // Unfilled GTC and post-only orders rest until the order TTL cancels them
+        let (time_in_force, post_only) = match order_type {
+            OrderType::Ioc => (Some("immediate_or_cancel"), None),
+            OrderType::Market => (Some("fill_or_kill"), None),
+            OrderType::Limit => (None, None),
+            OrderType::PostOnly => (None, Some(true)),
         };
 
         Self {
             ticker,
-            action: "sell",
+            action,
             side,
             order_type: "limit",
             count,
@@ -94,7 +120,8 @@ impl<'a> KalshiOrderRequest<'a> {
             no_price,
             client_order_id,
             expiration_ts: None,
-            time_in_force: Some("immediate_or_cancel"),
+            time_in_force,
+            post_only,
         }
     }
 }
@@ -102,6 +129,9 @@ impl<'a> KalshiOrderRequest<'a> {
 #[derive(Debug, Clone, Deserialize)]
 pub struct KalshiOrderResponse {
     pub order: KalshiOrderDetails,
+    /// Whether the order had to be cancelled at its time-to-live (set locally)
+    #[serde(skip)]
+    pub outcome: OrderOutcome,
 }
 
 #[allow(dead_code)]
@@ -131,6 +161,13 @@ pub struct KalshiOrderDetails {
     pub taker_fill_cost: Option<i64>,
     #[serde(default)]
     pub maker_fill_cost: Option<i64>,
+    #[serde(default)]
+    pub taker_fees: Option<i64>,
+    #[serde(default)]
+    pub maker_fees: Option<i64>,
+    /// Idempotency key the order was submitted with
+    #[serde(default)]
+    pub client_order_id: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -140,6 +177,11 @@ impl KalshiOrderDetails {
         self.taker_fill_count.unwrap_or(0) + self.maker_fill_count.unwrap_or(0)
     }
 
+    /// Fees charged on the fills so far (cents)
+    pub fn fees(&self) -> i64 {
+        self.taker_fees.unwrap_or(0) + self.maker_fees.unwrap_or(0)
+    }
+
     /// Check if order was fully filled
     pub fn is_filled(&self) -> bool {
         self.status == "executed" || self.remaining_count == Some(0)
@@ -149,47 +191,150 @@ impl KalshiOrderDetails {
     pub fn is_partial(&self) -> bool {
         self.filled_count() > 0 && !self.is_filled()
     }
+
+    /// Still on the book (or not yet matched), so the fill counts can change
+    pub fn is_resting(&self) -> bool {
+        matches!(self.status.as_str(), "pending" | "resting")
+    }
 }
 
 // === Kalshi Auth Config ===
 
+/// Kalshi deployment to connect to. API keys are issued per environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KalshiEnvironment {
+    #[default]
+    Production,
+    /// Demo exchange with fake money
+    Demo,
+}
+
+impl KalshiEnvironment {
+    /// Parse from KALSHI_ENV (`production`/`prod` or `demo`)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "production" | "prod" => Some(Self::Production),
+            "demo" | "sandbox" => Some(Self::Demo),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("KALSHI_ENV") {
+            Ok(v) => Self::parse(&v)
+                .with_context(|| format!("Invalid KALSHI_ENV '{}' (expected production or demo)", v)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// REST API base URL
+    pub fn api_base(&self) -> &'static str {
+        match self {
+            Self::Production => KALSHI_API_BASE,
+            Self::Demo => KALSHI_DEMO_API_BASE,
+        }
+    }
+
+    /// WebSocket URL
+    pub fn ws_url(&self) -> &'static str {
+        match self {
+            Self::Production => KALSHI_WS_URL,
+            Self::Demo => KALSHI_DEMO_WS_URL,
+        }
+    }
+
+    /// Host name shared by the REST and WebSocket endpoints
+    pub fn host(&self) -> &'static str {
+        let url = self.ws_url();
+        let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+        rest.split('/').next().unwrap_or(rest)
+    }
+}
+
+impl std::fmt::Display for KalshiEnvironment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Production => write!(f, "production"),
+            Self::Demo => write!(f, "demo"),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct KalshiConfig {
     pub api_key_id: String,
-    pub private_key: RsaPrivateKey,
+    /// Path the private key was loaded from
+    pub key_path: String,
+    /// RSA-PSS/SHA256 signer, parsed once at load
+    signing_key: SigningKey<Sha256>,
+    pub environment: KalshiEnvironment,
 }
 
 impl KalshiConfig {
     pub fn from_env() -> Result<Self> {
         dotenvy::dotenv().ok();
-        let api_key_id = std::env::var("KALSHI_API_KEY_ID").context("KALSHI_API_KEY_ID not set")?;
+        let api_key_id = std::env::var("KALSHI_API_KEY_ID")
+            .map_err(|_| Error::Auth("KALSHI_API_KEY_ID not set".into()))?;
         // Support both KALSHI_PRIVATE_KEY_PATH and KALSHI_PRIVATE_KEY_FILE for compatibility
         let key_path = std::env::var("KALSHI_PRIVATE_KEY_PATH")
             .or_else(|_| std::env::var("KALSHI_PRIVATE_KEY_FILE"))
             .unwrap_or_else(|_| "kalshi_private_key.txt".to_string());
+        let environment = KalshiEnvironment::from_env()?;
+        Self::load(api_key_id, key_path, environment)
+    }
+
+    /// Load and parse the private key (PKCS#1 or PKCS#8 PEM)
+    pub fn load(api_key_id: String, key_path: String, environment: KalshiEnvironment) -> Result<Self> {
         let private_key_pem = std::fs::read_to_string(&key_path)
-            .with_context(|| format!("Failed to read private key from {}", key_path))?
-            .trim()
-            .to_owned();
-        let private_key = RsaPrivateKey::from_pkcs1_pem(&private_key_pem)
-            .context("Failed to parse private key PEM")?;
-        Ok(Self { api_key_id, private_key })
+            .with_context(|| format!("Failed to read private key from {}", key_path))?;
+        let private_key = parse_private_key(&private_key_pem)
+            .with_context(|| format!("Failed to parse private key PEM from {}", key_path))?;
+        Ok(Self {
+            api_key_id,
+            key_path,
+            signing_key: SigningKey::<Sha256>::new(private_key),
+            environment,
+        })
+    }
+
+    /// Sign and verify a probe message so a bad key fails at startup
+    /// rather than on the first request.
+    pub fn verify_key(&self) -> Result<()> {
+        let probe = b"kalshi-key-check";
+        let signature = self.signing_key.sign_with_rng(&mut rand::thread_rng(), probe);
+        self.signing_key
+            .verifying_key()
+            .verify(probe, &signature)
+            .map_err(Error::auth)
+            .with_context(|| format!("Kalshi private key {} failed signature self-check", self.key_path))
     }
 
+    /// RSA-PSS SHA256 signature over `message` (timestamp + method + path), base64
     pub fn sign(&self, message: &str) -> Result<String> {
         tracing::debug!("[KALSHI-DEBUG] Signing message: {}", message);
-        let signing_key = SigningKey::<Sha256>::new(self.private_key.clone());
-        let signature = signing_key.sign_with_rng(&mut rand::thread_rng(), message.as_bytes());
+        let signature = self.signing_key.sign_with_rng(&mut rand::thread_rng(), message.as_bytes());
         let sig_b64 = BASE64.encode(signature.to_bytes());
         tracing::debug!("[KALSHI-DEBUG] Signature (first 50 chars): {}...", &sig_b64[..50.min(sig_b64.len())]);
         Ok(sig_b64)
     }
 }
 
+fn parse_private_key(pem: &str) -> Result<RsaPrivateKey> {
+    let pem = pem.trim();
+    RsaPrivateKey::from_pkcs1_pem(pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs8_pem(pem))
+        .map_err(|_| Error::Auth(
+            "Expected an RSA private key in PKCS#1 (BEGIN RSA PRIVATE KEY) or PKCS#8 (BEGIN PRIVATE KEY) format".into()))
+}
+
 // === Kalshi REST API Client ===
 
 /// Timeout for order requests (shorter than general API timeout)
 const ORDER_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Retries of a rate-limited GET before giving up
+const GET_MAX_RETRIES: u32 = 5;
+
 use std::sync::atomic::{AtomicU32, Ordering};
 
 /// Global order counter for unique client_order_id generation
@@ -197,20 +342,95 @@ static ORDER_COUNTER: AtomicU32 = AtomicU32::new(0);
 
 pub struct KalshiApiClient {
     http: reqwest::Client,
+    /// REST base URL for `config.environment`
+    base_url: &'static str,
     pub config: KalshiConfig,
+    order_ttl: OrderTtlConfig,
+}
+
+/// Response from GET /portfolio/balance
+#[derive(Debug, Deserialize)]
+struct KalshiBalanceResponse {
+    /// Available balance in cents
+    balance: i64,
+}
+
+/// Response from GET /portfolio/positions
+#[derive(Debug, Deserialize)]
+struct KalshiPositionsResponse {
+    #[serde(default)]
+    market_positions: Vec<KalshiMarketPosition>,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+/// Response from GET /portfolio/orders
+#[derive(Debug, Deserialize)]
+struct KalshiOrdersResponse {
+    #[serde(default)]
+    orders: Vec<KalshiOrderDetails>,
+}
+
+/// Keep the client order ID known from submission on a refreshed order
+fn merge_client_order_id(mut latest: KalshiOrderDetails, previous: &KalshiOrderDetails) -> KalshiOrderDetails {
+    if latest.client_order_id.is_none() {
+        latest.client_order_id = previous.client_order_id.clone();
+    }
+    latest
+}
+
+/// Net position in one Kalshi market
+#[derive(Debug, Clone, Deserialize)]
+pub struct KalshiMarketPosition {
+    pub ticker: String,
+    /// Contracts held: positive for YES, negative for NO
+    pub position: i64,
+    /// Cost of the current position in cents
+    #[serde(default)]
+    pub market_exposure: i64,
+}
+
+impl KalshiMarketPosition {
+    /// Average entry price in dollars
+    pub fn avg_price(&self) -> f64 {
+        if self.position == 0 {
+            return 0.0;
+        }
+        self.market_exposure as f64 / 100.0 / self.position.unsigned_abs() as f64
+    }
 }
 
 impl KalshiApiClient {
     pub fn new(config: KalshiConfig) -> Self {
         Self {
-            http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build()
-                .expect("Failed to build HTTP client"),
+            http: HttpConfig::kalshi_from_env().client().expect("Failed to build HTTP client"),
+            base_url: config.environment.api_base(),
             config,
+            order_ttl: OrderTtlConfig::from_env(),
         }
     }
 
+    /// Check that the API key is accepted by the configured environment.
+    /// Production and demo keys are not interchangeable. Returns the balance in cents.
+    pub async fn validate_credentials(&self) -> Result<i64> {
+        self.get_balance().await
+            .with_context(|| format!(
+                "Kalshi credentials rejected by the {} environment (API keys are environment-specific, check KALSHI_ENV)",
+                self.config.environment
+            ))
+    }
+
+    /// Cash available for new orders, in cents
+    pub async fn get_balance(&self) -> Result<i64> {
+        let resp: KalshiBalanceResponse = self.get("/portfolio/balance").await.inspect_err(|e| {
+            // Only rate limits are retried, so only they exhaust the retries
+            if matches!(e.root(), Error::RateLimited(_)) {
+                retries_exhausted("balance fetch", Platform::Kalshi, GET_MAX_RETRIES + 1, e);
+            }
+        })?;
+        Ok(resp.balance)
+    }
+
     #[inline]
     fn next_order_id() -> ArrayString<24> {
         let counter = ORDER_COUNTER.fetch_add(1, Ordering::Relaxed);
@@ -223,10 +443,10 @@ impl KalshiApiClient {
     /// Generic authenticated GET request with retry on rate limit
     async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
         let mut retries = 0;
-        const MAX_RETRIES: u32 = 5;
 
         loop {
-            let url = format!("{}{}", KALSHI_API_BASE, path);
+            rate_limit::acquire(Platform::Kalshi).await;
+            let url = format!("{}{}", self.base_url, path);
             let timestamp_ms = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
@@ -241,6 +461,7 @@ impl KalshiApiClient {
                 .header("KALSHI-ACCESS-SIGNATURE", &signature)
                 .header("KALSHI-ACCESS-TIMESTAMP", timestamp_ms.to_string())
                 .send()
+                .timed(Platform::Kalshi)
                 .await?;
             
             let status = resp.status();
@@ -248,23 +469,21 @@ impl KalshiApiClient {
             // Handle rate limit with exponential backoff
             if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
                 retries += 1;
-                if retries > MAX_RETRIES {
-                    anyhow::bail!("Kalshi API rate limited after {} retries", MAX_RETRIES);
+                if retries > GET_MAX_RETRIES {
+                    return Err(Error::RateLimited(Platform::Kalshi));
                 }
                 let backoff_ms = 2000 * (1 << retries); // 4s, 8s, 16s, 32s, 64s
                 debug!("[KALSHI] Rate limited, backing off {}ms (retry {}/{})", 
-                       backoff_ms, retries, MAX_RETRIES);
+                       backoff_ms, retries, GET_MAX_RETRIES);
                 tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
                 continue;
             }
             
             if !status.is_success() {
-                let body = resp.text().await.unwrap_or_default();
-                anyhow::bail!("Kalshi API error {}: {}", status, body);
+                return Err(Error::from_failed(Platform::Kalshi, resp).await);
             }
             
             let data: T = resp.json().await?;
-            tokio::time::sleep(Duration::from_millis(KALSHI_API_DELAY_MS)).await;
             return Ok(data);
         }
     }
@@ -272,7 +491,13 @@ impl KalshiApiClient {
     pub async fn get_events(&self, series_ticker: &str, limit: u32) -> Result<Vec<KalshiEvent>> {
         let path = format!("/events?series_ticker={}&limit={}&status=open", series_ticker, limit);
         let resp: KalshiEventsResponse = self.get(&path).await?;
-        Ok(resp.events)
+        // The series is implied by the query when the response leaves it out
+        Ok(resp.events.into_iter()
+            .map(|mut event| {
+                event.series_ticker.get_or_insert_with(|| series_ticker.to_string());
+                event
+            })
+            .collect())
     }
     
     pub async fn get_markets(&self, event_ticker: &str) -> Result<Vec<KalshiMarket>> {
@@ -280,10 +505,36 @@ impl KalshiApiClient {
         let resp: KalshiMarketsResponse = self.get(&path).await?;
         Ok(resp.markets)
     }
+
+    /// Current state of a single market
+    pub async fn get_market(&self, ticker: &str) -> Result<KalshiMarket> {
+        let resp: KalshiMarketResponse = self.get(&format!("/markets/{}", ticker)).await?;
+        Ok(resp.market)
+    }
+
+    /// All markets with a non-zero position (follows pagination)
+    pub async fn get_positions(&self) -> Result<Vec<KalshiMarketPosition>> {
+        let mut positions = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut path = "/portfolio/positions?count_filter=position&limit=1000".to_string();
+            if let Some(c) = &cursor {
+                path.push_str("&cursor=");
+                path.push_str(c);
+            }
+            let resp: KalshiPositionsResponse = self.get(&path).await?;
+            positions.extend(resp.market_positions.into_iter().filter(|p| p.position != 0));
+            match resp.cursor {
+                Some(c) if !c.is_empty() => cursor = Some(c),
+                _ => return Ok(positions),
+            }
+        }
+    }
     
     /// Generic authenticated POST request
     async fn post<T: serde::de::DeserializeOwned, B: Serialize>(&self, path: &str, body: &B) -> Result<T> {
-        let url = format!("{}{}", KALSHI_API_BASE, path);
+        rate_limit::acquire(Platform::Kalshi).await;
+        let url = format!("{}{}", self.base_url, path);
         let timestamp_ms = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -302,12 +553,11 @@ impl KalshiApiClient {
             .timeout(ORDER_TIMEOUT)
             .json(body)
             .send()
+            .timed(Platform::Kalshi)
             .await?;
 
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            anyhow::bail!("Kalshi API error {}: {}", status, body);
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Kalshi, resp).await);
         }
         
         let data: T = resp.json().await?;
@@ -319,58 +569,194 @@ impl KalshiApiClient {
         let path = "/portfolio/orders";
         self.post(path, order).await
     }
+
+    /// Look up a recent order on `ticker` by the client order ID it was submitted with
+    pub async fn find_order(&self, ticker: &str, client_order_id: &str) -> Result<Option<KalshiOrderDetails>> {
+        let path = format!("/portfolio/orders?ticker={}&limit=100", ticker);
+        let resp: KalshiOrdersResponse = self.get(&path).await?;
+        Ok(resp.orders.into_iter()
+            .find(|o| o.client_order_id.as_deref() == Some(client_order_id)))
+    }
+
+    /// Fetch an order by its exchange order ID
+    pub async fn get_order(&self, order_id: &str) -> Result<KalshiOrderDetails> {
+        let resp: KalshiOrderResponse = self.get(&format!("/portfolio/orders/{}", order_id)).await?;
+        Ok(resp.order)
+    }
+
+    /// Cancel the unfilled remainder of an order
+    pub async fn cancel_order(&self, order_id: &str) -> Result<KalshiOrderDetails> {
+        rate_limit::acquire(Platform::Kalshi).await;
+        let path = format!("/portfolio/orders/{}", order_id);
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let signature = self.config.sign(&format!("{}DELETE/trade-api/v2{}", timestamp_ms, path))?;
+
+        let resp = self.http
+            .delete(format!("{}{}", self.base_url, path))
+            .header("KALSHI-ACCESS-KEY", &self.config.api_key_id)
+            .header("KALSHI-ACCESS-SIGNATURE", &signature)
+            .header("KALSHI-ACCESS-TIMESTAMP", timestamp_ms.to_string())
+            .timeout(ORDER_TIMEOUT)
+            .send()
+            .timed(Platform::Kalshi)
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Kalshi, resp).await);
+        }
+        let data: KalshiOrderResponse = resp.json().await?;
+        Ok(data.order)
+    }
+
+    /// Poll an accepted order until it stops matching, so the fill counts are
+    /// final. IOC orders normally come back done; one still pending or resting
+    /// when its time-to-live runs out is cancelled.
+    async fn await_final(&self, mut order: KalshiOrderDetails) -> (KalshiOrderDetails, OrderOutcome) {
+        let deadline = tokio::time::Instant::now() + self.order_ttl.ttl();
+        while order.is_resting() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(ORDER_STATUS_POLL_MS)).await;
+            match self.get_order(&order.order_id).await {
+                Ok(latest) => order = merge_client_order_id(latest, &order),
+                Err(e) => warn!("[KALSHI] Status check of {} failed: {}", order.order_id, e),
+            }
+        }
+
+        if !order.is_resting() {
+            return (order, OrderOutcome::Completed);
+        }
+        warn!("[KALSHI] Order {} still {} after {}ms - cancelling remainder",
+              order.order_id, order.status, self.order_ttl.ttl_ms);
+        self.cancel_confirmed(order).await
+    }
+
+    /// Cancel a resting order until the exchange reports it done. The cancel
+    /// response (or a fresh read when the cancel itself failed) must show the
+    /// order off the book; otherwise the remainder may still fill.
+    pub async fn cancel_confirmed(&self, mut order: KalshiOrderDetails) -> (KalshiOrderDetails, OrderOutcome) {
+        let attempts = self.order_ttl.cancel_attempts;
+        for attempt in 1..=attempts {
+            match self.cancel_order(&order.order_id).await {
+                Ok(canceled) => order = merge_client_order_id(canceled, &order),
+                Err(e) => {
+                    warn!("[KALSHI] Cancel of {} failed (attempt {}/{}): {}", order.order_id, attempt, attempts, e);
+                    // It may have filled or been cancelled in the meantime
+                    if let Ok(latest) = self.get_order(&order.order_id).await {
+                        order = merge_client_order_id(latest, &order);
+                    }
+                }
+            }
+            if !order.is_resting() {
+                let outcome = if order.status == "executed" { OrderOutcome::Completed } else { OrderOutcome::Expired };
+                return (order, outcome);
+            }
+        }
+        retries_exhausted("order cancel", Platform::Kalshi, attempts, &Error::Invalid(format!(
+            "order {} still {}", order.order_id, order.status)));
+        (order, OrderOutcome::CancelUnconfirmed)
+    }
+
+    /// Re-read an order whose cancel went unconfirmed and cancel it again if
+    /// it is still resting
+    pub async fn confirm_cancelled(&self, order_id: &str) -> Result<(KalshiOrderDetails, OrderOutcome)> {
+        let order = self.get_order(order_id).await?;
+        if !order.is_resting() {
+            return Ok((order, OrderOutcome::Expired));
+        }
+        Ok(self.cancel_confirmed(order).await)
+    }
+
+    /// Submit an order, retrying only when the previous attempt's outcome is
+    /// unknown and no order with its client order ID exists. Kalshi rejects a
+    /// second order with the same client order ID, so a retry never doubles a fill.
+    async fn submit_order(&self, order: &KalshiOrderRequest<'_>) -> Result<KalshiOrderResponse> {
+        let key = order.client_order_id.as_ref();
+        let mut attempt = 1;
+        loop {
+            let err = match self.create_order(order).await {
+                Ok(mut resp) => {
+                    resp.order.client_order_id.get_or_insert_with(|| key.to_string());
+                    return Ok(resp);
+                }
+                Err(e) if e.outcome_unknown() => e,
+                Err(e) => return Err(e.context(format!("Kalshi order {}", key))),
+            };
+
+            warn!("[KALSHI] Order {} outcome unknown ({}), checking status", key, err);
+            match self.find_order(&order.ticker, key).await {
+                Ok(Some(existing)) => {
+                    info!("[KALSHI] Order {} was accepted as {}", key, existing.order_id);
+                    return Ok(KalshiOrderResponse { order: existing, outcome: OrderOutcome::default() });
+                }
+                Ok(None) if attempt < ORDER_SUBMIT_ATTEMPTS => {
+                    attempt += 1;
+                    warn!("[KALSHI] Order {} not found, resubmitting (attempt {}/{})",
+                          key, attempt, ORDER_SUBMIT_ATTEMPTS);
+                }
+                Ok(None) => {
+                    let err = err.context(format!("Kalshi order {} not placed", key));
+                    retries_exhausted("order submit", Platform::Kalshi, attempt, &err);
+                    return Err(err);
+                }
+                // Can't tell whether it was placed: resubmitting could double the position
+                Err(lookup) => {
+                    let err = err.context(format!("Kalshi order {} status unknown (lookup failed: {})", key, lookup));
+                    retries_exhausted("order submit", Platform::Kalshi, attempt, &err);
+                    return Err(err);
+                }
+            }
+        }
+    }
     
     /// Create an IOC buy order (convenience method)
-    pub async fn buy_ioc(
-        &self,
-        ticker: &str,
-        side: &str,  // "yes" or "no"
-        price_cents: i64,
-        count: i64,
-    ) -> Result<KalshiOrderResponse> {
-        debug_assert!(!ticker.is_empty(), "ticker must not be empty");
-        debug_assert!(price_cents >= 1 && price_cents <= 99, "price must be 1-99");
-        debug_assert!(count >= 1, "count must be >= 1");
+    #[allow(dead_code)]
+    pub async fn buy_ioc(&self, ticker: &str, side: &str, price: Price, count: i64) -> Result<KalshiOrderResponse> {
+        self.place("buy", ticker, side, price, count, OrderType::Ioc).await
+    }
 
-        let side_static: &'static str = if side == "yes" { "yes" } else { "no" };
-        let order_id = Self::next_order_id();
-        let order = KalshiOrderRequest::ioc_buy(
-            Cow::Borrowed(ticker),
-            side_static,
-            price_cents,
-            count,
-            Cow::Borrowed(&order_id)
-        );
-        debug!("[KALSHI] IOC {} {} @{}¢ x{}", side, ticker, price_cents, count);
+    pub async fn sell_ioc(&self, ticker: &str, side: &str, price: Price, count: i64) -> Result<KalshiOrderResponse> {
+        self.place("sell", ticker, side, price, count, OrderType::Ioc).await
+    }
 
-        let resp = self.create_order(&order).await?;
-        debug!("[KALSHI] {} filled={}", resp.order.status, resp.order.filled_count());
-        Ok(resp)
+    /// Buy order of the given type
+    pub async fn buy(&self, ticker: &str, side: &str, price: Price, count: i64, order_type: OrderType) -> Result<KalshiOrderResponse> {
+        self.place("buy", ticker, side, price, count, order_type).await
     }
 
-    pub async fn sell_ioc(
+    /// Place an order and wait until its fill is final (resting orders are
+    /// cancelled at the order TTL)
+    async fn place(
         &self,
+        action: &'static str,
         ticker: &str,
-        side: &str,
-        price_cents: i64,
+        side: &str,  // "yes" or "no"
+        price: Price,
         count: i64,
+        order_type: OrderType,
     ) -> Result<KalshiOrderResponse> {
         debug_assert!(!ticker.is_empty(), "ticker must not be empty");
-        debug_assert!(price_cents >= 1 && price_cents <= 99, "price must be 1-99");
         debug_assert!(count >= 1, "count must be >= 1");
+        if !price.is_tradable() {
+            return Err(Error::Invalid(format!("Kalshi price {} outside 1-99¢", price)));
+        }
 
         let side_static: &'static str = if side == "yes" { "yes" } else { "no" };
         let order_id = Self::next_order_id();
-        let order = KalshiOrderRequest::ioc_sell(
+        let order = KalshiOrderRequest::new(
             Cow::Borrowed(ticker),
+            action,
             side_static,
-            price_cents,
+            price.to_kalshi(),
             count,
-            Cow::Borrowed(&order_id)
+            Cow::Borrowed(&order_id),
+            order_type,
         );
-        debug!("[KALSHI] SELL {} {} @{}¢ x{}", side, ticker, price_cents, count);
+        debug!("[KALSHI] {} {} {} {} @{} x{}", order_type, action.to_uppercase(), side, ticker, price, count);
 
-        let resp = self.create_order(&order).await?;
+        let mut resp = self.submit_order(&order).await?;
+        (resp.order, resp.outcome) = self.await_final(resp.order).await;
         debug!("[KALSHI] {} filled={}", resp.order.status, resp.order.filled_count());
         Ok(resp)
     }
@@ -382,11 +768,14 @@ impl KalshiApiClient {
 pub struct KalshiWsMessage {
     #[serde(rename = "type")]
     pub msg_type: String,
+    /// Subscription the message belongs to
+    pub sid: Option<i64>,
+    /// Per-subscription sequence number; a gap means a delta was missed
+    pub seq: Option<i64>,
     pub msg: Option<KalshiWsMsgBody>,
 }
 
-#[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct KalshiWsMsgBody {
     pub market_ticker: Option<String>,
     // Snapshot fields - arrays of [price_cents, quantity]
@@ -398,6 +787,9 @@ pub struct KalshiWsMsgBody {
     pub side: Option<String>,
 }
 
+/// Markets per subscribe message
+const SUBSCRIBE_BATCH: usize = 100;
+
 #[derive(Serialize)]
 struct SubscribeCmd {
     id: i32,
@@ -411,16 +803,130 @@ struct SubscribeParams {
     market_tickers: Vec<String>,
 }
 
+// =============================================================================
+// Local Order Book
+// =============================================================================
+
+/// Resting bids of one Kalshi market, contracts per price in cents.
+/// Kalshi only publishes bids: a side's asks are the other side's bids at the
+/// complement price.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KalshiBook {
+    yes: BTreeMap<i64, i64>,
+    no: BTreeMap<i64, i64>,
+}
+
+impl KalshiBook {
+    pub fn from_snapshot(body: &KalshiWsMsgBody) -> Self {
+        let levels = |side: &Option<Vec<Vec<i64>>>| side.iter().flatten()
+            .filter(|l| l.len() >= 2 && l[1] > 0)
+            .map(|l| (l[0], l[1]))
+            .collect();
+        Self { yes: levels(&body.yes), no: levels(&body.no) }
+    }
+
+    /// Add `delta` contracts to the bids at `price` on `side` ("yes"/"no");
+    /// returns the level's new quantity
+    pub fn apply_delta(&mut self, side: &str, price: i64, delta: i64) -> Option<i64> {
+        let levels = match side {
+            "yes" => &mut self.yes,
+            "no" => &mut self.no,
+            _ => return None,
+        };
+        let qty = levels.get(&price).copied().unwrap_or(0).saturating_add(delta).max(0);
+        if qty == 0 {
+            levels.remove(&price);
+        } else {
+            levels.insert(price, qty);
+        }
+        Some(qty)
+    }
+
+    /// Best asks as stored in `AtomicMarketState`: (yes_ask, no_ask, yes_size, no_size)
+    pub fn top_of_book(&self) -> (PriceCents, PriceCents, SizeCents, SizeCents) {
+        let (yes_ask, yes_size) = complement_ask(&self.no).unwrap_or((NO_PRICE, 0));
+        let (no_ask, no_size) = complement_ask(&self.yes).unwrap_or((NO_PRICE, 0));
+        (yes_ask, no_ask, yes_size, no_size)
+    }
+
+    /// Best YES and NO bids in cents
+    pub fn best_bids(&self) -> (Option<i64>, Option<i64>) {
+        let best = |levels: &BTreeMap<i64, i64>| levels.keys().next_back().copied();
+        (best(&self.yes), best(&self.no))
+    }
+
+    /// YES and NO bids adding up to 100¢ or more: the YES bid is at or above
+    /// the YES ask (`100 - NO bid`), a feed glitch rather than free money
+    pub fn is_crossed(&self) -> bool {
+        matches!(self.best_bids(), (Some(yes), Some(no)) if yes + no >= 100)
+    }
+
+    fn bids(&self, side: &str) -> &BTreeMap<i64, i64> {
+        if side == "yes" { &self.yes } else { &self.no }
+    }
+}
+
+/// `OrderBookStore` key of one side ("yes"/"no") of a Kalshi market
+pub fn book_hash(ticker: &str, side: &str) -> u64 {
+    fxhash_str(&format!("{}:{}", ticker, side))
+}
+
+fn other_side(side: &str) -> &'static str {
+    if side == "yes" { "no" } else { "yes" }
+}
+
+/// Replace both sides' books in the store, in the Polymarket layout: the YES
+/// book bids are the YES bids and its asks the NO bids at `100 - price`
+fn publish_snapshot(books: &OrderBookStore, ticker: &str, book: &KalshiBook) {
+    for side in ["yes", "no"] {
+        books.apply_snapshot(
+            book_hash(ticker, side),
+            book.bids(side).iter().map(|(&p, &q)| (p as u64 * 100, q as f64)),
+            book.bids(other_side(side)).iter().map(|(&p, &q)| ((100 - p) as u64 * 100, q as f64)),
+        );
+    }
+}
+
+/// Mirror one bid level change into both sides' books
+fn publish_level(books: &OrderBookStore, ticker: &str, side: &str, price: i64, qty: i64) {
+    books.apply_level(book_hash(ticker, side), BookSide::Bid, price as u64 * 100, qty as f64);
+    books.apply_level(book_hash(ticker, other_side(side)), BookSide::Ask, (100 - price) as u64 * 100, qty as f64);
+}
+
+/// Last sequence number per subscription
+#[derive(Debug, Default)]
+pub struct SeqTracker {
+    last: HashMap<i64, i64>,
+}
+
+impl SeqTracker {
+    /// Record `seq` on `sid`; `Err` with the expected number on a gap
+    pub fn check(&mut self, sid: i64, seq: i64) -> std::result::Result<(), i64> {
+        match self.last.insert(sid, seq) {
+            Some(prev) if seq != prev + 1 => Err(prev + 1),
+            _ => Ok(()),
+        }
+    }
+}
+
 // =============================================================================
 // WebSocket Runner
 // =============================================================================
 
-/// WebSocket runner
+/// WebSocket runner. Keeps a local book per market from the `orderbook_delta`
+/// channel, mirrored into `books`, and returns on a sequence gap or a silent
+/// connection so the caller reconnects and gets fresh snapshots. The
+/// subscriptions go out in batches spread over `warmup` (see
+/// `PollSchedule::staggered_batches`). `link` is marked once the socket is open.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_ws(
     config: &KalshiConfig,
     state: Arc<GlobalState>,
+    books: Arc<OrderBookStore>,
     exec_tx: mpsc::Sender<FastExecutionRequest>,
     threshold_cents: PriceCents,
+    warmup: Duration,
+    link: Arc<FeedLink>,
 ) -> Result<()> {
     let tickers: Vec<String> = state.markets.iter()
         .take(state.market_count())
@@ -434,203 +940,205 @@ pub async fn run_ws(
     }
 
     let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)?
+        .duration_since(UNIX_EPOCH)
+        .map_err(Error::invalid)?
         .as_millis()
         .to_string();
 
     let signature = config.sign(&format!("{}GET/trade-api/ws/v2", timestamp))?;
 
     let request = Request::builder()
-        .uri(KALSHI_WS_URL)
+        .uri(config.environment.ws_url())
         .header("KALSHI-ACCESS-KEY", &config.api_key_id)
         .header("KALSHI-ACCESS-SIGNATURE", &signature)
         .header("KALSHI-ACCESS-TIMESTAMP", &timestamp)
-        .header("Host", "api.elections.kalshi.com")
+        .header("Host", config.environment.host())
         .header("Connection", "Upgrade")
         .header("Upgrade", "websocket")
         .header("Sec-WebSocket-Version", "13")
         .header("Sec-WebSocket-Key", tokio_tungstenite::tungstenite::handshake::client::generate_key())
-        .body(())?;
+        .body(())
+        .map_err(Error::invalid)?;
 
     let (ws_stream, _) = connect_async(request).await.context("Failed to connect to Kalshi")?;
     info!("[KALSHI] Connected");
+    link.connected();
 
     let (mut write, mut read) = ws_stream.split();
 
-    // Subscribe to all tickers
-    let subscribe_msg = SubscribeCmd {
-        id: 1,
-        cmd: "subscribe",
-        params: SubscribeParams {
-            channels: vec!["orderbook_delta"],
-            market_tickers: tickers.clone(),
-        },
-    };
+    // Nothing from the previous connection is trusted until its snapshot arrives
+    books.clear();
+    for market in state.markets.iter().take(state.market_count()) {
+        market.kalshi.store(NO_PRICE, NO_PRICE, 0, 0);
+    }
 
-    write.send(Message::Text(serde_json::to_string(&subscribe_msg)?)).await?;
-    info!("[KALSHI] Subscribed to {} markets", tickers.len());
+    // Subscribe to all tickers, a batch at a time over the warm-up
+    let ticker_count = tickers.len();
+    let mut batches = PollSchedule::staggered_batches(tickers, SUBSCRIBE_BATCH, warmup, tokio::time::Instant::now());
+    let (mut command_id, mut subscribed) = (0, 0);
 
     let clock = NanoClock::new();
+    let mut local: HashMap<u16, KalshiBook> = HashMap::new();
+    let mut seqs = SeqTracker::default();
+    let stale_after_ms = CrossedBookConfig::from_env().stale_after_ms;
+    let mut ping_interval = interval(Duration::from_secs(KALSHI_PING_INTERVAL_SECS));
+    let mut last_message = Instant::now();
+
+    loop {
+        let next_batch = batches.front().map(|(due, _)| *due);
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_batch.unwrap_or_else(tokio::time::Instant::now)), if next_batch.is_some() => {
+                let Some((_, batch)) = batches.pop_front() else { continue };
+                command_id += 1;
+                subscribed += batch.len();
+                let subscribe_msg = SubscribeCmd {
+                    id: command_id,
+                    cmd: "subscribe",
+                    params: SubscribeParams {
+                        channels: vec!["orderbook_delta"],
+                        market_tickers: batch,
+                    },
+                };
+                write.send(Message::Text(serde_json::to_string(&subscribe_msg)?)).await?;
+                if batches.is_empty() {
+                    info!("[KALSHI] Subscribed to {} markets", ticker_count);
+                } else {
+                    debug!("[KALSHI] Subscribed to {}/{} markets", subscribed, ticker_count);
+                }
+            }
 
-    while let Some(msg) = read.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                match serde_json::from_str::<KalshiWsMessage>(&text) {
-                    Ok(kalshi_msg) => {
-                        let ticker = kalshi_msg.msg.as_ref()
-                            .and_then(|m| m.market_ticker.as_ref());
+            _ = ping_interval.tick() => {
+                if let Err(e) = write.send(Message::Ping(vec![])).await {
+                    error!("[KALSHI] Failed to send ping: {}", e);
+                    break;
+                }
+            }
 
-                        let Some(ticker) = ticker else { continue };
-                        let ticker_hash = fxhash_str(ticker);
+            msg = read.next() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        last_message = Instant::now();
+                        let kalshi_msg = match serde_json::from_str::<KalshiWsMessage>(&text) {
+                            Ok(kalshi_msg) => kalshi_msg,
+                            Err(e) => {
+                                // Log at trace level - unknown message types are normal
+                                tracing::trace!("[KALSHI] WS parse error: {} (msg: {}...)", e, &text[..text.len().min(100)]);
+                                continue;
+                            }
+                        };
 
-                        let Some(&market_id) = state.kalshi_to_id.get(&ticker_hash) else { continue };
+                        if kalshi_msg.msg_type == "error" {
+                            error!("[KALSHI] WebSocket error message: {}", text);
+                            continue;
+                        }
+                        if let (Some(sid), Some(seq)) = (kalshi_msg.sid, kalshi_msg.seq) {
+                            if let Err(expected) = seqs.check(sid, seq) {
+                                return Err(Error::Network(format!(
+                                    "Kalshi sequence gap on subscription {}: got {}, expected {}", sid, seq, expected)));
+                            }
+                        }
+
+                        let Some(body) = &kalshi_msg.msg else { continue };
+                        let Some(ticker) = body.market_ticker.as_deref() else { continue };
+                        let Some(&market_id) = state.kalshi_to_id.get(&fxhash_str(ticker)) else { continue };
                         let market = &state.markets[market_id as usize];
 
-                        match kalshi_msg.msg_type.as_str() {
+                        let book = match kalshi_msg.msg_type.as_str() {
                             "orderbook_snapshot" => {
-                                if let Some(body) = &kalshi_msg.msg {
-                                    process_kalshi_snapshot(market, body);
-
-                                    // Check for arbs
-                                    let arb_mask = market.check_arbs(threshold_cents);
-                                    if arb_mask != 0 {
-                                        send_kalshi_arb_request(market_id, market, arb_mask, &exec_tx, &clock).await;
-                                    }
-                                }
+                                let book = KalshiBook::from_snapshot(body);
+                                publish_snapshot(&books, ticker, &book);
+                                let slot = local.entry(market_id).or_default();
+                                *slot = book;
+                                slot
                             }
                             "orderbook_delta" => {
-                                if let Some(body) = &kalshi_msg.msg {
-                                    process_kalshi_delta(market, body);
-
-                                    let arb_mask = market.check_arbs(threshold_cents);
-                                    if arb_mask != 0 {
-                                        send_kalshi_arb_request(market_id, market, arb_mask, &exec_tx, &clock).await;
-                                    }
+                                let (Some(side), Some(price), Some(delta)) = (body.side.as_deref(), body.price, body.delta) else { continue };
+                                let Some(book) = local.get_mut(&market_id) else {
+                                    debug!("[KALSHI] Delta for {} before its snapshot", ticker);
+                                    continue;
+                                };
+                                let Some(qty) = book.apply_delta(side, price, delta) else { continue };
+                                publish_level(&books, ticker, side, price, qty);
+                                book
+                            }
+                            _ => continue,
+                        };
+
+                        state.freshness.mark(Platform::Kalshi);
+                        match market.kalshi.check_crossed(book.is_crossed(), unix_ms(), stale_after_ms) {
+                            BookCheck::Valid => {}
+                            BookCheck::Recovered { crossed_ms } => info!("[KALSHI] {} book valid again after {}ms", ticker, crossed_ms),
+                            BookCheck::Crossed { first } => {
+                                if first {
+                                    let (yes_bid, no_bid) = book.best_bids();
+                                    warn!("[KALSHI] ⚠️ Crossed or locked book on {}: YES bid {}¢ + NO bid {}¢ - opportunities suppressed",
+                                          ticker, yes_bid.unwrap_or_default(), no_bid.unwrap_or_default());
                                 }
+                                continue;
                             }
-                            _ => {}
+                            BookCheck::Stale { crossed_ms } => {
+                                warn!("[KALSHI] ⚠️ {} book crossed for {}ms - market marked stale", ticker, crossed_ms);
+                                market.kalshi.store(NO_PRICE, NO_PRICE, 0, 0);
+                                continue;
+                            }
+                        }
+
+                        let (yes_ask, no_ask, yes_size, no_size) = book.top_of_book();
+                        market.kalshi.store(yes_ask, no_ask, yes_size, no_size);
+
+                        let arb_mask = market.check_arbs(threshold_cents);
+                        if arb_mask != 0 {
+                            send_kalshi_arb_request(market_id, market, arb_mask, &exec_tx, &clock).await;
                         }
                     }
-                    Err(e) => {
-                        // Log at trace level - unknown message types are normal
-                        tracing::trace!("[KALSHI] WS parse error: {} (msg: {}...)", e, &text[..text.len().min(100)]);
+                    Some(Ok(Message::Ping(data))) => {
+                        // Kalshi's heartbeat
+                        let _ = write.send(Message::Pong(data)).await;
+                        last_message = Instant::now();
                     }
+                    Some(Ok(Message::Pong(_))) => {
+                        last_message = Instant::now();
+                    }
+                    Some(Ok(Message::Close(frame))) => {
+                        warn!("[KALSHI] Server closed: {:?}", frame);
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        error!("[KALSHI] WebSocket error: {}", e);
+                        break;
+                    }
+                    None => {
+                        warn!("[KALSHI] Stream ended");
+                        break;
+                    }
+                    _ => {}
                 }
             }
-            Ok(Message::Ping(data)) => {
-                let _ = write.send(Message::Pong(data)).await;
-            }
-            Err(e) => {
-                error!("[KALSHI] WebSocket error: {}", e);
-                break;
-            }
-            _ => {}
+        }
+
+        if last_message.elapsed() > Duration::from_secs(KALSHI_WS_STALE_SECS) {
+            warn!("[KALSHI] Stale connection, reconnecting...");
+            break;
         }
     }
 
     Ok(())
 }
 
-/// Process Kalshi orderbook snapshot
-/// Note: Kalshi sends BIDS - to buy YES you pay (100 - best_NO_bid), to buy NO you pay (100 - best_YES_bid)
+/// Best bid on one side as an ask on the other: buying NO at `100 - YES_bid`
+/// fills against the YES bidders, for as many contracts as they bid.
+/// Levels map price in cents to quantity; the size is in contracts × 100 like
+/// the Polymarket side, so both legs of a cross-platform arb are sized in contracts.
 #[inline]
-fn process_kalshi_snapshot(market: &crate::types::AtomicMarketState, body: &KalshiWsMsgBody) {
-    // Find best YES bid (highest price) - this determines NO ask
-    let (no_ask, no_size) = body.yes.as_ref()
-        .and_then(|levels| {
-            levels.iter()
-                .filter_map(|l| {
-                    if l.len() >= 2 && l[1] > 0 {  // Has quantity
-                        Some((l[0], l[1]))  // (price, qty)
-                    } else {
-                        None
-                    }
-                })
-                .max_by_key(|(p, _)| *p)  // Highest bid
-                .map(|(price, qty)| {
-                    let ask = (100 - price) as PriceCents;  // To buy NO, pay 100 - YES_bid
-                    let size = (qty * price / 100) as SizeCents;
-                    (ask, size)
-                })
-        })
-        .unwrap_or((0, 0));
-
-    // Find best NO bid (highest price) - this determines YES ask
-    let (yes_ask, yes_size) = body.no.as_ref()
-        .and_then(|levels| {
-            levels.iter()
-                .filter_map(|l| {
-                    if l.len() >= 2 && l[1] > 0 {
-                        Some((l[0], l[1]))
-                    } else {
-                        None
-                    }
-                })
-                .max_by_key(|(p, _)| *p)
-                .map(|(price, qty)| {
-                    let ask = (100 - price) as PriceCents;  // To buy YES, pay 100 - NO_bid
-                    let size = (qty * price / 100) as SizeCents;
-                    (ask, size)
-                })
+fn complement_ask(levels: &BTreeMap<i64, i64>) -> Option<(PriceCents, SizeCents)> {
+    levels.iter().rev()
+        .filter(|&(_, &qty)| qty > 0)
+        .find_map(|(&price, &qty)| Some((Price::from_kalshi(price)?, qty)))  // Highest bid
+        .map(|(price, qty)| {
+            let ask = price.complement().cents();
+            let size = (qty.saturating_mul(100)).clamp(0, SizeCents::MAX as i64) as SizeCents;
+            (ask, size)
         })
-        .unwrap_or((0, 0));
-
-    // Store
-    market.kalshi.store(yes_ask, no_ask, yes_size, no_size);
-}
-
-/// Process Kalshi orderbook delta
-/// Note: Deltas update bid levels; we recompute asks from best bids
-#[inline]
-fn process_kalshi_delta(market: &crate::types::AtomicMarketState, body: &KalshiWsMsgBody) {
-    // For deltas, recompute from snapshot-like format
-    // Kalshi deltas have yes/no as arrays of [price, new_qty]
-    let (current_yes, current_no, current_yes_size, current_no_size) = market.kalshi.load();
-
-    // Process YES bid updates (affects NO ask)
-    let (no_ask, no_size) = if let Some(levels) = &body.yes {
-        // Find best (highest) YES bid with non-zero quantity
-        levels.iter()
-            .filter_map(|l| {
-                if l.len() >= 2 && l[1] > 0 {
-                    Some((l[0], l[1]))
-                } else {
-                    None
-                }
-            })
-            .max_by_key(|(p, _)| *p)
-            .map(|(price, qty)| {
-                let ask = (100 - price) as PriceCents;
-                let size = (qty * price / 100) as SizeCents;
-                (ask, size)
-            })
-            .unwrap_or((current_no, current_no_size))
-    } else {
-        (current_no, current_no_size)
-    };
-
-    // Process NO bid updates (affects YES ask)
-    let (yes_ask, yes_size) = if let Some(levels) = &body.no {
-        levels.iter()
-            .filter_map(|l| {
-                if l.len() >= 2 && l[1] > 0 {
-                    Some((l[0], l[1]))
-                } else {
-                    None
-                }
-            })
-            .max_by_key(|(p, _)| *p)
-            .map(|(price, qty)| {
-                let ask = (100 - price) as PriceCents;
-                let size = (qty * price / 100) as SizeCents;
-                (ask, size)
-            })
-            .unwrap_or((current_yes, current_yes_size))
-    } else {
-        (current_yes, current_yes_size)
-    };
-
-    market.kalshi.store(yes_ask, no_ask, yes_size, no_size);
 }
 
 /// Send arb request from Kalshi handler
@@ -642,30 +1150,97 @@ async fn send_kalshi_arb_request(
     exec_tx: &mpsc::Sender<FastExecutionRequest>,
     clock: &NanoClock,
 ) {
-    let (k_yes, k_no, k_yes_size, k_no_size) = market.kalshi.load();
-    let (p_yes, p_no, p_yes_size, p_no_size) = market.poly.load();
-
-    let (yes_price, no_price, yes_size, no_size, arb_type) = if arb_mask & 1 != 0 {
-        (p_yes, k_no, p_yes_size, k_no_size, ArbType::PolyYesKalshiNo)
-    } else if arb_mask & 2 != 0 {
-        (k_yes, p_no, k_yes_size, p_no_size, ArbType::KalshiYesPolyNo)
-    } else if arb_mask & 4 != 0 {
-        (p_yes, p_no, p_yes_size, p_no_size, ArbType::PolyOnly)
-    } else if arb_mask & 8 != 0 {
-        (k_yes, k_no, k_yes_size, k_no_size, ArbType::KalshiOnly)
-    } else {
-        return;
-    };
-
-    let req = FastExecutionRequest {
-        market_id,
-        yes_price,
-        no_price,
-        yes_size,
-        no_size,
-        arb_type,
-        detected_ns: clock.now_ns(),
-    };
-
-    let _ = exec_tx.try_send(req);
-}
\ No newline at end of file
+    if let Some(req) = FastExecutionRequest::from_arb_mask(market_id, market, arb_mask, clock.now_ns()) {
+        let _ = exec_tx.try_send(req);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_type_parameters() {
+        let order = |order_type| {
+            let req = KalshiOrderRequest::new("KXTEST".into(), "buy", "no", 45, 10, "id".into(), order_type);
+            serde_json::to_value(req).unwrap()
+        };
+
+        let ioc = order(OrderType::Ioc);
+        assert_eq!((ioc["type"].as_str(), ioc["no_price"].as_i64()), (Some("limit"), Some(45)));
+        assert_eq!(ioc["time_in_force"], "immediate_or_cancel");
+        assert!(ioc.get("post_only").is_none());
+        assert_eq!(order(OrderType::Market)["time_in_force"], "fill_or_kill");
+
+        let limit = order(OrderType::Limit);
+        assert!(limit.get("time_in_force").is_none() && limit.get("post_only").is_none());
+        let post_only = order(OrderType::PostOnly);
+        assert!(post_only.get("time_in_force").is_none());
+        assert_eq!(post_only["post_only"], true);
+    }
+
+    #[test]
+    fn test_book_from_snapshot_and_deltas() {
+        let snapshot = KalshiWsMsgBody {
+            market_ticker: Some("KXEPL-CHE".into()),
+            yes: Some(vec![vec![40, 100], vec![42, 25]]),
+            no: Some(vec![vec![55, 60], vec![0, 0]]),
+            ..Default::default()
+        };
+        let mut book = KalshiBook::from_snapshot(&snapshot);
+        // YES ask = 100 - best NO bid, NO ask = 100 - best YES bid
+        assert_eq!(book.top_of_book(), (45, 58, 6000, 2500));
+
+        let books = OrderBookStore::new();
+        publish_snapshot(&books, "KXEPL-CHE", &book);
+        assert_eq!(books.best_ask(book_hash("KXEPL-CHE", "yes")), Some((4500, 60.0)));
+
+        // Best YES bid pulled, a better NO bid joins
+        assert_eq!(book.apply_delta("yes", 42, -25), Some(0));
+        assert_eq!(book.apply_delta("no", 57, 10), Some(10));
+        assert_eq!(book.apply_delta("maybe", 57, 10), None);
+        assert_eq!(book.top_of_book(), (43, 60, 1000, 10000));
+        publish_level(&books, "KXEPL-CHE", "no", 57, 10);
+        assert_eq!(books.best_ask(book_hash("KXEPL-CHE", "yes")), Some((4300, 10.0)));
+
+        assert_eq!(book.apply_delta("yes", 40, -500), Some(0), "never below zero");
+        assert_eq!(book.top_of_book().1, NO_PRICE);
+    }
+
+    #[test]
+    fn test_crossed_or_locked_book() {
+        let snapshot = KalshiWsMsgBody {
+            yes: Some(vec![vec![42, 10]]),
+            no: Some(vec![vec![55, 10]]),
+            ..Default::default()
+        };
+        let mut book = KalshiBook::from_snapshot(&snapshot);
+        assert!(!book.is_crossed());
+
+        // A NO bid at 58¢ locks the book: the YES ask (100 - 58) meets the YES bid
+        book.apply_delta("no", 58, 5);
+        assert!(book.is_crossed());
+        let books = OrderBookStore::new();
+        publish_snapshot(&books, "KXEPL-CHE", &book);
+        assert!(books.is_crossed(book_hash("KXEPL-CHE", "yes")));
+
+        book.apply_delta("no", 60, 5);
+        assert_eq!(book.best_bids(), (Some(42), Some(60)));
+        // Read as asks, a crossed book sums below a dollar
+        let (yes_ask, no_ask, _, _) = book.top_of_book();
+        assert_eq!(yes_ask + no_ask, 98);
+
+        book.apply_delta("no", 58, -5);
+        book.apply_delta("no", 60, -5);
+        assert!(!book.is_crossed());
+    }
+
+    #[test]
+    fn test_sequence_gap_detected() {
+        let mut seqs = SeqTracker::default();
+        assert_eq!(seqs.check(1, 1), Ok(()));
+        assert_eq!(seqs.check(1, 2), Ok(()));
+        assert_eq!(seqs.check(2, 7), Ok(()), "first message of another subscription");
+        assert_eq!(seqs.check(1, 4), Err(3));
+    }
+}
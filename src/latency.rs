@@ -0,0 +1,186 @@
+//! Rolling round-trip latency per platform.
+//!
+//! Each REST call to Kalshi or Polymarket is timed from send to response
+//! headers and recorded in its platform's window of the most recent
+//! `LATENCY_WINDOW` samples, so memory stays fixed however long the bot runs.
+//! Percentiles are computed on demand for the status report, the metrics
+//! endpoint and the p95 alert.
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config::LatencyConfig;
+use crate::types::Platform;
+
+/// Percentiles over the current window, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    /// Samples in the window
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+struct Window {
+    samples_us: Vec<u32>,
+    /// Slot the next sample overwrites once the window is full
+    next: usize,
+}
+
+/// Fixed-size ring of the latest samples
+pub struct LatencyHistogram {
+    capacity: usize,
+    window: Mutex<Window>,
+}
+
+impl LatencyHistogram {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            window: Mutex::new(Window { samples_us: Vec::with_capacity(capacity), next: 0 }),
+        }
+    }
+
+    pub fn record(&self, latency: Duration) {
+        let us = latency.as_micros().min(u32::MAX as u128) as u32;
+        let mut w = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        if w.samples_us.len() < self.capacity {
+            w.samples_us.push(us);
+        } else {
+            let next = w.next;
+            w.samples_us[next] = us;
+            w.next = (next + 1) % self.capacity;
+        }
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        let mut sorted = self.window.lock().unwrap_or_else(|e| e.into_inner()).samples_us.clone();
+        if sorted.is_empty() {
+            return LatencyStats::default();
+        }
+        sorted.sort_unstable();
+        // Nearest rank
+        let pct = |p: f64| {
+            let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+            sorted[rank - 1] as f64 / 1000.0
+        };
+        LatencyStats { samples: sorted.len(), p50_ms: pct(0.50), p95_ms: pct(0.95), p99_ms: pct(0.99) }
+    }
+}
+
+static KALSHI: OnceLock<LatencyHistogram> = OnceLock::new();
+static POLYMARKET: OnceLock<LatencyHistogram> = OnceLock::new();
+
+/// Shared window for `platform`, sized from the environment on first use
+pub fn histogram(platform: Platform) -> &'static LatencyHistogram {
+    let slot = match platform {
+        Platform::Kalshi => &KALSHI,
+        Platform::Polymarket => &POLYMARKET,
+    };
+    slot.get_or_init(|| LatencyHistogram::new(LatencyConfig::from_env().window))
+}
+
+/// Await a request to `platform`, recording how long it took
+pub async fn timed<T>(platform: Platform, request: impl Future<Output = T>) -> T {
+    let start = Instant::now();
+    let out = request.await;
+    histogram(platform).record(start.elapsed());
+    out
+}
+
+/// `.timed(platform)` on a request future, for method chains
+pub trait Timed: Future + Sized {
+    fn timed(self, platform: Platform) -> impl Future<Output = Self::Output> {
+        timed(platform, self)
+    }
+}
+
+impl<F: Future> Timed for F {}
+
+/// Current percentiles for both platforms
+pub fn snapshot() -> Vec<(Platform, LatencyStats)> {
+    [Platform::Kalshi, Platform::Polymarket].into_iter()
+        .map(|platform| (platform, histogram(platform).stats()))
+        .collect()
+}
+
+/// Raises one alert per platform when its p95 crosses the threshold, and
+/// re-arms once it is back under
+pub struct LatencyMonitor {
+    config: LatencyConfig,
+    alerting: HashSet<Platform>,
+}
+
+impl LatencyMonitor {
+    pub fn new(config: LatencyConfig) -> Self {
+        Self { config, alerting: HashSet::new() }
+    }
+
+    /// Platforms that just went over the threshold
+    pub fn check(&mut self, stats: &[(Platform, LatencyStats)]) -> Vec<(Platform, LatencyStats)> {
+        let Some(threshold) = self.config.alert_p95_ms else { return Vec::new() };
+        let mut breached = Vec::new();
+        for &(platform, s) in stats {
+            if s.samples < self.config.min_samples {
+                continue;
+            }
+            if s.p95_ms > threshold as f64 {
+                if self.alerting.insert(platform) {
+                    breached.push((platform, s));
+                }
+            } else {
+                self.alerting.remove(&platform);
+            }
+        }
+        breached
+    }
+
+    pub fn threshold_ms(&self) -> Option<u64> {
+        self.config.alert_p95_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_over_bounded_window() {
+        let hist = LatencyHistogram::new(100);
+        assert_eq!(hist.stats(), LatencyStats::default());
+
+        for ms in 1..=100 {
+            hist.record(Duration::from_millis(ms));
+        }
+        let s = hist.stats();
+        assert_eq!((s.samples, s.p50_ms, s.p95_ms, s.p99_ms), (100, 50.0, 95.0, 99.0));
+
+        // Older samples are overwritten, the window never grows
+        for _ in 0..100 {
+            hist.record(Duration::from_millis(500));
+        }
+        let s = hist.stats();
+        assert_eq!(s.samples, 100);
+        assert_eq!(s.p50_ms, 500.0);
+    }
+
+    #[test]
+    fn test_monitor_alerts_once_per_breach() {
+        let mut monitor = LatencyMonitor::new(LatencyConfig { window: 100, alert_p95_ms: Some(200), min_samples: 10 });
+        let stats = |samples, p95_ms| LatencyStats { samples, p50_ms: 50.0, p95_ms, p99_ms: p95_ms };
+
+        // Too few samples to judge
+        assert!(monitor.check(&[(Platform::Kalshi, stats(5, 900.0))]).is_empty());
+
+        let slow = [(Platform::Kalshi, stats(50, 350.0)), (Platform::Polymarket, stats(50, 120.0))];
+        assert_eq!(monitor.check(&slow), vec![slow[0]]);
+        assert!(monitor.check(&slow).is_empty(), "still slow, already alerted");
+
+        assert!(monitor.check(&[(Platform::Kalshi, stats(50, 150.0))]).is_empty());
+        assert_eq!(monitor.check(&slow).len(), 1, "re-armed after recovering");
+    }
+}
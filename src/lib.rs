@@ -3,14 +3,30 @@
 //! A high-performance, production-ready arbitrage trading system for cross-platform
 //! prediction markets with real-time price monitoring and execution.
 
+pub mod backtest;
 pub mod cache;
+#[cfg(feature = "charts")]
+pub mod chart;
 pub mod circuit_breaker;
+pub mod clock_skew;
 pub mod config;
 pub mod discovery;
+pub mod error;
+pub mod event_log;
 pub mod execution;
+pub mod feed;
 pub mod kalshi;
+pub mod latency;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod polymarket;
 pub mod polymarket_clob;
 pub mod position_tracker;
+pub mod preflight;
+pub mod rate_limit;
+pub mod shadow;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_sink;
 pub mod telegram;
-pub mod types;
\ No newline at end of file
+pub mod types;
+pub mod webhook;
\ No newline at end of file
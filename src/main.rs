@@ -22,56 +22,128 @@
 //! - **Circuit breaker protection** with configurable risk limits
 //! - **Market discovery system** with intelligent caching and incremental updates
 
+mod backtest;
 mod cache;
+#[cfg(feature = "charts")]
+mod chart;
 mod circuit_breaker;
+mod clock_skew;
 mod config;
 mod discovery;
+mod error;
+mod event_log;
 mod execution;
+mod feed;
 mod kalshi;
+mod latency;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod polymarket;
 mod polymarket_clob;
 mod position_tracker;
+mod preflight;
+mod rate_limit;
+mod shadow;
+#[cfg(feature = "sqlite")]
+mod sqlite_sink;
 mod telegram;
 mod types;
+mod webhook;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{error, info, warn};
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{debug, error, info, warn};
 
 use cache::TeamCache;
-use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
-use config::{ARB_THRESHOLD, ENABLED_LEAGUES, WS_RECONNECT_DELAY_SECS, POLY_ONLY_MODE};
-use discovery::DiscoveryClient;
-use execution::{ExecutionEngine, create_execution_channel, run_execution_loop};
+use circuit_breaker::{BreakerState, CircuitBreaker, CircuitBreakerConfig};
+use config::{AppConfig, ApprovalConfig, ARB_THRESHOLD, BookDepthConfig, ClockSkewConfig, DeliveryAlertConfig, ENABLED_LEAGUES, EventLogConfig, FlattenConfig, HttpConfig, IdleConfig, LatencyConfig, LedgerConfig, LogFormat, NegRiskConfig, PlatformHealthConfig, POLY_CLOB_HOST, POLY_ONLY_MODE, POLYGON_CHAIN_ID, ReconcileConfig, ReconnectConfig, RecordConfig, RetryAlertConfig, ShadowConfig, StaleTicksConfig, Strategy, WebhookConfig};
+use discovery::{DiscoveryClient, PollSchedule, StatusSource};
+use execution::{
+    ExecutionEngine, ExecutionMode, ExecutionReport,
+    create_execution_channel, create_execution_report_channel, create_full_set_channel, create_spread_channel, flatten_all, run_execution_loop, run_trailing_stops,
+};
 use kalshi::{KalshiConfig, KalshiApiClient};
-use polymarket_clob::{PolymarketAsyncClient, PreparedCreds, SharedAsyncClient};
-use position_tracker::{PositionTracker, create_position_channel, position_writer_loop};
-use telegram::{TelegramConfig, TelegramBot, TelegramNotifier, TelegramNotification, PerformanceTracker, create_telegram_channel};
-use types::{GlobalState, PriceCents};
-
-/// Polymarket CLOB API host
-const POLY_CLOB_HOST: &str = "https://clob.polymarket.com";
-/// Polygon chain ID
-const POLYGON_CHAIN_ID: u64 = 137;
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("arb_bot=info".parse().unwrap()),
-        )
-        .init();
+use latency::LatencyMonitor;
+use polymarket::MarketStatus;
+use polymarket_clob::{OrderBookStore, PolymarketAsyncClient, PreparedCreds, SharedAsyncClient};
+use position_tracker::{PositionLimits, PositionTracker, create_position_channel, mark_positions, position_writer_loop, reconcile_with_exchanges};
+use shadow::ShadowBook;
+use telegram::{BotCommand, PnlWindow, TelegramBot, TelegramNotifier, TelegramNotification, PerformanceTracker, PERFORMANCE_FILE, create_telegram_channel};
+use types::{GlobalState, MarketPair, PriceCents, unix_ms};
+
+fn main() -> Result<()> {
+    // .env and the config file go into the environment before the runtime
+    // starts any thread: setting variables while others read them is unsound
+    dotenvy::dotenv().ok();
+    let config_file = config::load_config_file()?;
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(config_file))
+}
+
+async fn run(config_file: Option<(String, usize)>) -> Result<()> {
+    // Initialize logging (.env and the config file are loaded first so RUST_LOG and LOG_FORMAT apply)
+    let filter = tracing_subscriber::EnvFilter::from_default_env()
+        .add_directive("arb_bot=info".parse().unwrap());
+    match LogFormat::from_env() {
+        LogFormat::Pretty => tracing_subscriber::fmt().with_env_filter(filter).init(),
+        // Event fields (market, profit_cents, latency_us...) at the top level next to level and message
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_env_filter(filter)
+            .init(),
+    }
+
+    // Backtest: replay a recording offline instead of trading
+    #[cfg(feature = "backtest")]
+    if let Ok(path) = std::env::var("REPLAY_FILE") {
+        info!("[BACKTEST] Replaying {} (dry run, no network)", path);
+        let report = tokio::task::spawn_blocking(move || backtest::replay_file(std::path::Path::new(&path))).await??;
+        report.log();
+        return Ok(());
+    }
+    #[cfg(not(feature = "backtest"))]
+    if std::env::var("REPLAY_FILE").is_ok() {
+        anyhow::bail!("REPLAY_FILE requires the `backtest` feature (cargo run --release --features backtest)");
+    }
+
+    if let Some((path, applied)) = &config_file {
+        info!("⚙️  Config file {} ({} setting(s) not overridden by the environment)", path, applied);
+    }
+
+    // Preflight: check every integration and exit, without trading
+    let preflight = std::env::args().nth(1).is_some_and(|arg| arg == "preflight")
+        || std::env::var("PREFLIGHT").is_ok_and(|v| v == "1" || v == "true");
+    if preflight {
+        info!("[PREFLIGHT] Checking configuration, exchanges, clock and Telegram (no orders are placed)");
+        let report = preflight::run().await;
+        report.log();
+        if !report.passed() {
+            anyhow::bail!("Preflight failed: {} critical check(s)", report.failures());
+        }
+        return Ok(());
+    }
+    let app_config = AppConfig::from_env()?;
+    let config_summary = app_config.summary();
 
     info!("🚀 Prediction Market Arbitrage System v2.0");
     info!("   Profit threshold: <{:.1}¢ ({:.1}% minimum profit)",
           ARB_THRESHOLD * 100.0, (1.0 - ARB_THRESHOLD) * 100.0);
     info!("   Monitored leagues: {:?}", ENABLED_LEAGUES);
+    info!("   Min profit: {:.1}¢/contract, match threshold {:.2}",
+          app_config.fees.min_profit_cents, app_config.matching.threshold);
+    if let Some(cents) = app_config.risk.max_daily_loss_cents {
+        info!("   Daily loss limit: ${:.2}", cents as f64 / 100.0);
+    }
 
     // Check for dry run mode
-    let dry_run = std::env::var("DRY_RUN").map(|v| v == "1" || v == "true").unwrap_or(true);
+    let execution_mode = ExecutionMode::from_env();
+    let dry_run = execution_mode == ExecutionMode::DryRun;
     if dry_run {
         info!("   Mode: DRY RUN (set DRY_RUN=0 to execute)");
     } else {
@@ -86,32 +158,72 @@ async fn main() -> Result<()> {
     }
 
     // Initialize Telegram notifications
-    let telegram_notifier = if let Some(tg_config) = TelegramConfig::from_env() {
+    let (telegram_notifier, mut telegram_commands, telegram_handles, telegram_approvals) = if let Some(tg_config) = app_config.telegram {
         info!("📱 Telegram notifications enabled");
+        let enabled = tg_config.enabled;
+        let routes = tg_config.routes_from_env();
+        // Commands are only read from the default bot
         let bot = TelegramBot::new(tg_config);
-        let channel = create_telegram_channel(bot);
-        TelegramNotifier::new(Some(channel))
+        let commands = if enabled { Some(bot.poll_commands()) } else { None };
+        // Approval buttons are answered through the command loop
+        let approvals = if enabled { Some(bot.serve_approvals()) } else { None };
+        let (channel, handle) = create_telegram_channel(bot);
+        let mut notifier = TelegramNotifier::new(Some(channel));
+        let mut handles = vec![handle];
+        for route in routes {
+            info!("📱 Telegram route: {:?} → {} chat(s)", route.categories, route.config.chat_ids.len());
+            let (channel, handle) = create_telegram_channel(TelegramBot::new(route.config));
+            for category in route.categories {
+                notifier = notifier.with_route(category, channel.clone());
+            }
+            handles.push(handle);
+        }
+        (notifier, commands, handles, approvals)
     } else {
         info!("📱 Telegram notifications disabled (set TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID to enable)");
-        TelegramNotifier::none()
+        (TelegramNotifier::none(), None, Vec::new(), None)
     };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    // Critical operations whose retries ran out, beyond their log line
+    let retry_alerts = RetryAlertConfig::from_env();
+    if let Some(mut exhausted) = error::subscribe_exhausted(Duration::from_secs(retry_alerts.cooldown_secs)) {
+        let retry_telegram = telegram_notifier.clone();
+        let mut shutdown = shutdown_rx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.wait_for(|&stop| stop) => return,
+                    failure = exhausted.recv() => match failure {
+                        Some(failure) => retry_telegram.notify(TelegramNotification::Error {
+                            message: format!("Retries exhausted: {}", failure),
+                        }),
+                        None => return,
+                    },
+                }
+            }
+        });
+    }
+    if let Some(min_notional) = ApprovalConfig::from_env().min_notional {
+        if telegram_approvals.is_some() {
+            info!("🙋 Live trades above ${:.2} wait for Telegram approval", min_notional);
+        } else {
+            warn!("🙋 TRADE_APPROVAL_MIN_NOTIONAL is set but Telegram is disabled: trades above ${:.2} will be skipped", min_notional);
+        }
+    }
 
     // Load Kalshi credentials (only if not in POLY_ONLY mode)
-    let kalshi_config = if !POLY_ONLY_MODE {
-        let config = KalshiConfig::from_env()?;
-        info!("[KALSHI] API key loaded");
+    let kalshi_config = if let Some(config) = app_config.kalshi {
+        config.verify_key()?;
+        info!("[KALSHI] API key loaded ({} environment)", config.environment);
         Some(config)
     } else {
         info!("[KALSHI] Skipped (POLY_ONLY_MODE enabled)");
         None
     };
 
-    // Load Polymarket credentials
-    dotenvy::dotenv().ok();
-    let poly_private_key = std::env::var("POLY_PRIVATE_KEY")
-        .context("POLY_PRIVATE_KEY not set")?;
-    let poly_funder = std::env::var("POLY_FUNDER")
-        .context("POLY_FUNDER not set (your wallet address)")?;
+    // Polymarket credentials
+    let poly_private_key = app_config.poly_private_key;
+    let poly_funder = app_config.poly_funder;
 
     // Create async Polymarket client and derive API credentials
     info!("[POLYMARKET] Creating async client and deriving API credentials...");
@@ -138,11 +250,15 @@ async fn main() -> Result<()> {
     info!("📂 Loaded {} team code mappings", team_cache.len());
 
     // Create Kalshi API client (only if not in POLY_ONLY mode)
-    let kalshi_api = if let Some(ref config) = kalshi_config {
-        Some(Arc::new(KalshiApiClient::new(config.clone())))
-    } else {
-        None
-    };
+    let kalshi_api = kalshi_config.as_ref()
+        .map(|config| Arc::new(KalshiApiClient::new(config.clone())));
+
+    // Fail fast if the key belongs to a different environment
+    if let Some(api) = &kalshi_api {
+        let balance = api.validate_credentials().await?;
+        info!("[KALSHI] Credentials verified on {} (balance: ${:.2})",
+              api.config.environment, balance as f64 / 100.0);
+    }
 
     // Run discovery (with caching support)
     let force_discovery = std::env::var("FORCE_DISCOVERY")
@@ -155,16 +271,23 @@ async fn main() -> Result<()> {
     let discovery = DiscoveryClient::new(
         KalshiApiClient::new(KalshiConfig::from_env()?),
         team_cache
-    );
+    ).with_lookup_warmup(Duration::from_secs(app_config.poll.warmup_secs));
 
     let result = if force_discovery {
         discovery.discover_all_force(ENABLED_LEAGUES).await
     } else {
         discovery.discover_all(ENABLED_LEAGUES).await
     };
+    let gamma_cache = discovery.gamma_cache();
+    gamma_cache.spawn_sweeper();
 
     info!("📊 Market discovery complete:");
     info!("   - Matched market pairs: {}", result.pairs.len());
+    let lookups = gamma_cache.stats();
+    if lookups.hits + lookups.misses + polymarket::uncached_lookups() > 0 {
+        info!("   - Gamma lookups: {} hot (uncached), {} cache hits, {} cache misses",
+              polymarket::uncached_lookups(), lookups.hits, lookups.misses);
+    }
 
     if !result.errors.is_empty() {
         for err in &result.errors {
@@ -172,6 +295,14 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Resolved configuration, logged once the pair count is known
+    let platforms = if POLY_ONLY_MODE { "polymarket" } else { "kalshi,polymarket" };
+    info!(pairs = result.pairs.len(), platforms, strategy = ?Strategy::from_env(), mode = ?execution_mode,
+          "[CONFIG] Effective configuration: {} pair(s) on {}", result.pairs.len(), platforms);
+    for (section, value) in &config_summary {
+        info!(section = *section, "[CONFIG]   {}: {}", section, value);
+    }
+
     if result.pairs.is_empty() {
         error!("No market pairs found!");
         return Ok(());
@@ -186,6 +317,19 @@ async fn main() -> Result<()> {
               pair.kalshi_market_ticker);
     }
 
+    // Pairs matched for the first time only trade once their probation is over
+    let shadow = ShadowBook::load(ShadowConfig::from_env());
+    let enrolled = shadow.enroll(result.pairs.iter().map(|p| &*p.pair_id), unix_ms());
+    if shadow.enabled() {
+        info!("👻 {} new pairs on probation, {} in total ({} observations before trading)",
+              enrolled, shadow.on_probation(), shadow.observations_required());
+    }
+
+    // Polymarket neg-risk events, traded as full sets
+    let multi_outcome = discovery::discover_multi_outcome(
+        &polymarket::GammaClient::new(), &NegRiskConfig::from_env().events,
+    ).await;
+
     // Build global state
     let markets_count = result.pairs.len();
     let state = Arc::new({
@@ -193,7 +337,11 @@ async fn main() -> Result<()> {
         for pair in result.pairs {
             s.add_pair(pair);
         }
-        info!("📡 Global state initialized: tracking {} markets", s.market_count());
+        for market in multi_outcome {
+            s.add_multi_outcome(market);
+        }
+        info!("📡 Global state initialized: tracking {} markets, {} multi-outcome events",
+              s.market_count(), s.multi_outcome.len());
         s
     });
 
@@ -204,30 +352,170 @@ async fn main() -> Result<()> {
         mode: format!("{} - {}", mode_str, platform_str),
         markets_count,
     });
+    let auth_telegram = telegram_notifier.clone();
+    poly_async.on_auth_failure(move |e| auth_telegram.notify(TelegramNotification::Error {
+        message: format!("Polymarket API credentials could not be renewed: {}", e),
+    }));
 
     // Initialize execution infrastructure
     let (exec_tx, exec_rx) = create_execution_channel();
+    let (full_set_tx, full_set_rx) = create_full_set_channel();
+    let (spread_tx, spread_rx) = create_spread_channel();
     let circuit_breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig::from_env()));
 
-    let position_tracker = Arc::new(RwLock::new(PositionTracker::new()));
+    // Restore persisted positions and check them against the exchanges
+    let position_tracker = Arc::new(RwLock::new({
+        let mut tracker = PositionTracker::load();
+        tracker.set_limits(PositionLimits::load(app_config.position_limits));
+        tracker.set_ledger(LedgerConfig::from_env().path.map(Into::into));
+        tracker
+    }));
+    let reconcile_config = ReconcileConfig::from_env();
+    if reconcile_config.enabled {
+        match reconcile_with_exchanges(&position_tracker, kalshi_api.as_deref(), &poly_async, &state, reconcile_config).await {
+            Ok(report) => telegram_notifier.notify(TelegramNotification::PositionsReconciled {
+                matched: report.matched,
+                adopted: report.adopted,
+                corrected: report.corrected,
+                dropped: report.dropped,
+                unconfirmed: report.unconfirmed,
+            }),
+            Err(e) => {
+                warn!("[POSITIONS] Reconciliation failed, keeping persisted positions: {}", e);
+                telegram_notifier.notify(TelegramNotification::Error {
+                    message: format!("Position reconciliation failed: {}", e),
+                });
+            }
+        }
+    }
     let (position_channel, position_rx) = create_position_channel();
+    let (report_tx, mut report_rx) = create_execution_report_channel();
+    let trade_webhook = webhook::spawn(WebhookConfig::from_env());
 
-    tokio::spawn(position_writer_loop(position_rx, position_tracker));
+    // Telegram failing: the webhook is the only channel left
+    let delivery_webhook = trade_webhook.clone();
+    tokio::spawn(telegram::watch_delivery(DeliveryAlertConfig::from_env(), move |rate, sends| {
+        if let Some(webhook) = &delivery_webhook {
+            webhook.alert("telegram_failing", format!("{:.0}% of the last {} Telegram notifications failed", rate * 100.0, sends));
+        }
+    }));
+
+    let mut position_writer_handle = tokio::spawn(position_writer_loop(position_rx, position_tracker.clone()));
 
     let threshold_cents: PriceCents = ((ARB_THRESHOLD * 100.0).round() as u16).max(1);
     info!("   Execution threshold: {} cents", threshold_cents);
+    info!("   Order type: {}", config::default_order_type());
+    info!("   Strategy: {:?}", Strategy::from_env());
+    for (name, http) in [("Kalshi", HttpConfig::kalshi_from_env()), ("Polymarket", HttpConfig::poly_from_env()), ("Telegram", HttpConfig::telegram_from_env()), ("Webhook", HttpConfig::webhook_from_env())] {
+        if let Some(proxy) = http.proxy_host() {
+            info!("   {} requests via proxy {}", name, proxy);
+        }
+    }
+
 
+    let poly_books = Arc::new(OrderBookStore::new());
+    let kalshi_books = Arc::new(OrderBookStore::new());
 
-    let engine = Arc::new(ExecutionEngine::new(
+    // Clients for the /flatten kill switch
+    let flatten_kalshi = kalshi_api.clone();
+    let flatten_poly = poly_async.clone();
+
+    let engine = ExecutionEngine::new(
         kalshi_api.clone(),
-        poly_async,
+        Some(poly_async),
+        poly_books.clone(),
         state.clone(),
         circuit_breaker.clone(),
         position_channel,
-        dry_run,
+        position_tracker.clone(),
+        report_tx,
+        execution_mode,
+    );
+    let engine = match telegram_approvals {
+        Some(approvals) => engine.with_approvals(approvals),
+        None => engine,
+    }.with_shadow(shadow.clone()).with_kalshi_books(kalshi_books.clone())
+        .with_risk_and_fees(app_config.risk, app_config.fees.clone());
+    // The WebSocket subscriptions are spread over the poll warm-up: the books wait for them
+    let warmup_config = config::WarmupConfig {
+        timeout_secs: config::WarmupConfig::from_env().timeout_secs.map(|secs| secs + app_config.poll.warmup_secs),
+    };
+    let warmup = Arc::new(discovery::Warmup::new(warmup_config, unix_ms()));
+    let platform_health = Arc::new(discovery::PlatformHealth::new(PlatformHealthConfig::from_env()));
+    let engine = engine.with_warmup(warmup.clone()).with_platform_health(platform_health.clone());
+    let decisions = event_log::spawn(EventLogConfig::from_env());
+    #[cfg(feature = "sqlite")]
+    let sqlite = sqlite_sink::spawn(config::SqliteConfig::from_env());
+    #[cfg(feature = "sqlite")]
+    let decisions = match &sqlite {
+        Some(sink) => Some(sink.tee(decisions)),
+        None => decisions,
+    };
+    let engine = Arc::new(match decisions {
+        Some(events) => engine.with_event_log(events),
+        None => engine,
+    });
+
+    let risk_guard = engine.risk_guard();
+
+    // Host clock against each exchange, at startup and then periodically
+    let clock_config = ClockSkewConfig::from_env();
+    let mut clock_probes = vec![(types::Platform::Polymarket, format!("{}/time", POLY_CLOB_HOST))];
+    if let Some(config) = &kalshi_config {
+        clock_probes.push((types::Platform::Kalshi, format!("{}/exchange/status", config.environment.api_base())));
+    }
+    let clock_telegram = telegram_notifier.clone();
+    tokio::spawn(clock_skew::monitor(
+        engine.clock_skew(),
+        clock_probes,
+        Duration::from_secs(clock_config.check_interval_secs),
+        move |platform, skew_ms| clock_telegram.notify(TelegramNotification::Error {
+            message: format!("Host clock {:+}ms off {} (limit {}ms): no orders are sent to {} until the clock is fixed",
+                             skew_ms, platform, clock_config.max_skew_ms.unwrap_or_default(), platform),
+        }),
+        shutdown_rx.clone(),
     ));
+    if Strategy::from_env() == Strategy::SpreadCapture {
+        tokio::spawn(run_trailing_stops(engine.clone(), config::TrailingStopConfig::from_env(), shutdown_rx.clone()));
+    }
+    let mut exec_handle = tokio::spawn(run_execution_loop(exec_rx, full_set_rx, spread_rx, engine, shutdown_rx.clone()));
 
-    let exec_handle = tokio::spawn(run_execution_loop(exec_rx, engine));
+    // Price feeds go through the post-trade cooldown and the fee check on their way to execution
+    let cooldowns = discovery::TradeCooldowns::new(position_tracker.clone(), app_config.cooldown);
+    let disabled_markets = Arc::new(discovery::DisabledMarkets::load());
+    let disabled = disabled_markets.list();
+    if !disabled.is_empty() {
+        info!("⛔ {} market(s) disabled until /enable: {}", disabled.len(), disabled.join(", "));
+    }
+    let idle = Arc::new(discovery::IdleWatchdog::new(IdleConfig::from_env(), unix_ms()));
+    let stale_ticks = Arc::new(discovery::StaleTicks::new(StaleTicksConfig::from_env()));
+    let (opportunity_tx, opportunity_rx) = create_execution_channel();
+    tokio::spawn(discovery::screen_opportunities(
+        state.clone(), cooldowns.clone(), disabled_markets.clone(), platform_health.clone(), stale_ticks.clone(), BookDepthConfig::from_env(), app_config.fees.clone(), idle.clone(), opportunity_rx, exec_tx.clone(),
+    ));
+    if let Some(after) = idle.alert_after() {
+        info!("💤 Idle alert after {}s without any opportunity", after.as_secs());
+    }
+    if !warmup.is_done() {
+        info!("⏳ Trading held until every market's books are in (at most {}s)", warmup_config.timeout_secs.unwrap_or_default());
+    }
+    let warmup_telegram = telegram_notifier.clone();
+    tokio::spawn(discovery::watch_warmup(warmup, state.clone(), poly_books.clone(), kalshi_config.is_some(), move |done| {
+        warmup_telegram.notify(TelegramNotification::WarmupComplete {
+            ready: done.ready,
+            total: done.total,
+            elapsed_secs: done.elapsed.as_secs_f64(),
+            timed_out: done.timed_out,
+        })
+    }));
+    if let Some(max_ticks) = stale_ticks.max_ticks() {
+        info!("🧊 Markets stale after {} status polls without a book update", max_ticks);
+    }
+    let idle_telegram = telegram_notifier.clone();
+    tokio::spawn(discovery::watch_idle(idle.clone(), move |idle| idle_telegram.notify(TelegramNotification::NoOpportunities {
+        idle_secs: idle.as_secs(),
+        markets: markets_count,
+    }), shutdown_rx.clone()));
 
     // === TEST MODE: Synthetic arbitrage injection ===
     // TEST_ARB=1 to enable, TEST_ARB_TYPE=poly_yes_kalshi_no|kalshi_yes_poly_no|poly_only|kalshi_only
@@ -235,13 +523,14 @@ async fn main() -> Result<()> {
     if test_arb {
         let test_state = state.clone();
         let test_exec_tx = exec_tx.clone();
+        let test_books = poly_books.clone();
         let test_dry_run = dry_run;
 
         // Parse arb type from environment (default: poly_yes_kalshi_no)
         let arb_type_str = std::env::var("TEST_ARB_TYPE").unwrap_or_else(|_| "poly_yes_kalshi_no".to_string());
 
         tokio::spawn(async move {
-            use types::{FastExecutionRequest, ArbType};
+            use types::{FastExecutionRequest, ArbType, fxhash_str};
 
             // Wait for WebSocket connections to establish and populate orderbooks
             info!("[TEST] Injecting synthetic arbitrage opportunity in 10 seconds...");
@@ -282,8 +571,18 @@ async fn main() -> Result<()> {
                             no_size: 1000,   // 1000¢ = 10 contracts
                             arb_type,
                             detected_ns: 0,
+                            snapshot_ms: 0,
+                            order_type: None,
                         };
 
+                        // Seed the Polymarket books so the depth check sees the synthetic prices
+                        if matches!(arb_type, ArbType::PolyYesKalshiNo | ArbType::PolyOnly) {
+                            test_books.apply_snapshot(fxhash_str(&pair.poly_yes_token), [], [(yes_price as u64 * 100, 10.0)]);
+                        }
+                        if matches!(arb_type, ArbType::KalshiYesPolyNo | ArbType::PolyOnly) {
+                            test_books.apply_snapshot(fxhash_str(&pair.poly_no_token), [], [(no_price as u64 * 100, 10.0)]);
+                        }
+
                         warn!("[TEST] 🧪 Injecting synthetic {:?} arbitrage for: {}", arb_type, pair.description);
                         warn!("[TEST]    Scenario: {}", description);
                         warn!("[TEST]    Position size capped to 10 contracts for safety");
@@ -299,20 +598,33 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Subscriptions ramp up over the warm-up; reconnects after it subscribe at once
+    let subscribe_warmup_end = tokio::time::Instant::now() + Duration::from_secs(app_config.poll.warmup_secs);
+    let subscribe_warmup = move || subscribe_warmup_end.saturating_duration_since(tokio::time::Instant::now());
+
     // Initialize Kalshi WebSocket connection (only if not in POLY_ONLY mode)
-    let kalshi_handle = if !POLY_ONLY_MODE {
+    let kalshi_handle = if let Some(kalshi_ws_config) = kalshi_config.clone() {
         let kalshi_state = state.clone();
-        let kalshi_exec_tx = exec_tx.clone();
+        let kalshi_exec_tx = opportunity_tx.clone();
         let kalshi_threshold = threshold_cents;
-        let kalshi_ws_config = KalshiConfig::from_env()?;
-        Some(tokio::spawn(async move {
-            loop {
-                if let Err(e) = kalshi::run_ws(&kalshi_ws_config, kalshi_state.clone(), kalshi_exec_tx.clone(), kalshi_threshold).await {
-                    error!("[KALSHI] WebSocket disconnected: {} - reconnecting...", e);
-                }
-                tokio::time::sleep(tokio::time::Duration::from_secs(WS_RECONNECT_DELAY_SECS)).await;
-            }
-        }))
+        let kalshi_books = kalshi_books.clone();
+        let kalshi_feed_telegram = telegram_notifier.clone();
+        let kalshi_ws_config = Arc::new(kalshi_ws_config);
+        Some(tokio::spawn(feed::supervise(
+            types::Platform::Kalshi,
+            ReconnectConfig::kalshi_from_env(),
+            circuit_breaker.clone(),
+            move |down| kalshi_feed_telegram.notify(TelegramNotification::FeedDown {
+                platform: types::Platform::Kalshi,
+                down_secs: down.as_secs(),
+            }),
+            move |link| {
+                let (config, state, books, exec_tx) =
+                    (kalshi_ws_config.clone(), kalshi_state.clone(), kalshi_books.clone(), kalshi_exec_tx.clone());
+                let warmup = subscribe_warmup();
+                async move { kalshi::run_ws(&config, state, books, exec_tx, kalshi_threshold, warmup, link).await }
+            },
+        )))
     } else {
         info!("[KALSHI] WebSocket skipped (POLY_ONLY_MODE enabled)");
         None
@@ -320,14 +632,101 @@ async fn main() -> Result<()> {
 
     // Initialize Polymarket WebSocket connection
     let poly_state = state.clone();
-    let poly_exec_tx = exec_tx.clone();
+    let poly_exec_tx = opportunity_tx.clone();
     let poly_threshold = threshold_cents;
-    let poly_handle = tokio::spawn(async move {
-        loop {
-            if let Err(e) = polymarket::run_ws(poly_state.clone(), poly_exec_tx.clone(), poly_threshold).await {
-                error!("[POLYMARKET] WebSocket disconnected: {} - reconnecting...", e);
+    let heartbeat_books = poly_books.clone();
+    let resolution_books = poly_books.clone();
+    let record_books = poly_books.clone();
+    match Strategy::from_env() {
+        Strategy::Arbitrage => tokio::spawn(discovery::watch_full_set_arbs(state.clone(), poly_books.clone(), disabled_markets.clone(), platform_health.clone(), stale_ticks.clone(), idle.clone(), full_set_tx)),
+        Strategy::SpreadCapture => tokio::spawn(discovery::watch_spreads(state.clone(), poly_books.clone(), cooldowns, disabled_markets.clone(), platform_health.clone(), stale_ticks.clone(), idle, spread_tx)),
+    };
+    let poly_feed_telegram = telegram_notifier.clone();
+    let poly_handle = tokio::spawn(feed::supervise(
+        types::Platform::Polymarket,
+        ReconnectConfig::poly_from_env(),
+        circuit_breaker.clone(),
+        move |down| poly_feed_telegram.notify(TelegramNotification::FeedDown {
+            platform: types::Platform::Polymarket,
+            down_secs: down.as_secs(),
+        }),
+        move |link| polymarket::run_ws(poly_state.clone(), poly_books.clone(), poly_exec_tx.clone(), poly_threshold, subscribe_warmup(), link),
+    ));
+
+    // Optional order-book recording for backtests
+    tokio::spawn(backtest::record_loop(RecordConfig::from_env(), state.clone(), record_books));
+
+    // Market status pollers, one per platform so a slow API never delays the other
+    let poll_config = app_config.poll;
+    let (status_tx, mut status_rx) = mpsc::channel(64);
+    let mut status_sources = vec![StatusSource::Polymarket(polymarket::GammaClient::new())];
+    if let Some(api) = &kalshi_api {
+        status_sources.push(StatusSource::Kalshi(api.clone()));
+    }
+    let poll_handles: Vec<_> = status_sources.into_iter().map(|source| {
+        let platform = source.platform();
+        let base = Duration::from_secs(poll_config.interval_secs(platform));
+        let fast = poll_config.watchlist_interval_secs.map(Duration::from_secs);
+        let markets = state.markets.iter()
+            .filter_map(|m| m.pair.as_ref().map(|pair| (m.market_id, poll_config.watches(pair))));
+        let now = tokio::time::Instant::now();
+        let schedule = PollSchedule::new(base, fast, markets, now)
+            .staggered(Duration::from_secs(poll_config.warmup_secs), now);
+        info!("[DISCOVERY] {} status polling: {} markets every {}s ({} watchlisted every {}s), first polls spread over {}s",
+              platform, schedule.markets(), base.as_secs(), schedule.watched(),
+              fast.filter(|f| *f < base).unwrap_or(base).as_secs(), poll_config.warmup_secs);
+        let (health_positions, health_telegram) = (position_tracker.clone(), telegram_notifier.clone());
+        let on_health = move |down: bool| {
+            let (positions, telegram) = (health_positions.clone(), health_telegram.clone());
+            tokio::spawn(async move {
+                let unmanaged = if down { positions.read().await.open_on(platform) } else { Vec::new() };
+                if down {
+                    warn!("[DISCOVERY] 🚧 {} unreachable, degraded mode: its pairs are skipped, {} position(s) unmanaged until it recovers",
+                          platform, unmanaged.len());
+                } else {
+                    info!("[DISCOVERY] ✅ {} reachable again, degraded mode over", platform);
+                }
+                telegram.notify(TelegramNotification::PlatformDegraded { platform, down, unmanaged });
+            });
+        };
+        let stale_telegram = telegram_notifier.clone();
+        let on_stale = move |pair: &MarketPair, ticks: u32, silent: Duration| {
+            stale_telegram.notify(TelegramNotification::MarketStale {
+                market: pair.description.to_string(),
+                ticks,
+                silent_secs: silent.as_secs(),
+            })
+        };
+        tokio::spawn(discovery::poll_market_status(
+            source, schedule, state.clone(), platform_health.clone(), on_health, stale_ticks.clone(), on_stale, status_tx.clone(),
+            shutdown_rx.clone(),
+        ))
+    }).collect();
+    drop(status_tx);
+
+    // Resolution handler: stop quoting closed markets and settle positions
+    let resolution_state = state.clone();
+    let resolution_positions = position_tracker.clone();
+    let resolution_telegram = telegram_notifier.clone();
+    let resolution_handle = tokio::spawn(async move {
+        // Closed markets awaiting resolution; settled ones are dropped
+        let mut halted = std::collections::HashSet::new();
+        while let Some((platform, market_id, status)) = status_rx.recv().await {
+            let Some(pair) = resolution_state.get_by_id(market_id).and_then(|m| m.pair.clone()) else { continue };
+            if halted.insert(market_id) {
+                info!("[DISCOVERY] Market closed on {}, no longer quoting: {}", platform, pair.description);
+                polymarket::halt_market(&resolution_state, &resolution_books, market_id);
+            }
+            let MarketStatus::Resolved { yes_won } = status else { continue };
+            halted.remove(&market_id);
+            if let Some(settlement) = resolution_positions.write().await.settle(&pair.pair_id, yes_won) {
+                resolution_telegram.notify(TelegramNotification::PositionSettled {
+                    market: settlement.description,
+                    yes_won: settlement.yes_won,
+                    contracts: settlement.contracts,
+                    realized_pnl: settlement.realized_pnl,
+                });
             }
-            tokio::time::sleep(tokio::time::Duration::from_secs(WS_RECONNECT_DELAY_SECS)).await;
         }
     });
 
@@ -336,29 +735,103 @@ async fn main() -> Result<()> {
     let heartbeat_threshold = threshold_cents;
     let poly_only_mode = POLY_ONLY_MODE;
     let heartbeat_telegram = telegram_notifier.clone();
-    let heartbeat_handle = tokio::spawn(async move {
+    let heartbeat_cb = circuit_breaker.clone();
+    let flatten_health = platform_health.clone();
+    let mut cb_state_rx = circuit_breaker.subscribe();
+    let heartbeat_positions = position_tracker.clone();
+    let heartbeat_disabled = disabled_markets.clone();
+    let gamma_cache = gamma_cache.clone();
+
+    // Performance stats: updated by the heartbeat, read by the metrics endpoint
+    let daily_reset_hour = config::daily_reset_hour();
+    let mut perf_tracker = PerformanceTracker::load_from_path(PERFORMANCE_FILE, daily_reset_hour)
+        .unwrap_or_else(|e| {
+            info!("[PERF] No usable stats file ({}), starting fresh", e);
+            PerformanceTracker::new(daily_reset_hour)
+        });
+    perf_tracker.lifetime_uptime = std::env::var("PERF_LIFETIME_UPTIME")
+        .map(|v| v == "1" || v == "true")
+        .unwrap_or(false);
+    // A restart on a day already past the loss limit stays halted
+    let now = chrono::Utc::now();
+    if let Some(resume_at) = risk_guard.check_daily_loss(perf_tracker.daily_pnl_at(now), now) {
+        telegram_notifier.notify(TelegramNotification::DailyLossLimitHit {
+            loss_cents: -perf_tracker.daily_pnl_at(now),
+            limit_cents: risk_guard.limits().max_daily_loss_cents.unwrap_or_default(),
+            resume_at,
+        });
+    }
+    let perf_tracker = Arc::new(std::sync::Mutex::new(perf_tracker));
+
+    #[cfg(feature = "metrics")]
+    {
+        let sources = metrics::MetricsSources {
+            performance: perf_tracker.clone(),
+            positions: position_tracker.clone(),
+            circuit_breaker: circuit_breaker.clone(),
+            state: state.clone(),
+            gamma_cache: gamma_cache.clone(),
+            health: config::HealthConfig::from_env(),
+            platforms: if POLY_ONLY_MODE {
+                vec![types::Platform::Polymarket]
+            } else {
+                vec![types::Platform::Kalshi, types::Platform::Polymarket]
+            },
+        };
+        let addr = config::metrics_bind_addr()?;
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr, sources).await {
+                error!("[METRICS] Endpoint stopped: {:#}", e);
+            }
+        });
+    }
+
+    let heartbeat_perf = perf_tracker.clone();
+    let mut heartbeat_handle = tokio::spawn(async move {
         use crate::types::kalshi_fee_cents;
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
         // Telegram status report every 30 minutes
         let mut telegram_interval = tokio::time::interval(tokio::time::Duration::from_secs(1800));
-        let mut perf_tracker = PerformanceTracker::new();
+        let perf = || heartbeat_perf.lock().unwrap_or_else(|e| e.into_inner());
+        let mut latency_monitor = LatencyMonitor::new(LatencyConfig::from_env());
         
         loop {
             tokio::select! {
                 _ = interval.tick() => {
+                    if let Err(e) = perf().save_to_path(PERFORMANCE_FILE) {
+                        warn!("[PERF] Failed to save stats: {}", e);
+                    }
+
+                    let latency = latency::snapshot();
+                    for (platform, stats) in latency_monitor.check(&latency) {
+                        let threshold_ms = latency_monitor.threshold_ms().unwrap_or_default();
+                        warn!("[LATENCY] {} p95 {:.0}ms over {}ms (p50 {:.0}ms, p99 {:.0}ms, {} requests)",
+                              platform, stats.p95_ms, threshold_ms, stats.p50_ms, stats.p99_ms, stats.samples);
+                        heartbeat_telegram.notify(TelegramNotification::HighLatency { platform, stats, threshold_ms });
+                    }
+                    for (platform, s) in latency.iter().filter(|(_, s)| s.samples > 0) {
+                        debug!("[LATENCY] {} p50={:.0}ms p95={:.0}ms p99={:.0}ms ({} requests)",
+                               platform, s.p50_ms, s.p95_ms, s.p99_ms, s.samples);
+                    }
+
                     let market_count = heartbeat_state.market_count();
                     let mut with_kalshi = 0;
                     let mut with_poly = 0;
                     let mut with_both = 0;
+                    let mut crossed = 0;
+                    let mut stale = 0;
                     // Track best arbitrage opportunity
                     // For POLY_ONLY: (total_cost, market_id, p_yes, p_no)
                     // For cross-platform: (total_cost, market_id, p_yes, k_no, k_yes, p_no, fee, is_poly_yes_kalshi_no)
+                    #[allow(clippy::type_complexity)]
                     let mut best_arb: Option<(u16, u16, u16, u16, u16, u16, u16, bool)> = None;
                     let mut best_poly_only_arb: Option<(u16, u16, u16, u16)> = None;
 
                     for market in heartbeat_state.markets.iter().take(market_count) {
-                        let (k_yes, k_no, _, _) = market.kalshi.load();
-                        let (p_yes, p_no, _, _) = market.poly.load();
+                        let (k_yes, k_no, _, _) = market.kalshi.quotes();
+                        let (p_yes, p_no, _, _) = market.poly.quotes();
+                        crossed += (market.kalshi.is_crossed() || market.poly.is_crossed()) as usize;
+                        stale += (market.kalshi.is_stale() || market.poly.is_stale()) as usize;
                         let has_k = k_yes > 0 && k_no > 0;
                         let has_p = p_yes > 0 && p_no > 0;
                         if k_yes > 0 || k_no > 0 { with_kalshi += 1; }
@@ -441,29 +914,396 @@ async fn main() -> Result<()> {
                     warn!("   ⚠️  No markets with both Kalshi and Polymarket prices - verify WebSocket connections");
                 }
             }
+                    if crossed > 0 {
+                        warn!("   ⚠️  {} market(s) with a crossed or locked book ({} stale) - not traded until it clears", crossed, stale);
+                    }
                 }
                 
                 // Telegram periodic status report (every 30 minutes)
                 _ = telegram_interval.tick() => {
+                    let cache_stats = gamma_cache.stats();
+                    let marks = mark_positions(&mut *heartbeat_positions.write().await, &heartbeat_state, &heartbeat_books);
+                    let max_open_positions = heartbeat_positions.read().await.max_open_positions();
+                    let mut perf_tracker = perf();
+                    let now = chrono::Utc::now();
+                    let (total, today) = (perf_tracker.pnl(PnlWindow::All, now), perf_tracker.pnl(PnlWindow::Today, now));
                     heartbeat_telegram.notify(TelegramNotification::StatusUpdate {
                         uptime_hours: perf_tracker.uptime_hours(),
-                        total_trades: perf_tracker.total_trades,
-                        successful_trades: perf_tracker.successful_trades,
-                        total_profit_cents: perf_tracker.total_profit_cents,
+                        total_trades: total.trades,
+                        successful_trades: total.successful_trades,
+                        cancelled_orders: perf_tracker.cancelled_orders,
+                        declined_trades: perf_tracker.declined_trades,
+                        total_profit_cents: total.profit_cents,
+                        today_trades: today.trades,
+                        today_successful_trades: today.successful_trades,
+                        today_profit_cents: today.profit_cents,
                         markets_monitored: heartbeat_state.market_count(),
+                        paper_trades: perf_tracker.paper_trades,
+                        paper_profit_cents: perf_tracker.paper_profit_cents,
+                        top_markets: perf_tracker.top_markets(3),
+                        cache_hits: cache_stats.hits,
+                        cache_misses: cache_stats.misses,
+                        unrealized_pnl: marks.total,
+                        open_positions: marks.positions.len(),
+                        stale_positions: marks.stale_positions,
+                        max_open_positions,
+                        telegram_sent: telegram::delivery().sent(),
+                        telegram_failed: telegram::delivery().failed(),
+                        profit_history: perf_tracker.profit_series(),
+                        latency: latency::snapshot(),
+                        periodic: true,
                     });
                 }
+
+                // Circuit breaker transitions
+                Ok(()) = cb_state_rx.changed() => {
+                    let state = *cb_state_rx.borrow_and_update();
+                    match state {
+                        BreakerState::Open => {
+                            let reason = heartbeat_cb.status().await.trip_reason
+                                .map(|r| r.to_string())
+                                .unwrap_or_else(|| "Unknown".to_string());
+                            heartbeat_telegram.notify(TelegramNotification::CircuitBreakerTripped {
+                                reason,
+                                cooldown_secs: heartbeat_cb.cooldown_secs(),
+                            });
+                        }
+                        BreakerState::Closed => {
+                            heartbeat_telegram.notify(TelegramNotification::CircuitBreakerReset);
+                        }
+                        BreakerState::HalfOpen => {
+                            info!("[CB] Half-open: waiting for probe trade");
+                        }
+                    }
+                }
+
+                // Opportunities and trades from the execution engine
+                // (the channel closes once the engine has shut down)
+                report = report_rx.recv() => match report {
+                    None => break,
+                    Some(ExecutionReport::Opportunity(opp)) => {
+                        perf().record_opportunity();
+                        heartbeat_telegram.notify(TelegramNotification::OpportunityDetected {
+                            market: opp.market,
+                            yes_price: opp.yes_price,
+                            no_price: opp.no_price,
+                            profit_cents: opp.profit_cents,
+                            arb_type: format!("{:?}", opp.arb_type),
+                            executable_profit_cents: opp.value.executable_profit_cents.round() as i64,
+                            contracts: opp.value.contracts,
+                            confidence: opp.value.confidence,
+                        });
+                    }
+                    Some(ExecutionReport::Trade(report)) => {
+                        if let Some(webhook) = &trade_webhook {
+                            webhook.send(&report);
+                        }
+                        #[cfg(feature = "sqlite")]
+                        if let Some(sink) = &sqlite {
+                            sink.trade(&report);
+                        }
+                        if report.simulated {
+                            perf().record_paper_trade(report.success, report.profit_cents);
+                        } else if !report.success && report.cancelled_orders > 0 {
+                            // Expired before anything hedged: not a failed trade
+                            perf().record_cancelled(report.cancelled_orders, report.profit_cents);
+                        } else {
+                            let mut tracker = perf();
+                            tracker.record_trade_for(
+                                &report.market,
+                                &format!("{:?}", report.arb_type),
+                                report.success,
+                                report.profit_cents,
+                            );
+                            tracker.record_cancelled(report.cancelled_orders, 0);
+                        }
+                        if !report.simulated {
+                            let now = chrono::Utc::now();
+                            let daily_pnl = perf().daily_pnl_at(now);
+                            if let Some(resume_at) = risk_guard.check_daily_loss(daily_pnl, now) {
+                                heartbeat_telegram.notify(TelegramNotification::DailyLossLimitHit {
+                                    loss_cents: -daily_pnl,
+                                    limit_cents: risk_guard.limits().max_daily_loss_cents.unwrap_or_default(),
+                                    resume_at,
+                                });
+                            }
+                        }
+                        heartbeat_telegram.notify(TelegramNotification::TradeExecuted {
+                            market: report.market,
+                            contracts: report.contracts,
+                            requested_contracts: report.requested_contracts,
+                            hedge_contracts: report.hedge_contracts,
+                            limit_hit: report.limit_hit.map(|l| l.to_string()),
+                            profit_cents: report.profit_cents,
+                            success: report.success,
+                            latency_ms: report.latency_ms,
+                            simulated: report.simulated,
+                            unwound_contracts: report.unwound_contracts,
+                            cancelled_orders: report.cancelled_orders,
+                        });
+                    }
+                    Some(ExecutionReport::Underfunded(underfunded)) => {
+                        heartbeat_telegram.notify(TelegramNotification::Error {
+                            message: format!(
+                                "Insufficient {} balance: missed {}¢ profit on {} (needs ${:.2}, ${:.2} available)",
+                                underfunded.platform, underfunded.expected_profit_cents, underfunded.market,
+                                underfunded.required_cents as f64 / 100.0, underfunded.available_cents as f64 / 100.0,
+                            ),
+                        });
+                    }
+                    Some(ExecutionReport::Declined(declined)) => {
+                        info!("🙋 Skipped {} {:?} ({} contracts, ${:.2}): {:?}",
+                              declined.market, declined.arb_type, declined.contracts, declined.notional, declined.decision);
+                        perf().record_declined();
+                    }
+                    Some(ExecutionReport::Probation(probation)) if !probation.graduated => {
+                        heartbeat_telegram.notify(TelegramNotification::Error {
+                            message: format!(
+                                "{} failed probation: mean edge {:.1}¢ over {} observations, likely mismatched markets. \
+                                 Not traded until its entry is removed from .shadow_pairs.json",
+                                probation.market, probation.record.mean_edge_cents(), probation.record.observations,
+                            ),
+                        });
+                    }
+                    Some(ExecutionReport::Probation(_)) => {}
+                    Some(ExecutionReport::CancelUnconfirmed(order)) => {
+                        heartbeat_telegram.notify(TelegramNotification::Error {
+                            message: format!(
+                                "{} order {} on {} still resting after {} cancel checks: it may fill unhedged, check it on the exchange",
+                                order.platform, order.order_id, order.market, order.checks,
+                            ),
+                        });
+                    }
+                    Some(ExecutionReport::TrailingStop(stop)) => {
+                        heartbeat_telegram.notify(TelegramNotification::TrailingStopFired {
+                            market: stop.market,
+                            leg: format!("{}/{}", stop.platform, stop.side),
+                            high_water: stop.high_water,
+                            mark: stop.mark,
+                            contracts: stop.contracts,
+                            closed: stop.closed,
+                            realized_pnl: stop.realized_pnl,
+                            error: stop.error,
+                            simulated: stop.simulated,
+                        });
+                    }
+                },
+
+                // Remote commands from Telegram
+                Some(command) = async {
+                    match telegram_commands.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match command {
+                        BotCommand::Status => {
+                            let cache_stats = gamma_cache.stats();
+                            let marks = mark_positions(&mut *heartbeat_positions.write().await, &heartbeat_state, &heartbeat_books);
+                            let max_open_positions = heartbeat_positions.read().await.max_open_positions();
+                            let mut perf_tracker = perf();
+                            let now = chrono::Utc::now();
+                            let (total, today) = (perf_tracker.pnl(PnlWindow::All, now), perf_tracker.pnl(PnlWindow::Today, now));
+                            heartbeat_telegram.notify(TelegramNotification::StatusUpdate {
+                                uptime_hours: perf_tracker.uptime_hours(),
+                                total_trades: total.trades,
+                                successful_trades: total.successful_trades,
+                                cancelled_orders: perf_tracker.cancelled_orders,
+                                declined_trades: perf_tracker.declined_trades,
+                                total_profit_cents: total.profit_cents,
+                                today_trades: today.trades,
+                                today_successful_trades: today.successful_trades,
+                                today_profit_cents: today.profit_cents,
+                                markets_monitored: heartbeat_state.market_count(),
+                                paper_trades: perf_tracker.paper_trades,
+                                paper_profit_cents: perf_tracker.paper_profit_cents,
+                                top_markets: perf_tracker.top_markets(3),
+                                cache_hits: cache_stats.hits,
+                                cache_misses: cache_stats.misses,
+                                unrealized_pnl: marks.total,
+                                open_positions: marks.positions.len(),
+                                stale_positions: marks.stale_positions,
+                                max_open_positions,
+                                telegram_sent: telegram::delivery().sent(),
+                                telegram_failed: telegram::delivery().failed(),
+                                profit_history: perf_tracker.profit_series(),
+                                latency: latency::snapshot(),
+                                periodic: false,
+                            });
+                        }
+                        BotCommand::Pause => {
+                            heartbeat_cb.halt().await;
+                            heartbeat_telegram.notify(TelegramNotification::CommandReply { command });
+                        }
+                        BotCommand::Resume => {
+                            heartbeat_cb.reset().await;
+                            heartbeat_telegram.notify(TelegramNotification::CommandReply { command });
+                        }
+                        // Unknown window: the reply is the usage
+                        BotCommand::Pnl(None) => {
+                            heartbeat_telegram.notify(TelegramNotification::CommandReply { command });
+                        }
+                        BotCommand::Pnl(Some(window)) => {
+                            let stats = perf().pnl(window, chrono::Utc::now());
+                            heartbeat_telegram.notify(TelegramNotification::PnlReport { window, stats });
+                        }
+                        BotCommand::Positions => {
+                            let summary = heartbeat_positions.read().await.summary();
+                            heartbeat_telegram.notify(TelegramNotification::PositionsReport {
+                                open_positions: summary.open_positions,
+                                total_contracts: summary.total_contracts,
+                                total_cost_basis: summary.total_cost_basis,
+                                guaranteed_profit: summary.total_guaranteed_profit,
+                                unmatched_exposure: summary.total_unmatched_exposure,
+                                realized_pnl: summary.realized_pnl,
+                            });
+                        }
+                        BotCommand::Disable(_) | BotCommand::Enable(_) | BotCommand::Disabled => {
+                            let changed = match &command {
+                                BotCommand::Disable(market) => heartbeat_disabled.disable(market),
+                                BotCommand::Enable(market) => heartbeat_disabled.enable(market),
+                                _ => false,
+                            };
+                            let matched = match &command {
+                                BotCommand::Disable(market) | BotCommand::Enable(market) => heartbeat_state.markets.iter()
+                                    .filter_map(|m| m.pair.as_ref())
+                                    .filter(|pair| discovery::DisabledMarkets::matches(market, pair))
+                                    .count()
+                                    + heartbeat_state.multi_outcome.iter()
+                                        .filter(|full_set| discovery::DisabledMarkets::matches_full_set(market, full_set))
+                                        .count(),
+                                _ => 0,
+                            };
+                            if changed {
+                                info!("[TELEGRAM] {:?}: {} monitored pair(s) named", command, matched);
+                            }
+                            heartbeat_telegram.notify(TelegramNotification::DisabledMarkets {
+                                command,
+                                changed,
+                                matched,
+                                markets: heartbeat_disabled.list(),
+                            });
+                        }
+                        BotCommand::Flatten => {
+                            heartbeat_telegram.notify(TelegramNotification::CommandReply { command });
+                            // Closes are retried for a while; keep the heartbeat responsive
+                            let kalshi = flatten_kalshi.clone();
+                            let poly = flatten_poly.clone();
+                            let state = heartbeat_state.clone();
+                            let positions = heartbeat_positions.clone();
+                            let cb = heartbeat_cb.clone();
+                            let health = flatten_health.clone();
+                            let telegram = heartbeat_telegram.clone();
+                            tokio::spawn(async move {
+                                let report = flatten_all(
+                                    kalshi.as_deref(), Some(&poly), &state, &positions, &cb, &health,
+                                    execution_mode, FlattenConfig::from_env(),
+                                ).await;
+                                let (closed, failed): (Vec<_>, Vec<_>) = report.legs.iter().partition(|l| l.is_done());
+                                telegram.notify(TelegramNotification::PositionsFlattened {
+                                    closed: closed.iter().map(|l| l.to_string()).collect(),
+                                    failed: failed.iter().map(|l| l.to_string()).collect(),
+                                    realized_pnl: report.realized_pnl,
+                                    simulated: report.simulated,
+                                });
+                            });
+                        }
+                    }
+                }
             }
         }
+
+        if let Err(e) = perf().save_to_path(PERFORMANCE_FILE) {
+            warn!("[PERF] Failed to save stats: {}", e);
+        }
     });
 
-    // Main event loop - run until termination
+    // Main event loop - run until SIGINT/SIGTERM
     info!("✅ All systems operational - entering main event loop");
+    let signal = shutdown_signal().await;
+    let shutdown_timeout_secs = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(shutdown_timeout_secs);
+    info!("[SHUTDOWN] {} received, shutting down (timeout {}s)", signal, shutdown_timeout_secs);
+
+    // Stop price feeds and market monitoring so no new opportunities come in
     if let Some(kalshi_h) = kalshi_handle {
-        let _ = tokio::join!(kalshi_h, poly_handle, heartbeat_handle, exec_handle);
-    } else {
-        let _ = tokio::join!(poly_handle, heartbeat_handle, exec_handle);
+        kalshi_h.abort();
+    }
+    poly_handle.abort();
+    resolution_handle.abort();
+    let _ = resolution_handle.await;
+
+    // Let in-progress orders complete; dropping the engine closes the report
+    // and fill channels, which ends the heartbeat and position writer
+    let _ = shutdown_tx.send(true);
+    // The pollers' health and stale callbacks hold Telegram notifier clones:
+    // they must be gone before the notifier is dropped below
+    for mut handle in poll_handles {
+        if tokio::time::timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("[SHUTDOWN] Timed out stopping market status polls");
+            handle.abort();
+            let _ = handle.await;
+        }
+    }
+    if tokio::time::timeout_at(deadline, &mut exec_handle).await.is_err() {
+        warn!("[SHUTDOWN] Timed out waiting for in-progress orders, abandoning them");
+        exec_handle.abort();
+        let _ = exec_handle.await;
+    }
+    if tokio::time::timeout_at(deadline, &mut heartbeat_handle).await.is_err() {
+        warn!("[SHUTDOWN] Timed out processing execution reports");
+        heartbeat_handle.abort();
+        let _ = heartbeat_handle.await;
+    }
+    if tokio::time::timeout_at(deadline, &mut position_writer_handle).await.is_err() {
+        warn!("[SHUTDOWN] Timed out recording fills");
+        position_writer_handle.abort();
+    }
+    match position_tracker.read().await.flush() {
+        Ok(()) => info!("[SHUTDOWN] Positions saved"),
+        Err(e) => error!("[SHUTDOWN] Failed to save positions: {}", e),
+    }
+    shadow.flush();
+
+    // Send the stop notice, then wait for the Telegram queues to drain
+    telegram_notifier.notify(TelegramNotification::BotStopped {
+        reason: signal.to_string(),
+    });
+    drop(telegram_notifier);
+    for mut handle in telegram_handles {
+        if tokio::time::timeout_at(deadline, &mut handle).await.is_err() {
+            warn!("[SHUTDOWN] Timed out sending Telegram notifications");
+            handle.abort();
+        }
     }
 
+    info!("[SHUTDOWN] Stopped");
     Ok(())
 }
+
+/// Resolves on SIGINT (Ctrl-C) or SIGTERM with the signal name
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = sigterm.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                warn!("[SHUTDOWN] Cannot listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
@@ -0,0 +1,301 @@
+//! Prometheus metrics and health endpoints (feature `metrics`).
+//!
+//! Serves `GET /metrics` in the Prometheus text exposition format over a
+//! minimal HTTP/1.1 listener. Values are read on each scrape from the live
+//! `PerformanceTracker`, `PositionTracker` and `CircuitBreaker`, so there is no
+//! separate metrics state to keep in sync.
+//!
+//! Rate limiter wait counters and REST latency percentiles per platform are
+//! appended to `/metrics`, to show when the bot is bound by the exchanges'
+//! request limits or slowed down by their response times. Gamma slug lookups
+//! are counted by path (hot markets bypass the cache) to show what
+//! `HOT_MARKETS` costs in requests.
+//!
+//! `GET /health` reports how long ago each platform's feed last delivered a
+//! price update, and answers 503 when one is older than its `HealthConfig`
+//! threshold (the bot is running but no longer seeing prices).
+
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info};
+
+use crate::cache::CacheStats;
+use crate::circuit_breaker::{BreakerState, CircuitBreaker};
+use crate::config::HealthConfig;
+use crate::latency::{self, LatencyStats};
+use crate::polymarket::{self, SlugCache};
+use crate::position_tracker::{PositionSummary, SharedPositionTracker};
+use crate::rate_limit::{self, ThrottleStats};
+use crate::telegram::{self, DeliveryStats, PerformanceTracker};
+use crate::types::{FeedFreshness, GlobalState, Platform, unix_ms};
+
+/// Shared state scraped by the endpoint
+#[derive(Clone)]
+pub struct MetricsSources {
+    pub performance: Arc<Mutex<PerformanceTracker>>,
+    pub positions: SharedPositionTracker,
+    pub circuit_breaker: Arc<CircuitBreaker>,
+    pub state: Arc<GlobalState>,
+    /// Gamma slug lookups made during discovery
+    pub gamma_cache: Arc<SlugCache>,
+    pub health: HealthConfig,
+    /// Feeds checked by `/health` (Kalshi is skipped in POLY_ONLY mode)
+    pub platforms: Vec<Platform>,
+}
+
+impl MetricsSources {
+    async fn render(&self) -> String {
+        let positions = self.positions.read().await.summary();
+        let breaker = self.circuit_breaker.state();
+        let mut out = {
+            let perf = self.performance.lock().unwrap_or_else(|e| e.into_inner());
+            render(&perf, &positions, breaker)
+        };
+        let throttle: Vec<_> = [Platform::Kalshi, Platform::Polymarket].into_iter()
+            .map(|platform| (platform, rate_limit::limiter(platform).stats()))
+            .collect();
+        out.push_str(&render_throttle(&throttle));
+        out.push_str(&render_latency(&latency::snapshot()));
+        out.push_str(&render_delivery(telegram::delivery()));
+        out.push_str(&render_lookups(polymarket::uncached_lookups(), self.gamma_cache.stats()));
+        out
+    }
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Prometheus text format for the current state
+pub fn render(perf: &PerformanceTracker, positions: &PositionSummary, breaker: BreakerState) -> String {
+    let mut out = String::with_capacity(2048);
+
+    metric(&mut out, "arb_trades_total", "counter", "Live trades executed", perf.total_trades);
+    metric(&mut out, "arb_trades_successful_total", "counter", "Live trades with both legs filled", perf.successful_trades);
+    metric(&mut out, "arb_orders_cancelled_total", "counter", "Live orders cancelled after resting past their time-to-live", perf.cancelled_orders);
+    metric(&mut out, "arb_trades_declined_total", "counter", "Live trades skipped after an approval was rejected or timed out", perf.declined_trades);
+    metric(&mut out, "arb_profit_cents_total", "counter", "Realized live profit in cents", perf.total_profit_cents);
+    metric(&mut out, "arb_paper_trades_total", "counter", "Simulated trades in dry-run mode", perf.paper_trades);
+    metric(&mut out, "arb_paper_profit_cents_total", "counter", "Simulated profit in cents", perf.paper_profit_cents);
+    metric(&mut out, "arb_opportunities_total", "counter", "Distinct arbitrage opportunities detected", perf.opportunities_detected);
+    metric(&mut out, "arb_uptime_seconds", "gauge", "Reported uptime", perf.uptime_hours() * 3600.0);
+
+    metric(&mut out, "arb_open_positions", "gauge", "Open arbitrage positions", positions.open_positions);
+    metric(&mut out, "arb_position_contracts", "gauge", "Contracts held across open positions", positions.total_contracts);
+    metric(&mut out, "arb_position_cost_basis_dollars", "gauge", "Cost basis of open positions", positions.total_cost_basis);
+    metric(&mut out, "arb_unmatched_exposure_dollars", "gauge", "Unhedged exposure across open positions", positions.total_unmatched_exposure);
+
+    let _ = writeln!(out, "# HELP arb_circuit_breaker_state Circuit breaker state (1 for the current state)");
+    let _ = writeln!(out, "# TYPE arb_circuit_breaker_state gauge");
+    for (label, state) in [
+        ("closed", BreakerState::Closed),
+        ("open", BreakerState::Open),
+        ("half_open", BreakerState::HalfOpen),
+    ] {
+        let _ = writeln!(out, "arb_circuit_breaker_state{{state=\"{}\"}} {}", label, (breaker == state) as u8);
+    }
+
+    out
+}
+
+/// Per-platform time spent waiting on the shared rate limiters
+pub fn render_throttle(stats: &[(Platform, ThrottleStats)]) -> String {
+    let mut out = String::with_capacity(512);
+    let _ = writeln!(out, "# HELP arb_rate_limit_throttled_total Requests that waited for a rate limit permit");
+    let _ = writeln!(out, "# TYPE arb_rate_limit_throttled_total counter");
+    for (platform, s) in stats {
+        let _ = writeln!(out, "arb_rate_limit_throttled_total{{platform=\"{}\"}} {}", platform.to_string().to_lowercase(), s.throttled);
+    }
+    let _ = writeln!(out, "# HELP arb_rate_limit_wait_seconds_total Time spent waiting for rate limit permits");
+    let _ = writeln!(out, "# TYPE arb_rate_limit_wait_seconds_total counter");
+    for (platform, s) in stats {
+        let _ = writeln!(out, "arb_rate_limit_wait_seconds_total{{platform=\"{}\"}} {}", platform.to_string().to_lowercase(), s.wait_secs);
+    }
+    out
+}
+
+/// Per-platform REST round-trip percentiles over the rolling window
+pub fn render_latency(stats: &[(Platform, LatencyStats)]) -> String {
+    let mut out = String::with_capacity(512);
+    let _ = writeln!(out, "# HELP arb_api_latency_ms REST round-trip latency percentiles over the most recent requests");
+    let _ = writeln!(out, "# TYPE arb_api_latency_ms gauge");
+    for (platform, s) in stats {
+        let platform = platform.to_string().to_lowercase();
+        for (quantile, value) in [("0.5", s.p50_ms), ("0.95", s.p95_ms), ("0.99", s.p99_ms)] {
+            let _ = writeln!(out, "arb_api_latency_ms{{platform=\"{}\",quantile=\"{}\"}} {}", platform, quantile, value);
+        }
+    }
+    let _ = writeln!(out, "# HELP arb_api_latency_samples Requests in the latency window");
+    let _ = writeln!(out, "# TYPE arb_api_latency_samples gauge");
+    for (platform, s) in stats {
+        let _ = writeln!(out, "arb_api_latency_samples{{platform=\"{}\"}} {}", platform.to_string().to_lowercase(), s.samples);
+    }
+    out
+}
+
+/// Telegram notifications delivered and failed since startup
+pub fn render_delivery(stats: &DeliveryStats) -> String {
+    let mut out = String::with_capacity(256);
+    metric(&mut out, "arb_telegram_sent_total", "counter", "Telegram notifications delivered", stats.sent());
+    metric(&mut out, "arb_telegram_failed_total", "counter", "Telegram notifications that failed to send", stats.failed());
+    out
+}
+
+/// Gamma slug lookups by path: hot markets always send a request, the
+/// other markets only on a cache miss
+pub fn render_lookups(uncached: u64, cache: CacheStats) -> String {
+    let mut out = String::with_capacity(256);
+    let _ = writeln!(out, "# HELP arb_gamma_lookups_total Gamma slug lookups by path (uncached = hot market, cache skipped)");
+    let _ = writeln!(out, "# TYPE arb_gamma_lookups_total counter");
+    for (path, count) in [("uncached", uncached), ("cache_hit", cache.hits), ("cache_miss", cache.misses)] {
+        let _ = writeln!(out, "arb_gamma_lookups_total{{path=\"{}\"}} {}", path, count);
+    }
+    out
+}
+
+/// `/health` JSON body; the bool is false when any platform is stale
+pub fn render_health(freshness: &FeedFreshness, config: &HealthConfig, platforms: &[Platform], now_ms: u64) -> (bool, String) {
+    let mut healthy = true;
+    let sources: Vec<_> = platforms.iter().map(|&platform| {
+        let age_secs = freshness.age_ms(platform, now_ms) as f64 / 1000.0;
+        let max_age_secs = config.max_age_secs(platform);
+        let fresh = age_secs <= max_age_secs as f64;
+        healthy &= fresh;
+        serde_json::json!({
+            "platform": platform.to_string().to_lowercase(),
+            "last_update_ms": freshness.last_update_ms(platform),
+            "age_secs": age_secs,
+            "max_age_secs": max_age_secs,
+            "fresh": fresh,
+        })
+    }).collect();
+
+    let body = serde_json::json!({
+        "status": if healthy { "ok" } else { "stale" },
+        "sources": sources,
+    });
+    (healthy, format!("{}\n", body))
+}
+
+/// Accept scrapes until the listener fails
+pub async fn serve(addr: SocketAddr, sources: MetricsSources) -> Result<()> {
+    let listener = TcpListener::bind(addr).await
+        .with_context(|| format!("Failed to bind metrics endpoint on {}", addr))?;
+    info!("[METRICS] Serving Prometheus metrics on http://{}/metrics", addr);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let sources = sources.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &sources).await {
+                debug!("[METRICS] Request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, sources: &MetricsSources) -> Result<()> {
+    // Only the request line matters; headers and body are ignored
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let (status, content_type, body) = if method == "GET" && path == "/metrics" {
+        ("200 OK", "text/plain; version=0.0.4", sources.render().await)
+    } else if method == "GET" && path == "/health" {
+        let (healthy, body) = render_health(&sources.state.freshness, &sources.health, &sources.platforms, unix_ms());
+        let status = if healthy { "200 OK" } else { "503 Service Unavailable" };
+        (status, "application/json", body)
+    } else {
+        ("404 Not Found", "text/plain", "not found\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let mut perf = PerformanceTracker::new(0);
+        perf.record_trade(true, 42);
+        perf.record_opportunity();
+        let positions = PositionSummary { open_positions: 2, ..Default::default() };
+
+        let text = render(&perf, &positions, BreakerState::HalfOpen);
+        assert!(text.contains("# TYPE arb_trades_total counter\narb_trades_total 1\n"));
+        assert!(text.contains("arb_profit_cents_total 42\n"));
+        assert!(text.contains("arb_opportunities_total 1\n"));
+        assert!(text.contains("arb_open_positions 2\n"));
+        assert!(text.contains("arb_circuit_breaker_state{state=\"half_open\"} 1\n"));
+        assert!(text.contains("arb_circuit_breaker_state{state=\"closed\"} 0\n"));
+    }
+
+    #[test]
+    fn test_render_throttle_per_platform() {
+        let text = render_throttle(&[
+            (Platform::Kalshi, ThrottleStats { throttled: 3, wait_secs: 0.25 }),
+            (Platform::Polymarket, ThrottleStats::default()),
+        ]);
+        assert!(text.contains("arb_rate_limit_throttled_total{platform=\"kalshi\"} 3\n"));
+        assert!(text.contains("arb_rate_limit_wait_seconds_total{platform=\"kalshi\"} 0.25\n"));
+        assert!(text.contains("arb_rate_limit_throttled_total{platform=\"polymarket\"} 0\n"));
+    }
+
+    #[test]
+    fn test_render_lookups_by_path() {
+        let text = render_lookups(4, CacheStats { hits: 10, misses: 2, entries: 2 });
+        assert!(text.contains("arb_gamma_lookups_total{path=\"uncached\"} 4\n"));
+        assert!(text.contains("arb_gamma_lookups_total{path=\"cache_hit\"} 10\n"));
+        assert!(text.contains("arb_gamma_lookups_total{path=\"cache_miss\"} 2\n"));
+    }
+
+    #[test]
+    fn test_render_latency_quantiles() {
+        let text = render_latency(&[
+            (Platform::Kalshi, LatencyStats { samples: 40, p50_ms: 80.0, p95_ms: 210.5, p99_ms: 400.0 }),
+            (Platform::Polymarket, LatencyStats::default()),
+        ]);
+        assert!(text.contains("arb_api_latency_ms{platform=\"kalshi\",quantile=\"0.95\"} 210.5\n"));
+        assert!(text.contains("arb_api_latency_ms{platform=\"polymarket\",quantile=\"0.5\"} 0\n"));
+        assert!(text.contains("arb_api_latency_samples{platform=\"kalshi\"} 40\n"));
+    }
+
+    #[test]
+    fn test_render_health_flags_stale_feed() {
+        let freshness = FeedFreshness::new();
+        let config = HealthConfig { kalshi_max_age_secs: 60, poly_max_age_secs: 30 };
+        let now = unix_ms() + 1_000;
+        freshness.mark_at(Platform::Kalshi, now - 10_000);
+        freshness.mark_at(Platform::Polymarket, now - 45_000);
+
+        let (healthy, body) = render_health(&freshness, &config, &[Platform::Kalshi, Platform::Polymarket], now);
+        assert!(!healthy);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["status"], "stale");
+        assert_eq!(json["sources"][0]["platform"], "kalshi");
+        assert_eq!(json["sources"][0]["fresh"], true);
+        assert_eq!(json["sources"][1]["fresh"], false);
+        assert_eq!(json["sources"][1]["age_secs"], 45.0);
+
+        // Only the feeds in use are checked
+        let (healthy, _) = render_health(&freshness, &config, &[Platform::Kalshi], now);
+        assert!(healthy);
+    }
+}
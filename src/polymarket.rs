@@ -3,21 +3,28 @@
 //! This module provides WebSocket client for real-time Polymarket price feeds
 //! and REST API client for market discovery via the Gamma API.
 
-use anyhow::{Context, Result};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time::{interval, Instant};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
-use crate::config::{POLYMARKET_WS_URL, POLY_PING_INTERVAL_SECS, GAMMA_API_BASE};
+use crate::cache::{CacheConfig, TtlCache};
+use crate::error::{Context, Error, Result};
+use crate::config::{POLYMARKET_WS_URL, POLY_PING_INTERVAL_SECS, GAMMA_API_BASE, CrossedBookConfig, HttpConfig, ImbalanceConfig, RetryConfig};
+use crate::discovery::PollSchedule;
 use crate::execution::NanoClock;
+use crate::feed::FeedLink;
+use crate::polymarket_clob::{BookSide, OrderBookStore, price_to_bps};
+use crate::latency::Timed;
+use crate::rate_limit;
 use crate::types::{
-    GlobalState, FastExecutionRequest, ArbType, PriceCents, SizeCents,
-    parse_price, fxhash_str,
+    BookCheck, GlobalState, FastExecutionRequest, MultiOutcomeMarket, OrderConstraints, Outcome, Platform, Price, PriceCents, SizeCents,
+    fxhash_str, unix_ms, NO_PRICE,
 };
 
 // === WebSocket Message Types ===
@@ -25,7 +32,6 @@ use crate::types::{
 #[derive(Deserialize, Debug)]
 pub struct BookSnapshot {
     pub asset_id: String,
-    #[allow(dead_code)]
     pub bids: Vec<PriceLevel>,
     pub asks: Vec<PriceLevel>,
 }
@@ -47,93 +53,344 @@ pub struct PriceChangeEvent {
 pub struct PriceChangeItem {
     pub asset_id: String,
     pub price: Option<String>,
+    /// New aggregate size at this price level ("0" removes it)
+    pub size: Option<String>,
     pub side: Option<String>,
 }
 
 #[derive(Serialize)]
 struct SubscribeCmd {
     assets_ids: Vec<String>,
-    #[serde(rename = "type")]
-    sub_type: &'static str,
+    /// Channel of the first message on a connection
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    sub_type: Option<&'static str>,
+    /// "subscribe" on the messages that add tokens to an open subscription
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<&'static str>,
 }
 
+/// Tokens per subscribe message
+const SUBSCRIBE_BATCH: usize = 100;
+
 // === Gamma API Client ===
 
+/// Slug → market lookups; `None` caches "no active market"
+pub type SlugCache = TtlCache<String, Option<GammaListing>>;
+
+/// Tokens and order constraints of an active Gamma market
+#[derive(Debug, Clone, PartialEq)]
+pub struct GammaListing {
+    pub yes_token: String,
+    pub no_token: String,
+    pub constraints: OrderConstraints,
+}
+
+/// Slug lookups sent straight to Gamma for hot markets (see `lookup_market`)
+static UNCACHED_LOOKUPS: AtomicU64 = AtomicU64::new(0);
+
+/// Slug lookups that skipped the cache since startup. Lookups through the
+/// cache are counted by the `SlugCache` stats: each miss is one request.
+pub fn uncached_lookups() -> u64 {
+    UNCACHED_LOOKUPS.load(Ordering::Relaxed)
+}
+
 pub struct GammaClient {
     http: reqwest::Client,
+    retry: RetryConfig,
+    cache: Arc<SlugCache>,
+}
+
+impl Default for GammaClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GammaClient {
     pub fn new() -> Self {
         Self {
-            http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build()
-                .expect("Failed to build HTTP client"),
+            http: HttpConfig::poly_from_env().client().expect("Failed to build HTTP client"),
+            retry: RetryConfig::poly_from_env(),
+            cache: Arc::new(TtlCache::new(CacheConfig::from_env())),
+        }
+    }
+
+    /// Slug lookup cache (for stats and the background sweep)
+    pub fn cache(&self) -> Arc<SlugCache> {
+        self.cache.clone()
+    }
+
+    /// GET with retries on timeouts, connection errors, 429 and 5xx.
+    /// Other 4xx responses are returned immediately.
+    async fn get_with_retry(&self, url: &str) -> Result<reqwest::Response> {
+        let mut attempt = 1;
+        loop {
+            rate_limit::acquire(Platform::Polymarket).await;
+            let retryable = match self.http.get(url).send().timed(Platform::Polymarket).await {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let retryable = status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+                    if !retryable || attempt >= self.retry.max_attempts {
+                        return Ok(resp);
+                    }
+                    status.to_string()
+                }
+                Err(e) => {
+                    let retryable = e.is_timeout() || e.is_connect();
+                    if !retryable || attempt >= self.retry.max_attempts {
+                        return Err(e.into());
+                    }
+                    e.to_string()
+                }
+            };
+
+            let delay = self.retry.delay_for(attempt - 1);
+            warn!("[POLY] Gamma request failed ({}), retry {}/{} in {:?}",
+                  retryable, attempt, self.retry.max_attempts - 1, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
     }
     
-    /// Look up Polymarket market by slug, return (yes_token, no_token)
+    /// Look up Polymarket market by slug, return its tokens and order constraints
     /// Tries both the exact date and next day (timezone handling)
-    pub async fn lookup_market(&self, slug: &str) -> Result<Option<(String, String)>> {
+    ///
+    /// `bypass_cache` is for hot markets: the cache is not read, so a market
+    /// relisted under new tokens (or one that just became active) is picked
+    /// up at once instead of after `CACHE_TTL_SECS`. It is not any faster:
+    /// the price is one Gamma request per slug every time, against the shared
+    /// Polymarket rate limit; the result still refreshes the cache.
+    pub async fn lookup_market(&self, slug: &str, bypass_cache: bool) -> Result<Option<GammaListing>> {
         // Try exact slug first
-        if let Some(tokens) = self.try_lookup_slug(slug).await? {
-            return Ok(Some(tokens));
+        if let Some(listing) = self.try_lookup_slug(slug, bypass_cache).await? {
+            return Ok(Some(listing));
         }
         
         // Try with next day (Polymarket may use local time)
         if let Some(next_day_slug) = increment_date_in_slug(slug) {
-            if let Some(tokens) = self.try_lookup_slug(&next_day_slug).await? {
+            if let Some(listing) = self.try_lookup_slug(&next_day_slug, bypass_cache).await? {
                 info!("  📅 Found with next-day slug: {}", next_day_slug);
-                return Ok(Some(tokens));
+                return Ok(Some(listing));
             }
         }
         
         Ok(None)
     }
     
-    async fn try_lookup_slug(&self, slug: &str) -> Result<Option<(String, String)>> {
+    async fn try_lookup_slug(&self, slug: &str, bypass_cache: bool) -> Result<Option<GammaListing>> {
+        if bypass_cache {
+            UNCACHED_LOOKUPS.fetch_add(1, Ordering::Relaxed);
+        } else if let Some(cached) = self.cache.get(&slug.to_string()) {
+            return Ok(cached);
+        }
+
         let url = format!("{}/markets?slug={}", GAMMA_API_BASE, slug);
         
-        let resp = self.http.get(&url).send().await?;
+        let resp = self.get_with_retry(&url).await?;
         
+        // Failed requests are not cached
         if !resp.status().is_success() {
             return Ok(None);
         }
         
         let markets: Vec<GammaMarket> = resp.json().await?;
-        
-        if markets.is_empty() {
-            return Ok(None);
+        let listing = markets.first().and_then(GammaMarket::listing);
+
+        self.cache.insert(slug.to_string(), listing.clone());
+        Ok(listing)
+    }
+
+    /// Current status of a market by slug, bypassing the lookup cache.
+    /// `None` if Gamma does not know the slug.
+    pub async fn market_status(&self, slug: &str) -> Result<Option<MarketStatus>> {
+        let url = format!("{}/markets?slug={}", GAMMA_API_BASE, slug);
+        let resp = self.get_with_retry(&url).await?;
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await
+                .context(format!("Gamma status lookup for {}", slug)));
         }
-        
-        let market = &markets[0];
-        
-        // Check if active and not closed
-        if market.closed == Some(true) || market.active == Some(false) {
-            return Ok(None);
+        let markets: Vec<GammaMarket> = resp.json().await?;
+        Ok(markets.first().map(GammaMarket::status))
+    }
+
+    /// Active markets listed under a Polymarket event slug
+    pub async fn event_markets(&self, event_slug: &str) -> Result<Vec<GammaEventMarket>> {
+        let url = format!("{}/events?slug={}", GAMMA_API_BASE, event_slug);
+
+        let resp = self.get_with_retry(&url).await?;
+        if !resp.status().is_success() {
+            return Ok(Vec::new());
         }
-        
-        // Parse clobTokenIds JSON array
-        let token_ids: Vec<String> = market.clob_token_ids
-            .as_ref()
-            .and_then(|s| serde_json::from_str(s).ok())
-            .unwrap_or_default();
-        
-        if token_ids.len() >= 2 {
-            Ok(Some((token_ids[0].clone(), token_ids[1].clone())))
-        } else {
-            Ok(None)
+
+        let events: Vec<GammaEvent> = resp.json().await?;
+        Ok(events.into_iter()
+            .flat_map(|event| event.markets)
+            .filter_map(|market| {
+                let listing = market.listing()?;
+                Some(GammaEventMarket {
+                    slug: market.slug?,
+                    question: market.question?,
+                    listing,
+                })
+            })
+            .collect())
+    }
+
+    /// A neg-risk event as a multi-outcome market, one YES token per outcome.
+    /// `None` if the event is unknown, not neg-risk or not fully tradable.
+    pub async fn neg_risk_event(&self, event_slug: &str) -> Result<Option<MultiOutcomeMarket>> {
+        let url = format!("{}/events?slug={}", GAMMA_API_BASE, event_slug);
+        let resp = self.get_with_retry(&url).await?;
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await
+                .context(format!("Gamma event lookup for {}", event_slug)));
+        }
+        let events: Vec<GammaEvent> = resp.json().await?;
+        Ok(events.first().and_then(GammaEvent::multi_outcome))
+    }
+}
+
+/// Tradable market listed under a Polymarket event
+#[derive(Debug, Clone)]
+pub struct GammaEventMarket {
+    pub slug: String,
+    pub question: String,
+    pub listing: GammaListing,
+}
+
+#[derive(Debug, Deserialize)]
+struct GammaEvent {
+    slug: Option<String>,
+    title: Option<String>,
+    #[serde(rename = "negRisk")]
+    neg_risk: Option<bool>,
+    /// Outcomes can still be added (placeholders, "Other"), so the listed
+    /// ones are not exhaustive
+    #[serde(rename = "negRiskAugmented")]
+    neg_risk_augmented: Option<bool>,
+    #[serde(default)]
+    markets: Vec<GammaMarket>,
+}
+
+impl GammaEvent {
+    /// Outcomes of a neg-risk event. Every listed market must be tradable:
+    /// buying all outcomes is only riskless if none is missing.
+    fn multi_outcome(&self) -> Option<MultiOutcomeMarket> {
+        if self.neg_risk != Some(true) || self.neg_risk_augmented == Some(true) || self.markets.len() < 2 {
+            return None;
         }
+        let outcomes = self.markets.iter()
+            .map(|market| {
+                let listing = market.listing()?;
+                let name = market.group_item_title.clone().or_else(|| market.question.clone())?;
+                Some(Outcome { name: name.into(), token: listing.yes_token.into(), constraints: listing.constraints })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let slug = self.slug.clone()?;
+        Some(MultiOutcomeMarket {
+            description: self.title.clone().unwrap_or_else(|| slug.clone()).into(),
+            event_slug: slug.into(),
+            neg_risk: true,
+            outcomes,
+        })
     }
 }
 
 #[derive(Debug, Deserialize)]
 struct GammaMarket {
+    slug: Option<String>,
+    question: Option<String>,
+    /// Outcome label within a multi-outcome event, e.g. "Arsenal"
+    #[serde(rename = "groupItemTitle")]
+    group_item_title: Option<String>,
     #[serde(rename = "clobTokenIds")]
     clob_token_ids: Option<String>,
     active: Option<bool>,
     closed: Option<bool>,
+    /// JSON array of outcome prices aligned with `clobTokenIds`, e.g. "[\"1\", \"0\"]"
+    #[serde(rename = "outcomePrices")]
+    outcome_prices: Option<String>,
+    #[serde(rename = "umaResolutionStatus")]
+    uma_resolution_status: Option<String>,
+    /// Price tick in dollars, e.g. 0.01
+    #[serde(rename = "orderPriceMinTickSize")]
+    order_price_min_tick_size: Option<f64>,
+    /// Smallest order in shares
+    #[serde(rename = "orderMinSize")]
+    order_min_size: Option<f64>,
+}
+
+impl GammaMarket {
+    /// Tokens and order constraints if the market is active and not closed.
+    /// Constraints Gamma leaves out fall back to Polymarket's defaults.
+    fn listing(&self) -> Option<GammaListing> {
+        let (yes_token, no_token) = self.tokens()?;
+        let default = OrderConstraints::default();
+        let valid = |v: &f64| v.is_finite() && *v > 0.0;
+        Some(GammaListing {
+            yes_token,
+            no_token,
+            constraints: OrderConstraints {
+                tick_bps: self.order_price_min_tick_size.filter(valid)
+                    .map_or(default.tick_bps, |tick| (tick * 10_000.0).round().max(1.0) as u64),
+                min_size: self.order_min_size.filter(valid).unwrap_or(default.min_size),
+            },
+        })
+    }
+
+    /// (yes_token, no_token) if the market is active and not closed
+    fn tokens(&self) -> Option<(String, String)> {
+        if self.closed == Some(true) || self.active == Some(false) {
+            return None;
+        }
+        // Parse clobTokenIds JSON array
+        let token_ids: Vec<String> = self.clob_token_ids
+            .as_ref()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default();
+        (token_ids.len() >= 2).then(|| (token_ids[0].clone(), token_ids[1].clone()))
+    }
+
+    /// Trading status; a closed market counts as resolved once its outcome
+    /// prices have settled to 1/0
+    fn status(&self) -> MarketStatus {
+        let resolved = self.uma_resolution_status.as_deref() == Some("resolved");
+        if self.closed != Some(true) && !resolved {
+            return MarketStatus::Open;
+        }
+        let prices: Vec<f64> = self.outcome_prices
+            .as_ref()
+            .and_then(|s| serde_json::from_str::<Vec<String>>(s).ok())
+            .map(|v| v.iter().filter_map(|p| p.parse().ok()).collect())
+            .unwrap_or_default();
+        match prices.as_slice() {
+            [yes, no] if *yes > 0.99 && *no < 0.01 => MarketStatus::Resolved { yes_won: true },
+            [yes, no] if *yes < 0.01 && *no > 0.99 => MarketStatus::Resolved { yes_won: false },
+            _ => MarketStatus::Closed,
+        }
+    }
+}
+
+/// Lifecycle state of a market (Polymarket, or Kalshi via `KalshiMarket::market_status`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketStatus {
+    Open,
+    /// Trading stopped, outcome not final yet
+    Closed,
+    /// Outcome final; `yes_won` refers to the first (YES) token
+    Resolved { yes_won: bool },
+}
+
+/// Stop quoting a closed market: clear its Polymarket prices and books so
+/// no further arbs are detected on it
+pub fn halt_market(state: &GlobalState, books: &OrderBookStore, market_id: u16) {
+    let Some(market) = state.get_by_id(market_id) else { return };
+    market.poly.store(NO_PRICE, NO_PRICE, 0, 0);
+    market.poly_imbalance.clear();
+    if let Some(pair) = &market.pair {
+        books.remove(fxhash_str(&pair.poly_yes_token));
+        books.remove(fxhash_str(&pair.poly_no_token));
+    }
 }
 
 /// Increment the date in a Polymarket slug by 1 day
@@ -173,26 +430,65 @@ fn increment_date_in_slug(slug: &str) -> Option<String> {
 // WebSocket Runner
 // =============================================================================
 
-/// Parse size from Polymarket (format: "123.45" dollars)
+/// Parse a book level into (price_bps, size)
+#[inline(always)]
+fn parse_level(level: &PriceLevel) -> Option<(u64, f64)> {
+    let price = parse_price(&level.price)?;
+    let size = level.size.parse::<f64>().ok()?;
+    Some((price, size))
+}
+
+/// Parse a 0–1 dollar price into basis points; `None` for anything else
+/// (NaN, negative, above $1), which would otherwise land on a 0 or capped price
+#[inline(always)]
+fn parse_price(s: &str) -> Option<u64> {
+    let price = s.parse::<f64>().ok().filter(|p| (0.0..=1.0).contains(p))?;
+    Some(price_to_bps(price))
+}
+
+/// Convert an ask in basis points to cents, rounded up so a sub-cent ask
+/// never looks cheaper than it is; `NO_PRICE` if out of range
+#[inline(always)]
+pub fn ask_bps_to_cents(bps: u64) -> PriceCents {
+    Price::ask_from_bps(bps).map_or(NO_PRICE, Price::cents)
+}
+
+/// Convert a bid in basis points to cents, rounded down; `NO_PRICE` if out
+/// of range or below a cent
+#[inline(always)]
+pub fn bid_bps_to_cents(bps: u64) -> PriceCents {
+    Price::bid_from_bps(bps).map_or(NO_PRICE, Price::cents)
+}
+
+/// Convert a size in contracts to SizeCents
 #[inline(always)]
-fn parse_size(s: &str) -> SizeCents {
-    // Parse as f64 and convert to cents
-    s.parse::<f64>()
-        .map(|size| (size * 100.0).round() as SizeCents)
-        .unwrap_or(0)
+pub fn contracts_to_size_cents(contracts: f64) -> SizeCents {
+    (contracts * 100.0).round() as SizeCents
 }
 
-/// WebSocket runner
+/// WebSocket runner.
+///
+/// Maintains a local book per token in `books`. On every (re)connect the books
+/// are cleared and rebuilt from the `book` snapshots the market channel sends
+/// after subscription; level updates are ignored until a token's snapshot arrives.
+/// The subscriptions go out in batches spread over `warmup` (see
+/// `PollSchedule::staggered_batches`). `link` is marked once the socket is open.
 pub async fn run_ws(
     state: Arc<GlobalState>,
+    books: Arc<OrderBookStore>,
     exec_tx: mpsc::Sender<FastExecutionRequest>,
     threshold_cents: PriceCents,
+    warmup: Duration,
+    link: Arc<FeedLink>,
 ) -> Result<()> {
-    let tokens: Vec<String> = state.markets.iter()
+    let mut tokens: Vec<String> = state.markets.iter()
         .take(state.market_count())
         .filter_map(|m| m.pair.as_ref())
         .flat_map(|p| [p.poly_yes_token.to_string(), p.poly_no_token.to_string()])
+        .chain(state.multi_outcome.iter().flat_map(|m| m.outcomes.iter().map(|o| o.token.to_string())))
         .collect();
+    tokens.sort_unstable();
+    tokens.dedup();
 
     if tokens.is_empty() {
         info!("[POLY] No markets to monitor");
@@ -205,24 +501,42 @@ pub async fn run_ws(
         .context("Failed to connect to Polymarket")?;
 
     info!("[POLY] Connected");
+    link.connected();
 
     let (mut write, mut read) = ws_stream.split();
 
-    // Subscribe
-    let subscribe_msg = SubscribeCmd {
-        assets_ids: tokens.clone(),
-        sub_type: "market",
-    };
-
-    write.send(Message::Text(serde_json::to_string(&subscribe_msg)?)).await?;
-    info!("[POLY] Subscribed to {} tokens", tokens.len());
+    // Subscribe, a batch at a time over the warm-up
+    books.clear();
+    let token_count = tokens.len();
+    let mut batches = PollSchedule::staggered_batches(tokens, SUBSCRIBE_BATCH, warmup, Instant::now());
+    let mut subscribed = 0;
 
     let clock = NanoClock::new();
+    let depth_bps = ImbalanceConfig::from_env().depth_bps();
+    let stale_after_ms = CrossedBookConfig::from_env().stale_after_ms;
     let mut ping_interval = interval(Duration::from_secs(POLY_PING_INTERVAL_SECS));
     let mut last_message = Instant::now();
 
     loop {
+        let next_batch = batches.front().map(|(due, _)| *due);
         tokio::select! {
+            _ = tokio::time::sleep_until(next_batch.unwrap_or_else(Instant::now)), if next_batch.is_some() => {
+                let Some((_, batch)) = batches.pop_front() else { continue };
+                let first = subscribed == 0;
+                subscribed += batch.len();
+                let subscribe_msg = SubscribeCmd {
+                    assets_ids: batch,
+                    sub_type: first.then_some("market"),
+                    operation: (!first).then_some("subscribe"),
+                };
+                write.send(Message::Text(serde_json::to_string(&subscribe_msg)?)).await?;
+                if batches.is_empty() {
+                    info!("[POLY] Subscribed to {} tokens", token_count);
+                } else {
+                    debug!("[POLY] Subscribed to {}/{} tokens", subscribed, token_count);
+                }
+            }
+
             _ = ping_interval.tick() => {
                 if let Err(e) = write.send(Message::Ping(vec![])).await {
                     error!("[POLY] Failed to send ping: {}", e);
@@ -236,17 +550,20 @@ pub async fn run_ws(
                         last_message = Instant::now();
 
                         // Try book snapshot first
-                        if let Ok(books) = serde_json::from_str::<Vec<BookSnapshot>>(&text) {
-                            for book in &books {
-                                process_book(&state, book, &exec_tx, threshold_cents, &clock).await;
+                        if let Ok(snapshots) = serde_json::from_str::<Vec<BookSnapshot>>(&text) {
+                            for book in &snapshots {
+                                process_book(&state, &books, book, &exec_tx, threshold_cents, &clock, depth_bps, stale_after_ms).await;
                             }
                         }
+                        else if let Ok(book) = serde_json::from_str::<BookSnapshot>(&text) {
+                            process_book(&state, &books, &book, &exec_tx, threshold_cents, &clock, depth_bps, stale_after_ms).await;
+                        }
                         // Try price change event
                         else if let Ok(event) = serde_json::from_str::<PriceChangeEvent>(&text) {
                             if event.event_type.as_deref() == Some("price_change") {
                                 if let Some(changes) = &event.price_changes {
                                     for change in changes {
-                                        process_price_change(&state, change, &exec_tx, threshold_cents, &clock).await;
+                                        process_price_change(&state, &books, change, &exec_tx, threshold_cents, &clock, depth_bps, stale_after_ms).await;
                                     }
                                 }
                             }
@@ -289,100 +606,170 @@ pub async fn run_ws(
     Ok(())
 }
 
-/// Process book snapshot
+/// Process book snapshot: replaces the local book for the token
 #[inline]
+#[allow(clippy::too_many_arguments)]
 async fn process_book(
     state: &GlobalState,
+    books: &OrderBookStore,
     book: &BookSnapshot,
     exec_tx: &mpsc::Sender<FastExecutionRequest>,
     threshold_cents: PriceCents,
     clock: &NanoClock,
+    depth_bps: u64,
+    stale_after_ms: u64,
 ) {
     let token_hash = fxhash_str(&book.asset_id);
-
-    // Find best ask (lowest price)
-    let (best_ask, ask_size) = book.asks.iter()
-        .filter_map(|l| {
-            let price = parse_price(&l.price);
-            let size = parse_size(&l.size);
-            if price > 0 { Some((price, size)) } else { None }
-        })
-        .min_by_key(|(p, _)| *p)
-        .unwrap_or((0, 0));
-
-    // Check if YES token
-    if let Some(&market_id) = state.poly_yes_to_id.get(&token_hash) {
-        let market = &state.markets[market_id as usize];
-        market.poly.update_yes(best_ask, ask_size);
-
-        // Check arbs
-        let arb_mask = market.check_arbs(threshold_cents);
-        if arb_mask != 0 {
-            send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
-        }
-    }
-    // Check if NO token
-    else if let Some(&market_id) = state.poly_no_to_id.get(&token_hash) {
-        let market = &state.markets[market_id as usize];
-        market.poly.update_no(best_ask, ask_size);
-
-        // Check arbs
-        let arb_mask = market.check_arbs(threshold_cents);
-        if arb_mask != 0 {
-            send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
-        }
+    let best_ask = books.apply_snapshot(
+        token_hash,
+        book.bids.iter().filter_map(parse_level),
+        book.asks.iter().filter_map(parse_level),
+    );
+    state.freshness.mark(Platform::Polymarket);
+    update_imbalance(state, books, token_hash, depth_bps);
+    if book_valid(state, books, token_hash, exec_tx, threshold_cents, clock, stale_after_ms).await {
+        update_best_ask(state, token_hash, best_ask, exec_tx, threshold_cents, clock).await;
     }
 }
 
-/// Process price change
+/// Process price change: applies the level update to the local book
 #[inline]
+#[allow(clippy::too_many_arguments)]
 async fn process_price_change(
     state: &GlobalState,
+    books: &OrderBookStore,
     change: &PriceChangeItem,
     exec_tx: &mpsc::Sender<FastExecutionRequest>,
     threshold_cents: PriceCents,
     clock: &NanoClock,
+    depth_bps: u64,
+    stale_after_ms: u64,
 ) {
-    // Only process ASK side updates
-    if !matches!(change.side.as_deref(), Some("ASK" | "ask")) {
+    let Some(side) = change.side.as_deref().and_then(BookSide::parse) else { return };
+    let (Some(price_str), Some(size_str)) = (&change.price, &change.size) else { return };
+    let Some(price) = parse_price(price_str) else {
+        debug!("[POLY] Ignoring price change outside 0-1: {}", price_str);
         return;
-    }
-
-    let Some(price_str) = &change.price else { return };
-    let price = parse_price(price_str);
-    if price == 0 { return; }
+    };
+    let Ok(size) = size_str.parse::<f64>() else { return };
 
     let token_hash = fxhash_str(&change.asset_id);
 
-    // Check YES token
-    if let Some(&market_id) = state.poly_yes_to_id.get(&token_hash) {
-        let market = &state.markets[market_id as usize];
-        let (current_yes, _, current_yes_size, _) = market.poly.load();
+    // Ignore updates until the token's snapshot has been received
+    let Some(best_ask) = books.apply_level(token_hash, side, price, size) else { return };
+    state.freshness.mark(Platform::Polymarket);
+    update_imbalance(state, books, token_hash, depth_bps);
+    // A bid can cross the book as well as an ask
+    if !book_valid(state, books, token_hash, exec_tx, threshold_cents, clock, stale_after_ms).await {
+        return;
+    }
 
-        // Only update if new price is better (lower)
-        if price < current_yes || current_yes == 0 {
-            // Keep existing size - it may be stale but FAK orders handle partial fills.
-            // Size is an upper bound anyway; better to attempt arb than miss it.
-            market.poly.update_yes(price, current_yes_size);
+    if side == BookSide::Ask {
+        update_best_ask(state, token_hash, best_ask, exec_tx, threshold_cents, clock).await;
+    }
+}
 
+/// Hold back the market of `token_hash` while one of its token books is
+/// crossed or locked, and clear its prices once that lasts past the stale
+/// delay. Returns whether the update can be priced as usual.
+async fn book_valid(
+    state: &GlobalState,
+    books: &OrderBookStore,
+    token_hash: u64,
+    exec_tx: &mpsc::Sender<FastExecutionRequest>,
+    threshold_cents: PriceCents,
+    clock: &NanoClock,
+    stale_after_ms: u64,
+) -> bool {
+    let Some(&market_id) = state.poly_yes_to_id.get(&token_hash).or_else(|| state.poly_no_to_id.get(&token_hash)) else {
+        return true;
+    };
+    let market = &state.markets[market_id as usize];
+    let Some(pair) = &market.pair else { return true };
+    let (yes, no) = (fxhash_str(&pair.poly_yes_token), fxhash_str(&pair.poly_no_token));
+
+    match market.poly.check_crossed(books.is_crossed(yes) || books.is_crossed(no), unix_ms(), stale_after_ms) {
+        BookCheck::Valid => true,
+        BookCheck::Crossed { first } => {
+            if first {
+                warn!("[POLY] ⚠️ Crossed or locked book on {} - opportunities suppressed", pair.description);
+            }
+            false
+        }
+        BookCheck::Stale { crossed_ms } => {
+            warn!("[POLY] ⚠️ {} book crossed for {}ms - market marked stale", pair.description, crossed_ms);
+            market.poly.store(NO_PRICE, NO_PRICE, 0, 0);
+            false
+        }
+        BookCheck::Recovered { crossed_ms } => {
+            info!("[POLY] {} book valid again after {}ms", pair.description, crossed_ms);
+            // Both tokens' asks were held back while it was crossed
+            let ask = |hash| books.best_ask(hash)
+                .map_or((NO_PRICE, 0), |(bps, contracts)| (ask_bps_to_cents(bps), contracts_to_size_cents(contracts)));
+            let ((yes_ask, yes_size), (no_ask, no_size)) = (ask(yes), ask(no));
+            market.poly.store(yes_ask, no_ask, yes_size, no_size);
             let arb_mask = market.check_arbs(threshold_cents);
             if arb_mask != 0 {
                 send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
             }
+            false
         }
     }
-    // Check NO token
+}
+
+/// Recompute a token's top-of-book imbalance into the market state
+#[inline]
+fn update_imbalance(state: &GlobalState, books: &OrderBookStore, token_hash: u64, depth_bps: u64) {
+    let (market_id, yes_token) = match state.poly_yes_to_id.get(&token_hash) {
+        Some(&id) => (id, true),
+        None => match state.poly_no_to_id.get(&token_hash) {
+            Some(&id) => (id, false),
+            None => return,
+        },
+    };
+    state.markets[market_id as usize].poly_imbalance.store(yes_token, books.imbalance(token_hash, depth_bps));
+}
+
+/// Push a token's best ask into the market state and check for arbs
+#[inline]
+async fn update_best_ask(
+    state: &GlobalState,
+    token_hash: u64,
+    best_ask: Option<(u64, f64)>,
+    exec_tx: &mpsc::Sender<FastExecutionRequest>,
+    threshold_cents: PriceCents,
+    clock: &NanoClock,
+) {
+    let (price, size) = best_ask
+        .map(|(bps, contracts)| (ask_bps_to_cents(bps), contracts_to_size_cents(contracts)))
+        .unwrap_or((0, 0));
+
+    // Check if YES token
+    if let Some(&market_id) = state.poly_yes_to_id.get(&token_hash) {
+        let market = &state.markets[market_id as usize];
+        let (current_yes, _, current_yes_size, _) = market.poly.load();
+        if (price, size) == (current_yes, current_yes_size) {
+            return;
+        }
+        market.poly.update_yes(price, size);
+
+        let arb_mask = market.check_arbs(threshold_cents);
+        if arb_mask != 0 {
+            send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
+        }
+    }
+    // Check if NO token
     else if let Some(&market_id) = state.poly_no_to_id.get(&token_hash) {
         let market = &state.markets[market_id as usize];
         let (_, current_no, _, current_no_size) = market.poly.load();
+        if (price, size) == (current_no, current_no_size) {
+            return;
+        }
+        market.poly.update_no(price, size);
 
-        if price < current_no || current_no == 0 {
-            market.poly.update_no(price, current_no_size);
-
-            let arb_mask = market.check_arbs(threshold_cents);
-            if arb_mask != 0 {
-                send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
-            }
+        let arb_mask = market.check_arbs(threshold_cents);
+        if arb_mask != 0 {
+            send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
         }
     }
 }
@@ -396,36 +783,63 @@ async fn send_arb_request(
     exec_tx: &mpsc::Sender<FastExecutionRequest>,
     clock: &NanoClock,
 ) {
-    let (k_yes, k_no, k_yes_size, k_no_size) = market.kalshi.load();
-    let (p_yes, p_no, p_yes_size, p_no_size) = market.poly.load();
-
-    // Priority order: cross-platform arbs first (more reliable)
-    let (yes_price, no_price, yes_size, no_size, arb_type) = if arb_mask & 1 != 0 {
-        // Poly YES + Kalshi NO
-        (p_yes, k_no, p_yes_size, k_no_size, ArbType::PolyYesKalshiNo)
-    } else if arb_mask & 2 != 0 {
-        // Kalshi YES + Poly NO
-        (k_yes, p_no, k_yes_size, p_no_size, ArbType::KalshiYesPolyNo)
-    } else if arb_mask & 4 != 0 {
-        // Poly only (both sides)
-        (p_yes, p_no, p_yes_size, p_no_size, ArbType::PolyOnly)
-    } else if arb_mask & 8 != 0 {
-        // Kalshi only (both sides)
-        (k_yes, k_no, k_yes_size, k_no_size, ArbType::KalshiOnly)
-    } else {
-        return;
-    };
+    if let Some(req) = FastExecutionRequest::from_arb_mask(market_id, market, arb_mask, clock.now_ns()) {
+        // send! ~~ 
+        let _ = exec_tx.try_send(req);
+    }
+}
 
-    let req = FastExecutionRequest {
-        market_id,
-        yes_price,
-        no_price,
-        yes_size,
-        no_size,
-        arb_type,
-        detected_ns: clock.now_ns(),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_prices_at_the_cent_boundaries() {
+        let level = |price: &str| parse_level(&PriceLevel { price: price.into(), size: "10".into() }).map(|(bps, _)| bps);
+        assert_eq!(level("0.005"), Some(50));
+        assert_eq!(level("0.995"), Some(9950));
+        assert_eq!(level("0"), Some(0));
+        assert_eq!(level("1"), Some(10_000));
+        for bogus in ["-0.01", "1.01", "NaN", "inf", "abc"] {
+            assert_eq!(level(bogus), None, "{}", bogus);
+        }
+
+        // A sub-cent ask costs a full cent; a sub-cent bid is no bid
+        assert_eq!(ask_bps_to_cents(50), 1);
+        assert_eq!(bid_bps_to_cents(50), NO_PRICE);
+        assert_eq!(ask_bps_to_cents(9950), 100);
+        assert_eq!(bid_bps_to_cents(9950), 99);
+        // 45.4¢ is not a 45¢ ask: rounding it down would add 0.6¢ of phantom edge
+        assert_eq!(ask_bps_to_cents(4540), 46);
+        assert_eq!(ask_bps_to_cents(10_001), NO_PRICE);
+    }
 
-    // send! ~~ 
-    let _ = exec_tx.try_send(req);
-}
\ No newline at end of file
+    #[test]
+    fn test_neg_risk_event_outcomes() {
+        let json = r#"[{
+            "slug": "epl-winner-2026", "title": "Premier League Winner", "negRisk": true,
+            "markets": [
+                {"question": "Will Arsenal win?", "groupItemTitle": "Arsenal", "active": true, "closed": false,
+                 "clobTokenIds": "[\"111\", \"112\"]", "orderPriceMinTickSize": 0.001, "orderMinSize": 15},
+                {"question": "Will Liverpool win?", "active": true, "closed": false,
+                 "clobTokenIds": "[\"221\", \"222\"]"}
+            ]
+        }]"#;
+        let mut events: Vec<GammaEvent> = serde_json::from_str(json).unwrap();
+        let market = events[0].multi_outcome().unwrap();
+        assert_eq!(&*market.description, "Premier League Winner");
+        let outcomes: Vec<_> = market.outcomes.iter().map(|o| (&*o.name, &*o.token)).collect();
+        assert_eq!(outcomes, vec![("Arsenal", "111"), ("Will Liverpool win?", "221")]);
+        // Constraints Gamma leaves out fall back to the defaults
+        assert_eq!(market.outcomes[0].constraints, OrderConstraints { tick_bps: 10, min_size: 15.0 });
+        assert_eq!(market.outcomes[1].constraints, OrderConstraints::default());
+
+        // A closed outcome leaves the set incomplete
+        events[0].markets[1].closed = Some(true);
+        assert!(events[0].multi_outcome().is_none());
+
+        events[0].markets[1].closed = Some(false);
+        events[0].neg_risk = Some(false);
+        assert!(events[0].multi_outcome().is_none());
+    }
+}
@@ -3,9 +3,9 @@
 //! This module provides high-performance order execution for the Polymarket CLOB,
 //! including pre-computed authentication credentials and optimized request handling.
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::{Result, anyhow};
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE;
 use ethers::signers::{LocalWallet, Signer};
@@ -15,14 +15,27 @@ use ethers::types::U256;
 use hmac::{Hmac, Mac};
 use reqwest::header::{HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
+use rustc_hash::FxHashMap;
 use serde_json::json;
 use sha2::Sha256;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::broadcast;
+
+use crate::config::{
+    HttpConfig, ORDER_STATUS_POLL_MS, ORDER_SUBMIT_ATTEMPTS, OrderTtlConfig, POLY_AUTH_BACKOFF_MAX_MS, POLY_AUTH_BACKOFF_MS,
+    POLY_DATA_API_BASE, PolyAuthConfig,
+};
+use crate::error::{retries_exhausted, Error, Result};
+use crate::latency::Timed;
+use crate::rate_limit;
+use crate::types::{OrderConstraints, OrderOutcome, OrderType, Platform, Price};
 
 const USER_AGENT: &str = "py_clob_client";
 const MSG_TO_SIGN: &str = "This message attests that I control the given wallet";
 const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+/// Largest page the Data API returns for `/positions`
+const POSITIONS_PAGE_SIZE: usize = 500;
 
 // ============================================================================
 // PRE-COMPUTED EIP712 CONSTANTS
@@ -54,14 +67,15 @@ pub struct PreparedCreds {
 
 impl PreparedCreds {
     pub fn from_api_creds(creds: &ApiCreds) -> Result<Self> {
-        let decoded_secret = URL_SAFE.decode(&creds.api_secret)?;
+        let decoded_secret = URL_SAFE.decode(&creds.api_secret)
+            .map_err(|e| Error::Auth(format!("Invalid API secret: {}", e)))?;
         let hmac_template = HmacSha256::new_from_slice(&decoded_secret)
-            .map_err(|e| anyhow!("Invalid HMAC key: {}", e))?;
+            .map_err(|e| Error::Auth(format!("Invalid HMAC key: {}", e)))?;
 
         let api_key_header = HeaderValue::from_str(&creds.api_key)
-            .map_err(|e| anyhow!("Invalid API key for header: {}", e))?;
+            .map_err(|e| Error::Auth(format!("Invalid API key for header: {}", e)))?;
         let passphrase_header = HeaderValue::from_str(&creds.api_passphrase)
-            .map_err(|e| anyhow!("Invalid passphrase for header: {}", e))?;
+            .map_err(|e| Error::Auth(format!("Invalid passphrase for header: {}", e)))?;
 
         Ok(Self {
             api_key: creds.api_key.clone(),
@@ -98,6 +112,48 @@ impl PreparedCreds {
     }
 }
 
+/// L2 credentials in use and when they are due for renewal
+struct CredsState {
+    creds: Arc<PreparedCreds>,
+    /// Renew from this point on, ahead of the expiry
+    refresh_at: Option<Instant>,
+    /// Renewals failed in a row
+    failures: u32,
+    /// No renewal is attempted before this after a failure
+    retry_at: Option<Instant>,
+}
+
+impl CredsState {
+    fn new(creds: PreparedCreds, config: &PolyAuthConfig, now: Instant) -> Self {
+        Self { creds: Arc::new(creds), refresh_at: Self::next_refresh(config, now), failures: 0, retry_at: None }
+    }
+
+    fn next_refresh(config: &PolyAuthConfig, now: Instant) -> Option<Instant> {
+        config.creds_ttl_secs.map(|ttl| now + Duration::from_secs(ttl.saturating_sub(config.refresh_margin_secs)))
+    }
+
+    /// Close enough to the expiry to renew before the next request
+    fn refresh_due(&self, now: Instant) -> bool {
+        self.refresh_at.is_some_and(|at| now >= at) && !self.backing_off(now)
+    }
+
+    fn backing_off(&self, now: Instant) -> bool {
+        self.retry_at.is_some_and(|at| now < at)
+    }
+
+    fn renewed(&mut self, creds: PreparedCreds, config: &PolyAuthConfig, now: Instant) {
+        *self = Self::new(creds, config, now);
+    }
+
+    /// Back off before the next attempt; true on the first failure in a row
+    fn renewal_failed(&mut self, now: Instant) -> bool {
+        self.failures += 1;
+        let backoff = POLY_AUTH_BACKOFF_MS.saturating_mul(1 << (self.failures - 1).min(16)).min(POLY_AUTH_BACKOFF_MAX_MS);
+        self.retry_at = Some(now + Duration::from_millis(backoff));
+        self.failures == 1
+    }
+}
+
 fn add_default_headers(headers: &mut HeaderMap) {
     headers.insert("User-Agent", HeaderValue::from_static(USER_AGENT));
     headers.insert("Accept", HeaderValue::from_static("*/*"));
@@ -105,6 +161,10 @@ fn add_default_headers(headers: &mut HeaderMap) {
     headers.insert("Content-Type", HeaderValue::from_static("application/json"));
 }
 
+fn header_value(s: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(s).map_err(|e| Error::Auth(format!("Invalid auth header value: {}", e)))
+}
+
 #[inline(always)]
 fn current_unix_ts() -> u64 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
@@ -130,7 +190,7 @@ fn clob_auth_digest(chain_id: u64, address_str: &str, timestamp: u64, nonce: u64
         "message": { "address": address_str, "timestamp": timestamp.to_string(), "nonce": nonce, "message": MSG_TO_SIGN }
     });
     let typed: TypedData = serde_json::from_value(typed_json)?;
-    Ok(typed.encode_eip712()?.into())
+    Ok(typed.encode_eip712().map_err(Error::auth)?.into())
 }
 
 #[derive(Debug, Clone)]
@@ -186,11 +246,15 @@ pub struct OrderStruct {
 #[derive(Debug, Clone, Serialize)]
 pub struct SignedOrder { 
     pub order: OrderStruct, 
-    pub signature: String 
+    pub signature: String,
+    /// EIP712 hash of the order: the CLOB uses it as the order ID, so it is
+    /// known before submitting and doubles as the idempotency key
+    #[serde(skip)]
+    pub order_hash: String,
 }
 
 impl SignedOrder {
-    pub fn post_body(&self, owner: &str, order_type: &str) -> String {
+    pub fn post_body(&self, owner: &str, order_type: &str, post_only: bool) -> String {
         let side_str = if self.order.side == 0 { "BUY" } else { "SELL" };
         let mut buf = String::with_capacity(512);
         buf.push_str(r#"{"order":{"salt":"#);
@@ -223,14 +287,18 @@ impl SignedOrder {
         buf.push_str(owner);
         buf.push_str(r#"","orderType":""#);
         buf.push_str(order_type);
-        buf.push_str(r#""}"#);
+        buf.push('"');
+        if post_only {
+            buf.push_str(r#","postOnly":true"#);
+        }
+        buf.push('}');
         buf
     }
 }
 
 #[inline(always)]
 fn generate_seed() -> u128 {
-    (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() % u128::from(u32::MAX)) as u128
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() % u128::from(u32::MAX)
 }
 
 // ============================================================================
@@ -275,11 +343,25 @@ pub fn get_order_amounts_sell(size_micro: u64, price_bps: u64) -> (i32, u128, u1
     (1, maker, taker)
 }
 
+/// Tick size and minimum order size from a CLOB `/book` response
+pub fn parse_constraints(book: &serde_json::Value) -> Result<OrderConstraints> {
+    let field = |name: &str| {
+        let value = &book[name];
+        value.as_str().and_then(|s| s.parse::<f64>().ok()).or_else(|| value.as_f64())
+            .filter(|v| v.is_finite() && *v > 0.0)
+            .ok_or_else(|| Error::Serialization(format!("invalid {} {}", name, value)))
+    };
+    Ok(OrderConstraints {
+        tick_bps: (field("tick_size")? * 10_000.0).round().max(1.0) as u64,
+        min_size: field("min_order_size")?,
+    })
+}
+
 /// Validate price is within allowed range for tick=0.01
 #[inline(always)]
 pub fn price_valid(price_bps: u64) -> bool {
     // For tick=0.01: price must be >= 0.01 (100 bps) and <= 0.99 (9900 bps)
-    price_bps >= 100 && price_bps <= 9900
+    (100..=9900).contains(&price_bps)
 }
 
 fn order_typed_data(chain_id: u64, exchange: &str, data: &OrderData<'_>) -> Result<TypedData> {
@@ -313,12 +395,12 @@ fn order_typed_data(chain_id: u64, exchange: &str, data: &OrderData<'_>) -> Resu
             "maker": data.maker,
             "signer": data.signer,
             "taker": data.taker,
-            "tokenId": U256::from_dec_str(data.token_id)?,
-            "makerAmount": U256::from_dec_str(data.maker_amount)?,
-            "takerAmount": U256::from_dec_str(data.taker_amount)?,
-            "expiration": U256::from_dec_str(data.expiration)?,
-            "nonce": U256::from_dec_str(data.nonce)?,
-            "feeRateBps": U256::from_dec_str(data.fee_rate_bps)?,
+            "tokenId": dec_u256(data.token_id)?,
+            "makerAmount": dec_u256(data.maker_amount)?,
+            "takerAmount": dec_u256(data.taker_amount)?,
+            "expiration": dec_u256(data.expiration)?,
+            "nonce": dec_u256(data.nonce)?,
+            "feeRateBps": dec_u256(data.fee_rate_bps)?,
             "side": data.side,
             "signatureType": data.signature_type,
         }
@@ -326,13 +408,17 @@ fn order_typed_data(chain_id: u64, exchange: &str, data: &OrderData<'_>) -> Resu
     Ok(serde_json::from_value(typed_json)?)
 }
 
+fn dec_u256(s: &str) -> Result<U256> {
+    U256::from_dec_str(s).map_err(|e| Error::Invalid(format!("Invalid order field '{}': {}", s, e)))
+}
+
 fn get_exchange_address(chain_id: u64, neg_risk: bool) -> Result<String> {
     match (chain_id, neg_risk) {
         (137, true) => Ok("0xC5d563A36AE78145C45a50134d48A1215220f80a".into()),
         (137, false) => Ok("0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E".into()),
         (80002, true) => Ok("0xd91E80cF2E7be2e162c6513ceD06f1dD0dA35296".into()),
         (80002, false) => Ok("0xdFE02Eb6733538f8Ea35D585af8DE5958AD99E40".into()),
-        _ => Err(anyhow!("unsupported chain")),
+        _ => Err(Error::Invalid(format!("unsupported chain {}", chain_id))),
     }
 }
 
@@ -342,7 +428,7 @@ fn get_exchange_address(chain_id: u64, neg_risk: bool) -> Result<String> {
 
 /// Order type for Polymarket
 #[derive(Debug, Clone, Copy)]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::upper_case_acronyms)]
 pub enum PolyOrderType {
     /// Good Till Cancelled (default)
     GTC,
@@ -355,6 +441,16 @@ pub enum PolyOrderType {
 }
 
 impl PolyOrderType {
+    /// CLOB order type and post-only flag for an `OrderType`
+    pub fn for_order(order_type: OrderType) -> (Self, bool) {
+        match order_type {
+            OrderType::Ioc => (PolyOrderType::FAK, false),
+            OrderType::Market => (PolyOrderType::FOK, false),
+            OrderType::Limit => (PolyOrderType::GTC, false),
+            OrderType::PostOnly => (PolyOrderType::GTC, true),
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             PolyOrderType::GTC => "GTC",
@@ -369,6 +465,52 @@ impl PolyOrderType {
 // GET ORDER RESPONSE
 // ============================================================================
 
+/// One trade from GET /data/trades: our order was either the taker or one of the makers
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolymarketTrade {
+    pub id: String,
+    #[serde(default)]
+    pub taker_order_id: String,
+    pub size: String,
+    pub price: String,
+    #[serde(default)]
+    pub fee_rate_bps: String,
+    #[serde(default)]
+    pub maker_orders: Vec<PolymarketMakerFill>,
+}
+
+/// A maker order's part of a trade
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolymarketMakerFill {
+    pub order_id: String,
+    pub matched_amount: String,
+    pub price: String,
+    #[serde(default)]
+    pub fee_rate_bps: String,
+}
+
+impl PolymarketTrade {
+    /// (shares, price, fee in dollars) matched by `order_id` in this trade.
+    /// The fee follows the CLOB formula: rate × min(p, 1 - p) × shares.
+    pub fn fill_of(&self, order_id: &str) -> Option<(f64, f64, f64)> {
+        let (size, price, bps) = if self.taker_order_id == order_id {
+            (&self.size, &self.price, &self.fee_rate_bps)
+        } else {
+            let maker = self.maker_orders.iter().find(|m| m.order_id == order_id)?;
+            (&maker.matched_amount, &maker.price, &maker.fee_rate_bps)
+        };
+        let (size, price) = (size.parse::<f64>().ok()?, price.parse::<f64>().ok()?);
+        let rate = bps.parse::<f64>().unwrap_or(0.0) / 10_000.0;
+        Some((size, price, rate * price.min(1.0 - price) * size))
+    }
+}
+
+#[derive(Deserialize)]
+struct TradesPage {
+    #[serde(default)]
+    data: Vec<PolymarketTrade>,
+}
+
 /// Response from GET /data/order/{order_id}
 #[derive(Debug, Clone, Deserialize)]
 #[allow(dead_code)]
@@ -394,6 +536,31 @@ pub struct PolymarketOrderResponse {
     pub owner: Option<String>,
 }
 
+/// Response from GET /balance-allowance
+#[derive(Debug, Clone, Deserialize)]
+struct BalanceAllowanceResponse {
+    /// USDC balance in micro-units (6 decimals)
+    balance: String,
+}
+
+/// Position entry from the Data API `/positions` endpoint
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolyPosition {
+    /// Outcome token id
+    pub asset: String,
+    pub size: f64,
+    #[serde(default)]
+    pub avg_price: f64,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// "Yes" / "No" (or a team name for sports markets)
+    #[serde(default)]
+    pub outcome: Option<String>,
+}
+
 // ============================================================================
 // ASYNC CLIENT
 // ============================================================================
@@ -411,18 +578,19 @@ pub struct PolymarketAsyncClient {
 
 impl PolymarketAsyncClient {
     pub fn new(host: &str, chain_id: u64, private_key: &str, funder: &str) -> Result<Self> {
-        let wallet = private_key.parse::<LocalWallet>()?.with_chain_id(chain_id);
+        let wallet = private_key.parse::<LocalWallet>()
+            .map_err(|e| Error::Auth(format!("Invalid private key: {}", e)))?
+            .with_chain_id(chain_id);
         let wallet_address_str = format!("{:?}", wallet.address());
         let address_header = HeaderValue::from_str(&wallet_address_str)
-            .map_err(|e| anyhow!("Invalid wallet address for header: {}", e))?;
+            .map_err(|e| Error::Auth(format!("Invalid wallet address for header: {}", e)))?;
 
         // Build async client with connection pooling and keepalive
-        let http = reqwest::Client::builder()
+        let http = HttpConfig::poly_from_env().client_builder()?
             .pool_max_idle_per_host(10)
             .pool_idle_timeout(std::time::Duration::from_secs(90))
             .tcp_keepalive(std::time::Duration::from_secs(30))
             .tcp_nodelay(true)
-            .timeout(std::time::Duration::from_secs(10))
             .build()?;
 
         Ok(Self {
@@ -441,25 +609,24 @@ impl PolymarketAsyncClient {
     fn build_l1_headers(&self, nonce: u64) -> Result<HeaderMap> {
         let timestamp = current_unix_ts();
         let digest = clob_auth_digest(self.chain_id, &self.wallet_address_str, timestamp, nonce)?;
-        let sig = self.wallet.sign_hash(digest)?;
+        let sig = self.wallet.sign_hash(digest).map_err(Error::auth)?;
         let mut headers = HeaderMap::new();
         headers.insert("POLY_ADDRESS", self.address_header.clone());
-        headers.insert("POLY_SIGNATURE", HeaderValue::from_str(&format!("0x{}", sig))?);
-        headers.insert("POLY_TIMESTAMP", HeaderValue::from_str(&timestamp.to_string())?);
-        headers.insert("POLY_NONCE", HeaderValue::from_str(&nonce.to_string())?);
+        headers.insert("POLY_SIGNATURE", header_value(&format!("0x{}", sig))?);
+        headers.insert("POLY_TIMESTAMP", header_value(&timestamp.to_string())?);
+        headers.insert("POLY_NONCE", header_value(&nonce.to_string())?);
         add_default_headers(&mut headers);
         Ok(headers)
     }
 
     /// Derive API credentials from L1 wallet signature
     pub async fn derive_api_key(&self, nonce: u64) -> Result<ApiCreds> {
+        rate_limit::acquire(Platform::Polymarket).await;
         let url = format!("{}/auth/derive-api-key", self.host);
         let headers = self.build_l1_headers(nonce)?;
-        let resp = self.http.get(&url).headers(headers).send().await?;
+        let resp = self.http.get(&url).headers(headers).send().timed(Platform::Polymarket).await?;
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("derive-api-key failed: {} {}", status, body));
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("derive-api-key"));
         }
         Ok(resp.json().await?)
     }
@@ -474,8 +641,8 @@ impl PolymarketAsyncClient {
 
         let mut headers = HeaderMap::with_capacity(9);
         headers.insert("POLY_ADDRESS", self.address_header.clone());
-        headers.insert("POLY_SIGNATURE", HeaderValue::from_str(&sig_b64)?);
-        headers.insert("POLY_TIMESTAMP", HeaderValue::from_str(&timestamp.to_string())?);
+        headers.insert("POLY_SIGNATURE", header_value(&sig_b64)?);
+        headers.insert("POLY_TIMESTAMP", header_value(&timestamp.to_string())?);
         headers.insert("POLY_API_KEY", creds.api_key_header());
         headers.insert("POLY_PASSPHRASE", creds.passphrase_header());
         add_default_headers(&mut headers);
@@ -484,6 +651,7 @@ impl PolymarketAsyncClient {
 
     /// Post order 
     pub async fn post_order_async(&self, body: String, creds: &PreparedCreds) -> Result<reqwest::Response> {
+        rate_limit::acquire(Platform::Polymarket).await;
         let path = "/order";
         let url = format!("{}{}", self.host, path);
         let headers = self.build_l2_headers("POST", path, Some(&body), creds)?;
@@ -493,6 +661,7 @@ impl PolymarketAsyncClient {
             .headers(headers)
             .body(body)
             .send()
+            .timed(Platform::Polymarket)
             .await?;
 
         Ok(resp)
@@ -500,6 +669,7 @@ impl PolymarketAsyncClient {
 
     /// Get order by ID 
     pub async fn get_order_async(&self, order_id: &str, creds: &PreparedCreds) -> Result<PolymarketOrderResponse> {
+        rate_limit::acquire(Platform::Polymarket).await;
         let path = format!("/data/order/{}", order_id);
         let url = format!("{}{}", self.host, path);
         let headers = self.build_l2_headers("GET", &path, None, creds)?;
@@ -508,30 +678,199 @@ impl PolymarketAsyncClient {
             .get(&url)
             .headers(headers)
             .send()
+            .timed(Platform::Polymarket)
             .await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("get_order failed {}: {}", status, body));
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("get_order"));
         }
 
         Ok(resp.json().await?)
     }
 
+    /// Get order by ID, `None` if the CLOB has no such order
+    pub async fn find_order_async(&self, order_id: &str, creds: &PreparedCreds) -> Result<Option<PolymarketOrderResponse>> {
+        rate_limit::acquire(Platform::Polymarket).await;
+        let path = format!("/data/order/{}", order_id);
+        let url = format!("{}{}", self.host, path);
+        let headers = self.build_l2_headers("GET", &path, None, creds)?;
+
+        let resp = self.http
+            .get(&url)
+            .headers(headers)
+            .send()
+            .timed(Platform::Polymarket)
+            .await?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("get_order"));
+        }
+
+        // Unknown IDs come back as an empty body or `null`
+        let body = resp.text().await?;
+        if body.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Look up a trade by ID
+    pub async fn get_trade_async(&self, trade_id: &str, creds: &PreparedCreds) -> Result<Option<PolymarketTrade>> {
+        rate_limit::acquire(Platform::Polymarket).await;
+        // Query parameters are not part of the signed path
+        let path = "/data/trades";
+        let url = format!("{}{}?id={}", self.host, path, trade_id);
+        let headers = self.build_l2_headers("GET", path, None, creds)?;
+
+        let resp = self.http
+            .get(&url)
+            .headers(headers)
+            .send()
+            .timed(Platform::Polymarket)
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("get_trades"));
+        }
+        let page: TradesPage = resp.json().await?;
+        Ok(page.data.into_iter().find(|t| t.id == trade_id))
+    }
+
+    /// Cancel an order's unmatched remainder
+    pub async fn cancel_order_async(&self, order_id: &str, creds: &PreparedCreds) -> Result<()> {
+        rate_limit::acquire(Platform::Polymarket).await;
+        let path = "/order";
+        let url = format!("{}{}", self.host, path);
+        let body = json!({ "orderID": order_id }).to_string();
+        let headers = self.build_l2_headers("DELETE", path, Some(&body), creds)?;
+
+        let resp = self.http
+            .delete(&url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .timed(Platform::Polymarket)
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("cancel_order"));
+        }
+        Ok(())
+    }
+
+    /// USDC collateral available to the funder wallet, in cents
+    pub async fn get_balance_async(&self, creds: &PreparedCreds) -> Result<i64> {
+        rate_limit::acquire(Platform::Polymarket).await;
+        let path = "/balance-allowance";
+        // Same signature type as the orders; the query string is not signed
+        let url = format!("{}{}?asset_type=COLLATERAL&signature_type=1", self.host, path);
+        let headers = self.build_l2_headers("GET", path, None, creds)?;
+
+        let resp = self.http
+            .get(&url)
+            .headers(headers)
+            .send()
+            .timed(Platform::Polymarket)
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("get_balance"));
+        }
+
+        let resp: BalanceAllowanceResponse = resp.json().await?;
+        let micro: u64 = resp.balance.parse()
+            .map_err(|_| Error::Serialization(format!("invalid balance {:?}", resp.balance)))?;
+        Ok((micro / 10_000) as i64)
+    }
+
     /// Check neg_risk for token - with caching
     pub async fn check_neg_risk(&self, token_id: &str) -> Result<bool> {
+        rate_limit::acquire(Platform::Polymarket).await;
         let url = format!("{}/neg-risk?token_id={}", self.host, token_id);
         let resp = self.http
             .get(&url)
             .header("User-Agent", USER_AGENT)
             .send()
+            .timed(Platform::Polymarket)
             .await?;
 
         let val: serde_json::Value = resp.json().await?;
         Ok(val["neg_risk"].as_bool().unwrap_or(false))
     }
 
+    /// Tick size and minimum order size of the token's market
+    pub async fn get_order_constraints(&self, token_id: &str) -> Result<OrderConstraints> {
+        rate_limit::acquire(Platform::Polymarket).await;
+        let url = format!("{}/book?token_id={}", self.host, token_id);
+        let resp = self.http
+            .get(&url)
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .timed(Platform::Polymarket)
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("get_order_constraints"));
+        }
+
+        parse_constraints(&resp.json().await?)
+    }
+
+    /// A token of the CLOB's sampling markets, to fetch a book without
+    /// running discovery
+    pub async fn sample_token(&self) -> Result<String> {
+        rate_limit::acquire(Platform::Polymarket).await;
+        let url = format!("{}/sampling-markets", self.host);
+        let resp = self.http
+            .get(&url)
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .timed(Platform::Polymarket)
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_failed(Platform::Polymarket, resp).await.context("sampling-markets"));
+        }
+
+        let markets: serde_json::Value = resp.json().await?;
+        markets["data"].as_array().into_iter().flatten()
+            .flat_map(|m| m["tokens"].as_array().into_iter().flatten())
+            .find_map(|t| t["token_id"].as_str().filter(|id| !id.is_empty()))
+            .map(str::to_string)
+            .ok_or_else(|| Error::Serialization("no token in /sampling-markets".into()))
+    }
+
+    /// Open positions held by the funder wallet (Data API, every page: it
+    /// pages by offset, up to `POSITIONS_PAGE_SIZE` at a time)
+    pub async fn get_positions(&self) -> Result<Vec<PolyPosition>> {
+        let mut positions = Vec::new();
+        loop {
+            rate_limit::acquire(Platform::Polymarket).await;
+            let url = format!("{}/positions?user={}&sizeThreshold=0.01&limit={}&offset={}",
+                              POLY_DATA_API_BASE, self.funder, POSITIONS_PAGE_SIZE, positions.len());
+            let resp = self.http
+                .get(&url)
+                .header("User-Agent", USER_AGENT)
+                .send()
+                .timed(Platform::Polymarket)
+                .await?;
+
+            if !resp.status().is_success() {
+                return Err(Error::from_failed(Platform::Polymarket, resp).await.context("get_positions"));
+            }
+
+            let page: Vec<PolyPosition> = resp.json().await?;
+            let last = page.len() < POSITIONS_PAGE_SIZE;
+            positions.extend(page);
+            if last {
+                return Ok(positions);
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub fn wallet_address(&self) -> &str {
         &self.wallet_address_str
@@ -548,25 +887,135 @@ impl PolymarketAsyncClient {
     }
 }
 
+type AuthFailureHook = Box<dyn Fn(&Error) + Send + Sync>;
+
 /// Shared async client wrapper for use in execution engine
 pub struct SharedAsyncClient {
     inner: Arc<PolymarketAsyncClient>,
-    creds: PreparedCreds,
+    creds: std::sync::RwLock<CredsState>,
+    /// Held while deriving new credentials so concurrent rejections renew once
+    renewal: tokio::sync::Mutex<()>,
+    auth_config: PolyAuthConfig,
+    on_auth_failure: OnceLock<AuthFailureHook>,
     chain_id: u64,
     /// Pre-cached neg_risk lookups
     neg_risk_cache: std::sync::RwLock<HashMap<String, bool>>,
+    order_ttl: OrderTtlConfig,
 }
 
 impl SharedAsyncClient {
     pub fn new(client: PolymarketAsyncClient, creds: PreparedCreds, chain_id: u64) -> Self {
+        let auth_config = PolyAuthConfig::from_env();
         Self {
             inner: Arc::new(client),
-            creds,
+            creds: std::sync::RwLock::new(CredsState::new(creds, &auth_config, Instant::now())),
+            renewal: tokio::sync::Mutex::new(()),
+            auth_config,
+            on_auth_failure: OnceLock::new(),
             chain_id,
             neg_risk_cache: std::sync::RwLock::new(HashMap::new()),
+            order_ttl: OrderTtlConfig::from_env(),
         }
     }
 
+    /// Called once per run of failed credential renewals
+    pub fn on_auth_failure(&self, hook: impl Fn(&Error) + Send + Sync + 'static) {
+        let _ = self.on_auth_failure.set(Box::new(hook));
+    }
+
+    /// Credentials for the next request, renewed first when about to expire
+    async fn creds(&self) -> Arc<PreparedCreds> {
+        let (creds, due) = {
+            let state = self.creds.read().unwrap_or_else(|e| e.into_inner());
+            (state.creds.clone(), state.refresh_due(Instant::now()))
+        };
+        if !due {
+            return creds;
+        }
+        tracing::info!("[POLY-ASYNC] API credentials about to expire, renewing");
+        // Still valid until they actually expire
+        self.renew(&creds).await.unwrap_or(creds)
+    }
+
+    /// Replace `stale` with freshly derived credentials. Callers rejected
+    /// with the same credentials share one renewal.
+    async fn renew(&self, stale: &Arc<PreparedCreds>) -> Result<Arc<PreparedCreds>> {
+        let _renewal = self.renewal.lock().await;
+        {
+            let state = self.creds.read().unwrap_or_else(|e| e.into_inner());
+            if !Arc::ptr_eq(&state.creds, stale) {
+                return Ok(state.creds.clone());
+            }
+            if state.backing_off(Instant::now()) {
+                return Err(Error::Auth(format!(
+                    "Polymarket credential renewal failed {} times, backing off", state.failures)));
+            }
+        }
+
+        let derived = match self.inner.derive_api_key(0).await {
+            Ok(api_creds) => PreparedCreds::from_api_creds(&api_creds),
+            Err(e) => Err(e),
+        };
+        let now = Instant::now();
+        let mut state = self.creds.write().unwrap_or_else(|e| e.into_inner());
+        match derived {
+            Ok(creds) => {
+                if state.failures > 0 {
+                    tracing::info!("[POLY-ASYNC] API credentials renewed after {} failed attempts", state.failures);
+                } else {
+                    tracing::info!("[POLY-ASYNC] API credentials renewed");
+                }
+                state.renewed(creds, &self.auth_config, now);
+                Ok(state.creds.clone())
+            }
+            Err(e) => {
+                let first = state.renewal_failed(now);
+                drop(state);
+                let err = Error::Auth(format!("Polymarket credential renewal failed: {}", e));
+                tracing::error!("[POLY-ASYNC] {}", err);
+                if first {
+                    if let Some(hook) = self.on_auth_failure.get() {
+                        hook(&err);
+                    }
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Run an authenticated request. A 401/403 renews the credentials and
+    /// retries once; only a failed renewal or a second rejection is returned.
+    async fn authed<T, Fut>(&self, request: impl Fn(Arc<PreparedCreds>) -> Fut) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let creds = self.creds().await;
+        match request(creds.clone()).await {
+            Err(e) if matches!(e.root(), Error::Auth(_)) => {
+                tracing::warn!("[POLY-ASYNC] Credentials rejected ({}), renewing and retrying", e);
+                let fresh = self.renew(&creds).await?;
+                request(fresh).await
+            }
+            result => result,
+        }
+    }
+
+    /// Open positions held by the funder wallet
+    pub async fn get_positions(&self) -> Result<Vec<PolyPosition>> {
+        self.inner.get_positions().await
+    }
+
+    /// USDC available for new orders, in cents
+    pub async fn get_balance(&self) -> Result<i64> {
+        self.authed(|creds| async move { self.inner.get_balance_async(&creds).await }).await
+            .inspect_err(|e| {
+                // Rejected again with renewed credentials
+                if matches!(e.root(), Error::Auth(_)) {
+                    retries_exhausted("balance fetch", Platform::Polymarket, 2, e);
+                }
+            })
+    }
+
     /// Load neg_risk cache from JSON file (output of build_sports_cache.py)
     pub fn load_cache(&self, path: &str) -> Result<usize> {
         let data = std::fs::read_to_string(path)?;
@@ -578,22 +1027,26 @@ impl SharedAsyncClient {
     }
 
     /// Execute FAK buy order - 
-    pub async fn buy_fak(&self, token_id: &str, price: f64, size: f64) -> Result<PolyFillAsync> {
+    #[allow(dead_code)]
+    pub async fn buy_fak(&self, token_id: &str, price: Price, size: f64) -> Result<PolyFillAsync> {
+        self.buy(token_id, price, size, OrderType::Ioc).await
+    }
+
+    /// Execute FAK sell order - 
+    pub async fn sell_fak(&self, token_id: &str, price: Price, size: f64) -> Result<PolyFillAsync> {
         debug_assert!(!token_id.is_empty(), "token_id must not be empty");
-        debug_assert!(price > 0.0 && price < 1.0, "price must be 0 < p < 1");
         debug_assert!(size >= 1.0, "size must be >= 1");
-        self.execute_order(token_id, price, size, "BUY").await
+        self.execute_order(token_id, price, size, "SELL", OrderType::Ioc).await
     }
 
-    /// Execute FAK sell order - 
-    pub async fn sell_fak(&self, token_id: &str, price: f64, size: f64) -> Result<PolyFillAsync> {
+    /// Buy order of the given type; resting orders are cancelled at the order TTL
+    pub async fn buy(&self, token_id: &str, price: Price, size: f64, order_type: OrderType) -> Result<PolyFillAsync> {
         debug_assert!(!token_id.is_empty(), "token_id must not be empty");
-        debug_assert!(price > 0.0 && price < 1.0, "price must be 0 < p < 1");
         debug_assert!(size >= 1.0, "size must be >= 1");
-        self.execute_order(token_id, price, size, "SELL").await
+        self.execute_order(token_id, price, size, "BUY", order_type).await
     }
 
-    async fn execute_order(&self, token_id: &str, price: f64, size: f64, side: &str) -> Result<PolyFillAsync> {
+    async fn execute_order(&self, token_id: &str, price: Price, size: f64, side: &str, order_type: OrderType) -> Result<PolyFillAsync> {
         // Check neg_risk cache first
         let neg_risk = {
             let cache = self.neg_risk_cache.read().unwrap();
@@ -612,52 +1065,189 @@ impl SharedAsyncClient {
 
         // Build signed order
         let signed = self.build_signed_order(token_id, price, size, side, neg_risk)?;
-        // Owner must be the API key (not wallet address or funder!)
-        let body = signed.post_body(&self.creds.api_key, PolyOrderType::FAK.as_str());
-
-        // Post order
-        let resp = self.inner.post_order_async(body, &self.creds).await?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Polymarket order failed {}: {}", status, body));
-        }
-
-        let resp_json: serde_json::Value = resp.json().await?;
-        let order_id = resp_json["orderID"].as_str().unwrap_or("unknown").to_string();
-
-        // Query fill status
-        let order_info = self.inner.get_order_async(&order_id, &self.creds).await?;
+        let (clob_type, post_only) = PolyOrderType::for_order(order_type);
+        let order_hash = signed.order_hash.as_str();
+
+        // Post order. Retries resend the same signed order, which the CLOB
+        // rejects as a duplicate if the earlier attempt was in fact accepted.
+        let mut attempt = 1;
+        let order_info = loop {
+            let err = match self.post_order(&signed, clob_type, post_only).await {
+                Ok(order_id) => {
+                    let order_id = order_id.unwrap_or_else(|| order_hash.to_string());
+                    // Query fill status
+                    break self.get_order(&order_id).await?;
+                }
+                Err(e) if e.outcome_unknown() => e,
+                Err(e) => return Err(e.context(format!("Polymarket order {}", order_hash))),
+            };
+
+            tracing::warn!("[POLY-ASYNC] Order {} outcome unknown ({}), checking status", order_hash, err);
+            match self.authed(|creds| async move { self.inner.find_order_async(order_hash, &creds).await }).await {
+                Ok(Some(existing)) => break existing,
+                Ok(None) if attempt < ORDER_SUBMIT_ATTEMPTS => {
+                    attempt += 1;
+                    tracing::warn!("[POLY-ASYNC] Order {} not found, resubmitting (attempt {}/{})",
+                                   order_hash, attempt, ORDER_SUBMIT_ATTEMPTS);
+                }
+                Ok(None) => {
+                    let err = err.context(format!("Polymarket order {} not placed", order_hash));
+                    retries_exhausted("order submit", Platform::Polymarket, attempt, &err);
+                    return Err(err);
+                }
+                // Can't tell whether it was placed: resubmitting could double the position
+                Err(lookup) => {
+                    let err = err.context(format!("Polymarket order {} status unknown (lookup failed: {})", order_hash, lookup));
+                    retries_exhausted("order submit", Platform::Polymarket, attempt, &err);
+                    return Err(err);
+                }
+            }
+        };
+        let (order_info, outcome) = self.await_final(order_info).await;
+        let order_id = order_info.id.clone();
         let filled_size: f64 = order_info.size_matched.parse().unwrap_or(0.0);
-        let order_price: f64 = order_info.price.parse().unwrap_or(price);
+        let order_price: f64 = order_info.price.parse().unwrap_or(price.to_poly());
 
         tracing::debug!(
-            "[POLY-ASYNC] FAK {} {}: status={}, filled={:.2}/{:.2}, price={:.4}",
-            side, order_id, order_info.status, filled_size, size, order_price
+            "[POLY-ASYNC] {} {} {}: status={}, filled={:.2}/{:.2}, price={:.4}",
+            clob_type.as_str(), side, order_id, order_info.status, filled_size, size, order_price
         );
 
         Ok(PolyFillAsync {
             order_id,
             filled_size,
             fill_cost: filled_size * order_price,
+            fees: 0.0,
+            outcome,
         })
     }
 
+    /// What an order actually filled, from its trades: the matched prices
+    /// (a taker can fill better than its limit) and the fees charged.
+    /// Falls back to the order's own price when it lists no trades.
+    pub async fn fill_details(&self, order_id: &str) -> Result<PolyFillAsync> {
+        let order = self.get_order(order_id).await?;
+        let matched: f64 = order.size_matched.parse().unwrap_or(0.0);
+        let mut fill = PolyFillAsync {
+            order_id: order.id.clone(),
+            filled_size: matched,
+            fill_cost: matched * order.price.parse::<f64>().unwrap_or(0.0),
+            fees: 0.0,
+            outcome: OrderOutcome::Completed,
+        };
+        let trade_ids: Vec<&str> = order.associate_trades.iter().filter_map(|t| t.as_str()).collect();
+        if trade_ids.is_empty() {
+            return Ok(fill);
+        }
+        let (mut size, mut cost, mut fees) = (0.0, 0.0, 0.0);
+        for trade_id in trade_ids {
+            let trade = self.authed(|creds| async move { self.inner.get_trade_async(trade_id, &creds).await }).await?;
+            let Some((shares, price, fee)) = trade.and_then(|t| t.fill_of(&order.id)) else {
+                return Err(Error::Invalid(format!("trade {} of order {} not found", trade_id, order.id)));
+            };
+            size += shares;
+            cost += shares * price;
+            fees += fee;
+        }
+        fill.filled_size = size;
+        fill.fill_cost = cost;
+        fill.fees = fees;
+        Ok(fill)
+    }
+
+    /// Poll an order until matching is done so `size_matched` is final. FAK
+    /// orders can sit in "delayed" (sports markets match after a delay);
+    /// one still pending when its time-to-live runs out is cancelled.
+    async fn await_final(&self, mut order: PolymarketOrderResponse) -> (PolymarketOrderResponse, OrderOutcome) {
+        let deadline = tokio::time::Instant::now() + self.order_ttl.ttl();
+        while order_pending(&order.status) && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(ORDER_STATUS_POLL_MS)).await;
+            match self.get_order(&order.id).await {
+                Ok(latest) => order = latest,
+                Err(e) => tracing::warn!("[POLY-ASYNC] Status check of {} failed: {}", order.id, e),
+            }
+        }
+
+        if !order_pending(&order.status) {
+            return (order, OrderOutcome::Completed);
+        }
+        tracing::warn!("[POLY-ASYNC] Order {} still {} after {}ms - cancelling remainder",
+                       order.id, order.status, self.order_ttl.ttl_ms);
+        self.cancel_confirmed(order).await
+    }
+
+    /// Cancel a pending order until a fresh read shows it no longer live.
+    /// Whatever matched before the cancel is the fill; while the cancel is
+    /// unconfirmed the remainder may still match.
+    pub async fn cancel_confirmed(&self, mut order: PolymarketOrderResponse) -> (PolymarketOrderResponse, OrderOutcome) {
+        let attempts = self.order_ttl.cancel_attempts;
+        for attempt in 1..=attempts {
+            let order_id = order.id.as_str();
+            if let Err(e) = self.authed(|creds| async move { self.inner.cancel_order_async(order_id, &creds).await }).await {
+                tracing::warn!("[POLY-ASYNC] Cancel of {} failed (attempt {}/{}): {}", order.id, attempt, attempts, e);
+            }
+            match self.get_order(&order.id).await {
+                Ok(latest) => order = latest,
+                Err(e) => tracing::warn!("[POLY-ASYNC] Status check of {} failed: {}", order.id, e),
+            }
+            if !order_pending(&order.status) {
+                let outcome = if order.status.eq_ignore_ascii_case("matched") {
+                    OrderOutcome::Completed
+                } else {
+                    OrderOutcome::Expired
+                };
+                return (order, outcome);
+            }
+        }
+        retries_exhausted("order cancel", Platform::Polymarket, attempts, &Error::Invalid(format!(
+            "order {} still {}", order.id, order.status)));
+        (order, OrderOutcome::CancelUnconfirmed)
+    }
+
+    /// Re-read an order whose cancel went unconfirmed and cancel it again if
+    /// it is still live
+    pub async fn confirm_cancelled(&self, order_id: &str) -> Result<(PolymarketOrderResponse, OrderOutcome)> {
+        let order = self.get_order(order_id).await?;
+        if !order_pending(&order.status) {
+            return Ok((order, OrderOutcome::Expired));
+        }
+        Ok(self.cancel_confirmed(order).await)
+    }
+
+    async fn get_order(&self, order_id: &str) -> Result<PolymarketOrderResponse> {
+        self.authed(|creds| async move { self.inner.get_order_async(order_id, &creds).await }).await
+    }
+
+    /// Post a signed order, returning the order ID from the response
+    async fn post_order(&self, signed: &SignedOrder, order_type: PolyOrderType, post_only: bool) -> Result<Option<String>> {
+        self.authed(|creds| async move {
+            // Owner must be the API key (not wallet address or funder!)
+            let body = signed.post_body(&creds.api_key, order_type.as_str(), post_only);
+            let resp = self.inner.post_order_async(body, &creds).await?;
+
+            if !resp.status().is_success() {
+                return Err(Error::from_failed(Platform::Polymarket, resp).await.context("Polymarket order"));
+            }
+
+            let resp_json: serde_json::Value = resp.json().await?;
+            Ok(resp_json["orderID"].as_str().map(str::to_string))
+        }).await
+    }
+
     /// Build a signed order
     fn build_signed_order(
         &self,
         token_id: &str,
-        price: f64,
+        price: Price,
         size: f64,
         side: &str,
         neg_risk: bool,
     ) -> Result<SignedOrder> {
-        let price_bps = price_to_bps(price);
+        let price_bps = price.to_bps();
         let size_micro = size_to_micro(size);
 
         if !price_valid(price_bps) {
-            return Err(anyhow!("price {} ({}bps) outside allowed range", price, price_bps));
+            return Err(Error::Invalid(format!("price {} ({}bps) outside allowed range", price, price_bps)));
         }
 
         let (side_code, maker_amt, taker_amt) = if side.eq_ignore_ascii_case("BUY") {
@@ -665,7 +1255,7 @@ impl SharedAsyncClient {
         } else if side.eq_ignore_ascii_case("SELL") {
             get_order_amounts_sell(size_micro, price_bps)
         } else {
-            return Err(anyhow!("side must be BUY or SELL"));
+            return Err(Error::Invalid("side must be BUY or SELL".into()));
         };
 
         let salt = generate_seed();
@@ -689,9 +1279,10 @@ impl SharedAsyncClient {
         };
         let exchange = get_exchange_address(self.chain_id, neg_risk)?;
         let typed = order_typed_data(self.chain_id, &exchange, &data)?;
-        let digest = typed.encode_eip712()?;
+        let digest = typed.encode_eip712().map_err(Error::auth)?;
 
-        let sig = self.inner.wallet.sign_hash(H256::from(digest))?;
+        let order_hash = H256::from(digest);
+        let sig = self.inner.wallet.sign_hash(order_hash).map_err(Error::auth)?;
 
         // Only allocate strings once for the final OrderStruct (serialization needs owned)
         Ok(SignedOrder {
@@ -710,14 +1301,510 @@ impl SharedAsyncClient {
                 signature_type: 1,
             },
             signature: format!("0x{}", sig),
+            order_hash: format!("{:#x}", order_hash),
         })
     }
 }
 
+/// Order still matching: resting on the book or in the matching delay
+fn order_pending(status: &str) -> bool {
+    status.eq_ignore_ascii_case("live") || status.eq_ignore_ascii_case("delayed")
+}
+
 /// Async fill result
 #[derive(Debug, Clone)]
 pub struct PolyFillAsync {
     pub order_id: String,
     pub filled_size: f64,
     pub fill_cost: f64,
-}
\ No newline at end of file
+    /// Fees charged in dollars (only known from `fill_details`)
+    pub fees: f64,
+    /// Whether the order had to be cancelled at its time-to-live
+    pub outcome: OrderOutcome,
+}
+// ============================================================================
+// ORDER BOOK
+// ============================================================================
+
+/// Side of a price level update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+impl BookSide {
+    /// Parse the side of a WS price change ("BUY"/"SELL", or "BID"/"ASK")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "BUY" | "buy" | "BID" | "bid" => Some(Self::Bid),
+            "SELL" | "sell" | "ASK" | "ask" => Some(Self::Ask),
+            _ => None,
+        }
+    }
+}
+
+/// Local order book for one CLOB token: price (bps) → size (contracts)
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    pub bids: BTreeMap<u64, f64>,
+    pub asks: BTreeMap<u64, f64>,
+}
+
+impl OrderBook {
+    /// Replace the book with a full snapshot
+    pub fn apply_snapshot<B, A>(&mut self, bids: B, asks: A)
+    where
+        B: IntoIterator<Item = (u64, f64)>,
+        A: IntoIterator<Item = (u64, f64)>,
+    {
+        self.bids = bids.into_iter().filter(|&(p, s)| p > 0 && s > 0.0).collect();
+        self.asks = asks.into_iter().filter(|&(p, s)| p > 0 && s > 0.0).collect();
+    }
+
+    /// Set the aggregate size at a price level (0 removes the level)
+    pub fn apply_level(&mut self, side: BookSide, price_bps: u64, size: f64) {
+        let levels = match side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        };
+        if size > 0.0 {
+            levels.insert(price_bps, size);
+        } else {
+            levels.remove(&price_bps);
+        }
+    }
+
+    /// Highest bid (price_bps, size)
+    pub fn best_bid(&self) -> Option<(u64, f64)> {
+        self.bids.iter().next_back().map(|(&p, &s)| (p, s))
+    }
+
+    /// Lowest ask (price_bps, size)
+    pub fn best_ask(&self) -> Option<(u64, f64)> {
+        self.asks.iter().next().map(|(&p, &s)| (p, s))
+    }
+
+    /// Best bid at or above the best ask (crossed or locked): a feed glitch,
+    /// the book cannot be traded on
+    pub fn is_crossed(&self) -> bool {
+        matches!((self.best_bid(), self.best_ask()), (Some((bid, _)), Some((ask, _))) if bid >= ask)
+    }
+
+    /// Bid vs ask volume on levels within `depth_bps` of the best bid and
+    /// best ask: (bids - asks) / (bids + asks), in [-1, 1]. `None` for an
+    /// empty book.
+    pub fn imbalance(&self, depth_bps: u64) -> Option<f64> {
+        let bids: f64 = self.best_bid().map_or(0.0, |(best, _)| {
+            self.bids.range(best.saturating_sub(depth_bps)..).map(|(_, s)| s).sum()
+        });
+        let asks: f64 = self.best_ask().map_or(0.0, |(best, _)| {
+            self.asks.range(..=best + depth_bps).map(|(_, s)| s).sum()
+        });
+        let total = bids + asks;
+        (total > 0.0).then(|| (bids - asks) / total)
+    }
+
+    /// Levels in fill order: asks from lowest, bids from highest
+    fn levels(&self, side: BookSide) -> Box<dyn Iterator<Item = (u64, f64)> + '_> {
+        match side {
+            BookSide::Ask => Box::new(self.asks.iter().map(|(&p, &s)| (p, s))),
+            BookSide::Bid => Box::new(self.bids.iter().rev().map(|(&p, &s)| (p, s))),
+        }
+    }
+
+    /// Walk one side of the book to fill `contracts` (asks to buy, bids to sell).
+    /// Fails if the side does not hold enough size.
+    pub fn vwap(&self, side: BookSide, contracts: f64) -> Result<DepthFill> {
+        if contracts <= 0.0 {
+            return Err(Error::Invalid(format!("Invalid quantity {}", contracts)));
+        }
+
+        let mut remaining = contracts;
+        let mut notional = 0.0;
+        for (price_bps, size) in self.levels(side) {
+            let take = size.min(remaining);
+            notional += take * price_bps as f64;
+            remaining -= take;
+            if remaining <= 1e-9 {
+                return Ok(DepthFill {
+                    avg_price_bps: notional / contracts,
+                    worst_price_bps: price_bps,
+                });
+            }
+        }
+
+        Err(Error::InsufficientDepth(format!(
+            "wanted {} contracts, book has {}",
+            contracts,
+            contracts - remaining
+        )))
+    }
+
+    /// Largest quantity fillable with an average price no worse than
+    /// `max_avg_price_bps` (at most for asks, at least for bids)
+    pub fn max_fill_within(&self, side: BookSide, max_avg_price_bps: f64) -> f64 {
+        // Work in "cost" space so both sides fill while avg <= limit
+        let sign = match side {
+            BookSide::Ask => 1.0,
+            BookSide::Bid => -1.0,
+        };
+        let limit = sign * max_avg_price_bps;
+
+        let mut filled = 0.0;
+        let mut notional = 0.0;
+        for (price_bps, size) in self.levels(side) {
+            let price = sign * price_bps as f64;
+            if notional + size * price <= limit * (filled + size) {
+                filled += size;
+                notional += size * price;
+            } else {
+                // Partial level: solve (notional + t*price) / (filled + t) = limit
+                let take = ((limit * filled - notional) / (price - limit)).clamp(0.0, size);
+                filled += take;
+                break;
+            }
+        }
+        filled
+    }
+}
+
+/// Result of walking the book for a given quantity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthFill {
+    /// Volume-weighted average fill price in basis points
+    pub avg_price_bps: f64,
+    /// Price of the deepest level touched, in basis points
+    pub worst_price_bps: u64,
+}
+
+/// Top-of-book change for a token
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct QuoteUpdate {
+    pub token_hash: u64,
+    pub best_bid: Option<(u64, f64)>,
+    pub best_ask: Option<(u64, f64)>,
+}
+
+/// Order books for all subscribed tokens, keyed by `fxhash_str(token_id)`.
+/// Publishes every best bid/ask change on a broadcast channel.
+pub struct OrderBookStore {
+    books: std::sync::RwLock<FxHashMap<u64, OrderBook>>,
+    quotes: broadcast::Sender<QuoteUpdate>,
+}
+
+impl Default for OrderBookStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderBookStore {
+    pub fn new() -> Self {
+        let (quotes, _) = broadcast::channel(1024);
+        Self {
+            books: std::sync::RwLock::new(FxHashMap::default()),
+            quotes,
+        }
+    }
+
+    /// Stream of best bid/ask changes
+    pub fn subscribe(&self) -> broadcast::Receiver<QuoteUpdate> {
+        self.quotes.subscribe()
+    }
+
+    /// Drop one token's book (market closed)
+    pub fn remove(&self, token_hash: u64) {
+        self.books.write().unwrap_or_else(|e| e.into_inner()).remove(&token_hash);
+    }
+
+    /// Drop all books (after a reconnect, until fresh snapshots arrive)
+    pub fn clear(&self) {
+        self.books.write().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Replace a token's book with a snapshot; returns the new best ask
+    pub fn apply_snapshot<B, A>(&self, token_hash: u64, bids: B, asks: A) -> Option<(u64, f64)>
+    where
+        B: IntoIterator<Item = (u64, f64)>,
+        A: IntoIterator<Item = (u64, f64)>,
+    {
+        self.update(token_hash, true, |book| book.apply_snapshot(bids, asks))
+            .and_then(|(_, ask)| ask)
+    }
+
+    /// Apply a level update to a token's book.
+    /// Returns `None` if no snapshot has been received for the token yet,
+    /// otherwise `Some(best_ask)` after the update.
+    pub fn apply_level(&self, token_hash: u64, side: BookSide, price_bps: u64, size: f64) -> Option<Option<(u64, f64)>> {
+        self.update(token_hash, false, |book| book.apply_level(side, price_bps, size))
+            .map(|(_, ask)| ask)
+    }
+
+    /// VWAP for filling `contracts` against a token's current book
+    pub fn vwap(&self, token_hash: u64, side: BookSide, contracts: f64) -> Result<DepthFill> {
+        self.books.read().unwrap_or_else(|e| e.into_inner())
+            .get(&token_hash)
+            .ok_or_else(|| Error::InsufficientDepth("No order book for token".into()))?
+            .vwap(side, contracts)
+    }
+
+    /// Best bid of a token's book, (price_bps, size)
+    pub fn best_bid(&self, token_hash: u64) -> Option<(u64, f64)> {
+        self.books.read().unwrap_or_else(|e| e.into_inner())
+            .get(&token_hash)
+            .and_then(OrderBook::best_bid)
+    }
+
+    /// Best ask of a token's book, (price_bps, size)
+    pub fn best_ask(&self, token_hash: u64) -> Option<(u64, f64)> {
+        self.books.read().unwrap_or_else(|e| e.into_inner())
+            .get(&token_hash)
+            .and_then(OrderBook::best_ask)
+    }
+
+    /// Whether a token's book is crossed or locked (false if no book)
+    pub fn is_crossed(&self, token_hash: u64) -> bool {
+        self.books.read().unwrap_or_else(|e| e.into_inner())
+            .get(&token_hash)
+            .is_some_and(OrderBook::is_crossed)
+    }
+
+    /// Top-of-book imbalance of a token's book (`None` if no book)
+    pub fn imbalance(&self, token_hash: u64, depth_bps: u64) -> Option<f64> {
+        self.books.read().unwrap_or_else(|e| e.into_inner())
+            .get(&token_hash)
+            .and_then(|book| book.imbalance(depth_bps))
+    }
+
+    /// Quantity fillable on a token's book within an average price limit (0 if no book)
+    pub fn max_fill_within(&self, token_hash: u64, side: BookSide, max_avg_price_bps: f64) -> f64 {
+        self.books.read().unwrap_or_else(|e| e.into_inner())
+            .get(&token_hash)
+            .map(|book| book.max_fill_within(side, max_avg_price_bps))
+            .unwrap_or(0.0)
+    }
+
+    /// Copy of a token's current book
+    #[allow(dead_code)]
+    pub fn get(&self, token_hash: u64) -> Option<OrderBook> {
+        self.books.read().unwrap_or_else(|e| e.into_inner()).get(&token_hash).cloned()
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn update(
+        &self,
+        token_hash: u64,
+        create: bool,
+        f: impl FnOnce(&mut OrderBook),
+    ) -> Option<(Option<(u64, f64)>, Option<(u64, f64)>)> {
+        let (before, after) = {
+            let mut books = self.books.write().unwrap_or_else(|e| e.into_inner());
+            let book = if create {
+                books.entry(token_hash).or_default()
+            } else {
+                books.get_mut(&token_hash)?
+            };
+            let before = (book.best_bid(), book.best_ask());
+            f(book);
+            (before, (book.best_bid(), book.best_ask()))
+        };
+
+        if before != after {
+            let _ = self.quotes.send(QuoteUpdate {
+                token_hash,
+                best_bid: after.0,
+                best_ask: after.1,
+            });
+        }
+        Some(after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepared(key: &str) -> PreparedCreds {
+        PreparedCreds::from_api_creds(&ApiCreds {
+            api_key: key.into(),
+            api_secret: URL_SAFE.encode(b"secret"),
+            api_passphrase: "pass".into(),
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_creds_renewal_schedule_and_backoff() {
+        let config = PolyAuthConfig { creds_ttl_secs: Some(3600), refresh_margin_secs: 300 };
+        let start = Instant::now();
+        let mut state = CredsState::new(prepared("k1"), &config, start);
+        assert!(!state.refresh_due(start + Duration::from_secs(3299)));
+        assert!(state.refresh_due(start + Duration::from_secs(3300)), "renewed ahead of the expiry");
+
+        // Failures back off exponentially, alerting only on the first
+        let t = start + Duration::from_secs(3300);
+        assert!(state.renewal_failed(t));
+        assert!(!state.refresh_due(t + Duration::from_millis(4_999)));
+        assert!(state.refresh_due(t + Duration::from_millis(5_000)));
+        assert!(!state.renewal_failed(t));
+        assert!(state.backing_off(t + Duration::from_millis(9_999)));
+        for _ in 0..20 {
+            state.renewal_failed(t);
+        }
+        assert!(!state.backing_off(t + Duration::from_millis(POLY_AUTH_BACKOFF_MAX_MS)), "backoff is capped");
+
+        state.renewed(prepared("k2"), &config, t);
+        assert_eq!((state.creds.api_key.as_str(), state.failures, state.retry_at), ("k2", 0, None));
+        assert!(!state.refresh_due(t + Duration::from_secs(60)));
+
+        let reactive = CredsState::new(prepared("k1"), &PolyAuthConfig { creds_ttl_secs: None, refresh_margin_secs: 300 }, start);
+        assert!(!reactive.refresh_due(start + Duration::from_secs(86_400 * 365)));
+    }
+
+    #[test]
+    fn test_trade_fill_as_taker_or_maker() {
+        let trade: PolymarketTrade = serde_json::from_value(json!({
+            "id": "t1", "taker_order_id": "0xtaker", "size": "10", "price": "0.42", "fee_rate_bps": "100",
+            "maker_orders": [
+                {"order_id": "0xmaker", "matched_amount": "4", "price": "0.40", "fee_rate_bps": "0"},
+            ],
+        })).unwrap();
+        let (size, price, fee) = trade.fill_of("0xtaker").unwrap();
+        assert_eq!((size, price), (10.0, 0.42));
+        assert!((fee - 0.042).abs() < 1e-9, "1% of min(p, 1 - p) per share");
+        assert_eq!(trade.fill_of("0xmaker"), Some((4.0, 0.40, 0.0)));
+        assert_eq!(trade.fill_of("0xother"), None);
+    }
+
+    #[test]
+    fn test_parse_book_constraints() {
+        let book = json!({"market": "0xabc", "tick_size": "0.001", "min_order_size": "5", "bids": [], "asks": []});
+        assert_eq!(parse_constraints(&book).unwrap(), OrderConstraints { tick_bps: 10, min_size: 5.0 });
+        let coarse = json!({"tick_size": 0.1, "min_order_size": "15"});
+        assert_eq!(parse_constraints(&coarse).unwrap(), OrderConstraints { tick_bps: 1000, min_size: 15.0 });
+        assert!(parse_constraints(&json!({"tick_size": "0.01"})).is_err());
+        assert!(parse_constraints(&json!({"tick_size": "0", "min_order_size": "5"})).is_err());
+    }
+
+    #[test]
+    fn test_order_type_post_body() {
+        let signed = SignedOrder {
+            order: OrderStruct {
+                salt: 1,
+                maker: "0xmaker".into(),
+                signer: "0xsigner".into(),
+                taker: ZERO_ADDRESS.into(),
+                token_id: "123".into(),
+                maker_amount: "4500000".into(),
+                taker_amount: "10000000".into(),
+                expiration: "0".into(),
+                nonce: "0".into(),
+                fee_rate_bps: "0".into(),
+                side: 0,
+                signature_type: 1,
+            },
+            signature: "0xsig".into(),
+            order_hash: "0xhash".into(),
+        };
+        let body = |order_type| {
+            let (clob_type, post_only) = PolyOrderType::for_order(order_type);
+            serde_json::from_str::<serde_json::Value>(&signed.post_body("key", clob_type.as_str(), post_only)).unwrap()
+        };
+
+        let ioc = body(OrderType::Ioc);
+        assert_eq!((ioc["orderType"].as_str(), ioc["owner"].as_str()), (Some("FAK"), Some("key")));
+        assert_eq!(ioc["order"]["side"], "BUY");
+        assert!(ioc.get("postOnly").is_none());
+        assert_eq!(body(OrderType::Market)["orderType"], "FOK");
+        assert_eq!(body(OrderType::Limit)["orderType"], "GTC");
+        let post_only = body(OrderType::PostOnly);
+        assert_eq!((post_only["orderType"].as_str(), post_only["postOnly"].as_bool()), (Some("GTC"), Some(true)));
+    }
+
+    #[test]
+    fn test_order_book_imbalance() {
+        let mut book = OrderBook::default();
+        assert_eq!(book.imbalance(200), None);
+
+        book.apply_snapshot(
+            [(4800, 30.0), (4700, 10.0), (4000, 500.0)],
+            [(5000, 10.0), (5300, 100.0)],
+        );
+        // Within 2¢: bids 30 + 10, asks 10; the far levels are ignored
+        let imbalance = book.imbalance(200).unwrap();
+        assert!((imbalance - 0.6).abs() < 1e-9);
+
+        // Only the best 2¢ of each side count: the deep bid now leads
+        book.apply_level(BookSide::Bid, 4800, 0.0);
+        book.apply_level(BookSide::Bid, 4700, 0.0);
+        assert!((book.imbalance(200).unwrap() - 490.0 / 510.0).abs() < 1e-9);
+
+        // All asks once the bids are gone
+        book.apply_level(BookSide::Bid, 4000, 0.0);
+        assert_eq!(book.imbalance(200), Some(-1.0));
+    }
+
+    #[test]
+    fn test_order_book_incremental_updates() {
+        let store = OrderBookStore::new();
+        let mut quotes = store.subscribe();
+
+        // Updates before a snapshot are ignored
+        assert!(store.apply_level(1, BookSide::Ask, 5000, 10.0).is_none());
+
+        let best = store.apply_snapshot(1, [(4800, 5.0)], [(5200, 20.0), (5100, 10.0)]);
+        assert_eq!(best, Some((5100, 10.0)));
+        assert!(quotes.try_recv().is_ok());
+
+        // Removing the best level exposes the next one
+        assert_eq!(store.apply_level(1, BookSide::Ask, 5100, 0.0), Some(Some((5200, 20.0))));
+        assert_eq!(store.get(1).unwrap().best_bid(), Some((4800, 5.0)));
+
+        // Deeper change leaves top of book alone: no quote published
+        let _ = quotes.try_recv();
+        store.apply_level(1, BookSide::Ask, 5500, 3.0);
+        assert!(quotes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_vwap_walks_depth() {
+        let mut book = OrderBook::default();
+        book.apply_snapshot([(4800, 5.0), (4700, 10.0)], [(5000, 10.0), (5200, 10.0)]);
+
+        // Within the top level: VWAP is the quoted price
+        let fill = book.vwap(BookSide::Ask, 4.0).unwrap();
+        assert_eq!(fill, DepthFill { avg_price_bps: 5000.0, worst_price_bps: 5000 });
+
+        // 10 @ 50¢ + 5 @ 52¢
+        let fill = book.vwap(BookSide::Ask, 15.0).unwrap();
+        assert!((fill.avg_price_bps - 5066.666).abs() < 0.01);
+        assert_eq!(fill.worst_price_bps, 5200);
+
+        // Bids fill from the top down
+        let fill = book.vwap(BookSide::Bid, 10.0).unwrap();
+        assert!((fill.avg_price_bps - 4750.0).abs() < 0.01);
+
+        assert!(book.vwap(BookSide::Ask, 25.0).is_err());
+    }
+
+    #[test]
+    fn test_max_fill_within_slippage() {
+        let mut book = OrderBook::default();
+        book.apply_snapshot([(4800, 10.0), (4600, 10.0)], [(5000, 10.0), (5400, 10.0)]);
+
+        // No slippage allowed: only the top level
+        assert!((book.max_fill_within(BookSide::Ask, 5000.0) - 10.0).abs() < 1e-9);
+
+        // 1¢ average slippage: 10 @ 50¢ + 3.33 @ 54¢ averages 51¢
+        let qty = book.max_fill_within(BookSide::Ask, 5100.0);
+        assert!((qty - 13.333).abs() < 0.01);
+        assert!((book.vwap(BookSide::Ask, qty).unwrap().avg_price_bps - 5100.0).abs() < 0.01);
+
+        // Bids: average must stay above the limit
+        assert!((book.max_fill_within(BookSide::Bid, 4700.0) - 20.0).abs() < 1e-9);
+
+        // Whole book within budget
+        assert!((book.max_fill_within(BookSide::Ask, 9900.0) - 20.0).abs() < 1e-9);
+    }
+}
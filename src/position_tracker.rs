@@ -5,15 +5,32 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, RwLock};
 use tracing::{info, warn};
 
+use crate::config::{PositionLimitsConfig, ReconcileConfig};
+use crate::kalshi::KalshiApiClient;
+use crate::polymarket_clob::{OrderBookStore, SharedAsyncClient};
+use crate::types::{GlobalState, MarketPair, Platform, Price, fxhash_str, unix_ms};
+
 const POSITION_FILE: &str = "positions.json";
 
+/// Every (platform, side) leg of an `ArbPosition`
+const LEGS: [(&str, &str); 4] = [
+    ("kalshi", "yes"),
+    ("kalshi", "no"),
+    ("polymarket", "yes"),
+    ("polymarket", "no"),
+];
+
+/// Contract counts closer than this are considered equal
+const CONTRACT_EPSILON: f64 = 1e-6;
+
 /// A single position leg on one platform
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PositionLeg {
@@ -23,6 +40,15 @@ pub struct PositionLeg {
     pub cost_basis: f64,
     /// Average price per contract
     pub avg_price: f64,
+    /// Last best bid seen for this leg (used when the market has no bid)
+    #[serde(default)]
+    pub last_mark: Option<f64>,
+    /// Highest bid seen while this leg held directional inventory (trailing stop)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_water_mark: Option<f64>,
+    /// Client order IDs of the fills that built this leg
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub client_order_ids: Vec<String>,
 }
 
 #[allow(dead_code)]
@@ -51,6 +77,18 @@ impl PositionLeg {
     pub fn profit_if_win(&self) -> f64 {
         self.value_if_win() - self.cost_basis
     }
+
+    /// Set the contract count reported by an exchange. Growing the leg
+    /// adopts the exchange's average price; shrinking keeps the tracked one.
+    fn set_contracts(&mut self, contracts: f64, exchange_avg_price: f64) {
+        if contracts > self.contracts {
+            self.cost_basis = contracts * exchange_avg_price;
+            self.avg_price = exchange_avg_price;
+        } else if self.contracts > 0.0 {
+            self.cost_basis *= contracts / self.contracts;
+        }
+        self.contracts = contracts;
+    }
 }
 
 /// A paired position (arb position spans both platforms)
@@ -83,8 +121,13 @@ pub struct ArbPosition {
     /// Status: "open", "closed", "resolved"
     pub status: String,
     
-    /// Realized P&L (set when position closes/resolves)
+    /// Realized P&L (set when position closes/resolves; accumulates partial closes)
     pub realized_pnl: Option<f64>,
+
+    /// Unix timestamp in milliseconds of the latest fill, which starts the
+    /// market's post-trade cooldown
+    #[serde(default)]
+    pub last_trade_ms: u64,
 }
 
 #[allow(dead_code)]
@@ -128,6 +171,20 @@ impl ArbPosition {
         yes_total.min(no_total)
     }
     
+    /// Position size in contract pairs: the larger of the YES and NO totals
+    pub fn side_contracts(&self) -> f64 {
+        let yes_total = self.kalshi_yes.contracts + self.poly_yes.contracts;
+        let no_total = self.kalshi_no.contracts + self.poly_no.contracts;
+        yes_total.max(no_total)
+    }
+    
+    /// Signed exposure: YES minus NO contracts across platforms
+    pub fn inventory(&self) -> f64 {
+        let yes_total = self.kalshi_yes.contracts + self.poly_yes.contracts;
+        let no_total = self.kalshi_no.contracts + self.poly_no.contracts;
+        yes_total - no_total
+    }
+
     /// Unmatched exposure (contracts without offsetting position)
     pub fn unmatched_exposure(&self) -> f64 {
         let yes_total = self.kalshi_yes.contracts + self.poly_yes.contracts;
@@ -135,6 +192,53 @@ impl ArbPosition {
         (yes_total - no_total).abs()
     }
     
+    /// Leg for a platform ("kalshi" / "polymarket") and side ("yes" / "no")
+    pub fn leg(&self, platform: &str, side: &str) -> Option<&PositionLeg> {
+        match (platform, side) {
+            ("kalshi", "yes") => Some(&self.kalshi_yes),
+            ("kalshi", "no") => Some(&self.kalshi_no),
+            ("polymarket", "yes") => Some(&self.poly_yes),
+            ("polymarket", "no") => Some(&self.poly_no),
+            _ => None,
+        }
+    }
+
+    pub fn leg_mut(&mut self, platform: &str, side: &str) -> Option<&mut PositionLeg> {
+        match (platform, side) {
+            ("kalshi", "yes") => Some(&mut self.kalshi_yes),
+            ("kalshi", "no") => Some(&mut self.kalshi_no),
+            ("polymarket", "yes") => Some(&mut self.poly_yes),
+            ("polymarket", "no") => Some(&mut self.poly_no),
+            _ => None,
+        }
+    }
+
+    /// Lots closed by resolving the position at `exit_time`: each held leg
+    /// pays $1 or nothing, and the entry fees are split by contracts
+    pub fn settlement_lots(&self, outcome_yes_won: bool, exit_time: &str) -> Vec<ClosedLot> {
+        let total = self.total_contracts();
+        LEGS.iter()
+            .filter_map(|&(platform, side)| {
+                let leg = self.leg(platform, side).filter(|l| l.contracts > CONTRACT_EPSILON)?;
+                let exit_price = if (side == "yes") == outcome_yes_won { 1.0 } else { 0.0 };
+                let fees = self.total_fees * leg.contracts / total;
+                Some(ClosedLot {
+                    entry_time: self.opened_at.clone(),
+                    exit_time: exit_time.to_string(),
+                    market_id: self.market_id.clone(),
+                    description: self.description.clone(),
+                    platform: platform.to_string(),
+                    side: side.to_string(),
+                    contracts: leg.contracts,
+                    entry_price: leg.avg_price,
+                    exit_price,
+                    realized_pnl: leg.contracts * exit_price - leg.cost_basis - fees,
+                    fees,
+                })
+            })
+            .collect()
+    }
+
     /// Mark position as resolved with outcome
     pub fn resolve(&mut self, outcome_yes_won: bool) {
         let payout = if outcome_yes_won {
@@ -145,11 +249,67 @@ impl ArbPosition {
             self.kalshi_no.contracts + self.poly_no.contracts
         };
         
-        self.realized_pnl = Some(payout - self.total_cost());
+        // Keep P&L already realized by partial closes
+        self.realized_pnl = Some(self.realized_pnl.unwrap_or(0.0) + payout - self.total_cost());
         self.status = "resolved".to_string();
     }
 }
 
+/// Contracts of one leg closed by a sale or a resolution, as written to the
+/// accounting ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedLot {
+    /// RFC 3339 timestamps
+    pub entry_time: String,
+    pub exit_time: String,
+    pub market_id: String,
+    pub description: String,
+    pub platform: String,
+    pub side: String,
+    pub contracts: f64,
+    /// Per contract, in dollars
+    pub entry_price: f64,
+    pub exit_price: f64,
+    /// After fees, in dollars
+    pub realized_pnl: f64,
+    pub fees: f64,
+}
+
+/// Header row of the ledger
+const LEDGER_HEADER: &str =
+    "entry_time,exit_time,market_id,description,platform,side,contracts,entry_price,exit_price,realized_pnl,fees";
+
+/// Quote a CSV field if it holds a separator, quote or line break
+fn csv_field(s: &str) -> std::borrow::Cow<'_, str> {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\"")).into()
+    } else {
+        s.into()
+    }
+}
+
+/// Append `lots` to the CSV ledger at `path`, writing the header first if the
+/// file is new or empty. Dollar amounts are rounded to 4 decimals.
+pub fn export_csv(path: &Path, lots: &[ClosedLot]) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let mut out = String::new();
+    if file.metadata()?.len() == 0 {
+        out.push_str(LEDGER_HEADER);
+        out.push('\n');
+    }
+    for lot in lots {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{:.4},{:.4},{:.4},{:.4}\n",
+            csv_field(&lot.entry_time), csv_field(&lot.exit_time), csv_field(&lot.market_id),
+            csv_field(&lot.description), csv_field(&lot.platform), csv_field(&lot.side),
+            lot.contracts, lot.entry_price, lot.exit_price, lot.realized_pnl, lot.fees,
+        ));
+    }
+    // A single write per batch so a crash never leaves half a row behind another
+    file.write_all(out.as_bytes())?;
+    Ok(())
+}
+
 /// Summary of all positions
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[allow(dead_code)]
@@ -190,6 +350,18 @@ pub struct PositionTracker {
 
     /// Cumulative all-time P&L
     pub all_time_pnl: f64,
+
+    /// Exposure limits enforced by `can_open` (configuration, not persisted)
+    #[serde(skip)]
+    limits: PositionLimits,
+
+    /// CSV ledger every close is appended to (configuration, not persisted)
+    #[serde(skip)]
+    ledger: Option<PathBuf>,
+
+    /// Markets with an entry in flight, holding a slot until their fills land
+    #[serde(skip)]
+    reserved: HashSet<String>,
 }
 
 /// Data structure for serialization
@@ -215,9 +387,116 @@ impl PositionTracker {
             daily_realized_pnl: 0.0,
             trading_date: today_string(),
             all_time_pnl: 0.0,
+            limits: PositionLimits::default(),
+            ledger: None,
+            reserved: HashSet::new(),
+        }
+    }
+
+    /// Replace the exposure limits enforced by `can_open`
+    pub fn set_limits(&mut self, limits: PositionLimits) {
+        self.limits = limits;
+    }
+
+    /// Append every close from now on to the CSV ledger at `path` (None = off)
+    pub fn set_ledger(&mut self, path: Option<PathBuf>) {
+        self.ledger = path;
+    }
+
+    fn append_ledger(&self, lots: &[ClosedLot]) {
+        let Some(path) = &self.ledger else { return };
+        if let Err(e) = export_csv(path, lots) {
+            warn!("[POSITIONS] Failed to append {} closed lot(s) to {}: {:#}", lots.len(), path.display(), e);
+        }
+    }
+
+    /// Largest order, up to `contracts`, that keeps `market_id` and the whole
+    /// book within the configured limits. `cost_per_contract` is the dollar
+    /// cost of one contract pair and is used for the notional caps. Fills are
+    /// recorded asynchronously, so an order completed in the last ~100ms may
+    /// not be counted yet.
+    pub fn can_open(&self, market_id: &str, contracts: f64, cost_per_contract: f64) -> OpenCheck {
+        let (market_contracts, market_notional) = self.positions.get(market_id)
+            .filter(|p| p.status == "open")
+            .map_or((0.0, 0.0), |p| (p.side_contracts(), p.total_cost()));
+        let (total_contracts, total_notional) = self.open_positions().iter()
+            .fold((0.0, 0.0), |(c, n), p| (c + p.side_contracts(), n + p.total_cost()));
+
+        let mut check = OpenCheck { contracts, limit: None };
+        let mut cap = |room: f64, hit: LimitHit| {
+            let room = room.max(0.0);
+            if room < check.contracts {
+                check.contracts = room;
+                check.limit = Some(hit);
+            }
+        };
+
+        let market = self.limits.for_market(market_id);
+        if let Some(max) = market.max_contracts {
+            cap(max - market_contracts, LimitHit::MarketContracts(max));
+        }
+        if let Some(max) = market.max_notional.filter(|_| cost_per_contract > 0.0) {
+            cap((max - market_notional) / cost_per_contract, LimitHit::MarketNotional(max));
+        }
+        if let Some(max) = self.limits.total.max_contracts {
+            cap(max - total_contracts, LimitHit::TotalContracts(max));
+        }
+        if let Some(max) = self.limits.total.max_notional.filter(|_| cost_per_contract > 0.0) {
+            cap((max - total_notional) / cost_per_contract, LimitHit::TotalNotional(max));
         }
+        check
     }
     
+    /// Slots taken: open positions plus entries in flight on other markets
+    pub fn slots_used(&self) -> usize {
+        let in_flight = self.reserved.iter()
+            .filter(|id| self.positions.get(*id).is_none_or(|p| p.status != "open"))
+            .count();
+        self.open_positions().len() + in_flight
+    }
+
+    /// Cap on concurrently open positions (None = unlimited)
+    pub fn max_open_positions(&self) -> Option<usize> {
+        self.limits.max_open_positions
+    }
+
+    /// Take a slot for an entry on `market_id`, false when every slot is in
+    /// use. A market with an open position or an entry in flight already
+    /// holds one.
+    pub fn acquire_slot(&mut self, market_id: &str) -> bool {
+        let held = self.reserved.contains(market_id)
+            || self.positions.get(market_id).is_some_and(|p| p.status == "open");
+        if held {
+            return true;
+        }
+        if self.limits.max_open_positions.is_some_and(|max| self.slots_used() >= max) {
+            return false;
+        }
+        self.reserved.insert(market_id.to_string());
+        true
+    }
+
+    /// End the entry reserved on `market_id`. If it filled, the open position
+    /// keeps the slot until it is closed.
+    pub fn release_slot(&mut self, market_id: &str) {
+        self.reserved.remove(market_id);
+    }
+
+    /// Inventory of a market's open position (0 if none), see `ArbPosition::inventory`
+    pub fn inventory(&self, market_id: &str) -> f64 {
+        self.positions.get(market_id)
+            .filter(|p| p.status == "open")
+            .map_or(0.0, ArbPosition::inventory)
+    }
+
+    /// Cooldown left on `market_id` at `now_ms` after its latest fill,
+    /// `None` once it has run out
+    pub fn cooldown_remaining(&self, market_id: &str, cooldown: Duration, now_ms: u64) -> Option<Duration> {
+        let last_trade_ms = self.positions.get(market_id)?.last_trade_ms;
+        let elapsed = Duration::from_millis(now_ms.saturating_sub(last_trade_ms));
+        cooldown.checked_sub(elapsed).filter(|left| !left.is_zero())
+    }
+
     /// Load from file or create new
     pub fn load() -> Self {
         Self::load_from(POSITION_FILE)
@@ -259,7 +538,7 @@ impl PositionTracker {
     
     pub fn save_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let json = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, json)?;
+        write_atomic(path.as_ref(), json.as_bytes())?;
         Ok(())
     }
     
@@ -274,15 +553,34 @@ impl PositionTracker {
         };
         // Try to spawn on runtime; if no runtime, save synchronously
         if tokio::runtime::Handle::try_current().is_ok() {
-            tokio::spawn(async move {
+            let seq = SAVE_SEQ.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            tokio::task::spawn_blocking(move || {
                 if let Ok(json) = serde_json::to_string_pretty(&data) {
-                    let _ = tokio::fs::write(POSITION_FILE, json).await;
+                    let mut last_written = LAST_SAVED_SEQ.lock().unwrap_or_else(|e| e.into_inner());
+                    // A newer snapshot already landed
+                    if last_written.is_some_and(|last| last > seq) {
+                        return;
+                    }
+                    match write_atomic(Path::new(POSITION_FILE), json.as_bytes()) {
+                        Ok(()) => *last_written = Some(seq),
+                        Err(e) => warn!("[POSITIONS] Failed to save positions: {}", e),
+                    }
                 }
             });
         } else if let Ok(json) = serde_json::to_string_pretty(&data) {
-            let _ = std::fs::write(POSITION_FILE, json);
+            let _ = write_atomic(Path::new(POSITION_FILE), json.as_bytes());
         }
     }
+
+    /// Save synchronously; background saves still pending will not overwrite it
+    pub fn flush(&self) -> Result<()> {
+        let seq = SAVE_SEQ.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let json = serde_json::to_string_pretty(self)?;
+        let mut last_written = LAST_SAVED_SEQ.lock().unwrap_or_else(|e| e.into_inner());
+        write_atomic(Path::new(POSITION_FILE), json.as_bytes())?;
+        *last_written = Some(seq);
+        Ok(())
+    }
     
     /// Record a fill
     pub fn record_fill(&mut self, fill: &FillRecord) {
@@ -296,15 +594,18 @@ impl PositionTracker {
             .entry(fill.market_id.clone())
             .or_insert_with(|| ArbPosition::new(&fill.market_id, &fill.description));
 
-        match (fill.platform.as_str(), fill.side.as_str()) {
-            ("kalshi", "yes") => position.kalshi_yes.add(fill.contracts, fill.price),
-            ("kalshi", "no") => position.kalshi_no.add(fill.contracts, fill.price),
-            ("polymarket", "yes") => position.poly_yes.add(fill.contracts, fill.price),
-            ("polymarket", "no") => position.poly_no.add(fill.contracts, fill.price),
-            _ => warn!("[POSITIONS] Unknown platform/side: {}/{}", fill.platform, fill.side),
+        match position.leg_mut(&fill.platform, &fill.side) {
+            Some(leg) => {
+                leg.add(fill.contracts, fill.price);
+                if !fill.client_order_id.is_empty() {
+                    leg.client_order_ids.push(fill.client_order_id.clone());
+                }
+            }
+            None => warn!("[POSITIONS] Unknown platform/side: {}/{}", fill.platform, fill.side),
         }
 
         position.total_fees += fill.fees;
+        position.last_trade_ms = unix_ms();
 
         info!("[POSITIONS] Recorded fill: {} {} {} @{:.1}¢ x{:.0} (fees: ${:.4})",
               fill.platform, fill.side, fill.market_id,
@@ -326,8 +627,11 @@ impl PositionTracker {
     /// Mark a position as resolved
     pub fn resolve_position(&mut self, market_id: &str, yes_won: bool) -> Option<f64> {
         if let Some(position) = self.positions.get_mut(market_id) {
+            let prior = position.realized_pnl.unwrap_or(0.0);
+            let lots = position.settlement_lots(yes_won, &chrono::Utc::now().to_rfc3339());
             position.resolve(yes_won);
-            let pnl = position.realized_pnl.unwrap_or(0.0);
+            let pnl = position.realized_pnl.unwrap_or(0.0) - prior;
+            self.append_ledger(&lots);
             
             self.daily_realized_pnl += pnl;
             self.all_time_pnl += pnl;
@@ -342,6 +646,67 @@ impl PositionTracker {
         }
     }
     
+    /// Settle an open position at the market's resolution outcome, booking
+    /// the realized P&L. `None` if there is no open position for the market.
+    pub fn settle(&mut self, market_id: &str, yes_won: bool) -> Option<Settlement> {
+        let position = self.positions.get(market_id).filter(|p| p.status == "open")?;
+        let description = position.description.clone();
+        let contracts = position.side_contracts();
+        let realized_pnl = self.resolve_position(market_id, yes_won)?;
+        Some(Settlement {
+            description,
+            yes_won,
+            contracts,
+            realized_pnl,
+        })
+    }
+    
+    /// Book the sale of `contracts` from one leg for `proceeds` dollars and
+    /// return the realized P&L against the leg's average cost. The position
+    /// is marked "closed" once no leg holds contracts.
+    pub fn record_close(&mut self, market_id: &str, platform: &str, side: &str, contracts: f64, proceeds: f64) -> Option<f64> {
+        let position = self.positions.get_mut(market_id).filter(|p| p.status == "open")?;
+        let leg = position.leg_mut(platform, side)?;
+        let contracts = contracts.min(leg.contracts);
+        let cost = leg.avg_price * contracts;
+        leg.contracts -= contracts;
+        leg.cost_basis = (leg.cost_basis - cost).max(0.0);
+        if leg.contracts <= CONTRACT_EPSILON {
+            *leg = PositionLeg::default();
+        }
+
+        let mut pnl = proceeds - cost;
+        let mut fees = 0.0;
+        if position.total_contracts() <= CONTRACT_EPSILON {
+            // Entry fees are booked with the last leg closed
+            fees = position.total_fees;
+            pnl -= fees;
+            position.status = "closed".to_string();
+        }
+        position.realized_pnl = Some(position.realized_pnl.unwrap_or(0.0) + pnl);
+        let lot = ClosedLot {
+            entry_time: position.opened_at.clone(),
+            exit_time: chrono::Utc::now().to_rfc3339(),
+            market_id: market_id.to_string(),
+            description: position.description.clone(),
+            platform: platform.to_string(),
+            side: side.to_string(),
+            contracts,
+            entry_price: if contracts > 0.0 { cost / contracts } else { 0.0 },
+            exit_price: if contracts > 0.0 { proceeds / contracts } else { 0.0 },
+            realized_pnl: pnl,
+            fees,
+        };
+        self.append_ledger(&[lot]);
+        self.daily_realized_pnl += pnl;
+        self.all_time_pnl += pnl;
+
+        info!("[POSITIONS] Closed {} {} {} x{:.0} for ${:.2} (P&L: ${:.2})",
+              platform, side, market_id, contracts, proceeds, pnl);
+        self.save_async();
+        Some(pnl)
+    }
+
     /// Get summary statistics
     pub fn summary(&self) -> PositionSummary {
         let mut summary = PositionSummary::default();
@@ -359,6 +724,7 @@ impl PositionTracker {
                     summary.resolved_positions += 1;
                     summary.realized_pnl += position.realized_pnl.unwrap_or(0.0);
                 }
+                "closed" => summary.realized_pnl += position.realized_pnl.unwrap_or(0.0),
                 _ => {}
             }
         }
@@ -373,6 +739,17 @@ impl PositionTracker {
             .collect()
     }
     
+    /// Descriptions of the open positions holding contracts on `platform`
+    pub fn open_on(&self, platform: Platform) -> Vec<String> {
+        self.open_positions().into_iter()
+            .filter(|p| match platform {
+                Platform::Kalshi => p.kalshi_yes.contracts > 0.0 || p.kalshi_no.contracts > 0.0,
+                Platform::Polymarket => p.poly_yes.contracts > 0.0 || p.poly_no.contracts > 0.0,
+            })
+            .map(|p| p.description.clone())
+            .collect()
+    }
+
     /// Daily P&L (realized only)
     pub fn daily_pnl(&self) -> f64 {
         self.daily_realized_pnl
@@ -384,6 +761,520 @@ impl PositionTracker {
         self.trading_date = today_string();
         self.save_async();
     }
+
+    /// Mark open positions to market at the best bid of each leg, which is
+    /// what the contracts would fetch if sold now. `best_bid` returns the
+    /// bid in dollars for (market_id, platform, side). A leg without a bid
+    /// is marked at its last known bid (or its entry price if none was ever
+    /// seen) and its position is flagged stale.
+    pub fn mark_to_market<F>(&mut self, mut best_bid: F) -> UnrealizedPnl
+    where
+        F: FnMut(&str, &str, &str) -> Option<f64>,
+    {
+        let mut result = UnrealizedPnl::default();
+        let mut market_ids: Vec<_> = self.positions.iter()
+            .filter(|(_, p)| p.status == "open")
+            .map(|(id, _)| id.clone())
+            .collect();
+        market_ids.sort();
+
+        for market_id in market_ids {
+            let position = self.positions.get_mut(&market_id).expect("collected above");
+            let mut pnl = -position.total_fees;
+            let mut stale = false;
+            for &(platform, side) in &LEGS {
+                let Some(leg) = position.leg_mut(platform, side) else { continue };
+                if leg.contracts <= CONTRACT_EPSILON {
+                    continue;
+                }
+                let price = match best_bid(&market_id, platform, side) {
+                    Some(bid) => {
+                        leg.last_mark = Some(bid);
+                        bid
+                    }
+                    None => {
+                        stale = true;
+                        leg.last_mark.unwrap_or(leg.avg_price)
+                    }
+                };
+                pnl += leg.unrealized_pnl(price);
+            }
+
+            result.total += pnl;
+            result.stale_positions += stale as usize;
+            result.positions.push(PositionMark { market_id, unrealized_pnl: pnl, stale });
+        }
+        result
+    }
+
+    /// Trailing stops on directional inventory: for each open position whose
+    /// YES and NO contracts don't offset, the legs on the excess side track
+    /// the high-water mark of their best bid (`best_bid` as in
+    /// `mark_to_market`), and those whose bid fell `retrace` dollars or more
+    /// below it are returned with the unhedged contracts to sell. Legs that
+    /// are hedged again lose their high-water mark.
+    pub fn trailing_stops<F>(&mut self, retrace: f64, mut best_bid: F) -> Vec<TrailingStop>
+    where
+        F: FnMut(&str, &str, &str) -> Option<f64>,
+    {
+        let mut stops = Vec::new();
+        let mut market_ids: Vec<_> = self.positions.iter()
+            .filter(|(_, p)| p.status == "open")
+            .map(|(id, _)| id.clone())
+            .collect();
+        market_ids.sort();
+
+        for market_id in market_ids {
+            let position = self.positions.get_mut(&market_id).expect("collected above");
+            let inventory = position.inventory();
+            let excess_side = if inventory > 0.0 { "yes" } else { "no" };
+            let mut unhedged = inventory.abs();
+            let description = position.description.clone();
+            for &(platform, side) in &LEGS {
+                let Some(leg) = position.leg_mut(platform, side) else { continue };
+                if side != excess_side || unhedged <= CONTRACT_EPSILON || leg.contracts <= CONTRACT_EPSILON {
+                    leg.high_water_mark = None;
+                    continue;
+                }
+                let contracts = leg.contracts.min(unhedged);
+                unhedged -= contracts;
+                let Some(bid) = best_bid(&market_id, platform, side) else { continue };
+                let high_water = leg.high_water_mark.map_or(bid, |hwm| hwm.max(bid));
+                leg.high_water_mark = Some(high_water);
+                if high_water - bid >= retrace - 1e-9 {
+                    stops.push(TrailingStop {
+                        market_id: market_id.clone(),
+                        description: description.clone(),
+                        platform,
+                        side,
+                        contracts,
+                        high_water,
+                        mark: bid,
+                    });
+                }
+            }
+        }
+        stops
+    }
+
+    /// Restart a leg's trailing stop from its next bid
+    pub fn reset_high_water(&mut self, market_id: &str, platform: &str, side: &str) {
+        if let Some(leg) = self.positions.get_mut(market_id).and_then(|p| p.leg_mut(platform, side)) {
+            leg.high_water_mark = None;
+        }
+    }
+
+    /// True if any tracked leg on `platforms` holds more than the exchange
+    /// reports, i.e. reconciling would need a confirmation snapshot
+    pub fn has_missing_legs(&self, observed: &[ExchangePosition], platforms: &[&str]) -> bool {
+        let seen = index_contracts(observed);
+        self.open_positions().iter().any(|position| {
+            LEGS.iter()
+                .filter(|(platform, _)| platforms.contains(platform))
+                .any(|&(platform, side)| {
+                    let tracked = position.leg(platform, side).map_or(0.0, |l| l.contracts);
+                    let exchange = seen.get(&(position.market_id.as_str(), platform, side)).copied().unwrap_or(0.0);
+                    tracked - exchange > CONTRACT_EPSILON
+                })
+        })
+    }
+
+    /// Align open positions on `platforms` with what the exchanges report.
+    ///
+    /// Legs the exchange holds but the tracker does not are adopted at the
+    /// exchange's average price. Legs the tracker holds but the exchange does
+    /// not are only reduced when `confirmation` (a later snapshot) agrees with
+    /// `observed`; otherwise they are left as-is and reported as unconfirmed.
+    /// Positions left with no contracts are dropped.
+    pub fn reconcile(
+        &mut self,
+        observed: &[ExchangePosition],
+        confirmation: &[ExchangePosition],
+        platforms: &[&str],
+    ) -> ReconcileReport {
+        let mut report = ReconcileReport::default();
+        let seen = index_contracts(observed);
+        let confirmed = index_contracts(confirmation);
+
+        // Every leg either side knows about
+        let mut keys: BTreeSet<(String, &'static str, &'static str)> = BTreeSet::new();
+        for position in self.positions.values().filter(|p| p.status == "open") {
+            for &(platform, side) in &LEGS {
+                if platforms.contains(&platform)
+                    && position.leg(platform, side).is_some_and(|l| l.contracts > CONTRACT_EPSILON)
+                {
+                    keys.insert((position.market_id.clone(), platform, side));
+                }
+            }
+        }
+        for p in observed.iter().filter(|p| platforms.contains(&p.platform)) {
+            keys.insert((p.market_id.clone(), p.platform, p.side));
+        }
+
+        for (market_id, platform, side) in keys {
+            let tracked_position = self.positions.get(&market_id).filter(|p| p.status == "open");
+            let tracked = tracked_position
+                .and_then(|p| p.leg(platform, side))
+                .map_or(0.0, |l| l.contracts);
+            let key = (market_id.as_str(), platform, side);
+            let exchange = seen.get(&key).copied().unwrap_or(0.0);
+            let label = format!("{} {}/{} {:.0}→{:.0}", market_id, platform, side, tracked, exchange);
+
+            if (tracked - exchange).abs() <= CONTRACT_EPSILON {
+                report.matched += 1;
+                continue;
+            }
+
+            if exchange < tracked {
+                let confirmed_contracts = confirmed.get(&key).copied().unwrap_or(0.0);
+                if (confirmed_contracts - exchange).abs() > CONTRACT_EPSILON {
+                    warn!("[POSITIONS] Unconfirmed divergence, keeping tracked leg: {}", label);
+                    report.unconfirmed.push(label);
+                    continue;
+                }
+            }
+
+            let source = observed.iter().find(|p| (p.market_id.as_str(), p.platform, p.side) == key);
+            if tracked_position.is_none() {
+                // Unknown to the tracker (or a stale non-open entry): adopt it fresh
+                let description = source.map_or(market_id.as_str(), |p| p.description.as_str());
+                self.positions.insert(market_id.clone(), ArbPosition::new(&market_id, description));
+            }
+            let position = self.positions.get_mut(&market_id).expect("position inserted above");
+            if let Some(leg) = position.leg_mut(platform, side) {
+                leg.set_contracts(exchange, source.map_or(0.0, |p| p.avg_price));
+            }
+
+            if tracked <= CONTRACT_EPSILON {
+                info!("[POSITIONS] Adopted exchange position: {}", label);
+                report.adopted.push(label);
+            } else if exchange <= CONTRACT_EPSILON {
+                warn!("[POSITIONS] Dropped ghost leg: {}", label);
+                report.dropped.push(label);
+            } else {
+                warn!("[POSITIONS] Corrected leg: {}", label);
+                report.corrected.push(label);
+            }
+        }
+
+        // Ghost positions with nothing left on any leg
+        self.positions.retain(|_, p| p.status != "open" || p.total_contracts() > CONTRACT_EPSILON);
+
+        if report.has_changes() {
+            self.save_async();
+        }
+        report
+    }
+}
+
+/// A position closed out by market resolution
+#[derive(Debug, Clone)]
+pub struct Settlement {
+    pub description: String,
+    pub yes_won: bool,
+    /// Position size in contract pairs
+    pub contracts: f64,
+    pub realized_pnl: f64,
+}
+
+/// Contract and notional caps for one market (or for the whole book)
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct MarketLimit {
+    pub max_contracts: Option<f64>,
+    /// Dollars of cost basis
+    pub max_notional: Option<f64>,
+}
+
+/// Exposure limits: a default per-market cap, a global cap, and per-market overrides
+#[derive(Debug, Clone, Default)]
+pub struct PositionLimits {
+    pub per_market: MarketLimit,
+    pub total: MarketLimit,
+    /// Cap on concurrently open positions, entries in flight included
+    pub max_open_positions: Option<usize>,
+    /// Keyed by tracker market id or Kalshi market ticker
+    overrides: HashMap<String, MarketLimit>,
+}
+
+impl PositionLimits {
+    pub fn new(per_market: MarketLimit, total: MarketLimit, overrides: HashMap<String, MarketLimit>) -> Self {
+        Self { per_market, total, max_open_positions: None, overrides }
+    }
+
+    /// Build from config, reading overrides from `overrides_path` if present:
+    /// `{"KXNFLGAME-25DEC28BUFNE-BUF": {"max_contracts": 50, "max_notional": 25.0}}`
+    pub fn load(config: PositionLimitsConfig) -> Self {
+        let overrides = match std::fs::read_to_string(&config.overrides_path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("[POSITIONS] Failed to parse position limits {}: {}", config.overrides_path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        if !overrides.is_empty() {
+            info!("[POSITIONS] Loaded {} per-market position limits", overrides.len());
+        }
+        let limits = Self::new(
+            MarketLimit {
+                max_contracts: config.max_contracts_per_market,
+                max_notional: config.max_notional_per_market,
+            },
+            MarketLimit {
+                max_contracts: config.max_total_contracts,
+                max_notional: config.max_total_notional,
+            },
+            overrides,
+        );
+        Self { max_open_positions: config.max_open_positions, ..limits }
+    }
+
+    /// Effective limit for a market: override fields win over the default.
+    /// Pair ids end with the Kalshi ticker, so overrides may use either.
+    pub fn for_market(&self, market_id: &str) -> MarketLimit {
+        let found = self.overrides.get(market_id).or_else(|| {
+            self.overrides.iter()
+                .find(|(key, _)| market_id.strip_suffix(key.as_str()).is_some_and(|rest| rest.ends_with('-')))
+                .map(|(_, limit)| limit)
+        });
+        match found {
+            Some(o) => MarketLimit {
+                max_contracts: o.max_contracts.or(self.per_market.max_contracts),
+                max_notional: o.max_notional.or(self.per_market.max_notional),
+            },
+            None => self.per_market,
+        }
+    }
+}
+
+/// Limit that constrained an order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitHit {
+    MarketContracts(f64),
+    MarketNotional(f64),
+    TotalContracts(f64),
+    TotalNotional(f64),
+}
+
+impl std::fmt::Display for LimitHit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitHit::MarketContracts(max) => write!(f, "max {:.0} contracts per market", max),
+            LimitHit::MarketNotional(max) => write!(f, "max ${:.2} per market", max),
+            LimitHit::TotalContracts(max) => write!(f, "max {:.0} contracts in total", max),
+            LimitHit::TotalNotional(max) => write!(f, "max ${:.2} in total", max),
+        }
+    }
+}
+
+/// Result of `PositionTracker::can_open`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpenCheck {
+    /// Contracts that may be opened (the request, or less if trimmed)
+    pub contracts: f64,
+    /// Tightest limit that reduced the order
+    pub limit: Option<LimitHit>,
+}
+
+/// Mark-to-market value of one open position
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct PositionMark {
+    pub market_id: String,
+    /// Liquidation value at the best bids minus cost basis and fees
+    pub unrealized_pnl: f64,
+    /// At least one leg had no bid and was marked at its last known price
+    pub stale: bool,
+}
+
+/// Unrealized P&L across open positions
+#[derive(Debug, Clone, Default)]
+pub struct UnrealizedPnl {
+    pub positions: Vec<PositionMark>,
+    pub total: f64,
+    pub stale_positions: usize,
+}
+
+/// Directional leg whose bid retraced past its trailing stop
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrailingStop {
+    pub market_id: String,
+    pub description: String,
+    /// "kalshi" or "polymarket"
+    pub platform: &'static str,
+    /// "yes" or "no"
+    pub side: &'static str,
+    /// Unhedged contracts on the leg, the ones to sell
+    pub contracts: f64,
+    /// Highest bid seen since the inventory built up, in dollars
+    pub high_water: f64,
+    /// Current bid, in dollars
+    pub mark: f64,
+}
+
+/// Mark the tracker against live quotes: Polymarket legs use the CLOB book's
+/// best bid, Kalshi legs the complement of the opposite side's best ask
+/// (a YES bid at p is a NO ask at 1-p).
+pub fn mark_positions(tracker: &mut PositionTracker, state: &GlobalState, books: &OrderBookStore) -> UnrealizedPnl {
+    tracker.mark_to_market(best_bids(state, books))
+}
+
+/// Best bid in dollars of each (market_id, platform, side) leg, priced as
+/// in `mark_positions`
+pub fn best_bids<'a>(state: &'a GlobalState, books: &'a OrderBookStore) -> impl FnMut(&str, &str, &str) -> Option<f64> + 'a {
+    let pairs: HashMap<&str, (&MarketPair, u16)> = state.markets.iter()
+        .filter_map(|m| m.pair.as_deref().map(|pair| (&*pair.pair_id, (pair, m.market_id))))
+        .collect();
+
+    move |market_id, platform, side| {
+        let &(pair, id) = pairs.get(market_id)?;
+        match platform {
+            "polymarket" => {
+                let token = if side == "yes" { &pair.poly_yes_token } else { &pair.poly_no_token };
+                let (bid_bps, _) = books.get(fxhash_str(token))?.best_bid()?;
+                Some(bid_bps as f64 / 10_000.0)
+            }
+            "kalshi" => {
+                let (yes_ask, no_ask, _, _) = state.get_by_id(id)?.kalshi.load();
+                let opposite_ask = if side == "yes" { no_ask } else { yes_ask };
+                // The bid on one side is the complement of the other side's ask
+                Price::from_cents(opposite_ask)
+                    .filter(|ask| *ask > Price::ZERO)
+                    .map(|ask| ask.complement().to_poly())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Position on one leg as reported by an exchange, keyed like the tracker
+#[derive(Debug, Clone)]
+pub struct ExchangePosition {
+    /// Tracker market id (pair id when the market is known to discovery)
+    pub market_id: String,
+    pub description: String,
+    /// "kalshi" or "polymarket"
+    pub platform: &'static str,
+    /// "yes" or "no"
+    pub side: &'static str,
+    pub contracts: f64,
+    /// Average entry price in dollars
+    pub avg_price: f64,
+}
+
+/// Outcome of a startup reconciliation (entries are "market platform/side tracked→exchange")
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileReport {
+    /// Legs that already agreed
+    pub matched: usize,
+    /// Exchange legs the tracker did not know about
+    pub adopted: Vec<String>,
+    /// Legs whose contract count was corrected
+    pub corrected: Vec<String>,
+    /// Ghost legs removed after confirmation
+    pub dropped: Vec<String>,
+    /// Missing legs kept because the confirmation snapshot disagreed
+    pub unconfirmed: Vec<String>,
+}
+
+impl ReconcileReport {
+    pub fn has_changes(&self) -> bool {
+        !self.adopted.is_empty() || !self.corrected.is_empty() || !self.dropped.is_empty()
+    }
+}
+
+fn index_contracts(positions: &[ExchangePosition]) -> HashMap<(&str, &'static str, &'static str), f64> {
+    let mut index = HashMap::with_capacity(positions.len());
+    for p in positions {
+        *index.entry((p.market_id.as_str(), p.platform, p.side)).or_insert(0.0) += p.contracts;
+    }
+    index
+}
+
+/// Fetch current positions from the exchanges, mapped to tracker market ids
+/// through the discovered pairs. Returns the positions and the platforms queried.
+pub async fn fetch_exchange_positions(
+    kalshi: Option<&KalshiApiClient>,
+    poly: &SharedAsyncClient,
+    state: &GlobalState,
+) -> Result<(Vec<ExchangePosition>, Vec<&'static str>)> {
+    let mut positions = Vec::new();
+    let mut platforms = vec!["polymarket"];
+
+    if let Some(kalshi) = kalshi {
+        platforms.push("kalshi");
+        for p in kalshi.get_positions().await? {
+            let pair = state.id_by_kalshi_hash(fxhash_str(&p.ticker))
+                .and_then(|id| state.get_by_id(id))
+                .and_then(|m| m.pair.clone());
+            positions.push(ExchangePosition {
+                market_id: pair.as_ref().map_or_else(|| p.ticker.clone(), |pair| pair.pair_id.to_string()),
+                description: pair.as_ref().map_or_else(|| p.ticker.clone(), |pair| pair.description.to_string()),
+                platform: "kalshi",
+                side: if p.position > 0 { "yes" } else { "no" },
+                contracts: p.position.unsigned_abs() as f64,
+                avg_price: p.avg_price(),
+            });
+        }
+    }
+
+    for p in poly.get_positions().await? {
+        let hash = fxhash_str(&p.asset);
+        let (id, side) = match (state.id_by_poly_yes_hash(hash), state.id_by_poly_no_hash(hash)) {
+            (Some(id), _) => (Some(id), "yes"),
+            (None, Some(id)) => (Some(id), "no"),
+            (None, None) => {
+                let is_no = p.outcome.as_deref().is_some_and(|o| o.eq_ignore_ascii_case("no"));
+                (None, if is_no { "no" } else { "yes" })
+            }
+        };
+        let pair = id.and_then(|id| state.get_by_id(id)).and_then(|m| m.pair.clone());
+        // Full-set outcomes no pair trades are tracked by token
+        let fallback_id = match state.poly_outcome_to_id.contains_key(&hash) {
+            true => p.asset.clone(),
+            false => p.slug.clone().unwrap_or_else(|| p.asset.clone()),
+        };
+        positions.push(ExchangePosition {
+            market_id: pair.as_ref().map_or(fallback_id, |pair| pair.pair_id.to_string()),
+            description: pair.as_ref().map_or_else(
+                || p.title.clone().unwrap_or_else(|| p.asset.clone()),
+                |pair| pair.description.to_string(),
+            ),
+            platform: "polymarket",
+            side,
+            contracts: p.size,
+            avg_price: p.avg_price,
+        });
+    }
+
+    Ok((positions, platforms))
+}
+
+/// Reconcile persisted positions with the exchanges. A second snapshot is
+/// taken after `confirm_delay_secs` before any tracked leg is reduced, so a
+/// lagging API cannot wipe a real position.
+pub async fn reconcile_with_exchanges(
+    tracker: &SharedPositionTracker,
+    kalshi: Option<&KalshiApiClient>,
+    poly: &SharedAsyncClient,
+    state: &GlobalState,
+    config: ReconcileConfig,
+) -> Result<ReconcileReport> {
+    let (observed, platforms) = fetch_exchange_positions(kalshi, poly, state).await?;
+
+    let needs_confirmation = tracker.read().await.has_missing_legs(&observed, &platforms);
+    let confirmation = if needs_confirmation {
+        info!("[POSITIONS] Tracked legs missing on exchange, confirming in {}s...", config.confirm_delay_secs);
+        tokio::time::sleep(Duration::from_secs(config.confirm_delay_secs)).await;
+        fetch_exchange_positions(kalshi, poly, state).await?.0
+    } else {
+        observed.clone()
+    };
+
+    let report = tracker.write().await.reconcile(&observed, &confirmation, &platforms);
+    info!("[POSITIONS] Reconciled: {} matched, {} adopted, {} corrected, {} dropped, {} unconfirmed",
+          report.matched, report.adopted.len(), report.corrected.len(),
+          report.dropped.len(), report.unconfirmed.len());
+    Ok(report)
 }
 
 /// Record of a single fill
@@ -398,11 +1289,14 @@ pub struct FillRecord {
     pub fees: f64,
     #[allow(dead_code)]
     pub order_id: String,
+    /// Idempotency key the order was submitted with (empty if unknown)
+    pub client_order_id: String,
     #[allow(dead_code)]
     pub timestamp: String,
 }
 
 impl FillRecord {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         market_id: &str,
         description: &str,
@@ -422,9 +1316,16 @@ impl FillRecord {
             price,
             fees,
             order_id: order_id.to_string(),
+            client_order_id: String::new(),
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
+
+    /// Attach the client order ID the fill's order was submitted with
+    pub fn with_client_order_id(mut self, client_order_id: &str) -> Self {
+        self.client_order_id = client_order_id.to_string();
+        self
+    }
 }
 
 #[allow(dead_code)]
@@ -435,33 +1336,66 @@ pub fn create_position_tracker() -> SharedPositionTracker {
     Arc::new(RwLock::new(PositionTracker::load()))
 }
 
+/// Background saves are numbered so an older snapshot never overwrites a newer one
+static SAVE_SEQ: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+static LAST_SAVED_SEQ: std::sync::Mutex<Option<u64>> = std::sync::Mutex::new(None);
+
+/// Write via a temp file and rename so a crash never leaves a truncated file
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
 fn today_string() -> String {
     chrono::Utc::now().format("%Y-%m-%d").to_string()
 }
 
+/// Update applied by `position_writer_loop`, in the order it was sent
+#[derive(Debug, Clone)]
+pub enum PositionUpdate {
+    Fill(FillRecord),
+    /// `PositionTracker::release_slot`, queued behind the entry's fills
+    ReleaseSlot(String),
+}
+
+impl PositionTracker {
+    fn apply(&mut self, update: &PositionUpdate) {
+        match update {
+            PositionUpdate::Fill(fill) => self.record_fill_internal(fill),
+            PositionUpdate::ReleaseSlot(market_id) => self.release_slot(market_id),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct PositionChannel {
-    tx: mpsc::UnboundedSender<FillRecord>,
+    tx: mpsc::UnboundedSender<PositionUpdate>,
 }
 
 impl PositionChannel {
-    pub fn new(tx: mpsc::UnboundedSender<FillRecord>) -> Self {
+    pub fn new(tx: mpsc::UnboundedSender<PositionUpdate>) -> Self {
         Self { tx }
     }
 
     #[inline]
     pub fn record_fill(&self, fill: FillRecord) {
-        let _ = self.tx.send(fill);
+        let _ = self.tx.send(PositionUpdate::Fill(fill));
+    }
+
+    /// Release the slot of an entry on `market_id` once its fills are recorded
+    pub fn release_slot(&self, market_id: &str) {
+        let _ = self.tx.send(PositionUpdate::ReleaseSlot(market_id.to_string()));
     }
 }
 
-pub fn create_position_channel() -> (PositionChannel, mpsc::UnboundedReceiver<FillRecord>) {
+pub fn create_position_channel() -> (PositionChannel, mpsc::UnboundedReceiver<PositionUpdate>) {
     let (tx, rx) = mpsc::unbounded_channel();
     (PositionChannel::new(tx), rx)
 }
 
 pub async fn position_writer_loop(
-    mut rx: mpsc::UnboundedReceiver<FillRecord>,
+    mut rx: mpsc::UnboundedReceiver<PositionUpdate>,
     tracker: Arc<RwLock<PositionTracker>>,
 ) {
     let mut batch = Vec::with_capacity(16);
@@ -471,12 +1405,20 @@ pub async fn position_writer_loop(
         tokio::select! {
             biased;
 
-            Some(fill) = rx.recv() => {
-                batch.push(fill);
+            update = rx.recv() => {
+                let Some(update) = update else {
+                    // Every sender is gone (shutdown): apply what is left and stop
+                    let mut guard = tracker.write().await;
+                    for update in batch.drain(..) {
+                        guard.apply(&update);
+                    }
+                    return;
+                };
+                batch.push(update);
                 if batch.len() >= 16 {
                     let mut guard = tracker.write().await;
-                    for fill in batch.drain(..) {
-                        guard.record_fill_internal(&fill);
+                    for update in batch.drain(..) {
+                        guard.apply(&update);
                     }
                     guard.save_async();
                 }
@@ -484,8 +1426,8 @@ pub async fn position_writer_loop(
             _ = interval.tick() => {
                 if !batch.is_empty() {
                     let mut guard = tracker.write().await;
-                    for fill in batch.drain(..) {
-                        guard.record_fill_internal(&fill);
+                    for update in batch.drain(..) {
+                        guard.apply(&update);
                     }
                     guard.save_async();
                 }
@@ -511,6 +1453,52 @@ mod tests {
         assert!((leg.profit_if_win() - 5.50).abs() < 0.001); // $10 payout - $4.50 cost
     }
     
+    #[test]
+    fn test_fill_client_order_ids_kept_on_leg() {
+        let mut tracker = PositionTracker::new();
+        tracker.record_fill_internal(&FillRecord::new("TEST-MARKET", "Test", "kalshi", "no", 5.0, 0.50, 0.0, "k-1")
+            .with_client_order_id("a17000000001"));
+        tracker.record_fill_internal(&FillRecord::new("TEST-MARKET", "Test", "kalshi", "no", 5.0, 0.50, 0.0, "k-2"));
+        tracker.record_fill_internal(&FillRecord::new("TEST-MARKET", "Test", "polymarket", "yes", 10.0, 0.45, 0.0, "0xabc")
+            .with_client_order_id("0xabc"));
+
+        let pos = tracker.get("TEST-MARKET").unwrap();
+        assert_eq!(pos.kalshi_no.client_order_ids, vec!["a17000000001"]);
+        assert_eq!(pos.poly_yes.client_order_ids, vec!["0xabc"]);
+
+        // Survives a save/load, and files written before keys existed still load
+        let json = serde_json::to_string(pos).unwrap();
+        let loaded: ArbPosition = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.kalshi_no.client_order_ids, vec!["a17000000001"]);
+        let old: PositionLeg = serde_json::from_str(r#"{"contracts":1.0,"cost_basis":0.5,"avg_price":0.5}"#).unwrap();
+        assert!(old.client_order_ids.is_empty());
+    }
+
+    #[test]
+    fn test_inventory_is_yes_minus_no() {
+        let mut tracker = PositionTracker::new();
+        tracker.record_fill_internal(&FillRecord::new("TEST-MARKET", "Test", "polymarket", "yes", 12.0, 0.41, 0.0, "0x1"));
+        tracker.record_fill_internal(&FillRecord::new("TEST-MARKET", "Test", "polymarket", "no", 5.0, 0.53, 0.0, "0x2"));
+        assert_eq!(tracker.inventory("TEST-MARKET"), 7.0);
+        tracker.record_fill_internal(&FillRecord::new("TEST-MARKET", "Test", "kalshi", "no", 10.0, 0.55, 0.0, "k-1"));
+        assert_eq!(tracker.inventory("TEST-MARKET"), -3.0);
+        assert_eq!(tracker.inventory("OTHER"), 0.0);
+    }
+
+    #[test]
+    fn test_cooldown_runs_from_latest_fill() {
+        let mut tracker = PositionTracker::new();
+        let cooldown = Duration::from_secs(5);
+        assert_eq!(tracker.cooldown_remaining("TEST-MARKET", cooldown, unix_ms()), None, "never traded");
+
+        tracker.record_fill_internal(&FillRecord::new("TEST-MARKET", "Test", "polymarket", "yes", 10.0, 0.45, 0.0, "0x1"));
+        let traded = tracker.positions["TEST-MARKET"].last_trade_ms;
+        assert!(traded > 0);
+        assert_eq!(tracker.cooldown_remaining("TEST-MARKET", cooldown, traded + 1_500), Some(Duration::from_millis(3_500)));
+        assert_eq!(tracker.cooldown_remaining("TEST-MARKET", cooldown, traded + 5_000), None);
+        assert_eq!(tracker.cooldown_remaining("OTHER", cooldown, traded), None);
+    }
+
     #[test]
     fn test_arb_position_guaranteed_profit() {
         let mut pos = ArbPosition::new("TEST-MARKET", "Test");
@@ -561,4 +1549,251 @@ mod tests {
         assert!((pos.realized_pnl.unwrap() - 0.50).abs() < 0.001);
         assert_eq!(pos.status, "resolved");
     }
+
+    #[test]
+    fn test_reconcile_adopts_and_confirms_ghosts() {
+        let exchange = |market: &str, platform, side, contracts| ExchangePosition {
+            market_id: market.to_string(),
+            description: market.to_string(),
+            platform,
+            side,
+            contracts,
+            avg_price: 0.40,
+        };
+        let mut tracker = PositionTracker::new();
+        let held = tracker.get_or_create("HELD", "Held");
+        held.poly_yes.add(10.0, 0.45);
+        held.kalshi_no.add(10.0, 0.50);
+        tracker.get_or_create("GHOST", "Ghost").poly_no.add(5.0, 0.30);
+
+        let observed = vec![
+            exchange("HELD", "polymarket", "yes", 10.0),
+            exchange("HELD", "kalshi", "no", 10.0),
+            exchange("NEW", "polymarket", "yes", 3.0),
+        ];
+        assert!(tracker.has_missing_legs(&observed, &["polymarket"]));
+
+        // Confirmation still shows the ghost: keep it
+        let stale = vec![exchange("GHOST", "polymarket", "no", 5.0)];
+        let report = tracker.reconcile(&observed, &stale, &["polymarket"]);
+        assert_eq!(report.matched, 1);
+        assert_eq!(report.adopted.len(), 1);
+        assert_eq!(report.unconfirmed.len(), 1);
+        assert!(tracker.get("GHOST").is_some());
+        let adopted = tracker.get("NEW").unwrap();
+        assert!((adopted.poly_yes.cost_basis - 1.20).abs() < 0.001);
+
+        // Confirmed missing: dropped; Kalshi legs untouched when not queried
+        let report = tracker.reconcile(&observed, &observed, &["polymarket"]);
+        assert_eq!(report.dropped.len(), 1);
+        assert!(tracker.get("GHOST").is_none());
+        assert!((tracker.get("HELD").unwrap().kalshi_no.contracts - 10.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_mark_to_market_with_stale_leg() {
+        let mut tracker = PositionTracker::new();
+        let pos = tracker.get_or_create("ARB", "Arb");
+        pos.poly_yes.add(10.0, 0.45);
+        pos.kalshi_no.add(10.0, 0.50);
+        pos.total_fees = 0.10;
+
+        // Both legs quoted: 10 × (0.55 - 0.45) + 10 × (0.40 - 0.50) - 0.10
+        let marks = tracker.mark_to_market(|_, platform, _| Some(if platform == "polymarket" { 0.55 } else { 0.40 }));
+        assert!((marks.total - (-0.10)).abs() < 0.001);
+        assert_eq!(marks.stale_positions, 0);
+
+        // Kalshi bid disappears: marked at the last bid seen and flagged stale
+        let marks = tracker.mark_to_market(|_, platform, _| (platform == "polymarket").then_some(0.60));
+        assert!((marks.total - 0.40).abs() < 0.001);
+        assert_eq!(marks.stale_positions, 1);
+        assert!(marks.positions[0].stale);
+    }
+
+    #[test]
+    fn test_trailing_stop_on_directional_inventory_only() {
+        let mut tracker = PositionTracker::new();
+        // Hedged pair: never stopped out
+        let pos = tracker.get_or_create("ARB", "Arb");
+        pos.poly_yes.add(10.0, 0.45);
+        pos.kalshi_no.add(10.0, 0.50);
+        // Spread capture long 15 YES against 5 NO: 10 directional
+        let pos = tracker.get_or_create("MM", "Market making");
+        pos.poly_yes.add(15.0, 0.40);
+        pos.poly_no.add(5.0, 0.58);
+
+        let retrace = 0.05;
+        for bid in [0.42, 0.50, 0.47] {
+            assert!(tracker.trailing_stops(retrace, |_, _, _| Some(bid)).is_empty());
+        }
+        // No bid: the high-water mark is kept
+        assert!(tracker.trailing_stops(retrace, |_, _, _| None).is_empty());
+        let stops = tracker.trailing_stops(retrace, |_, _, _| Some(0.45));
+        assert_eq!(stops, [TrailingStop {
+            market_id: "MM".into(),
+            description: "Market making".into(),
+            platform: "polymarket",
+            side: "yes",
+            contracts: 10.0,
+            high_water: 0.50,
+            mark: 0.45,
+        }]);
+
+        // Hedged again: the stop is disarmed and starts over from the next bid
+        tracker.record_close("MM", "polymarket", "yes", 10.0, 4.5);
+        assert!(tracker.trailing_stops(retrace, |_, _, _| Some(0.30)).is_empty());
+        assert_eq!(tracker.open_positions().iter().find(|p| p.market_id == "MM").unwrap().poly_yes.high_water_mark, None);
+    }
+
+    #[test]
+    fn test_settle_books_resolution_pnl() {
+        let mut tracker = PositionTracker::new();
+        let pos = tracker.get_or_create("ARB", "Arb");
+        pos.poly_yes.add(10.0, 0.45);
+        pos.kalshi_no.add(10.0, 0.50);
+
+        let settlement = tracker.settle("ARB", false).unwrap();
+        assert!((settlement.realized_pnl - 0.50).abs() < 0.001);
+        assert!((settlement.contracts - 10.0).abs() < 0.001);
+        assert!((tracker.daily_pnl() - 0.50).abs() < 0.001);
+        assert_eq!(tracker.summary().resolved_positions, 1);
+
+        // Already settled
+        assert!(tracker.settle("ARB", true).is_none());
+    }
+
+    #[test]
+    fn test_partial_close_then_settle() {
+        let mut tracker = PositionTracker::new();
+        let pos = tracker.get_or_create("ARB", "Arb");
+        pos.poly_yes.add(10.0, 0.45);
+        pos.kalshi_no.add(10.0, 0.50);
+
+        // Sold 4 YES at 0.40: -$0.20 against the 0.45 average
+        let pnl = tracker.record_close("ARB", "polymarket", "yes", 4.0, 1.60).unwrap();
+        assert!((pnl + 0.20).abs() < 0.001);
+        let pos = tracker.get("ARB").unwrap();
+        assert_eq!(pos.status, "open");
+        assert!((pos.poly_yes.cost_basis - 2.70).abs() < 0.001);
+
+        // NO wins: 10 paid out against the remaining $7.70 cost
+        let settlement = tracker.settle("ARB", false).unwrap();
+        assert!((settlement.realized_pnl - 2.30).abs() < 0.001);
+        assert!((tracker.get("ARB").unwrap().realized_pnl.unwrap() - 2.10).abs() < 0.001);
+        assert!((tracker.daily_pnl() - 2.10).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_closes_appended_to_ledger() {
+        let path = std::env::temp_dir().join(format!("closed_positions_{}.csv", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let mut tracker = PositionTracker::new();
+        tracker.set_ledger(Some(path.clone()));
+        let pos = tracker.get_or_create("ARB", "Chelsea vs Aston Villa, EPL");
+        pos.poly_yes.add(10.0, 0.45);
+        pos.kalshi_no.add(10.0, 0.50);
+        pos.total_fees = 0.20;
+
+        tracker.record_close("ARB", "polymarket", "yes", 4.0, 1.60).unwrap();
+        // A restart appends to the same file without repeating the header
+        let mut tracker = PositionTracker { positions: tracker.positions.clone(), ..PositionTracker::new() };
+        tracker.set_ledger(Some(path.clone()));
+        tracker.settle("ARB", false).unwrap();
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 4, "{}", csv);
+        assert_eq!(rows[0], LEDGER_HEADER);
+        fn cols(row: &str) -> Vec<&str> {
+            row.rsplitn(8, ',').collect::<Vec<_>>().into_iter().rev().collect()
+        }
+        // Sold 4 YES at 0.40 against 0.45: fees stay with the legs still open
+        assert!(rows[1].contains(",ARB,\"Chelsea vs Aston Villa, EPL\",polymarket,yes,4,"), "{}", rows[1]);
+        assert_eq!(cols(rows[1])[1..], ["polymarket", "yes", "4", "0.4500", "0.4000", "-0.2000", "0.0000"]);
+        // NO won: the 6 remaining YES expire worthless, the 10 NO pay $1; $0.20 fees split 6/16 and 10/16
+        assert_eq!(cols(rows[2])[1..], ["kalshi", "no", "10", "0.5000", "1.0000", "4.8750", "0.1250"]);
+        assert_eq!(cols(rows[3])[1..], ["polymarket", "yes", "6", "0.4500", "0.0000", "-2.7750", "0.0750"]);
+
+        let total: f64 = rows[1..].iter().map(|r| cols(r)[6].parse::<f64>().unwrap()).sum();
+        assert!((total - tracker.get("ARB").unwrap().realized_pnl.unwrap()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_can_open_trims_to_limits() {
+        let mut overrides = HashMap::new();
+        overrides.insert("KXTEST-BIG".to_string(), MarketLimit { max_contracts: Some(100.0), max_notional: None });
+        let mut tracker = PositionTracker::new();
+        tracker.set_limits(PositionLimits::new(
+            MarketLimit { max_contracts: Some(20.0), max_notional: Some(20.0) },
+            MarketLimit { max_contracts: None, max_notional: Some(15.0) },
+            overrides,
+        ));
+        let pos = tracker.get_or_create("poly-slug-KXTEST-SMALL", "Small");
+        pos.poly_yes.add(15.0, 0.45);
+        pos.kalshi_no.add(15.0, 0.50);
+
+        // 5 contracts of room left in the market
+        let check = tracker.can_open("poly-slug-KXTEST-SMALL", 10.0, 0.10);
+        assert_eq!(check, OpenCheck { contracts: 5.0, limit: Some(LimitHit::MarketContracts(20.0)) });
+
+        // Override raises the contract cap; the $15 global notional cap
+        // (with $14.25 already held) binds before the default $20 per market
+        let check = tracker.can_open("poly-slug-KXTEST-BIG", 30.0, 0.95);
+        assert!((check.contracts - 0.75 / 0.95).abs() < 0.001);
+        assert_eq!(check.limit, Some(LimitHit::TotalNotional(15.0)));
+
+        assert_eq!(tracker.can_open("other", 1.0, 0.0).limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_open_position_slots() {
+        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
+        tracker.write().await.set_limits(PositionLimits { max_open_positions: Some(2), ..PositionLimits::default() });
+        let (channel, rx) = create_position_channel();
+        let writer = tokio::spawn(position_writer_loop(rx, tracker.clone()));
+
+        {
+            let mut t = tracker.write().await;
+            assert!(t.acquire_slot("MARKET-A"));
+            assert!(t.acquire_slot("MARKET-B"));
+            assert!(!t.acquire_slot("MARKET-C"), "both slots in flight");
+            assert!(t.acquire_slot("MARKET-A"), "already holds a slot");
+        }
+
+        // A fills and keeps its slot as a position; B ends unfilled
+        channel.record_fill(FillRecord::new("MARKET-A", "A", "kalshi", "yes", 5.0, 0.40, 0.0, "order-1"));
+        channel.release_slot("MARKET-A");
+        channel.release_slot("MARKET-B");
+        drop(channel);
+        writer.await.unwrap();
+
+        let mut t = tracker.write().await;
+        assert_eq!(t.slots_used(), 1);
+        assert!(t.acquire_slot("MARKET-C"));
+        assert!(!t.acquire_slot("MARKET-D"));
+        t.release_slot("MARKET-C");
+
+        // Closing A frees its slot
+        t.resolve_position("MARKET-A", true);
+        assert_eq!(t.slots_used(), 0);
+        assert!(t.acquire_slot("MARKET-D"));
+    }
+
+    #[tokio::test]
+    async fn test_writer_loop_applies_pending_fills_on_shutdown() {
+        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
+        let (channel, rx) = create_position_channel();
+        let writer = tokio::spawn(position_writer_loop(rx, tracker.clone()));
+
+        channel.record_fill(FillRecord::new("TEST-MARKET", "Test", "kalshi", "yes", 5.0, 0.40, 0.0, "order-1"));
+        drop(channel);
+
+        tokio::time::timeout(Duration::from_secs(1), writer).await
+            .expect("writer should stop once the channel closes")
+            .unwrap();
+        let summary = tracker.read().await.summary();
+        assert_eq!(summary.open_positions, 1);
+        assert_eq!(summary.total_contracts, 5.0);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,212 @@
+//! Preflight checks of every integration, without placing orders.
+//!
+//! Run with `PREFLIGHT=1` (or the `preflight` argument) before leaving the bot
+//! unattended: it validates the configuration, authenticates with Polymarket
+//! and Kalshi and fetches a sample of each, measures the clock skew, then
+//! sends the results as a Telegram test message. The process exits non-zero
+//! if any critical check failed.
+
+use std::fmt::Display;
+use tracing::{error, info, warn};
+
+use crate::clock_skew;
+use crate::config::{get_league_configs, AppConfig, ClockSkewConfig, HttpConfig, POLYGON_CHAIN_ID, POLY_CLOB_HOST};
+use crate::kalshi::KalshiApiClient;
+use crate::polymarket_clob::{PolymarketAsyncClient, PreparedCreds};
+use crate::telegram::TelegramBot;
+use crate::types::Platform;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not applicable (integration not configured) or blocked by an earlier failure
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    /// A failure here fails the preflight
+    pub critical: bool,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn line(&self) -> String {
+        let mark = match self.status {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Fail if self.critical => "❌",
+            CheckStatus::Fail => "⚠️",
+            CheckStatus::Skipped => "⏭",
+        };
+        format!("{} {}: {}", mark, self.name, self.detail)
+    }
+}
+
+/// Outcome of every check, in the order they ran
+#[derive(Debug, Default)]
+pub struct PreflightReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl PreflightReport {
+    fn record<T, E: Display>(&mut self, name: &'static str, critical: bool, result: Result<T, E>, detail: impl FnOnce(&T) -> String) -> Option<T> {
+        let (status, detail, value) = match result {
+            Ok(value) => (CheckStatus::Pass, detail(&value), Some(value)),
+            Err(e) => (CheckStatus::Fail, format!("{:#}", e), None),
+        };
+        self.checks.push(CheckResult { name, critical, status, detail });
+        value
+    }
+
+    fn skip(&mut self, name: &'static str, reason: &str) {
+        self.checks.push(CheckResult { name, critical: false, status: CheckStatus::Skipped, detail: reason.to_string() });
+    }
+
+    /// Critical checks that failed
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|c| c.critical && c.status == CheckStatus::Fail).count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+    }
+
+    /// One line per check
+    pub fn lines(&self) -> Vec<String> {
+        self.checks.iter().map(CheckResult::line).collect()
+    }
+
+    pub fn log(&self) {
+        for check in &self.checks {
+            match check.status {
+                CheckStatus::Pass => info!("[PREFLIGHT] {}", check.line()),
+                CheckStatus::Fail if check.critical => error!("[PREFLIGHT] {}", check.line()),
+                CheckStatus::Fail | CheckStatus::Skipped => warn!("[PREFLIGHT] {}", check.line()),
+            }
+        }
+        if self.passed() {
+            info!("[PREFLIGHT] All critical checks passed");
+        } else {
+            error!("[PREFLIGHT] {} critical check(s) failed", self.failures());
+        }
+    }
+}
+
+/// Run every check; nothing is traded
+pub async fn run() -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let Some(config) = report.record("config", true, AppConfig::from_env(), |_| "valid".to_string()) else {
+        for name in ["polymarket", "polymarket_book", "kalshi", "kalshi_markets", "clock_skew", "telegram"] {
+            report.skip(name, "configuration invalid");
+        }
+        return report;
+    };
+
+    // Polymarket: derive the API credentials and read the balance, then a public book
+    let poly = async {
+        let client = PolymarketAsyncClient::new(POLY_CLOB_HOST, POLYGON_CHAIN_ID, &config.poly_private_key, &config.poly_funder)?;
+        let creds = PreparedCreds::from_api_creds(&client.derive_api_key(0).await?)?;
+        let balance = client.get_balance_async(&creds).await?;
+        Ok::<_, crate::error::Error>((client, balance))
+    }.await;
+    match report.record("polymarket", true, poly, |(_, balance)| format!("authenticated, balance ${:.2}", *balance as f64 / 100.0)) {
+        Some((client, _)) => {
+            let book = async {
+                let token = client.sample_token().await?;
+                let constraints = client.get_order_constraints(&token).await?;
+                Ok::<_, crate::error::Error>((token, constraints))
+            }.await;
+            report.record("polymarket_book", true, book, |(token, c)| format!(
+                "book of {}… fetched (tick {}bps, min size {})", &token[..token.len().min(12)], c.tick_bps, c.min_size,
+            ));
+        }
+        None => report.skip("polymarket_book", "no Polymarket credentials"),
+    }
+
+    // Kalshi: key self-check and balance, then a league's events
+    let mut probes = vec![(Platform::Polymarket, format!("{}/time", POLY_CLOB_HOST))];
+    match &config.kalshi {
+        Some(kalshi) => {
+            probes.push((Platform::Kalshi, format!("{}/exchange/status", kalshi.environment.api_base())));
+            let api = KalshiApiClient::new(kalshi.clone());
+            let auth = match kalshi.verify_key() {
+                Ok(()) => api.validate_credentials().await,
+                Err(e) => Err(e),
+            };
+            let environment = kalshi.environment;
+            if report.record("kalshi", true, auth, |balance| format!(
+                "authenticated on {}, balance ${:.2}", environment, *balance as f64 / 100.0,
+            )).is_some() {
+                let series = get_league_configs().first().map(|l| l.kalshi_series_game).unwrap_or_default();
+                report.record("kalshi_markets", true, api.get_events(series, 5).await, |events| format!(
+                    "{} {} event(s) fetched", events.len(), series,
+                ));
+            } else {
+                report.skip("kalshi_markets", "no Kalshi credentials");
+            }
+        }
+        None => {
+            report.skip("kalshi", "POLY_ONLY_MODE");
+            report.skip("kalshi_markets", "POLY_ONLY_MODE");
+        }
+    }
+
+    // Host clock against each exchange, with the limit that blocks orders
+    let max_skew_ms = ClockSkewConfig::from_env().max_skew_ms;
+    let mut skews = Vec::new();
+    let mut skew_error = None;
+    for (platform, url) in &probes {
+        let http = HttpConfig::for_platform(*platform).client().expect("Failed to build HTTP client");
+        match clock_skew::measure(&http, url).await {
+            Ok(skew_ms) if max_skew_ms.is_some_and(|max| skew_ms.unsigned_abs() > max) => {
+                skew_error.get_or_insert(format!("{} {:+}ms off (limit {}ms)", platform, skew_ms, max_skew_ms.unwrap_or_default()));
+            }
+            Ok(skew_ms) => skews.push(format!("{} {:+}ms", platform, skew_ms)),
+            Err(e) => {
+                skew_error.get_or_insert(format!("{}: {:#}", platform, e));
+            }
+        }
+    }
+    report.record("clock_skew", true, skew_error.map_or(Ok(()), Err), |_| skews.join(", "));
+
+    // Telegram last, so the test message carries the results
+    match config.telegram {
+        Some(telegram) if telegram.enabled => {
+            let mut text = format!("🧪 Preflight: {}\n\n", if report.passed() { "OK" } else { "FAILED" });
+            text.push_str(&report.lines().join("\n"));
+            let chats = telegram.chat_ids.len();
+            let sent = TelegramBot::new(telegram).send_message(&text).await;
+            report.record("telegram", true, sent, |_| format!("test message delivered to {} chat(s)", chats));
+        }
+        Some(_) => report.skip("telegram", "TELEGRAM_ENABLED=false"),
+        None => report.skip("telegram", "not configured"),
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_critical_failures_fail_preflight() {
+        let mut report = PreflightReport::default();
+        report.record("config", true, Ok::<_, String>(()), |_| "valid".to_string());
+        report.record("extra", false, Err::<(), _>("unreachable"), |_| String::new());
+        report.skip("kalshi", "POLY_ONLY_MODE");
+        assert!(report.passed());
+
+        assert_eq!(report.record("polymarket", true, Err::<i64, _>("401 Unauthorized"), |b| b.to_string()), None);
+        assert!(!report.passed());
+        assert_eq!(report.failures(), 1);
+        assert_eq!(report.lines(), [
+            "✅ config: valid",
+            "⚠️ extra: unreachable",
+            "⏭ kalshi: POLY_ONLY_MODE",
+            "❌ polymarket: 401 Unauthorized",
+        ]);
+    }
+}
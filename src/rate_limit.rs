@@ -0,0 +1,104 @@
+//! Per-platform token buckets for outbound REST calls.
+//!
+//! Every Kalshi and Polymarket request path awaits a permit from its
+//! platform's bucket before sending, so discovery, execution and
+//! reconciliation draw on one budget per exchange instead of pacing
+//! themselves independently. Time spent waiting is accumulated for the
+//! metrics endpoint.
+
+use governor::{Quota, RateLimiter, state::NotKeyed, state::InMemoryState, clock::DefaultClock, middleware::NoOpMiddleware};
+use std::num::NonZeroU32;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use crate::config::RateLimitConfig;
+use crate::types::Platform;
+
+type DirectRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>;
+
+/// Time requests spent waiting for a permit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ThrottleStats {
+    /// Requests that had to wait
+    pub throttled: u64,
+    /// Total wait across those requests (seconds)
+    pub wait_secs: f64,
+}
+
+/// Token bucket for one platform
+pub struct PlatformRateLimiter {
+    limiter: DirectRateLimiter,
+    throttled: AtomicU64,
+    wait_us: AtomicU64,
+}
+
+impl PlatformRateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let rps = NonZeroU32::new(config.requests_per_sec.max(1)).unwrap();
+        let burst = NonZeroU32::new(config.burst.max(1)).unwrap();
+        Self {
+            limiter: RateLimiter::direct(Quota::per_second(rps).allow_burst(burst)),
+            throttled: AtomicU64::new(0),
+            wait_us: AtomicU64::new(0),
+        }
+    }
+
+    /// Wait until the bucket has a token, then take it
+    pub async fn acquire(&self) {
+        if self.limiter.check().is_ok() {
+            return;
+        }
+        let start = Instant::now();
+        self.limiter.until_ready().await;
+        self.throttled.fetch_add(1, Ordering::Relaxed);
+        self.wait_us.fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+    }
+
+    #[allow(dead_code)]
+    pub fn stats(&self) -> ThrottleStats {
+        ThrottleStats {
+            throttled: self.throttled.load(Ordering::Relaxed),
+            wait_secs: self.wait_us.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+        }
+    }
+}
+
+static KALSHI: OnceLock<PlatformRateLimiter> = OnceLock::new();
+static POLYMARKET: OnceLock<PlatformRateLimiter> = OnceLock::new();
+
+/// Shared bucket for `platform`, configured from the environment on first use
+pub fn limiter(platform: Platform) -> &'static PlatformRateLimiter {
+    match platform {
+        Platform::Kalshi => KALSHI.get_or_init(|| PlatformRateLimiter::new(RateLimitConfig::kalshi_from_env())),
+        Platform::Polymarket => POLYMARKET.get_or_init(|| PlatformRateLimiter::new(RateLimitConfig::poly_from_env())),
+    }
+}
+
+/// Wait for a permit to send one request to `platform`
+#[inline]
+pub async fn acquire(platform: Platform) {
+    limiter(platform).acquire().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_burst_then_throttle() {
+        let limiter = PlatformRateLimiter::new(RateLimitConfig { requests_per_sec: 20, burst: 2 });
+
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(limiter.stats(), ThrottleStats::default(), "burst is not throttled");
+
+        let start = Instant::now();
+        limiter.acquire().await;
+        assert!(start.elapsed().as_millis() >= 30, "third request waits for a token");
+
+        let stats = limiter.stats();
+        assert_eq!(stats.throttled, 1);
+        assert!(stats.wait_secs > 0.0);
+    }
+}
@@ -0,0 +1,311 @@
+//! Probation of newly matched market pairs.
+//!
+//! The title matcher in `discovery` can pair two markets that read alike but
+//! resolve differently (another date, another line, another settlement
+//! source). Such a pair shows a large and persistent "arbitrage". A pair seen
+//! for the first time is therefore shadowed: the `ExecutionEngine` evaluates
+//! its opportunities as usual but only logs the would-be P&L. After
+//! `SHADOW_OBSERVATIONS` observations or `SHADOW_HOURS`, whichever comes
+//! first, the pair goes live if its mean edge stayed under
+//! `SHADOW_MAX_EDGE_CENTS` per contract and is rejected otherwise.
+//!
+//! Records are kept in `.shadow_pairs.json` across restarts, written by a
+//! background task; removing a rejected pair's entry puts it back on
+//! probation. When the file does not exist yet (first run, or probation just
+//! turned on) the pairs matched at startup are recorded as live, so only
+//! pairs matched afterwards go through probation.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::config::ShadowConfig;
+
+/// Probation records file
+const SHADOW_STATE_PATH: &str = ".shadow_pairs.json";
+
+/// Opportunities on a pair closer together than this count as one observation
+const OBSERVATION_GAP_MS: u64 = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowStatus {
+    Probation,
+    Live,
+    Rejected,
+}
+
+/// Probation record of one pair
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShadowRecord {
+    pub status: ShadowStatus,
+    /// Unix timestamp in milliseconds
+    pub first_seen_ms: u64,
+    pub observations: u32,
+    pub last_observation_ms: u64,
+    /// Sum of the observed per-contract edges
+    pub edge_sum_cents: f64,
+    /// Would-be profit of every observation at its full size
+    pub pnl_cents: f64,
+}
+
+impl ShadowRecord {
+    fn new(status: ShadowStatus, now_ms: u64) -> Self {
+        Self {
+            status,
+            first_seen_ms: now_ms,
+            observations: 0,
+            last_observation_ms: 0,
+            edge_sum_cents: 0.0,
+            pnl_cents: 0.0,
+        }
+    }
+
+    pub fn mean_edge_cents(&self) -> f64 {
+        if self.observations == 0 {
+            return 0.0;
+        }
+        self.edge_sum_cents / self.observations as f64
+    }
+}
+
+/// What to do with an opportunity on a pair
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShadowDecision {
+    /// Live pair
+    Trade,
+    /// On probation: log the would-be trade only
+    Shadow(ShadowRecord),
+    /// Probation just ended with a plausible edge; trades from now on
+    Graduated(ShadowRecord),
+    /// Probation just ended with an edge too large to be real
+    Rejected(ShadowRecord),
+    /// Rejected earlier
+    Blocked,
+}
+
+/// Probation state of every pair seen so far
+pub struct ShadowBook {
+    config: ShadowConfig,
+    /// None keeps the records in memory only
+    path: Option<PathBuf>,
+    records: Mutex<BTreeMap<String, ShadowRecord>>,
+    /// No records file existed: the next `enroll` records its pairs as live
+    seed_live: AtomicBool,
+    /// Wakes the writer task after a change
+    dirty: Option<watch::Sender<()>>,
+}
+
+impl ShadowBook {
+    /// In-memory book, nothing persisted
+    #[allow(dead_code)]
+    pub fn new(config: ShadowConfig) -> Self {
+        Self { config, path: None, records: Mutex::default(), seed_live: AtomicBool::new(false), dirty: None }
+    }
+
+    /// Book persisted to `.shadow_pairs.json`, starting from its contents.
+    /// Changes are written by a background task on the current runtime.
+    pub fn load(config: ShadowConfig) -> Arc<Self> {
+        let path = PathBuf::from(SHADOW_STATE_PATH);
+        let seed_live = !path.exists();
+        let records = match Self::read(&path) {
+            Ok(records) => records,
+            Err(e) => {
+                warn!("[SHADOW] {:#}, starting with no pair on record", e);
+                BTreeMap::new()
+            }
+        };
+        let (dirty, mut changed) = watch::channel(());
+        let book = Arc::new(Self {
+            config,
+            path: Some(path),
+            records: Mutex::new(records),
+            seed_live: AtomicBool::new(seed_live),
+            dirty: Some(dirty),
+        });
+
+        // Holds a weak reference so the task ends once the book is dropped
+        let weak = Arc::downgrade(&book);
+        tokio::spawn(async move {
+            while changed.changed().await.is_ok() {
+                let Some(book) = weak.upgrade() else { return };
+                let _ = tokio::task::spawn_blocking(move || book.flush()).await;
+            }
+        });
+        book
+    }
+
+    fn read(path: &Path) -> Result<BTreeMap<String, ShadowRecord>> {
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let data = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("Invalid probation records in {}", path.display()))
+    }
+
+    /// Have the writer task save the records
+    fn save(&self) {
+        if let Some(dirty) = &self.dirty {
+            dirty.send_replace(());
+        }
+    }
+
+    /// Write the records now (the writer task does this after every change;
+    /// called directly at shutdown)
+    pub fn flush(&self) {
+        let Some(path) = &self.path else { return };
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let result = serde_json::to_string_pretty(&records)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| std::fs::write(path, data).with_context(|| format!("Failed to write {}", path.display())));
+        if let Err(e) = result {
+            warn!("[SHADOW] {:#}", e);
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.observations.is_some()
+    }
+
+    pub fn observations_required(&self) -> u32 {
+        self.config.observations.unwrap_or_default()
+    }
+
+    /// Start the probation of pairs never seen before; returns how many.
+    /// Without a records file yet, the pairs are recorded as live instead.
+    pub fn enroll<'a>(&self, pair_ids: impl IntoIterator<Item = &'a str>, now_ms: u64) -> usize {
+        if !self.enabled() {
+            return 0;
+        }
+        let status = match self.seed_live.swap(false, Ordering::Relaxed) {
+            true => ShadowStatus::Live,
+            false => ShadowStatus::Probation,
+        };
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let before = records.len();
+        for pair_id in pair_ids {
+            records.entry(pair_id.to_string()).or_insert_with(|| ShadowRecord::new(status, now_ms));
+        }
+        let enrolled = records.len() - before;
+        drop(records);
+        if enrolled > 0 {
+            self.save();
+        }
+        if status == ShadowStatus::Live {
+            info!("[SHADOW] No {} yet: {} known pairs recorded as live", SHADOW_STATE_PATH, enrolled);
+            return 0;
+        }
+        enrolled
+    }
+
+    /// Pairs currently on probation
+    pub fn on_probation(&self) -> usize {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|r| r.status == ShadowStatus::Probation)
+            .count()
+    }
+
+    /// Record an opportunity of `contracts` at `edge_cents` per contract on
+    /// `pair_id` and decide whether it may trade
+    pub fn evaluate(&self, pair_id: &str, edge_cents: f64, contracts: i64, now_ms: u64) -> ShadowDecision {
+        let Some(required) = self.config.observations else { return ShadowDecision::Trade };
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let record = records.entry(pair_id.to_string()).or_insert_with(|| ShadowRecord::new(ShadowStatus::Probation, now_ms));
+        match record.status {
+            ShadowStatus::Live => return ShadowDecision::Trade,
+            ShadowStatus::Rejected => return ShadowDecision::Blocked,
+            ShadowStatus::Probation => {}
+        }
+
+        let counted = record.observations == 0 || now_ms.saturating_sub(record.last_observation_ms) >= OBSERVATION_GAP_MS;
+        if counted {
+            record.observations += 1;
+            record.last_observation_ms = now_ms;
+            record.edge_sum_cents += edge_cents;
+            record.pnl_cents += edge_cents * contracts as f64;
+        }
+
+        let expired = self.config.max_hours
+            .is_some_and(|hours| now_ms.saturating_sub(record.first_seen_ms) as f64 >= hours * 3_600_000.0);
+        let decision = if record.observations < required && !expired {
+            ShadowDecision::Shadow(record.clone())
+        } else if record.mean_edge_cents() <= self.config.max_edge_cents {
+            record.status = ShadowStatus::Live;
+            ShadowDecision::Graduated(record.clone())
+        } else {
+            record.status = ShadowStatus::Rejected;
+            ShadowDecision::Rejected(record.clone())
+        };
+        let changed = counted || record.status != ShadowStatus::Probation;
+        drop(records);
+        if changed {
+            self.save();
+        }
+        decision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: u64 = 60_000;
+
+    fn book(observations: u32, max_hours: Option<f64>) -> ShadowBook {
+        ShadowBook::new(ShadowConfig { observations: Some(observations), max_hours, max_edge_cents: 10.0 })
+    }
+
+    #[test]
+    fn test_pair_graduates_after_sane_observations() {
+        let book = book(3, None);
+        assert_eq!(book.enroll(["epl-che-avl", "nba-lal-bos"], 0), 2);
+        assert_eq!(book.enroll(["epl-che-avl"], 0), 0, "already enrolled");
+
+        assert!(matches!(book.evaluate("epl-che-avl", 2.0, 50, MIN), ShadowDecision::Shadow(r) if r.observations == 1));
+        // Repeats within the gap are the same observation
+        assert!(matches!(book.evaluate("epl-che-avl", 30.0, 50, MIN + 1000), ShadowDecision::Shadow(r) if r.observations == 1));
+        assert!(matches!(book.evaluate("epl-che-avl", 3.0, 50, 2 * MIN), ShadowDecision::Shadow(_)));
+        match book.evaluate("epl-che-avl", 4.0, 10, 3 * MIN) {
+            ShadowDecision::Graduated(r) => {
+                assert_eq!(r.observations, 3);
+                assert_eq!(r.mean_edge_cents(), 3.0);
+                assert_eq!(r.pnl_cents, 290.0);
+            }
+            other => panic!("expected graduation, got {:?}", other),
+        }
+        assert_eq!(book.evaluate("epl-che-avl", 40.0, 10, 4 * MIN), ShadowDecision::Trade);
+        assert_eq!(book.on_probation(), 1);
+    }
+
+    #[test]
+    fn test_implausible_edge_rejects_pair() {
+        let book = book(20, Some(1.0));
+        book.enroll(["epl-che-avl"], 0);
+        assert!(matches!(book.evaluate("epl-che-avl", 35.0, 100, MIN), ShadowDecision::Shadow(_)));
+        // Time limit reached before the observation count
+        assert!(matches!(book.evaluate("epl-che-avl", 30.0, 100, 60 * MIN), ShadowDecision::Rejected(r) if r.observations == 2));
+        assert_eq!(book.evaluate("epl-che-avl", 2.0, 100, 61 * MIN), ShadowDecision::Blocked);
+
+        let disabled = ShadowBook::new(ShadowConfig { observations: None, max_hours: None, max_edge_cents: 10.0 });
+        assert_eq!(disabled.enroll(["epl-che-avl"], 0), 0);
+        assert_eq!(disabled.evaluate("epl-che-avl", 35.0, 100, MIN), ShadowDecision::Trade);
+    }
+
+    #[test]
+    fn test_first_enroll_without_records_seeds_live_pairs() {
+        let book = ShadowBook { seed_live: AtomicBool::new(true), ..book(3, None) };
+        assert_eq!(book.enroll(["epl-che-avl", "nba-lal-bos"], 0), 0, "known pairs are not put on probation");
+        assert_eq!(book.on_probation(), 0);
+        assert_eq!(book.evaluate("epl-che-avl", 35.0, 100, MIN), ShadowDecision::Trade);
+
+        // Pairs matched later still go through probation
+        assert_eq!(book.enroll(["epl-che-avl", "nfl-kc-buf"], MIN), 1);
+        assert!(matches!(book.evaluate("nfl-kc-buf", 2.0, 100, MIN), ShadowDecision::Shadow(_)));
+    }
+}
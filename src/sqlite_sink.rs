@@ -0,0 +1,344 @@
+//! Opportunities and trades persisted to SQLite (feature `sqlite`).
+//!
+//! With `SQLITE_DB_FILE=<file>` every decision the `ExecutionEngine` records
+//! for the event log and every reported trade are also written to three
+//! tables, for querying the history with SQL:
+//!
+//! - `opportunities`: one row per evaluated opportunity, with its inputs and
+//!   the decision (`traded`, `below_threshold`, `vanished`, `skipped`)
+//! - `trades`: one row per trade, as sent to the webhook
+//! - `trade_fills`: the legs of each trade (`trade_id` → `trades.id`)
+//!
+//! Both main tables are indexed on the market and on the timestamp. Rows are
+//! queued and written from a dedicated thread, up to `SQLITE_BATCH_SIZE` per
+//! transaction; when the queue of `SQLITE_QUEUE_SIZE` is full new rows are
+//! dropped rather than holding up execution. The NDJSON event log keeps
+//! working alongside.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::Path;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::config::SqliteConfig;
+use crate::event_log::{Decision, DecisionEvent, EventSender};
+use crate::execution::TradeReport;
+use crate::webhook::TradeRecord;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS opportunities (
+    id INTEGER PRIMARY KEY,
+    ts_ms INTEGER NOT NULL,
+    market_id INTEGER NOT NULL,
+    pair_id TEXT NOT NULL,
+    market_type TEXT NOT NULL,
+    description TEXT NOT NULL,
+    arb_type TEXT NOT NULL,
+    yes_price INTEGER NOT NULL,
+    no_price INTEGER NOT NULL,
+    yes_size INTEGER NOT NULL,
+    no_size INTEGER NOT NULL,
+    kalshi_fee_cents INTEGER NOT NULL,
+    net_profit_cents REAL NOT NULL,
+    min_profit_cents REAL NOT NULL,
+    decision TEXT NOT NULL,
+    dry_run INTEGER,
+    success INTEGER,
+    profit_cents INTEGER,
+    reason TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_opportunities_pair_ts ON opportunities (pair_id, ts_ms);
+CREATE INDEX IF NOT EXISTS idx_opportunities_ts ON opportunities (ts_ms);
+
+CREATE TABLE IF NOT EXISTS trades (
+    id INTEGER PRIMARY KEY,
+    ts_ms INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    market TEXT NOT NULL,
+    arb_type TEXT NOT NULL,
+    contracts INTEGER NOT NULL,
+    requested_contracts INTEGER NOT NULL,
+    hedge_contracts INTEGER,
+    limit_hit TEXT,
+    profit_cents INTEGER NOT NULL,
+    success INTEGER NOT NULL,
+    simulated INTEGER NOT NULL,
+    latency_ms INTEGER NOT NULL,
+    unwound_contracts INTEGER NOT NULL,
+    cancelled_orders INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_trades_market_ts ON trades (market, ts_ms);
+CREATE INDEX IF NOT EXISTS idx_trades_ts ON trades (ts_ms);
+
+CREATE TABLE IF NOT EXISTS trade_fills (
+    trade_id INTEGER NOT NULL REFERENCES trades (id),
+    platform TEXT NOT NULL,
+    side TEXT NOT NULL,
+    contracts INTEGER NOT NULL,
+    cost_cents INTEGER NOT NULL,
+    order_id TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_trade_fills_trade ON trade_fills (trade_id);
+";
+
+/// One queued row
+#[derive(Debug, Clone)]
+pub enum SinkRecord {
+    Opportunity(DecisionEvent),
+    Trade { ts_ms: u64, record: TradeRecord },
+}
+
+/// Name of a unit enum variant as serde writes it, e.g. `poly_yes_kalshi_no`
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(other) => other.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Database connection with the schema in place
+pub struct SqliteSink {
+    conn: Connection,
+}
+
+impl SqliteSink {
+    /// Open (or create) the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+        Self::init(conn)
+    }
+
+    /// Database kept in memory, for tests
+    #[allow(dead_code)]
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA).context("Failed to create the SQLite schema")?;
+        Ok(Self { conn })
+    }
+
+    #[allow(dead_code)]
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Write `records` in one transaction
+    pub fn write_batch(&mut self, records: &[SinkRecord]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for record in records {
+            match record {
+                SinkRecord::Opportunity(event) => Self::insert_opportunity(&tx, event)?,
+                SinkRecord::Trade { ts_ms, record } => Self::insert_trade(&tx, *ts_ms, record)?,
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn insert_opportunity(conn: &Connection, event: &DecisionEvent) -> Result<()> {
+        let (decision, dry_run, success, profit_cents, reason) = match &event.decision {
+            Decision::Traded { dry_run, success, profit_cents, error } => {
+                ("traded", Some(*dry_run), Some(*success), Some(*profit_cents), error.clone())
+            }
+            Decision::BelowThreshold { reason } => ("below_threshold", None, None, None, Some(reason.clone())),
+            Decision::Vanished => ("vanished", None, None, None, None),
+            Decision::Skipped { reason } => ("skipped", None, None, None, Some(reason.clone())),
+        };
+        let inputs = &event.inputs;
+        conn.prepare_cached(
+            "INSERT INTO opportunities (ts_ms, market_id, pair_id, market_type, description, arb_type,
+                yes_price, no_price, yes_size, no_size, kalshi_fee_cents, net_profit_cents, min_profit_cents,
+                decision, dry_run, success, profit_cents, reason)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        )?.execute(params![
+            event.ts_ms as i64, event.market_id, &*event.pair_id, label(&event.market_type), &*event.description,
+            label(&event.arb_type), inputs.yes_price, inputs.no_price, inputs.yes_size, inputs.no_size,
+            inputs.kalshi_fee_cents, inputs.net_profit_cents, inputs.min_profit_cents,
+            decision, dry_run, success, profit_cents, reason,
+        ])?;
+        Ok(())
+    }
+
+    fn insert_trade(conn: &Connection, ts_ms: u64, trade: &TradeRecord) -> Result<()> {
+        conn.prepare_cached(
+            "INSERT INTO trades (ts_ms, timestamp, market, arb_type, contracts, requested_contracts, hedge_contracts,
+                limit_hit, profit_cents, success, simulated, latency_ms, unwound_contracts, cancelled_orders)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        )?.execute(params![
+            ts_ms as i64, trade.timestamp, trade.market, label(&trade.arb_type), trade.contracts,
+            trade.requested_contracts, trade.hedge_contracts, trade.limit_hit, trade.profit_cents, trade.success,
+            trade.simulated, trade.latency_ms as i64, trade.unwound_contracts, trade.cancelled_orders,
+        ])?;
+        let trade_id = conn.last_insert_rowid();
+        let mut insert_fill = conn.prepare_cached(
+            "INSERT INTO trade_fills (trade_id, platform, side, contracts, cost_cents, order_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for fill in &trade.fills {
+            insert_fill.execute(params![trade_id, fill.platform, fill.side, fill.contracts, fill.cost_cents, fill.order_id])?;
+        }
+        Ok(())
+    }
+}
+
+/// Queue into the writer thread
+#[derive(Clone)]
+pub struct SqliteSender {
+    tx: mpsc::Sender<SinkRecord>,
+}
+
+impl SqliteSender {
+    fn queue(&self, record: SinkRecord) {
+        if self.tx.try_send(record).is_err() {
+            debug!("[SQLITE] Queue full, row dropped");
+        }
+    }
+
+    pub fn opportunity(&self, event: &DecisionEvent) {
+        self.queue(SinkRecord::Opportunity(event.clone()));
+    }
+
+    pub fn trade(&self, report: &TradeReport) {
+        let now = chrono::Utc::now();
+        self.queue(SinkRecord::Trade { ts_ms: now.timestamp_millis() as u64, record: TradeRecord::new(report, now) });
+    }
+
+    /// Decision channel for `ExecutionEngine::with_event_log` feeding both
+    /// this database and `events` (the NDJSON log), if enabled
+    pub fn tee(&self, events: Option<EventSender>) -> EventSender {
+        let (tx, mut rx) = mpsc::channel::<DecisionEvent>(1024);
+        let sink = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                sink.opportunity(&event);
+                if let Some(events) = &events {
+                    let _ = events.try_send(event);
+                }
+            }
+        });
+        tx
+    }
+}
+
+/// Start the writer thread, or `None` when the sink is disabled or the
+/// database can't be opened
+pub fn spawn(config: SqliteConfig) -> Option<SqliteSender> {
+    let path = config.path?;
+    let mut sink = match SqliteSink::open(Path::new(&path)) {
+        Ok(sink) => sink,
+        Err(e) => {
+            warn!("[SQLITE] Sink disabled: {:#}", e);
+            return None;
+        }
+    };
+    info!("[SQLITE] Writing opportunities and trades to {} (up to {} rows per transaction)", path, config.batch_size);
+
+    let (tx, mut rx) = mpsc::channel::<SinkRecord>(config.queue_size);
+    let batch_size = config.batch_size;
+    std::thread::Builder::new()
+        .name("sqlite-sink".into())
+        .spawn(move || {
+            let mut batch = Vec::with_capacity(batch_size);
+            // Whatever queued up while the last batch was written goes in the next one
+            while let Some(record) = rx.blocking_recv() {
+                batch.push(record);
+                while batch.len() < batch_size {
+                    match rx.try_recv() {
+                        Ok(record) => batch.push(record),
+                        Err(_) => break,
+                    }
+                }
+                if let Err(e) = sink.write_batch(&batch) {
+                    warn!("[SQLITE] {} row(s) lost: {:#}", batch.len(), e);
+                }
+                batch.clear();
+            }
+        })
+        .map_err(|e| warn!("[SQLITE] Sink disabled: {}", e))
+        .ok()?;
+    Some(SqliteSender { tx })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_log::DecisionInputs;
+    use crate::execution::TradeFill;
+    use crate::types::{ArbType, MarketType};
+
+    fn opportunity(ts_ms: u64, pair_id: &str, decision: Decision) -> SinkRecord {
+        SinkRecord::Opportunity(DecisionEvent {
+            ts_ms,
+            market_id: 3,
+            pair_id: pair_id.into(),
+            market_type: MarketType::Moneyline,
+            description: "Chelsea vs Aston Villa".into(),
+            arb_type: ArbType::PolyYesKalshiNo,
+            inputs: DecisionInputs {
+                yes_price: 40, no_price: 55, yes_size: 1000, no_size: 800,
+                kalshi_fee_cents: 2, net_profit_cents: 2.5, min_profit_cents: 1.0,
+            },
+            decision,
+        })
+    }
+
+    #[test]
+    fn test_batch_written_to_indexed_tables() {
+        let mut sink = SqliteSink::in_memory().unwrap();
+        let trade = TradeRecord {
+            timestamp: "2025-12-27T15:00:00+00:00".into(),
+            market: "Chelsea vs Aston Villa".into(),
+            arb_type: ArbType::PolyYesKalshiNo,
+            contracts: 10,
+            requested_contracts: 12,
+            hedge_contracts: Some(10),
+            limit_hit: None,
+            profit_cents: 25,
+            success: true,
+            simulated: false,
+            latency_ms: 180,
+            unwound_contracts: 0,
+            cancelled_orders: 0,
+            fills: vec![
+                TradeFill { platform: "polymarket", side: "yes".into(), contracts: 10, cost_cents: 400, order_id: "0xabc".into() },
+                TradeFill { platform: "kalshi", side: "no".into(), contracts: 10, cost_cents: 550, order_id: "k-1".into() },
+            ],
+        };
+        sink.write_batch(&[
+            opportunity(1_000, "epl-che-avl", Decision::Traded { dry_run: false, success: true, profit_cents: 25, error: None }),
+            opportunity(2_000, "epl-che-avl", Decision::Skipped { reason: "Trade capital too small".into() }),
+            opportunity(3_000, "epl-ars-bri", Decision::Vanished),
+            SinkRecord::Trade { ts_ms: 1_200, record: trade },
+        ]).unwrap();
+
+        let conn = sink.connection();
+        let rows: Vec<(String, Option<String>)> = conn
+            .prepare("SELECT decision, reason FROM opportunities WHERE pair_id = 'epl-che-avl' ORDER BY ts_ms").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(rows, [("traded".to_string(), None), ("skipped".to_string(), Some("Trade capital too small".to_string()))]);
+        let arb_type: String = conn.query_row("SELECT arb_type FROM opportunities LIMIT 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(arb_type, label(&ArbType::PolyYesKalshiNo));
+
+        let (trade_id, profit): (i64, i64) = conn
+            .query_row("SELECT id, profit_cents FROM trades WHERE market = 'Chelsea vs Aston Villa'", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!(profit, 25);
+        let cost: i64 = conn
+            .query_row("SELECT SUM(cost_cents) FROM trade_fills WHERE trade_id = ?1", [trade_id], |r| r.get(0))
+            .unwrap();
+        assert_eq!(cost, 950);
+
+        let indexes: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'index' AND name LIKE 'idx_%' ORDER BY name").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(indexes, ["idx_opportunities_pair_ts", "idx_opportunities_ts", "idx_trade_fills_trade", "idx_trades_market_ts", "idx_trades_ts"]);
+    }
+}
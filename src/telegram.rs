@@ -5,35 +5,499 @@
 
 use anyhow::Result;
 use reqwest::Client;
-use serde::Serialize;
-use std::sync::Arc;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use crate::config::{DeliveryAlertConfig, HttpConfig};
+use crate::execution::{ApprovalDecision, ApprovalRequest, ApprovalSender};
+use crate::latency::LatencyStats;
+use crate::types::{Platform, Price};
+
+/// Long-poll timeout passed to `getUpdates` (seconds)
+const LONG_POLL_TIMEOUT_SECS: u64 = 30;
+
+/// Telegram's limit on photo captions (characters after parsing markup)
+const MAX_CAPTION_CHARS: usize = 1024;
+
+/// Minimum spacing between two queued notifications.
+/// Throttling is handled by honouring `retry_after` on 429 responses.
+const MIN_SEND_INTERVAL_MS: u64 = 50;
+
+/// Fewer sends than this in the window are too few to judge delivery by
+const MIN_DELIVERY_SAMPLES: usize = 5;
+
+/// Recent send outcomes kept for the failure rate
+const MAX_DELIVERY_SAMPLES: usize = 1000;
+
+/// Formatting mode used for outgoing messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    #[default]
+    Html,
+    MarkdownV2,
+    /// Plain text, no markup
+    None,
+}
+
+impl ParseMode {
+    /// Parse from a config string (case-insensitive)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "html" => Some(Self::Html),
+            "markdownv2" | "markdown" => Some(Self::MarkdownV2),
+            "none" | "plain" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Value of the Bot API `parse_mode` field (omitted for plain text)
+    fn api_value(self) -> Option<&'static str> {
+        match self {
+            Self::Html => Some("HTML"),
+            Self::MarkdownV2 => Some("MarkdownV2"),
+            Self::None => None,
+        }
+    }
+}
+
+/// Notification importance, used to filter what reaches the chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Severity {
+    #[default]
+    Info,
+    Important,
+    Critical,
+}
+
+impl Severity {
+    /// Parse from a config string (case-insensitive)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "info" => Some(Self::Info),
+            "important" => Some(Self::Important),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Language of notification texts. Emoji, layout and number formats are shared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    Fr,
+    En,
+}
+
+impl Language {
+    /// Parse from a config string (case-insensitive)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "fr" | "french" => Some(Self::Fr),
+            "en" | "english" => Some(Self::En),
+            _ => None,
+        }
+    }
+
+    /// Text for this language
+    fn pick<'a>(self, fr: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Self::Fr => fr,
+            Self::En => en,
+        }
+    }
+}
+
+/// Unit of profit amounts in messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfitUnit {
+    Cents,
+    Dollars,
+}
+
+impl ProfitUnit {
+    /// Parse from a config string (case-insensitive)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "cents" | "c" | "¢" => Some(Self::Cents),
+            "dollars" | "usd" | "$" => Some(Self::Dollars),
+            _ => None,
+        }
+    }
+}
+
+/// How profit amounts and returns are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProfitFormat {
+    /// None keeps each message's own unit: cents per trade or opportunity,
+    /// dollars for totals
+    pub unit: Option<ProfitUnit>,
+    /// Decimals of amounts and percentages; None is 0 for cents and 2 for
+    /// dollars and percentages
+    pub decimals: Option<usize>,
+}
+
+impl ProfitFormat {
+    /// `cents`, in `default` unless a unit is configured
+    fn amount(self, cents: f64, default: ProfitUnit, signed: bool) -> String {
+        match self.unit.unwrap_or(default) {
+            ProfitUnit::Cents if signed => format!("{:+.*}¢", self.decimals.unwrap_or(0), cents),
+            ProfitUnit::Cents => format!("{:.*}¢", self.decimals.unwrap_or(0), cents),
+            ProfitUnit::Dollars if signed => format!("${:+.*}", self.decimals.unwrap_or(2), cents / 100.0),
+            ProfitUnit::Dollars => format!("${:.*}", self.decimals.unwrap_or(2), cents / 100.0),
+        }
+    }
+
+    fn pct(self, pct: f64) -> String {
+        format!("{:.*}%", self.decimals.unwrap_or(2), pct)
+    }
+}
+
+/// Return of `profit_cents` per contract on the capital one contract of each
+/// leg ties up, in percent (0 for a free pair)
+fn return_pct(profit_cents: f64, yes_price: Price, no_price: Price) -> f64 {
+    yes_price.checked_add(no_price)
+        .filter(|cost| *cost > Price::ZERO)
+        .map_or(0.0, |cost| profit_cents / cost.cents() as f64 * 100.0)
+}
+
+/// Placeholders each notification kind fills in a user template, besides
+/// `{time}` which every template can use
+const TEMPLATE_PLACEHOLDERS: &[(&str, &[&str])] = &[
+    ("bot_started", &["mode", "markets"]),
+    ("opportunity_detected", &["market", "yes_price", "no_price", "profit_cents", "profit_pct", "arb_type",
+                               "executable_profit_cents", "contracts", "confidence"]),
+    ("trade_executed", &["market", "status", "contracts", "requested_contracts", "profit_cents", "latency_ms", "simulated"]),
+    ("position_settled", &["market", "outcome", "contracts", "realized_pnl"]),
+    ("trailing_stop", &["market", "leg", "high_water", "mark", "contracts", "closed", "realized_pnl"]),
+    ("warmup_complete", &["ready", "total", "elapsed_secs"]),
+    ("no_opportunities", &["idle_min", "markets"]),
+    ("market_stale", &["market", "ticks", "silent_secs"]),
+    ("feed_down", &["platform", "down_secs"]),
+    ("platform_degraded", &["platform", "status", "unmanaged"]),
+    ("high_latency", &["platform", "p50_ms", "p95_ms", "p99_ms", "threshold_ms"]),
+    ("error", &["message"]),
+    ("bot_stopped", &["reason"]),
+    ("circuit_breaker_tripped", &["reason", "cooldown_secs"]),
+    ("circuit_breaker_reset", &[]),
+    ("daily_loss_limit", &["loss_cents", "limit_cents", "resume_at"]),
+];
+
+/// Piece of a message template
+#[derive(Debug, PartialEq, Eq)]
+enum TemplatePart<'a> {
+    Text(String),
+    Field(&'a str),
+}
+
+/// Split `template` into text and `{name}` placeholders; `{{` and `}}` are
+/// literal braces
+fn template_parts(template: &str) -> std::result::Result<Vec<TemplatePart<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|&(_, c)| c == '{').is_some() => text.push('{'),
+            '}' if chars.next_if(|&(_, c)| c == '}').is_some() => text.push('}'),
+            '{' => {
+                let len = template[i + 1..].find('}').ok_or_else(|| format!("unclosed '{{' at {}", i))?;
+                while chars.next_if(|&(j, _)| j <= i + 1 + len).is_some() {}
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(TemplatePart::Field(template[i + 1..i + 1 + len].trim()));
+            }
+            '}' => return Err(format!("unmatched '}}' at {} (write '}}}}' for a brace)", i)),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    Ok(parts)
+}
+
+/// Why `template` can't be used for `kind`, None if it can
+fn template_error(kind: &str, template: &str) -> Option<String> {
+    let known = TEMPLATE_PLACEHOLDERS.iter().find(|(k, _)| *k == kind)?.1;
+    let unknown = match template_parts(template) {
+        Ok(parts) => parts.into_iter()
+            .filter_map(|part| match part {
+                TemplatePart::Field(name) if name != "time" && !known.contains(&name) => Some(name),
+                _ => None,
+            })
+            .collect::<Vec<_>>(),
+        Err(e) => return Some(e),
+    };
+    (!unknown.is_empty()).then(|| {
+        let mut valid = known.to_vec();
+        valid.push("time");
+        format!("unknown placeholder {{{}}} (valid: {})", unknown.join("}, {"), valid.join(", "))
+    })
+}
+
+/// Variable holding the template of a notification kind
+fn template_var(kind: &str) -> String {
+    format!("TELEGRAM_TEMPLATE_{}", kind.to_uppercase())
+}
+
+/// Every invalid `TELEGRAM_TEMPLATE_<KIND>` seen through `get`, checked at
+/// startup so a bad placeholder doesn't surface at send time
+pub fn template_errors(get: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    TEMPLATE_PLACEHOLDERS.iter()
+        .filter_map(|(kind, _)| {
+            let key = template_var(kind);
+            let template = get(&key)?;
+            template_error(kind, &template).map(|e| format!("{}={:?}: {}", key, template, e))
+        })
+        .collect()
+}
+
+/// User formats per notification kind (`TELEGRAM_TEMPLATE_<KIND>`), sent
+/// instead of the built-in text
+#[derive(Debug, Clone, Default)]
+pub struct MessageTemplates(HashMap<&'static str, String>);
+
+impl MessageTemplates {
+    /// Templates set through `var`; invalid ones are skipped with a warning
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut templates = HashMap::new();
+        for (kind, _) in TEMPLATE_PLACEHOLDERS {
+            let Some(template) = var(&template_var(kind)).filter(|t| !t.trim().is_empty()) else { continue };
+            match template_error(kind, &template) {
+                Some(e) => warn!("[TELEGRAM] Ignoring {}: {}", template_var(kind), e),
+                None => {
+                    templates.insert(*kind, template);
+                }
+            }
+        }
+        Self(templates)
+    }
+
+    fn get(&self, kind: &str) -> Option<&str> {
+        self.0.get(kind).map(String::as_str)
+    }
+}
 
 /// Telegram Bot configuration
 #[derive(Clone)]
 pub struct TelegramConfig {
     pub bot_token: String,
-    pub chat_id: String,
+    /// Destination chats (comma-separated in `TELEGRAM_CHAT_ID`)
+    pub chat_ids: Vec<String>,
     pub enabled: bool,
+    /// Number of retries after the first attempt when Telegram rate-limits us
+    pub max_retries: u32,
+    /// Backoff used when a 429 response carries no usable `retry_after`
+    pub retry_backoff_ms: u64,
+    pub parse_mode: ParseMode,
+    /// Notifications below this severity are dropped
+    pub min_severity: Severity,
+    /// Window for coalescing opportunity notifications into one digest (0 = disabled)
+    pub batch_window_ms: u64,
+    pub language: Language,
+    /// Attach a cumulative profit chart to status updates (feature `charts`)
+    #[cfg_attr(not(feature = "charts"), allow(dead_code))]
+    pub status_chart: bool,
+    /// Minimum spacing per notification kind, see `NotificationThrottle`
+    pub throttle: HashMap<&'static str, Duration>,
+    pub profit_format: ProfitFormat,
+    /// ASCII only: no emoji, no markup and no `parse_mode`, for gateways
+    /// (SMS...) that mangle them
+    pub plain_text: bool,
+    pub templates: MessageTemplates,
+}
+
+/// Default `TELEGRAM_THROTTLE`: one alert per market per minute
+const DEFAULT_THROTTLE: &str = "opportunity_detected=60,trailing_stop=60";
+
+/// `kind=secs` pairs, comma-separated (`status_update=3600,opportunity_detected=60`).
+/// Critical kinds and unknown names are ignored.
+fn parse_throttle(s: &str) -> HashMap<&'static str, Duration> {
+    let mut intervals = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once('=').and_then(|(kind, secs)| {
+            let kind = TelegramNotification::THROTTLED_KINDS.iter().find(|k| k.eq_ignore_ascii_case(kind.trim()))?;
+            Some((*kind, Duration::from_secs(secs.trim().parse().ok()?)))
+        });
+        match parsed {
+            Some((kind, interval)) => {
+                intervals.insert(kind, interval);
+            }
+            None => warn!("[TELEGRAM] Ignoring TELEGRAM_THROTTLE entry {:?} (expected <kind>=<secs>, kind one of {})",
+                          entry, TelegramNotification::THROTTLED_KINDS.join(", ")),
+        }
+    }
+    intervals
+}
+
+/// Comma-separated chat IDs
+fn parse_chat_ids(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
 
 impl TelegramConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Option<Self> {
         let bot_token = std::env::var("TELEGRAM_BOT_TOKEN").ok()?;
-        let chat_id = std::env::var("TELEGRAM_CHAT_ID").ok()?;
+        let chat_ids = parse_chat_ids(&std::env::var("TELEGRAM_CHAT_ID").ok()?);
+        if chat_ids.is_empty() {
+            return None;
+        }
         let enabled = std::env::var("TELEGRAM_ENABLED")
             .map(|v| v == "1" || v.to_lowercase() == "true")
             .unwrap_or(true);
+        let max_retries = std::env::var("TELEGRAM_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+        let retry_backoff_ms = std::env::var("TELEGRAM_RETRY_BACKOFF_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1000);
+        let parse_mode = std::env::var("TELEGRAM_PARSE_MODE")
+            .ok()
+            .and_then(|v| ParseMode::parse(&v))
+            .unwrap_or_default();
+        let min_severity = std::env::var("TELEGRAM_MIN_SEVERITY")
+            .ok()
+            .and_then(|v| Severity::parse(&v))
+            .unwrap_or_default();
+
+        let batch_window_ms = std::env::var("TELEGRAM_BATCH_WINDOW_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let language = std::env::var("TELEGRAM_LANGUAGE")
+            .ok()
+            .and_then(|v| Language::parse(&v))
+            .unwrap_or_default();
+        let status_chart = std::env::var("TELEGRAM_STATUS_CHART")
+            .map(|v| v == "1" || v.to_lowercase() == "true")
+            .unwrap_or(false);
+        let profit_format = ProfitFormat {
+            unit: std::env::var("TELEGRAM_PROFIT_UNIT").ok().and_then(|v| ProfitUnit::parse(&v)),
+            decimals: std::env::var("TELEGRAM_PROFIT_DECIMALS").ok().and_then(|v| v.parse().ok()).map(|d: usize| d.min(6)),
+        };
+        let plain_text = std::env::var("TELEGRAM_PLAIN_TEXT")
+            .map(|v| v == "1" || v.to_lowercase() == "true")
+            .unwrap_or(false);
+        if status_chart && !cfg!(feature = "charts") {
+            warn!("[TELEGRAM] TELEGRAM_STATUS_CHART ignored: built without the `charts` feature");
+        }
 
         Some(Self {
             bot_token,
-            chat_id,
+            chat_ids,
             enabled,
+            max_retries,
+            retry_backoff_ms,
+            parse_mode,
+            min_severity,
+            batch_window_ms,
+            language,
+            status_chart: status_chart && cfg!(feature = "charts"),
+            throttle: parse_throttle(&std::env::var("TELEGRAM_THROTTLE").unwrap_or_else(|_| DEFAULT_THROTTLE.into())),
+            profit_format,
+            plain_text,
+            templates: MessageTemplates::from_vars(|key| std::env::var(key).ok()),
         })
     }
+
+    /// Formatting actually applied: plain text overrides `parse_mode`
+    fn message_mode(&self) -> ParseMode {
+        if self.plain_text { ParseMode::None } else { self.parse_mode }
+    }
+
+    /// Per-category destinations from `TELEGRAM_<CATEGORY>_BOT_TOKEN` and
+    /// `TELEGRAM_<CATEGORY>_CHAT_ID` (e.g. `TELEGRAM_OPPORTUNITY_CHAT_ID`).
+    /// A missing token or chat falls back to this config's; all other
+    /// settings are shared.
+    pub fn routes_from_env(&self) -> Vec<TelegramRoute> {
+        self.routes_from(|key| std::env::var(key).ok())
+    }
+
+    /// Routes read through `var`. Categories sent to the same token and chats
+    /// share one bot; ones matching this config are left to the default bot.
+    fn routes_from(&self, var: impl Fn(&str) -> Option<String>) -> Vec<TelegramRoute> {
+        let mut routes: Vec<TelegramRoute> = Vec::new();
+        for category in NotificationCategory::ALL {
+            let name = category.env_name();
+            let token = var(&format!("TELEGRAM_{}_BOT_TOKEN", name)).filter(|t| !t.trim().is_empty());
+            let chat_ids = var(&format!("TELEGRAM_{}_CHAT_ID", name))
+                .map(|v| parse_chat_ids(&v))
+                .filter(|ids| !ids.is_empty());
+            if token.is_none() && chat_ids.is_none() {
+                continue;
+            }
+            let bot_token = token.map(|t| t.trim().to_string()).unwrap_or_else(|| self.bot_token.clone());
+            let chat_ids = chat_ids.unwrap_or_else(|| self.chat_ids.clone());
+            if bot_token == self.bot_token && chat_ids == self.chat_ids {
+                continue;
+            }
+
+            match routes.iter_mut().find(|r| r.config.bot_token == bot_token && r.config.chat_ids == chat_ids) {
+                Some(route) => route.categories.push(category),
+                None => routes.push(TelegramRoute {
+                    categories: vec![category],
+                    config: Self { bot_token, chat_ids, ..self.clone() },
+                }),
+            }
+        }
+        routes
+    }
+}
+
+/// A bot and chats receiving some notification categories instead of the default bot
+#[derive(Clone)]
+pub struct TelegramRoute {
+    pub categories: Vec<NotificationCategory>,
+    pub config: TelegramConfig,
+}
+
+/// Notification groups that can be sent to their own bot and chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationCategory {
+    /// Detected opportunities (and their digests)
+    Opportunity,
+    /// Executed and simulated trades
+    Trade,
+    /// Settlements, reconciliation and flattening
+    Position,
+    /// Start/stop notices and status reports
+    Status,
+    /// Errors and circuit breaker transitions
+    Alert,
+    /// Replies to remote commands
+    Command,
+}
+
+impl NotificationCategory {
+    pub const ALL: [Self; 6] = [Self::Opportunity, Self::Trade, Self::Position, Self::Status, Self::Alert, Self::Command];
+
+    /// Name used in the route environment variables
+    pub fn env_name(self) -> &'static str {
+        match self {
+            Self::Opportunity => "OPPORTUNITY",
+            Self::Trade => "TRADE",
+            Self::Position => "POSITION",
+            Self::Status => "STATUS",
+            Self::Alert => "ALERT",
+            Self::Command => "COMMAND",
+        }
+    }
 }
 
 /// Types of notifications that can be sent
@@ -47,26 +511,83 @@ pub enum TelegramNotification {
     /// Arbitrage opportunity detected
     OpportunityDetected {
         market: String,
-        yes_price: u16,
-        no_price: u16,
+        yes_price: Price,
+        no_price: Price,
         profit_cents: i16,
         arb_type: String,
+        /// Net profit times the contracts both books can fill, in cents
+        executable_profit_cents: i64,
+        contracts: i64,
+        /// Liquidity confidence, 0-1
+        confidence: f64,
     },
     /// Trade executed
     TradeExecuted {
         market: String,
         contracts: i64,
+        /// Intended size; differs from `contracts` when sizing was reduced
+        requested_contracts: i64,
+        /// Size sent on every leg
+        hedge_contracts: Option<i64>,
+        /// Position limit that trimmed the order
+        limit_hit: Option<String>,
         profit_cents: i16,
         success: bool,
         latency_ms: u64,
+        /// Paper trade from dry-run mode
+        simulated: bool,
+        /// Excess contracts of a partial fill sold back (loss included in profit)
+        unwound_contracts: i64,
+        /// Legs cancelled after resting past their time-to-live
+        cancelled_orders: u32,
+    },
+    /// Position closed out by market resolution
+    PositionSettled {
+        market: String,
+        yes_won: bool,
+        contracts: f64,
+        realized_pnl: f64,
     },
     /// Periodic status update
     StatusUpdate {
         uptime_hours: f64,
         total_trades: u64,
         successful_trades: u64,
+        /// Orders cancelled at their time-to-live (not counted as trades)
+        cancelled_orders: u64,
+        /// Trades skipped because approval was rejected or timed out
+        declined_trades: u64,
         total_profit_cents: i64,
+        /// Live trades and profit of the current trading day
+        today_trades: u64,
+        today_successful_trades: u64,
+        today_profit_cents: i64,
         markets_monitored: usize,
+        /// Paper trading totals (dry-run mode)
+        paper_trades: u64,
+        paper_profit_cents: i64,
+        /// Most profitable markets (market, profit in cents)
+        top_markets: Vec<(String, i64)>,
+        /// Market metadata cache counters
+        cache_hits: u64,
+        cache_misses: u64,
+        /// Mark-to-market P&L of open positions (dollars)
+        unrealized_pnl: f64,
+        open_positions: usize,
+        /// Positions marked at a last known price because a leg has no bid
+        stale_positions: usize,
+        /// MAX_OPEN_POSITIONS, if set
+        max_open_positions: Option<usize>,
+        /// Telegram notifications delivered and failed since startup
+        telegram_sent: u64,
+        telegram_failed: u64,
+        /// Cumulative profit after each trade (unix seconds, cents) for the chart
+        #[cfg_attr(not(feature = "charts"), allow(dead_code))]
+        profit_history: Vec<(i64, i64)>,
+        /// REST round-trip percentiles per platform
+        latency: Vec<(Platform, LatencyStats)>,
+        /// Heartbeat report, edited in place (false for /status replies)
+        periodic: bool,
     },
     /// Error occurred
     Error {
@@ -76,6 +597,389 @@ pub enum TelegramNotification {
     BotStopped {
         reason: String,
     },
+    /// Circuit breaker opened and trading halted
+    CircuitBreakerTripped {
+        reason: String,
+        cooldown_secs: u64,
+    },
+    /// Circuit breaker closed and trading resumed
+    CircuitBreakerReset,
+    /// Realized loss for the trading day went past the daily limit; trading halted
+    DailyLossLimitHit {
+        loss_cents: i64,
+        limit_cents: i64,
+        /// When trading resumes (start of the next trading day)
+        resume_at: chrono::DateTime<chrono::Utc>,
+    },
+    /// A platform's p95 REST latency went over the alert threshold
+    HighLatency {
+        platform: Platform,
+        stats: LatencyStats,
+        threshold_ms: u64,
+    },
+    /// Startup warmup over: trading enabled
+    WarmupComplete {
+        /// Markets with all their books in, out of `total`
+        ready: usize,
+        total: usize,
+        elapsed_secs: f64,
+        /// Enabled by the timeout with books still missing
+        timed_out: bool,
+    },
+    /// No opportunity detected on any market for a while
+    NoOpportunities {
+        idle_secs: u64,
+        markets: usize,
+    },
+    /// A market's books stopped updating for `MAX_STALE_TICKS` cycles:
+    /// it is no longer traded until they do
+    MarketStale {
+        market: String,
+        ticks: u32,
+        silent_secs: u64,
+    },
+    /// A WebSocket feed has been down past the alert delay
+    FeedDown {
+        platform: Platform,
+        down_secs: u64,
+    },
+    /// A platform's API stopped answering (or came back): its pairs are
+    /// skipped meanwhile and its positions are left unmanaged
+    PlatformDegraded {
+        platform: Platform,
+        down: bool,
+        /// Open positions with a leg on the platform
+        unmanaged: Vec<String>,
+    },
+    /// Open positions summary (reply to /positions)
+    PositionsReport {
+        open_positions: usize,
+        total_contracts: f64,
+        total_cost_basis: f64,
+        guaranteed_profit: f64,
+        unmatched_exposure: f64,
+        realized_pnl: f64,
+    },
+    /// Startup reconciliation of persisted positions against the exchanges
+    PositionsReconciled {
+        matched: usize,
+        adopted: Vec<String>,
+        corrected: Vec<String>,
+        dropped: Vec<String>,
+        unconfirmed: Vec<String>,
+    },
+    /// Outcome of the /flatten kill switch (entries are "market platform/side closed/held")
+    PositionsFlattened {
+        closed: Vec<String>,
+        failed: Vec<String>,
+        realized_pnl: f64,
+        /// Dry run: nothing was sent
+        simulated: bool,
+    },
+    /// Directional inventory sold after its bid retraced past the trailing stop
+    TrailingStopFired {
+        market: String,
+        /// "platform/side"
+        leg: String,
+        /// High-water mark and bid that fired the stop, in dollars
+        high_water: f64,
+        mark: f64,
+        contracts: f64,
+        closed: f64,
+        realized_pnl: f64,
+        error: Option<String>,
+        simulated: bool,
+    },
+    /// Realized P&L over a window (reply to /pnl)
+    PnlReport {
+        window: PnlWindow,
+        stats: DailyStats,
+    },
+    /// Acknowledgement of a remote command (/pause, /resume, /flatten)
+    CommandReply {
+        command: BotCommand,
+    },
+    /// Reply to /disable, /enable and /disabled
+    DisabledMarkets {
+        command: BotCommand,
+        /// The command changed the set
+        changed: bool,
+        /// Monitored pairs the command's market names
+        matched: usize,
+        /// Disabled keys after the command
+        markets: Vec<String>,
+    },
+}
+
+impl TelegramNotification {
+    /// Severity used for filtering
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::OpportunityDetected { .. } => Severity::Info,
+            Self::BotStarted { .. }
+            | Self::TradeExecuted { .. }
+            | Self::PositionSettled { .. }
+            | Self::TrailingStopFired { .. }
+            | Self::StatusUpdate { periodic: true, .. }
+            | Self::WarmupComplete { .. }
+            | Self::PositionsReconciled { .. }
+            | Self::HighLatency { .. }
+            | Self::NoOpportunities { .. }
+            | Self::MarketStale { .. }
+            | Self::CircuitBreakerReset => Severity::Important,
+            Self::Error { .. }
+            | Self::BotStopped { .. }
+            | Self::CircuitBreakerTripped { .. }
+            | Self::DailyLossLimitHit { .. }
+            | Self::FeedDown { .. }
+            | Self::PlatformDegraded { .. }
+            | Self::PositionsFlattened { .. } => Severity::Critical,
+            // Replies to explicit commands (/status included) are never filtered
+            Self::StatusUpdate { periodic: false, .. }
+            | Self::PositionsReport { .. }
+            | Self::PnlReport { .. }
+            | Self::CommandReply { .. }
+            | Self::DisabledMarkets { .. } => Severity::Critical,
+        }
+    }
+
+    /// Kinds `TELEGRAM_THROTTLE` can space out; critical ones always go out at once
+    pub const THROTTLED_KINDS: &'static [&'static str] = &[
+        "bot_started", "opportunity_detected", "trade_executed", "position_settled", "trailing_stop", "status_update",
+        "positions_reconciled", "high_latency", "no_opportunities", "market_stale", "circuit_breaker_reset",
+    ];
+
+    /// Throttle kind and subject (the market for opportunities and trades),
+    /// or None if never throttled
+    pub fn throttle_key(&self) -> Option<(&'static str, &str)> {
+        match self {
+            Self::BotStarted { .. } => Some(("bot_started", "")),
+            Self::OpportunityDetected { market, .. } => Some(("opportunity_detected", market)),
+            Self::TradeExecuted { market, .. } => Some(("trade_executed", market)),
+            Self::PositionSettled { market, .. } => Some(("position_settled", market)),
+            // A stop that doesn't fill fires again at every check
+            Self::TrailingStopFired { market, .. } => Some(("trailing_stop", market)),
+            // Replies to /status are never held back
+            Self::StatusUpdate { periodic, .. } => periodic.then_some(("status_update", "")),
+            Self::PositionsReconciled { .. } => Some(("positions_reconciled", "")),
+            Self::HighLatency { platform: Platform::Kalshi, .. } => Some(("high_latency", "kalshi")),
+            Self::HighLatency { platform: Platform::Polymarket, .. } => Some(("high_latency", "polymarket")),
+            Self::NoOpportunities { .. } => Some(("no_opportunities", "")),
+            Self::MarketStale { market, .. } => Some(("market_stale", market)),
+            Self::CircuitBreakerReset => Some(("circuit_breaker_reset", "")),
+            Self::Error { .. }
+            | Self::BotStopped { .. }
+            | Self::WarmupComplete { .. }
+            | Self::CircuitBreakerTripped { .. }
+            | Self::DailyLossLimitHit { .. }
+            | Self::FeedDown { .. }
+            | Self::PlatformDegraded { .. }
+            | Self::PositionsFlattened { .. }
+            | Self::PositionsReport { .. }
+            | Self::PnlReport { .. }
+            | Self::CommandReply { .. }
+            | Self::DisabledMarkets { .. } => None,
+        }
+    }
+
+    /// Template kind and placeholder values (see `TEMPLATE_PLACEHOLDERS`),
+    /// or None if the kind has no template support
+    fn template_fields(&self, l: Language) -> Option<(&'static str, Vec<(&'static str, String)>)> {
+        let fields = match self {
+            Self::BotStarted { mode, markets_count } => ("bot_started", vec![
+                ("mode", mode.clone()),
+                ("markets", markets_count.to_string()),
+            ]),
+            Self::OpportunityDetected { market, yes_price, no_price, profit_cents, arb_type, executable_profit_cents, contracts, confidence } => {
+                ("opportunity_detected", vec![
+                    ("market", market.clone()),
+                    ("yes_price", yes_price.cents().to_string()),
+                    ("no_price", no_price.cents().to_string()),
+                    ("profit_cents", profit_cents.to_string()),
+                    ("profit_pct", format!("{:.2}", return_pct(*profit_cents as f64, *yes_price, *no_price))),
+                    ("arb_type", arb_type.clone()),
+                    ("executable_profit_cents", executable_profit_cents.to_string()),
+                    ("contracts", contracts.to_string()),
+                    ("confidence", format!("{:.0}", confidence * 100.0)),
+                ])
+            }
+            Self::TradeExecuted { market, contracts, requested_contracts, profit_cents, success, latency_ms, simulated, cancelled_orders, .. } => {
+                let status = match (success, *cancelled_orders > 0) {
+                    (true, _) => l.pick("SUCCÈS", "SUCCESS"),
+                    (false, true) => l.pick("EXPIRÉ", "EXPIRED"),
+                    (false, false) => l.pick("ÉCHEC", "FAILED"),
+                };
+                ("trade_executed", vec![
+                    ("market", market.clone()),
+                    ("status", status.to_string()),
+                    ("contracts", contracts.to_string()),
+                    ("requested_contracts", requested_contracts.to_string()),
+                    ("profit_cents", profit_cents.to_string()),
+                    ("latency_ms", latency_ms.to_string()),
+                    ("simulated", simulated.to_string()),
+                ])
+            }
+            Self::PositionSettled { market, yes_won, contracts, realized_pnl } => ("position_settled", vec![
+                ("market", market.clone()),
+                ("outcome", if *yes_won { "YES" } else { "NO" }.to_string()),
+                ("contracts", format!("{:.0}", contracts)),
+                ("realized_pnl", format!("{:+.2}", realized_pnl)),
+            ]),
+            Self::TrailingStopFired { market, leg, high_water, mark, contracts, closed, realized_pnl, .. } => ("trailing_stop", vec![
+                ("market", market.clone()),
+                ("leg", leg.clone()),
+                ("high_water", format!("{:.2}", high_water)),
+                ("mark", format!("{:.2}", mark)),
+                ("contracts", format!("{:.0}", contracts)),
+                ("closed", format!("{:.0}", closed)),
+                ("realized_pnl", format!("{:+.2}", realized_pnl)),
+            ]),
+            Self::WarmupComplete { ready, total, elapsed_secs, .. } => ("warmup_complete", vec![
+                ("ready", ready.to_string()),
+                ("total", total.to_string()),
+                ("elapsed_secs", format!("{:.1}", elapsed_secs)),
+            ]),
+            Self::NoOpportunities { idle_secs, markets } => ("no_opportunities", vec![
+                ("idle_min", (idle_secs / 60).to_string()),
+                ("markets", markets.to_string()),
+            ]),
+            Self::MarketStale { market, ticks, silent_secs } => ("market_stale", vec![
+                ("market", market.clone()),
+                ("ticks", ticks.to_string()),
+                ("silent_secs", silent_secs.to_string()),
+            ]),
+            Self::FeedDown { platform, down_secs } => ("feed_down", vec![
+                ("platform", platform.to_string()),
+                ("down_secs", down_secs.to_string()),
+            ]),
+            Self::PlatformDegraded { platform, down, unmanaged } => ("platform_degraded", vec![
+                ("platform", platform.to_string()),
+                ("status", if *down { l.pick("injoignable", "down") } else { l.pick("rétablie", "back") }.to_string()),
+                ("unmanaged", unmanaged.len().to_string()),
+            ]),
+            Self::HighLatency { platform, stats, threshold_ms } => ("high_latency", vec![
+                ("platform", platform.to_string()),
+                ("p50_ms", format!("{:.0}", stats.p50_ms)),
+                ("p95_ms", format!("{:.0}", stats.p95_ms)),
+                ("p99_ms", format!("{:.0}", stats.p99_ms)),
+                ("threshold_ms", threshold_ms.to_string()),
+            ]),
+            Self::Error { message } => ("error", vec![("message", message.clone())]),
+            Self::BotStopped { reason } => ("bot_stopped", vec![("reason", reason.clone())]),
+            Self::CircuitBreakerTripped { reason, cooldown_secs } => ("circuit_breaker_tripped", vec![
+                ("reason", reason.clone()),
+                ("cooldown_secs", cooldown_secs.to_string()),
+            ]),
+            Self::CircuitBreakerReset => ("circuit_breaker_reset", Vec::new()),
+            Self::DailyLossLimitHit { loss_cents, limit_cents, resume_at } => ("daily_loss_limit", vec![
+                ("loss_cents", loss_cents.to_string()),
+                ("limit_cents", limit_cents.to_string()),
+                ("resume_at", resume_at.format("%Y-%m-%d %H:%M UTC").to_string()),
+            ]),
+            Self::StatusUpdate { .. }
+            | Self::PositionsReport { .. }
+            | Self::PositionsReconciled { .. }
+            | Self::PositionsFlattened { .. }
+            | Self::PnlReport { .. }
+            | Self::CommandReply { .. }
+            | Self::DisabledMarkets { .. } => return None,
+        };
+        Some(fields)
+    }
+
+    /// Category used for routing to a bot
+    pub fn category(&self) -> NotificationCategory {
+        match self {
+            Self::OpportunityDetected { .. } => NotificationCategory::Opportunity,
+            Self::TradeExecuted { .. } => NotificationCategory::Trade,
+            Self::PositionSettled { .. }
+            | Self::TrailingStopFired { .. }
+            | Self::PositionsReconciled { .. }
+            | Self::PositionsFlattened { .. } => NotificationCategory::Position,
+            Self::BotStarted { .. }
+            | Self::BotStopped { .. }
+            | Self::WarmupComplete { .. }
+            | Self::StatusUpdate { .. } => NotificationCategory::Status,
+            Self::Error { .. }
+            | Self::CircuitBreakerTripped { .. }
+            | Self::DailyLossLimitHit { .. }
+            | Self::HighLatency { .. }
+            | Self::FeedDown { .. }
+            | Self::PlatformDegraded { .. }
+            | Self::NoOpportunities { .. }
+            | Self::MarketStale { .. }
+            | Self::CircuitBreakerReset => NotificationCategory::Alert,
+            Self::PositionsReport { .. }
+            | Self::PnlReport { .. }
+            | Self::CommandReply { .. }
+            | Self::DisabledMarkets { .. } => NotificationCategory::Command,
+        }
+    }
+}
+
+/// Remote control commands received via `getUpdates`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotCommand {
+    Status,
+    Pause,
+    Resume,
+    Positions,
+    /// Halt trading and close every open position
+    Flatten,
+    /// Stop trading one market, named by pair id, Kalshi ticker or Polymarket slug
+    Disable(String),
+    /// Trade a disabled market again
+    Enable(String),
+    /// List the disabled markets (also `/disable` or `/enable` without a market)
+    Disabled,
+    /// Realized P&L over a window (`/pnl`, `/pnl week`...); None for an
+    /// unknown window, answered with the usage
+    Pnl(Option<PnlWindow>),
+}
+
+/// Period queried by `/pnl`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PnlWindow {
+    /// Current trading day (from `DAILY_RESET_HOUR_UTC`)
+    Today,
+    /// Last 7 days
+    Week,
+    /// Every trade on record
+    All,
+}
+
+impl PnlWindow {
+    fn parse(arg: Option<&str>) -> Option<Self> {
+        match arg.map(str::to_lowercase).as_deref() {
+            None | Some("today") | Some("day") => Some(Self::Today),
+            Some("week") | Some("7d") => Some(Self::Week),
+            Some("all") => Some(Self::All),
+            Some(_) => None,
+        }
+    }
+}
+
+impl BotCommand {
+    /// Parse a slash command from message text, e.g. `/status` or `/status@my_bot`
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let command = words.next()?.strip_prefix('/')?;
+        let command = command.split('@').next().unwrap_or(command);
+        let arg = words.next();
+        let market = arg.map(str::to_string);
+        match command.to_lowercase().as_str() {
+            "status" => Some(Self::Status),
+            "pause" => Some(Self::Pause),
+            "resume" => Some(Self::Resume),
+            "positions" => Some(Self::Positions),
+            "flatten" => Some(Self::Flatten),
+            "disable" => Some(market.map_or(Self::Disabled, Self::Disable)),
+            "enable" => Some(market.map_or(Self::Disabled, Self::Enable)),
+            "disabled" => Some(Self::Disabled),
+            "pnl" => Some(Self::Pnl(PnlWindow::parse(arg))),
+            _ => None,
+        }
+    }
 }
 
 /// Telegram message sender
@@ -83,264 +987,2485 @@ pub enum TelegramNotification {
 pub struct TelegramBot {
     config: TelegramConfig,
     client: Client,
-    api_url: String,
+    api_base: String,
+    /// Chat → live status message edited by periodic status updates
+    status_messages: Arc<Mutex<HashMap<String, StatusMessage>>>,
+    /// Approval request id → request waiting for a button press
+    approvals: Arc<Mutex<HashMap<u64, PendingApproval>>>,
+}
+
+/// Trade approval posted to the chats, not answered yet
+struct PendingApproval {
+    reply: oneshot::Sender<ApprovalDecision>,
+    /// Text of the request, kept to append the outcome
+    text: String,
+    /// (chat, message id) of each copy of the request
+    messages: Vec<(String, i64)>,
+}
+
+/// Live status message in one chat
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct StatusMessage {
+    id: i64,
+    /// Sent as a chart photo (edited with editMessageMedia)
+    photo: bool,
+}
+
+/// PNG attachment for a multipart request
+fn png_part(png: &[u8]) -> Part {
+    Part::bytes(png.to_vec())
+        .file_name("status.png")
+        .mime_str("image/png")
+        .expect("static MIME type is valid")
 }
 
 #[derive(Serialize)]
 struct SendMessageRequest<'a> {
     chat_id: &'a str,
     text: &'a str,
-    parse_mode: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<&'a serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct AnswerCallbackQueryRequest<'a> {
+    callback_query_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct EditMessageTextRequest<'a> {
+    chat_id: &'a str,
+    message_id: i64,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct PinChatMessageRequest<'a> {
+    chat_id: &'a str,
+    message_id: i64,
+    disable_notification: bool,
+}
+
+/// `result` of a successful sendMessage / editMessageText call
+#[derive(Deserialize)]
+struct SentMessage {
+    message_id: i64,
+}
+
+/// Error payload returned by the Bot API on failure
+#[derive(Deserialize)]
+struct ErrorResponse {
+    parameters: Option<ResponseParameters>,
+}
+
+#[derive(Deserialize)]
+struct ResponseParameters {
+    retry_after: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct GetUpdatesRequest {
+    offset: i64,
+    timeout: u64,
+    allowed_updates: [&'static str; 2],
+}
+
+#[derive(Deserialize)]
+struct GetUpdatesResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+    callback_query: Option<CallbackQuery>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+/// Inline keyboard button press
+#[derive(Deserialize)]
+struct CallbackQuery {
+    id: String,
+    /// Message carrying the button
+    message: Option<Message>,
+    data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Decision and request id from approval button data (`approve:<id>` / `reject:<id>`)
+fn parse_approval_callback(data: &str) -> Option<(ApprovalDecision, u64)> {
+    let (action, id) = data.split_once(':')?;
+    let decision = match action {
+        "approve" => ApprovalDecision::Approved,
+        "reject" => ApprovalDecision::Rejected,
+        _ => return None,
+    };
+    Some((decision, id.parse().ok()?))
+}
+
+/// Extract `parameters.retry_after` (seconds) from a 429 error body
+fn parse_retry_after(body: &str) -> Option<Duration> {
+    serde_json::from_str::<ErrorResponse>(body)
+        .ok()?
+        .parameters?
+        .retry_after
+        .map(Duration::from_secs)
 }
 
 impl TelegramBot {
     /// Create a new Telegram bot instance
     pub fn new(config: TelegramConfig) -> Self {
-        let api_url = format!("https://api.telegram.org/bot{}/sendMessage", config.bot_token);
+        let api_base = format!("https://api.telegram.org/bot{}", config.bot_token);
         Self {
             config,
-            client: Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build()
-                .expect("Failed to build HTTP client"),
-            api_url,
+            client: HttpConfig::telegram_from_env().client().expect("Failed to build HTTP client"),
+            api_base,
+            status_messages: Arc::new(Mutex::new(HashMap::new())),
+            approvals: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    /// Send a message to Telegram
+    /// Send a message to every configured chat.
+    /// A failure for one chat does not stop delivery to the others.
     pub async fn send_message(&self, text: &str) -> Result<()> {
         if !self.config.enabled {
             return Ok(());
         }
 
+        let mut errors = Vec::new();
+        for chat_id in &self.config.chat_ids {
+            if let Err(e) = self.send_to_chat(chat_id, text).await {
+                errors.push(format!("{}: {}", chat_id, e));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Failed to deliver to {}/{} chats: {}",
+                          errors.len(), self.config.chat_ids.len(), errors.join("; "))
+        }
+    }
+
+    /// Send a message to one chat, returning its message id
+    async fn send_to_chat(&self, chat_id: &str, text: &str) -> Result<Option<i64>> {
+        self.send_with_markup(chat_id, text, None).await
+    }
+
+    /// Send a message with an optional `reply_markup` (inline keyboard) to one chat
+    async fn send_with_markup(&self, chat_id: &str, text: &str, reply_markup: Option<&serde_json::Value>) -> Result<Option<i64>> {
         let request = SendMessageRequest {
-            chat_id: &self.config.chat_id,
+            chat_id,
             text,
-            parse_mode: "HTML",
+            parse_mode: self.config.message_mode().api_value(),
+            reply_markup,
         };
+        let result = self.call("sendMessage", &request).await?;
+        Ok(serde_json::from_value::<SentMessage>(result).ok().map(|m| m.message_id))
+    }
 
-        let response = self.client
-            .post(&self.api_url)
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            error!("[TELEGRAM] Failed to send message: {}", error_text);
+    /// Replace the text of a message previously sent to `chat_id`
+    async fn edit_in_chat(&self, chat_id: &str, message_id: i64, text: &str) -> Result<()> {
+        let request = EditMessageTextRequest {
+            chat_id,
+            message_id,
+            text,
+            parse_mode: self.config.message_mode().api_value(),
+        };
+        match self.call("editMessageText", &request).await {
+            // Same text as before: nothing to update
+            Err(e) if e.to_string().contains("message is not modified") => Ok(()),
+            other => other.map(|_| ()),
         }
+    }
 
-        Ok(())
+    /// Send a photo with a caption to one chat, returning its message id
+    pub async fn send_photo(&self, chat_id: &str, png: &[u8], caption: &str) -> Result<Option<i64>> {
+        let parse_mode = self.config.message_mode().api_value();
+        let result = self.call_with("sendPhoto", || {
+            let mut form = Form::new()
+                .text("chat_id", chat_id.to_string())
+                .text("caption", caption.to_string())
+                .part("photo", png_part(png));
+            if let Some(mode) = parse_mode {
+                form = form.text("parse_mode", mode);
+            }
+            form
+        }).await?;
+        Ok(serde_json::from_value::<SentMessage>(result).ok().map(|m| m.message_id))
     }
 
-    /// Format and send a notification
-    pub async fn notify(&self, notification: TelegramNotification) -> Result<()> {
-        let message = self.format_notification(notification);
-        self.send_message(&message).await
+    /// Replace the photo and caption of a message previously sent to `chat_id`
+    async fn edit_photo_in_chat(&self, chat_id: &str, message_id: i64, png: &[u8], caption: &str) -> Result<()> {
+        let media = serde_json::json!({
+            "type": "photo",
+            "media": "attach://chart",
+            "caption": caption,
+            "parse_mode": self.config.message_mode().api_value(),
+        }).to_string();
+        self.call_with("editMessageMedia", || {
+            Form::new()
+                .text("chat_id", chat_id.to_string())
+                .text("message_id", message_id.to_string())
+                .text("media", media.clone())
+                .part("chart", png_part(png))
+        }).await.map(|_| ())
     }
 
-    /// Format notification into a readable message
-    fn format_notification(&self, notification: TelegramNotification) -> String {
-        match notification {
-            TelegramNotification::BotStarted { mode, markets_count } => {
-                format!(
-                    "🚀 <b>Bot Arbitrage Démarré</b>\n\n\
-                    📊 Mode: <code>{}</code>\n\
-                    🎯 Marchés surveillés: <b>{}</b>\n\
-                    ⏰ Heure: {}",
-                    mode,
-                    markets_count,
-                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-                )
+    /// Send a photo with a caption to every configured chat
+    async fn send_photo_message(&self, png: &[u8], caption: &str) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let mut errors = Vec::new();
+        for chat_id in &self.config.chat_ids {
+            if let Err(e) = self.send_photo(chat_id, png, caption).await {
+                errors.push(format!("{}: {}", chat_id, e));
             }
+        }
 
-            TelegramNotification::OpportunityDetected {
-                market,
-                yes_price,
-                no_price,
-                profit_cents,
-                arb_type,
-            } => {
-                let profit_pct = profit_cents as f64 / 100.0;
-                format!(
-                    "🎯 <b>Opportunité Détectée!</b>\n\n\
-                    📈 Marché: <code>{}</code>\n\
-                    💰 YES: {}¢ | NO: {}¢\n\
-                    💵 Profit: <b>{}¢ ({:.2}%)</b>\n\
-                    🔄 Type: {}",
-                    market,
-                    yes_price,
-                    no_price,
-                    profit_cents,
-                    profit_pct,
-                    arb_type
-                )
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Failed to deliver to {}/{} chats: {}",
+                          errors.len(), self.config.chat_ids.len(), errors.join("; "))
+        }
+    }
+
+    /// Update the live status message in every chat: edit the one sent by the
+    /// previous periodic update, or send (and pin) a new one when there is none,
+    /// it can no longer be edited (deleted, too old), or it is a text message
+    /// and the update now carries a chart (or the reverse).
+    pub async fn update_status_message(&self, text: &str, chart: Option<&[u8]>) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let mut errors = Vec::new();
+        for chat_id in &self.config.chat_ids {
+            let previous = self.status_messages.lock().unwrap_or_else(|e| e.into_inner()).get(chat_id).copied()
+                .filter(|m| m.photo == chart.is_some());
+            if let Some(StatusMessage { id, .. }) = previous {
+                let edited = match chart {
+                    Some(png) => self.edit_photo_in_chat(chat_id, id, png, text).await,
+                    None => self.edit_in_chat(chat_id, id, text).await,
+                };
+                match edited {
+                    Ok(()) => continue,
+                    Err(e) => warn!("[TELEGRAM] Could not edit status message in {}, sending a new one: {}", chat_id, e),
+                }
             }
 
-            TelegramNotification::TradeExecuted {
-                market,
-                contracts,
-                profit_cents,
-                success,
-                latency_ms,
-            } => {
-                let emoji = if success { "✅" } else { "❌" };
-                let status = if success { "SUCCÈS" } else { "ÉCHEC" };
-                format!(
-                    "{} <b>Trade {}</b>\n\n\
-                    📈 Marché: <code>{}</code>\n\
-                    📦 Contrats: <b>{}</b>\n\
-                    💵 Profit: <b>{}¢</b>\n\
-                    ⚡ Latence: {}ms",
-                    emoji,
-                    status,
-                    market,
-                    contracts,
-                    profit_cents,
-                    latency_ms
-                )
+            let sent = match chart {
+                Some(png) => self.send_photo(chat_id, png, text).await,
+                None => self.send_to_chat(chat_id, text).await,
+            };
+            match sent {
+                Ok(Some(id)) => {
+                    let message = StatusMessage { id, photo: chart.is_some() };
+                    self.status_messages.lock().unwrap_or_else(|e| e.into_inner()).insert(chat_id.clone(), message);
+                    let pin = PinChatMessageRequest { chat_id, message_id: id, disable_notification: true };
+                    if let Err(e) = self.call("pinChatMessage", &pin).await {
+                        debug!("[TELEGRAM] Could not pin status message in {}: {}", chat_id, e);
+                    }
+                }
+                Ok(None) => {
+                    self.status_messages.lock().unwrap_or_else(|e| e.into_inner()).remove(chat_id);
+                }
+                Err(e) => errors.push(format!("{}: {}", chat_id, e)),
             }
+        }
 
-            TelegramNotification::StatusUpdate {
-                uptime_hours,
-                total_trades,
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Failed to update status in {}/{} chats: {}",
+                          errors.len(), self.config.chat_ids.len(), errors.join("; "))
+        }
+    }
+
+    /// Call a Bot API method with a JSON body and return its `result`
+    async fn call<R: Serialize>(&self, method: &str, request: &R) -> Result<serde_json::Value> {
+        let url = format!("{}/{}", self.api_base, method);
+        self.send_with_retry(|| self.client.post(&url).json(request)).await
+    }
+
+    /// Call a Bot API method with a multipart body built by `form` (once per attempt)
+    async fn call_with(&self, method: &str, form: impl Fn() -> Form) -> Result<serde_json::Value> {
+        let url = format!("{}/{}", self.api_base, method);
+        self.send_with_retry(|| self.client.post(&url).multipart(form())).await
+    }
+
+    /// Send the request built by `request` and return the response's `result`,
+    /// retrying on 429 after the advertised `retry_after`
+    async fn send_with_retry(&self, request: impl Fn() -> reqwest::RequestBuilder) -> Result<serde_json::Value> {
+        let mut attempt = 0u32;
+        loop {
+            let response = request().send().await?;
+
+            let status = response.status();
+            if status.is_success() {
+                let body: serde_json::Value = response.json().await.unwrap_or_default();
+                return Ok(body["result"].clone());
+            }
+
+            let error_text = response.text().await.unwrap_or_default();
+
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < self.config.max_retries {
+                let delay = parse_retry_after(&error_text)
+                    .unwrap_or_else(|| Duration::from_millis(self.config.retry_backoff_ms));
+                attempt += 1;
+                warn!("[TELEGRAM] Rate limited, retrying in {:?} (attempt {}/{})",
+                      delay, attempt, self.config.max_retries);
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            anyhow::bail!("Telegram API error {}: {}", status, error_text);
+        }
+    }
+
+    /// Spawn a long-polling `getUpdates` loop and return the parsed commands.
+    /// Only messages from the configured chats are accepted.
+    pub fn poll_commands(&self) -> mpsc::Receiver<BotCommand> {
+        let (tx, rx) = mpsc::channel::<BotCommand>(16);
+        let bot = self.clone();
+
+        tokio::spawn(async move {
+            let mut offset = 0i64;
+            loop {
+                let updates = match bot.get_updates(offset).await {
+                    Ok(updates) => updates,
+                    Err(e) => {
+                        warn!("[TELEGRAM] getUpdates failed: {}", e);
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+                };
+
+                for update in updates {
+                    offset = offset.max(update.update_id + 1);
+                    if let Some(query) = update.callback_query {
+                        bot.handle_callback(query).await;
+                        continue;
+                    }
+                    let Some(message) = update.message else { continue };
+                    let chat_id = message.chat.id.to_string();
+                    if !bot.config.chat_ids.contains(&chat_id) {
+                        warn!("[TELEGRAM] Ignoring message from unauthorized chat {}", chat_id);
+                        continue;
+                    }
+                    if let Some(command) = message.text.as_deref().and_then(BotCommand::parse) {
+                        info!("[TELEGRAM] Received command {:?} from chat {}", command, chat_id);
+                        if tx.send(command).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Serve trade approval requests from the execution engine: each one is
+    /// posted with Approve/Reject buttons to every chat and the first press
+    /// decides. Presses arrive through the `poll_commands` loop, which must be running.
+    pub fn serve_approvals(&self) -> ApprovalSender {
+        let (tx, mut rx) = mpsc::channel::<ApprovalRequest>(8);
+        let bot = self.clone();
+        tokio::spawn(async move {
+            while let Some(request) = rx.recv().await {
+                let bot = bot.clone();
+                tokio::spawn(async move { bot.request_approval(request).await });
+            }
+        });
+        tx
+    }
+
+    /// Post one approval request and settle it as timed out if nobody
+    /// answers in time. If no chat received it, the reply is dropped and the
+    /// engine sees the approval as unavailable.
+    async fn request_approval(&self, request: ApprovalRequest) {
+        let id = request.id;
+        let text = self.format_approval(&request);
+        let l = self.config.language;
+        let keyboard = serde_json::json!({ "inline_keyboard": [[
+            { "text": l.pick("✅ Approuver", "✅ Approve"), "callback_data": format!("approve:{}", id) },
+            { "text": l.pick("❌ Rejeter", "❌ Reject"), "callback_data": format!("reject:{}", id) },
+        ]] });
+
+        // Registered before sending so an early press is not lost
+        self.approvals.lock().unwrap_or_else(|e| e.into_inner())
+            .insert(id, PendingApproval { reply: request.reply, text: text.clone(), messages: Vec::new() });
+        let mut delivered = false;
+        for chat_id in &self.config.chat_ids {
+            match self.send_with_markup(chat_id, &text, Some(&keyboard)).await {
+                Ok(message_id) => {
+                    delivered = true;
+                    if let (Some(message_id), Some(pending)) = (message_id, self.approvals.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&id)) {
+                        pending.messages.push((chat_id.clone(), message_id));
+                    }
+                }
+                Err(e) => warn!("[TELEGRAM] Failed to send approval request to {}: {}", chat_id, e),
+            }
+        }
+        if !delivered {
+            error!("[TELEGRAM] Approval request {} reached no chat", id);
+            self.approvals.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+            return;
+        }
+
+        tokio::time::sleep(request.timeout).await;
+        if self.resolve_approval(id, ApprovalDecision::TimedOut).await {
+            info!("[TELEGRAM] Approval request {} timed out", id);
+        }
+    }
+
+    /// Settle a pending approval: answer the engine and replace the buttons
+    /// with the outcome. False if it was already settled.
+    async fn resolve_approval(&self, id: u64, decision: ApprovalDecision) -> bool {
+        let Some(pending) = self.approvals.lock().unwrap_or_else(|e| e.into_inner()).remove(&id) else {
+            return false;
+        };
+        let _ = pending.reply.send(decision);
+        let text = format!("{}{}", pending.text, self.format_decision(decision));
+        for (chat_id, message_id) in &pending.messages {
+            if let Err(e) = self.edit_in_chat(chat_id, *message_id, &text).await {
+                debug!("[TELEGRAM] Could not update approval message in {}: {}", chat_id, e);
+            }
+        }
+        true
+    }
+
+    /// Handle an inline button press from one of the configured chats
+    async fn handle_callback(&self, query: CallbackQuery) {
+        let l = self.config.language;
+        let chat_id = query.message.as_ref().map(|m| m.chat.id.to_string());
+        let answer = match chat_id.filter(|c| self.config.chat_ids.contains(c)) {
+            None => {
+                warn!("[TELEGRAM] Ignoring button press from unauthorized chat");
+                None
+            }
+            Some(chat_id) => match query.data.as_deref().and_then(parse_approval_callback) {
+                Some((decision, id)) if self.resolve_approval(id, decision).await => {
+                    info!("[TELEGRAM] Approval request {} answered {:?} from chat {}", id, decision, chat_id);
+                    Some(if decision == ApprovalDecision::Approved {
+                        l.pick("Trade approuvé", "Trade approved")
+                    } else {
+                        l.pick("Trade rejeté", "Trade rejected")
+                    })
+                }
+                Some(_) => Some(l.pick("Demande expirée", "Request expired")),
+                None => None,
+            },
+        };
+
+        // Always answered, so the client stops showing the button as pending
+        let request = AnswerCallbackQueryRequest { callback_query_id: &query.id, text: answer };
+        if let Err(e) = self.call("answerCallbackQuery", &request).await {
+            debug!("[TELEGRAM] answerCallbackQuery failed: {}", e);
+        }
+    }
+
+    /// Fetch pending updates after `offset`, waiting up to the long-poll timeout
+    async fn get_updates(&self, offset: i64) -> Result<Vec<Update>> {
+        let request = GetUpdatesRequest {
+            offset,
+            timeout: LONG_POLL_TIMEOUT_SECS,
+            allowed_updates: ["message", "callback_query"],
+        };
+
+        let response = self.client
+            .post(format!("{}/getUpdates", self.api_base))
+            .timeout(Duration::from_secs(LONG_POLL_TIMEOUT_SECS + 10))
+            .json(&request)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Telegram API error {}: {}", status, error_text);
+        }
+
+        let body: GetUpdatesResponse = response.json().await?;
+        if !body.ok {
+            anyhow::bail!("Telegram API returned ok=false");
+        }
+        Ok(body.result)
+    }
+
+    /// Format and send a notification. Periodic status updates edit the
+    /// live status message instead of posting a new one. Status updates carry
+    /// the profit chart as a photo when `status_chart` is on, falling back to
+    /// text if it can't be rendered or the text is too long for a caption.
+    pub async fn notify(&self, notification: TelegramNotification) -> Result<()> {
+        let periodic = matches!(notification, TelegramNotification::StatusUpdate { periodic: true, .. });
+        let chart = self.status_chart(&notification);
+        let message = self.format_notification(notification);
+        let chart = chart.filter(|_| message.chars().count() <= MAX_CAPTION_CHARS);
+        match (periodic, chart) {
+            (true, chart) => self.update_status_message(&message, chart.as_deref()).await,
+            (false, Some(png)) => self.send_photo_message(&png, &message).await,
+            (false, None) => self.send_message(&message).await,
+        }
+    }
+
+    /// Profit chart for a status update, if enabled and there is history to plot
+    #[cfg(feature = "charts")]
+    fn status_chart(&self, notification: &TelegramNotification) -> Option<Vec<u8>> {
+        let TelegramNotification::StatusUpdate { profit_history, .. } = notification else { return None };
+        if !self.config.status_chart || profit_history.is_empty() {
+            return None;
+        }
+        crate::chart::profit_chart_png(profit_history)
+            .map_err(|e| warn!("[TELEGRAM] Failed to render profit chart: {}", e))
+            .ok()
+    }
+
+    #[cfg(not(feature = "charts"))]
+    fn status_chart(&self, _notification: &TelegramNotification) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Text of a trade approval request
+    fn format_approval(&self, request: &ApprovalRequest) -> String {
+        let mut m = MessageBuilder::new(&self.config);
+        let l = self.config.language;
+        let f = self.config.profit_format;
+        m.text("🙋 ").bold(l.pick("Approbation Requise", "Approval Required"))
+            .text(l.pick("\n\n📈 Marché: ", "\n\n📈 Market: ")).code(&request.market)
+            .text(&format!("\n🔄 Type: {:?}", request.arb_type))
+            .text(l.pick("\n📦 Contrats: ", "\n📦 Contracts: ")).bold(&request.contracts.to_string())
+            .text(l.pick("\n💰 Montant: ", "\n💰 Notional: ")).bold(&format!("${:.2}", request.notional))
+            .text(l.pick("\n💵 Profit attendu: ", "\n💵 Expected profit: ")).bold(&f.amount(request.expected_profit_cents as f64, ProfitUnit::Cents, false))
+            .text(&format!("\n⏱ {} {}s", l.pick("Expire dans", "Expires in"), request.timeout.as_secs()));
+        m.finish()
+    }
+
+    /// Outcome line appended to an answered approval request
+    fn format_decision(&self, decision: ApprovalDecision) -> String {
+        let mut m = MessageBuilder::new(&self.config);
+        let l = self.config.language;
+        let (emoji, label) = match decision {
+            ApprovalDecision::Approved => ("✅ ", l.pick("Approuvé", "Approved")),
+            ApprovalDecision::Rejected => ("❌ ", l.pick("Rejeté", "Rejected")),
+            ApprovalDecision::TimedOut => ("⌛ ", l.pick("Expiré, trade ignoré", "Expired, trade skipped")),
+            ApprovalDecision::Unavailable => ("⚠️ ", l.pick("Indisponible", "Unavailable")),
+        };
+        m.text("\n\n").text(emoji).bold(label);
+        m.finish()
+    }
+
+    /// The user's template for `notification` filled in, if one is set
+    fn format_template(&self, notification: &TelegramNotification) -> Option<String> {
+        let (kind, fields) = notification.template_fields(self.config.language)?;
+        let template = self.config.templates.get(kind)?;
+        let parts = template_parts(template).ok()?;
+        let mut m = MessageBuilder::new(&self.config);
+        for part in parts {
+            match part {
+                TemplatePart::Text(text) => m.text(&text),
+                TemplatePart::Field("time") => m.text(&chrono::Local::now().format("%H:%M:%S").to_string()),
+                TemplatePart::Field(name) => match fields.iter().find(|(field, _)| *field == name) {
+                    Some((_, value)) => m.text(value),
+                    None => m.text(&format!("{{{}}}", name)),
+                },
+            };
+        }
+        Some(m.finish())
+    }
+
+    /// Format notification into a readable message: the user's template for
+    /// its kind if set, the built-in text otherwise
+    fn format_notification(&self, notification: TelegramNotification) -> String {
+        if let Some(message) = self.format_template(&notification) {
+            return message;
+        }
+        let mut m = MessageBuilder::new(&self.config);
+        let l = self.config.language;
+        let f = self.config.profit_format;
+        match notification {
+            TelegramNotification::BotStarted { mode, markets_count } => {
+                m.text("🚀 ").bold(l.pick("Bot Arbitrage Démarré", "Arbitrage Bot Started"))
+                    .text("\n\n📊 Mode: ").code(&mode)
+                    .text(l.pick("\n🎯 Marchés surveillés: ", "\n🎯 Markets monitored: ")).bold(&markets_count.to_string())
+                    .text(&format!("\n⏰ {}: {}", l.pick("Heure", "Time"), chrono::Local::now().format("%Y-%m-%d %H:%M:%S")));
+            }
+
+            TelegramNotification::OpportunityDetected {
+                market,
+                yes_price,
+                no_price,
+                profit_cents,
+                arb_type,
+                executable_profit_cents,
+                contracts,
+                confidence,
+            } => {
+                let profit_pct = return_pct(profit_cents as f64, yes_price, no_price);
+                m.text("🎯 ").bold(l.pick("Opportunité Détectée!", "Opportunity Detected!"))
+                    .text(l.pick("\n\n📈 Marché: ", "\n\n📈 Market: ")).code(&market)
+                    .text(&format!("\n💰 YES: {} | NO: {}", yes_price, no_price))
+                    .text("\n💵 Profit: ").bold(&format!("{} ({})", f.amount(profit_cents as f64, ProfitUnit::Cents, false), f.pct(profit_pct)))
+                    .text(&format!("\n📊 {}: ~{} ({} {}) | {}: {:.0}%",
+                                   l.pick("Exécutable", "Executable"), f.amount(executable_profit_cents as f64, ProfitUnit::Cents, false), contracts,
+                                   l.pick("contrats", "contracts"), l.pick("Confiance", "Confidence"), confidence * 100.0))
+                    .text("\n🔄 Type: ").text(&arb_type);
+            }
+
+            TelegramNotification::TradeExecuted {
+                market,
+                contracts,
+                requested_contracts,
+                hedge_contracts,
+                limit_hit,
+                profit_cents,
+                success,
+                latency_ms,
+                simulated,
+                unwound_contracts,
+                cancelled_orders,
+            } => {
+                let expired = !success && cancelled_orders > 0;
+                let emoji = if success { "✅" } else if expired { "⏱" } else { "❌" };
+                let status = if success {
+                    l.pick("SUCCÈS", "SUCCESS")
+                } else if expired {
+                    l.pick("EXPIRÉ", "EXPIRED")
+                } else {
+                    l.pick("ÉCHEC", "FAILED")
+                };
+                let tag = if simulated { l.pick(" (SIMULÉ)", " (SIMULATED)") } else { "" };
+                m.text(emoji).text(" ").bold(&format!("Trade {}{}", status, tag))
+                    .text(l.pick("\n\n📈 Marché: ", "\n\n📈 Market: ")).code(&market)
+                    .text(l.pick("\n📦 Contrats: ", "\n📦 Contracts: ")).bold(&contracts.to_string());
+                if contracts < requested_contracts {
+                    m.text(&format!(" / {} ({})", requested_contracts, l.pick("taille réduite", "reduced size")));
+                }
+                if let Some(hedge) = hedge_contracts {
+                    m.text(&format!("\n⚖️ {}: {}", l.pick("Taille par jambe", "Size per leg"), hedge));
+                }
+                if let Some(limit) = &limit_hit {
+                    m.text(l.pick("\n🚧 Limite de position: ", "\n🚧 Position limit: ")).text(limit);
+                }
+                if unwound_contracts > 0 {
+                    m.text(&format!("\n🔄 {}: {}", l.pick("Débouclés (fill partiel)", "Unwound (partial fill)"), unwound_contracts));
+                }
+                if cancelled_orders > 0 {
+                    m.text(&format!("\n⏱ {}: {}", l.pick("Ordres annulés (TTL)", "Orders cancelled (TTL)"), cancelled_orders));
+                }
+                m.text("\n💵 Profit: ").bold(&f.amount(profit_cents as f64, ProfitUnit::Cents, false))
+                    .text(&format!("\n⚡ {}: {}ms", l.pick("Latence", "Latency"), latency_ms));
+            }
+
+            TelegramNotification::PositionSettled { market, yes_won, contracts, realized_pnl } => {
+                let emoji = if realized_pnl >= 0.0 { "🏁" } else { "🔻" };
+                m.text(emoji).text(" ").bold(l.pick("Position Réglée", "Position Settled"))
+                    .text(l.pick("\n\n📈 Marché: ", "\n\n📈 Market: ")).code(&market)
+                    .text(l.pick("\n🎯 Résultat: ", "\n🎯 Outcome: ")).bold(if yes_won { "YES" } else { "NO" })
+                    .text(&format!("\n📦 {}: {:.0}", l.pick("Contrats", "Contracts"), contracts))
+                    .text(l.pick("\n💰 P&L réalisé: ", "\n💰 Realized P&L: ")).bold(&f.amount(realized_pnl * 100.0, ProfitUnit::Dollars, true));
+            }
+
+            TelegramNotification::TrailingStopFired { market, leg, high_water, mark, contracts, closed, realized_pnl, error, simulated } => {
+                m.text("📉 ").bold(l.pick("Stop Suiveur Déclenché", "Trailing Stop Fired"));
+                if simulated {
+                    m.text(l.pick(" (simulation)", " (simulated)"));
+                }
+                m.text(l.pick("\n\n📈 Marché: ", "\n\n📈 Market: ")).code(&market)
+                    .text(&format!("\n🎯 {}: ", l.pick("Jambe", "Leg"))).code(&leg)
+                    .text(&format!("\n📊 Bid: {} → {}",
+                                   f.amount(high_water * 100.0, ProfitUnit::Cents, false), f.amount(mark * 100.0, ProfitUnit::Cents, false)));
+                if simulated {
+                    m.text(&format!("\n📦 {}: {:.0}", l.pick("Contrats à vendre", "Contracts to sell"), contracts));
+                } else {
+                    m.text(&format!("\n📦 {}: {:.0}/{:.0}", l.pick("Contrats vendus", "Contracts sold"), closed, contracts))
+                        .text(l.pick("\n💰 P&L réalisé: ", "\n💰 Realized P&L: ")).bold(&f.amount(realized_pnl * 100.0, ProfitUnit::Dollars, true));
+                }
+                if let Some(error) = error {
+                    m.text(l.pick("\n❌ Erreur: ", "\n❌ Error: ")).code(&error);
+                }
+            }
+
+            TelegramNotification::StatusUpdate {
+                uptime_hours,
+                total_trades,
                 successful_trades,
+                cancelled_orders,
+                declined_trades,
                 total_profit_cents,
+                today_trades,
+                today_successful_trades,
+                today_profit_cents,
                 markets_monitored,
+                paper_trades,
+                paper_profit_cents,
+                top_markets,
+                cache_hits,
+                cache_misses,
+                unrealized_pnl,
+                open_positions,
+                stale_positions,
+                max_open_positions,
+                telegram_sent,
+                telegram_failed,
+                latency,
+                ..
             } => {
-                let success_rate = if total_trades > 0 {
-                    (successful_trades as f64 / total_trades as f64) * 100.0
-                } else {
-                    0.0
-                };
-                let profit_dollars = total_profit_cents as f64 / 100.0;
-                format!(
-                    "📊 <b>Rapport de Statut</b>\n\n\
-                    ⏱ Uptime: <b>{:.1}h</b>\n\
-                    🎯 Marchés: {}\n\
-                    📈 Trades: {}/{} ({:.1}% succès)\n\
-                    💰 Profit Total: <b>${:.2}</b>\n\
-                    ⏰ {}",
-                    uptime_hours,
-                    markets_monitored,
-                    successful_trades,
-                    total_trades,
-                    success_rate,
-                    profit_dollars,
-                    chrono::Local::now().format("%H:%M:%S")
-                )
+                let success_rate = success_rate(successful_trades, total_trades);
+                m.text("📊 ").bold(l.pick("Rapport de Statut", "Status Report"))
+                    .text("\n\n⏱ Uptime: ").bold(&format!("{:.1}h", uptime_hours))
+                    .text(&format!("\n🎯 {}: {}", l.pick("Marchés", "Markets"), markets_monitored))
+                    .text(&format!("\n📈 Trades: {}/{} ({:.1}% {})", successful_trades, total_trades, success_rate, l.pick("succès", "success")));
+                if cancelled_orders > 0 {
+                    m.text(&format!("\n⏱ {}: {}", l.pick("Ordres expirés", "Expired orders"), cancelled_orders));
+                }
+                if declined_trades > 0 {
+                    m.text(&format!("\n🙋 {}: {}", l.pick("Trades non approuvés", "Trades not approved"), declined_trades));
+                }
+                m
+                    .text(l.pick("\n💰 Profit Total: ", "\n💰 Total Profit: ")).bold(&f.amount(total_profit_cents as f64, ProfitUnit::Dollars, false))
+                    .text(&format!("\n📅 {}: {}/{}", l.pick("Trades du jour", "Today's trades"), today_successful_trades, today_trades))
+                    .text(l.pick("\n💵 Profit du jour: ", "\n💵 Today's profit: ")).bold(&f.amount(today_profit_cents as f64, ProfitUnit::Dollars, false));
+                if open_positions > 0 {
+                    m.text(&format!("\n📂 {}: {} ({} positions", l.pick("P&L latent", "Unrealized P&L"),
+                                    f.amount(unrealized_pnl * 100.0, ProfitUnit::Dollars, true), open_positions));
+                    if stale_positions > 0 {
+                        m.text(&format!(", {} {}", stale_positions, l.pick("sans bid", "without bid")));
+                    }
+                    m.text(")");
+                }
+                if let Some(max) = max_open_positions {
+                    m.text(&format!("\n🗂 {}: {}/{}", l.pick("Positions ouvertes", "Open positions"), open_positions, max));
+                }
+                m.text(&format!("\n📨 Telegram: {} {}, {} {}",
+                    telegram_sent, l.pick("envoyés", "sent"), telegram_failed, l.pick("échecs", "failed")));
+                if paper_trades > 0 {
+                    m.text(&format!("\n🧪 Paper: {} trades, {}", paper_trades, f.amount(paper_profit_cents as f64, ProfitUnit::Dollars, false)));
+                }
+                if cache_hits + cache_misses > 0 {
+                    let hit_rate = cache_hits as f64 / (cache_hits + cache_misses) as f64 * 100.0;
+                    m.text(&format!("\n🗃 Cache: {} hits / {} misses ({:.0}%)", cache_hits, cache_misses, hit_rate));
+                }
+                for (platform, s) in latency.iter().filter(|(_, s)| s.samples > 0) {
+                    m.text(&format!("\n📶 {} {} p50/p95/p99: {:.0}/{:.0}/{:.0}ms",
+                                    l.pick("Latence", "Latency"), platform, s.p50_ms, s.p95_ms, s.p99_ms));
+                }
+                if !top_markets.is_empty() {
+                    m.text("\n\n🏆 ").bold(l.pick("Top Marchés", "Top Markets"));
+                    for (i, (market, profit_cents)) in top_markets.iter().enumerate() {
+                        m.text(&format!("\n{}. ", i + 1)).code(market)
+                            .text(&format!(" {}", f.amount(*profit_cents as f64, ProfitUnit::Cents, true)));
+                    }
+                }
+                m.text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
             }
 
             TelegramNotification::Error { message } => {
-                format!(
-                    "⚠️ <b>Erreur Détectée</b>\n\n\
-                    <code>{}</code>\n\
-                    ⏰ {}",
-                    message,
-                    chrono::Local::now().format("%H:%M:%S")
-                )
+                m.text("⚠️ ").bold(l.pick("Erreur Détectée", "Error Detected"))
+                    .text("\n\n").code(&message)
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
             }
 
             TelegramNotification::BotStopped { reason } => {
-                format!(
-                    "🛑 <b>Bot Arrêté</b>\n\n\
-                    📝 Raison: {}\n\
-                    ⏰ {}",
-                    reason,
-                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-                )
+                m.text("🛑 ").bold(l.pick("Bot Arrêté", "Bot Stopped"))
+                    .text(l.pick("\n\n📝 Raison: ", "\n\n📝 Reason: ")).text(&reason)
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S")));
+            }
+
+            TelegramNotification::CircuitBreakerTripped { reason, cooldown_secs } => {
+                m.text("🚨 ").bold(l.pick("Circuit Breaker Déclenché", "Circuit Breaker Tripped"))
+                    .text(l.pick("\n\n📝 Raison: ", "\n\n📝 Reason: ")).code(&reason)
+                    .text(&format!("\n⏳ {}: {}s", l.pick("Trade de test après", "Test trade after"), cooldown_secs))
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
+            }
+
+            TelegramNotification::DailyLossLimitHit { loss_cents, limit_cents, resume_at } => {
+                m.text("🛑 ").bold(l.pick("Limite de Perte Journalière Atteinte", "Daily Loss Limit Hit"))
+                    .text(l.pick("\n\n📉 Perte du jour: ", "\n\n📉 Today's loss: ")).bold(&format!("${:.2}", loss_cents as f64 / 100.0))
+                    .text(&format!(" ({} ${:.2})", l.pick("limite", "limit"), limit_cents as f64 / 100.0))
+                    .text(l.pick("\n⏸ Nouveaux trades suspendus jusqu'à ", "\n⏸ New trades halted until "))
+                    .bold(&resume_at.format("%Y-%m-%d %H:%M UTC").to_string());
+            }
+
+            TelegramNotification::HighLatency { platform, stats, threshold_ms } => {
+                m.text("🐢 ").bold(l.pick("Latence Élevée", "High Latency"))
+                    .text(&format!("\n\n🌐 {}: ", l.pick("Plateforme", "Platform"))).bold(&platform.to_string())
+                    .text("\n⏱ p95: ").bold(&format!("{:.0}ms", stats.p95_ms))
+                    .text(&format!(" ({} {}ms)", l.pick("seuil", "threshold"), threshold_ms))
+                    .text(&format!("\n📊 p50/p99: {:.0}/{:.0}ms ({} {})", stats.p50_ms, stats.p99_ms, stats.samples, l.pick("requêtes", "requests")))
+                    .text(l.pick("\n⚠️ Les écarts d'arbitrage risquent de disparaître avant exécution",
+                                 "\n⚠️ Arbitrage edges may vanish before orders land"))
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
+            }
+
+            TelegramNotification::WarmupComplete { ready, total, elapsed_secs, timed_out } => {
+                if timed_out {
+                    m.text("⏱ ").bold(l.pick("Préchauffage Expiré", "Warmup Timed Out"))
+                        .text(&format!("\n\n📚 {}: {}/{}", l.pick("Carnets reçus", "Books in"), ready, total))
+                        .text(l.pick("\n⚠️ Certains marchés n'ont pas encore de carnet complet",
+                                     "\n⚠️ Some markets don't have a complete book yet"));
+                } else {
+                    m.text("✅ ").bold(l.pick("Préchauffage Terminé", "Warmup Complete"))
+                        .text(&format!("\n\n📚 {}: {}", l.pick("Carnets reçus pour tous les marchés", "Books in for all markets"), total));
+                }
+                m.text(&format!("\n⏱ {}: {:.1}s", l.pick("Durée", "Took"), elapsed_secs))
+                    .text(l.pick("\n🟢 Trading activé", "\n🟢 Trading enabled"));
+            }
+
+            TelegramNotification::NoOpportunities { idle_secs, markets } => {
+                m.text("💤 ").bold(l.pick("Aucune Opportunité", "No Opportunities"))
+                    .text(l.pick("\n\n⏱ Rien détecté depuis ", "\n\n⏱ Nothing detected for ")).bold(&format!("{} min", idle_secs / 60))
+                    .text(&format!(" ({} {})", markets, l.pick("marchés suivis", "markets monitored")))
+                    .text(l.pick("\n⚠️ Vérifier les flux de prix et le matching des marchés",
+                                 "\n⚠️ Check the price feeds and market matching"))
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
+            }
+
+            TelegramNotification::MarketStale { market, ticks, silent_secs } => {
+                m.text("🧊 ").bold(l.pick("Marché Figé", "Market Stale"))
+                    .text("\n\n📊 ").bold(&market)
+                    .text(l.pick("\n⏱ Aucune mise à jour du carnet depuis ", "\n⏱ No book update for "))
+                    .bold(&format!("{} cycles ({} s)", ticks, silent_secs))
+                    .text(l.pick("\n⏸ Plus tradé jusqu'à la prochaine mise à jour",
+                                 "\n⏸ Not traded until the next update"))
+                    .text(l.pick("\n⚠️ Vérifier l'abonnement au flux et le parsing",
+                                 "\n⚠️ Check the feed subscription and parsing"))
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
+            }
+
+            TelegramNotification::PlatformDegraded { platform, down, unmanaged } => {
+                if down {
+                    m.text("🚧 ").bold(l.pick("Mode Dégradé", "Degraded Mode"))
+                        .text(&format!("\n\n🌐 {} ", platform)).text(l.pick("injoignable", "unreachable"))
+                        .text(l.pick("\n⏸ Paires concernées ignorées, l'autre plateforme continue",
+                                     "\n⏸ Its pairs are skipped, the other platform keeps trading"));
+                    if !unmanaged.is_empty() {
+                        m.text("\n\n⚠️ ").bold(l.pick("Positions non gérées", "Unmanaged positions")).text(&format!(": {}", unmanaged.len()));
+                        for market in &unmanaged {
+                            m.text("\n  • ").text(market);
+                        }
+                    }
+                } else {
+                    m.text("✅ ").bold(l.pick("Fin du Mode Dégradé", "Degraded Mode Over"))
+                        .text(&format!("\n\n🌐 {} ", platform)).text(l.pick("de nouveau joignable, paires réactivées",
+                                                                                 "reachable again, pairs re-enabled"));
+                }
+                m.text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
+            }
+
+            TelegramNotification::FeedDown { platform, down_secs } => {
+                m.text("📡 ").bold(l.pick("Flux Déconnecté", "Feed Disconnected"))
+                    .text(&format!("\n\n🌐 {}: ", l.pick("Plateforme", "Platform"))).bold(&platform.to_string())
+                    .text(l.pick("\n⏱ Hors ligne depuis ", "\n⏱ Down for ")).bold(&format!("{} min {:02} s", down_secs / 60, down_secs % 60))
+                    .text(l.pick("\n⚠️ Aucun prix reçu, reconnexion en cours",
+                                 "\n⚠️ No prices coming in, still reconnecting"))
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
+            }
+
+            TelegramNotification::CircuitBreakerReset => {
+                m.text("✅ ").bold(l.pick("Circuit Breaker Réarmé", "Circuit Breaker Reset"))
+                    .text(l.pick("\n\n▶️ Trading repris", "\n\n▶️ Trading resumed"))
+                    .text(&format!("\n⏰ {}", chrono::Local::now().format("%H:%M:%S")));
+            }
+
+            TelegramNotification::PositionsReport {
+                open_positions,
+                total_contracts,
+                total_cost_basis,
+                guaranteed_profit,
+                unmatched_exposure,
+                realized_pnl,
+            } => {
+                m.text("📂 ").bold(l.pick("Positions Ouvertes", "Open Positions"))
+                    .text("\n\n📦 Positions: ").bold(&open_positions.to_string())
+                    .text(&format!(" ({:.0} {})", total_contracts, l.pick("contrats", "contracts")))
+                    .text(&format!("\n💵 {}: ${:.2}", l.pick("Coût", "Cost"), total_cost_basis))
+                    .text(l.pick("\n🔒 Profit garanti: ", "\n🔒 Guaranteed profit: ")).bold(&format!("${:.2}", guaranteed_profit))
+                    .text(&format!("\n⚠️ {}: ${:.2}", l.pick("Exposition non couverte", "Unhedged exposure"), unmatched_exposure))
+                    .text(&format!("\n💰 {}: ${:.2}", l.pick("P&L réalisé", "Realized P&L"), realized_pnl));
+            }
+
+            TelegramNotification::PositionsReconciled { matched, adopted, corrected, dropped, unconfirmed } => {
+                let clean = adopted.is_empty() && corrected.is_empty() && dropped.is_empty() && unconfirmed.is_empty();
+                m.text(if clean { "✅ " } else { "🔄 " }).bold(l.pick("Réconciliation des Positions", "Position Reconciliation"))
+                    .text(&format!("\n\n✔️ {}: {}", l.pick("Conformes", "Matched"), matched));
+                for (title, entries) in [
+                    (l.pick("📥 Adoptées", "📥 Adopted"), &adopted),
+                    (l.pick("✏️ Corrigées", "✏️ Corrected"), &corrected),
+                    (l.pick("🗑 Supprimées", "🗑 Dropped"), &dropped),
+                    (l.pick("⏳ Non confirmées", "⏳ Unconfirmed"), &unconfirmed),
+                ] {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    m.text(&format!("\n{}: {}", title, entries.len()));
+                    for entry in entries {
+                        m.text("\n  • ").code(entry);
+                    }
+                }
+            }
+
+            TelegramNotification::PositionsFlattened { closed, failed, realized_pnl, simulated } => {
+                let emoji = if failed.is_empty() { "🧯" } else { "⚠️" };
+                m.text(emoji).text(" ").bold(l.pick("Positions Liquidées", "Positions Flattened"));
+                if simulated {
+                    m.text(l.pick(" (simulation)", " (simulated)"));
+                }
+                for (title, entries) in [
+                    (l.pick("✅ Fermées", "✅ Closed"), &closed),
+                    (l.pick("❌ Échecs", "❌ Failed"), &failed),
+                ] {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    m.text(&format!("\n\n{}: {}", title, entries.len()));
+                    for entry in entries {
+                        m.text("\n  • ").code(entry);
+                    }
+                }
+                if closed.is_empty() && failed.is_empty() {
+                    m.text(l.pick("\n\nAucune position ouverte", "\n\nNo open positions"));
+                }
+                m.text(l.pick("\n\n💰 P&L réalisé: ", "\n\n💰 Realized P&L: ")).bold(&format!("${:+.2}", realized_pnl))
+                    .text(l.pick("\n⏸ Trading suspendu jusqu'à /resume", "\n⏸ Trading paused until /resume"));
+            }
+
+            TelegramNotification::PnlReport { window, stats } => {
+                let title = match window {
+                    PnlWindow::Today => l.pick("Aujourd'hui", "Today"),
+                    PnlWindow::Week => l.pick("7 derniers jours", "Last 7 days"),
+                    PnlWindow::All => l.pick("Depuis le début", "All time"),
+                };
+                m.text("💰 ").bold(&format!("P&L · {}", title))
+                    .text(&format!("\n\n📈 Trades: {}/{} ({:.1}% {})", stats.successful_trades, stats.trades,
+                                   success_rate(stats.successful_trades, stats.trades), l.pick("succès", "success")))
+                    .text(l.pick("\n💵 Profit réalisé: ", "\n💵 Realized profit: ")).bold(&f.amount(stats.profit_cents as f64, ProfitUnit::Dollars, true));
+            }
+
+            TelegramNotification::CommandReply { command: BotCommand::Pnl(None) } => {
+                m.text("🤖 Usage: ").code("/pnl [today|week|all]")
+                    .text(l.pick("\n\ntoday: journée de trading en cours\nweek: 7 derniers jours\nall: depuis le début",
+                                 "\n\ntoday: current trading day\nweek: last 7 days\nall: every trade on record"));
+            }
+
+            TelegramNotification::CommandReply { command } => {
+                let message = match command {
+                    BotCommand::Pause => l.pick("Trading en pause", "Trading paused"),
+                    BotCommand::Resume => l.pick("Trading repris", "Trading resumed"),
+                    BotCommand::Flatten => l.pick("Trading arrêté, liquidation des positions...", "Trading halted, flattening positions..."),
+                    _ => l.pick("Commande reçue", "Command received"),
+                };
+                m.text("🤖 ").text(message);
+            }
+
+            TelegramNotification::DisabledMarkets { command, changed, matched, markets } => {
+                match &command {
+                    BotCommand::Disable(market) => {
+                        m.text("⛔ ").code(market).text(if changed {
+                            l.pick(" désactivé", " disabled")
+                        } else {
+                            l.pick(" déjà désactivé", " already disabled")
+                        });
+                        if matched == 0 {
+                            m.text(l.pick(" (aucun marché surveillé ne correspond)", " (no monitored market matches)"));
+                        }
+                        m.text("\n\n");
+                    }
+                    BotCommand::Enable(market) => {
+                        m.text("✅ ").code(market).text(if changed {
+                            l.pick(" réactivé", " enabled")
+                        } else {
+                            l.pick(" n'était pas désactivé", " was not disabled")
+                        }).text("\n\n");
+                    }
+                    _ => {}
+                }
+                if markets.is_empty() {
+                    m.text(l.pick("Aucun marché désactivé", "No market disabled"));
+                } else {
+                    m.bold(l.pick("Marchés désactivés", "Disabled markets")).text(&format!(": {}", markets.len()));
+                    for market in &markets {
+                        m.text("\n  • ").code(market);
+                    }
+                }
             }
         }
+        m.finish()
+    }
+
+    /// Send several opportunities as a single summary message
+    pub async fn notify_digest(&self, mut opportunities: Vec<TelegramNotification>) -> Result<()> {
+        if opportunities.len() == 1 {
+            return self.notify(opportunities.remove(0)).await;
+        }
+        let message = self.format_digest(&opportunities);
+        self.send_message(&message).await
     }
+
+    /// Format a batch of opportunity notifications into one summary
+    fn format_digest(&self, opportunities: &[TelegramNotification]) -> String {
+        let mut m = MessageBuilder::new(&self.config);
+        let l = self.config.language;
+        let f = self.config.profit_format;
+        m.text("🎯 ").bold(&format!("{} {}", opportunities.len(), l.pick("Opportunités Détectées", "Opportunities Detected")))
+            .text("\n");
+        for notification in opportunities {
+            if let TelegramNotification::OpportunityDetected { market, profit_cents, arb_type, executable_profit_cents, confidence, .. } = notification {
+                m.text("\n📈 ").code(market)
+                    .text(" → ").bold(&f.amount(*profit_cents as f64, ProfitUnit::Cents, false))
+                    .text(&format!(" ({}, ~{} {:.0}%)", arb_type, f.amount(*executable_profit_cents as f64, ProfitUnit::Cents, false), confidence * 100.0));
+            }
+        }
+        m.finish()
+    }
+}
+
+/// Builds a message for a given parse mode, applying the markup and escaping it requires
+struct MessageBuilder {
+    mode: ParseMode,
+    /// Transliterated to ASCII on `finish`
+    plain: bool,
+    buf: String,
 }
 
+impl MessageBuilder {
+    fn new(config: &TelegramConfig) -> Self {
+        Self { mode: config.message_mode(), plain: config.plain_text, buf: String::with_capacity(256) }
+    }
+
+    fn escape(&self, s: &str) -> String {
+        match self.mode {
+            ParseMode::Html => escape_html(s),
+            ParseMode::MarkdownV2 => escape_markdown_v2(s),
+            ParseMode::None => s.to_string(),
+        }
+    }
+
+    /// Append plain text
+    fn text(&mut self, s: &str) -> &mut Self {
+        let escaped = self.escape(s);
+        self.buf.push_str(&escaped);
+        self
+    }
+
+    /// Append bold text
+    fn bold(&mut self, s: &str) -> &mut Self {
+        let escaped = self.escape(s);
+        match self.mode {
+            ParseMode::Html => { self.buf.push_str("<b>"); self.buf.push_str(&escaped); self.buf.push_str("</b>"); }
+            ParseMode::MarkdownV2 => { self.buf.push('*'); self.buf.push_str(&escaped); self.buf.push('*'); }
+            ParseMode::None => self.buf.push_str(&escaped),
+        }
+        self
+    }
+
+    /// Append monospace text
+    fn code(&mut self, s: &str) -> &mut Self {
+        match self.mode {
+            ParseMode::Html => {
+                let escaped = self.escape(s);
+                self.buf.push_str("<code>"); self.buf.push_str(&escaped); self.buf.push_str("</code>");
+            }
+            ParseMode::MarkdownV2 => {
+                // Inside inline code only ` and \ need escaping
+                self.buf.push('`');
+                for c in s.chars() {
+                    if c == '`' || c == '\\' { self.buf.push('\\'); }
+                    self.buf.push(c);
+                }
+                self.buf.push('`');
+            }
+            ParseMode::None => self.buf.push_str(s),
+        }
+        self
+    }
+
+    fn finish(self) -> String {
+        if self.plain { plain_ascii(&self.buf) } else { self.buf }
+    }
+}
+
+/// `s` in plain ASCII: accents and symbols spelled out, emoji dropped with
+/// the space that followed them
+fn plain_ascii(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut dropped = false;
+    for c in s.chars() {
+        if c.is_ascii() {
+            if !(dropped && c == ' ' && (out.is_empty() || out.ends_with(['\n', ' ']))) {
+                out.push(c);
+            }
+            dropped = false;
+            continue;
+        }
+        let ascii = match c {
+            'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => "a",
+            'À' | 'Á' | 'Â' | 'Ä' => "A",
+            'ç' => "c",
+            'Ç' => "C",
+            'é' | 'è' | 'ê' | 'ë' => "e",
+            'É' | 'È' | 'Ê' | 'Ë' => "E",
+            'í' | 'î' | 'ï' => "i",
+            'Î' | 'Ï' => "I",
+            'ó' | 'ô' | 'ö' => "o",
+            'Ô' | 'Ö' => "O",
+            'ú' | 'ù' | 'û' | 'ü' => "u",
+            'Ù' | 'Û' | 'Ü' => "U",
+            'ñ' => "n",
+            'ÿ' => "y",
+            'œ' => "oe",
+            'Œ' => "OE",
+            'æ' => "ae",
+            '¢' => "c",
+            '€' => "EUR",
+            '→' => "->",
+            '←' => "<-",
+            '≥' => ">=",
+            '≤' => "<=",
+            '≈' => "~",
+            '±' => "+/-",
+            '×' => "x",
+            '…' => "...",
+            '•' | '·' | '–' | '—' => "-",
+            '‘' | '’' => "'",
+            '“' | '”' | '«' | '»' => "\"",
+            '\u{a0}' | '\u{202f}' => " ",
+            _ => {
+                dropped = true;
+                continue;
+            }
+        };
+        out.push_str(ascii);
+        dropped = false;
+    }
+    out.lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+}
+
+/// Share of `trades` that succeeded, in percent
+fn success_rate(successful: u64, trades: u64) -> f64 {
+    if trades > 0 {
+        successful as f64 / trades as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Escape the characters Telegram's HTML parser treats as markup
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape the characters reserved by Telegram's MarkdownV2 syntax
+fn escape_markdown_v2(s: &str) -> String {
+    const RESERVED: &str = "_*[]()~`>#+-=|{}.!\\";
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if RESERVED.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Default location of persisted performance statistics
+pub const PERFORMANCE_FILE: &str = ".performance_stats.json";
+
 /// Statistics tracker for performance monitoring
 pub struct PerformanceTracker {
     start_time: Instant,
+    /// Unix timestamp of the first run recorded in the stats file
+    pub first_started_at: i64,
+    /// Uptime accumulated by previous runs (seconds)
+    prior_uptime_secs: f64,
+    /// Report lifetime uptime instead of the current process only
+    pub lifetime_uptime: bool,
     pub total_trades: u64,
     pub successful_trades: u64,
+    /// Orders cancelled at their time-to-live, kept out of the success rate
+    pub cancelled_orders: u64,
+    /// Trades skipped because their approval was rejected or timed out
+    pub declined_trades: u64,
     pub total_profit_cents: i64,
     pub opportunities_detected: u64,
+    /// Simulated trades from dry-run mode, kept apart from live P&L
+    pub paper_trades: u64,
+    pub paper_successful_trades: u64,
+    pub paper_profit_cents: i64,
+    /// Breakdown keyed by market identifier
+    pub by_market: HashMap<String, MarketStats>,
+    /// Breakdown keyed by arb type
+    pub by_arb_type: HashMap<String, MarketStats>,
+    /// Cumulative profit after each successful trade, oldest first
+    pub profit_history: Vec<ProfitPoint>,
+    /// UTC hour at which `today` starts over
+    daily_reset_hour: u32,
+    /// Live counters of the current trading day; its profit is the realized
+    /// P&L the daily loss limit applies to
+    pub today: DailyStats,
+    /// Counters of the trading days before `today` within the last week,
+    /// oldest first (`/pnl week`)
+    pub past_days: Vec<DailyStats>,
+}
+
+/// Live trade counters of one trading day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyStats {
+    /// Unix timestamp (seconds) at which this trading day started
+    pub started_at: i64,
+    pub trades: u64,
+    pub successful_trades: u64,
+    pub profit_cents: i64,
+}
+
+/// Start of the trading day `now` falls in, when days start at `reset_hour` UTC
+pub fn trading_day_start(now: chrono::DateTime<chrono::Utc>, reset_hour: u32) -> i64 {
+    let offset = reset_hour as i64 * 3600;
+    (now.timestamp() - offset).div_euclid(86_400) * 86_400 + offset
+}
+
+/// Most recent profit points kept for the status chart
+pub const MAX_PROFIT_HISTORY: usize = 2000;
+
+/// Cumulative profit after one trade
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProfitPoint {
+    /// Unix timestamp (seconds)
+    pub ts: i64,
+    /// Cumulative profit in cents (live or paper, per `paper`)
+    pub profit_cents: i64,
+    #[serde(default)]
+    pub paper: bool,
+}
+
+/// Trading days covered by `/pnl week`, today included
+pub const PNL_WEEK_DAYS: i64 = 7;
+
+/// Trade statistics for one market or arb type
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarketStats {
+    pub trades: u64,
+    pub successful_trades: u64,
+    pub profit_cents: i64,
+}
+
+/// Data structure for serialization
+#[derive(Serialize, Deserialize)]
+struct PerformanceSaveData {
+    first_started_at: i64,
+    prior_uptime_secs: f64,
+    total_trades: u64,
+    successful_trades: u64,
+    #[serde(default)]
+    cancelled_orders: u64,
+    #[serde(default)]
+    declined_trades: u64,
+    total_profit_cents: i64,
+    opportunities_detected: u64,
+    #[serde(default)]
+    paper_trades: u64,
+    #[serde(default)]
+    paper_successful_trades: u64,
+    #[serde(default)]
+    paper_profit_cents: i64,
+    #[serde(default)]
+    by_market: HashMap<String, MarketStats>,
+    #[serde(default)]
+    by_arb_type: HashMap<String, MarketStats>,
+    #[serde(default)]
+    profit_history: Vec<ProfitPoint>,
+    #[serde(default)]
+    today: DailyStats,
+    #[serde(default)]
+    past_days: Vec<DailyStats>,
 }
 
 impl PerformanceTracker {
-    pub fn new() -> Self {
+    /// Empty stats whose trading days start at `daily_reset_hour` UTC
+    pub fn new(daily_reset_hour: u32) -> Self {
         Self {
             start_time: Instant::now(),
+            first_started_at: chrono::Utc::now().timestamp(),
+            prior_uptime_secs: 0.0,
+            lifetime_uptime: false,
             total_trades: 0,
             successful_trades: 0,
+            cancelled_orders: 0,
+            declined_trades: 0,
             total_profit_cents: 0,
             opportunities_detected: 0,
+            paper_trades: 0,
+            paper_successful_trades: 0,
+            paper_profit_cents: 0,
+            by_market: HashMap::new(),
+            by_arb_type: HashMap::new(),
+            profit_history: Vec::new(),
+            daily_reset_hour,
+            today: DailyStats { started_at: trading_day_start(chrono::Utc::now(), daily_reset_hour), ..DailyStats::default() },
+            past_days: Vec::new(),
         }
     }
 
+    /// Load persisted counters; the current process uptime starts from now.
+    /// A saved day that started at another reset hour rolls over on first use.
+    pub fn load_from_path<P: AsRef<Path>>(path: P, daily_reset_hour: u32) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let data: PerformanceSaveData = serde_json::from_str(&contents)?;
+        Ok(Self {
+            start_time: Instant::now(),
+            first_started_at: data.first_started_at,
+            prior_uptime_secs: data.prior_uptime_secs,
+            lifetime_uptime: false,
+            total_trades: data.total_trades,
+            successful_trades: data.successful_trades,
+            cancelled_orders: data.cancelled_orders,
+            declined_trades: data.declined_trades,
+            total_profit_cents: data.total_profit_cents,
+            opportunities_detected: data.opportunities_detected,
+            paper_trades: data.paper_trades,
+            paper_successful_trades: data.paper_successful_trades,
+            paper_profit_cents: data.paper_profit_cents,
+            by_market: data.by_market,
+            by_arb_type: data.by_arb_type,
+            profit_history: data.profit_history,
+            daily_reset_hour,
+            today: data.today,
+            past_days: data.past_days,
+        })
+    }
+
+    /// Save counters, folding the current process uptime into the cumulative total
+    pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let data = PerformanceSaveData {
+            first_started_at: self.first_started_at,
+            prior_uptime_secs: self.lifetime_uptime_secs(),
+            total_trades: self.total_trades,
+            successful_trades: self.successful_trades,
+            cancelled_orders: self.cancelled_orders,
+            declined_trades: self.declined_trades,
+            total_profit_cents: self.total_profit_cents,
+            opportunities_detected: self.opportunities_detected,
+            paper_trades: self.paper_trades,
+            paper_successful_trades: self.paper_successful_trades,
+            paper_profit_cents: self.paper_profit_cents,
+            by_market: self.by_market.clone(),
+            by_arb_type: self.by_arb_type.clone(),
+            profit_history: self.profit_history.clone(),
+            today: self.today,
+            past_days: self.past_days.clone(),
+        };
+        let json = serde_json::to_string_pretty(&data)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Record a live trade. Failed trades can still carry a realized loss
+    /// (an unhedged partial fill that was sold back), which is booked too.
     pub fn record_trade(&mut self, success: bool, profit_cents: i16) {
+        self.record_trade_at(success, profit_cents, chrono::Utc::now());
+    }
+
+    /// `record_trade` at `now`
+    pub fn record_trade_at(&mut self, success: bool, profit_cents: i16, now: chrono::DateTime<chrono::Utc>) {
+        self.roll_today(now);
         self.total_trades += 1;
+        self.today.trades += 1;
         if success {
             self.successful_trades += 1;
-            self.total_profit_cents += profit_cents as i64;
+            self.today.successful_trades += 1;
+        }
+        if success || profit_cents != 0 {
+            self.book_profit(profit_cents as i64, now);
+        }
+    }
+
+    /// Trades, successes and realized profit over `window`. Today and all time
+    /// come from the running counters (what the status report shows); the
+    /// week adds up the saved counters of the last 7 trading days.
+    pub fn pnl(&mut self, window: PnlWindow, now: chrono::DateTime<chrono::Utc>) -> DailyStats {
+        match window {
+            PnlWindow::Today => {
+                self.roll_today(now);
+                self.today
+            }
+            PnlWindow::Week => {
+                self.roll_today(now);
+                let started_at = self.today.started_at - (PNL_WEEK_DAYS - 1) * 86_400;
+                self.past_days.iter()
+                    .chain([&self.today])
+                    .filter(|day| day.started_at >= started_at)
+                    .fold(DailyStats { started_at, ..DailyStats::default() }, |mut stats, day| {
+                        stats.trades += day.trades;
+                        stats.successful_trades += day.successful_trades;
+                        stats.profit_cents += day.profit_cents;
+                        stats
+                    })
+            }
+            PnlWindow::All => DailyStats {
+                started_at: self.first_started_at,
+                trades: self.total_trades,
+                successful_trades: self.successful_trades,
+                profit_cents: self.total_profit_cents,
+            },
+        }
+    }
+
+    /// Add realized live profit to the total and the trading day
+    fn book_profit(&mut self, profit_cents: i64, now: chrono::DateTime<chrono::Utc>) {
+        self.total_profit_cents += profit_cents;
+        self.push_profit_point(self.total_profit_cents, false);
+        self.roll_today(now);
+        self.today.profit_cents += profit_cents;
+    }
+
+    /// Start a new trading day if `now` is past the current one's end. The
+    /// day that ends is kept for the week while it is less than 7 days old.
+    pub fn roll_today(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        let started_at = trading_day_start(now, self.daily_reset_hour);
+        if self.today.started_at != started_at {
+            let ended = std::mem::replace(&mut self.today, DailyStats { started_at, ..DailyStats::default() });
+            if ended.trades > 0 || ended.profit_cents != 0 {
+                self.past_days.push(ended);
+            }
+            self.past_days.retain(|day| day.started_at > started_at - PNL_WEEK_DAYS * 86_400);
+        }
+    }
+
+    /// Realized live P&L of the trading day `now` falls on
+    pub fn daily_pnl_at(&self, now: chrono::DateTime<chrono::Utc>) -> i64 {
+        if trading_day_start(now, self.daily_reset_hour) == self.today.started_at { self.today.profit_cents } else { 0 }
+    }
+
+    /// Record a trade and attribute it to its market and arb type
+    pub fn record_trade_for(&mut self, market: &str, arb_type: &str, success: bool, profit_cents: i16) {
+        self.record_trade(success, profit_cents);
+        for stats in [
+            self.by_market.entry(market.to_string()).or_default(),
+            self.by_arb_type.entry(arb_type.to_string()).or_default(),
+        ] {
+            stats.trades += 1;
+            if success {
+                stats.successful_trades += 1;
+            }
+            stats.profit_cents += profit_cents as i64;
+        }
+    }
+
+    /// Record orders cancelled at their time-to-live. An attempt whose orders
+    /// all expired unhedged is not a trade and stays out of the success rate;
+    /// any loss it realized (unwinding the other leg) is still booked.
+    pub fn record_cancelled(&mut self, orders: u32, profit_cents: i16) {
+        self.cancelled_orders += orders as u64;
+        if profit_cents != 0 {
+            let now = chrono::Utc::now();
+            self.book_profit(profit_cents as i64, now);
+        }
+    }
+
+    /// Record a trade skipped for lack of approval; it is not counted as a trade
+    pub fn record_declined(&mut self) {
+        self.declined_trades += 1;
+    }
+
+    /// Record a simulated trade without touching live counters. Like a live
+    /// trade, a failed one still books the loss it realized.
+    pub fn record_paper_trade(&mut self, success: bool, profit_cents: i16) {
+        self.paper_trades += 1;
+        if success {
+            self.paper_successful_trades += 1;
+        }
+        if success || profit_cents != 0 {
+            self.paper_profit_cents += profit_cents as i64;
+            self.push_profit_point(self.paper_profit_cents, true);
         }
     }
 
+    fn push_profit_point(&mut self, profit_cents: i64, paper: bool) {
+        self.profit_history.push(ProfitPoint { ts: chrono::Utc::now().timestamp(), profit_cents, paper });
+        if self.profit_history.len() > MAX_PROFIT_HISTORY {
+            let excess = self.profit_history.len() - MAX_PROFIT_HISTORY;
+            self.profit_history.drain(..excess);
+        }
+    }
+
+    /// Cumulative profit series for the chart: live trades, or paper trades
+    /// when there are no live ones yet (dry run)
+    pub fn profit_series(&self) -> Vec<(i64, i64)> {
+        let live = self.profit_history.iter().any(|p| !p.paper);
+        self.profit_history.iter()
+            .filter(|p| p.paper != live)
+            .map(|p| (p.ts, p.profit_cents))
+            .collect()
+    }
+
+    /// Most profitable markets, best first
+    pub fn top_markets(&self, n: usize) -> Vec<(String, i64)> {
+        let mut markets: Vec<(String, i64)> = self.by_market.iter()
+            .map(|(market, stats)| (market.clone(), stats.profit_cents))
+            .collect();
+        markets.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        markets.truncate(n);
+        markets
+    }
+
     pub fn record_opportunity(&mut self) {
         self.opportunities_detected += 1;
     }
 
+    /// Uptime in hours: current process, or all runs when `lifetime_uptime` is set
     pub fn uptime_hours(&self) -> f64 {
-        self.start_time.elapsed().as_secs_f64() / 3600.0
+        if self.lifetime_uptime {
+            self.lifetime_uptime_secs() / 3600.0
+        } else {
+            self.start_time.elapsed().as_secs_f64() / 3600.0
+        }
+    }
+
+    fn lifetime_uptime_secs(&self) -> f64 {
+        self.prior_uptime_secs + self.start_time.elapsed().as_secs_f64()
     }
 }
 
 impl Default for PerformanceTracker {
     fn default() -> Self {
-        Self::new()
+        Self::new(0)
+    }
+}
+
+/// Per-kind spacing of notifications: one sent within its kind's interval
+/// (per market for opportunities and trades) is dropped
+pub struct NotificationThrottle {
+    intervals: HashMap<&'static str, Duration>,
+    last_sent: HashMap<(&'static str, String), Instant>,
+}
+
+impl NotificationThrottle {
+    pub fn new(intervals: HashMap<&'static str, Duration>) -> Self {
+        Self { intervals, last_sent: HashMap::new() }
+    }
+
+    /// True if `notification` may be sent at `now`, which then counts as its last send
+    pub fn allow(&mut self, notification: &TelegramNotification, now: Instant) -> bool {
+        let Some((kind, subject)) = notification.throttle_key() else { return true };
+        let Some(&interval) = self.intervals.get(kind).filter(|i| !i.is_zero()) else { return true };
+        let key = (kind, subject.to_string());
+        if self.last_sent.get(&key).is_some_and(|&last| now.duration_since(last) < interval) {
+            return false;
+        }
+        self.last_sent.insert(key, now);
+        true
     }
 }
 
 /// Channel for sending notifications
 pub type TelegramChannel = mpsc::Sender<TelegramNotification>;
 
-/// Create a notification channel and spawn the sender loop
-pub fn create_telegram_channel(bot: TelegramBot) -> TelegramChannel {
+/// Create a notification channel and spawn the sender loop.
+/// The loop ends once every sender is dropped and the queue is drained.
+pub fn create_telegram_channel(bot: TelegramBot) -> (TelegramChannel, JoinHandle<()>) {
     let (tx, mut rx) = mpsc::channel::<TelegramNotification>(100);
 
-    tokio::spawn(async move {
+    let handle = tokio::spawn(async move {
+        let batch_window = Duration::from_millis(bot.config.batch_window_ms);
+        let mut throttle = NotificationThrottle::new(bot.config.throttle.clone());
+        let dropped = |n: &TelegramNotification, throttle: &mut NotificationThrottle| {
+            if n.severity() < bot.config.min_severity {
+                return true;
+            }
+            let allowed = throttle.allow(n, Instant::now());
+            if !allowed {
+                debug!("[TELEGRAM] Throttled {:?}", n.throttle_key());
+            }
+            !allowed
+        };
+
         while let Some(notification) = rx.recv().await {
-            if let Err(e) = bot.notify(notification).await {
-                error!("[TELEGRAM] Failed to send notification: {}", e);
+            // Drop before sending so filtered messages don't consume the send budget
+            if dropped(&notification, &mut throttle) {
+                continue;
+            }
+
+            let is_opportunity = matches!(notification, TelegramNotification::OpportunityDetected { .. });
+            if batch_window.is_zero() || !is_opportunity {
+                send_paced(&bot, notification).await;
+                continue;
+            }
+
+            // Batching: collect opportunities for the window, pass everything else through
+            let mut batch = vec![notification];
+            let deadline = tokio::time::Instant::now() + batch_window;
+            while let Ok(Some(next)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+                if dropped(&next, &mut throttle) {
+                    continue;
+                }
+                if matches!(next, TelegramNotification::OpportunityDetected { .. }) {
+                    batch.push(next);
+                } else {
+                    send_paced(&bot, next).await;
+                }
+            }
+
+            let sent = bot.notify_digest(batch).await;
+            delivery().record(sent.is_ok(), Instant::now());
+            if let Err(e) = sent {
+                error!("[TELEGRAM] Failed to send digest: {}", e);
             }
-            // Rate limiting: max 1 message per second
-            tokio::time::sleep(Duration::from_millis(500)).await;
+            tokio::time::sleep(Duration::from_millis(MIN_SEND_INTERVAL_MS)).await;
         }
     });
 
-    tx
+    (tx, handle)
+}
+
+/// Send one notification and wait the minimum interval
+async fn send_paced(bot: &TelegramBot, notification: TelegramNotification) {
+    let sent = bot.notify(notification).await;
+    delivery().record(sent.is_ok(), Instant::now());
+    if let Err(e) = sent {
+        error!("[TELEGRAM] Failed to send notification: {}", e);
+    }
+    tokio::time::sleep(Duration::from_millis(MIN_SEND_INTERVAL_MS)).await;
+}
+
+/// Outcome of the notifications sent by every bot
+#[derive(Debug, Default)]
+pub struct DeliveryStats {
+    sent: AtomicU64,
+    failed: AtomicU64,
+    /// (time, delivered) of the latest sends, oldest first
+    recent: Mutex<VecDeque<(Instant, bool)>>,
+}
+
+static DELIVERY: OnceLock<DeliveryStats> = OnceLock::new();
+
+/// Counters shared by every bot's queue
+pub fn delivery() -> &'static DeliveryStats {
+    DELIVERY.get_or_init(DeliveryStats::default)
+}
+
+impl DeliveryStats {
+    pub fn record(&self, delivered: bool, now: Instant) {
+        let counter = if delivered { &self.sent } else { &self.failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() >= MAX_DELIVERY_SAMPLES {
+            recent.pop_front();
+        }
+        recent.push_back((now, delivered));
+    }
+
+    /// Notifications delivered since startup
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Notifications that failed since startup
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Failed share of the sends in the last `window` and how many there
+    /// were, None when too few to judge
+    pub fn failure_rate(&self, window: Duration, now: Instant) -> Option<(f64, usize)> {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        while recent.front().is_some_and(|(at, _)| now.duration_since(*at) > window) {
+            recent.pop_front();
+        }
+        let sends = recent.len();
+        let failed = recent.iter().filter(|(_, delivered)| !delivered).count();
+        (sends >= MIN_DELIVERY_SAMPLES).then(|| (failed as f64 / sends as f64, sends))
+    }
+}
+
+/// Raises one alert per stretch of failing sends
+pub struct DeliveryMonitor {
+    config: DeliveryAlertConfig,
+    alerted: bool,
+}
+
+impl DeliveryMonitor {
+    pub fn new(config: DeliveryAlertConfig) -> Self {
+        Self { config, alerted: false }
+    }
+
+    /// Failure rate and sends counted, once when the rate reaches the
+    /// threshold; re-armed when it is measured below again
+    pub fn check(&mut self, stats: &DeliveryStats, now: Instant) -> Option<(f64, usize)> {
+        let threshold = self.config.failure_rate?;
+        match stats.failure_rate(Duration::from_secs(self.config.window_secs), now) {
+            Some((rate, sends)) if rate >= threshold => {
+                (!std::mem::replace(&mut self.alerted, true)).then_some((rate, sends))
+            }
+            Some(_) => {
+                self.alerted = false;
+                None
+            }
+            None => None,
+        }
+    }
 }
 
-/// Optional wrapper for Telegram channel
+/// Check the delivery counters every minute, calling `on_failing` with the
+/// failure rate and the sends counted when Telegram looks down. Returns at
+/// once if the alert is disabled.
+pub async fn watch_delivery(config: DeliveryAlertConfig, mut on_failing: impl FnMut(f64, usize)) {
+    if config.failure_rate.is_none() {
+        return;
+    }
+    let mut monitor = DeliveryMonitor::new(config);
+    let mut ticker = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        ticker.tick().await;
+        if let Some((rate, sends)) = monitor.check(delivery(), Instant::now()) {
+            error!("[TELEGRAM] {:.0}% of the last {} notifications failed", rate * 100.0, sends);
+            on_failing(rate, sends);
+        }
+    }
+}
+
+/// Optional wrapper for Telegram channels: a default bot, plus bots that
+/// take over specific notification categories
 #[derive(Clone)]
 pub struct TelegramNotifier {
     channel: Option<TelegramChannel>,
+    routes: HashMap<NotificationCategory, TelegramChannel>,
 }
 
 impl TelegramNotifier {
     pub fn new(channel: Option<TelegramChannel>) -> Self {
-        Self { channel }
+        Self { channel, routes: HashMap::new() }
     }
 
     pub fn none() -> Self {
-        Self { channel: None }
+        Self::new(None)
     }
 
-    /// Send a notification (non-blocking)
+    /// Send `category` through `channel` instead of the default bot
+    pub fn with_route(mut self, category: NotificationCategory, channel: TelegramChannel) -> Self {
+        self.routes.insert(category, channel);
+        self
+    }
+
+    /// Send a notification (non-blocking) to its category's bot, or the default one
     pub fn notify(&self, notification: TelegramNotification) {
-        if let Some(ref tx) = self.channel {
+        let channel = self.routes.get(&notification.category()).or(self.channel.as_ref());
+        if let Some(tx) = channel {
             let _ = tx.try_send(notification);
         }
     }
 
     /// Check if Telegram is enabled
+    #[allow(dead_code)]
     pub fn is_enabled(&self) -> bool {
-        self.channel.is_some()
+        self.channel.is_some() || !self.routes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_bot(parse_mode: ParseMode) -> TelegramBot {
+        TelegramBot::new(TelegramConfig {
+            bot_token: "test".into(),
+            chat_ids: vec!["1".into()],
+            enabled: false,
+            max_retries: 0,
+            retry_backoff_ms: 0,
+            parse_mode,
+            min_severity: Severity::Info,
+            batch_window_ms: 0,
+            language: Language::Fr,
+            status_chart: false,
+            throttle: HashMap::new(),
+            profit_format: ProfitFormat::default(),
+            plain_text: false,
+            templates: MessageTemplates::default(),
+        })
+    }
+
+    #[test]
+    fn test_html_escapes_market_name() {
+        let bot = test_bot(ParseMode::Html);
+        let msg = bot.format_notification(TelegramNotification::OpportunityDetected {
+            market: "Will <Company> raise >$1B & more?".into(),
+            yes_price: Price::from_cents(40).unwrap(),
+            no_price: Price::from_cents(55).unwrap(),
+            profit_cents: 5,
+            arb_type: "<poly_only>".into(),
+            executable_profit_cents: 60,
+            contracts: 12,
+            confidence: 0.24,
+        });
+        assert!(msg.contains("YES: 40¢ | NO: 55¢"));
+        assert!(msg.contains("Exécutable: ~60¢ (12 contrats) | Confiance: 24%"));
+        assert!(msg.contains("5¢ (5.26%)"), "profit is relative to the 95¢ cost");
+
+        assert!(msg.contains("<code>Will &lt;Company&gt; raise &gt;$1B &amp; more?</code>"));
+        assert!(msg.contains("&lt;poly_only&gt;"));
+        assert!(!msg.contains("<Company>"));
+
+        // Only the builder's own tags remain
+        let stripped = msg
+            .replace("<b>", "").replace("</b>", "")
+            .replace("<code>", "").replace("</code>", "");
+        assert!(!stripped.contains('<') && !stripped.contains('>'));
+    }
+
+    #[test]
+    fn test_markdown_v2_escapes_reserved_characters() {
+        for c in "_*[]()~`>#+-=|{}.!\\".chars() {
+            assert_eq!(escape_markdown_v2(&c.to_string()), format!("\\{}", c), "{:?}", c);
+        }
+        assert_eq!(escape_markdown_v2("Lakers vs Celtics: 45¢"), "Lakers vs Celtics: 45¢");
+        assert_eq!(escape_markdown_v2("Will X win? (O/U 2.5) - $1B!"), "Will X win? \\(O/U 2\\.5\\) \\- $1B\\!");
+        assert_eq!(escape_markdown_v2(r"a\_b"), r"a\\\_b", "backslashes are escaped too");
+    }
+
+    #[test]
+    fn test_profit_pct_on_capital_deployed() {
+        // 100 contracts bought at 40¢ + 52¢: $92 deployed for a $3 net profit
+        let yes = Price::from_cents(40).unwrap();
+        let no = Price::from_cents(52).unwrap();
+        assert!((return_pct(3.0, yes, no) - 3.2609).abs() < 1e-4);
+        assert_eq!(return_pct(3.0, Price::ZERO, Price::ZERO), 0.0);
+
+        let opportunity = || TelegramNotification::OpportunityDetected {
+            market: "Lakers vs Celtics".into(),
+            yes_price: yes,
+            no_price: no,
+            profit_cents: 3,
+            arb_type: "poly_yes_kalshi_no".into(),
+            executable_profit_cents: 300,
+            contracts: 100,
+            confidence: 1.0,
+        };
+        let msg = test_bot(ParseMode::None).format_notification(opportunity());
+        assert!(msg.contains("Profit: 3¢ (3.26%)"), "{}", msg);
+        assert!(msg.contains("Exécutable: ~300¢ (100 contrats)"));
+
+        let mut bot = test_bot(ParseMode::None);
+        bot.config.profit_format = ProfitFormat { unit: Some(ProfitUnit::Dollars), decimals: Some(3) };
+        let msg = bot.format_notification(opportunity());
+        assert!(msg.contains("Profit: $0.030 (3.261%)"), "{}", msg);
+        assert!(msg.contains("Exécutable: ~$3.000 (100 contrats)"));
+    }
+
+    #[test]
+    fn test_language_selects_templates() {
+        let trade = || TelegramNotification::TradeExecuted {
+            market: "Lakers vs Celtics".into(),
+            contracts: 3,
+            requested_contracts: 5,
+            hedge_contracts: Some(3),
+            limit_hit: None,
+            profit_cents: 12,
+            success: true,
+            latency_ms: 42,
+            simulated: true,
+            unwound_contracts: 0,
+            cancelled_orders: 0,
+        };
+        let fr = test_bot(ParseMode::None).format_notification(trade());
+        let mut bot = test_bot(ParseMode::None);
+        bot.config.language = Language::En;
+        let en = bot.format_notification(trade());
+
+        assert_eq!(fr, "✅ Trade SUCCÈS (SIMULÉ)\n\n📈 Marché: Lakers vs Celtics\n📦 Contrats: 3 / 5 (taille réduite)\n⚖️ Taille par jambe: 3\n💵 Profit: 12¢\n⚡ Latence: 42ms");
+        assert_eq!(en, "✅ Trade SUCCESS (SIMULATED)\n\n📈 Market: Lakers vs Celtics\n📦 Contracts: 3 / 5 (reduced size)\n⚖️ Size per leg: 3\n💵 Profit: 12¢\n⚡ Latency: 42ms");
+        assert_eq!(Language::parse("EN"), Some(Language::En));
+        assert_eq!(Language::default(), Language::Fr);
+    }
+
+    #[test]
+    fn test_message_templates() {
+        let vars: HashMap<String, String> = [
+            ("TELEGRAM_TEMPLATE_TRADE_EXECUTED", "{status} {market}: {profit_cents}c in {latency_ms}ms {{ok}}"),
+            ("TELEGRAM_TEMPLATE_MARKET_STALE", "   "),
+        ].into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        let mut bot = test_bot(ParseMode::Html);
+        bot.config.templates = MessageTemplates::from_vars(|key| vars.get(key).cloned());
+        let trade = TelegramNotification::TradeExecuted {
+            market: "Aston <Villa>".into(),
+            contracts: 3,
+            requested_contracts: 3,
+            hedge_contracts: None,
+            limit_hit: None,
+            profit_cents: 12,
+            success: true,
+            latency_ms: 42,
+            simulated: false,
+            unwound_contracts: 0,
+            cancelled_orders: 0,
+        };
+        // Values are escaped like the built-in text
+        assert_eq!(bot.format_notification(trade.clone()), "SUCCÈS Aston &lt;Villa&gt;: 12c in 42ms {ok}");
+        bot.config.language = Language::En;
+        assert!(bot.format_notification(trade).starts_with("SUCCESS "));
+
+        // No template (or a blank one): the built-in text
+        let stale = bot.format_notification(TelegramNotification::MarketStale { market: "che".into(), ticks: 3, silent_secs: 30 });
+        assert!(stale.contains("Market Stale"), "{}", stale);
+
+        // Every placeholder a kind fills in is one it declares
+        for notification in [
+            TelegramNotification::BotStarted { mode: "DRY".into(), markets_count: 4 },
+            TelegramNotification::MarketStale { market: "che".into(), ticks: 3, silent_secs: 30 },
+            TelegramNotification::CircuitBreakerReset,
+        ] {
+            let (kind, fields) = notification.template_fields(Language::Fr).unwrap();
+            let known = TEMPLATE_PLACEHOLDERS.iter().find(|(k, _)| *k == kind).unwrap().1;
+            assert_eq!(fields.iter().map(|(name, _)| *name).collect::<Vec<_>>(), known, "{}", kind);
+        }
+    }
+
+    #[test]
+    fn test_template_errors_caught_at_startup() {
+        let vars: HashMap<&str, &str> = [
+            ("TELEGRAM_TEMPLATE_TRADE_EXECUTED", "{market} {profit}"),
+            ("TELEGRAM_TEMPLATE_FEED_DOWN", "{platform} down for {down_secs"),
+            ("TELEGRAM_TEMPLATE_ERROR", "oops} {message}"),
+            ("TELEGRAM_TEMPLATE_BOT_STOPPED", "{reason} at {time}"),
+        ].into_iter().collect();
+        let errors = template_errors(|key| vars.get(key).map(|v| v.to_string()));
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].starts_with("TELEGRAM_TEMPLATE_TRADE_EXECUTED=") && errors[0].contains("unknown placeholder {profit}"), "{}", errors[0]);
+        assert!(errors.iter().any(|e| e.contains("FEED_DOWN") && e.contains("unclosed")));
+        assert!(errors.iter().any(|e| e.contains("ERROR") && e.contains("unmatched")));
+
+        // Invalid templates are not used
+        let templates = MessageTemplates::from_vars(|key| vars.get(key).map(|v| v.to_string()));
+        assert_eq!(templates.get("trade_executed"), None);
+        assert_eq!(templates.get("bot_stopped"), Some("{reason} at {time}"));
+        assert_eq!(template_parts("a {{b}} {c}").unwrap(), vec![TemplatePart::Text("a {b} ".into()), TemplatePart::Field("c")]);
+    }
+
+    #[test]
+    fn test_plain_text_mode() {
+        let mut bot = test_bot(ParseMode::Html);
+        bot.config.plain_text = true;
+        assert_eq!(bot.config.message_mode().api_value(), None);
+        let msg = bot.format_notification(TelegramNotification::TradeExecuted {
+            market: "Aston <Villa> & Chelsea".into(),
+            contracts: 3,
+            requested_contracts: 5,
+            hedge_contracts: Some(3),
+            limit_hit: None,
+            profit_cents: 12,
+            success: false,
+            latency_ms: 42,
+            simulated: true,
+            unwound_contracts: 0,
+            cancelled_orders: 0,
+        });
+        assert_eq!(msg, "Trade ECHEC (SIMULE)\n\nMarche: Aston <Villa> & Chelsea\nContrats: 3 / 5 (taille reduite)\nTaille par jambe: 3\nProfit: 12c\nLatence: 42ms");
+        assert_eq!(plain_ascii("🎯 3 → ≥5¢ · fin 🏁\n  • ok"), "3 -> >=5c - fin\n  - ok");
+    }
+
+    /// Local Bot API stand-in: answers each request with the next canned
+    /// (status, body) and records the method and JSON body it received
+    async fn mock_api(responses: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<(String, serde_json::Value)>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/botTEST", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let (head_len, content_len) = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&buf[..pos]).to_lowercase();
+                        let len = head.lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .and_then(|v| v.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        break (pos + 4, len);
+                    }
+                };
+                while buf.len() < head_len + content_len {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let head = String::from_utf8_lossy(&buf[..head_len]).to_string();
+                let method = head.split_whitespace().nth(1).unwrap_or("").rsplit('/').next().unwrap_or("").to_string();
+                let json = serde_json::from_slice(&buf[head_len..]).unwrap_or_default();
+                seen.lock().unwrap().push((method, json));
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (base, requests)
+    }
+
+    #[tokio::test]
+    async fn test_status_message_edited_in_place() {
+        let (api_base, requests) = mock_api(vec![
+            (200, r#"{"ok":true,"result":{"message_id":7}}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+            (200, r#"{"ok":true,"result":{"message_id":7}}"#),
+            (400, r#"{"ok":false,"description":"Bad Request: message to edit not found"}"#),
+            (200, r#"{"ok":true,"result":{"message_id":9}}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+        ]).await;
+        let mut bot = test_bot(ParseMode::None);
+        bot.config.enabled = true;
+        bot.api_base = api_base;
+
+        // First update is sent and pinned, the next one edits it
+        bot.update_status_message("first", None).await.unwrap();
+        bot.update_status_message("second", None).await.unwrap();
+        // Edit fails (message deleted): a fresh message replaces it
+        bot.update_status_message("third", None).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|(m, _)| m.as_str()).collect();
+        assert_eq!(methods, ["sendMessage", "pinChatMessage", "editMessageText", "editMessageText", "sendMessage", "pinChatMessage"]);
+        assert_eq!(requests[2].1["message_id"], 7);
+        assert_eq!(requests[2].1["text"], "second");
+        assert_eq!(requests[4].1["text"], "third");
+        assert_eq!(bot.status_messages.lock().unwrap().get("1").map(|m| m.id), Some(9));
+    }
+
+    fn approval_request(timeout: Duration) -> (ApprovalRequest, oneshot::Receiver<ApprovalDecision>) {
+        let (reply, rx) = oneshot::channel();
+        let request = ApprovalRequest {
+            id: 4,
+            market: "Chiefs vs Bills".into(),
+            arb_type: crate::types::ArbType::PolyYesKalshiNo,
+            contracts: 50,
+            notional: 48.5,
+            expected_profit_cents: 150,
+            timeout,
+            reply,
+        };
+        (request, rx)
+    }
+
+    #[test]
+    fn test_parse_approval_callback() {
+        assert_eq!(parse_approval_callback("approve:12"), Some((ApprovalDecision::Approved, 12)));
+        assert_eq!(parse_approval_callback("reject:3"), Some((ApprovalDecision::Rejected, 3)));
+        assert_eq!(parse_approval_callback("approve:"), None);
+        assert_eq!(parse_approval_callback("flatten:1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_approval_answered_by_button() {
+        let (api_base, requests) = mock_api(vec![
+            (200, r#"{"ok":true,"result":{"message_id":5}}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+        ]).await;
+        let mut bot = test_bot(ParseMode::None);
+        bot.config.enabled = true;
+        bot.api_base = api_base;
+
+        let (request, rx) = approval_request(Duration::from_secs(60));
+        let approver = bot.clone();
+        tokio::spawn(async move { approver.request_approval(request).await });
+        while bot.approvals.lock().unwrap().get(&4).is_none_or(|p| p.messages.is_empty()) {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let press = |chat: i64| CallbackQuery {
+            id: "cb".into(),
+            message: Some(Message { chat: Chat { id: chat }, text: None }),
+            data: Some("approve:4".into()),
+        };
+        // Presses from other chats are not decisions
+        bot.handle_callback(press(99)).await;
+        assert!(bot.approvals.lock().unwrap().contains_key(&4));
+        bot.handle_callback(press(1)).await;
+        assert_eq!(rx.await.unwrap(), ApprovalDecision::Approved);
+
+        let requests = requests.lock().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|(m, _)| m.as_str()).collect();
+        assert_eq!(methods, ["sendMessage", "answerCallbackQuery", "editMessageText", "answerCallbackQuery"]);
+        assert_eq!(requests[0].1["reply_markup"]["inline_keyboard"][0][0]["callback_data"], "approve:4");
+        assert!(requests[1].1.get("text").is_none());
+        assert_eq!(requests[2].1["message_id"], 5);
+        assert!(requests[2].1["text"].as_str().unwrap().ends_with("✅ Approuvé"));
+        assert!(requests[2].1.get("reply_markup").is_none(), "buttons removed once decided");
+        assert_eq!(requests[3].1["text"], "Trade approuvé");
+    }
+
+    #[tokio::test]
+    async fn test_approval_times_out() {
+        let (api_base, _requests) = mock_api(vec![
+            (200, r#"{"ok":true,"result":{"message_id":5}}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+        ]).await;
+        let mut bot = test_bot(ParseMode::None);
+        bot.config.enabled = true;
+        bot.api_base = api_base;
+
+        let (request, rx) = approval_request(Duration::from_millis(50));
+        bot.request_approval(request).await;
+        assert_eq!(rx.await.unwrap(), ApprovalDecision::TimedOut);
+        // A late press finds nothing to settle
+        assert!(!bot.resolve_approval(4, ApprovalDecision::Approved).await);
+
+        // Nothing delivered: the reply is dropped
+        let (request, rx) = approval_request(Duration::from_millis(50));
+        bot.api_base = "http://127.0.0.1:1/botTEST".into();
+        bot.request_approval(request).await;
+        assert!(rx.await.is_err());
+    }
+
+    #[test]
+    fn test_throttle_per_kind_and_market() {
+        let intervals = parse_throttle("opportunity_detected=60, status_update=3600,error=10,bogus");
+        assert_eq!(intervals.len(), 2, "critical and unknown kinds are not throttled");
+        let mut throttle = NotificationThrottle::new(intervals);
+        let opportunity = |market: &str| TelegramNotification::OpportunityDetected {
+            market: market.into(),
+            yes_price: Price::from_cents(40).unwrap(),
+            no_price: Price::from_cents(55).unwrap(),
+            profit_cents: 5,
+            arb_type: "PolyOnly".into(),
+            executable_profit_cents: 60,
+            contracts: 12,
+            confidence: 0.24,
+        };
+        let error = TelegramNotification::Error { message: "boom".into() };
+        let t0 = Instant::now();
+
+        assert!(throttle.allow(&opportunity("Chelsea vs Aston Villa"), t0));
+        assert!(!throttle.allow(&opportunity("Chelsea vs Aston Villa"), t0 + Duration::from_secs(59)));
+        assert!(throttle.allow(&opportunity("Lakers vs Celtics"), t0 + Duration::from_secs(1)), "throttled per market");
+        assert!(throttle.allow(&opportunity("Chelsea vs Aston Villa"), t0 + Duration::from_secs(60)));
+        for _ in 0..3 {
+            assert!(throttle.allow(&error, t0), "errors always go out");
+        }
+        assert!(throttle.allow(&TelegramNotification::CircuitBreakerReset, t0));
+        assert!(throttle.allow(&TelegramNotification::CircuitBreakerReset, t0), "no interval configured");
+    }
+
+    #[test]
+    fn test_routes_from_env_vars() {
+        let config = test_bot(ParseMode::Html).config;
+        let vars: HashMap<&str, &str> = [
+            ("TELEGRAM_OPPORTUNITY_CHAT_ID", "-100200"),
+            ("TELEGRAM_TRADE_BOT_TOKEN", "alerts"),
+            ("TELEGRAM_TRADE_CHAT_ID", "-100300"),
+            ("TELEGRAM_ALERT_BOT_TOKEN", "alerts"),
+            ("TELEGRAM_ALERT_CHAT_ID", "-100300"),
+            // Same as the default bot: not a separate route
+            ("TELEGRAM_STATUS_CHAT_ID", "1"),
+        ].into_iter().collect();
+
+        let routes = config.routes_from(|key| vars.get(key).map(|v| v.to_string()));
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].categories, [NotificationCategory::Opportunity]);
+        assert_eq!(routes[0].config.bot_token, "test", "token falls back to the default bot's");
+        assert_eq!(routes[0].config.chat_ids, ["-100200"]);
+        assert_eq!(routes[1].categories, [NotificationCategory::Trade, NotificationCategory::Alert]);
+        assert_eq!(routes[1].config.bot_token, "alerts");
+        assert_eq!(routes[1].config.parse_mode, ParseMode::Html, "other settings are shared");
+    }
+
+    #[test]
+    fn test_notifier_routes_by_category() {
+        let (default_tx, mut default_rx) = mpsc::channel(4);
+        let (alert_tx, mut alert_rx) = mpsc::channel(4);
+        let notifier = TelegramNotifier::new(Some(default_tx))
+            .with_route(NotificationCategory::Alert, alert_tx);
+
+        notifier.notify(TelegramNotification::Error { message: "boom".into() });
+        notifier.notify(TelegramNotification::CircuitBreakerReset);
+        notifier.notify(TelegramNotification::BotStopped { reason: "SIGTERM".into() });
+
+        assert!(matches!(alert_rx.try_recv(), Ok(TelegramNotification::Error { .. })));
+        assert!(matches!(alert_rx.try_recv(), Ok(TelegramNotification::CircuitBreakerReset)));
+        assert!(alert_rx.try_recv().is_err());
+        assert!(matches!(default_rx.try_recv(), Ok(TelegramNotification::BotStopped { .. })));
+        assert!(default_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_bot_command_parse() {
+        assert_eq!(BotCommand::parse("/status"), Some(BotCommand::Status));
+        assert_eq!(BotCommand::parse("/Pause now"), Some(BotCommand::Pause));
+        assert_eq!(BotCommand::parse("/resume@arb_bot"), Some(BotCommand::Resume));
+        assert_eq!(BotCommand::parse("  /positions"), Some(BotCommand::Positions));
+        assert_eq!(BotCommand::parse("/flatten"), Some(BotCommand::Flatten));
+        assert_eq!(BotCommand::parse("/disable KXEPLGAME-25DEC27CFCAVL-CFC"), Some(BotCommand::Disable("KXEPLGAME-25DEC27CFCAVL-CFC".into())));
+        assert_eq!(BotCommand::parse("/enable@arb_bot epl-che-avl"), Some(BotCommand::Enable("epl-che-avl".into())));
+        assert_eq!(BotCommand::parse("/disable"), Some(BotCommand::Disabled));
+        assert_eq!(BotCommand::parse("/disabled"), Some(BotCommand::Disabled));
+        assert_eq!(BotCommand::parse("/pnl"), Some(BotCommand::Pnl(Some(PnlWindow::Today))));
+        assert_eq!(BotCommand::parse("/pnl@arb_bot Week"), Some(BotCommand::Pnl(Some(PnlWindow::Week))));
+        assert_eq!(BotCommand::parse("/pnl all"), Some(BotCommand::Pnl(Some(PnlWindow::All))));
+        assert_eq!(BotCommand::parse("/pnl month"), Some(BotCommand::Pnl(None)), "answered with the usage");
+        assert_eq!(BotCommand::parse("status"), None);
+        assert_eq!(BotCommand::parse("/unknown"), None);
+    }
+
+    #[test]
+    fn test_performance_tracker_persistence() {
+        let path = std::env::temp_dir().join(format!("perf_stats_{}.json", std::process::id()));
+
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_trade(true, 12);
+        tracker.record_trade(false, 0);
+        tracker.record_opportunity();
+        tracker.save_to_path(&path).unwrap();
+
+        let loaded = PerformanceTracker::load_from_path(&path, 0).unwrap();
+        assert_eq!(loaded.total_trades, 2);
+        assert_eq!(loaded.successful_trades, 1);
+        assert_eq!(loaded.total_profit_cents, 12);
+        assert_eq!(loaded.opportunities_detected, 1);
+        assert_eq!(loaded.first_started_at, tracker.first_started_at);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(PerformanceTracker::load_from_path(&path, 0).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_unwound_partial_fill_loss_is_booked() {
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_trade_for("NFL-A", "poly_only", true, 5);
+        // Nothing hedged, the excess was sold back 8¢ under cost
+        tracker.record_trade_for("NFL-A", "poly_only", false, -8);
+
+        assert_eq!(tracker.successful_trades, 1);
+        assert_eq!(tracker.total_profit_cents, -3);
+        assert_eq!(tracker.by_market["NFL-A"].profit_cents, -3);
+        assert_eq!(tracker.profit_series().last().map(|p| p.1), Some(-3));
+    }
+
+    #[test]
+    fn test_expired_orders_stay_out_of_success_rate() {
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_trade(true, 4);
+        // Both legs expired unfilled, then one leg expired after the other filled and was unwound
+        tracker.record_cancelled(2, 0);
+        tracker.record_cancelled(1, -6);
+
+        assert_eq!((tracker.successful_trades, tracker.total_trades), (1, 1));
+        assert_eq!(tracker.cancelled_orders, 3);
+        assert_eq!(tracker.total_profit_cents, -2);
+    }
+
+    #[test]
+    fn test_daily_pnl_rolls_over_with_trading_day() {
+        use chrono::TimeZone;
+        let at = |d, h, m| chrono::Utc.with_ymd_and_hms(2026, 3, d, h, m, 0).unwrap();
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_trade_at(false, -300, at(14, 23, 59));
+        tracker.record_trade_at(true, 100, at(14, 23, 59));
+        assert_eq!(tracker.daily_pnl_at(at(14, 23, 59)), -200);
+        assert_eq!(tracker.daily_pnl_at(at(15, 0, 1)), 0, "a new day starts at zero");
+        tracker.record_trade_at(false, -50, at(15, 0, 1));
+        assert_eq!(tracker.daily_pnl_at(at(15, 0, 1)), -50);
+
+        // The loss limit's day is the trading day, across UTC midnight
+        let mut tracker = PerformanceTracker::new(6);
+        tracker.record_trade_at(false, -300, at(14, 23, 59));
+        tracker.record_trade_at(false, -50, at(15, 5, 59));
+        assert_eq!(tracker.daily_pnl_at(at(15, 5, 59)), -350);
+        assert_eq!(tracker.daily_pnl_at(at(15, 5, 59)), tracker.today.profit_cents);
+        assert_eq!(tracker.daily_pnl_at(at(15, 6, 0)), 0);
+
+        // Live trades count towards today, paper trades don't
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_trade(false, -40);
+        tracker.record_cancelled(1, -10);
+        tracker.record_paper_trade(true, 25);
+        assert_eq!(tracker.daily_pnl_at(chrono::Utc::now()), -50);
+    }
+
+    #[test]
+    fn test_today_resets_at_configured_hour() {
+        use chrono::TimeZone;
+        let at = |d, h, m| chrono::Utc.with_ymd_and_hms(2026, 3, d, h, m, 0).unwrap();
+        let mut tracker = PerformanceTracker::new(6);
+        tracker.record_trade_at(true, 5, at(14, 23, 0));
+        // Still the same trading day after UTC midnight
+        tracker.record_trade_at(false, -2, at(15, 5, 59));
+        assert_eq!(tracker.today, DailyStats { started_at: at(14, 6, 0).timestamp(), trades: 2, successful_trades: 1, profit_cents: 3 });
+
+        tracker.record_trade_at(true, 4, at(15, 6, 0));
+        assert_eq!((tracker.today.trades, tracker.today.profit_cents), (1, 4));
+        assert_eq!((tracker.total_trades, tracker.total_profit_cents), (3, 7), "lifetime counters keep going");
+
+        // A restart later the same trading day keeps its counters
+        let path = std::env::temp_dir().join(format!("perf_today_{}.json", std::process::id()));
+        tracker.save_to_path(&path).unwrap();
+        let mut loaded = PerformanceTracker::load_from_path(&path, 6).unwrap();
+        std::fs::remove_file(&path).ok();
+        loaded.roll_today(at(15, 20, 0));
+        assert_eq!(loaded.today.trades, 1);
+        loaded.roll_today(at(16, 6, 0));
+        assert_eq!(loaded.today, DailyStats { started_at: at(16, 6, 0).timestamp(), ..DailyStats::default() });
+    }
+
+    #[test]
+    fn test_pnl_windows() {
+        use chrono::TimeZone;
+        let at = |d, h| chrono::Utc.with_ymd_and_hms(2026, 3, d, h, 0, 0).unwrap();
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_trade_at(true, 50, at(1, 12));
+        tracker.record_trade_at(true, 5, at(9, 12));
+        tracker.record_trade_at(false, -2, at(12, 12));
+        tracker.record_trade_at(false, 0, at(15, 9));
+        tracker.record_trade_at(true, 4, at(15, 10));
+
+        let now = at(15, 18);
+        assert_eq!(tracker.pnl(PnlWindow::Today, now), DailyStats { started_at: at(15, 0).timestamp(), trades: 2, successful_trades: 1, profit_cents: 4 });
+        assert_eq!(tracker.pnl(PnlWindow::Week, now), DailyStats { started_at: at(9, 0).timestamp(), trades: 4, successful_trades: 2, profit_cents: 7 });
+        let all = tracker.pnl(PnlWindow::All, now);
+        assert_eq!((all.trades, all.successful_trades, all.profit_cents), (5, 3, 57));
+
+        // The week survives a restart
+        let path = std::env::temp_dir().join(format!("perf_pnl_{}.json", std::process::id()));
+        tracker.save_to_path(&path).unwrap();
+        let mut loaded = PerformanceTracker::load_from_path(&path, 0).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.pnl(PnlWindow::Week, now).profit_cents, 7);
+        // Only the days of the week are kept
+        assert_eq!(loaded.past_days.iter().map(|d| d.started_at).collect::<Vec<_>>(), vec![at(9, 0).timestamp(), at(12, 0).timestamp()]);
+    }
+
+    #[test]
+    fn test_performance_tracker_breakdown() {
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_trade_for("NFL-A", "poly_only", true, 5);
+        tracker.record_trade_for("NFL-B", "poly_only", true, 9);
+        tracker.record_trade_for("NFL-A", "poly_yes_kalshi_no", true, 7);
+        tracker.record_trade_for("NFL-C", "poly_only", false, 0);
+        tracker.record_trade(true, 3);
+
+        assert_eq!(tracker.total_trades, 5);
+        assert_eq!(tracker.total_profit_cents, 24);
+        assert_eq!(tracker.by_arb_type["poly_only"].trades, 3);
+        assert_eq!(tracker.by_arb_type["poly_only"].profit_cents, 14);
+        assert_eq!(tracker.top_markets(2), vec![("NFL-A".to_string(), 12), ("NFL-B".to_string(), 9)]);
+    }
+
+    #[test]
+    fn test_paper_trade_loss_is_booked() {
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_paper_trade(true, 10);
+        tracker.record_paper_trade(false, -4);
+        tracker.record_paper_trade(false, 0);
+        assert_eq!((tracker.paper_trades, tracker.paper_successful_trades, tracker.paper_profit_cents), (3, 1, 6));
+        let series: Vec<i64> = tracker.profit_series().iter().map(|p| p.1).collect();
+        assert_eq!(series, [10, 6], "a failed trade without P&L adds no point");
+    }
+
+    #[test]
+    fn test_profit_history_series_and_cap() {
+        let mut tracker = PerformanceTracker::new(0);
+        tracker.record_paper_trade(true, 4);
+        tracker.record_paper_trade(true, 6);
+        tracker.record_trade(false, 0);
+        // Dry run only: the chart plots paper profit
+        let series: Vec<i64> = tracker.profit_series().iter().map(|p| p.1).collect();
+        assert_eq!(series, [4, 10]);
+
+        tracker.record_trade(true, 3);
+        let series: Vec<i64> = tracker.profit_series().iter().map(|p| p.1).collect();
+        assert_eq!(series, [3]);
+
+        for _ in 0..MAX_PROFIT_HISTORY {
+            tracker.record_trade(true, 1);
+        }
+        assert_eq!(tracker.profit_history.len(), MAX_PROFIT_HISTORY);
+        assert_eq!(tracker.profit_history.last().unwrap().profit_cents, 3 + MAX_PROFIT_HISTORY as i64);
+    }
+
+    #[tokio::test]
+    async fn test_status_message_switches_to_photo() {
+        let (api_base, requests) = mock_api(vec![
+            (200, r#"{"ok":true,"result":{"message_id":7}}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+            (200, r#"{"ok":true,"result":{"message_id":8}}"#),
+            (200, r#"{"ok":true,"result":true}"#),
+            (200, r#"{"ok":true,"result":{"message_id":8}}"#),
+        ]).await;
+        let mut bot = test_bot(ParseMode::None);
+        bot.config.enabled = true;
+        bot.api_base = api_base;
+
+        // A text status message can't be edited into a photo: a new one is sent
+        bot.update_status_message("text", None).await.unwrap();
+        bot.update_status_message("chart", Some(b"png")).await.unwrap();
+        bot.update_status_message("chart", Some(b"png")).await.unwrap();
+
+        let methods: Vec<String> = requests.lock().unwrap().iter().map(|(m, _)| m.clone()).collect();
+        assert_eq!(methods, ["sendMessage", "pinChatMessage", "sendPhoto", "pinChatMessage", "editMessageMedia"]);
+        assert_eq!(bot.status_messages.lock().unwrap().get("1"), Some(&StatusMessage { id: 8, photo: true }));
+    }
+
+    #[test]
+    fn test_failing_delivery_alerted_once() {
+        let config = DeliveryAlertConfig { failure_rate: Some(0.5), window_secs: 60 };
+        let mut monitor = DeliveryMonitor::new(config);
+        let stats = DeliveryStats::default();
+        let t = Instant::now();
+        for delivered in [true, false, false, false] {
+            stats.record(delivered, t);
+        }
+        assert_eq!(monitor.check(&stats, t), None, "too few sends to judge");
+
+        stats.record(false, t + Duration::from_secs(1));
+        assert_eq!(monitor.check(&stats, t + Duration::from_secs(1)), Some((0.8, 5)));
+        assert_eq!(monitor.check(&stats, t + Duration::from_secs(2)), None, "already alerted");
+        assert_eq!((stats.sent(), stats.failed()), (1, 4));
+
+        // Recovered: the old failures leave the window and the alert re-arms
+        let later = t + Duration::from_secs(120);
+        for _ in 0..5 {
+            stats.record(true, later);
+        }
+        assert_eq!(monitor.check(&stats, later), None);
+        for _ in 0..5 {
+            stats.record(false, later);
+        }
+        assert_eq!(monitor.check(&stats, later), Some((0.5, 10)));
     }
 }
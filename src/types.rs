@@ -4,10 +4,12 @@
 //! orderbook representation, and arbitrage opportunity detection.
 
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI16, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 use rustc_hash::FxHashMap;
 
+use crate::config::{FeeSchedule, Liquidity, SpreadCaptureConfig};
+
 // === Market Types ===
 
 /// Market category for a matched trading pair
@@ -45,8 +47,11 @@ pub struct MarketPair {
     pub market_type: MarketType,
     /// Human-readable market description
     pub description: Arc<str>,
-    /// Kalshi event ticker identifier
+    /// Kalshi event ticker identifier; pairs sharing it settle on the same event
     pub kalshi_event_ticker: Arc<str>,
+    /// Kalshi series the event belongs to (empty in caches written before it was recorded)
+    #[serde(default)]
+    pub kalshi_series_ticker: Arc<str>,
     /// Kalshi market ticker identifier
     pub kalshi_market_ticker: Arc<str>,
     /// Polymarket market slug
@@ -55,16 +60,183 @@ pub struct MarketPair {
     pub poly_yes_token: Arc<str>,
     /// Polymarket NO outcome token address
     pub poly_no_token: Arc<str>,
+    /// Tick and minimum order size of the Polymarket market, from discovery
+    #[serde(default)]
+    pub poly_constraints: OrderConstraints,
     /// Line value for spread/total markets (if applicable)
     pub line_value: Option<f64>,
     /// Team suffix for team-specific markets
     pub team_suffix: Option<Arc<str>>,
 }
 
+/// Order constraints Polymarket sets per market: prices must sit on the tick
+/// and orders carry at least `min_size` contracts, or the CLOB rejects them
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OrderConstraints {
+    /// Price increment in basis points of a dollar (100 = 1¢)
+    pub tick_bps: u64,
+    /// Smallest order in contracts
+    pub min_size: f64,
+}
+
+impl Default for OrderConstraints {
+    /// Polymarket's usual 1¢ tick and 5-share minimum
+    fn default() -> Self {
+        Self { tick_bps: 100, min_size: 5.0 }
+    }
+}
+
+impl OrderConstraints {
+    /// Highest price on the tick at or below `price`, for buy limits; `None`
+    /// if nothing tradable is left
+    pub fn floor_price(&self, price: PriceCents) -> Option<PriceCents> {
+        let tick = self.tick_bps.max(1);
+        let bps = price as u64 * 100 / tick * tick;
+        Price::from_bps(bps).filter(|p| p.is_tradable()).map(Price::cents)
+    }
+
+    /// Lowest price on the tick at or above `price`, for sell limits
+    pub fn ceil_price(&self, price: PriceCents) -> Option<PriceCents> {
+        let tick = self.tick_bps.max(1);
+        let bps = (price as u64 * 100).div_ceil(tick) * tick;
+        Price::from_bps(bps).filter(|p| p.is_tradable()).map(Price::cents)
+    }
+
+    pub fn on_tick(&self, price: Price) -> bool {
+        price.to_bps().is_multiple_of(self.tick_bps.max(1))
+    }
+
+    pub fn allows_size(&self, contracts: f64) -> bool {
+        contracts >= self.min_size
+    }
+}
+
+/// One outcome of a `MultiOutcomeMarket`; its token pays $1 if the outcome wins
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub name: Arc<str>,
+    /// Polymarket token bought for this outcome
+    pub token: Arc<str>,
+    /// Tick and minimum order size of the outcome's market
+    #[serde(default)]
+    pub constraints: OrderConstraints,
+}
+
+/// Market whose outcomes are mutually exclusive and exhaustive: exactly one
+/// pays out, so one share of every outcome is worth $1 at resolution. A
+/// Polymarket neg-risk event (one YES token per outcome) is such a market;
+/// a binary market is the two-outcome case, its YES and NO tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiOutcomeMarket {
+    /// Polymarket event slug (the market slug for a binary pair)
+    pub event_slug: Arc<str>,
+    pub description: Arc<str>,
+    /// Outcomes trade on the neg-risk exchange
+    pub neg_risk: bool,
+    pub outcomes: Vec<Outcome>,
+}
+
+/// Full-set arbitrage on a `MultiOutcomeMarket`: buy every outcome when the
+/// asks sum to less than 100¢
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiOutcomeRequest {
+    /// Index into `GlobalState::multi_outcome`
+    pub market_index: u16,
+    /// (best ask, size) per outcome, in the market's outcome order
+    pub asks: Vec<(PriceCents, SizeCents)>,
+    /// Detection timestamp in nanoseconds since system start
+    pub detected_ns: u64,
+}
+
+impl MultiOutcomeRequest {
+    /// Cost of one share of every outcome at the asks
+    pub fn cost_cents(&self) -> u32 {
+        self.asks.iter().map(|&(price, _)| price as u32).sum()
+    }
+
+    /// Full sets fillable at the top of every book
+    pub fn contracts(&self) -> i64 {
+        self.asks.iter().map(|&(_, size)| (size / 100) as i64).min().unwrap_or(0)
+    }
+
+    /// Profit per full set in cents after Polymarket taker fees on every leg
+    /// and fixed costs; `None` while an outcome has no ask
+    pub fn net_profit_cents(&self, fees: &FeeSchedule) -> Option<f64> {
+        if self.asks.is_empty() || self.asks.iter().any(|&(price, _)| price == NO_PRICE) {
+            return None;
+        }
+        let contracts = self.contracts();
+        let leg_fees: f64 = self.asks.iter()
+            .map(|&(price, _)| fees.fee_cents(Platform::Polymarket, price as f64, contracts, Liquidity::Taker))
+            .sum();
+        Some(100.0 - self.cost_cents() as f64 - leg_fees - fees.fixed_cost_cents)
+    }
+
+    /// Loss if the legs that filled all lose: everything but the cheapest
+    /// outcome (with two legs, the dearer one)
+    pub fn worst_case_loss_cents(&self, contracts: i64) -> i64 {
+        let cheapest = self.asks.iter().map(|&(price, _)| price as i64).min().unwrap_or(0);
+        (self.cost_cents() as i64 - cheapest) * contracts
+    }
+}
+
+/// Top of a paired market's Polymarket YES book, wide enough for
+/// `Strategy::SpreadCapture` to quote inside it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadRequest {
+    pub market_id: u16,
+    /// Best YES bid
+    pub bid: PriceCents,
+    /// Best YES ask
+    pub ask: PriceCents,
+    /// Detection timestamp in nanoseconds since system start
+    pub detected_ns: u64,
+}
+
+/// Two-sided quote on one market: a YES bid, and a YES ask placed as a NO bid
+/// at `100 - ask`. A side is `None` while inventory is at its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpreadQuote {
+    /// (price, contracts)
+    pub bid: Option<(PriceCents, i64)>,
+    pub ask: Option<(PriceCents, i64)>,
+}
+
+impl SpreadRequest {
+    pub fn spread_cents(&self) -> u16 {
+        self.ask.saturating_sub(self.bid)
+    }
+
+    /// Quotes one cent inside the spread, skewed against `inventory` (YES
+    /// minus NO contracts). Quotes never reach the opposite best, so they
+    /// rest as makers; `None` if no side is left to quote.
+    pub fn quote(&self, config: &SpreadCaptureConfig, inventory: f64) -> Option<SpreadQuote> {
+        let excess = inventory - config.target_inventory;
+        let skew = (config.skew_cents * (excess / config.max_inventory).clamp(-1.0, 1.0)).round() as i32;
+        let (bid, ask) = (self.bid as i32, self.ask as i32);
+        let quote_bid = (bid + 1 - skew).min(ask - 1);
+        let quote_ask = (ask - 1 - skew).max(bid + 1);
+        if quote_bid >= quote_ask {
+            return None;
+        }
+
+        let side = |price: i32, room: f64| {
+            let contracts = (config.quote_contracts as f64).min(room.floor()) as i64;
+            ((1..=99).contains(&price) && contracts >= 1).then_some((price as PriceCents, contracts))
+        };
+        let quote = SpreadQuote {
+            bid: side(quote_bid, config.max_inventory - excess),
+            ask: side(quote_ask, config.max_inventory + excess),
+        };
+        (quote.bid.is_some() || quote.ask.is_some()).then_some(quote)
+    }
+}
+
 /// Price representation in cents (1-99 for $0.01-$0.99), 0 indicates no price available
 pub type PriceCents = u16;
 
-/// Size representation in cents (dollar amount × 100), maximum ~$655k per side
+/// Size as payout notional in cents (contracts × 100, one contract pays $1),
+/// maximum ~655 contracts per side
 pub type SizeCents = u16;
 
 /// Maximum number of concurrently tracked markets
@@ -96,11 +268,88 @@ pub fn unpack_orderbook(packed: u64) -> (PriceCents, PriceCents, SizeCents, Size
 pub struct AtomicOrderbook {
     /// Packed orderbook state: [yes_ask:16][no_ask:16][yes_size:16][no_size:16]
     packed: AtomicU64,
+    /// Unix timestamp in milliseconds of the last store or update (0 = never)
+    updated_ms: AtomicU64,
+    /// Since when the platform's book has been crossed or locked (0 = valid)
+    crossed_since_ms: AtomicU64,
+    /// Crossed past the stale delay; its prices were cleared
+    stale: AtomicBool,
+}
+
+/// Outcome of `AtomicOrderbook::check_crossed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookCheck {
+    Valid,
+    /// Valid again after being crossed for `crossed_ms`
+    Recovered { crossed_ms: u64 },
+    /// Crossed or locked; `first` on the update that crossed it
+    Crossed { first: bool },
+    /// Crossed past the stale delay, reported once per crossing
+    Stale { crossed_ms: u64 },
 }
 
 impl AtomicOrderbook {
     pub const fn new() -> Self {
-        Self { packed: AtomicU64::new(0) }
+        Self {
+            packed: AtomicU64::new(0),
+            updated_ms: AtomicU64::new(0),
+            crossed_since_ms: AtomicU64::new(0),
+            stale: AtomicBool::new(false),
+        }
+    }
+
+    /// Current state, with no prices while the book is crossed or locked:
+    /// a glitched book reads as a huge free opportunity
+    #[inline(always)]
+    pub fn quotes(&self) -> (PriceCents, PriceCents, SizeCents, SizeCents) {
+        if self.is_crossed() {
+            return (NO_PRICE, NO_PRICE, 0, 0);
+        }
+        self.load()
+    }
+
+    #[inline(always)]
+    pub fn is_crossed(&self) -> bool {
+        self.crossed_since_ms.load(Ordering::Acquire) != 0
+    }
+
+    /// Crossed past the stale delay
+    pub fn is_stale(&self) -> bool {
+        self.stale.load(Ordering::Acquire)
+    }
+
+    /// Record whether the platform's book is crossed or locked at `now_ms`
+    pub fn check_crossed(&self, crossed: bool, now_ms: u64, stale_after_ms: u64) -> BookCheck {
+        let since = self.crossed_since_ms.load(Ordering::Acquire);
+        match (crossed, since) {
+            (false, 0) => BookCheck::Valid,
+            (false, since) => {
+                self.crossed_since_ms.store(0, Ordering::Release);
+                self.stale.store(false, Ordering::Release);
+                BookCheck::Recovered { crossed_ms: now_ms.saturating_sub(since) }
+            }
+            (true, 0) => {
+                self.crossed_since_ms.store(now_ms.max(1), Ordering::Release);
+                BookCheck::Crossed { first: true }
+            }
+            (true, since) => {
+                let crossed_ms = now_ms.saturating_sub(since);
+                if crossed_ms >= stale_after_ms && !self.stale.swap(true, Ordering::AcqRel) {
+                    BookCheck::Stale { crossed_ms }
+                } else {
+                    BookCheck::Crossed { first: false }
+                }
+            }
+        }
+    }
+
+    /// When the prices were last written, `None` before the first update
+    #[inline(always)]
+    pub fn updated_ms(&self) -> Option<u64> {
+        match self.updated_ms.load(Ordering::Acquire) {
+            0 => None,
+            ms => Some(ms),
+        }
     }
 
     /// Load current state
@@ -113,6 +362,7 @@ impl AtomicOrderbook {
     #[inline(always)]
     pub fn store(&self, yes_ask: PriceCents, no_ask: PriceCents, yes_size: SizeCents, no_size: SizeCents) {
         self.packed.store(pack_orderbook(yes_ask, no_ask, yes_size, no_size), Ordering::Release);
+        self.updated_ms.store(unix_ms(), Ordering::Release);
     }
 
     /// Update YES side only
@@ -127,6 +377,7 @@ impl AtomicOrderbook {
                 Err(c) => current = c,
             }
         }
+        self.updated_ms.store(unix_ms(), Ordering::Release);
     }
 
     /// Update NO side only
@@ -141,6 +392,7 @@ impl AtomicOrderbook {
                 Err(c) => current = c,
             }
         }
+        self.updated_ms.store(unix_ms(), Ordering::Release);
     }
 }
 
@@ -150,12 +402,59 @@ impl Default for AtomicOrderbook {
     }
 }
 
+/// Marker for "no imbalance known" (book empty or not received yet)
+const NO_IMBALANCE: i16 = i16::MIN;
+
+/// Top-of-book imbalance of the Polymarket YES and NO books: (bid volume -
+/// ask volume) / (bid volume + ask volume), stored in basis points. +1.0 is
+/// all bids (buying pressure), -1.0 all asks.
+pub struct BookImbalance {
+    yes: AtomicI16,
+    no: AtomicI16,
+}
+
+impl BookImbalance {
+    pub const fn new() -> Self {
+        Self { yes: AtomicI16::new(NO_IMBALANCE), no: AtomicI16::new(NO_IMBALANCE) }
+    }
+
+    /// (yes, no), `None` where unknown
+    #[inline(always)]
+    pub fn load(&self) -> (Option<f64>, Option<f64>) {
+        let get = |v: &AtomicI16| match v.load(Ordering::Acquire) {
+            NO_IMBALANCE => None,
+            bps => Some(bps as f64 / 10_000.0),
+        };
+        (get(&self.yes), get(&self.no))
+    }
+
+    #[inline(always)]
+    pub fn store(&self, yes_token: bool, imbalance: Option<f64>) {
+        let bps = imbalance.map_or(NO_IMBALANCE, |v| (v.clamp(-1.0, 1.0) * 10_000.0).round() as i16);
+        let slot = if yes_token { &self.yes } else { &self.no };
+        slot.store(bps, Ordering::Release);
+    }
+
+    pub fn clear(&self) {
+        self.yes.store(NO_IMBALANCE, Ordering::Release);
+        self.no.store(NO_IMBALANCE, Ordering::Release);
+    }
+}
+
+impl Default for BookImbalance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Complete market state tracking both platforms' orderbooks for a single market
 pub struct AtomicMarketState {
     /// Kalshi platform orderbook state
     pub kalshi: AtomicOrderbook,
     /// Polymarket platform orderbook state
     pub poly: AtomicOrderbook,
+    /// Polymarket top-of-book imbalance per outcome token
+    pub poly_imbalance: BookImbalance,
     /// Market pair metadata (immutable after discovery phase)
     pub pair: Option<Arc<MarketPair>>,
     /// Unique market identifier for O(1) lookups
@@ -167,6 +466,7 @@ impl AtomicMarketState {
         Self {
             kalshi: AtomicOrderbook::new(),
             poly: AtomicOrderbook::new(),
+            poly_imbalance: BookImbalance::new(),
             pair: None,
             market_id,
         }
@@ -174,14 +474,26 @@ impl AtomicMarketState {
 
     #[inline(always)]
     pub fn check_arbs(&self, threshold_cents: PriceCents) -> u8 {
+        self.check_arbs_in(threshold_cents, crate::config::POLY_ONLY_MODE)
+    }
+
+    /// `check_arbs` with the mode given instead of read from POLY_ONLY_MODE
+    #[inline(always)]
+    pub fn check_arbs_in(&self, threshold_cents: PriceCents, poly_only: bool) -> u8 {
+        self.check_arbs_with(threshold_cents, poly_only, configured_kalshi_fees())
+    }
+
+    /// `check_arbs_in` with the Kalshi fees given instead of read from
+    /// KALSHI_FEE_TIERS
+    #[inline(always)]
+    pub fn check_arbs_with(&self, threshold_cents: PriceCents, poly_only: bool, kalshi_fees: &KalshiFeeTable) -> u8 {
         use wide::{i16x8, CmpLt};
-        use crate::config::POLY_ONLY_MODE;
 
-        let (k_yes, k_no, _, _) = self.kalshi.load();
-        let (p_yes, p_no, _, _) = self.poly.load();
+        let (k_yes, k_no, _, _) = self.kalshi.quotes();
+        let (p_yes, p_no, _, _) = self.poly.quotes();
 
         // In POLY_ONLY mode, only check Poly-Poly arbitrage
-        if POLY_ONLY_MODE {
+        if poly_only {
             if p_yes == NO_PRICE || p_no == NO_PRICE {
                 return 0;
             }
@@ -197,14 +509,14 @@ impl AtomicMarketState {
             return 0;
         }
 
-        let k_yes_fee = KALSHI_FEE_TABLE[k_yes as usize];
-        let k_no_fee = KALSHI_FEE_TABLE[k_no as usize];
+        let k_yes_fee = kalshi_fees[(k_yes as usize).min(100)];
+        let k_no_fee = kalshi_fees[(k_no as usize).min(100)];
 
         let costs = i16x8::new([
-            (p_yes + k_no + k_no_fee) as i16,
-            (k_yes + k_yes_fee + p_no) as i16,
+            (p_yes + k_no) as i16 + k_no_fee,
+            (k_yes + p_no) as i16 + k_yes_fee,
             (p_yes + p_no) as i16,
-            (k_yes + k_yes_fee + k_no + k_no_fee) as i16,
+            (k_yes + k_no) as i16 + k_yes_fee + k_no_fee,
             i16::MAX, i16::MAX, i16::MAX, i16::MAX,
         ]);
 
@@ -234,6 +546,28 @@ static KALSHI_FEE_TABLE: [u16; 101] = {
     table
 };
 
+/// Kalshi taker fee in cents of one contract at each price 0-100¢
+/// (negative = rebate)
+pub type KalshiFeeTable = [i16; 101];
+
+/// Taker fees of a single contract under `fees`' Kalshi tiers, the size
+/// `check_arbs` screens top-of-book quotes at
+pub fn kalshi_fee_table(fees: &FeeSchedule) -> KalshiFeeTable {
+    let mut table = [0i16; 101];
+    for (price, fee) in table.iter_mut().enumerate().take(100).skip(1) {
+        *fee = fees.fee_cents(Platform::Kalshi, price as f64, 1, Liquidity::Taker).round() as i16;
+    }
+    table
+}
+
+static KALSHI_FEES: OnceLock<KalshiFeeTable> = OnceLock::new();
+
+/// Kalshi fee table from KALSHI_FEE_TIERS, built on first use
+#[inline(always)]
+pub fn configured_kalshi_fees() -> &'static KalshiFeeTable {
+    KALSHI_FEES.get_or_init(|| kalshi_fee_table(&FeeSchedule::from_env()))
+}
+
 /// Calculate Kalshi trading fee in cents for a single contract at the given price.
 /// For typical prices (10-90 cents), fees are usually 1-2 cents per contract.
 #[inline(always)]
@@ -244,13 +578,16 @@ pub fn kalshi_fee_cents(price_cents: PriceCents) -> PriceCents {
     KALSHI_FEE_TABLE[price_cents as usize]
 }
 
-/// Convert f64 price (0.01-0.99) to PriceCents (1-99)
+/// Convert f64 price (0.00-1.00) to PriceCents (0-100), rounded like
+/// `Price::from_poly`. Returns 0 outside that range.
+#[allow(dead_code)]
 #[inline(always)]
 pub fn price_to_cents(price: f64) -> PriceCents {
-    ((price * 100.0).round() as PriceCents).clamp(0, 99)
+    Price::from_poly(price).map_or(0, Price::cents)
 }
 
 /// Convert PriceCents back to f64
+#[allow(dead_code)]
 #[inline(always)]
 pub fn cents_to_price(cents: PriceCents) -> f64 {
     cents as f64 / 100.0
@@ -258,6 +595,7 @@ pub fn cents_to_price(cents: PriceCents) -> f64 {
 
 /// Parse price from string "0.XX" format (Polymarket)
 /// Returns 0 if parsing fails
+#[allow(dead_code)]
 #[inline(always)]
 pub fn parse_price(s: &str) -> PriceCents {
     let bytes = s.as_bytes();
@@ -278,12 +616,133 @@ pub fn parse_price(s: &str) -> PriceCents {
     }
     // Fallback to standard parse
     s.parse::<f64>()
-        .map(|p| price_to_cents(p))
+        .map(price_to_cents)
         .unwrap_or(0)
 }
 
+/// Price of one contract in whole cents (0–100¢, a contract pays $1).
+///
+/// Kalshi quotes in cents and Polymarket on a 0–1 dollar scale; prices from
+/// either go through the conversions here, so the two scales cannot be mixed
+/// up. Construction and arithmetic are checked: anything outside 0–100¢ is
+/// `None` rather than clamped or wrapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Price(PriceCents);
+
+impl Price {
+    pub const ZERO: Price = Price(0);
+    /// One tick, the lowest price an order can be placed at
+    pub const CENT: Price = Price(1);
+    /// Full payout of a winning contract
+    #[allow(dead_code)]
+    pub const DOLLAR: Price = Price(100);
+
+    #[inline(always)]
+    pub const fn from_cents(cents: PriceCents) -> Option<Price> {
+        if cents <= 100 { Some(Price(cents)) } else { None }
+    }
+
+    /// Kalshi price (integer cents, as in orders and orderbook levels)
+    #[inline(always)]
+    pub fn from_kalshi(cents: i64) -> Option<Price> {
+        u16::try_from(cents).ok().and_then(Price::from_cents)
+    }
+
+    /// Polymarket price (dollars, 0–1), rounded to the nearest cent. Goes
+    /// through basis points so float noise (0.995 stored as 0.99499…) can't
+    /// tip a half-cent either way.
+    #[inline(always)]
+    pub fn from_poly(dollars: f64) -> Option<Price> {
+        if !(0.0..=1.0).contains(&dollars) {
+            return None;
+        }
+        Price::from_bps((dollars * 10_000.0).round() as u64)
+    }
+
+    /// Polymarket price in basis points of a dollar (0–10000), rounded to the nearest cent
+    #[inline(always)]
+    pub fn from_bps(bps: u64) -> Option<Price> {
+        if bps > 10_000 {
+            return None;
+        }
+        Price::from_cents(((bps + 50) / 100) as PriceCents)
+    }
+
+    /// Polymarket ask in basis points, rounded up to the cent: a sub-cent ask
+    /// is never counted cheaper than it is, which would inflate an arb's edge
+    #[inline(always)]
+    pub fn ask_from_bps(bps: u64) -> Option<Price> {
+        if bps > 10_000 {
+            return None;
+        }
+        Price::from_cents(bps.div_ceil(100) as PriceCents)
+    }
+
+    /// Polymarket bid in basis points, rounded down to the cent
+    #[inline(always)]
+    pub fn bid_from_bps(bps: u64) -> Option<Price> {
+        if bps > 10_000 {
+            return None;
+        }
+        Price::from_cents((bps / 100) as PriceCents)
+    }
+
+    #[inline(always)]
+    pub const fn cents(self) -> PriceCents {
+        self.0
+    }
+
+    /// Price for a Kalshi order
+    #[inline(always)]
+    pub fn to_kalshi(self) -> i64 {
+        self.0 as i64
+    }
+
+    /// Price for a Polymarket order (dollars, 0–1)
+    #[inline(always)]
+    pub fn to_poly(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// Basis points of a dollar (Polymarket order book scale)
+    #[inline(always)]
+    pub fn to_bps(self) -> u64 {
+        self.0 as u64 * 100
+    }
+
+    /// Price of the other outcome: a YES bid at `p` is a NO ask at `100 - p`
+    #[inline(always)]
+    pub fn complement(self) -> Price {
+        Price(100 - self.0)
+    }
+
+    #[inline(always)]
+    pub fn checked_add(self, other: Price) -> Option<Price> {
+        Price::from_cents(self.0.checked_add(other.0)?)
+    }
+
+    #[allow(dead_code)]
+    #[inline(always)]
+    pub fn checked_sub(self, other: Price) -> Option<Price> {
+        Some(Price(self.0.checked_sub(other.0)?))
+    }
+
+    /// True for prices an order can be placed at (1–99¢)
+    #[inline(always)]
+    pub fn is_tradable(self) -> bool {
+        (1..=99).contains(&self.0)
+    }
+}
+
+impl std::fmt::Display for Price {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}¢", self.0)
+    }
+}
+
 /// Arbitrage opportunity type, determining the execution strategy
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ArbType {
     /// Cross-platform: Buy Polymarket YES + Buy Kalshi NO
     PolyYesKalshiNo,
@@ -295,6 +754,100 @@ pub enum ArbType {
     KalshiOnly,
 }
 
+impl ArbType {
+    /// This arb's bit in a `check_arbs` mask
+    pub fn mask_bit(self) -> u8 {
+        match self {
+            ArbType::PolyYesKalshiNo => 1,
+            ArbType::KalshiYesPolyNo => 2,
+            ArbType::PolyOnly => 4,
+            ArbType::KalshiOnly => 8,
+        }
+    }
+
+    /// Platforms of the YES and NO legs
+    pub fn leg_platforms(self) -> (Platform, Platform) {
+        match self {
+            ArbType::PolyYesKalshiNo => (Platform::Polymarket, Platform::Kalshi),
+            ArbType::KalshiYesPolyNo => (Platform::Kalshi, Platform::Polymarket),
+            ArbType::PolyOnly => (Platform::Polymarket, Platform::Polymarket),
+            ArbType::KalshiOnly => (Platform::Kalshi, Platform::Kalshi),
+        }
+    }
+}
+
+/// How a submitted order stopped matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrderOutcome {
+    /// Finished on its own (filled, or the IOC/FAK remainder was killed)
+    #[default]
+    Completed,
+    /// Still resting when its time-to-live ran out; the cancel was confirmed
+    /// by the exchange, so the fill counts are final
+    Expired,
+    /// Time-to-live ran out but the exchange never confirmed the cancel; the
+    /// remainder may still fill
+    CancelUnconfirmed,
+}
+
+/// What an opportunity is worth given the depth behind its quotes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpportunityValue {
+    /// Net profit per contract times the contracts fillable on both legs
+    pub executable_profit_cents: f64,
+    /// Contracts fillable on both legs at the quotes
+    pub contracts: i64,
+    /// Liquidity confidence, 0 (one contract or less) to 1 (deep books)
+    pub confidence: f64,
+}
+
+impl OpportunityValue {
+    /// Ranking score: executable profit weighted by liquidity confidence
+    pub fn score(&self) -> f64 {
+        self.executable_profit_cents.max(0.0) * self.confidence
+    }
+}
+
+/// How arb legs are sent to the exchanges. Every type is priced at the leg's
+/// limit, so none can pay more than the book walk allowed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    /// Immediate-or-cancel: take what is available up to the limit, cancel the rest
+    #[default]
+    Ioc,
+    /// Fill-or-kill at the limit: the whole size at once or nothing
+    Market,
+    /// Rests on the book until filled or cancelled at the order time-to-live
+    Limit,
+    /// Resting limit that is rejected instead of taking liquidity (maker rebates)
+    PostOnly,
+}
+
+impl OrderType {
+    /// From `ioc`, `market`, `limit` or `post_only`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "ioc" => Some(Self::Ioc),
+            "market" | "fok" => Some(Self::Market),
+            "limit" | "gtc" => Some(Self::Limit),
+            "post_only" => Some(Self::PostOnly),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for OrderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Ioc => "IOC",
+            Self::Market => "FOK",
+            Self::Limit => "GTC",
+            Self::PostOnly => "post-only",
+        })
+    }
+}
+
 /// High-priority execution request for an arbitrage opportunity
 #[derive(Debug, Clone, Copy)]
 pub struct FastExecutionRequest {
@@ -312,14 +865,41 @@ pub struct FastExecutionRequest {
     pub arb_type: ArbType,
     /// Detection timestamp in nanoseconds since system start
     pub detected_ns: u64,
+    /// Unix timestamp in milliseconds of the older of the two books the
+    /// prices were read from (0 = unknown)
+    pub snapshot_ms: u64,
+    /// How the legs are sent; `None` uses the engine default (`ORDER_TYPE`)
+    pub order_type: Option<OrderType>,
 }
 
 impl FastExecutionRequest {
+    /// Age of the prices at `now_ms`, `None` if they carry no timestamp
+    #[inline]
+    pub fn snapshot_age_ms(&self, now_ms: u64) -> Option<u64> {
+        (self.snapshot_ms > 0).then(|| now_ms.saturating_sub(self.snapshot_ms))
+    }
+
     #[inline(always)]
     pub fn profit_cents(&self) -> i16 {
         100 - (self.yes_price as i16 + self.no_price as i16 + self.estimated_fee_cents() as i16)
     }
 
+    /// Profit per contract in cents after both legs' fees and fixed
+    /// per-contract costs, at maker rates when the request is post-only
+    #[inline]
+    pub fn net_profit_cents(&self, fees: &FeeSchedule) -> f64 {
+        self.net_profit_cents_as(fees, self.order_type.map_or(Liquidity::Taker, Liquidity::of))
+    }
+
+    /// `net_profit_cents` with both legs at `liquidity`
+    #[inline]
+    pub fn net_profit_cents_as(&self, fees: &FeeSchedule, liquidity: Liquidity) -> f64 {
+        let (yes_price, no_price) = (self.yes_price as f64, self.no_price as f64);
+        100.0 - yes_price - no_price
+            - leg_fees_cents(self.arb_type, yes_price, no_price, self.hedged_contracts(), liquidity, fees)
+            - fees.fixed_cost_cents
+    }
+
     #[inline(always)]
     pub fn estimated_fee_cents(&self) -> PriceCents {
         match self.arb_type {
@@ -332,6 +912,81 @@ impl FastExecutionRequest {
             ArbType::KalshiOnly => kalshi_fee_cents(self.yes_price) + kalshi_fee_cents(self.no_price),
         }
     }
+
+    /// Contracts to buy on each leg: the same count on both sides, so exactly
+    /// one leg pays $1 per contract at resolution whatever the outcome
+    #[inline(always)]
+    pub fn hedged_contracts(&self) -> i64 {
+        (self.yes_size.min(self.no_size) / 100) as i64
+    }
+
+    /// Estimated value at the quoted sizes. Confidence is the share of
+    /// `full_confidence_contracts` the thinner book covers, from 0 to 1.
+    pub fn value(&self, fees: &FeeSchedule, full_confidence_contracts: i64) -> OpportunityValue {
+        let contracts = self.hedged_contracts();
+        OpportunityValue {
+            executable_profit_cents: self.net_profit_cents(fees) * contracts as f64,
+            contracts,
+            confidence: (contracts as f64 / full_confidence_contracts.max(1) as f64).min(1.0),
+        }
+    }
+
+    /// Build the request for the most profitable arb in a `check_arbs` mask,
+    /// out of the four YES/NO leg combinations across the two platforms.
+    /// Ties go to cross-platform arbs (more reliable).
+    pub fn from_arb_mask(market_id: u16, market: &AtomicMarketState, arb_mask: u8, detected_ns: u64) -> Option<Self> {
+        let (k_yes, k_no, k_yes_size, k_no_size) = market.kalshi.quotes();
+        let (p_yes, p_no, p_yes_size, p_no_size) = market.poly.quotes();
+        let k_ms = market.kalshi.updated_ms().unwrap_or(0);
+        let p_ms = market.poly.updated_ms().unwrap_or(0);
+        let cross_ms = k_ms.min(p_ms);
+
+        let legs = [
+            // Poly YES + Kalshi NO
+            (p_yes, k_no, p_yes_size, k_no_size, cross_ms, ArbType::PolyYesKalshiNo),
+            // Kalshi YES + Poly NO
+            (k_yes, p_no, k_yes_size, p_no_size, cross_ms, ArbType::KalshiYesPolyNo),
+            // Poly only (both sides)
+            (p_yes, p_no, p_yes_size, p_no_size, p_ms, ArbType::PolyOnly),
+            // Kalshi only (both sides)
+            (k_yes, k_no, k_yes_size, k_no_size, k_ms, ArbType::KalshiOnly),
+        ];
+
+        // A crossed or missing side quotes as NO_PRICE and is never tradeable
+        legs.into_iter()
+            .enumerate()
+            .filter(|&(bit, _)| arb_mask & (1 << bit) != 0)
+            .filter(|&(_, (yes_price, no_price, ..))| yes_price != NO_PRICE && no_price != NO_PRICE)
+            .map(|(_, (yes_price, no_price, yes_size, no_size, snapshot_ms, arb_type))| Self {
+                market_id,
+                yes_price,
+                no_price,
+                yes_size,
+                no_size,
+                arb_type,
+                detected_ns,
+                snapshot_ms,
+                order_type: None,
+            })
+            // max_by_key keeps the last maximum; reverse so the earliest wins ties
+            .rev()
+            .max_by_key(|req| req.profit_cents())
+    }
+}
+
+/// Fees in cents for one contract of each leg, both legs `contracts` at
+/// `liquidity`
+#[inline]
+pub fn leg_fees_cents(
+    arb_type: ArbType,
+    yes_price: f64,
+    no_price: f64,
+    contracts: i64,
+    liquidity: Liquidity,
+    fees: &FeeSchedule,
+) -> f64 {
+    let (yes_platform, no_platform) = arb_type.leg_platforms();
+    fees.fee_cents(yes_platform, yes_price, contracts, liquidity) + fees.fee_cents(no_platform, no_price, contracts, liquidity)
 }
 
 /// Global market state manager for all tracked markets across both platforms
@@ -350,6 +1005,18 @@ pub struct GlobalState {
 
     /// O(1) lookup map: pre-hashed Polymarket NO token → market_id
     pub poly_no_to_id: FxHashMap<u64, u16>,
+
+    /// Pre-hashed Kalshi event ticker → market_ids of the pairs in that event
+    pub kalshi_event_to_ids: FxHashMap<u64, Vec<u16>>,
+
+    /// Last price update per platform, for health checks
+    pub freshness: FeedFreshness,
+
+    /// Polymarket multi-outcome markets checked for full-set arbs
+    pub multi_outcome: Vec<MultiOutcomeMarket>,
+
+    /// Pre-hashed outcome token → (index into `multi_outcome`, outcome index)
+    pub poly_outcome_to_id: FxHashMap<u64, (u16, u16)>,
 }
 
 impl GlobalState {
@@ -365,6 +1032,10 @@ impl GlobalState {
             kalshi_to_id: FxHashMap::default(),
             poly_yes_to_id: FxHashMap::default(),
             poly_no_to_id: FxHashMap::default(),
+            kalshi_event_to_ids: FxHashMap::default(),
+            freshness: FeedFreshness::new(),
+            multi_outcome: Vec::new(),
+            poly_outcome_to_id: FxHashMap::default(),
         }
     }
 
@@ -386,6 +1057,9 @@ impl GlobalState {
         self.kalshi_to_id.insert(kalshi_hash, market_id);
         self.poly_yes_to_id.insert(poly_yes_hash, market_id);
         self.poly_no_to_id.insert(poly_no_hash, market_id);
+        if !pair.kalshi_event_ticker.is_empty() {
+            self.kalshi_event_to_ids.entry(fxhash_str(&pair.kalshi_event_ticker)).or_default().push(market_id);
+        }
 
         // Store pair
         self.markets[market_id as usize].pair = Some(Arc::new(pair));
@@ -393,6 +1067,16 @@ impl GlobalState {
         Some(market_id)
     }
 
+    /// Add a multi-outcome market, returns its index
+    pub fn add_multi_outcome(&mut self, market: MultiOutcomeMarket) -> Option<u16> {
+        let index = u16::try_from(self.multi_outcome.len()).ok()?;
+        for (leg, outcome) in market.outcomes.iter().enumerate() {
+            self.poly_outcome_to_id.insert(fxhash_str(&outcome.token), (index, leg as u16));
+        }
+        self.multi_outcome.push(market);
+        Some(index)
+    }
+
     /// Get market by Kalshi ticker hash (O(1))
     #[inline(always)]
     #[allow(dead_code)]
@@ -451,6 +1135,15 @@ impl GlobalState {
     pub fn market_count(&self) -> usize {
         self.next_market_id as usize
     }
+
+    /// The other pairs settling on the same Kalshi event as `pair`
+    pub fn event_siblings<'a>(&'a self, pair: &'a MarketPair) -> impl Iterator<Item = &'a Arc<MarketPair>> + 'a {
+        self.kalshi_event_to_ids.get(&fxhash_str(&pair.kalshi_event_ticker))
+            .into_iter()
+            .flatten()
+            .filter_map(|&id| self.get_by_id(id)?.pair.as_ref())
+            .filter(move |other| other.pair_id != pair.pair_id)
+    }
 }
 
 impl Default for GlobalState {
@@ -470,7 +1163,7 @@ pub fn fxhash_str(s: &str) -> u64 {
 
 // === Platform Enum ===
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 pub enum Platform {
     Kalshi,
@@ -486,6 +1179,73 @@ impl std::fmt::Display for Platform {
     }
 }
 
+// === Feed Freshness ===
+
+/// Time of the last price update received from each platform
+#[allow(dead_code)]
+pub struct FeedFreshness {
+    started_ms: u64,
+    /// Unix milliseconds per platform, 0 until the first update
+    last_update_ms: [AtomicU64; 2],
+}
+
+impl FeedFreshness {
+    pub fn new() -> Self {
+        Self {
+            started_ms: unix_ms(),
+            last_update_ms: [AtomicU64::new(0), AtomicU64::new(0)],
+        }
+    }
+
+    #[inline(always)]
+    fn slot(platform: Platform) -> usize {
+        match platform {
+            Platform::Kalshi => 0,
+            Platform::Polymarket => 1,
+        }
+    }
+
+    /// Record a price update received now
+    #[inline(always)]
+    pub fn mark(&self, platform: Platform) {
+        self.mark_at(platform, unix_ms());
+    }
+
+    #[inline(always)]
+    pub fn mark_at(&self, platform: Platform, now_ms: u64) {
+        self.last_update_ms[Self::slot(platform)].store(now_ms, Ordering::Relaxed);
+    }
+
+    #[allow(dead_code)]
+    pub fn last_update_ms(&self, platform: Platform) -> Option<u64> {
+        match self.last_update_ms[Self::slot(platform)].load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(ms),
+        }
+    }
+
+    /// Milliseconds since the last update (since startup if none arrived yet)
+    #[allow(dead_code)]
+    pub fn age_ms(&self, platform: Platform, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.last_update_ms(platform).unwrap_or(self.started_ms))
+    }
+}
+
+impl Default for FeedFreshness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Current Unix time in milliseconds
+#[inline(always)]
+pub fn unix_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -493,6 +1253,7 @@ impl std::fmt::Display for Platform {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::FeeTiers;
     use std::sync::Arc;
     use std::thread;
 
@@ -673,9 +1434,15 @@ mod tests {
         assert_eq!(price_to_cents(0.01), 1);
         assert_eq!(price_to_cents(0.99), 99);
         assert_eq!(price_to_cents(0.0), 0);
-        assert_eq!(price_to_cents(1.0), 99);  // Clamped to 99
+        assert_eq!(price_to_cents(1.0), 100);  // A full dollar, not clamped to 99
         assert_eq!(price_to_cents(0.505), 51);  // Rounded
         assert_eq!(price_to_cents(0.504), 50);  // Rounded
+        // Half-cents at both ends round the same way
+        assert_eq!(price_to_cents(0.005), 1);
+        assert_eq!(price_to_cents(0.995), 100);
+        // Out of range
+        assert_eq!(price_to_cents(1.5), 0);
+        assert_eq!(price_to_cents(-0.01), 0);
     }
 
     #[test]
@@ -686,6 +1453,91 @@ mod tests {
         assert!((cents_to_price(0) - 0.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_price_boundaries() {
+        // 0¢ and 100¢ are prices; 101¢ is not
+        assert_eq!(Price::from_cents(0), Some(Price::ZERO));
+        assert_eq!(Price::from_cents(100), Some(Price::DOLLAR));
+        assert_eq!(Price::from_cents(101), None);
+        assert_eq!(Price::from_kalshi(-1), None);
+        assert_eq!(Price::from_kalshi(100), Some(Price::DOLLAR));
+        assert_eq!(Price::from_poly(0.0), Some(Price::ZERO));
+        assert_eq!(Price::from_poly(1.0), Some(Price::DOLLAR));
+        assert_eq!(Price::from_poly(1.01), None);
+        assert_eq!(Price::from_poly(f64::NAN), None);
+        assert_eq!(Price::from_bps(10_000), Some(Price::DOLLAR));
+        assert_eq!(Price::from_bps(10_001), None);
+        assert!(!Price::ZERO.is_tradable());
+        assert!(!Price::DOLLAR.is_tradable());
+
+        assert_eq!(Price::ZERO.complement(), Price::DOLLAR);
+        assert_eq!(Price::DOLLAR.complement(), Price::ZERO);
+        assert_eq!(Price::DOLLAR.checked_add(Price::CENT), None);
+        assert_eq!(Price::ZERO.checked_sub(Price::CENT), None);
+        assert_eq!(Price::ZERO.to_poly(), 0.0);
+        assert_eq!(Price::DOLLAR.to_poly(), 1.0);
+        assert_eq!(Price::DOLLAR.to_kalshi(), 100);
+        assert_eq!(Price::ZERO.to_string(), "0¢");
+        assert_eq!(Price::DOLLAR.to_string(), "100¢");
+    }
+
+    #[test]
+    fn test_price_conversions() {
+        let p = Price::from_poly(0.47).unwrap();
+        assert_eq!(p.cents(), 47);
+        assert_eq!(p.to_kalshi(), 47);
+        assert_eq!(p.to_bps(), 4700);
+        assert_eq!(Price::from_kalshi(47), Some(p));
+        // Sub-cent Polymarket ticks round to the nearest cent
+        assert_eq!(Price::from_bps(4750), Price::from_cents(48));
+        assert_eq!(Price::from_bps(4749), Some(p));
+        assert_eq!(Price::from_poly(0.474), Some(p));
+        assert_eq!(p.checked_add(Price::from_cents(53).unwrap()), Some(Price::DOLLAR));
+        assert_eq!(p.complement().cents(), 53);
+    }
+
+    #[test]
+    fn test_sub_cent_prices_at_the_boundaries() {
+        // Half a cent rounds the same way at both ends of the scale
+        assert_eq!(Price::from_poly(0.005), Price::from_cents(1));
+        assert_eq!(Price::from_poly(0.995), Price::from_cents(100));
+        assert_eq!(Price::from_poly(0.004_9), Some(Price::ZERO));
+        assert_eq!(Price::from_poly(0.994_9), Price::from_cents(99));
+
+        // Asks round up and bids down, so neither side looks better than it is
+        for (bps, ask, bid) in [(0, 0, 0), (1, 1, 0), (50, 1, 0), (100, 1, 1), (4540, 46, 45),
+                                (9900, 99, 99), (9950, 100, 99), (9999, 100, 99), (10_000, 100, 100)] {
+            assert_eq!(Price::ask_from_bps(bps).map(Price::cents), Some(ask), "ask {}bps", bps);
+            assert_eq!(Price::bid_from_bps(bps).map(Price::cents), Some(bid), "bid {}bps", bps);
+            // An ask on one outcome is a bid on the other
+            assert_eq!(Price::ask_from_bps(bps).map(Price::complement), Price::bid_from_bps(10_000 - bps));
+        }
+        assert_eq!(Price::ask_from_bps(10_001), None);
+        assert_eq!(Price::bid_from_bps(10_001), None);
+    }
+
+    #[test]
+    fn test_order_constraints_tick_and_size() {
+        let cent = OrderConstraints::default();
+        assert_eq!(cent.floor_price(47), Some(47));
+        assert!(cent.on_tick(Price::from_cents(47).unwrap()));
+        assert!(!cent.allows_size(4.0));
+        assert!(cent.allows_size(5.0));
+
+        // Coarse 10¢ tick: buys round down, sells up
+        let coarse = OrderConstraints { tick_bps: 1000, min_size: 15.0 };
+        assert_eq!(coarse.floor_price(47), Some(40));
+        assert_eq!(coarse.ceil_price(47), Some(50));
+        assert_eq!(coarse.floor_price(40), Some(40));
+        assert_eq!(coarse.floor_price(7), None, "nothing tradable under one tick");
+        assert_eq!(coarse.ceil_price(93), None);
+        assert!(!coarse.on_tick(Price::from_cents(47).unwrap()));
+
+        // Sub-cent ticks leave every cent price valid
+        let fine = OrderConstraints { tick_bps: 10, min_size: 5.0 };
+        assert_eq!((fine.floor_price(3), fine.ceil_price(98)), (Some(3), Some(98)));
+    }
+
     #[test]
     fn test_parse_price() {
         // Standard "0.XX" format
@@ -698,6 +1550,8 @@ mod tests {
 
         // Fallback parsing
         assert_eq!(parse_price("0.505"), 51);
+        assert_eq!(parse_price("0.995"), 100);
+        assert_eq!(parse_price("1"), 100);
 
         // Invalid input
         assert_eq!(parse_price("invalid"), 0);
@@ -720,6 +1574,10 @@ mod tests {
         state
     }
 
+    fn standard_fees() -> KalshiFeeTable {
+        kalshi_fee_table(&FeeSchedule::default())
+    }
+
     #[test]
     fn test_check_arbs_poly_yes_kalshi_no() {
         // Poly YES 40¢ + Kalshi NO 50¢ = 90¢ raw
@@ -728,7 +1586,7 @@ mod tests {
         let state = make_market_state(55, 50, 40, 65);
 
         // threshold_cents is in cents, so 100 = $1.00
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         assert!(mask & 1 != 0, "Should detect Poly YES + Kalshi NO arb (bit 0)");
     }
@@ -740,7 +1598,7 @@ mod tests {
         // Effective = 92¢ → ARB
         let state = make_market_state(40, 65, 55, 50);
 
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         assert!(mask & 2 != 0, "Should detect Kalshi YES + Poly NO arb (bit 1)");
     }
@@ -750,7 +1608,7 @@ mod tests {
         // Poly YES 48¢ + Poly NO 50¢ = 98¢ → ARB (no fees!)
         let state = make_market_state(60, 60, 48, 50);
 
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         assert!(mask & 4 != 0, "Should detect Poly-only arb (bit 2)");
     }
@@ -762,11 +1620,30 @@ mod tests {
         // Effective = 92¢ → ARB
         let state = make_market_state(44, 44, 60, 60);
 
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         assert!(mask & 8 != 0, "Should detect Kalshi-only arb (bit 3)");
     }
 
+    #[test]
+    fn test_crossed_book_suppressed_then_stale() {
+        // Kalshi-only at 88¢ raw is what a crossed Kalshi book looks like
+        let state = make_market_state(44, 44, 60, 60);
+        assert_eq!(state.kalshi.check_crossed(false, 1_000, 5_000), BookCheck::Valid);
+        assert_eq!(state.kalshi.check_crossed(true, 1_000, 5_000), BookCheck::Crossed { first: true });
+        assert_eq!(state.check_arbs_in(100, false), 0);
+        assert!(FastExecutionRequest::from_arb_mask(0, &state, 8, 0).is_none(), "no request with a crossed leg");
+
+        assert_eq!(state.kalshi.check_crossed(true, 3_000, 5_000), BookCheck::Crossed { first: false });
+        assert_eq!(state.kalshi.check_crossed(true, 6_000, 5_000), BookCheck::Stale { crossed_ms: 5_000 });
+        assert!(state.kalshi.is_stale());
+        assert_eq!(state.kalshi.check_crossed(true, 9_000, 5_000), BookCheck::Crossed { first: false }, "reported once");
+
+        assert_eq!(state.kalshi.check_crossed(false, 9_500, 5_000), BookCheck::Recovered { crossed_ms: 8_500 });
+        assert!(!state.kalshi.is_crossed() && !state.kalshi.is_stale());
+        assert!(state.check_arbs_in(100, false) & 8 != 0);
+    }
+
     #[test]
     fn test_check_arbs_no_arbs() {
         // All prices efficient - no arbs
@@ -776,7 +1653,7 @@ mod tests {
         // Kalshi: 55 + 55 + 4 fee = 114 > 100
         let state = make_market_state(55, 55, 52, 52);
 
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         assert_eq!(mask, 0, "Should detect no arbs in efficient market");
     }
@@ -786,7 +1663,7 @@ mod tests {
         // Missing price should return no arbs
         let state = make_market_state(50, NO_PRICE, 50, 50);
 
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         assert_eq!(mask, 0, "Should return 0 when any price is missing");
     }
@@ -798,12 +1675,27 @@ mod tests {
         // Effective = 101¢ → NO ARB (> 100¢ threshold)
         let state = make_market_state(55, 50, 49, 55);
 
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         // Bit 0 should NOT be set (Poly YES + Kalshi NO = 101¢ > 100¢)
         assert!(mask & 1 == 0, "Fees should eliminate marginal arb");
     }
 
+    #[test]
+    fn test_check_arbs_uses_configured_kalshi_tiers() {
+        // Poly YES 48¢ + Kalshi NO 50¢: 100¢ with the standard 2¢ fee, 99¢
+        // at a 1.5% taker rate (fee on 50¢ = 1¢)
+        let state = make_market_state(55, 50, 48, 55);
+        assert!(state.check_arbs_with(100, false, &standard_fees()) & 1 == 0);
+        let fees = FeeSchedule { kalshi: FeeTiers::flat(150, 0), ..FeeSchedule::default() };
+        let table = kalshi_fee_table(&fees);
+        assert_eq!(table[50], 1);
+        assert_eq!(table[0], 0);
+        assert!(state.check_arbs_with(100, false, &table) & 1 != 0);
+
+        assert_eq!(standard_fees()[50], kalshi_fee_cents(50) as i16);
+    }
+
     #[test]
     fn test_check_arbs_multiple_arbs() {
         // Scenario where multiple arbs exist
@@ -811,7 +1703,7 @@ mod tests {
         // Poly: YES=40, NO=40 (sum=80, no fees)
         let state = make_market_state(40, 40, 40, 40);
 
-        let mask = state.check_arbs(100);
+        let mask = state.check_arbs_in(100, false);
 
         // Should detect all 4 combinations
         assert!(mask & 1 != 0, "Should detect Poly YES + Kalshi NO");
@@ -820,6 +1712,57 @@ mod tests {
         assert!(mask & 8 != 0, "Should detect Kalshi-only");
     }
 
+    #[test]
+    fn test_from_arb_mask_picks_most_profitable_combo() {
+        // Poly YES 45 + Kalshi NO 50 = 95 + 2 fee → 3¢
+        // Kalshi YES 40 + Poly NO 45 = 85 + 2 fee → 13¢
+        // Poly YES 45 + Poly NO 45 = 90 → 10¢
+        let state = make_market_state(40, 50, 45, 45);
+        let mask = state.check_arbs_in(100, false);
+        assert_eq!(mask & 0b111, 0b111);
+
+        let req = FastExecutionRequest::from_arb_mask(0, &state, mask, 0).unwrap();
+        assert_eq!(req.arb_type, ArbType::KalshiYesPolyNo);
+        assert_eq!((req.yes_price, req.no_price), (40, 45));
+        assert_eq!(req.profit_cents(), 13);
+
+        // Equal profit: the earlier (cross-platform first) combo wins
+        let state = make_market_state(40, 40, 40, 40);
+        let req = FastExecutionRequest::from_arb_mask(0, &state, 0b0011, 0).unwrap();
+        assert_eq!(req.arb_type, ArbType::PolyYesKalshiNo);
+        let req = FastExecutionRequest::from_arb_mask(0, &state, 0b1111, 0).unwrap();
+        assert_eq!(req.arb_type, ArbType::PolyOnly, "no fees beats the fee-paying combos");
+
+        assert!(FastExecutionRequest::from_arb_mask(0, &state, 0, 0).is_none());
+
+        for arb_type in [ArbType::PolyYesKalshiNo, ArbType::KalshiYesPolyNo, ArbType::PolyOnly, ArbType::KalshiOnly] {
+            let req = FastExecutionRequest::from_arb_mask(0, &state, arb_type.mask_bit(), 0).unwrap();
+            assert_eq!(req.arb_type, arb_type);
+        }
+    }
+
+    #[test]
+    fn test_request_carries_oldest_snapshot() {
+        let market = AtomicMarketState::new(0);
+        assert_eq!(market.poly.updated_ms(), None);
+        market.poly.store(40, 40, 1000, 1000);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        market.kalshi.store(40, 40, 1000, 1000);
+        let (k_ms, p_ms) = (market.kalshi.updated_ms().unwrap(), market.poly.updated_ms().unwrap());
+        assert!(k_ms > p_ms);
+
+        // Cross-platform prices are as old as the staler book
+        let cross = FastExecutionRequest::from_arb_mask(0, &market, ArbType::KalshiYesPolyNo.mask_bit(), 0).unwrap();
+        assert_eq!(cross.snapshot_ms, p_ms);
+        let kalshi_only = FastExecutionRequest::from_arb_mask(0, &market, ArbType::KalshiOnly.mask_bit(), 0).unwrap();
+        assert_eq!(kalshi_only.snapshot_ms, k_ms);
+
+        assert_eq!(cross.snapshot_age_ms(p_ms + 750), Some(750));
+        assert_eq!(cross.snapshot_age_ms(p_ms - 1), Some(0), "clock stepping back is no age");
+        let untimed = FastExecutionRequest { snapshot_ms: 0, ..cross };
+        assert_eq!(untimed.snapshot_age_ms(p_ms + 60_000), None);
+    }
+
     // =========================================================================
     // GlobalState Tests
     // =========================================================================
@@ -831,10 +1774,12 @@ mod tests {
             market_type: MarketType::Moneyline,
             description: format!("Test Market {}", id).into(),
             kalshi_event_ticker: format!("KXEPLGAME-{}", id).into(),
+            kalshi_series_ticker: "KXEPLGAME".into(),
             kalshi_market_ticker: format!("KXEPLGAME-{}-YES", id).into(),
             poly_slug: format!("test-{}", id).into(),
             poly_yes_token: format!("yes_token_{}", id).into(),
             poly_no_token: format!("no_token_{}", id).into(),
+            poly_constraints: Default::default(),
             line_value: None,
             team_suffix: None,
         }
@@ -952,6 +1897,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert_eq!(req.profit_cents(), 8);
@@ -970,6 +1917,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::KalshiYesPolyNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert_eq!(req.profit_cents(), 8);
@@ -988,6 +1937,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert_eq!(req.profit_cents(), 12);
@@ -1007,6 +1958,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::KalshiOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert_eq!(req.profit_cents(), 12);
@@ -1024,11 +1977,122 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert!(req.profit_cents() < 0, "Should have negative profit");
     }
 
+    #[test]
+    fn test_execution_request_net_profit_after_fees() {
+        // Poly YES 40¢ + Kalshi NO 50¢: 8¢ after the Kalshi fee
+        let req = FastExecutionRequest {
+            market_id: 0,
+            yes_price: 40,
+            no_price: 50,
+            yes_size: 1000,
+            no_size: 1000,
+            arb_type: ArbType::PolyYesKalshiNo,
+            detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
+        };
+        assert_eq!(req.net_profit_cents(&FeeSchedule::default()), 8.0);
+
+        // 2% Polymarket taker fee on min(40, 60)¢ = 0.8¢, plus 0.5¢ fixed costs
+        let fees = FeeSchedule { poly: FeeTiers::flat(200, 0), fixed_cost_cents: 0.5, min_profit_cents: 7.0, ..FeeSchedule::default() };
+        let net = req.net_profit_cents(&fees);
+        assert!((net - 6.7).abs() < 1e-9);
+        assert!(net < fees.min_profit_cents, "Marginal after fees");
+
+        // Kalshi-only arbs pay no Polymarket fee
+        let kalshi_only = FastExecutionRequest { arb_type: ArbType::KalshiOnly, ..req };
+        assert!((kalshi_only.net_profit_cents(&fees) - (kalshi_only.profit_cents() as f64 - 0.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_opportunity_value() {
+        let fees = FeeSchedule { fixed_cost_cents: 0.5, min_profit_cents: 1.0, ..FeeSchedule::default() };
+        let deep = FastExecutionRequest {
+            market_id: 0, yes_price: 40, no_price: 55, yes_size: 20_000, no_size: 8_000,
+            arb_type: ArbType::PolyOnly, detected_ns: 0, snapshot_ms: 0, order_type: None,
+        };
+        // 4.5¢ per contract on the 80 contracts of the thinner book
+        let value = deep.value(&fees, 50);
+        assert_eq!((value.contracts, value.confidence), (80, 1.0));
+        assert!((value.executable_profit_cents - 360.0).abs() < 1e-9);
+
+        // Wider edge but a single contract: ranks below the deep book
+        let thin = FastExecutionRequest { yes_price: 30, no_size: 100, ..deep }.value(&fees, 50);
+        assert_eq!((thin.contracts, thin.confidence), (1, 0.02));
+        assert!(thin.score() < value.score());
+    }
+
+    #[test]
+    fn test_order_type_parse() {
+        assert_eq!(OrderType::parse("IOC"), Some(OrderType::Ioc));
+        assert_eq!(OrderType::parse("market"), Some(OrderType::Market));
+        assert_eq!(OrderType::parse(" limit "), Some(OrderType::Limit));
+        assert_eq!(OrderType::parse("post-only"), Some(OrderType::PostOnly));
+        assert_eq!(OrderType::parse("gtd"), None);
+        assert_eq!(OrderType::default(), OrderType::Ioc);
+    }
+
+    #[test]
+    fn test_multi_outcome_full_set_profit() {
+        // Three candidates at 30¢ + 35¢ + 28¢ = 93¢ for a $1 payout
+        let req = MultiOutcomeRequest { market_index: 0, asks: vec![(30, 1000), (35, 500), (28, 2000)], detected_ns: 0 };
+        assert_eq!(req.cost_cents(), 93);
+        assert_eq!(req.contracts(), 5);
+        assert_eq!(req.net_profit_cents(&FeeSchedule::default()), Some(7.0));
+        // Nothing hedges if 30¢ and 35¢ fill and the 28¢ outcome wins
+        assert_eq!(req.worst_case_loss_cents(5), 325);
+
+        // 2% taker fee on each leg: 0.6 + 0.7 + 0.56
+        let fees = FeeSchedule { poly: FeeTiers::flat(200, 0), fixed_cost_cents: 0.5, min_profit_cents: 1.0, ..FeeSchedule::default() };
+        assert!((req.net_profit_cents(&fees).unwrap() - (7.0 - 1.86 - 0.5)).abs() < 1e-9);
+
+        // An outcome without an ask can't be bought
+        let missing = MultiOutcomeRequest { asks: vec![(30, 1000), (NO_PRICE, 0)], ..req };
+        assert_eq!(missing.net_profit_cents(&fees), None);
+    }
+
+    /// The Polymarket side of a binary pair as a two-outcome market
+    fn binary_full_set(pair: &MarketPair) -> MultiOutcomeMarket {
+        MultiOutcomeMarket {
+            event_slug: pair.poly_slug.clone(),
+            description: pair.description.clone(),
+            neg_risk: false,
+            outcomes: vec![
+                Outcome { name: "Yes".into(), token: pair.poly_yes_token.clone(), constraints: pair.poly_constraints },
+                Outcome { name: "No".into(), token: pair.poly_no_token.clone(), constraints: pair.poly_constraints },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_binary_market_is_two_outcome_case() {
+        let pair = make_test_pair("epl-che-avl");
+        let market = binary_full_set(&pair);
+        assert_eq!(market.outcomes.iter().map(|o| o.token.clone()).collect::<Vec<_>>(),
+                   vec![pair.poly_yes_token.clone(), pair.poly_no_token.clone()]);
+
+        let fees = FeeSchedule { poly: FeeTiers::flat(200, 0), fixed_cost_cents: 0.25, min_profit_cents: 1.0, ..FeeSchedule::default() };
+        let binary = FastExecutionRequest {
+            market_id: 0, yes_price: 40, no_price: 48, yes_size: 1000, no_size: 700,
+            arb_type: ArbType::PolyOnly, detected_ns: 0, snapshot_ms: 0, order_type: None,
+        };
+        let full_set = MultiOutcomeRequest { market_index: 0, asks: vec![(40, 1000), (48, 700)], detected_ns: 0 };
+        assert_eq!(full_set.net_profit_cents(&fees), Some(binary.net_profit_cents(&fees)));
+        assert_eq!(full_set.contracts(), binary.hedged_contracts());
+        assert_eq!(full_set.worst_case_loss_cents(7), 48 * 7);
+
+        let mut state = GlobalState::new();
+        assert_eq!(state.add_multi_outcome(market), Some(0));
+        assert_eq!(state.poly_outcome_to_id.get(&fxhash_str(&pair.poly_no_token)), Some(&(0, 1)));
+    }
+
     #[test]
     fn test_execution_request_estimated_fee() {
         // PolyYesKalshiNo → fee on Kalshi NO
@@ -1040,6 +2104,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
         assert_eq!(req1.estimated_fee_cents(), kalshi_fee_cents(50));
 
@@ -1052,6 +2118,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::KalshiYesPolyNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
         assert_eq!(req2.estimated_fee_cents(), kalshi_fee_cents(40));
 
@@ -1064,6 +2132,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
         assert_eq!(req3.estimated_fee_cents(), 0);
 
@@ -1076,6 +2146,8 @@ mod tests {
             no_size: 1000,
             arb_type: ArbType::KalshiOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
         assert_eq!(req4.estimated_fee_cents(), kalshi_fee_cents(40) + kalshi_fee_cents(50));
     }
@@ -1114,10 +2186,12 @@ mod tests {
             market_type: MarketType::Moneyline,
             description: "Chelsea vs Arsenal".into(),
             kalshi_event_ticker: "KXEPLGAME-25DEC27CFCARS".into(),
+            kalshi_series_ticker: "KXEPLGAME".into(),
             kalshi_market_ticker: "KXEPLGAME-25DEC27CFCARS-CFC".into(),
             poly_slug: "chelsea-vs-arsenal".into(),
             poly_yes_token: "yes_token_cfc".into(),
             poly_no_token: "no_token_cfc".into(),
+            poly_constraints: Default::default(),
             line_value: None,
             team_suffix: Some("CFC".into()),
         };
@@ -1142,7 +2216,7 @@ mod tests {
 
         // 3. Check for arbs (threshold = 100 cents = $1.00)
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         // 4. Verify arb detected
         assert!(arb_mask & 1 != 0, "Should detect Poly YES + Kalshi NO arb");
@@ -1159,6 +2233,8 @@ mod tests {
             no_size: k_no_sz,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert!(req.profit_cents() > 0, "Should have positive profit");
@@ -1188,7 +2264,7 @@ mod tests {
                     }
 
                     // Check arbs (should never panic) - threshold = 100 cents
-                    let _ = market.check_arbs(100);
+                    let _ = market.check_arbs_in(100, false);
                 }
             })
         }).collect();
@@ -1207,6 +2283,28 @@ mod tests {
         assert!(p_yes > 0 && p_yes < 100);
         assert!(p_no > 0 && p_no < 100);
     }
+
+    #[test]
+    fn test_spread_quote_skews_with_inventory() {
+        let config = SpreadCaptureConfig {
+            min_spread_cents: 4, quote_contracts: 10, target_inventory: 0.0, max_inventory: 100.0, skew_cents: 2.0,
+        };
+        let req = SpreadRequest { market_id: 0, bid: 40, ask: 48, detected_ns: 0 };
+        assert_eq!(req.spread_cents(), 8);
+        assert_eq!(req.quote(&config, 0.0), Some(SpreadQuote { bid: Some((41, 10)), ask: Some((47, 10)) }));
+
+        // Long YES: both quotes lower, and the bid shrinks to the room left
+        assert_eq!(req.quote(&config, 95.0), Some(SpreadQuote { bid: Some((39, 5)), ask: Some((45, 10)) }));
+        // At the limit only the side working it off is quoted
+        assert_eq!(req.quote(&config, 100.0), Some(SpreadQuote { bid: None, ask: Some((45, 10)) }));
+        assert_eq!(req.quote(&config, -150.0), Some(SpreadQuote { bid: Some((43, 10)), ask: None }));
+
+        // Skew never pushes a quote through the opposite best
+        let tight = SpreadRequest { bid: 40, ask: 43, ..req };
+        let skewed = SpreadCaptureConfig { skew_cents: 10.0, ..config };
+        assert_eq!(tight.quote(&skewed, -100.0), Some(SpreadQuote { bid: Some((42, 10)), ask: None }));
+        assert_eq!(SpreadRequest { ask: 42, ..tight }.quote(&config, 0.0), None, "no room inside the spread");
+    }
 }
 
 // === Kalshi API Types ===
@@ -1222,6 +2320,9 @@ pub struct KalshiEventsResponse {
 #[derive(Debug, Deserialize, Clone)]
 pub struct KalshiEvent {
     pub event_ticker: String,
+    /// Series the event is listed under
+    #[serde(default)]
+    pub series_ticker: Option<String>,
     pub title: String,
     #[serde(default)]
     #[allow(dead_code)]
@@ -1248,6 +2349,17 @@ pub struct KalshiMarket {
     pub floor_strike: Option<f64>,
     pub volume: Option<i64>,
     pub liquidity: Option<i64>,
+    /// Lifecycle state (`active`, `closed`, `settled`, ...)
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Settlement outcome (`yes`/`no`) once determined
+    #[serde(default)]
+    pub result: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KalshiMarketResponse {
+    pub market: KalshiMarket,
 }
 
 // === Polymarket/Gamma API Types ===
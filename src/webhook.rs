@@ -0,0 +1,316 @@
+//! Trade records POSTed to a webhook.
+//!
+//! With `WEBHOOK_URL` set, every trade the `ExecutionEngine` reports (the
+//! details of the Telegram `TradeExecuted` notification, plus a timestamp and
+//! each leg's fill and order ID) is POSTed as one JSON object, for ingestion
+//! into an external ledger. Records wait in a queue of `WEBHOOK_QUEUE_SIZE`
+//! and are sent one at a time; when the endpoint falls that far behind new
+//! records are dropped rather than holding up execution. Network errors, 429
+//! and 5xx responses are retried up to `WEBHOOK_MAX_ATTEMPTS` times with
+//! exponential backoff.
+//!
+//! The same endpoint receives alerts that cannot go through Telegram, such
+//! as Telegram itself failing (`{"timestamp", "alert", "message"}`).
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::config::{HttpConfig, WebhookConfig};
+use crate::execution::{TradeFill, TradeReport};
+use crate::types::ArbType;
+
+/// Body of one POST
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradeRecord {
+    /// RFC 3339, when the trade was reported
+    pub timestamp: String,
+    pub market: String,
+    pub arb_type: ArbType,
+    /// Matched contracts
+    pub contracts: i64,
+    pub requested_contracts: i64,
+    /// Size sent on every leg
+    pub hedge_contracts: Option<i64>,
+    pub limit_hit: Option<String>,
+    pub profit_cents: i16,
+    pub success: bool,
+    /// Paper trade from dry-run mode
+    pub simulated: bool,
+    pub latency_ms: u64,
+    pub unwound_contracts: i64,
+    pub cancelled_orders: u32,
+    pub fills: Vec<TradeFill>,
+}
+
+impl TradeRecord {
+    pub fn new(report: &TradeReport, timestamp: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            timestamp: timestamp.to_rfc3339(),
+            market: report.market.clone(),
+            arb_type: report.arb_type,
+            contracts: report.contracts,
+            requested_contracts: report.requested_contracts,
+            hedge_contracts: report.hedge_contracts,
+            limit_hit: report.limit_hit.as_ref().map(|l| l.to_string()),
+            profit_cents: report.profit_cents,
+            success: report.success,
+            simulated: report.simulated,
+            latency_ms: report.latency_ms,
+            unwound_contracts: report.unwound_contracts,
+            cancelled_orders: report.cancelled_orders,
+            fills: report.fills.clone(),
+        }
+    }
+}
+
+/// Body of an alert POST
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertRecord {
+    /// RFC 3339, when the alert was raised
+    pub timestamp: String,
+    /// Kind of alert, e.g. `telegram_failing`
+    pub alert: &'static str,
+    pub message: String,
+}
+
+/// What the sender task posts
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+enum WebhookRecord {
+    Trade(TradeRecord),
+    Alert(AlertRecord),
+}
+
+impl WebhookRecord {
+    fn describe(&self) -> String {
+        match self {
+            Self::Trade(record) => format!("trade record for {}", record.market),
+            Self::Alert(record) => format!("{} alert", record.alert),
+        }
+    }
+}
+
+/// `Name: value`, or a bare value for `Authorization`
+pub fn parse_auth_header(header: &str) -> Result<(HeaderName, HeaderValue)> {
+    let named = header.split_once(':')
+        .and_then(|(name, value)| Some((HeaderName::from_bytes(name.trim().as_bytes()).ok()?, value.trim())));
+    let (name, value) = named.unwrap_or((AUTHORIZATION, header.trim()));
+    let mut value = HeaderValue::from_str(value).context("Invalid WEBHOOK_AUTH_HEADER value")?;
+    value.set_sensitive(true);
+    Ok((name, value))
+}
+
+/// Worth another attempt: the endpoint is overloaded or failing, not rejecting the record
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// POSTs records to the configured endpoint
+pub struct WebhookClient {
+    http: reqwest::Client,
+    url: String,
+    auth: Option<(HeaderName, HeaderValue)>,
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+impl WebhookClient {
+    pub fn new(http: &HttpConfig, url: String, auth_header: Option<&str>, max_attempts: u32, backoff: Duration) -> Result<Self> {
+        let http = http.client().context("Failed to build HTTP client")?;
+        let auth = auth_header.map(parse_auth_header).transpose()?;
+        Ok(Self { http, url, auth, max_attempts: max_attempts.max(1), backoff })
+    }
+
+    /// Send one record, retrying transient failures
+    pub async fn post(&self, record: &impl Serialize) -> Result<()> {
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+        loop {
+            let mut request = self.http.post(&self.url).json(record);
+            if let Some((name, value)) = &self.auth {
+                request = request.header(name.clone(), value.clone());
+            }
+            let error = match request.send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) if !retryable(resp.status()) => bail!("{} rejected the record: HTTP {}", self.url, resp.status()),
+                Ok(resp) => format!("HTTP {}", resp.status()),
+                Err(e) => e.to_string(),
+            };
+            if attempt >= self.max_attempts {
+                bail!("{} failed after {} attempts: {}", self.url, attempt, error);
+            }
+            debug!("[WEBHOOK] Attempt {}/{} failed ({}), retrying in {:?}", attempt, self.max_attempts, error, delay);
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+/// Queue of records for the sender task
+#[derive(Clone)]
+pub struct WebhookSender {
+    tx: mpsc::Sender<WebhookRecord>,
+}
+
+impl WebhookSender {
+    /// Queue a trade; dropped with a warning when the queue is full
+    pub fn send(&self, report: &TradeReport) {
+        self.queue(WebhookRecord::Trade(TradeRecord::new(report, chrono::Utc::now())));
+    }
+
+    /// Queue an alert of kind `alert`
+    pub fn alert(&self, alert: &'static str, message: String) {
+        self.queue(WebhookRecord::Alert(AlertRecord { timestamp: chrono::Utc::now().to_rfc3339(), alert, message }));
+    }
+
+    fn queue(&self, record: WebhookRecord) {
+        if let Err(mpsc::error::TrySendError::Full(record)) = self.tx.try_send(record) {
+            warn!("[WEBHOOK] Queue full, {} dropped", record.describe());
+        }
+    }
+}
+
+/// Start the sender task, or `None` when no webhook is configured
+pub fn spawn(config: WebhookConfig) -> Option<WebhookSender> {
+    let url = config.url?;
+    let client = match WebhookClient::new(&HttpConfig::webhook_from_env(), url.clone(), config.auth_header.as_deref(), config.max_attempts, Duration::from_millis(config.retry_backoff_ms)) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("[WEBHOOK] Trade webhook disabled: {:#}", e);
+            return None;
+        }
+    };
+    info!("[WEBHOOK] Posting trades to {}", url);
+
+    let (tx, mut rx) = mpsc::channel::<WebhookRecord>(config.queue_size);
+    tokio::spawn(async move {
+        while let Some(record) = rx.recv().await {
+            if let Err(e) = client.post(&record).await {
+                warn!("[WEBHOOK] Failed to deliver {}: {:#}", record.describe(), e);
+            }
+        }
+    });
+    Some(WebhookSender { tx })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn report() -> TradeReport {
+        TradeReport {
+            market: "Chelsea vs Aston Villa".into(),
+            arb_type: ArbType::PolyYesKalshiNo,
+            contracts: 10,
+            requested_contracts: 10,
+            hedge_contracts: Some(10),
+            limit_hit: None,
+            profit_cents: 25,
+            success: true,
+            latency_ms: 180,
+            simulated: false,
+            unwound_contracts: 0,
+            cancelled_orders: 0,
+            fills: vec![
+                TradeFill { platform: "polymarket", side: "yes".into(), contracts: 10, cost_cents: 420, order_id: "0xabc".into() },
+                TradeFill { platform: "kalshi", side: "no".into(), contracts: 10, cost_cents: 555, order_id: "k-1".into() },
+            ],
+        }
+    }
+
+    /// Local endpoint answering each request with the next status, recording
+    /// the request heads and bodies
+    async fn mock_endpoint(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<(String, serde_json::Value)>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/trades", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let (head_len, content_len) = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&buf[..pos]).to_lowercase();
+                        let len = head.lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .and_then(|v| v.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        break (pos + 4, len);
+                    }
+                };
+                while buf.len() < head_len + content_len {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let head = String::from_utf8_lossy(&buf[..head_len]).to_lowercase();
+                let json = serde_json::from_slice(&buf[head_len..]).unwrap_or_default();
+                seen.lock().unwrap().push((head, json));
+                let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn test_auth_header_forms() {
+        let (name, value) = parse_auth_header("X-Api-Key: s3cret").unwrap();
+        assert_eq!((name.as_str(), value.to_str().unwrap()), ("x-api-key", "s3cret"));
+        let (name, value) = parse_auth_header("Bearer abc").unwrap();
+        assert_eq!((name, value.to_str().unwrap()), (AUTHORIZATION, "Bearer abc"));
+        assert!(value.is_sensitive());
+    }
+
+    #[test]
+    fn test_alert_body() {
+        let record = WebhookRecord::Alert(AlertRecord {
+            timestamp: "2026-10-14T12:00:00+00:00".into(),
+            alert: "telegram_failing",
+            message: "80% of the last 5 Telegram notifications failed".into(),
+        });
+        assert_eq!(serde_json::to_value(&record).unwrap(), serde_json::json!({
+            "timestamp": "2026-10-14T12:00:00+00:00",
+            "alert": "telegram_failing",
+            "message": "80% of the last 5 Telegram notifications failed",
+        }));
+        assert_eq!(record.describe(), "telegram_failing alert");
+    }
+
+    #[tokio::test]
+    async fn test_record_posted_with_retry() {
+        let (url, requests) = mock_endpoint(vec![503, 429, 200, 400]).await;
+        let http = HttpConfig { proxy: None, connect_timeout_ms: 1000, timeout_ms: 5000 };
+        let client = WebhookClient::new(&http, url, Some("Bearer abc"), 3, Duration::from_millis(1)).unwrap();
+        let now = chrono::DateTime::parse_from_rfc3339("2026-10-14T12:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        let record = TradeRecord::new(&report(), now);
+        client.post(&record).await.unwrap();
+
+        {
+            let requests = requests.lock().unwrap();
+            assert_eq!(requests.len(), 3, "two transient failures, then delivered");
+            let (head, body) = &requests[2];
+            assert!(head.starts_with("post /trades") && head.contains("authorization: bearer abc"));
+            assert_eq!(body["timestamp"], "2026-10-14T12:00:00+00:00");
+            assert_eq!(body["arb_type"], "poly_yes_kalshi_no");
+            assert_eq!(body["fills"][1], serde_json::json!({
+                "platform": "kalshi", "side": "no", "contracts": 10, "cost_cents": 555, "order_id": "k-1",
+            }));
+        }
+
+        // A client error is not retried
+        assert!(client.post(&record).await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 4);
+    }
+}
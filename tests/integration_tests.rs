@@ -147,6 +147,8 @@ mod circuit_breaker_tests {
             max_daily_loss: 25.0,
             max_consecutive_errors: 3,
             cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
             enabled: true,
         }
     }
@@ -305,6 +307,8 @@ mod e2e_tests {
             max_daily_loss: 10.0,  // Low threshold for test
             max_consecutive_errors: 5,
             cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
             enabled: true,
         };
         
@@ -470,10 +474,12 @@ mod infra_integration_tests {
             market_type: MarketType::Moneyline,
             description: "Test Market".into(),
             kalshi_event_ticker: "KXEPLGAME-25DEC27CFCARS".into(),
+            kalshi_series_ticker: "KXEPLGAME".into(),
             kalshi_market_ticker: "KXEPLGAME-25DEC27CFCARS-CFC".into(),
             poly_slug: "arb-test".into(),
             poly_yes_token: "arb_yes_token".into(),
             poly_no_token: "arb_no_token".into(),
+            poly_constraints: Default::default(),
             line_value: None,
             team_suffix: Some("CFC".into()),
         };
@@ -501,7 +507,7 @@ mod infra_integration_tests {
         let (state, market_id) = setup_market(55, 50, 40, 65);
 
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);  // 100¢ = $1.00 threshold
+        let arb_mask = market.check_arbs_in(100, false);  // 100¢ = $1.00 threshold
 
         assert!(arb_mask & 1 != 0, "Should detect Poly YES + Kalshi NO arb (bit 0)");
     }
@@ -515,7 +521,7 @@ mod infra_integration_tests {
         let (state, market_id) = setup_market(40, 65, 55, 50);
 
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         assert!(arb_mask & 2 != 0, "Should detect Kalshi YES + Poly NO arb (bit 1)");
     }
@@ -527,7 +533,7 @@ mod infra_integration_tests {
         let (state, market_id) = setup_market(60, 60, 48, 50);
 
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         assert!(arb_mask & 4 != 0, "Should detect Poly-only arb (bit 2)");
     }
@@ -541,7 +547,7 @@ mod infra_integration_tests {
         let (state, market_id) = setup_market(44, 44, 60, 60);
 
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         assert!(arb_mask & 8 != 0, "Should detect Kalshi-only arb (bit 3)");
     }
@@ -555,7 +561,7 @@ mod infra_integration_tests {
         let (state, market_id) = setup_market(55, 50, 49, 55);
 
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         assert!(arb_mask & 1 == 0, "Fees should eliminate marginal Poly YES + Kalshi NO arb");
     }
@@ -567,7 +573,7 @@ mod infra_integration_tests {
         let (state, market_id) = setup_market(55, 55, 52, 52);
 
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         assert_eq!(arb_mask, 0, "Should detect no arbs in efficient market");
     }
@@ -578,7 +584,7 @@ mod infra_integration_tests {
         let (state, market_id) = setup_market(50, NO_PRICE, 50, 50);
 
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         assert_eq!(arb_mask, 0, "Should return 0 when any price is missing");
     }
@@ -622,6 +628,8 @@ mod infra_integration_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert_eq!(req.profit_cents(), 8, "Profit should be 8¢");
@@ -639,6 +647,8 @@ mod infra_integration_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         assert!(req.profit_cents() < 0, "Should calculate negative profit");
@@ -684,10 +694,12 @@ mod infra_integration_tests {
                 market_type: MarketType::Moneyline,
                 description: format!("Market {}", i).into(),
                 kalshi_event_ticker: format!("KXTEST-{}", i).into(),
+                kalshi_series_ticker: "KXTEST".into(),
                 kalshi_market_ticker: format!("KXTEST-{}-YES", i).into(),
                 poly_slug: format!("test-{}", i).into(),
                 poly_yes_token: format!("yes_{}", i).into(),
                 poly_no_token: format!("no_{}", i).into(),
+                poly_constraints: Default::default(),
                 line_value: None,
                 team_suffix: None,
             };
@@ -741,7 +753,7 @@ mod infra_integration_tests {
 
         // 2. Detect arb (threshold = 100 cents = $1.00)
         let market = state.get_by_id(market_id).unwrap();
-        let arb_mask = market.check_arbs(100);
+        let arb_mask = market.check_arbs_in(100, false);
 
         assert!(arb_mask & 1 != 0, "Step 2: Should detect arb");
 
@@ -758,6 +770,8 @@ mod infra_integration_tests {
             no_size: k_no_sz,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // 5. Verify request is valid
@@ -798,6 +812,8 @@ mod execution_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // 50 + 50 + 2 (fee) = 102 > 100 → negative profit
@@ -813,6 +829,8 @@ mod execution_tests {
             max_daily_loss: 25.0,
             max_consecutive_errors: 3,
             cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
             enabled: true,
         };
 
@@ -873,6 +891,373 @@ mod execution_tests {
         assert!(t2 > t1, "Clock should be monotonic");
         assert!(t2 - t1 >= 100_000, "Should measure at least 100µs");
     }
+    /// Test: repeated detections are reported once until they lapse or move
+    #[test]
+    fn test_opportunity_dedup() {
+        use arb_bot::config::OpportunityDedupConfig;
+        use arb_bot::execution::OpportunityDeduper;
+        use std::time::{Duration, Instant};
+
+        let mut dedup = OpportunityDeduper::new(OpportunityDedupConfig {
+            ttl_secs: 30,
+            min_profit_change_cents: 2,
+        });
+        let t0 = Instant::now();
+
+        assert!(dedup.observe(1, ArbType::PolyOnly, 3, t0), "First sighting is reported");
+        assert!(!dedup.observe(1, ArbType::PolyOnly, 3, t0 + Duration::from_secs(1)));
+        assert!(!dedup.observe(1, ArbType::PolyOnly, 4, t0 + Duration::from_secs(2)), "1¢ move is not material");
+        assert!(dedup.observe(1, ArbType::PolyOnly, 5, t0 + Duration::from_secs(3)), "2¢ move is material");
+
+        // Other direction on the same market is a distinct opportunity
+        assert!(dedup.observe(1, ArbType::KalshiOnly, 5, t0 + Duration::from_secs(3)));
+
+        // Continuous sightings keep it suppressed; a gap beyond the TTL re-reports
+        assert!(!dedup.observe(1, ArbType::PolyOnly, 5, t0 + Duration::from_secs(30)));
+        assert!(dedup.observe(1, ArbType::PolyOnly, 5, t0 + Duration::from_secs(61)));
+    }
+
+    /// Test: the imbalance filter only checks the Polymarket books being bought
+    #[test]
+    fn test_imbalance_entry_filter() {
+        use arb_bot::execution::imbalance_allows;
+
+        let book = BookImbalance::new();
+        book.store(true, Some(-0.4));
+        book.store(false, Some(0.3));
+        let imbalance = book.load();
+        assert_eq!(imbalance, (Some(-0.4), Some(0.3)));
+
+        // Disabled by default
+        assert!(imbalance_allows(None, ArbType::PolyOnly, imbalance));
+        // Poly YES is ask-heavy: blocked; Poly NO leans to bids: allowed
+        assert!(!imbalance_allows(Some(0.2), ArbType::PolyYesKalshiNo, imbalance));
+        assert!(imbalance_allows(Some(0.2), ArbType::KalshiYesPolyNo, imbalance));
+        assert!(!imbalance_allows(Some(0.2), ArbType::PolyOnly, imbalance));
+        assert!(imbalance_allows(Some(0.2), ArbType::KalshiOnly, imbalance));
+
+        // Unknown books don't block
+        book.clear();
+        assert!(imbalance_allows(Some(0.2), ArbType::PolyOnly, book.load()));
+    }
+
+    /// Test: both legs get the largest size valid on each platform
+    #[test]
+    fn test_hedge_size_respects_lots_and_minimums() {
+        use arb_bot::execution::hedge_size;
+
+        // Kalshi lot 1, Polymarket 5-share minimum
+        assert_eq!(hedge_size(12, &[(1, 1), (1, 5)]), Some(12));
+        assert_eq!(hedge_size(4, &[(1, 1), (1, 5)]), None, "below the Polymarket minimum");
+        // Lots of 2 and 3 only meet on multiples of 6
+        assert_eq!(hedge_size(17, &[(2, 2), (3, 3)]), Some(12));
+        assert_eq!(hedge_size(5, &[(2, 2), (3, 3)]), None);
+        // Minimum above the aligned size
+        assert_eq!(hedge_size(19, &[(10, 10), (1, 25)]), None);
+        assert_eq!(hedge_size(0, &[(1, 1), (1, 1)]), None);
+    }
+
+    /// Test: each platform must cover its own legs, Kalshi fees included
+    #[test]
+    fn test_required_funds_per_platform() {
+        use arb_bot::execution::{BalanceCache, required_funds};
+        use std::time::Duration;
+
+        // Poly YES 40¢ + Kalshi NO 45¢ (2¢ fee) x10
+        assert_eq!(required_funds(ArbType::PolyYesKalshiNo, 10, 40, 45),
+                   vec![(Platform::Polymarket, 400), (Platform::Kalshi, 470)]);
+        // Both legs on one account add up
+        assert_eq!(required_funds(ArbType::PolyOnly, 10, 40, 55), vec![(Platform::Polymarket, 950)]);
+        assert_eq!(required_funds(ArbType::KalshiOnly, 1, 40, 55).len(), 1);
+
+        let cache = BalanceCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get(Platform::Kalshi), None);
+        cache.store(Platform::Kalshi, 1_000);
+        assert_eq!(cache.get(Platform::Kalshi), Some(1_000));
+        assert_eq!(cache.get(Platform::Polymarket), None);
+        cache.invalidate(Platform::Kalshi);
+        assert_eq!(cache.get(Platform::Kalshi), None);
+
+        // Expired entries are refetched
+        let cache = BalanceCache::new(Duration::ZERO);
+        cache.store(Platform::Polymarket, 1_000);
+        assert_eq!(cache.get(Platform::Polymarket), None);
+
+        // Trades in flight hold their funds against the balance
+        let funds = required_funds(ArbType::PolyYesKalshiNo, 10, 40, 45);
+        assert_eq!(cache.reserve(&funds, &[Some(1_000), Some(1_000)]), Ok(()));
+        assert_eq!(cache.reserved(Platform::Kalshi), 470);
+        assert_eq!(cache.reserve(&funds, &[Some(1_000), Some(1_000)]), Ok(()));
+        assert_eq!(cache.reserve(&funds, &[Some(1_000), Some(1_000)]), Err((Platform::Polymarket, 400, 200)));
+        assert_eq!(cache.reserved(Platform::Polymarket), 800, "nothing held by a refused trade");
+        assert_eq!(cache.reserve(&funds, &[None, None]), Ok(()), "unknown balances are not checked");
+        cache.release(&funds);
+        cache.release(&funds);
+        cache.release(&funds);
+        assert_eq!((cache.reserved(Platform::Polymarket), cache.reserved(Platform::Kalshi)), (0, 0));
+    }
+
+    /// Test: the daily loss limit halts until the next trading day; oversized trades are refused
+    #[test]
+    fn test_risk_guard_daily_halt() {
+        use arb_bot::config::RiskLimitsConfig;
+        use arb_bot::execution::{RiskGuard, next_day_start, worst_case_loss_cents};
+        use chrono::{TimeZone, Utc};
+
+        let guard = RiskGuard::new(RiskLimitsConfig { max_daily_loss_cents: Some(5_000), max_trade_loss_cents: Some(2_000), daily_reset_hour: 0 });
+        let now = Utc.with_ymd_and_hms(2026, 3, 14, 23, 30, 0).unwrap();
+        let midnight = Utc.with_ymd_and_hms(2026, 3, 15, 0, 0, 0).unwrap();
+        assert_eq!(next_day_start(now, 0), midnight);
+        assert_eq!(next_day_start(midnight, 0), Utc.with_ymd_and_hms(2026, 3, 16, 0, 0, 0).unwrap());
+        assert_eq!(next_day_start(now, 6), Utc.with_ymd_and_hms(2026, 3, 15, 6, 0, 0).unwrap());
+        assert_eq!(next_day_start(midnight, 6), Utc.with_ymd_and_hms(2026, 3, 15, 6, 0, 0).unwrap());
+
+        assert_eq!(guard.check_daily_loss(-5_000, now), None, "at the limit is still allowed");
+        assert_eq!(guard.check_daily_loss(-5_001, now), Some(midnight));
+        assert_eq!(guard.check_daily_loss(-6_000, now), None, "already halted");
+        assert_eq!(guard.halted_until(now), Some(midnight));
+        assert_eq!(guard.halted_until(midnight), None, "lifted at the next trading day");
+
+        // With a 06:00 reset the halt runs to 06:00, not midnight
+        let late = RiskGuard::new(RiskLimitsConfig { max_daily_loss_cents: Some(5_000), max_trade_loss_cents: None, daily_reset_hour: 6 });
+        let resume = Utc.with_ymd_and_hms(2026, 3, 15, 6, 0, 0).unwrap();
+        assert_eq!(late.check_daily_loss(-5_001, now), Some(resume));
+        assert_eq!(late.halted_until(midnight), Some(resume));
+
+        // Poly YES 40¢ + Kalshi NO 45¢ (2¢ fee) x40: the Kalshi leg is the larger risk
+        assert_eq!(worst_case_loss_cents(ArbType::PolyYesKalshiNo, 40, 40, 45), 1_880);
+        assert!(guard.trade_allowed(1_880));
+        assert!(!guard.trade_allowed(worst_case_loss_cents(ArbType::PolyOnly, 50, 40, 55)));
+
+        let off = RiskGuard::new(RiskLimitsConfig::default());
+        assert_eq!(off.check_daily_loss(-1_000_000, now), None);
+        assert!(off.trade_allowed(i64::MAX));
+    }
+
+    /// Test: with several simultaneous opportunities, the deepest profitable one ranks first
+    #[test]
+    fn test_rank_top_opportunity() {
+        use arb_bot::config::FeeSchedule;
+        use arb_bot::execution::rank_top;
+
+        let fees = FeeSchedule { fixed_cost_cents: 0.0, min_profit_cents: 1.0, ..FeeSchedule::default() };
+        let req = |market_id, yes_price, size| FastExecutionRequest {
+            market_id, yes_price, no_price: 50, yes_size: size, no_size: size,
+            arb_type: ArbType::PolyOnly, detected_ns: 0, snapshot_ms: 0, order_type: None,
+        };
+        // 10¢ on 1 contract, 4¢ on 60 contracts, 4¢ on 60 again (tie)
+        let batch = vec![req(1, 40, 100), req(2, 46, 6_000), req(3, 46, 6_000)];
+        let (top, rest) = rank_top(batch, &fees, 50).unwrap();
+        assert_eq!(top.market_id, 2, "deep book wins, earliest on ties");
+        assert_eq!(rest.iter().map(|r| r.market_id).collect::<Vec<_>>(), vec![1, 3]);
+
+        assert!(rank_top(Vec::new(), &fees, 50).is_none());
+    }
+
+    /// Test: limited balance goes to the most profitable opportunities per dollar first
+    #[test]
+    fn test_allocate_by_profit_density() {
+        use arb_bot::config::FeeSchedule;
+        use arb_bot::execution::{allocate, AllocationLimit};
+        use arb_bot::types::Platform;
+
+        let fees = FeeSchedule { fixed_cost_cents: 0.0, min_profit_cents: 1.0, ..FeeSchedule::default() };
+        let req = |market_id, yes_price, no_price| FastExecutionRequest {
+            market_id, yes_price, no_price, yes_size: 10_000, no_size: 10_000,
+            arb_type: ArbType::PolyOnly, detected_ns: 0, snapshot_ms: 0, order_type: None,
+        };
+        // 5¢ on 95¢, 8¢ on 92¢, a worse arb on market 2 again, and no edge at all
+        let batch = [req(1, 45, 50), req(2, 42, 50), req(2, 44, 50), req(3, 50, 50)];
+        let wanted = [100, 30, 100, 100];
+
+        // $50 on Polymarket: market 2 takes its 30 (27.60), market 1 the 23 left $22.40 pays for
+        let allocations = allocate(&batch, &wanted, &[(Platform::Polymarket, 5_000)], &fees);
+        let sizes: Vec<i64> = allocations.iter().map(|a| a.contracts).collect();
+        assert_eq!(sizes, [23, 30, 0, 0]);
+        assert!(allocations[1].cents_per_dollar > allocations[0].cents_per_dollar);
+        assert_eq!(allocations[0].limit, Some(AllocationLimit::Balance(Platform::Polymarket)));
+        assert_eq!(allocations[1].limit, None);
+        assert_eq!(allocations[2].limit, Some(AllocationLimit::SameMarket));
+        assert_eq!(allocations[3].limit, Some(AllocationLimit::Unprofitable));
+
+        // Unknown balance: every profitable opportunity gets its full size
+        let sizes: Vec<i64> = allocate(&batch, &wanted, &[], &fees).iter().map(|a| a.contracts).collect();
+        assert_eq!(sizes, [100, 30, 0, 0]);
+    }
+
+    /// Test: a partial fill's short leg is only completed at a price that keeps the pair profitable
+    #[test]
+    fn test_partial_fill_completion_limit() {
+        use arb_bot::execution::completion_limit;
+
+        // YES filled at 55¢ avg: NO must cost at most 44¢, original limit 43¢ + 1¢ chase
+        assert_eq!(completion_limit(43, 55.0, 1, Platform::Polymarket), Some(44));
+        assert_eq!(completion_limit(43, 55.0, 5, Platform::Polymarket), Some(44), "capped below $1 per pair");
+        assert_eq!(completion_limit(43, 56.4, 0, Platform::Polymarket), Some(42), "ceiling rounds the entry cost up");
+        // On Kalshi the fee counts against the ceiling: 42¢ + 2¢ fee = 44¢
+        assert_eq!(completion_limit(43, 55.0, 1, Platform::Kalshi), Some(42));
+        // Long leg bought at 99¢: nothing left
+        assert_eq!(completion_limit(43, 99.0, 1, Platform::Polymarket), None);
+    }
+
+    /// Test: the P&L of an unwound partial fill counts its loss, not the full excess cost
+    #[test]
+    fn test_partial_fill_profit_accounting() {
+        use arb_bot::execution::{Unwind, arb_legs, hedged_profit_cents};
+
+        // 10 YES @45¢ and 6 NO @50¢; 4 YES sold back @35¢
+        let unwind = Unwind { contracts: 4, cost: 180, proceeds: 140 };
+        let yes = (10 - unwind.contracts, 450 - unwind.cost);
+        let profit = hedged_profit_cents(yes.0, yes.1, 6, 300) + unwind.pnl_cents();
+        assert_eq!(hedged_profit_cents(yes.0, yes.1, 6, 300), 30);
+        assert_eq!(profit, -10, "6 pairs lock in 30¢, the unwind loses 40¢");
+
+        // No leg filled at all: only the unwind loss remains
+        assert_eq!(hedged_profit_cents(5, 250, 0, 0), 0);
+
+        let (yes_leg, no_leg) = arb_legs(ArbType::PolyYesKalshiNo);
+        assert_eq!((yes_leg.to_string(), no_leg.to_string()), ("P_yes".to_string(), "K_no".to_string()));
+        assert_eq!(no_leg.platform_name(), "kalshi");
+    }
+
+    /// Test: the kill switch keeps retrying failed and partial closes, then books P&L
+    #[tokio::test]
+    async fn test_flatten_retries_failed_closes() {
+        use arb_bot::config::FlattenConfig;
+        use arb_bot::error::Error;
+        use arb_bot::execution::{close_legs, flatten_plan};
+        use std::sync::{Arc, Mutex};
+
+        let mut state = GlobalState::new();
+        state.add_pair(MarketPair {
+            pair_id: "flatten-test".into(),
+            league: "epl".into(),
+            market_type: MarketType::Moneyline,
+            description: "Flatten Test".into(),
+            kalshi_event_ticker: "KXFLAT".into(),
+            kalshi_series_ticker: "KXFLAT".into(),
+            kalshi_market_ticker: "KXFLAT-YES".into(),
+            poly_slug: "flatten-test".into(),
+            poly_yes_token: "flat_yes_token".into(),
+            poly_no_token: "flat_no_token".into(),
+            poly_constraints: Default::default(),
+            line_value: None,
+            team_suffix: None,
+        });
+
+        let mut tracker = PositionTracker::new();
+        tracker.record_fill_internal(&FillRecord::new("flatten-test", "Flatten Test", "kalshi", "no", 10.0, 0.50, 0.0, "k1"));
+        tracker.record_fill_internal(&FillRecord::new("flatten-test", "Flatten Test", "polymarket", "yes", 10.0, 0.40, 0.0, "p1"));
+        // Polymarket leg of a market discovery no longer knows: can't be sold
+        tracker.record_fill_internal(&FillRecord::new("orphan-slug", "Orphan", "polymarket", "yes", 5.0, 0.30, 0.0, "p2"));
+
+        let mut legs = flatten_plan(&tracker, &state);
+        assert_eq!(legs.len(), 3);
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let seen = calls.clone();
+        let config = FlattenConfig { max_attempts: 3, retry_delay_ms: 0 };
+        close_legs(&mut legs, config, move |platform, side, target, contracts| {
+            let mut calls = seen.lock().unwrap();
+            calls.push((target.to_string(), side, contracts));
+            let attempt = calls.iter().filter(|(t, _, _)| **t == *target).count();
+            std::future::ready(match (platform, attempt) {
+                ("kalshi", 1) => Err(Error::Network("timeout".into())),
+                ("kalshi", _) => Ok((contracts, contracts * 0.60)),
+                // Polymarket fills 6 then the rest
+                (_, 1) => Ok((6.0, 6.0 * 0.45)),
+                _ => Ok((contracts, contracts * 0.45)),
+            })
+        }).await;
+
+        let calls = calls.lock().unwrap().clone();
+        assert_eq!(calls, vec![
+            ("KXFLAT-YES".to_string(), "no", 10.0),
+            ("flat_yes_token".to_string(), "yes", 10.0),
+            ("KXFLAT-YES".to_string(), "no", 10.0),
+            ("flat_yes_token".to_string(), "yes", 4.0),
+        ]);
+
+        let mut realized = 0.0;
+        for leg in legs.iter().filter(|l| l.closed > 0.0) {
+            realized += tracker.record_close(&leg.market_id, leg.platform, leg.side, leg.closed, leg.proceeds).unwrap();
+        }
+        assert!((realized - 1.5).abs() < 1e-9, "kalshi +$1.00, poly +$0.50");
+        assert!(legs[..2].iter().all(|l| l.is_done() && l.error.is_none()));
+        assert_eq!(legs[2].attempts, 0);
+        assert_eq!(legs[2].error.as_deref(), Some("market not found"));
+
+        assert_eq!(tracker.get("flatten-test").unwrap().status, "closed");
+        let summary = tracker.summary();
+        assert_eq!(summary.open_positions, 1);
+        assert!((summary.realized_pnl - 1.5).abs() < 1e-9);
+    }
+
+    /// Test: a full-set outcome position, keyed by its token, is flattened by token
+    #[test]
+    fn test_flatten_full_set_outcome_by_token() {
+        use arb_bot::execution::flatten_plan;
+        use arb_bot::types::{MultiOutcomeMarket, Outcome};
+
+        let mut state = GlobalState::new();
+        let outcome = |name: &str, token: &str| Outcome { name: name.into(), token: token.into(), constraints: Default::default() };
+        state.add_multi_outcome(MultiOutcomeMarket {
+            event_slug: "epl-winner".into(),
+            description: "EPL Winner".into(),
+            neg_risk: true,
+            outcomes: vec![outcome("Arsenal", "ars_token"), outcome("Chelsea", "che_token"), outcome("Other", "oth_token")],
+        });
+
+        let mut tracker = PositionTracker::new();
+        tracker.record_fill_internal(&FillRecord::new("che_token", "EPL Winner - Chelsea", "polymarket", "yes", 4.0, 0.30, 0.0, "p1"));
+        tracker.record_fill_internal(&FillRecord::new("gone_token", "Gone", "polymarket", "yes", 4.0, 0.30, 0.0, "p2"));
+
+        let legs = flatten_plan(&tracker, &state);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].target.as_deref(), Some("che_token"));
+        assert_eq!(legs[1].error.as_deref(), Some("market not found"));
+    }
+
+    /// Test: a close rejected for a non-retryable reason is not retried
+    #[tokio::test]
+    async fn test_flatten_gives_up_on_closed_market() {
+        use arb_bot::config::FlattenConfig;
+        use arb_bot::error::Error;
+        use arb_bot::execution::{close_legs, flatten_plan};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let mut state = GlobalState::new();
+        state.add_pair(MarketPair {
+            pair_id: "closed-test".into(),
+            league: "epl".into(),
+            market_type: MarketType::Moneyline,
+            description: "Closed Test".into(),
+            kalshi_event_ticker: "KXCLOSED".into(),
+            kalshi_series_ticker: "KXCLOSED".into(),
+            kalshi_market_ticker: "KXCLOSED-YES".into(),
+            poly_slug: "closed-test".into(),
+            poly_yes_token: "closed_yes_token".into(),
+            poly_no_token: "closed_no_token".into(),
+            poly_constraints: Default::default(),
+            line_value: None,
+            team_suffix: None,
+        });
+        let mut tracker = PositionTracker::new();
+        tracker.record_fill_internal(&FillRecord::new("closed-test", "Closed Test", "kalshi", "no", 10.0, 0.50, 0.0, "k1"));
+
+        let mut legs = flatten_plan(&tracker, &state);
+        let calls = AtomicU32::new(0);
+        let config = FlattenConfig { max_attempts: 3, retry_delay_ms: 0 };
+        close_legs(&mut legs, config, |_, _, _, _| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(Err(Error::MarketClosed("KALSHI: market_closed".into())))
+        }).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(legs[0].attempts, 1);
+        assert!(legs[0].target.is_none());
+        assert!(legs[0].error.as_deref().unwrap().starts_with("market closed"));
+    }
 }
 
 // ============================================================================
@@ -1317,10 +1702,12 @@ mod process_mock_tests {
             market_type: MarketType::Moneyline,
             description: "Process Fast Test Market".into(),
             kalshi_event_ticker: "KXTEST-PROCESS".into(),
+            kalshi_series_ticker: "KXTEST".into(),
             kalshi_market_ticker: "KXTEST-PROCESS-YES".into(),
             poly_slug: "process-fast-test".into(),
             poly_yes_token: "pf_yes_token".into(),
             poly_no_token: "pf_no_token".into(),
+            poly_constraints: Default::default(),
             line_value: None,
             team_suffix: None,
         }
@@ -1333,6 +1720,8 @@ mod process_mock_tests {
             max_daily_loss: 50.0,
             max_consecutive_errors: 5,
             cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
             enabled: true,
         }
     }
@@ -1352,6 +1741,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         let result = MockExecutionResult {
@@ -1402,6 +1793,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         let result = MockExecutionResult {
@@ -1443,6 +1836,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::KalshiYesPolyNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         let result = MockExecutionResult {
@@ -1483,6 +1878,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         let result = MockExecutionResult {
@@ -1517,6 +1914,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Kalshi only fills 7 out of 10
@@ -1559,6 +1958,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Poly only fills 6 out of 10
@@ -1594,6 +1995,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Kalshi fills 0, Poly fills 10 (complete failure on one side)
@@ -1615,7 +2018,7 @@ mod process_mock_tests {
 
         // But position tracker still records the Poly fill (for exposure tracking)
         let tracker_guard = tracker.read().await;
-        let pos = tracker_guard.get(&pair.pair_id).expect("Should have position even with partial fills");
+        let _pos = tracker_guard.get(&pair.pair_id).expect("Should have position even with partial fills");
 
         // Poly fill should still be recorded (matched=0 means 0 recorded as matched)
         // The position exists but has 0 matched contracts
@@ -1636,6 +2039,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Kalshi fills 10, Poly fills 0
@@ -1670,6 +2075,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         let result = MockExecutionResult {
@@ -1708,6 +2115,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Partial fill: Kalshi 7, Poly 10
@@ -1747,6 +2156,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // First execution: 10 contracts
@@ -1805,6 +2216,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Execute multiple times
@@ -1834,9 +2247,9 @@ mod process_mock_tests {
     /// Test: PolyOnly arb (Poly YES + Poly NO on same platform - zero Kalshi fees)
     #[tokio::test]
     async fn test_process_poly_only_arb() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
-        let pair = test_market_pair();
+        let _tracker = Arc::new(RwLock::new(PositionTracker::new()));
+        let _cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let _pair = test_market_pair();
 
         // PolyOnly: Buy YES and NO both on Polymarket
         // This is unusual but profitable when Poly YES + Poly NO < $1
@@ -1848,11 +2261,13 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // For PolyOnly, both fills are from Polymarket
         // In real execution: leg1 = Poly YES, leg2 = Poly NO
-        let result = MockExecutionResult {
+        let _result = MockExecutionResult {
             kalshi_filled: 0,   // No Kalshi in PolyOnly
             poly_filled: 10,    // Both YES and NO filled on Poly (combined)
             kalshi_cost: 0,
@@ -1870,9 +2285,9 @@ mod process_mock_tests {
     /// Test: KalshiOnly arb (Kalshi YES + Kalshi NO on same platform - double fees)
     #[tokio::test]
     async fn test_process_kalshi_only_arb() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
-        let pair = test_market_pair();
+        let _tracker = Arc::new(RwLock::new(PositionTracker::new()));
+        let _cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let _pair = test_market_pair();
 
         // KalshiOnly: Buy YES and NO both on Kalshi
         // Must overcome DOUBLE fees (fee on YES side + fee on NO side)
@@ -1884,6 +2299,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::KalshiOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Double fee: kalshi_fee(44) + kalshi_fee(44)
@@ -1909,6 +2326,8 @@ mod process_mock_tests {
                     no_size: 1000,
                     arb_type: ArbType::PolyOnly,
                     detected_ns: 0,
+                    snapshot_ms: 0,
+                    order_type: None,
                 };
                 assert_eq!(req.estimated_fee_cents(), 0,
                     "PolyOnly should always have 0 fees, got {} for prices ({}, {})",
@@ -1932,6 +2351,8 @@ mod process_mock_tests {
                     no_size: 1000,
                     arb_type: ArbType::KalshiOnly,
                     detected_ns: 0,
+                    snapshot_ms: 0,
+                    order_type: None,
                 };
                 let expected = kalshi_fee_cents(yes_price) + kalshi_fee_cents(no_price);
                 assert_eq!(req.estimated_fee_cents(), expected,
@@ -1955,6 +2376,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
         assert_eq!(req1.estimated_fee_cents(), kalshi_fee_cents(50),
             "PolyYesKalshiNo fee should be on NO side (50¢)");
@@ -1968,6 +2391,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::KalshiYesPolyNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
         assert_eq!(req2.estimated_fee_cents(), kalshi_fee_cents(40),
             "KalshiYesPolyNo fee should be on YES side (40¢)");
@@ -1976,8 +2401,6 @@ mod process_mock_tests {
     /// Test: Profit comparison across all arb types with same prices
     #[test]
     fn test_profit_comparison_all_arb_types() {
-        use arb_bot::types::kalshi_fee_cents;
-
         let yes_price = 45u16;
         let no_price = 45u16;
         // Raw cost = 90¢, payout = 100¢
@@ -1991,6 +2414,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // KalshiOnly: double fees → less profit
@@ -2002,6 +2427,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::KalshiOnly,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // Cross-platform: single fee
@@ -2013,6 +2440,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         let cross2 = FastExecutionRequest {
@@ -2023,6 +2452,8 @@ mod process_mock_tests {
             no_size: 1000,
             arb_type: ArbType::KalshiYesPolyNo,
             detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
         };
 
         // PolyOnly should always be most profitable (no fees)
@@ -2043,4 +2474,474 @@ mod process_mock_tests {
         assert_eq!(cross1.profit_cents(), cross2.profit_cents(),
             "Both cross-platform types should have equal profit");
     }
-}
\ No newline at end of file
+}
+// ============================================================================
+// KALSHI ENVIRONMENT TESTS
+// ============================================================================
+
+mod kalshi_environment_tests {
+    use arb_bot::kalshi::KalshiEnvironment;
+
+    #[test]
+    fn test_environment_selects_endpoints() {
+        assert_eq!(KalshiEnvironment::parse("demo"), Some(KalshiEnvironment::Demo));
+        assert_eq!(KalshiEnvironment::parse(" PROD "), Some(KalshiEnvironment::Production));
+        assert_eq!(KalshiEnvironment::parse("staging"), None);
+        assert_eq!(KalshiEnvironment::default(), KalshiEnvironment::Production);
+
+        let demo = KalshiEnvironment::Demo;
+        assert!(demo.api_base().starts_with("https://demo-api.kalshi.co/"));
+        assert!(demo.ws_url().starts_with("wss://demo-api.kalshi.co/"));
+        assert_eq!(demo.host(), "demo-api.kalshi.co");
+        assert_eq!(KalshiEnvironment::Production.host(), "api.elections.kalshi.com");
+    }
+}
+
+mod kalshi_signing_tests {
+    use arb_bot::kalshi::{KalshiConfig, KalshiEnvironment};
+    use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+    use rsa::RsaPrivateKey;
+
+    #[test]
+    fn test_load_pkcs8_key_and_verify() {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let pem = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let path = std::env::temp_dir().join(format!("kalshi_test_key_{}.pem", std::process::id()));
+        std::fs::write(&path, pem.as_bytes()).unwrap();
+
+        let config = KalshiConfig::load(
+            "key-id".to_string(),
+            path.to_string_lossy().into_owned(),
+            KalshiEnvironment::Demo,
+        ).unwrap();
+        assert!(config.verify_key().is_ok());
+        assert!(!config.sign("1700000000000GET/trade-api/v2/portfolio/balance").unwrap().is_empty());
+
+        // Garbage fails at load with the offending path in the error
+        std::fs::write(&path, "not a key").unwrap();
+        let err = KalshiConfig::load(
+            "key-id".to_string(),
+            path.to_string_lossy().into_owned(),
+            KalshiEnvironment::Demo,
+        ).err().unwrap();
+        assert!(format!("{:#}", err).contains("PKCS#8"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+// ============================================================================
+// ORDER GATEWAY TESTS - The live execution path against scripted exchanges
+// ============================================================================
+
+mod order_gateway_tests {
+    use arb_bot::circuit_breaker::*;
+    use arb_bot::error::{Error, Result};
+    use arb_bot::execution::*;
+    use arb_bot::polymarket_clob::OrderBookStore;
+    use arb_bot::position_tracker::*;
+    use arb_bot::types::*;
+    use futures_util::future::BoxFuture;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    /// One scripted answer: after `delay`, this fill or error
+    type Step = (Duration, Result<OrderFill>);
+
+    /// Answers each (side, buy) order with the next step scripted for it and
+    /// records every order it was sent
+    #[derive(Default)]
+    struct ScriptedOrders {
+        steps: Mutex<HashMap<(&'static str, bool), VecDeque<Step>>>,
+        sent: Mutex<Vec<(&'static str, bool, PriceCents, i64)>>,
+        /// What the exchange reports afterwards, by order ID
+        details: HashMap<String, OrderFill>,
+        /// Answers to each cancel check, by order ID
+        cancels: Mutex<HashMap<String, VecDeque<OrderFill>>>,
+    }
+
+    impl ScriptedOrders {
+        fn on(self, side: &'static str, buy: bool, delay_ms: u64, result: Result<OrderFill>) -> Self {
+            self.steps.lock().unwrap().entry((side, buy)).or_default().push_back((Duration::from_millis(delay_ms), result));
+            self
+        }
+
+        fn reports(mut self, order_id: &str, filled: i64, cost_cents: i64, fee_cents: i64) -> Self {
+            self.details.insert(order_id.into(), OrderFill { filled, cost_cents, fee_cents, order_id: order_id.into(), ..Default::default() });
+            self
+        }
+
+        fn cancel_check(self, order_id: &str, filled: i64, cost_cents: i64, fee_cents: i64, outcome: OrderOutcome) -> Self {
+            let fill = OrderFill { filled, cost_cents, fee_cents, order_id: order_id.into(), outcome, ..Default::default() };
+            self.cancels.lock().unwrap().entry(order_id.into()).or_default().push_back(fill);
+            self
+        }
+
+        fn sent(&self) -> Vec<(&'static str, bool, PriceCents, i64)> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    impl OrderGateway for ScriptedOrders {
+        fn submit<'a>(&'a self, order: LegOrder<'a>) -> BoxFuture<'a, Result<OrderFill>> {
+            self.sent.lock().unwrap().push((order.side, order.buy, order.price.cents(), order.contracts));
+            let step = self.steps.lock().unwrap().get_mut(&(order.side, order.buy)).and_then(VecDeque::pop_front);
+            Box::pin(async move {
+                let (delay, result) = step.unwrap_or((Duration::ZERO, Err(Error::Invalid("unscripted order".into()))));
+                tokio::time::sleep(delay).await;
+                result
+            })
+        }
+
+        fn fill_details<'a>(&'a self, _platform: Platform, order_id: &'a str) -> BoxFuture<'a, Result<OrderFill>> {
+            let details = self.details.get(order_id).cloned().ok_or_else(|| Error::Invalid("no fill details".into()));
+            Box::pin(async move { details })
+        }
+
+        fn confirm_cancel<'a>(&'a self, _platform: Platform, order_id: &'a str) -> BoxFuture<'a, Result<OrderFill>> {
+            let check = self.cancels.lock().unwrap().get_mut(order_id).and_then(VecDeque::pop_front)
+                .ok_or_else(|| Error::Invalid("unscripted cancel check".into()));
+            Box::pin(async move { check })
+        }
+
+        fn configured(&self, platform: Platform) -> bool {
+            platform == Platform::Kalshi
+        }
+    }
+
+    fn fill(filled: i64, price: i64, order_id: &str) -> Result<OrderFill> {
+        Ok(OrderFill { filled, cost_cents: filled * price, order_id: order_id.into(), ..Default::default() })
+    }
+
+    /// Live engine with two Kalshi pairs in separate events quoted at
+    /// 40¢/45¢, sending through `orders`
+    fn live_engine(orders: Arc<ScriptedOrders>) -> (ExecutionEngine, SharedPositionTracker) {
+        let mut state = GlobalState::new();
+        for suffix in ["", "-2"] {
+            let market_id = state.add_pair(MarketPair {
+                pair_id: format!("gateway-test{}", suffix).into(),
+                league: "epl".into(),
+                market_type: MarketType::Moneyline,
+                description: format!("Gateway Test Market{}", suffix).into(),
+                kalshi_event_ticker: format!("KXTEST-GW{}", suffix).into(),
+                kalshi_series_ticker: "KXTEST".into(),
+                kalshi_market_ticker: format!("KXTEST-GW{}-YES", suffix).into(),
+                poly_slug: format!("gateway-test{}", suffix).into(),
+                poly_yes_token: format!("gw_yes_token{}", suffix).into(),
+                poly_no_token: format!("gw_no_token{}", suffix).into(),
+                poly_constraints: Default::default(),
+                line_value: None,
+                team_suffix: None,
+            }).unwrap();
+            state.get_by_id(market_id).unwrap().kalshi.store(40, 45, 1000, 1000);
+        }
+
+        let positions = Arc::new(RwLock::new(PositionTracker::new()));
+        let (position_channel, _position_rx) = create_position_channel();
+        let (report_tx, _report_rx) = create_execution_report_channel();
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            max_position_per_market: 1000,
+            max_total_position: 1000,
+            max_daily_loss: 100.0,
+            max_consecutive_errors: 5,
+            cooldown_secs: 60,
+            loss_window_secs: 3600,
+            max_window_loss_cents: 0,
+            enabled: true,
+        });
+        let engine = ExecutionEngine::new(
+            None,
+            None,
+            Arc::new(OrderBookStore::new()),
+            Arc::new(state),
+            Arc::new(breaker),
+            position_channel,
+            positions.clone(),
+            report_tx,
+            ExecutionMode::Live,
+        ).with_orders(orders);
+        (engine, positions)
+    }
+
+    fn request(contracts: i64) -> FastExecutionRequest {
+        FastExecutionRequest {
+            market_id: 0,
+            yes_price: 40,
+            no_price: 45,
+            yes_size: (contracts * 100) as u16,
+            no_size: (contracts * 100) as u16,
+            arb_type: ArbType::KalshiOnly,
+            detected_ns: 0,
+            snapshot_ms: 0,
+            order_type: None,
+        }
+    }
+
+    /// Test: both legs go out together, so a slow leg doesn't delay the other
+    #[tokio::test(start_paused = true)]
+    async fn test_legs_race_concurrently() {
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 300, fill(10, 40, "k-yes"))
+            .on("no", true, 50, fill(10, 45, "k-no")));
+        let (engine, _) = live_engine(orders.clone());
+
+        let started = tokio::time::Instant::now();
+        let result = engine.process(request(10)).await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_millis(300), "legs awaited together, not one after the other");
+
+        assert!(result.success);
+        assert_eq!(result.error, None);
+        assert_eq!(result.profit_cents, 150);
+        assert_eq!(orders.sent(), [("yes", true, 40, 10), ("no", true, 45, 10)]);
+    }
+
+    /// Test: a short leg that can't be completed has the excess sold back
+    #[tokio::test(start_paused = true)]
+    async fn test_partial_fill_unwinds_excess() {
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 20, fill(10, 40, "k-yes"))
+            .on("no", true, 80, fill(6, 45, "k-no"))
+            .on("no", true, 10, fill(0, 46, "k-no-2"))
+            .on("yes", false, 10, fill(4, 30, "k-yes-unwind")));
+        let (engine, _) = live_engine(orders.clone());
+
+        let result = engine.process(request(10)).await.unwrap();
+        assert!(result.success, "6 contracts hedged");
+        // 6 * 100 - 6 * 40 - 6 * 45, then 4 YES bought at 40¢ sold at 30¢
+        assert_eq!(result.profit_cents, 90 - 40);
+        assert_eq!(orders.sent(), [
+            ("yes", true, 40, 10),
+            ("no", true, 45, 10),
+            ("no", true, 46, 4),
+            ("yes", false, 30, 4),
+        ]);
+    }
+
+    /// Test: the profit booked comes from the fills the exchange reports, fees included
+    #[tokio::test(start_paused = true)]
+    async fn test_profit_reconciled_against_exchange_fills() {
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(10, 40, "k-yes"))
+            .on("no", true, 0, fill(10, 45, "k-no"))
+            .reports("k-yes", 10, 390, 20)
+            .reports("k-no", 10, 450, 20));
+        let (engine, _) = live_engine(orders.clone());
+
+        let result = engine.process(request(10)).await.unwrap();
+        assert!(result.success);
+        // 1000 - 390 - 450 - 40 in fees, not the 150 the order responses implied
+        assert_eq!(result.profit_cents, 120);
+        assert_eq!(orders.sent().len(), 2);
+    }
+
+    /// Test: fills are reconciled after the unwind, and the unwind's fees are booked
+    #[tokio::test(start_paused = true)]
+    async fn test_unwound_fill_reconciled_with_fees() {
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(10, 40, "k-yes"))
+            .on("no", true, 0, fill(6, 45, "k-no"))
+            .on("no", true, 0, fill(0, 46, "k-no-2"))
+            .on("yes", false, 0, fill(4, 30, "k-yes-unwind").map(|f| OrderFill { fee_cents: 3, ..f }))
+            .reports("k-yes", 10, 400, 10)
+            .reports("k-no", 6, 270, 6));
+        let (engine, _) = live_engine(orders.clone());
+
+        let result = engine.process(request(10)).await.unwrap();
+        assert!(result.success);
+        // 6 hedged pairs lock in 90¢, the unwind loses 40¢, 19¢ in fees
+        assert_eq!(result.profit_cents, 90 - 40 - 19);
+        assert_eq!(orders.sent().len(), 4);
+    }
+
+    /// Test: a leg that errors leaves the filled one to be unwound, and a
+    /// failure on both sends nothing more
+    #[tokio::test(start_paused = true)]
+    async fn test_leg_errors_unwind_or_fail() {
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 10, fill(5, 40, "k-yes"))
+            .on("no", true, 500, Err(Error::Network("timed out".into())))
+            .on("no", true, 0, Err(Error::Network("timed out".into())))
+            .on("yes", false, 0, fill(5, 30, "k-yes-unwind")));
+        let (engine, _) = live_engine(orders.clone());
+
+        let result = engine.process(request(5)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some("Partial/no fill"));
+        assert_eq!(result.profit_cents, -50, "5 YES sold 10¢ under cost");
+        assert_eq!(orders.sent().last(), Some(&("yes", false, 30, 5)));
+
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, Err(Error::MarketClosed("KXTEST-GW-YES".into())))
+            .on("no", true, 0, Err(Error::Network("reset".into()))));
+        let (engine, positions) = live_engine(orders.clone());
+        let result = engine.process(request(5)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.profit_cents, 0);
+        assert_eq!(orders.sent().len(), 2, "nothing filled, nothing to even out");
+        assert_eq!(positions.read().await.summary().open_positions, 0);
+    }
+
+    /// Test: the sizing policy sets the target, the book depth still caps it
+    #[tokio::test(start_paused = true)]
+    async fn test_sizing_policy_target_capped_by_depth() {
+        // A 15¢ gross edge sits below the first breakpoint: the minimum size
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(4, 40, "k-yes"))
+            .on("no", true, 0, fill(4, 45, "k-no")));
+        let (engine, _) = live_engine(orders.clone());
+        let engine = engine.with_sizing_policy(arb_bot::config::SizingPolicy::ProfitTiered(vec![(20.0, 4), (30.0, 40)]));
+        assert!(engine.process(request(10)).await.unwrap().success);
+        assert_eq!(orders.sent(), [("yes", true, 40, 4), ("no", true, 45, 4)]);
+
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(10, 40, "k-yes"))
+            .on("no", true, 0, fill(10, 45, "k-no")));
+        let (engine, _) = live_engine(orders.clone());
+        let engine = engine.with_sizing_policy(arb_bot::config::SizingPolicy::Fixed(25));
+        assert!(engine.process(request(10)).await.unwrap().success);
+        assert_eq!(orders.sent(), [("yes", true, 40, 10), ("no", true, 45, 10)], "never more than the book shows");
+    }
+
+    /// Test: contracts filled after a late cancel are balanced with the rest of the trade
+    #[tokio::test(start_paused = true)]
+    async fn test_late_fill_after_cancel_is_balanced() {
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(10, 40, "k-yes"))
+            .on("no", true, 0, Ok(OrderFill { filled: 6, cost_cents: 270, order_id: "k-no".into(), outcome: OrderOutcome::CancelUnconfirmed, ..Default::default() }))
+            .cancel_check("k-no", 6, 270, 0, OrderOutcome::CancelUnconfirmed)
+            .cancel_check("k-no", 8, 360, 0, OrderOutcome::Expired)
+            .on("no", true, 0, fill(2, 46, "k-no-2")));
+        let (engine, _) = live_engine(orders.clone());
+
+        let result = engine.process(request(10)).await.unwrap();
+        assert!(result.success);
+        assert_eq!(orders.sent(), [("yes", true, 40, 10), ("no", true, 45, 10), ("no", true, 46, 2)],
+                   "only the 2 contracts still short are completed");
+        // 10 * 100 - 10 * 40 - (8 * 45 + 2 * 46)
+        assert_eq!(result.profit_cents, 148);
+    }
+
+    /// Test: fees charged on contracts filled after a late cancel are booked
+    #[tokio::test(start_paused = true)]
+    async fn test_late_fill_after_cancel_keeps_fees() {
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(10, 40, "k-yes"))
+            .on("no", true, 0, Ok(OrderFill { filled: 6, cost_cents: 270, order_id: "k-no".into(), outcome: OrderOutcome::CancelUnconfirmed, ..Default::default() }))
+            .cancel_check("k-no", 10, 450, 7, OrderOutcome::Expired));
+        let (engine, _) = live_engine(orders.clone());
+
+        let result = engine.process(request(10)).await.unwrap();
+        assert!(result.success);
+        // 10 * 100 - 10 * 40 - 10 * 45 - 7
+        assert_eq!(result.profit_cents, 143);
+    }
+
+    /// Test: a cancel that is never confirmed stops being checked and is reported
+    #[tokio::test(start_paused = true)]
+    async fn test_unconfirmed_cancel_gives_up() {
+        let mut orders = ScriptedOrders::default()
+            .on("yes", true, 0, Ok(OrderFill { order_id: "k-yes".into(), outcome: OrderOutcome::CancelUnconfirmed, ..Default::default() }))
+            .on("no", true, 0, Ok(OrderFill { order_id: "k-no".into(), outcome: OrderOutcome::Expired, ..Default::default() }));
+        for _ in 0..20 {
+            orders = orders.cancel_check("k-yes", 0, 0, 0, OrderOutcome::CancelUnconfirmed);
+        }
+        let orders = Arc::new(orders);
+        let (engine, _) = live_engine(orders.clone());
+
+        let started = tokio::time::Instant::now();
+        let result = engine.process(request(10)).await.unwrap();
+        assert_eq!(result.error, Some("Cancel unconfirmed"));
+        assert_eq!(started.elapsed(), Duration::from_secs(60), "12 checks 5s apart");
+        assert_eq!(orders.cancels.lock().unwrap()["k-yes"].len(), 8);
+    }
+
+    /// Test: Kalshi legs are sized against the local Kalshi book, not just the quoted size
+    #[tokio::test(start_paused = true)]
+    async fn test_kalshi_book_caps_size() {
+        use arb_bot::kalshi::book_hash;
+
+        let books = Arc::new(OrderBookStore::new());
+        books.apply_snapshot(book_hash("KXTEST-GW-YES", "yes"), std::iter::empty(), [(4000, 4.0), (4200, 10.0)]);
+        books.apply_snapshot(book_hash("KXTEST-GW-YES", "no"), std::iter::empty(), [(4500, 100.0)]);
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(4, 40, "k-yes"))
+            .on("no", true, 0, fill(4, 45, "k-no")));
+        let (engine, _) = live_engine(orders.clone());
+        let engine = engine.with_kalshi_books(books);
+        assert!(engine.process(request(10)).await.unwrap().success);
+        assert_eq!(orders.sent(), [("yes", true, 40, 4), ("no", true, 45, 4)], "only 4 YES at the quoted 40¢");
+    }
+
+    /// Test: post-only legs rest one tick inside the spread instead of crossing at the ask
+    #[tokio::test(start_paused = true)]
+    async fn test_post_only_legs_priced_inside_spread() {
+        use arb_bot::kalshi::book_hash;
+
+        let books = Arc::new(OrderBookStore::new());
+        books.apply_snapshot(book_hash("KXTEST-GW-YES", "yes"), [(3700, 10.0)], [(4000, 100.0)]);
+        // Spread one tick wide: the NO leg joins the bid
+        books.apply_snapshot(book_hash("KXTEST-GW-YES", "no"), [(4400, 10.0)], [(4500, 100.0)]);
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(10, 38, "k-yes"))
+            .on("no", true, 0, fill(10, 44, "k-no")));
+        let (engine, _) = live_engine(orders.clone());
+        let engine = engine.with_kalshi_books(books);
+
+        let result = engine.process(FastExecutionRequest { order_type: Some(OrderType::PostOnly), ..request(10) }).await.unwrap();
+        assert!(result.success);
+        assert_eq!(orders.sent(), [("yes", true, 38, 10), ("no", true, 44, 10)]);
+    }
+
+    /// Test: nothing is sent while a leg's platform is down
+    #[tokio::test(start_paused = true)]
+    async fn test_platform_down_holds_orders() {
+        use arb_bot::config::PlatformHealthConfig;
+        use arb_bot::discovery::PlatformHealth;
+
+        let health = Arc::new(PlatformHealth::new(PlatformHealthConfig { down_after_errors: Some(1) }));
+        health.record(Platform::Kalshi, false);
+        let orders = Arc::new(ScriptedOrders::default()
+            .on("yes", true, 0, fill(10, 40, "k-yes"))
+            .on("no", true, 0, fill(10, 45, "k-no")));
+        let (engine, _) = live_engine(orders.clone());
+        let engine = engine.with_platform_health(health.clone());
+
+        let result = engine.process(request(10)).await.unwrap();
+        assert_eq!(result.error, Some("Platform down"));
+        assert!(orders.sent().is_empty());
+
+        health.record(Platform::Kalshi, true);
+        tokio::time::sleep(Duration::from_secs(11)).await;
+        let result = engine.process(request(10)).await.unwrap();
+        assert!(result.success, "traded again once it recovers: {:?}", result.error);
+    }
+
+    /// Test: opportunities on distinct markets run in parallel up to the bound
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_executes_markets_in_parallel() {
+        use arb_bot::execution::execute_batch;
+
+        let orders = || Arc::new(ScriptedOrders::default()
+            .on("yes", true, 100, fill(10, 40, "k-yes"))
+            .on("no", true, 100, fill(10, 45, "k-no"))
+            .on("yes", true, 100, fill(10, 40, "k-yes-2"))
+            .on("no", true, 100, fill(10, 45, "k-no-2")));
+        let batch = || vec![(request(10), None), (FastExecutionRequest { market_id: 1, ..request(10) }, None)];
+
+        let orders_parallel = orders();
+        let (engine, _) = live_engine(orders_parallel.clone());
+        let timing = execute_batch(Arc::new(engine.with_max_parallel(2)), batch()).await;
+        assert_eq!(timing.executions, 2);
+        assert_eq!(timing.elapsed, Duration::from_millis(100), "both markets at once");
+        assert_eq!(timing.sequential, Duration::from_millis(200));
+        assert_eq!(orders_parallel.sent().len(), 4);
+
+        // One permit: the second market waits for the first
+        let (engine, _) = live_engine(orders());
+        let timing = execute_batch(Arc::new(engine.with_max_parallel(1)), batch()).await;
+        assert_eq!(timing.elapsed, Duration::from_millis(200));
+        assert_eq!(timing.sequential, Duration::from_millis(200));
+    }
+}